@@ -0,0 +1,10 @@
+-- Optional per-profile VMAF target. When set, the pipeline probes short clips
+-- at several quality values and picks the most compressed one that still
+-- meets the target before running the full encode. NULL keeps the fixed
+-- quality-profile/crf_override behavior.
+ALTER TABLE library_profiles ADD COLUMN target_vmaf REAL;
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '19'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
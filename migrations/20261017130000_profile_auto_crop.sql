@@ -0,0 +1,8 @@
+-- Per-profile opt-in for removing black bars found by the analyzer's crop
+-- detection. Off by default so existing libraries keep their full frame.
+ALTER TABLE library_profiles ADD COLUMN auto_crop INTEGER NOT NULL DEFAULT 0;
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '20'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
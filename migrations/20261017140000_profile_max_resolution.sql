@@ -0,0 +1,8 @@
+-- Per-profile output resolution cap ("480p" through "2160p"). NULL keeps the
+-- source resolution, which matches the behavior before this column existed.
+ALTER TABLE library_profiles ADD COLUMN max_resolution TEXT;
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '21'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
@@ -0,0 +1,8 @@
+-- Per-profile Dolby Vision policy: 'skip', 'strip' (drop the RPU and keep the
+-- HDR10 base layer), or 'preserve' (carry a profile 8.1 RPU when supported).
+ALTER TABLE library_profiles ADD COLUMN dolby_vision_mode TEXT NOT NULL DEFAULT 'strip';
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '22'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
@@ -0,0 +1,7 @@
+-- Per-profile EBU R128 loudness target in LUFS. NULL disables normalization.
+ALTER TABLE library_profiles ADD COLUMN loudness_target_lufs REAL;
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '23'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
@@ -0,0 +1,21 @@
+-- Ordered rules that pick a library profile (or a skip) from the file itself.
+-- Rules are evaluated by ascending position before the watch folder profile.
+CREATE TABLE IF NOT EXISTS profile_rules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    position INTEGER NOT NULL DEFAULT 0,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    conditions_json TEXT NOT NULL DEFAULT '{}',
+    action TEXT NOT NULL DEFAULT 'profile',
+    profile_id INTEGER REFERENCES library_profiles(id),
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_profile_rules_position
+    ON profile_rules(position, id);
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '24'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
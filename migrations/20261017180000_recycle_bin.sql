@@ -0,0 +1,24 @@
+-- Originals moved aside by delete_source when the recycle bin is enabled.
+-- status is 'held' until the original is restored or purged by retention.
+CREATE TABLE IF NOT EXISTS recycle_bin_entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    job_id INTEGER REFERENCES jobs(id) ON DELETE SET NULL,
+    original_path TEXT NOT NULL,
+    recycled_path TEXT NOT NULL,
+    output_path TEXT,
+    size_bytes INTEGER NOT NULL DEFAULT 0,
+    status TEXT NOT NULL DEFAULT 'held',
+    recycled_at TEXT NOT NULL DEFAULT (datetime('now')),
+    resolved_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_recycle_bin_entries_job
+    ON recycle_bin_entries(job_id);
+
+CREATE INDEX IF NOT EXISTS idx_recycle_bin_entries_status
+    ON recycle_bin_entries(status, recycled_at);
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '25'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
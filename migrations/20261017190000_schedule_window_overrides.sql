@@ -0,0 +1,12 @@
+-- Schedule windows can set the engine mode, a concurrency override and an
+-- encoder backend allow-list while they are active. Overlapping windows are
+-- resolved by priority (highest wins, then the earliest window).
+ALTER TABLE schedule_windows ADD COLUMN engine_mode TEXT;
+ALTER TABLE schedule_windows ADD COLUMN concurrent_jobs INTEGER;
+ALTER TABLE schedule_windows ADD COLUMN allowed_backends TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE schedule_windows ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '26'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
@@ -0,0 +1,21 @@
+-- Retries are scheduled explicitly instead of through a fixed backoff ladder
+-- in the claim query. A queued job is claimable once `next_retry_at` is NULL
+-- or in the past; `retry_on_cpu` pins the next attempt to the CPU encoder.
+ALTER TABLE jobs ADD COLUMN next_retry_at TEXT;
+ALTER TABLE jobs ADD COLUMN retry_on_cpu INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE jobs ADD COLUMN last_failure_kind TEXT;
+
+-- Keep the old ladder's wait for jobs already queued for a retry.
+UPDATE jobs
+SET next_retry_at = CASE
+        WHEN attempt_count = 1 THEN datetime(updated_at, '+5 minutes')
+        WHEN attempt_count = 2 THEN datetime(updated_at, '+15 minutes')
+        WHEN attempt_count = 3 THEN datetime(updated_at, '+60 minutes')
+        ELSE datetime(updated_at, '+360 minutes')
+    END
+WHERE status = 'queued' AND COALESCE(attempt_count, 0) > 0;
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '27'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
@@ -0,0 +1,10 @@
+-- Per-job estimates filled in at analysis time, used to order the queue by
+-- expected savings or encode time. NULL until a job has been analyzed.
+ALTER TABLE jobs ADD COLUMN source_size_bytes INTEGER;
+ALTER TABLE jobs ADD COLUMN estimated_output_bytes INTEGER;
+ALTER TABLE jobs ADD COLUMN estimated_encode_seconds REAL;
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '28'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
@@ -0,0 +1,25 @@
+-- Space goals: encode until a filesystem reaches a free-space or
+-- reclaimed-space target, then stop. `device_id` matches `jobs.source_device`
+-- so the claim query can favour jobs on the goal's filesystem.
+CREATE TABLE IF NOT EXISTS space_goals (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    path TEXT NOT NULL,
+    device_id TEXT NOT NULL,
+    target_kind TEXT NOT NULL CHECK(target_kind IN ('free', 'reclaimed')),
+    target_gib REAL NOT NULL,
+    deadline TEXT,
+    drain_when_met INTEGER NOT NULL DEFAULT 1,
+    start_free_bytes INTEGER,
+    status TEXT NOT NULL DEFAULT 'active'
+        CHECK(status IN ('active', 'met', 'expired')),
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    finished_at DATETIME
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_space_goals_active_device
+    ON space_goals(device_id) WHERE status = 'active';
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '29'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
@@ -0,0 +1,30 @@
+-- Extra outputs a library profile produces from each source, such as a
+-- 1080p H.264 compatibility copy beside a 4K HEVC master. Profiles declare
+-- them as a JSON array; each variant a job produces is tracked as a child
+-- row with its own decision, stats and failure.
+ALTER TABLE library_profiles ADD COLUMN output_variants_json TEXT NOT NULL DEFAULT '[]';
+
+CREATE TABLE IF NOT EXISTS job_variants (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    job_id INTEGER NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    output_path TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'queued'
+        CHECK(status IN ('queued', 'encoding', 'completed', 'skipped', 'failed', 'cancelled')),
+    decision_reason TEXT,
+    failure_summary TEXT,
+    input_size_bytes INTEGER,
+    output_size_bytes INTEGER,
+    encode_time_seconds REAL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(job_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_variants_output_path
+    ON job_variants(output_path);
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '30'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
@@ -0,0 +1,29 @@
+-- Remote workers: other machines that lease queued jobs from this instance,
+-- encode them locally, and report back. A lease lives as long as its worker
+-- keeps heartbeating; an expired lease puts the job back in the queue.
+CREATE TABLE IF NOT EXISTS workers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    hardware_json TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'online'
+        CHECK(status IN ('online', 'offline')),
+    registered_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_seen_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS job_leases (
+    job_id INTEGER PRIMARY KEY REFERENCES jobs(id) ON DELETE CASCADE,
+    worker_id INTEGER NOT NULL REFERENCES workers(id) ON DELETE CASCADE,
+    leased_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at DATETIME NOT NULL,
+    plan_json TEXT,
+    metadata_json TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_leases_expires_at ON job_leases(expires_at);
+CREATE INDEX IF NOT EXISTS idx_job_leases_worker_id ON job_leases(worker_id);
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '31'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
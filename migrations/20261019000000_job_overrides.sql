@@ -0,0 +1,12 @@
+-- Per-job setting overrides supplied at enqueue time, layered over the
+-- job's library profile when it is planned.
+CREATE TABLE IF NOT EXISTS job_overrides (
+    job_id INTEGER PRIMARY KEY REFERENCES jobs(id) ON DELETE CASCADE,
+    overrides_json TEXT NOT NULL,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '32'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
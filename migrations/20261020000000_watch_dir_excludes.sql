@@ -0,0 +1,8 @@
+-- Per-watch-directory exclude globs, stored as a JSON array of patterns
+-- matched relative to the watch directory.
+ALTER TABLE watch_dirs ADD COLUMN exclude_globs_json TEXT NOT NULL DEFAULT '[]';
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '33'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
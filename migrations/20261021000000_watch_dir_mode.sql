@@ -0,0 +1,7 @@
+-- How each watch directory is monitored: `auto`, `native` or `poll`.
+ALTER TABLE watch_dirs ADD COLUMN watch_mode TEXT NOT NULL DEFAULT 'auto';
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '34'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
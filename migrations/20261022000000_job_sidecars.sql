@@ -0,0 +1,20 @@
+-- Companion files (subtitles, NFO, artwork, trickplay) carried over to a
+-- job's output name. action is 'moved' when the source was removed,
+-- 'copied' when it was kept, and 'restored' once a move was undone by
+-- restoring the recycled original.
+CREATE TABLE IF NOT EXISTS job_sidecars (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    job_id INTEGER NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+    source_path TEXT NOT NULL,
+    output_path TEXT NOT NULL,
+    action TEXT NOT NULL CHECK(action IN ('moved', 'copied', 'restored')),
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_sidecars_job
+    ON job_sidecars(job_id);
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '35'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
@@ -0,0 +1,7 @@
+-- Optional output path template; NULL keeps suffix-plus-extension naming.
+ALTER TABLE file_settings ADD COLUMN output_template TEXT;
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '36'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
@@ -0,0 +1,14 @@
+-- Output variants run as jobs of their own, so each one is claimed,
+-- retried and cancelled like any other job. The job_variants row now links
+-- the parent job to its variant job; the variant's status, decision and
+-- stats are read from that job, and the outcome columns here are no longer
+-- written.
+ALTER TABLE job_variants ADD COLUMN variant_job_id INTEGER REFERENCES jobs(id) ON DELETE SET NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_job_variants_variant_job_id
+    ON job_variants(variant_job_id);
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '37'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
@@ -0,0 +1,8 @@
+-- A worker token is bound to the worker it first registers. Requests made
+-- with it may only act as that worker.
+ALTER TABLE api_tokens ADD COLUMN worker_id INTEGER REFERENCES workers(id) ON DELETE SET NULL;
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '38'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
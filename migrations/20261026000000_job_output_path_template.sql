@@ -0,0 +1,9 @@
+-- The output path as enqueue computed it. A templated path keeps its media
+-- placeholders here while `output_path` holds the resolved path, so a rescan
+-- can tell an unchanged template from a new one.
+ALTER TABLE jobs ADD COLUMN output_path_template TEXT;
+
+INSERT OR REPLACE INTO schema_info (key, value) VALUES
+    ('schema_version', '39'),
+    ('min_compatible_version', '0.2.5'),
+    ('last_updated', datetime('now'));
@@ -22,6 +22,10 @@ pub struct Config {
     pub quality: QualityConfig,
     #[serde(default)]
     pub system: SystemConfig,
+    #[serde(default)]
+    pub hooks: Vec<HookConfig>,
+    #[serde(default)]
+    pub retry: RetryConfig,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -193,6 +197,11 @@ pub enum AudioMode {
     Copy,
     Aac,
     AacStereo,
+    /// Keep every track as-is and add a stereo AAC track derived from the
+    /// default surround track.
+    CopyPlusStereo,
+    /// Re-encode lossless tracks to AAC and copy everything else.
+    TranscodeLossless,
 }
 
 impl AudioMode {
@@ -201,6 +210,8 @@ impl AudioMode {
             Self::Copy => "copy",
             Self::Aac => "aac",
             Self::AacStereo => "aac_stereo",
+            Self::CopyPlusStereo => "copy_plus_stereo",
+            Self::TranscodeLossless => "transcode_lossless",
         }
     }
 }
@@ -223,6 +234,157 @@ impl HdrMode {
     }
 }
 
+/// What a library profile does with Dolby Vision sources. Profile 5 sources
+/// have no HDR10 base layer and are skipped under every mode.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[derive(Default)]
+pub enum DolbyVisionMode {
+    Skip,
+    /// Drop the RPU and keep the HDR10 base layer with its static metadata.
+    #[default]
+    Strip,
+    /// Carry the profile 8.1 RPU into the output when the encoder can.
+    Preserve,
+}
+
+impl DolbyVisionMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Skip => "skip",
+            Self::Strip => "strip",
+            Self::Preserve => "preserve",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "skip" => Some(Self::Skip),
+            "strip" => Some(Self::Strip),
+            "preserve" => Some(Self::Preserve),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[derive(Default)]
+pub enum DeinterlaceMode {
+    #[default]
+    Off,
+    /// Deinterlace when the analyzer flags interlaced metadata and the
+    /// field-order probe confirms it.
+    Auto,
+    Always,
+}
+
+impl DeinterlaceMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Auto => "auto",
+            Self::Always => "always",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[derive(Default)]
+pub enum DeinterlaceAlgorithm {
+    #[default]
+    Bwdif,
+    Yadif,
+}
+
+impl DeinterlaceAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Bwdif => "bwdif",
+            Self::Yadif => "yadif",
+        }
+    }
+}
+
+/// Whether the deinterlacer emits one frame per frame or one per field
+/// (doubling the frame rate for smoother motion).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[derive(Default)]
+pub enum DeinterlaceRate {
+    #[default]
+    SendFrame,
+    SendField,
+}
+
+impl DeinterlaceRate {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SendFrame => "send_frame",
+            Self::SendField => "send_field",
+        }
+    }
+
+    /// Value of the `rate` option on the VAAPI and QSV deinterlacers.
+    pub fn hardware_rate(&self) -> &'static str {
+        match self {
+            Self::SendFrame => "frame",
+            Self::SendField => "field",
+        }
+    }
+}
+
+/// Output resolution cap a library profile can apply. Bounds are expressed as
+/// (long edge, short edge) so portrait sources are capped the same way.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum MaxResolution {
+    #[serde(rename = "480p")]
+    P480,
+    #[serde(rename = "720p")]
+    P720,
+    #[serde(rename = "1080p")]
+    P1080,
+    #[serde(rename = "1440p")]
+    P1440,
+    #[serde(rename = "2160p")]
+    P2160,
+}
+
+impl MaxResolution {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::P480 => "480p",
+            Self::P720 => "720p",
+            Self::P1080 => "1080p",
+            Self::P1440 => "1440p",
+            Self::P2160 => "2160p",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "480p" => Some(Self::P480),
+            "720p" => Some(Self::P720),
+            "1080p" => Some(Self::P1080),
+            "1440p" => Some(Self::P1440),
+            "2160p" | "4k" => Some(Self::P2160),
+            _ => None,
+        }
+    }
+
+    /// Largest (long edge, short edge) the output may have.
+    pub fn bounds(&self) -> (u32, u32) {
+        match self {
+            Self::P480 => (854, 480),
+            Self::P720 => (1280, 720),
+            Self::P1080 => (1920, 1080),
+            Self::P1440 => (2560, 1440),
+            Self::P2160 => (3840, 2160),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 #[derive(Default)]
@@ -267,6 +429,30 @@ impl SubtitleMode {
     }
 }
 
+/// Order in which queued jobs of equal priority are started.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueOrder {
+    /// Oldest job first.
+    #[default]
+    Fifo,
+    LargestSourceFirst,
+    /// Largest estimated `source - output` first.
+    HighestSavingsFirst,
+    ShortestDurationFirst,
+}
+
+impl QueueOrder {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Fifo => "fifo",
+            Self::LargestSourceFirst => "largest_source_first",
+            Self::HighestSavingsFirst => "highest_savings_first",
+            Self::ShortestDurationFirst => "shortest_duration_first",
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 #[derive(Default)]
@@ -286,6 +472,15 @@ impl EngineMode {
         }
     }
 
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "background" => Some(Self::Background),
+            "balanced" => Some(Self::Balanced),
+            "throughput" => Some(Self::Throughput),
+            _ => None,
+        }
+    }
+
     /// Compute the appropriate concurrent job count for this
     /// mode given the number of logical CPU cores available.
     /// Returns 0 to signal "use the stored manual override".
@@ -317,6 +512,45 @@ pub struct ScannerConfig {
     /// the safe-incremental probe-cache shortcut handles them instead.
     #[serde(default)]
     pub aggressive_directory_pruning: bool,
+    /// Seconds between snapshot passes over polled watch directories.
+    #[serde(default = "default_watch_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+    /// Cap on `stat` calls per second while polling, so a large share is
+    /// walked gradually instead of all at once. `0` removes the cap.
+    #[serde(default = "default_watch_poll_max_stats_per_sec")]
+    pub poll_max_stats_per_sec: u32,
+}
+
+/// How a watch directory is monitored for new files.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchMode {
+    /// Poll on network and FUSE filesystems, native events elsewhere.
+    #[default]
+    Auto,
+    /// Kernel change notifications.
+    Native,
+    /// Periodic snapshot diffs, for mounts that never deliver events.
+    Poll,
+}
+
+impl WatchMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Native => "native",
+            Self::Poll => "poll",
+        }
+    }
+
+    /// Unknown values fall back to `Auto`.
+    pub fn from_str_lossy(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "native" => Self::Native,
+            "poll" => Self::Poll,
+            _ => Self::Auto,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
@@ -324,6 +558,12 @@ pub struct WatchDirConfig {
     pub path: String,
     #[serde(default = "default_true")]
     pub is_recursive: bool,
+    /// Globs matched relative to `path`; matching files and directories
+    /// are never scanned or watched.
+    #[serde(default)]
+    pub exclude_globs: Vec<String>,
+    #[serde(default)]
+    pub watch_mode: WatchMode,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -349,14 +589,28 @@ pub struct TranscodeConfig {
     #[serde(default = "default_tonemap_desat")]
     pub tonemap_desat: f32,
     #[serde(default)]
+    pub deinterlace_mode: DeinterlaceMode,
+    #[serde(default)]
+    pub deinterlace_algorithm: DeinterlaceAlgorithm,
+    #[serde(default)]
+    pub deinterlace_rate: DeinterlaceRate,
+    #[serde(default)]
     pub subtitle_mode: SubtitleMode,
     #[serde(default)]
     pub stream_rules: StreamRules,
     #[serde(default)]
     pub vmaf_min_score: Option<f64>,
+    /// Tie-breaker among queued jobs of equal priority.
+    #[serde(default)]
+    pub queue_order: QueueOrder,
+    /// Re-encode files that already carry Alchemist's processed-by marker
+    /// when they were made with different settings. Off by default, so
+    /// every marked file is skipped.
+    #[serde(default)]
+    pub reprocess_on_settings_change: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 pub struct StreamRules {
     /// Strip audio tracks whose title contains any of these
     /// strings (case-insensitive). Common use: ["commentary",
@@ -376,6 +630,31 @@ pub struct StreamRules {
     /// keep_audio_languages if both are set.
     #[serde(default)]
     pub keep_only_default_audio: bool,
+
+    /// Strip subtitle tracks whose title contains any of these
+    /// strings (case-insensitive). Common use: ["sdh",
+    /// "commentary"].
+    #[serde(default)]
+    pub strip_subtitles_by_title: Vec<String>,
+
+    /// If non-empty, keep ONLY subtitle tracks whose language tag
+    /// matches one of these ISO 639-2 codes. Tracks with no
+    /// language tag are always kept.
+    #[serde(default)]
+    pub keep_subtitle_languages: Vec<String>,
+
+    /// If true, keep only subtitle tracks flagged forced by the
+    /// source file.
+    #[serde(default)]
+    pub keep_only_forced_subtitles: bool,
+}
+
+impl StreamRules {
+    pub fn has_subtitle_rules(&self) -> bool {
+        !self.strip_subtitles_by_title.is_empty()
+            || !self.keep_subtitle_languages.is_empty()
+            || self.keep_only_forced_subtitles
+    }
 }
 
 // Removed default_quality_profile helper as Default trait on enum handles it now.
@@ -489,9 +768,10 @@ pub const NOTIFICATION_EVENT_ENCODE_FAILED: &str = "encode.failed";
 pub const NOTIFICATION_EVENT_SCAN_COMPLETED: &str = "scan.completed";
 pub const NOTIFICATION_EVENT_ENGINE_IDLE: &str = "engine.idle";
 pub const NOTIFICATION_EVENT_DISK_SPACE_LOW: &str = "disk.space_low";
+pub const NOTIFICATION_EVENT_SPACE_GOAL_COMPLETED: &str = "space_goal.completed";
 pub const NOTIFICATION_EVENT_DAILY_SUMMARY: &str = "daily.summary";
 
-pub const NOTIFICATION_EVENTS: [&str; 8] = [
+pub const NOTIFICATION_EVENTS: [&str; 9] = [
     NOTIFICATION_EVENT_ENCODE_QUEUED,
     NOTIFICATION_EVENT_ENCODE_STARTED,
     NOTIFICATION_EVENT_ENCODE_COMPLETED,
@@ -499,6 +779,7 @@ pub const NOTIFICATION_EVENTS: [&str; 8] = [
     NOTIFICATION_EVENT_SCAN_COMPLETED,
     NOTIFICATION_EVENT_ENGINE_IDLE,
     NOTIFICATION_EVENT_DISK_SPACE_LOW,
+    NOTIFICATION_EVENT_SPACE_GOAL_COMPLETED,
     NOTIFICATION_EVENT_DAILY_SUMMARY,
 ];
 
@@ -511,6 +792,7 @@ fn normalize_notification_event(event: &str) -> Option<&'static str> {
         "scan.completed" => Some(NOTIFICATION_EVENT_SCAN_COMPLETED),
         "engine.idle" => Some(NOTIFICATION_EVENT_ENGINE_IDLE),
         "disk.space_low" => Some(NOTIFICATION_EVENT_DISK_SPACE_LOW),
+        "space_goal.completed" => Some(NOTIFICATION_EVENT_SPACE_GOAL_COMPLETED),
         "daily.summary" => Some(NOTIFICATION_EVENT_DAILY_SUMMARY),
         _ => None,
     }
@@ -695,6 +977,18 @@ pub struct FileSettingsConfig {
     pub replace_strategy: String,
     #[serde(default)]
     pub output_root: Option<String>,
+    /// Output path template such as `{parent}/{stem} [{codec} {height}p].{ext}`.
+    /// Replaces `output_suffix` naming when set.
+    #[serde(default)]
+    pub output_template: Option<String>,
+    /// Where `delete_source` puts originals instead of deleting them.
+    #[serde(default)]
+    pub recycle_bin: RecycleBinConfig,
+    /// Move subtitles, NFO files and artwork named after the source over to
+    /// the output's name when the source is deleted or recycled and the
+    /// output is renamed or written elsewhere. A kept source keeps them.
+    #[serde(default = "default_true")]
+    pub carry_sidecars: bool,
 }
 
 impl Default for FileSettingsConfig {
@@ -705,10 +999,226 @@ impl Default for FileSettingsConfig {
             output_suffix: "-alchemist".to_string(),
             replace_strategy: "keep".to_string(),
             output_root: None,
+            output_template: None,
+            recycle_bin: RecycleBinConfig::default(),
+            carry_sidecars: true,
+        }
+    }
+}
+
+/// Recycle bin for originals removed by `delete_source`. When enabled, the
+/// original is moved aside after a successful encode and can be restored
+/// from the job until the retention policy purges it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RecycleBinConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Recycle directory. When unset, originals go to a `.alchemist-recycle`
+    /// folder beside the source so the move stays on the same filesystem.
+    #[serde(default)]
+    pub directory: Option<String>,
+    /// Purge originals held longer than this many days. `None` keeps them
+    /// until the size cap applies.
+    #[serde(default = "default_recycle_retention_days")]
+    pub retention_days: Option<u32>,
+    /// Purge the oldest originals once the bin holds more than this many GiB.
+    #[serde(default)]
+    pub max_size_gb: Option<u32>,
+}
+
+impl Default for RecycleBinConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: None,
+            retention_days: default_recycle_retention_days(),
+            max_size_gb: None,
         }
     }
 }
 
+fn default_recycle_retention_days() -> Option<u32> {
+    Some(30)
+}
+
+/// Job outcomes a post-processing hook can run after.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HookEvent {
+    Completed,
+    Failed,
+    Skipped,
+}
+
+impl HookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+/// A user command run after a job ends. The command is executed directly,
+/// not through a shell, with job details in `ALCHEMIST_*` environment
+/// variables.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HookConfig {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub events: Vec<HookEvent>,
+    /// Only run for inputs matching this glob (`*`, `?`, `**`).
+    #[serde(default)]
+    pub path_filter: Option<String>,
+    #[serde(default = "default_hook_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Log a hook that exits non-zero, times out, or cannot be started as an
+    /// error on the job. The job keeps the state it finished in.
+    #[serde(default)]
+    pub flag_job_on_error: bool,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_hook_timeout_secs() -> u64 {
+    300
+}
+
+/// How the wait before a retry grows with each attempt.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RetryBackoff {
+    /// `base_delay_secs` every time.
+    Fixed,
+    /// `base_delay_secs` times the number of attempts made.
+    Linear,
+    /// `base_delay_secs` doubled for each attempt after the first.
+    #[default]
+    Exponential,
+}
+
+/// Whether and when a job that failed one way is queued again.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Total attempts, including the first, before the job stays failed.
+    #[serde(default = "default_retry_max_attempts")]
+    pub max_attempts: u32,
+    #[serde(default)]
+    pub backoff: RetryBackoff,
+    #[serde(default = "default_retry_base_delay_secs")]
+    pub base_delay_secs: u64,
+    #[serde(default = "default_retry_max_delay_secs")]
+    pub max_delay_secs: u64,
+    /// Run the retry on the CPU encoder. Needs `hardware.allow_cpu_encoding`
+    /// and `hardware.allow_cpu_fallback`.
+    #[serde(default)]
+    pub fallback_to_cpu: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_attempts: default_retry_max_attempts(),
+            backoff: RetryBackoff::default(),
+            base_delay_secs: default_retry_base_delay_secs(),
+            max_delay_secs: default_retry_max_delay_secs(),
+            fallback_to_cpu: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn never() -> Self {
+        Self {
+            enabled: false,
+            ..Self::default()
+        }
+    }
+
+    /// The wait before the next attempt, or `None` when the job should stay
+    /// failed after `attempts_made` attempts.
+    pub fn next_delay(&self, attempts_made: u32) -> Option<std::time::Duration> {
+        if !self.enabled || attempts_made >= self.max_attempts {
+            return None;
+        }
+        let attempt = u64::from(attempts_made.max(1));
+        let secs = match self.backoff {
+            RetryBackoff::Fixed => self.base_delay_secs,
+            RetryBackoff::Linear => self.base_delay_secs.saturating_mul(attempt),
+            RetryBackoff::Exponential => self
+                .base_delay_secs
+                .saturating_mul(1u64 << (attempt - 1).min(32)),
+        };
+        Some(std::time::Duration::from_secs(
+            secs.min(self.max_delay_secs),
+        ))
+    }
+}
+
+fn default_retry_max_attempts() -> u32 {
+    3
+}
+
+fn default_retry_base_delay_secs() -> u64 {
+    300
+}
+
+fn default_retry_max_delay_secs() -> u64 {
+    6 * 60 * 60
+}
+
+/// Retry policy for each kind of job failure.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RetryConfig {
+    #[serde(default)]
+    pub transient: RetryPolicy,
+    #[serde(default = "RetryPolicy::never")]
+    pub media_corrupt: RetryPolicy,
+    #[serde(default = "default_encoder_unavailable_retry")]
+    pub encoder_unavailable: RetryPolicy,
+    #[serde(default = "RetryPolicy::never")]
+    pub planner_bug: RetryPolicy,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            transient: RetryPolicy::default(),
+            media_corrupt: RetryPolicy::never(),
+            encoder_unavailable: default_encoder_unavailable_retry(),
+            planner_bug: RetryPolicy::never(),
+        }
+    }
+}
+
+impl RetryConfig {
+    pub fn policy_for(&self, failure: &crate::media::pipeline::JobFailure) -> &RetryPolicy {
+        use crate::media::pipeline::JobFailure;
+        match failure {
+            JobFailure::Transient => &self.transient,
+            JobFailure::MediaCorrupt => &self.media_corrupt,
+            JobFailure::EncoderUnavailable => &self.encoder_unavailable,
+            JobFailure::PlannerBug => &self.planner_bug,
+        }
+    }
+}
+
+fn default_encoder_unavailable_retry() -> RetryPolicy {
+    RetryPolicy {
+        max_attempts: 2,
+        backoff: RetryBackoff::Fixed,
+        base_delay_secs: 60,
+        fallback_to_cpu: true,
+        ..RetryPolicy::default()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct ScheduleConfig {
     #[serde(default)]
@@ -723,6 +1233,21 @@ pub struct ScheduleWindowConfig {
     pub days_of_week: Vec<i32>,
     #[serde(default = "default_true")]
     pub enabled: bool,
+    /// Engine mode while the window is active.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine_mode: Option<EngineMode>,
+    /// Concurrent job limit while the window is active. Overrides the limit
+    /// `engine_mode` would compute.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub concurrent_jobs: Option<usize>,
+    /// Encoder backends jobs may start on while the window is active. Empty
+    /// allows every backend.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_backends: Vec<crate::media::pipeline::EncoderBackend>,
+    /// When windows overlap, the highest priority wins; ties go to the window
+    /// listed first.
+    #[serde(default)]
+    pub priority: i32,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -782,6 +1307,99 @@ pub struct SystemConfig {
     pub ui_theme: Option<String>,
     #[serde(default)]
     pub log_format: LogFormat,
+    #[serde(default)]
+    pub resource_governor: ResourceGovernorConfig,
+}
+
+/// What the resource governor does while the host is busy.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GovernorAction {
+    /// Start no new jobs and stop running encodes until the host is idle
+    /// again.
+    #[default]
+    Pause,
+    /// Start new jobs only while fewer than `reduced_concurrent_jobs` run.
+    Reduce,
+}
+
+/// Load-aware throttling. While any threshold is crossed or a listed process
+/// is running, the engine stops starting jobs (or starts fewer). Under
+/// `pause`, jobs already encoding are stopped until the host is idle; under
+/// `reduce` they keep running.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ResourceGovernorConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Average CPU usage, in percent, by programs other than Alchemist and
+    /// its ffmpeg processes that throttles the engine.
+    #[serde(default)]
+    pub max_cpu_percent: Option<f32>,
+    /// Memory usage, in percent, that throttles the engine.
+    #[serde(default)]
+    pub max_memory_percent: Option<f32>,
+    /// Hottest sensor reading, in °C, that throttles the engine.
+    #[serde(default)]
+    pub max_temperature_c: Option<f32>,
+    /// Process names (e.g. `Plex Transcoder`) that throttle the engine while
+    /// any of them is running. Matched case-insensitively.
+    #[serde(default)]
+    pub busy_processes: Vec<String>,
+    #[serde(default)]
+    pub action: GovernorAction,
+    /// Job limit while throttled with `action = "reduce"`.
+    #[serde(default = "default_governor_reduced_jobs")]
+    pub reduced_concurrent_jobs: usize,
+    /// How far below a threshold a reading must fall, in percentage points
+    /// or °C, before it counts as clear.
+    #[serde(default = "default_governor_resume_margin")]
+    pub resume_margin: f32,
+    /// How long every reading must stay clear before the engine resumes.
+    #[serde(default = "default_governor_resume_after_secs")]
+    pub resume_after_secs: u64,
+    #[serde(default = "default_governor_check_interval_secs")]
+    pub check_interval_secs: u64,
+}
+
+impl Default for ResourceGovernorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_cpu_percent: None,
+            max_memory_percent: None,
+            max_temperature_c: None,
+            busy_processes: Vec::new(),
+            action: GovernorAction::default(),
+            reduced_concurrent_jobs: default_governor_reduced_jobs(),
+            resume_margin: default_governor_resume_margin(),
+            resume_after_secs: default_governor_resume_after_secs(),
+            check_interval_secs: default_governor_check_interval_secs(),
+        }
+    }
+}
+
+fn default_governor_reduced_jobs() -> usize {
+    1
+}
+
+fn default_governor_resume_margin() -> f32 {
+    10.0
+}
+
+fn default_governor_resume_after_secs() -> u64 {
+    60
+}
+
+fn default_governor_check_interval_secs() -> u64 {
+    10
+}
+
+pub(crate) fn default_watch_poll_interval_secs() -> u64 {
+    60
+}
+
+pub(crate) fn default_watch_poll_max_stats_per_sec() -> u32 {
+    1000
 }
 
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
@@ -808,6 +1426,41 @@ pub struct ArrPathTranslation {
     pub to: String,
 }
 
+impl ArrPathTranslation {
+    fn apply(&self, path: &str) -> Option<String> {
+        let from = self.from.trim();
+        let to = self.to.trim();
+        if from.is_empty() || to.is_empty() || !path_has_prefix(path, from) {
+            return None;
+        }
+        let suffix = path.strip_prefix(from).unwrap_or_default();
+        Some(format!("{to}{suffix}"))
+    }
+
+    /// Rewrite `path` with the translation whose `from` prefix matches it
+    /// most specifically, the first one on a tie; unmatched paths are
+    /// returned unchanged.
+    pub fn translate(path: &str, translations: &[ArrPathTranslation]) -> String {
+        translations
+            .iter()
+            .rev()
+            .filter(|translation| translation.apply(path).is_some())
+            .max_by_key(|translation| translation.from.len())
+            .and_then(|translation| translation.apply(path))
+            .unwrap_or_else(|| path.to_string())
+    }
+}
+
+fn path_has_prefix(path: &str, prefix: &str) -> bool {
+    if path == prefix {
+        return true;
+    }
+    match path.strip_prefix(prefix) {
+        Some(rest) => rest.starts_with('/') || rest.starts_with('\\'),
+        None => false,
+    }
+}
+
 fn default_true() -> bool {
     true
 }
@@ -866,6 +1519,7 @@ impl Default for SystemConfig {
             arr_path_translations: Vec::new(),
             ui_theme: None,
             log_format: LogFormat::default(),
+            resource_governor: ResourceGovernorConfig::default(),
         }
     }
 }
@@ -956,9 +1610,14 @@ impl Default for Config {
                 tonemap_algorithm: TonemapAlgorithm::Hable,
                 tonemap_peak: default_tonemap_peak(),
                 tonemap_desat: default_tonemap_desat(),
+                deinterlace_mode: DeinterlaceMode::Off,
+                deinterlace_algorithm: DeinterlaceAlgorithm::Bwdif,
+                deinterlace_rate: DeinterlaceRate::SendFrame,
                 subtitle_mode: SubtitleMode::Copy,
                 stream_rules: StreamRules::default(),
                 vmaf_min_score: None,
+                queue_order: QueueOrder::default(),
+                reprocess_on_settings_change: false,
             },
             hardware: HardwareConfig {
                 preferred_vendor: None,
@@ -972,6 +1631,8 @@ impl Default for Config {
                 watch_enabled: false,
                 extra_watch_dirs: Vec::new(),
                 aggressive_directory_pruning: false,
+                poll_interval_secs: default_watch_poll_interval_secs(),
+                poll_max_stats_per_sec: default_watch_poll_max_stats_per_sec(),
             },
             notifications: NotificationsConfig::default(),
             files: FileSettingsConfig::default(),
@@ -991,7 +1652,10 @@ impl Default for Config {
                 arr_path_translations: Vec::new(),
                 ui_theme: None,
                 log_format: LogFormat::default(),
+                resource_governor: ResourceGovernorConfig::default(),
             },
+            hooks: Vec::new(),
+            retry: RetryConfig::default(),
         }
     }
 }
@@ -1039,6 +1703,70 @@ impl Config {
                 self.updates.check_interval_hours
             );
         }
+        if !(5..=86_400).contains(&self.scanner.poll_interval_secs) {
+            anyhow::bail!(
+                "scanner.poll_interval_secs must be between 5 and 86400, got {}",
+                self.scanner.poll_interval_secs
+            );
+        }
+        if self.files.recycle_bin.retention_days == Some(0) {
+            anyhow::bail!("files.recycle_bin.retention_days must be >= 1 when set");
+        }
+        if self.files.recycle_bin.max_size_gb == Some(0) {
+            anyhow::bail!("files.recycle_bin.max_size_gb must be >= 1 when set");
+        }
+        let governor = &self.system.resource_governor;
+        for (field, value) in [
+            ("max_cpu_percent", governor.max_cpu_percent),
+            ("max_memory_percent", governor.max_memory_percent),
+        ] {
+            if let Some(value) = value
+                && (value.is_nan() || value <= 0.0 || value > 100.0)
+            {
+                anyhow::bail!("system.resource_governor.{field} must be in (0, 100]");
+            }
+        }
+        if let Some(value) = governor.max_temperature_c
+            && (value.is_nan() || value <= 0.0)
+        {
+            anyhow::bail!("system.resource_governor.max_temperature_c must be > 0");
+        }
+        if governor.reduced_concurrent_jobs == 0 {
+            anyhow::bail!("system.resource_governor.reduced_concurrent_jobs must be >= 1");
+        }
+        if governor.resume_margin.is_nan() || governor.resume_margin < 0.0 {
+            anyhow::bail!("system.resource_governor.resume_margin must be >= 0");
+        }
+        if governor.check_interval_secs == 0 {
+            anyhow::bail!("system.resource_governor.check_interval_secs must be >= 1");
+        }
+        for (kind, policy) in [
+            ("transient", &self.retry.transient),
+            ("media_corrupt", &self.retry.media_corrupt),
+            ("encoder_unavailable", &self.retry.encoder_unavailable),
+            ("planner_bug", &self.retry.planner_bug),
+        ] {
+            if policy.max_attempts == 0 {
+                anyhow::bail!("retry.{kind}.max_attempts must be >= 1");
+            }
+            if policy.max_delay_secs < policy.base_delay_secs {
+                anyhow::bail!("retry.{kind}.max_delay_secs must be >= base_delay_secs");
+            }
+        }
+        for hook in &self.hooks {
+            if hook.name.trim().is_empty() {
+                anyhow::bail!("hooks[].name must not be empty");
+            }
+            if hook.command.trim().is_empty() {
+                anyhow::bail!("hook '{}' must set a command", hook.name);
+            }
+            if hook.events.is_empty() {
+                anyhow::bail!("hook '{}' must list at least one event", hook.name);
+            }
+            if hook.timeout_secs == 0 {
+                anyhow::bail!("hook '{}' timeout_secs must be >= 1", hook.name);
+            }
+        }
         for translation in &self.system.arr_path_translations {
             if translation.from.trim().is_empty() {
                 anyhow::bail!("system.arr_path_translations[].from must not be empty");
@@ -1104,6 +1832,16 @@ impl Config {
             anyhow::bail!("files.output_suffix must not contain path separators");
         }
 
+        if let Some(template) = self
+            .files
+            .output_template
+            .as_deref()
+            .filter(|template| !template.trim().is_empty())
+        {
+            crate::media::output_template::OutputTemplate::parse(template)
+                .map_err(|err| anyhow::anyhow!("files.output_template: {err}"))?;
+        }
+
         for window in &self.schedule.windows {
             validate_schedule_time(&window.start_time)?;
             validate_schedule_time(&window.end_time)?;
@@ -1117,6 +1855,9 @@ impl Config {
             {
                 anyhow::bail!("schedule.windows days_of_week must contain values 0-6");
             }
+            if window.concurrent_jobs == Some(0) {
+                anyhow::bail!("schedule.windows concurrent_jobs must be >= 1 when set");
+            }
         }
 
         validate_schedule_time(&self.notifications.daily_summary_time_local)?;
@@ -2,8 +2,8 @@ use crate::config::{OutputCodec, TonemapAlgorithm};
 use crate::error::{AlchemistError, Result};
 use crate::media::ffmpeg::{FFmpegCommandBuilder, encoder_caps_clone};
 use crate::media::pipeline::{
-    AudioCodec, AudioStreamPlan, Encoder, EncoderBackend, FilterStep, MediaAnalysis, RateControl,
-    SubtitleStreamPlan, TranscodeDecision, TranscodePlan,
+    AudioCodec, AudioStreamPlan, AudioTrackPlan, Encoder, EncoderBackend, FilterStep,
+    MediaAnalysis, RateControl, SubtitleStreamPlan, TranscodeDecision, TranscodePlan,
 };
 use crate::system::hardware::HardwareInfo;
 use serde::{Deserialize, Serialize};
@@ -153,12 +153,15 @@ pub fn build_plan(
             rate_control: None,
             encoder_preset: None,
             threads: 0,
-            audio: AudioStreamPlan::Copy,
-            audio_stream_indices: None,
+            audio: vec![AudioTrackPlan::all_streams(AudioStreamPlan::Copy)],
             subtitles: SubtitleStreamPlan::CopyAllCompatible,
             filters: Vec::new(),
             allow_fallback: true,
             fallback: None,
+            preserve_dolby_vision: false,
+            preserve_hdr10_plus: false,
+            downgrades: Vec::new(),
+            marker: None,
         });
     }
 
@@ -251,7 +254,9 @@ pub fn build_plan(
         filters.push(FilterStep::SubtitleBurn { stream_index });
     }
 
-    let audio = build_audio_plan(&normalized.audio)?;
+    let audio = vec![AudioTrackPlan::all_streams(build_audio_plan(
+        &normalized.audio,
+    )?)];
 
     Ok(TranscodePlan {
         decision: TranscodeDecision::Transcode {
@@ -269,11 +274,14 @@ pub fn build_plan(
         encoder_preset: normalized.video.preset.clone(),
         threads: 0,
         audio,
-        audio_stream_indices: None,
         subtitles,
         filters,
         allow_fallback: true,
         fallback: None,
+        preserve_dolby_vision: false,
+        preserve_hdr10_plus: false,
+        downgrades: Vec::new(),
+        marker: None,
     })
 }
 
@@ -428,14 +436,37 @@ fn planned_hdr_mode(plan: &TranscodePlan) -> String {
     "preserve".to_string()
 }
 
-fn planned_audio_summary(audio: &AudioStreamPlan) -> String {
-    match audio {
+fn planned_audio_summary(audio: &[AudioTrackPlan]) -> String {
+    let outputs: Vec<String> = audio
+        .iter()
+        .filter(|track| track.is_output())
+        .map(|track| planned_audio_action_summary(&track.action))
+        .collect();
+    if outputs.is_empty() {
+        return "remove".to_string();
+    }
+    outputs.join(" + ")
+}
+
+fn planned_audio_action_summary(action: &AudioStreamPlan) -> String {
+    match action {
         AudioStreamPlan::Copy => "copy".to_string(),
         AudioStreamPlan::Drop => "remove".to_string(),
+        AudioStreamPlan::DeriveDownmix {
+            codec,
+            bitrate_kbps,
+            channels,
+        } => format!(
+            "{} / {} kbps / {}ch (added)",
+            codec.ffmpeg_name(),
+            bitrate_kbps,
+            channels
+        ),
         AudioStreamPlan::Transcode {
             codec,
             bitrate_kbps,
             channels,
+            ..
         } => channels
             .map(|value| {
                 format!(
@@ -455,6 +486,13 @@ fn planned_subtitle_summary(subtitles: &SubtitleStreamPlan) -> String {
         SubtitleStreamPlan::Drop => "remove".to_string(),
         SubtitleStreamPlan::Burn { .. } => "burn in".to_string(),
         SubtitleStreamPlan::Extract { .. } => "extract".to_string(),
+        SubtitleStreamPlan::Select { streams, sidecars } => {
+            let mut summary = format!("keep {}", streams.len());
+            if !sidecars.is_empty() {
+                summary.push_str(&format!(" + {} sidecar", sidecars.len()));
+            }
+            summary
+        }
     }
 }
 
@@ -475,7 +513,11 @@ fn estimate_output_size(
     }
 
     let copy_all_streams = (plan.is_remux || plan.copy_video)
-        && matches!(plan.audio, AudioStreamPlan::Copy)
+        && !plan.audio.is_empty()
+        && plan
+            .audio
+            .iter()
+            .all(|track| matches!(track.action, AudioStreamPlan::Copy))
         && matches!(plan.subtitles, SubtitleStreamPlan::CopyAllCompatible)
         && plan.filters.is_empty();
     if copy_all_streams {
@@ -515,11 +557,18 @@ fn estimate_output_size(
             _ => (source_video_bps as f64 * codec_ratio(plan) * quality_multiplier(plan)).max(1.0),
         }
     };
-    let planned_audio_bps = match &plan.audio {
-        AudioStreamPlan::Copy => analysis.metadata.audio_bitrate_bps.unwrap_or(0) as f64,
-        AudioStreamPlan::Transcode { bitrate_kbps, .. } => f64::from(*bitrate_kbps) * 1000.0,
-        AudioStreamPlan::Drop => 0.0,
-    };
+    let planned_audio_bps: f64 = plan
+        .audio
+        .iter()
+        .map(|track| match &track.action {
+            AudioStreamPlan::Copy => analysis.metadata.audio_bitrate_bps.unwrap_or(0) as f64,
+            AudioStreamPlan::Transcode { bitrate_kbps, .. }
+            | AudioStreamPlan::DeriveDownmix { bitrate_kbps, .. } => {
+                f64::from(*bitrate_kbps) * 1000.0
+            }
+            AudioStreamPlan::Drop => 0.0,
+        })
+        .sum();
 
     let output_bits = (planned_video_bps + planned_audio_bps) * duration;
     let estimated_output_bytes = ((output_bits / 8.0) * 1.03).round().max(1.0) as u64;
@@ -680,16 +729,19 @@ fn build_audio_plan(settings: &ConversionAudioSettings) -> Result<AudioStreamPla
             codec: AudioCodec::Aac,
             bitrate_kbps: settings.bitrate_kbps.unwrap_or(160),
             channels: parse_audio_channels(settings.channels.as_deref()),
+            loudness: None,
         }),
         "opus" => Ok(AudioStreamPlan::Transcode {
             codec: AudioCodec::Opus,
             bitrate_kbps: settings.bitrate_kbps.unwrap_or(160),
             channels: parse_audio_channels(settings.channels.as_deref()),
+            loudness: None,
         }),
         "mp3" => Ok(AudioStreamPlan::Transcode {
             codec: AudioCodec::Mp3,
             bitrate_kbps: settings.bitrate_kbps.unwrap_or(192),
             channels: parse_audio_channels(settings.channels.as_deref()),
+            loudness: None,
         }),
         "remove" | "drop" | "none" => Ok(AudioStreamPlan::Drop),
         other => Err(AlchemistError::Config(format!(
@@ -892,6 +944,7 @@ mod tests {
                 audio_streams: Vec::new(),
                 dynamic_range: DynamicRange::Sdr,
                 chapter_count: 0,
+                alchemist_marker: None,
             },
             warnings: Vec::new(),
             confidence: AnalysisConfidence::High,
@@ -915,12 +968,15 @@ mod tests {
             rate_control: Some(RateControl::Crf { value: 24 }),
             encoder_preset: Some("medium".to_string()),
             threads: 0,
-            audio: AudioStreamPlan::Copy,
-            audio_stream_indices: None,
+            audio: vec![AudioTrackPlan::all_streams(AudioStreamPlan::Copy)],
             subtitles: SubtitleStreamPlan::CopyAllCompatible,
             filters: Vec::new(),
             allow_fallback: true,
             fallback: None,
+            preserve_dolby_vision: false,
+            preserve_hdr10_plus: false,
+            downgrades: Vec::new(),
+            marker: None,
         }
     }
 
@@ -951,11 +1007,12 @@ mod tests {
         let analysis = sample_analysis();
         let mut plan = sample_plan();
         plan.rate_control = Some(RateControl::Bitrate { kbps: 2_000 });
-        plan.audio = AudioStreamPlan::Transcode {
+        plan.audio = vec![AudioTrackPlan::all_streams(AudioStreamPlan::Transcode {
             codec: AudioCodec::Aac,
             bitrate_kbps: 128,
             channels: Some(2),
-        };
+            loudness: None,
+        })];
 
         let summary = build_preview_summary(&analysis, &plan, "mkv");
 
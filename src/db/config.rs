@@ -61,6 +61,8 @@ impl Db {
         let has_recursive = self.watch_dir_flags.has_recursive;
         let has_enabled = self.watch_dir_flags.has_enabled;
         let has_profile_id = self.watch_dir_flags.has_profile_id;
+        let has_exclude_globs = self.watch_dir_flags.has_exclude_globs;
+        let has_watch_mode = self.watch_dir_flags.has_watch_mode;
 
         let recursive_expr = if has_is_recursive {
             "is_recursive"
@@ -76,10 +78,21 @@ impl Db {
             ""
         };
         let profile_expr = if has_profile_id { "profile_id" } else { "NULL" };
+        let exclude_expr = if has_exclude_globs {
+            "exclude_globs_json"
+        } else {
+            "'[]'"
+        };
+        let mode_expr = if has_watch_mode {
+            "watch_mode"
+        } else {
+            "'auto'"
+        };
         let query = format!(
-            "SELECT id, path, {} as is_recursive, {} as profile_id, created_at
+            "SELECT id, path, {} as is_recursive, {} as profile_id,
+                    {} as exclude_globs_json, {} as watch_mode, created_at
              FROM watch_dirs {}ORDER BY path ASC",
-            recursive_expr, profile_expr, enabled_filter
+            recursive_expr, profile_expr, exclude_expr, mode_expr, enabled_filter
         );
 
         let dirs = sqlx::query_as::<_, WatchDir>(&query)
@@ -96,7 +109,8 @@ impl Db {
         let row = if has_is_recursive && has_profile_id {
             sqlx::query_as::<_, WatchDir>(
                 "INSERT INTO watch_dirs (path, is_recursive) VALUES (?, ?)
-                 RETURNING id, path, is_recursive, profile_id, created_at",
+                 RETURNING id, path, is_recursive, profile_id,
+                           '[]' as exclude_globs_json, 'auto' as watch_mode, created_at",
             )
             .bind(path)
             .bind(is_recursive)
@@ -105,7 +119,8 @@ impl Db {
         } else if has_is_recursive {
             sqlx::query_as::<_, WatchDir>(
                 "INSERT INTO watch_dirs (path, is_recursive) VALUES (?, ?)
-                 RETURNING id, path, is_recursive, NULL as profile_id, created_at",
+                 RETURNING id, path, is_recursive, NULL as profile_id,
+                           '[]' as exclude_globs_json, 'auto' as watch_mode, created_at",
             )
             .bind(path)
             .bind(is_recursive)
@@ -114,7 +129,8 @@ impl Db {
         } else if has_recursive && has_profile_id {
             sqlx::query_as::<_, WatchDir>(
                 "INSERT INTO watch_dirs (path, recursive) VALUES (?, ?)
-                 RETURNING id, path, recursive as is_recursive, profile_id, created_at",
+                 RETURNING id, path, recursive as is_recursive, profile_id,
+                           '[]' as exclude_globs_json, 'auto' as watch_mode, created_at",
             )
             .bind(path)
             .bind(is_recursive)
@@ -123,7 +139,8 @@ impl Db {
         } else if has_recursive {
             sqlx::query_as::<_, WatchDir>(
                 "INSERT INTO watch_dirs (path, recursive) VALUES (?, ?)
-                 RETURNING id, path, recursive as is_recursive, NULL as profile_id, created_at",
+                 RETURNING id, path, recursive as is_recursive, NULL as profile_id,
+                           '[]' as exclude_globs_json, 'auto' as watch_mode, created_at",
             )
             .bind(path)
             .bind(is_recursive)
@@ -132,7 +149,8 @@ impl Db {
         } else {
             sqlx::query_as::<_, WatchDir>(
                 "INSERT INTO watch_dirs (path) VALUES (?)
-                 RETURNING id, path, 1 as is_recursive, NULL as profile_id, created_at",
+                 RETURNING id, path, 1 as is_recursive, NULL as profile_id,
+                           '[]' as exclude_globs_json, 'auto' as watch_mode, created_at",
             )
             .bind(path)
             .fetch_one(&self.pool)
@@ -148,6 +166,8 @@ impl Db {
         let has_is_recursive = self.watch_dir_flags.has_is_recursive;
         let has_recursive = self.watch_dir_flags.has_recursive;
         let has_profile_id = self.watch_dir_flags.has_profile_id;
+        let has_exclude_globs = self.watch_dir_flags.has_exclude_globs;
+        let has_watch_mode = self.watch_dir_flags.has_watch_mode;
         let preserved_profiles = if has_profile_id {
             let rows = sqlx::query("SELECT path, profile_id FROM watch_dirs")
                 .fetch_all(&self.pool)
@@ -198,6 +218,23 @@ impl Db {
                     .execute(&mut *tx)
                     .await?;
             }
+            if has_exclude_globs {
+                sqlx::query("UPDATE watch_dirs SET exclude_globs_json = ? WHERE path = ?")
+                    .bind(
+                        serde_json::to_string(&watch_dir.exclude_globs)
+                            .unwrap_or_else(|_| "[]".to_string()),
+                    )
+                    .bind(&watch_dir.path)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            if has_watch_mode {
+                sqlx::query("UPDATE watch_dirs SET watch_mode = ? WHERE path = ?")
+                    .bind(watch_dir.watch_mode.as_str())
+                    .bind(&watch_dir.path)
+                    .execute(&mut *tx)
+                    .await?;
+            }
         }
         tx.commit().await?;
         Ok(())
@@ -244,7 +281,8 @@ impl Db {
     pub async fn get_all_profiles(&self) -> Result<Vec<LibraryProfile>> {
         let profiles = sqlx::query_as::<_, LibraryProfile>(
             "SELECT id, name, preset, codec, quality_profile, hdr_mode, audio_mode,
-                    crf_override, notes, created_at, updated_at
+                    crf_override, target_vmaf, auto_crop, max_resolution, dolby_vision_mode,
+                    loudness_target_lufs, output_variants_json, notes, created_at, updated_at
              FROM library_profiles
              ORDER BY id ASC",
         )
@@ -256,7 +294,8 @@ impl Db {
     pub async fn get_profile(&self, id: i64) -> Result<Option<LibraryProfile>> {
         let profile = sqlx::query_as::<_, LibraryProfile>(
             "SELECT id, name, preset, codec, quality_profile, hdr_mode, audio_mode,
-                    crf_override, notes, created_at, updated_at
+                    crf_override, target_vmaf, auto_crop, max_resolution, dolby_vision_mode,
+                    loudness_target_lufs, output_variants_json, notes, created_at, updated_at
              FROM library_profiles
              WHERE id = ?",
         )
@@ -269,8 +308,10 @@ impl Db {
     pub async fn create_profile(&self, profile: NewLibraryProfile) -> Result<i64> {
         let id = sqlx::query(
             "INSERT INTO library_profiles
-                (name, preset, codec, quality_profile, hdr_mode, audio_mode, crf_override, notes, updated_at)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)",
+                (name, preset, codec, quality_profile, hdr_mode, audio_mode, crf_override,
+                 target_vmaf, auto_crop, max_resolution, dolby_vision_mode, loudness_target_lufs,
+                 output_variants_json, notes, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)",
         )
         .bind(profile.name)
         .bind(profile.preset)
@@ -279,6 +320,12 @@ impl Db {
         .bind(profile.hdr_mode)
         .bind(profile.audio_mode)
         .bind(profile.crf_override)
+        .bind(profile.target_vmaf)
+        .bind(profile.auto_crop)
+        .bind(profile.max_resolution)
+        .bind(profile.dolby_vision_mode)
+        .bind(profile.loudness_target_lufs)
+        .bind(profile.output_variants_json)
         .bind(profile.notes)
         .execute(&self.pool)
         .await?
@@ -296,6 +343,12 @@ impl Db {
                  hdr_mode = ?,
                  audio_mode = ?,
                  crf_override = ?,
+                 target_vmaf = ?,
+                 auto_crop = ?,
+                 max_resolution = ?,
+                 dolby_vision_mode = ?,
+                 loudness_target_lufs = ?,
+                 output_variants_json = ?,
                  notes = ?,
                  updated_at = CURRENT_TIMESTAMP
              WHERE id = ?",
@@ -307,6 +360,12 @@ impl Db {
         .bind(profile.hdr_mode)
         .bind(profile.audio_mode)
         .bind(profile.crf_override)
+        .bind(profile.target_vmaf)
+        .bind(profile.auto_crop)
+        .bind(profile.max_resolution)
+        .bind(profile.dolby_vision_mode)
+        .bind(profile.loudness_target_lufs)
+        .bind(profile.output_variants_json)
         .bind(profile.notes)
         .bind(id)
         .execute(&self.pool)
@@ -362,7 +421,9 @@ impl Db {
         let normalized = Path::new(path);
         let candidate = sqlx::query_as::<_, LibraryProfile>(
             "SELECT lp.id, lp.name, lp.preset, lp.codec, lp.quality_profile, lp.hdr_mode,
-                    lp.audio_mode, lp.crf_override, lp.notes, lp.created_at, lp.updated_at
+                    lp.audio_mode, lp.crf_override, lp.target_vmaf, lp.auto_crop, lp.max_resolution,
+                    lp.dolby_vision_mode, lp.loudness_target_lufs, lp.output_variants_json,
+                    lp.notes, lp.created_at, lp.updated_at
              FROM watch_dirs wd
              JOIN library_profiles lp ON lp.id = wd.profile_id
              WHERE wd.profile_id IS NOT NULL
@@ -395,7 +456,9 @@ impl Db {
         let rows = sqlx::query(
             "SELECT wd.path,
                     lp.id, lp.name, lp.preset, lp.codec, lp.quality_profile, lp.hdr_mode,
-                    lp.audio_mode, lp.crf_override, lp.notes, lp.created_at, lp.updated_at
+                    lp.audio_mode, lp.crf_override, lp.target_vmaf, lp.auto_crop, lp.max_resolution,
+                    lp.dolby_vision_mode, lp.loudness_target_lufs, lp.output_variants_json,
+                    lp.notes, lp.created_at, lp.updated_at
              FROM watch_dirs wd
              JOIN library_profiles lp ON lp.id = wd.profile_id
              WHERE wd.profile_id IS NOT NULL",
@@ -415,6 +478,12 @@ impl Db {
                 hdr_mode: row.get("hdr_mode"),
                 audio_mode: row.get("audio_mode"),
                 crf_override: row.get("crf_override"),
+                target_vmaf: row.get("target_vmaf"),
+                auto_crop: row.get("auto_crop"),
+                max_resolution: row.get("max_resolution"),
+                dolby_vision_mode: row.get("dolby_vision_mode"),
+                loudness_target_lufs: row.get("loudness_target_lufs"),
+                output_variants_json: row.get("output_variants_json"),
                 notes: row.get("notes"),
                 created_at: row.get("created_at"),
                 updated_at: row.get("updated_at"),
@@ -442,6 +511,102 @@ impl Db {
         Ok(row.0)
     }
 
+    pub async fn count_profile_rules_using_profile(&self, profile_id: i64) -> Result<i64> {
+        let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM profile_rules WHERE profile_id = ?")
+            .bind(profile_id)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(row.0)
+    }
+
+    /// All profile rules in evaluation order.
+    pub async fn get_profile_rules(&self) -> Result<Vec<ProfileRule>> {
+        let rules = sqlx::query_as::<_, ProfileRule>(
+            "SELECT id, name, position, enabled, conditions_json, action, profile_id,
+                    created_at, updated_at
+             FROM profile_rules
+             ORDER BY position ASC, id ASC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rules)
+    }
+
+    pub async fn get_profile_rule(&self, id: i64) -> Result<Option<ProfileRule>> {
+        let rule = sqlx::query_as::<_, ProfileRule>(
+            "SELECT id, name, position, enabled, conditions_json, action, profile_id,
+                    created_at, updated_at
+             FROM profile_rules
+             WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(rule)
+    }
+
+    pub async fn create_profile_rule(&self, rule: NewProfileRule) -> Result<i64> {
+        let id = sqlx::query(
+            "INSERT INTO profile_rules
+                (name, position, enabled, conditions_json, action, profile_id, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)",
+        )
+        .bind(rule.name)
+        .bind(rule.position)
+        .bind(rule.enabled)
+        .bind(rule.conditions_json)
+        .bind(rule.action)
+        .bind(rule.profile_id)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        Ok(id)
+    }
+
+    pub async fn update_profile_rule(&self, id: i64, rule: NewProfileRule) -> Result<()> {
+        let result = sqlx::query(
+            "UPDATE profile_rules
+             SET name = ?,
+                 position = ?,
+                 enabled = ?,
+                 conditions_json = ?,
+                 action = ?,
+                 profile_id = ?,
+                 updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?",
+        )
+        .bind(rule.name)
+        .bind(rule.position)
+        .bind(rule.enabled)
+        .bind(rule.conditions_json)
+        .bind(rule.action)
+        .bind(rule.profile_id)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(crate::error::AlchemistError::Database(
+                sqlx::Error::RowNotFound,
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub async fn delete_profile_rule(&self, id: i64) -> Result<()> {
+        let result = sqlx::query("DELETE FROM profile_rules WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(crate::error::AlchemistError::Database(
+                sqlx::Error::RowNotFound,
+            ));
+        }
+        Ok(())
+    }
+
     pub async fn get_notification_targets(&self) -> Result<Vec<NotificationTarget>> {
         let flags = &self.notification_target_flags;
         let targets = if flags.has_target_type_v2 {
@@ -685,12 +850,22 @@ impl Db {
             .await?;
         for window in windows {
             sqlx::query(
-                "INSERT INTO schedule_windows (start_time, end_time, days_of_week, enabled) VALUES (?, ?, ?, ?)",
+                "INSERT INTO schedule_windows
+                    (start_time, end_time, days_of_week, enabled, engine_mode, concurrent_jobs,
+                     allowed_backends, priority)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
             )
             .bind(&window.start_time)
             .bind(&window.end_time)
             .bind(serde_json::to_string(&window.days_of_week).unwrap_or_else(|_| "[]".to_string()))
             .bind(window.enabled)
+            .bind(window.engine_mode.map(|mode| mode.as_str()))
+            .bind(window.concurrent_jobs.map(|jobs| jobs as i64))
+            .bind(
+                serde_json::to_string(&window.allowed_backends)
+                    .unwrap_or_else(|_| "[]".to_string()),
+            )
+            .bind(window.priority)
             .execute(&mut *tx)
             .await?;
         }
@@ -715,6 +890,7 @@ impl Db {
                     output_suffix: "-alchemist".to_string(),
                     replace_strategy: "keep".to_string(),
                     output_root: None,
+                    output_template: None,
                 })
             }
         }
@@ -727,10 +903,12 @@ impl Db {
         output_suffix: &str,
         replace_strategy: &str,
         output_root: Option<&str>,
+        output_template: Option<&str>,
     ) -> Result<FileSettings> {
         let row = sqlx::query_as::<_, FileSettings>(
             "UPDATE file_settings
-            SET delete_source = ?, output_extension = ?, output_suffix = ?, replace_strategy = ?, output_root = ?
+            SET delete_source = ?, output_extension = ?, output_suffix = ?, replace_strategy = ?, output_root = ?,
+                output_template = ?
             WHERE id = 1
             RETURNING *",
         )
@@ -739,6 +917,7 @@ impl Db {
         .bind(output_suffix)
         .bind(replace_strategy)
         .bind(output_root)
+        .bind(output_template)
         .fetch_one(&self.pool)
         .await?;
         Ok(row)
@@ -754,6 +933,7 @@ impl Db {
             &settings.output_suffix,
             &settings.replace_strategy,
             settings.output_root.as_deref(),
+            settings.output_template.as_deref(),
         )
         .await
     }
@@ -809,6 +989,12 @@ mod tests {
             hdr_mode: "preserve".to_string(),
             audio_mode: "copy".to_string(),
             crf_override: None,
+            target_vmaf: None,
+            auto_crop: false,
+            max_resolution: None,
+            dolby_vision_mode: "strip".to_string(),
+            loudness_target_lufs: None,
+            output_variants_json: "[]".to_string(),
             notes: None,
         }
     }
@@ -51,6 +51,13 @@ pub enum SystemEvent {
     DiskSpaceLow {
         reason: String,
     },
+    /// A space goal was met or reached its deadline. `outcome` is the goal's
+    /// final status (`met` or `expired`).
+    SpaceGoalCompleted {
+        goal_id: i64,
+        outcome: String,
+        message: String,
+    },
 }
 
 pub struct EventChannels {
@@ -0,0 +1,55 @@
+use crate::error::Result;
+
+use super::Db;
+use super::types::JobSidecar;
+
+impl Db {
+    pub async fn record_job_sidecar(
+        &self,
+        job_id: i64,
+        source_path: &str,
+        output_path: &str,
+        action: &str,
+    ) -> Result<i64> {
+        let id = sqlx::query(
+            "INSERT INTO job_sidecars (job_id, source_path, output_path, action)
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(job_id)
+        .bind(source_path)
+        .bind(output_path)
+        .bind(action)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        Ok(id)
+    }
+
+    pub async fn get_job_sidecars(&self, job_id: i64) -> Result<Vec<JobSidecar>> {
+        let sidecars = sqlx::query_as::<_, JobSidecar>(
+            "SELECT * FROM job_sidecars WHERE job_id = ? ORDER BY id ASC",
+        )
+        .bind(job_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(sidecars)
+    }
+
+    /// Forget sidecars recorded by an earlier run of the job.
+    pub async fn clear_job_sidecars(&self, job_id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM job_sidecars WHERE job_id = ?")
+            .bind(job_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_job_sidecar_action(&self, id: i64, action: &str) -> Result<()> {
+        sqlx::query("UPDATE job_sidecars SET action = ? WHERE id = ?")
+            .bind(action)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use crate::error::Result;
+
+use super::Db;
+use super::types::{JobVariant, VariantLink};
+
+impl Db {
+    /// Queue the variant `name` of `parent_job_id` as a job of its own that
+    /// writes `output_path`, returning the variant job's id. `input_key`
+    /// names the variant job uniquely; its real input is the parent's
+    /// source. A variant job that is running is left as it is; otherwise it
+    /// starts over.
+    pub async fn enqueue_variant_job(
+        &self,
+        parent_job_id: i64,
+        name: &str,
+        input_key: &str,
+        output_path: &str,
+    ) -> Result<i64> {
+        let mut tx = self.pool.begin().await?;
+        let requeued: Option<i64> = sqlx::query_scalar(
+            "INSERT INTO jobs (input_path, output_path, status, mtime_hash, updated_at)
+             VALUES (?, ?, 'queued', '', CURRENT_TIMESTAMP)
+             ON CONFLICT(input_path) DO UPDATE SET
+                output_path = excluded.output_path,
+                status = 'queued',
+                progress = 0.0,
+                attempt_count = 0,
+                next_retry_at = NULL,
+                retry_on_cpu = 0,
+                archived = 0,
+                updated_at = CURRENT_TIMESTAMP
+             WHERE status NOT IN ('analyzing', 'encoding', 'remuxing', 'resuming')
+             RETURNING id",
+        )
+        .bind(input_key)
+        .bind(output_path)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let variant_job_id = match requeued {
+            Some(id) => id,
+            None => {
+                sqlx::query_scalar("SELECT id FROM jobs WHERE input_path = ?")
+                    .bind(input_key)
+                    .fetch_one(&mut *tx)
+                    .await?
+            }
+        };
+        sqlx::query(
+            "INSERT INTO job_variants (job_id, name, output_path, variant_job_id)
+             VALUES (?, ?, ?, ?)
+             ON CONFLICT(job_id, name) DO UPDATE SET
+                output_path = excluded.output_path,
+                variant_job_id = excluded.variant_job_id,
+                updated_at = CURRENT_TIMESTAMP",
+        )
+        .bind(parent_job_id)
+        .bind(name)
+        .bind(output_path)
+        .bind(variant_job_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(variant_job_id)
+    }
+
+    /// The parent and variant name of `job_id` when it encodes an output
+    /// variant.
+    pub async fn get_variant_link(&self, job_id: i64) -> Result<Option<VariantLink>> {
+        let link = sqlx::query_as::<_, VariantLink>(
+            "SELECT job_id AS parent_job_id, name FROM job_variants WHERE variant_job_id = ?",
+        )
+        .bind(job_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(link)
+    }
+
+    pub async fn get_job_variants(&self, job_id: i64) -> Result<Vec<JobVariant>> {
+        let variants = sqlx::query_as::<_, JobVariant>(
+            "SELECT v.id, v.job_id, v.variant_job_id, v.name,
+                    COALESCE(j.output_path, v.output_path) AS output_path,
+                    COALESCE(j.status, v.status) AS status,
+                    (SELECT reason FROM decisions
+                     WHERE job_id = j.id
+                     ORDER BY created_at DESC, id DESC LIMIT 1) AS decision_reason,
+                    CASE WHEN j.status = 'failed' THEN f.legacy_summary END AS failure_summary,
+                    s.input_size_bytes, s.output_size_bytes, s.encode_time_seconds,
+                    v.created_at, COALESCE(j.updated_at, v.updated_at) AS updated_at
+             FROM job_variants v
+             LEFT JOIN jobs j ON j.id = v.variant_job_id
+             LEFT JOIN job_failure_explanations f ON f.job_id = j.id
+             LEFT JOIN encode_stats s ON s.job_id = j.id
+             WHERE v.job_id = ?
+             ORDER BY v.id ASC",
+        )
+        .bind(job_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(variants)
+    }
+}
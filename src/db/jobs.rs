@@ -15,17 +15,72 @@ use super::types::*;
 /// insert). Keeping it in one place ensures the `ON CONFLICT` change-detection
 /// semantics never drift between the two. Bind order:
 /// `(input_path, output_path, mtime_hash, source_device)`.
-const ENQUEUE_JOB_UPSERT_SQL: &str =
-    "INSERT INTO jobs (input_path, output_path, status, mtime_hash, source_device, updated_at)
-     VALUES (?, ?, 'queued', ?, ?, CURRENT_TIMESTAMP)
+///
+/// The enqueued path is also kept in `output_path_template`, and changes are
+/// detected against it: a templated `output_path` is later overwritten with
+/// its resolved form, and a rescan must not put the placeholders back.
+const ENQUEUE_JOB_UPSERT_SQL: &str = "INSERT INTO jobs
+        (input_path, output_path, output_path_template, status, mtime_hash, source_device,
+         updated_at)
+     VALUES (?1, ?2, ?2, 'queued', ?3, ?4, CURRENT_TIMESTAMP)
      ON CONFLICT(input_path) DO UPDATE SET
      output_path = excluded.output_path,
+     output_path_template = excluded.output_path_template,
      status = CASE WHEN mtime_hash != excluded.mtime_hash THEN 'queued' ELSE status END,
      archived = 0,
      mtime_hash = excluded.mtime_hash,
      source_device = excluded.source_device,
      updated_at = CURRENT_TIMESTAMP
-     WHERE mtime_hash != excluded.mtime_hash OR output_path != excluded.output_path";
+     WHERE mtime_hash != excluded.mtime_hash
+        OR COALESCE(output_path_template, output_path) != excluded.output_path_template";
+
+/// Tie-breaking `ORDER BY` terms for a strategy. Jobs not yet analyzed (no
+/// estimate) go after those that have one.
+fn queue_order_sql(order: crate::config::QueueOrder) -> &'static str {
+    use crate::config::QueueOrder;
+    match order {
+        QueueOrder::Fifo => "created_at ASC, id ASC",
+        QueueOrder::LargestSourceFirst => {
+            "source_size_bytes IS NULL, source_size_bytes DESC, created_at ASC, id ASC"
+        }
+        QueueOrder::HighestSavingsFirst => {
+            "estimated_output_bytes IS NULL, source_size_bytes - estimated_output_bytes DESC,
+             created_at ASC, id ASC"
+        }
+        QueueOrder::ShortestDurationFirst => {
+            "estimated_encode_seconds IS NULL, estimated_encode_seconds ASC,
+             created_at ASC, id ASC"
+        }
+    }
+}
+
+/// `ORDER BY` clause ranking queued jobs, first to start first. Explicit
+/// priority always wins. Next come jobs on a filesystem with an active space
+/// goal, highest estimated savings first, then the configured strategy.
+/// Bind each of `ranking.goal_devices` once, in order.
+/// Extra claim condition for `ranking`, placed before its `ORDER BY`.
+fn queue_filter_sql(ranking: &QueueRanking) -> &'static str {
+    if ranking.hold_cpu_retries {
+        "AND retry_on_cpu = 0"
+    } else {
+        ""
+    }
+}
+
+fn queue_ranking_sql(ranking: &QueueRanking) -> String {
+    let strategy = queue_order_sql(ranking.order);
+    if ranking.goal_devices.is_empty() {
+        return format!("priority DESC, {strategy}");
+    }
+    let placeholders = vec!["?"; ranking.goal_devices.len()].join(", ");
+    format!(
+        "priority DESC,
+         CASE WHEN source_device IN ({placeholders})
+              THEN COALESCE(source_size_bytes - estimated_output_bytes, -1)
+         END DESC,
+         {strategy}"
+    )
+}
 
 impl Db {
     pub async fn reset_interrupted_jobs(&self) -> Result<u64> {
@@ -103,6 +158,87 @@ impl Db {
         Ok(changed)
     }
 
+    /// Enqueue one file and store `overrides` on its job in the same
+    /// transaction, so the engine never claims it without them. With
+    /// overrides, an unchanged job that is not running is requeued too;
+    /// without, any overrides stored by an earlier enqueue are cleared.
+    pub async fn enqueue_job_with_overrides(
+        &self,
+        job: &PreparedEnqueue,
+        overrides: Option<&crate::media::overrides::JobOverrides>,
+    ) -> Result<bool> {
+        let overrides_json = overrides
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| crate::error::AlchemistError::Unknown(e.to_string()))?;
+
+        let mut tx = self.pool.begin().await?;
+        let mut changed = sqlx::query(ENQUEUE_JOB_UPSERT_SQL)
+            .bind(&job.input_path)
+            .bind(&job.output_path)
+            .bind(&job.mtime_hash)
+            .bind(&job.source_device)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+            > 0;
+        if !changed && overrides_json.is_some() {
+            changed = sqlx::query(
+                "UPDATE jobs
+                 SET status = 'queued', archived = 0, progress = 0.0, next_retry_at = NULL,
+                     updated_at = CURRENT_TIMESTAMP
+                 WHERE input_path = ?
+                   AND status NOT IN ('analyzing', 'encoding', 'remuxing', 'resuming')",
+            )
+            .bind(&job.input_path)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+                > 0;
+        }
+        if changed {
+            let job_id: i64 = sqlx::query_scalar("SELECT id FROM jobs WHERE input_path = ?")
+                .bind(&job.input_path)
+                .fetch_one(&mut *tx)
+                .await?;
+            match &overrides_json {
+                Some(overrides_json) => {
+                    sqlx::query(
+                        "INSERT INTO job_overrides (job_id, overrides_json) VALUES (?, ?)
+                         ON CONFLICT(job_id) DO UPDATE SET
+                            overrides_json = excluded.overrides_json,
+                            updated_at = CURRENT_TIMESTAMP",
+                    )
+                    .bind(job_id)
+                    .bind(overrides_json)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                None => {
+                    sqlx::query("DELETE FROM job_overrides WHERE job_id = ?")
+                        .bind(job_id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+        }
+        tx.commit().await?;
+        Ok(changed)
+    }
+
+    /// Overrides stored when the job was enqueued; unreadable rows count as none.
+    pub async fn get_job_overrides(
+        &self,
+        job_id: i64,
+    ) -> Result<Option<crate::media::overrides::JobOverrides>> {
+        let json: Option<String> =
+            sqlx::query_scalar("SELECT overrides_json FROM job_overrides WHERE job_id = ?")
+                .bind(job_id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(json.and_then(|json| serde_json::from_str(&json).ok()))
+    }
+
     pub async fn add_job(&self, job: Job) -> Result<()> {
         sqlx::query(
             "INSERT INTO jobs (input_path, output_path, status, mtime_hash, priority, progress, attempt_count, source_device, created_at, updated_at)
@@ -123,8 +259,18 @@ impl Db {
         Ok(())
     }
 
-    pub async fn get_next_job(&self) -> Result<Option<Job>> {
-        let job = sqlx::query_as::<_, Job>(
+    /// The job `claim_next_job_ranked` would claim next, without claiming it.
+    pub async fn get_next_job(&self, ranking: &QueueRanking) -> Result<Option<Job>> {
+        Ok(self
+            .get_claimable_jobs(ranking, 1)
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    /// The first `limit` jobs the engine would claim, in `ranking` order.
+    pub async fn get_claimable_jobs(&self, ranking: &QueueRanking, limit: i64) -> Result<Vec<Job>> {
+        let sql = format!(
             "SELECT id, input_path, output_path, status, NULL as decision_reason,
                     COALESCE(priority, 0) as priority, COALESCE(CAST(progress AS REAL), 0.0) as progress,
                     COALESCE(attempt_count, 0) as attempt_count,
@@ -134,21 +280,19 @@ impl Db {
              FROM jobs
              WHERE status = 'queued'
                AND archived = 0
-               AND (
-                    COALESCE(attempt_count, 0) = 0
-                    OR CASE
-                        WHEN COALESCE(attempt_count, 0) = 1 THEN datetime(updated_at, '+5 minutes')
-                        WHEN COALESCE(attempt_count, 0) = 2 THEN datetime(updated_at, '+15 minutes')
-                        WHEN COALESCE(attempt_count, 0) = 3 THEN datetime(updated_at, '+60 minutes')
-                        ELSE datetime(updated_at, '+360 minutes')
-                    END <= datetime('now')
-               )
-             ORDER BY priority DESC, created_at ASC LIMIT 1",
-        )
-        .fetch_optional(&self.pool)
-        .await?;
+               AND (next_retry_at IS NULL OR next_retry_at <= datetime('now'))
+               {}
+             ORDER BY {} LIMIT ?",
+            queue_filter_sql(ranking),
+            queue_ranking_sql(ranking),
+        );
+        let mut query = sqlx::query_as::<_, Job>(&sql);
+        for device in &ranking.goal_devices {
+            query = query.bind(device);
+        }
+        let jobs = query.bind(limit).fetch_all(&self.pool).await?;
 
-        Ok(job)
+        Ok(jobs)
     }
 
     pub async fn claim_next_job(&self) -> Result<Option<Job>> {
@@ -156,6 +300,14 @@ impl Db {
             .await
     }
 
+    pub async fn claim_next_job_with_mode(
+        &self,
+        mode: crate::config::EngineMode,
+    ) -> Result<Option<Job>> {
+        self.claim_next_job_ranked(mode, &QueueRanking::default())
+            .await
+    }
+
     /// Claim the next queued job, optionally excluding candidates that share a
     /// `source_device` with an already-running job.
     ///
@@ -168,9 +320,12 @@ impl Db {
     ///
     /// `Throughput` and `Background` modes do not perform device grouping;
     /// concurrency is governed solely by the processor's semaphore.
-    pub async fn claim_next_job_with_mode(
+    ///
+    /// Claimable jobs are taken in `ranking` order.
+    pub async fn claim_next_job_ranked(
         &self,
         mode: crate::config::EngineMode,
+        ranking: &QueueRanking,
     ) -> Result<Option<Job>> {
         let active_states = "('analyzing', 'encoding', 'remuxing', 'resuming')";
         let device_exclusion = match mode {
@@ -189,23 +344,16 @@ impl Db {
 
         let sql = format!(
             "UPDATE jobs
-             SET status = 'analyzing', updated_at = CURRENT_TIMESTAMP
+             SET status = 'analyzing', next_retry_at = NULL, updated_at = CURRENT_TIMESTAMP
              WHERE id = (
                  SELECT id
                  FROM jobs
                  WHERE status = 'queued'
                    AND archived = 0
                    {}
-                   AND (
-                        COALESCE(attempt_count, 0) = 0
-                        OR CASE
-                            WHEN COALESCE(attempt_count, 0) = 1 THEN datetime(updated_at, '+5 minutes')
-                            WHEN COALESCE(attempt_count, 0) = 2 THEN datetime(updated_at, '+15 minutes')
-                            WHEN COALESCE(attempt_count, 0) = 3 THEN datetime(updated_at, '+60 minutes')
-                            ELSE datetime(updated_at, '+360 minutes')
-                        END <= datetime('now')
-                   )
-                 ORDER BY priority DESC, created_at ASC LIMIT 1
+                   AND (next_retry_at IS NULL OR next_retry_at <= datetime('now'))
+                   {}
+                 ORDER BY {} LIMIT 1
              )
              RETURNING id, input_path, output_path, status, NULL as decision_reason,
                        COALESCE(priority, 0) as priority, COALESCE(CAST(progress AS REAL), 0.0) as progress,
@@ -214,11 +362,15 @@ impl Db {
                        created_at, updated_at,
                        input_metadata_json, source_device",
             device_exclusion,
+            queue_filter_sql(ranking),
+            queue_ranking_sql(ranking),
         );
 
-        let job = sqlx::query_as::<_, Job>(&sql)
-            .fetch_optional(&self.pool)
-            .await?;
+        let mut query = sqlx::query_as::<_, Job>(&sql);
+        for device in &ranking.goal_devices {
+            query = query.bind(device);
+        }
+        let job = query.fetch_optional(&self.pool).await?;
 
         Ok(job)
     }
@@ -311,6 +463,10 @@ impl Db {
                 "SELECT id, input_path, status
                      FROM jobs
                      WHERE status NOT IN ('cancelled') AND archived = 0
+                       AND id NOT IN (
+                           SELECT variant_job_id FROM job_variants
+                           WHERE variant_job_id IS NOT NULL
+                       )
                      ORDER BY input_path ASC",
             )
             .fetch_all(&self.pool)
@@ -511,6 +667,16 @@ impl Db {
         Ok(())
     }
 
+    /// Record the final output path once a templated path is resolved.
+    pub async fn set_job_output_path(&self, id: i64, output_path: &str) -> Result<()> {
+        sqlx::query("UPDATE jobs SET output_path = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?")
+            .bind(output_path)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
     /// Set job priority
     pub async fn set_job_priority(&self, id: i64, priority: i32) -> Result<()> {
         let result = sqlx::query(
@@ -539,10 +705,68 @@ impl Db {
         Ok(())
     }
 
+    /// Requeue a failed job to be claimed after `delay`. `retry_on_cpu`
+    /// sticks until the job is restarted.
+    pub async fn schedule_job_retry(
+        &self,
+        id: i64,
+        delay: std::time::Duration,
+        failure_kind: &str,
+        retry_on_cpu: bool,
+    ) -> Result<()> {
+        let result = sqlx::query(
+            "UPDATE jobs
+             SET status = 'queued',
+                 next_retry_at = datetime('now', ?),
+                 retry_on_cpu = MAX(retry_on_cpu, ?),
+                 last_failure_kind = ?,
+                 updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?",
+        )
+        .bind(format!("+{} seconds", delay.as_secs()))
+        .bind(retry_on_cpu)
+        .bind(failure_kind)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(crate::error::AlchemistError::Database(
+                sqlx::Error::RowNotFound,
+            ));
+        }
+        Ok(())
+    }
+
+    pub async fn get_job_retry_state(&self, id: i64) -> Result<JobRetryState> {
+        let state = sqlx::query_as::<_, JobRetryState>(
+            "SELECT next_retry_at, retry_on_cpu, last_failure_kind FROM jobs WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(state.unwrap_or_default())
+    }
+
+    /// Make a queued job waiting out its retry backoff claimable now.
+    /// Returns `false` when the job is not waiting for a retry.
+    pub async fn retry_job_now(&self, id: i64) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE jobs
+             SET next_retry_at = NULL, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ? AND status = 'queued' AND archived = 0
+               AND next_retry_at IS NOT NULL",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
     pub async fn restart_failed_jobs(&self) -> Result<u64> {
         let result = sqlx::query(
             "UPDATE jobs
-             SET status = 'queued', progress = 0.0, attempt_count = 0, updated_at = CURRENT_TIMESTAMP
+             SET status = 'queued', progress = 0.0, attempt_count = 0, next_retry_at = NULL, retry_on_cpu = 0, updated_at = CURRENT_TIMESTAMP
              WHERE status IN ('failed', 'cancelled') AND archived = 0",
         )
         .execute(&self.pool)
@@ -757,7 +981,7 @@ impl Db {
             return Ok(0);
         }
         let mut qb = sqlx::QueryBuilder::<sqlx::Sqlite>::new(
-            "UPDATE jobs SET status = 'queued', progress = 0.0, attempt_count = 0, updated_at = CURRENT_TIMESTAMP WHERE archived = 0 AND status NOT IN ('analyzing', 'encoding', 'remuxing', 'resuming') AND id IN (",
+            "UPDATE jobs SET status = 'queued', progress = 0.0, attempt_count = 0, next_retry_at = NULL, retry_on_cpu = 0, updated_at = CURRENT_TIMESTAMP WHERE archived = 0 AND status NOT IN ('analyzing', 'encoding', 'remuxing', 'resuming') AND id IN (",
         );
         let mut separated = qb.separated(", ");
         for id in ids {
@@ -809,6 +1033,8 @@ impl Db {
              SET status = 'queued',
                  progress = 0.0,
                  attempt_count = 0,
+                 next_retry_at = NULL,
+                 retry_on_cpu = 0,
                  updated_at = CURRENT_TIMESTAMP
              WHERE archived = 0
                AND id IN (",
@@ -875,6 +1101,8 @@ impl Db {
              SET status = 'queued',
                  progress = 0.0,
                  attempt_count = 0,
+                 next_retry_at = NULL,
+                 retry_on_cpu = 0,
                  updated_at = CURRENT_TIMESTAMP
              WHERE id IN (SELECT id FROM jobs_to_reanalyze)",
         )
@@ -935,6 +1163,10 @@ impl Db {
                  WHERE j.archived = 0
                    AND j.status != 'cancelled'
                    AND j.input_metadata_json IS NOT NULL
+                   AND NOT EXISTS (
+                       SELECT 1 FROM job_variants v
+                       WHERE v.variant_job_id = j.id
+                   )
                  ORDER BY j.updated_at DESC
                  LIMIT ?",
             )
@@ -983,37 +1215,86 @@ impl Db {
 
     /// Returns the 1-based position of a queued job in the priority queue,
     /// or `None` if the job is not currently queued.
-    pub async fn get_queue_position(&self, job_id: i64) -> Result<Option<u32>> {
-        let row = sqlx::query(
-            "SELECT priority, created_at FROM jobs WHERE id = ? AND status = 'queued' AND archived = 0",
-        )
-        .bind(job_id)
-        .fetch_optional(&self.pool)
-        .await?;
+    /// 1-based place of a queued job in the order the engine will start
+    /// jobs, and roughly how long until it starts: the estimated encode time
+    /// of every job ahead of it, spread across `concurrent_limit` workers.
+    pub async fn get_queue_position(
+        &self,
+        job_id: i64,
+        ranking: &QueueRanking,
+        concurrent_limit: usize,
+    ) -> Result<Option<QueuePosition>> {
+        let (_, avg_seconds) = self.recent_encode_time_sample(20).await?;
+        let sql = format!(
+            "SELECT position, seconds_ahead, unestimated_ahead FROM (
+                 SELECT id,
+                        ROW_NUMBER() OVER queue_order AS position,
+                        COALESCE(SUM(estimated_encode_seconds) OVER ahead, 0.0) AS seconds_ahead,
+                        COALESCE(SUM(estimated_encode_seconds IS NULL) OVER ahead, 0) AS unestimated_ahead
+                 FROM jobs
+                 WHERE status = 'queued' AND archived = 0
+                 WINDOW queue_order AS (ORDER BY {}),
+                        ahead AS (queue_order ROWS BETWEEN UNBOUNDED PRECEDING AND 1 PRECEDING)
+             )
+             WHERE id = ?",
+            queue_ranking_sql(ranking),
+        );
+        let mut query = sqlx::query(&sql);
+        for device in &ranking.goal_devices {
+            query = query.bind(device);
+        }
+        let row = query.bind(job_id).fetch_optional(&self.pool).await?;
 
         let Some(row) = row else {
             return Ok(None);
         };
 
-        let priority: i64 = row.get("priority");
-        let created_at: String = row.get("created_at");
+        let position: i64 = row.get("position");
+        let seconds_ahead: f64 = row.get("seconds_ahead");
+        let unestimated_ahead: i64 = row.get("unestimated_ahead");
+        let wait_seconds = match avg_seconds {
+            Some(avg) => Some(seconds_ahead + unestimated_ahead as f64 * avg),
+            None if unestimated_ahead == 0 => Some(seconds_ahead),
+            None => None,
+        };
 
-        let pos: i64 = sqlx::query_scalar(
-            "SELECT COUNT(*) FROM jobs
-             WHERE status = 'queued'
-               AND archived = 0
-               AND (
-                   priority > ?
-                   OR (priority = ? AND created_at < ?)
-               )",
+        Ok(Some(QueuePosition {
+            position: position as u32,
+            est_wait_seconds: wait_seconds
+                .map(|seconds| (seconds / concurrent_limit.max(1) as f64).ceil() as i64),
+        }))
+    }
+
+    /// The configured strategy plus the filesystems of active space goals.
+    pub async fn queue_ranking(&self, order: crate::config::QueueOrder) -> Result<QueueRanking> {
+        Ok(QueueRanking {
+            order,
+            goal_devices: self.get_active_space_goal_devices().await?,
+            hold_cpu_retries: false,
+        })
+    }
+
+    /// Store the analysis-time size and duration estimates used to order
+    /// the queue.
+    pub async fn set_job_estimates(
+        &self,
+        id: i64,
+        source_size_bytes: u64,
+        estimated_output_bytes: u64,
+        estimated_encode_seconds: Option<f64>,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE jobs
+             SET source_size_bytes = ?, estimated_output_bytes = ?, estimated_encode_seconds = ?
+             WHERE id = ?",
         )
-        .bind(priority)
-        .bind(priority)
-        .bind(&created_at)
-        .fetch_one(&self.pool)
+        .bind(source_size_bytes as i64)
+        .bind(estimated_output_bytes as i64)
+        .bind(estimated_encode_seconds)
+        .bind(id)
+        .execute(&self.pool)
         .await?;
-
-        Ok(Some((pos + 1) as u32))
+        Ok(())
     }
 
     pub async fn get_resume_session(&self, job_id: i64) -> Result<Option<JobResumeSession>> {
@@ -1223,6 +1504,10 @@ impl Db {
                        SELECT 1 FROM decisions d
                        WHERE d.job_id = j.id
                    )
+                   AND NOT EXISTS (
+                       SELECT 1 FROM job_variants v
+                       WHERE v.variant_job_id = j.id
+                   )
                  ORDER BY j.priority DESC, j.created_at ASC
                  LIMIT ? OFFSET ?",
             )
@@ -1282,11 +1567,16 @@ impl Db {
     }
 
     pub async fn has_job_with_output_path(&self, path: &str) -> Result<bool> {
-        let row: Option<(i64,)> =
-            sqlx::query_as("SELECT 1 FROM jobs WHERE output_path = ? AND archived = 0 LIMIT 1")
-                .bind(path)
-                .fetch_optional(&self.pool)
-                .await?;
+        let row: Option<(i64,)> = sqlx::query_as(
+            "SELECT 1 FROM jobs WHERE output_path = ? AND archived = 0
+             UNION ALL
+             SELECT 1 FROM job_variants WHERE output_path = ?
+             LIMIT 1",
+        )
+        .bind(path)
+        .bind(path)
+        .fetch_optional(&self.pool)
+        .await?;
         Ok(row.is_some())
     }
 
@@ -1387,6 +1677,61 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn rescans_keep_a_resolved_templated_output_path()
+    -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let mut db_path = std::env::temp_dir();
+        let token: u64 = rand::random();
+        db_path.push(format!("alchemist_template_rescan_test_{}.db", token));
+
+        let db = Db::new(db_path.to_string_lossy().as_ref()).await?;
+        let input = Path::new("/media/Heat.mkv");
+        let template = Path::new("/media/Heat [{codec}].mkv");
+        assert!(
+            db.enqueue_job(input, template, SystemTime::UNIX_EPOCH)
+                .await?
+        );
+        let job = db
+            .get_job_by_input_path("/media/Heat.mkv")
+            .await?
+            .ok_or_else(|| std::io::Error::other("missing templated job"))?;
+
+        // Processing resolves the template and completes the job.
+        db.set_job_output_path(job.id, "/media/Heat [hevc].mkv")
+            .await?;
+        db.update_job_status(job.id, JobState::Completed).await?;
+
+        assert!(
+            !db.enqueue_job(input, template, SystemTime::UNIX_EPOCH)
+                .await?
+        );
+        let job = db
+            .get_job_by_id(job.id)
+            .await?
+            .ok_or_else(|| std::io::Error::other("missing templated job"))?;
+        assert_eq!(job.output_path, "/media/Heat [hevc].mkv");
+        assert_eq!(job.status, JobState::Completed);
+
+        // A different template is still picked up.
+        assert!(
+            db.enqueue_job(
+                input,
+                Path::new("/media/Heat [{codec} {height}p].mkv"),
+                SystemTime::UNIX_EPOCH
+            )
+            .await?
+        );
+        let job = db
+            .get_job_by_id(job.id)
+            .await?
+            .ok_or_else(|| std::io::Error::other("missing templated job"))?;
+        assert_eq!(job.output_path, "/media/Heat [{codec} {height}p].mkv");
+
+        drop(db);
+        let _ = std::fs::remove_file(db_path);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_claim_next_job_marks_analyzing()
     -> std::result::Result<(), Box<dyn std::error::Error>> {
@@ -1587,29 +1932,154 @@ mod tests {
             .await?
             .ok_or_else(|| std::io::Error::other("missing backoff job"))?;
 
-        sqlx::query(
-            "UPDATE jobs
-             SET attempt_count = 1,
-                 updated_at = datetime('now')
-             WHERE id = ?",
+        db.schedule_job_retry(
+            job.id,
+            std::time::Duration::from_secs(300),
+            "encoder_unavailable",
+            true,
         )
-        .bind(job.id)
-        .execute(&db.pool)
         .await?;
 
         assert!(db.claim_next_job().await?.is_none());
+        let retry = db.get_job_retry_state(job.id).await?;
+        assert!(retry.next_retry_at.is_some());
+        assert!(retry.retry_on_cpu);
+        assert_eq!(
+            retry.last_failure_kind.as_deref(),
+            Some("encoder_unavailable")
+        );
 
-        sqlx::query(
-            "UPDATE jobs
-             SET updated_at = datetime('now', '-6 minutes')
-             WHERE id = ?",
-        )
-        .bind(job.id)
-        .execute(&db.pool)
-        .await?;
+        assert!(db.retry_job_now(job.id).await?);
+        assert!(!db.retry_job_now(job.id).await?);
+
+        // A window that forbids the CPU leaves the CPU retry queued.
+        let hold = QueueRanking {
+            hold_cpu_retries: true,
+            ..QueueRanking::default()
+        };
+        assert!(db.get_next_job(&hold).await?.is_none());
+        assert!(
+            db.claim_next_job_ranked(crate::config::EngineMode::Throughput, &hold)
+                .await?
+                .is_none()
+        );
 
         let claimed = db.claim_next_job().await?;
         assert!(claimed.is_some());
+        assert_eq!(db.get_job_retry_state(job.id).await?.next_retry_at, None);
+
+        db.update_job_status(job.id, JobState::Failed).await?;
+        db.batch_restart_jobs(&[job.id]).await?;
+        assert!(!db.get_job_retry_state(job.id).await?.retry_on_cpu);
+
+        drop(db);
+        let _ = std::fs::remove_file(db_path);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn queue_order_breaks_priority_ties_by_estimate()
+    -> std::result::Result<(), Box<dyn std::error::Error>> {
+        use crate::config::{EngineMode, QueueOrder};
+
+        let mut db_path = std::env::temp_dir();
+        let token: u64 = rand::random();
+        db_path.push(format!("alchemist_queue_order_test_{}.db", token));
+
+        let db = Db::new(db_path.to_string_lossy().as_ref()).await?;
+        // (source, estimated output, estimated seconds)
+        let estimates = [
+            ("episode", 300, 100, 600.0),
+            ("remux", 60_000, 58_000, 90.0),
+            ("film", 20_000, 6_000, 7_200.0),
+        ];
+        let mut ids = Vec::new();
+        for (name, source, output, seconds) in estimates {
+            let input = format!("{name}.mkv");
+            db.enqueue_job(
+                Path::new(&input),
+                Path::new(&format!("{name}-out.mkv")),
+                SystemTime::UNIX_EPOCH,
+            )
+            .await?;
+            let job = db
+                .get_job_by_input_path(&input)
+                .await?
+                .ok_or_else(|| std::io::Error::other("missing queued job"))?;
+            db.set_job_estimates(job.id, source, output, Some(seconds))
+                .await?;
+            ids.push(job.id);
+        }
+        db.enqueue_job(
+            Path::new("unanalyzed.mkv"),
+            Path::new("unanalyzed-out.mkv"),
+            SystemTime::UNIX_EPOCH,
+        )
+        .await?;
+
+        let ranking = |order| QueueRanking {
+            order,
+            ..QueueRanking::default()
+        };
+        let film_position =
+            async |ranking: QueueRanking| db.get_queue_position(ids[2], &ranking, 2).await;
+        assert_eq!(
+            film_position(ranking(QueueOrder::Fifo))
+                .await?
+                .map(|p| p.position),
+            Some(3)
+        );
+        assert_eq!(
+            film_position(ranking(QueueOrder::HighestSavingsFirst))
+                .await?
+                .map(|p| p.position),
+            Some(1)
+        );
+        // Two jobs ahead, 690 seconds of work across two workers.
+        assert_eq!(
+            film_position(ranking(QueueOrder::ShortestDurationFirst)).await?,
+            Some(QueuePosition {
+                position: 3,
+                est_wait_seconds: Some(345),
+            })
+        );
+
+        let next = db
+            .get_next_job(&ranking(QueueOrder::LargestSourceFirst))
+            .await?;
+        assert_eq!(next.map(|job| job.id), Some(ids[1]));
+
+        // A space goal on the film's filesystem moves it to the front.
+        sqlx::query("UPDATE jobs SET source_device = 'dev:goal' WHERE id = ?")
+            .bind(ids[2])
+            .execute(&db.pool)
+            .await?;
+        let goal_ranking = QueueRanking {
+            order: QueueOrder::Fifo,
+            goal_devices: vec!["dev:goal".to_string()],
+            ..QueueRanking::default()
+        };
+        assert_eq!(
+            film_position(goal_ranking).await?.map(|p| p.position),
+            Some(1)
+        );
+
+        // Explicit priority still wins over any strategy.
+        db.set_job_priority(ids[0], 10).await?;
+        let mut claimed = Vec::new();
+        while let Some(job) = db
+            .claim_next_job_ranked(
+                EngineMode::Throughput,
+                &ranking(QueueOrder::HighestSavingsFirst),
+            )
+            .await?
+        {
+            claimed.push(job.input_path);
+        }
+        assert_eq!(
+            claimed,
+            vec!["episode.mkv", "film.mkv", "remux.mkv", "unanalyzed.mkv"]
+        );
 
         drop(db);
         let _ = std::fs::remove_file(db_path);
@@ -2044,4 +2514,78 @@ mod tests {
         let _ = std::fs::remove_file(db_path);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn variant_jobs_requeue_on_rerun_and_reserve_their_output_paths()
+    -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let mut db_path = std::env::temp_dir();
+        let token: u64 = rand::random();
+        db_path.push(format!("alchemist_variants_test_{}.db", token));
+
+        let db = Db::new(db_path.to_string_lossy().as_ref()).await?;
+        db.enqueue_job(
+            Path::new("/media/film.mkv"),
+            Path::new("/media/film-alchemist.mkv"),
+            SystemTime::UNIX_EPOCH,
+        )
+        .await?;
+        let job = db
+            .get_job_by_input_path("/media/film.mkv")
+            .await?
+            .ok_or_else(|| std::io::Error::other("missing job"))?;
+
+        let variant_job_id = db
+            .enqueue_variant_job(
+                job.id,
+                "tablet",
+                "/media/film.mkv#variant=tablet",
+                "/compat/film.mp4",
+            )
+            .await?;
+        let link = db
+            .get_variant_link(variant_job_id)
+            .await?
+            .ok_or_else(|| std::io::Error::other("missing variant link"))?;
+        assert_eq!(link.parent_job_id, job.id);
+        assert_eq!(link.name, "tablet");
+        assert!(db.get_variant_link(job.id).await?.is_none());
+        assert!(db.has_job_with_output_path("/compat/film.mp4").await?);
+        // Variant jobs are planned from their parent's source when claimed.
+        let batch = db.get_jobs_for_analysis_batch(0, 10).await?;
+        assert!(batch.iter().all(|queued| queued.id != variant_job_id));
+
+        db.update_job_status(variant_job_id, JobState::Failed)
+            .await?;
+        let variants = db.get_job_variants(job.id).await?;
+        assert_eq!(variants.len(), 1);
+        assert_eq!(variants[0].variant_job_id, Some(variant_job_id));
+        assert_eq!(variants[0].status, "failed");
+
+        let rerun = db
+            .enqueue_variant_job(
+                job.id,
+                "tablet",
+                "/media/film.mkv#variant=tablet",
+                "/compat/film.mp4",
+            )
+            .await?;
+        assert_eq!(rerun, variant_job_id);
+        assert_eq!(db.get_job_variants(job.id).await?[0].status, "queued");
+
+        // A running variant job is not restarted under itself.
+        db.update_job_status(variant_job_id, JobState::Encoding)
+            .await?;
+        db.enqueue_variant_job(
+            job.id,
+            "tablet",
+            "/media/film.mkv#variant=tablet",
+            "/compat/film.mp4",
+        )
+        .await?;
+        assert_eq!(db.get_job_variants(job.id).await?[0].status, "encoding");
+
+        drop(db);
+        let _ = std::fs::remove_file(db_path);
+        Ok(())
+    }
 }
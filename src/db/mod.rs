@@ -2,11 +2,16 @@ mod config;
 mod conversion;
 mod events;
 mod hardware_cache;
+mod job_sidecars;
+mod job_variants;
 mod jobs;
 mod probe_cache;
+mod recycle_bin;
+mod space_goals;
 mod stats;
 mod system;
 mod types;
+mod workers;
 
 pub use events::*;
 pub use types::*;
@@ -43,6 +48,8 @@ pub(crate) struct WatchDirSchemaFlags {
     has_recursive: bool,
     has_enabled: bool,
     has_profile_id: bool,
+    has_exclude_globs: bool,
+    has_watch_mode: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -138,6 +145,8 @@ impl Db {
             has_recursive: check("recursive").await,
             has_enabled: check("enabled").await,
             has_profile_id: check("profile_id").await,
+            has_exclude_globs: check("exclude_globs_json").await,
+            has_watch_mode: check("watch_mode").await,
         };
 
         let notification_check = |column: &str| {
@@ -0,0 +1,118 @@
+use crate::error::Result;
+
+use super::Db;
+use super::types::RecycleBinEntry;
+
+impl Db {
+    pub async fn record_recycled_original(
+        &self,
+        job_id: i64,
+        original_path: &str,
+        recycled_path: &str,
+        output_path: Option<&str>,
+        size_bytes: i64,
+    ) -> Result<i64> {
+        let id = sqlx::query(
+            "INSERT INTO recycle_bin_entries
+                (job_id, original_path, recycled_path, output_path, size_bytes)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(job_id)
+        .bind(original_path)
+        .bind(recycled_path)
+        .bind(output_path)
+        .bind(size_bytes)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        Ok(id)
+    }
+
+    /// The most recent original still held for a job.
+    pub async fn get_held_recycle_entry_for_job(
+        &self,
+        job_id: i64,
+    ) -> Result<Option<RecycleBinEntry>> {
+        let entry = sqlx::query_as::<_, RecycleBinEntry>(
+            "SELECT * FROM recycle_bin_entries
+             WHERE job_id = ? AND status = 'held'
+             ORDER BY id DESC
+             LIMIT 1",
+        )
+        .bind(job_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(entry)
+    }
+
+    /// Every held original, oldest first.
+    pub async fn get_held_recycle_entries(&self) -> Result<Vec<RecycleBinEntry>> {
+        let entries = sqlx::query_as::<_, RecycleBinEntry>(
+            "SELECT * FROM recycle_bin_entries
+             WHERE status = 'held'
+             ORDER BY recycled_at ASC, id ASC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(entries)
+    }
+
+    /// Close out a held entry as restored. Restoring undoes the encode, so
+    /// the job drops its savings stats and returns to `cancelled`, where it
+    /// can be retried but is not picked up again on its own.
+    pub async fn complete_recycle_restore(&self, entry: &RecycleBinEntry) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(
+            "UPDATE recycle_bin_entries
+             SET status = 'restored', resolved_at = datetime('now')
+             WHERE id = ? AND status = 'held'",
+        )
+        .bind(entry.id)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(crate::error::AlchemistError::Database(
+                sqlx::Error::RowNotFound,
+            ));
+        }
+
+        if let Some(job_id) = entry.job_id {
+            sqlx::query("DELETE FROM encode_stats WHERE job_id = ?")
+                .bind(job_id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(
+                "UPDATE jobs
+                 SET status = 'cancelled',
+                     progress = 0.0,
+                     updated_at = CURRENT_TIMESTAMP
+                 WHERE id = ?",
+            )
+            .bind(job_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Close out a held entry as `restored` or `purged`.
+    pub async fn resolve_recycle_entry(&self, id: i64, status: &str) -> Result<()> {
+        let result = sqlx::query(
+            "UPDATE recycle_bin_entries
+             SET status = ?, resolved_at = datetime('now')
+             WHERE id = ? AND status = 'held'",
+        )
+        .bind(status)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(crate::error::AlchemistError::Database(
+                sqlx::Error::RowNotFound,
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use crate::error::Result;
+
+use super::Db;
+use super::types::{NewSpaceGoal, SpaceGoal};
+
+impl Db {
+    pub async fn create_space_goal(&self, goal: NewSpaceGoal) -> Result<i64> {
+        let id = sqlx::query(
+            "INSERT INTO space_goals
+                (path, device_id, target_kind, target_gib, deadline, drain_when_met, start_free_bytes)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(goal.path)
+        .bind(goal.device_id)
+        .bind(goal.target_kind)
+        .bind(goal.target_gib)
+        .bind(goal.deadline)
+        .bind(goal.drain_when_met)
+        .bind(goal.start_free_bytes)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        Ok(id)
+    }
+
+    /// Every goal, newest first.
+    pub async fn get_space_goals(&self) -> Result<Vec<SpaceGoal>> {
+        let goals = sqlx::query_as::<_, SpaceGoal>("SELECT * FROM space_goals ORDER BY id DESC")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(goals)
+    }
+
+    pub async fn get_space_goal(&self, id: i64) -> Result<Option<SpaceGoal>> {
+        let goal = sqlx::query_as::<_, SpaceGoal>("SELECT * FROM space_goals WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(goal)
+    }
+
+    pub async fn get_active_space_goals(&self) -> Result<Vec<SpaceGoal>> {
+        let goals = sqlx::query_as::<_, SpaceGoal>(
+            "SELECT * FROM space_goals WHERE status = 'active' ORDER BY id ASC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(goals)
+    }
+
+    /// Filesystems with an active goal, whose jobs the engine starts first.
+    pub async fn get_active_space_goal_devices(&self) -> Result<Vec<String>> {
+        let devices = sqlx::query_scalar(
+            "SELECT device_id FROM space_goals WHERE status = 'active' ORDER BY id ASC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(devices)
+    }
+
+    /// Close out an active goal as `met` or `expired`.
+    pub async fn finish_space_goal(&self, id: i64, status: &str) -> Result<()> {
+        let result = sqlx::query(
+            "UPDATE space_goals
+             SET status = ?, finished_at = CURRENT_TIMESTAMP
+             WHERE id = ? AND status = 'active'",
+        )
+        .bind(status)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(crate::error::AlchemistError::Database(
+                sqlx::Error::RowNotFound,
+            ));
+        }
+        Ok(())
+    }
+
+    pub async fn delete_space_goal(&self, id: i64) -> Result<()> {
+        let result = sqlx::query("DELETE FROM space_goals WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(crate::error::AlchemistError::Database(
+                sqlx::Error::RowNotFound,
+            ));
+        }
+        Ok(())
+    }
+}
@@ -166,6 +166,33 @@ impl Db {
         Ok(stats)
     }
 
+    /// Count and mean `encode_time_seconds` of the last `sample_limit`
+    /// encodes; the ETA falls back to this for jobs without an estimate.
+    pub(super) async fn recent_encode_time_sample(
+        &self,
+        sample_limit: i64,
+    ) -> Result<(i64, Option<f64>)> {
+        let sample = sqlx::query(
+            "SELECT COUNT(*) AS sample_size, AVG(encode_time_seconds) AS avg_seconds
+             FROM (
+                SELECT encode_time_seconds
+                FROM encode_stats
+                WHERE encode_time_seconds > 0
+                ORDER BY created_at DESC
+                LIMIT ?
+             )",
+        )
+        .bind(sample_limit.max(1))
+        .fetch_one(&self.pool)
+        .await?;
+
+        let sample_size: i64 = sample.get("sample_size");
+        let avg_seconds: Option<f64> = sample.try_get("avg_seconds")?;
+        Ok((sample_size, avg_seconds.filter(|avg| *avg > 0.0)))
+    }
+
+    /// Remaining work is the sum of each job's analysis-time encode estimate,
+    /// with the recent average standing in for jobs not yet analyzed.
     pub async fn get_queue_eta_estimate(
         &self,
         concurrent_limit: usize,
@@ -173,43 +200,76 @@ impl Db {
     ) -> Result<QueueEtaEstimate> {
         let pool = &self.pool;
         timed_query("get_queue_eta_estimate", || async {
-            let remaining_jobs: i64 = sqlx::query_scalar(
-                "SELECT COUNT(*)
+            let row = sqlx::query(
+                "SELECT COUNT(*) AS remaining_jobs,
+                        COUNT(estimated_encode_seconds) AS estimated_jobs,
+                        COALESCE(SUM(estimated_encode_seconds), 0.0) AS estimated_seconds
                  FROM jobs
                  WHERE archived = 0
                    AND status IN ('queued', 'analyzing', 'encoding', 'remuxing', 'resuming')",
             )
             .fetch_one(pool)
             .await?;
+            let remaining_jobs: i64 = row.get("remaining_jobs");
+            let estimated_jobs: i64 = row.get("estimated_jobs");
+            let estimated_seconds: f64 = row.get("estimated_seconds");
+
+            let (sample_size, avg_seconds) = self.recent_encode_time_sample(sample_limit).await?;
+            let unestimated_jobs = remaining_jobs - estimated_jobs;
+            let total_seconds = match avg_seconds {
+                Some(avg) => Some(estimated_seconds + unestimated_jobs as f64 * avg),
+                None if unestimated_jobs == 0 => Some(estimated_seconds),
+                None => None,
+            };
+            let est_seconds_remaining = total_seconds
+                .filter(|total| remaining_jobs > 0 && *total > 0.0)
+                .map(|total| (total / concurrent_limit.max(1) as f64).ceil() as i64);
+
+            Ok(QueueEtaEstimate {
+                remaining_jobs,
+                est_seconds_remaining,
+                sample_size,
+            })
+        })
+        .await
+    }
 
-            let sample = sqlx::query(
-                "SELECT COUNT(*) AS sample_size, AVG(encode_time_seconds) AS avg_seconds
+    /// Averages over the last `sample_limit` encodes of `source_codec` sources
+    /// to `output_codec`.
+    pub async fn get_encode_history(
+        &self,
+        source_codec: &str,
+        output_codec: &str,
+        sample_limit: i64,
+    ) -> Result<EncodeHistory> {
+        let pool = &self.pool;
+        timed_query("get_encode_history", || async {
+            let row = sqlx::query(
+                "SELECT COUNT(*) AS sample_size,
+                        AVG(CAST(output_size_bytes AS REAL) / input_size_bytes) AS avg_output_ratio,
+                        AVG(NULLIF(encode_speed, 0)) AS avg_encode_speed
                  FROM (
-                    SELECT encode_time_seconds
-                    FROM encode_stats
-                    WHERE encode_time_seconds > 0
-                    ORDER BY created_at DESC
+                    SELECT e.output_size_bytes, e.input_size_bytes, e.encode_speed
+                    FROM encode_stats e
+                    JOIN jobs j ON e.job_id = j.id
+                    WHERE e.input_size_bytes > 0
+                      AND e.output_size_bytes > 0
+                      AND LOWER(e.output_codec) = LOWER(?)
+                      AND LOWER(json_extract(j.input_metadata_json, '$.codec_name')) = LOWER(?)
+                    ORDER BY e.created_at DESC
                     LIMIT ?
                  )",
             )
+            .bind(output_codec)
+            .bind(source_codec)
             .bind(sample_limit.max(1))
             .fetch_one(pool)
             .await?;
 
-            let sample_size: i64 = sample.get("sample_size");
-            let avg_seconds: Option<f64> = sample.try_get("avg_seconds")?;
-            let est_seconds_remaining = match (remaining_jobs, sample_size, avg_seconds) {
-                (remaining, samples, Some(avg)) if remaining > 0 && samples > 0 && avg > 0.0 => {
-                    let concurrency = concurrent_limit.max(1) as f64;
-                    Some(((remaining as f64 * avg) / concurrency).ceil() as i64)
-                }
-                _ => None,
-            };
-
-            Ok(QueueEtaEstimate {
-                remaining_jobs,
-                est_seconds_remaining,
-                sample_size,
+            Ok(EncodeHistory {
+                sample_size: row.get("sample_size"),
+                avg_output_ratio: row.try_get("avg_output_ratio")?,
+                avg_encode_speed: row.try_get("avg_encode_speed")?,
             })
         })
         .await
@@ -389,6 +449,28 @@ impl Db {
         .await
     }
 
+    /// Bytes saved, counted as in `get_savings_summary`, by encodes of
+    /// sources on a space goal's filesystem since the goal was set.
+    pub async fn get_space_goal_savings(&self, goal_id: i64) -> Result<i64> {
+        let pool = &self.pool;
+        timed_query("get_space_goal_savings", || async {
+            let saved: i64 = sqlx::query_scalar(
+                "SELECT COALESCE(SUM(e.input_size_bytes - e.output_size_bytes), 0)
+                 FROM encode_stats e
+                 JOIN jobs j ON j.id = e.job_id
+                 JOIN space_goals g ON g.device_id = j.source_device
+                 WHERE g.id = ?
+                   AND e.output_size_bytes IS NOT NULL
+                   AND e.created_at >= g.created_at",
+            )
+            .bind(goal_id)
+            .fetch_one(pool)
+            .await?;
+            Ok(saved.max(0))
+        })
+        .await
+    }
+
     pub async fn get_job_stats(&self) -> Result<JobStats> {
         let pool = &self.pool;
         timed_query("get_job_stats", || async {
@@ -832,6 +914,28 @@ mod tests {
         assert_eq!(estimate.sample_size, 2);
         assert_eq!(estimate.est_seconds_remaining, Some(45));
 
+        // A job's own estimate replaces the sample average.
+        let job = db
+            .get_job_by_input_path("/tmp/eta-2.mkv")
+            .await?
+            .ok_or_else(|| std::io::Error::other("missing queued job"))?;
+        db.set_job_estimates(job.id, 1000, 500, Some(120.0)).await?;
+        let estimate = db.get_queue_eta_estimate(2, 20).await?;
+        assert_eq!(estimate.est_seconds_remaining, Some(90));
+
+        sqlx::query("UPDATE jobs SET input_metadata_json = ? WHERE input_path = ?")
+            .bind(r#"{"codec_name":"h264"}"#)
+            .bind("/tmp/eta-0.mkv")
+            .execute(&db.pool)
+            .await?;
+        let history = db.get_encode_history("H264", "hevc", 20).await?;
+        assert_eq!(history.sample_size, 1);
+        assert_eq!(history.avg_output_ratio, Some(0.5));
+        assert_eq!(
+            db.get_encode_history("hevc", "hevc", 20).await?.sample_size,
+            0
+        );
+
         drop(db);
         let _ = std::fs::remove_file(db_path);
         Ok(())
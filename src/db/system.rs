@@ -154,6 +154,7 @@ impl Db {
                     CASE
                         WHEN access_scope = 'arr_webhook' THEN 'arr_webhook'
                         WHEN access_scope = 'jellyfin' THEN 'jellyfin'
+                        WHEN access_scope = 'worker' THEN 'worker'
                         ELSE access_level
                     END AS access_level,
                     created_at, last_used_at, revoked_at
@@ -176,6 +177,7 @@ impl Db {
             ApiTokenAccessLevel::FullAccess => ("full_access", None),
             ApiTokenAccessLevel::ArrWebhook => ("read_only", Some("arr_webhook")),
             ApiTokenAccessLevel::Jellyfin => ("read_only", Some("jellyfin")),
+            ApiTokenAccessLevel::Worker => ("read_only", Some("worker")),
         };
         let token_hash = hash_api_token(token);
         let row = sqlx::query_as::<_, ApiToken>(
@@ -185,6 +187,7 @@ impl Db {
                        CASE
                          WHEN access_scope = 'arr_webhook' THEN 'arr_webhook'
                          WHEN access_scope = 'jellyfin' THEN 'jellyfin'
+                         WHEN access_scope = 'worker' THEN 'worker'
                          ELSE access_level
                        END AS access_level,
                        created_at, last_used_at, revoked_at",
@@ -205,9 +208,10 @@ impl Db {
                     CASE
                         WHEN access_scope = 'arr_webhook' THEN 'arr_webhook'
                         WHEN access_scope = 'jellyfin' THEN 'jellyfin'
+                        WHEN access_scope = 'worker' THEN 'worker'
                         ELSE access_level
                     END AS access_level,
-                    created_at, last_used_at, revoked_at
+                    worker_id, created_at, last_used_at, revoked_at
              FROM api_tokens
              WHERE token_hash = ? AND revoked_at IS NULL",
         )
@@ -1,3 +1,4 @@
+use crate::media::output_template::{OutputTemplate, PathValues};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
@@ -209,9 +210,24 @@ pub struct WatchDir {
     pub path: String,
     pub is_recursive: bool,
     pub profile_id: Option<i64>,
+    /// Serialized `Vec<String>` of exclude globs.
+    pub exclude_globs_json: String,
+    /// `auto`, `native` or `poll`.
+    pub watch_mode: String,
     pub created_at: DateTime<Utc>,
 }
 
+impl WatchDir {
+    /// Configured exclude globs; an unreadable list counts as none.
+    pub fn exclude_globs(&self) -> Vec<String> {
+        serde_json::from_str(&self.exclude_globs_json).unwrap_or_default()
+    }
+
+    pub fn watch_mode(&self) -> crate::config::WatchMode {
+        crate::config::WatchMode::from_str_lossy(&self.watch_mode)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
 pub struct LibraryProfile {
     pub id: i64,
@@ -222,11 +238,34 @@ pub struct LibraryProfile {
     pub hdr_mode: String,
     pub audio_mode: String,
     pub crf_override: Option<i32>,
+    /// Minimum VMAF the target-quality search must reach on probe clips.
+    /// `None` keeps the fixed rate control from the quality profile.
+    pub target_vmaf: Option<f64>,
+    /// Crop black bars when the analyzer settles on a stable crop rectangle.
+    pub auto_crop: bool,
+    /// Largest output resolution tier (for example `1080p`). Larger sources are
+    /// downscaled to fit; `None` keeps the source resolution.
+    pub max_resolution: Option<String>,
+    /// Dolby Vision policy: `skip`, `strip` (keep the HDR10 base layer), or
+    /// `preserve` (carry a profile 8.1 RPU when the encoder supports it).
+    pub dolby_vision_mode: String,
+    /// Integrated loudness target (LUFS) for two-pass EBU R128 normalization of
+    /// re-encoded audio tracks. `None` leaves loudness untouched.
+    pub loudness_target_lufs: Option<f64>,
+    /// Serialized `Vec<media::variants::OutputVariant>`.
+    pub output_variants_json: String,
     pub notes: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl LibraryProfile {
+    /// Declared output variants; an unreadable list counts as none.
+    pub fn output_variants(&self) -> Vec<crate::media::variants::OutputVariant> {
+        serde_json::from_str(&self.output_variants_json).unwrap_or_default()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct NewLibraryProfile {
     pub name: String,
@@ -236,9 +275,41 @@ pub struct NewLibraryProfile {
     pub hdr_mode: String,
     pub audio_mode: String,
     pub crf_override: Option<i32>,
+    pub target_vmaf: Option<f64>,
+    pub auto_crop: bool,
+    pub max_resolution: Option<String>,
+    pub dolby_vision_mode: String,
+    pub loudness_target_lufs: Option<f64>,
+    pub output_variants_json: String,
     pub notes: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct ProfileRule {
+    pub id: i64,
+    pub name: String,
+    /// Evaluation order; the lowest position that matches wins.
+    pub position: i64,
+    pub enabled: bool,
+    /// Serialized `media::profile_rules::RuleConditions`.
+    pub conditions_json: String,
+    /// `profile` (use `profile_id`) or `skip`.
+    pub action: String,
+    pub profile_id: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewProfileRule {
+    pub name: String,
+    pub position: i64,
+    pub enabled: bool,
+    pub conditions_json: String,
+    pub action: String,
+    pub profile_id: Option<i64>,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct JobFilterQuery {
     pub limit: i64,
@@ -333,6 +404,129 @@ pub struct UpsertJobResumeSegmentInput {
     pub attempt_count: i32,
 }
 
+/// Where a job stands in its retry policy.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, sqlx::FromRow)]
+pub struct JobRetryState {
+    /// When a queued job becomes claimable again; `None` when it is not
+    /// waiting out a backoff.
+    pub next_retry_at: Option<DateTime<Utc>>,
+    /// The next attempt runs on the CPU encoder.
+    pub retry_on_cpu: bool,
+    /// Failure code of the attempt that scheduled the retry.
+    pub last_failure_kind: Option<String>,
+}
+
+/// An original moved into the recycle bin instead of being deleted.
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct RecycleBinEntry {
+    pub id: i64,
+    pub job_id: Option<i64>,
+    pub original_path: String,
+    pub recycled_path: String,
+    /// Encoded output that replaced the original; removed on restore.
+    pub output_path: Option<String>,
+    pub size_bytes: i64,
+    /// `held`, `restored`, or `purged`.
+    pub status: String,
+    pub recycled_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// A companion file carried over to a job's output name.
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct JobSidecar {
+    pub id: i64,
+    pub job_id: i64,
+    pub source_path: String,
+    pub output_path: String,
+    /// `moved` or `restored`.
+    pub action: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One output variant of a job, read through the variant job that produces it.
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct JobVariant {
+    pub id: i64,
+    pub job_id: i64,
+    /// The job that encodes this variant; `None` once that job was deleted.
+    pub variant_job_id: Option<i64>,
+    pub name: String,
+    pub output_path: String,
+    /// The variant job's state.
+    pub status: String,
+    pub decision_reason: Option<String>,
+    pub failure_summary: Option<String>,
+    pub input_size_bytes: Option<i64>,
+    pub output_size_bytes: Option<i64>,
+    pub encode_time_seconds: Option<f64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Ties a variant job to the job whose source it is encoded from.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct VariantLink {
+    pub parent_job_id: i64,
+    pub name: String,
+}
+
+/// A remote machine that leases jobs from this instance.
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct Worker {
+    pub id: i64,
+    pub name: String,
+    /// The worker's `HardwareInfo` as JSON.
+    pub hardware_json: String,
+    /// `online` or `offline`.
+    pub status: String,
+    pub registered_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+}
+
+/// A job handed to a worker until `expires_at`, renewed by heartbeats.
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct JobLease {
+    pub job_id: i64,
+    pub worker_id: i64,
+    pub leased_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// The plan sent to the worker, kept for finalization.
+    pub plan_json: Option<String>,
+    pub metadata_json: Option<String>,
+}
+
+/// Encode until a filesystem has `target_gib` free or reclaimed, then stop.
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+pub struct SpaceGoal {
+    pub id: i64,
+    pub path: String,
+    /// `device_id_for(path)`, compared against `jobs.source_device`.
+    pub device_id: String,
+    /// `free` or `reclaimed`.
+    pub target_kind: String,
+    pub target_gib: f64,
+    pub deadline: Option<DateTime<Utc>>,
+    pub drain_when_met: bool,
+    /// Free space on the filesystem when the goal was set.
+    pub start_free_bytes: Option<i64>,
+    /// `active`, `met`, or `expired`.
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewSpaceGoal {
+    pub path: String,
+    pub device_id: String,
+    pub target_kind: String,
+    pub target_gib: f64,
+    pub deadline: Option<DateTime<Utc>>,
+    pub drain_when_met: bool,
+    pub start_free_bytes: Option<i64>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
 pub struct ScheduleWindow {
     pub id: i64,
@@ -340,6 +534,10 @@ pub struct ScheduleWindow {
     pub end_time: String,
     pub days_of_week: String, // as JSON string
     pub enabled: bool,
+    pub engine_mode: Option<String>,
+    pub concurrent_jobs: Option<i64>,
+    pub allowed_backends: String, // as JSON string
+    pub priority: i32,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
@@ -350,6 +548,9 @@ pub struct FileSettings {
     pub output_suffix: String,
     pub replace_strategy: String,
     pub output_root: Option<String>,
+    /// Output path template; see [`crate::media::output_template`]. Replaces
+    /// suffix-plus-extension naming when set.
+    pub output_template: Option<String>,
 }
 
 impl FileSettings {
@@ -357,23 +558,48 @@ impl FileSettings {
         self.output_path_for_source(input_path, None)
     }
 
+    /// The parsed output template, if one is configured and valid.
+    pub fn output_template(&self) -> Option<OutputTemplate> {
+        self.output_template
+            .as_deref()
+            .filter(|template| !template.trim().is_empty())
+            .and_then(|template| OutputTemplate::parse(template).ok())
+    }
+
+    /// Output path for `input_path`. With a template that uses media tokens
+    /// the result still holds `{token}` placeholders; the pipeline resolves
+    /// them once the job is planned.
     pub fn output_path_for_source(&self, input_path: &Path, source_root: Option<&Path>) -> PathBuf {
-        let mut output_path = self.output_base_path(input_path, source_root);
         let stem = input_path.file_stem().unwrap_or_default().to_string_lossy();
         let extension = self.output_extension.trim_start_matches('.');
         let suffix = self.output_suffix.as_str();
 
-        let mut filename = String::new();
-        filename.push_str(&stem);
-        filename.push_str(suffix);
-        if !extension.is_empty() {
-            filename.push('.');
-            filename.push_str(extension);
-        }
-        if filename.is_empty() {
-            filename.push_str("output");
-        }
-        output_path.set_file_name(filename);
+        let mut output_path = if let Some(template) = self.output_template() {
+            template.render(
+                &PathValues::for_source(
+                    input_path,
+                    source_root,
+                    extension,
+                    suffix,
+                    self.output_root.as_deref(),
+                ),
+                None,
+            )
+        } else {
+            let mut output_path = self.output_base_path(input_path, source_root);
+            let mut filename = String::new();
+            filename.push_str(&stem);
+            filename.push_str(suffix);
+            if !extension.is_empty() {
+                filename.push('.');
+                filename.push_str(extension);
+            }
+            if filename.is_empty() {
+                filename.push_str("output");
+            }
+            output_path.set_file_name(filename);
+            output_path
+        };
 
         if output_path == input_path {
             let safe_suffix = if suffix.is_empty() {
@@ -521,6 +747,33 @@ pub struct QueueEtaEstimate {
     pub sample_size: i64,
 }
 
+/// How the engine picks the next queued job; see `Db::queue_ranking`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueueRanking {
+    pub order: crate::config::QueueOrder,
+    /// Filesystems with an active space goal.
+    pub goal_devices: Vec<String>,
+    /// Leave jobs whose retry must run on the CPU encoder queued, as while a
+    /// schedule window forbids it.
+    pub hold_cpu_retries: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub struct QueuePosition {
+    pub position: u32,
+    pub est_wait_seconds: Option<i64>,
+}
+
+/// Averages over recent encodes of similar sources.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EncodeHistory {
+    pub sample_size: i64,
+    /// Mean `output / input` size.
+    pub avg_output_ratio: Option<f64>,
+    /// Mean media seconds encoded per wall-clock second.
+    pub avg_encode_speed: Option<f64>,
+}
+
 /// Detailed per-job encoding statistics
 #[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
 pub struct DetailedEncodeStats {
@@ -668,6 +921,7 @@ pub enum ApiTokenAccessLevel {
     FullAccess,
     ArrWebhook,
     Jellyfin,
+    Worker,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
@@ -686,6 +940,8 @@ pub struct ApiTokenRecord {
     pub name: String,
     pub token_hash: String,
     pub access_level: ApiTokenAccessLevel,
+    /// The worker a worker token registered, once it has.
+    pub worker_id: Option<i64>,
     pub created_at: DateTime<Utc>,
     pub last_used_at: Option<DateTime<Utc>>,
     pub revoked_at: Option<DateTime<Utc>>,
@@ -705,6 +961,7 @@ mod tests {
             output_suffix: "-alchemist".to_string(),
             replace_strategy: "keep".to_string(),
             output_root: None,
+            output_template: None,
         };
         let input = Path::new("video.mp4");
         let output = settings.output_path_for(input);
@@ -720,6 +977,7 @@ mod tests {
             output_suffix: "".to_string(),
             replace_strategy: "keep".to_string(),
             output_root: None,
+            output_template: None,
         };
         let input = Path::new("video.mkv");
         let output = settings.output_path_for(input);
@@ -735,6 +993,7 @@ mod tests {
             output_suffix: "-alchemist".to_string(),
             replace_strategy: "keep".to_string(),
             output_root: Some("/encoded".to_string()),
+            output_template: None,
         };
         let input = Path::new("/library/movies/action/video.mp4");
         let output = settings.output_path_for_source(input, Some(Path::new("/library")));
@@ -753,12 +1012,40 @@ mod tests {
             output_suffix: "-alchemist".to_string(),
             replace_strategy: "keep".to_string(),
             output_root: Some("/encoded".to_string()),
+            output_template: None,
         };
         let input = Path::new("/library/movies/video.mp4");
         let output = settings.output_path_for_source(input, Some(Path::new("/other")));
         assert_eq!(output, PathBuf::from("/library/movies/video-alchemist.mkv"));
     }
 
+    #[test]
+    fn test_output_path_follows_template() {
+        let settings = FileSettings {
+            id: 1,
+            delete_source: false,
+            output_extension: "mkv".to_string(),
+            output_suffix: "".to_string(),
+            replace_strategy: "keep".to_string(),
+            output_root: Some("/encoded".to_string()),
+            output_template: Some("{root}/{library}/{relative_dir}/{stem}.{ext}".to_string()),
+        };
+        let input = Path::new("/library/movies/action/video.mp4");
+        let output = settings.output_path_for_source(input, Some(Path::new("/library/movies")));
+        assert_eq!(output, PathBuf::from("/encoded/movies/action/video.mkv"));
+
+        let in_place = FileSettings {
+            output_root: None,
+            output_template: Some("{parent}/{stem}.{ext}".to_string()),
+            ..settings
+        };
+        let input = Path::new("/library/movies/video.mkv");
+        assert_eq!(
+            in_place.output_path_for(input),
+            PathBuf::from("/library/movies/video-alchemist.mkv")
+        );
+    }
+
     #[test]
     fn test_replace_strategy() {
         let mut settings = FileSettings {
@@ -768,6 +1055,7 @@ mod tests {
             output_suffix: "-alchemist".to_string(),
             replace_strategy: "keep".to_string(),
             output_root: None,
+            output_template: None,
         };
         assert!(!settings.should_replace_existing_output());
         settings.replace_strategy = "replace".to_string();
@@ -0,0 +1,258 @@
+use crate::error::Result;
+
+use super::Db;
+use super::types::{Job, JobLease, Worker};
+
+fn lease_modifier(lease_secs: u64) -> String {
+    format!("+{lease_secs} seconds")
+}
+
+impl Db {
+    /// Register `name`, or refresh its hardware and mark it online if it has
+    /// registered before.
+    /// Register or refresh the worker `name`. With `token_id`, the worker
+    /// token is bound to the worker it registers; returns `None` when the
+    /// token is bound to another worker or the worker to another active
+    /// token.
+    pub async fn register_worker(
+        &self,
+        name: &str,
+        hardware_json: &str,
+        token_id: Option<i64>,
+    ) -> Result<Option<Worker>> {
+        let mut tx = self.pool.begin().await?;
+        if let Some(token_id) = token_id {
+            let existing: Option<i64> = sqlx::query_scalar("SELECT id FROM workers WHERE name = ?")
+                .bind(name)
+                .fetch_optional(&mut *tx)
+                .await?;
+            let bound: Option<i64> =
+                sqlx::query_scalar("SELECT worker_id FROM api_tokens WHERE id = ?")
+                    .bind(token_id)
+                    .fetch_optional(&mut *tx)
+                    .await?
+                    .flatten();
+            if bound.is_some() && bound != existing {
+                return Ok(None);
+            }
+            if let Some(existing) = existing {
+                let claimed: Option<i64> = sqlx::query_scalar(
+                    "SELECT id FROM api_tokens
+                     WHERE worker_id = ? AND id != ? AND revoked_at IS NULL
+                     LIMIT 1",
+                )
+                .bind(existing)
+                .bind(token_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+                if claimed.is_some() {
+                    return Ok(None);
+                }
+            }
+        }
+        let worker = sqlx::query_as::<_, Worker>(
+            "INSERT INTO workers (name, hardware_json)
+             VALUES (?, ?)
+             ON CONFLICT(name) DO UPDATE SET
+                hardware_json = excluded.hardware_json,
+                status = 'online',
+                last_seen_at = CURRENT_TIMESTAMP
+             RETURNING *",
+        )
+        .bind(name)
+        .bind(hardware_json)
+        .fetch_one(&mut *tx)
+        .await?;
+        if let Some(token_id) = token_id {
+            sqlx::query("UPDATE api_tokens SET worker_id = ? WHERE id = ?")
+                .bind(worker.id)
+                .bind(token_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(Some(worker))
+    }
+
+    pub async fn get_worker(&self, id: i64) -> Result<Option<Worker>> {
+        let worker = sqlx::query_as::<_, Worker>("SELECT * FROM workers WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(worker)
+    }
+
+    pub async fn get_workers(&self) -> Result<Vec<Worker>> {
+        let workers = sqlx::query_as::<_, Worker>("SELECT * FROM workers ORDER BY name ASC")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(workers)
+    }
+
+    pub async fn touch_worker(&self, id: i64) -> Result<()> {
+        sqlx::query(
+            "UPDATE workers SET status = 'online', last_seen_at = CURRENT_TIMESTAMP WHERE id = ?",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Mark workers unseen for `timeout_secs` offline, returning their names.
+    pub async fn mark_stale_workers_offline(&self, timeout_secs: u64) -> Result<Vec<String>> {
+        let names = sqlx::query_scalar(
+            "UPDATE workers SET status = 'offline'
+             WHERE status = 'online' AND last_seen_at <= datetime('now', ?)
+             RETURNING name",
+        )
+        .bind(format!("-{timeout_secs} seconds"))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(names)
+    }
+
+    /// Claim the queued job `job_id` for `worker_id` for `lease_secs`.
+    /// Returns `None` when the job was claimed by someone else first.
+    pub async fn lease_job(
+        &self,
+        job_id: i64,
+        worker_id: i64,
+        lease_secs: u64,
+    ) -> Result<Option<Job>> {
+        let mut tx = self.pool.begin().await?;
+        let job = sqlx::query_as::<_, Job>(
+            "UPDATE jobs
+             SET status = 'analyzing', next_retry_at = NULL, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ? AND status = 'queued' AND archived = 0
+             RETURNING id, input_path, output_path, status, NULL as decision_reason,
+                       COALESCE(priority, 0) as priority, COALESCE(CAST(progress AS REAL), 0.0) as progress,
+                       COALESCE(attempt_count, 0) as attempt_count,
+                       NULL as vmaf_score,
+                       created_at, updated_at,
+                       input_metadata_json, source_device",
+        )
+        .bind(job_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if job.is_some() {
+            sqlx::query(
+                "INSERT INTO job_leases (job_id, worker_id, expires_at)
+                 VALUES (?, ?, datetime('now', ?))
+                 ON CONFLICT(job_id) DO UPDATE SET
+                    worker_id = excluded.worker_id,
+                    leased_at = CURRENT_TIMESTAMP,
+                    expires_at = excluded.expires_at,
+                    plan_json = NULL,
+                    metadata_json = NULL",
+            )
+            .bind(job_id)
+            .bind(worker_id)
+            .bind(lease_modifier(lease_secs))
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(job)
+    }
+
+    pub async fn get_job_lease(&self, job_id: i64) -> Result<Option<JobLease>> {
+        let lease = sqlx::query_as::<_, JobLease>("SELECT * FROM job_leases WHERE job_id = ?")
+            .bind(job_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(lease)
+    }
+
+    pub async fn get_worker_leases(&self, worker_id: i64) -> Result<Vec<JobLease>> {
+        let leases = sqlx::query_as::<_, JobLease>(
+            "SELECT * FROM job_leases WHERE worker_id = ? ORDER BY leased_at ASC",
+        )
+        .bind(worker_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(leases)
+    }
+
+    pub async fn count_job_leases(&self) -> Result<i64> {
+        let count = sqlx::query_scalar("SELECT COUNT(*) FROM job_leases")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(count)
+    }
+
+    /// Extend a lease by `lease_secs` from now. Returns `false` when the
+    /// worker no longer holds it.
+    pub async fn renew_job_lease(
+        &self,
+        job_id: i64,
+        worker_id: i64,
+        lease_secs: u64,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE job_leases SET expires_at = datetime('now', ?)
+             WHERE job_id = ? AND worker_id = ?",
+        )
+        .bind(lease_modifier(lease_secs))
+        .bind(job_id)
+        .bind(worker_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn set_job_lease_plan(
+        &self,
+        job_id: i64,
+        plan_json: &str,
+        metadata_json: &str,
+    ) -> Result<()> {
+        sqlx::query("UPDATE job_leases SET plan_json = ?, metadata_json = ? WHERE job_id = ?")
+            .bind(plan_json)
+            .bind(metadata_json)
+            .bind(job_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn release_job_lease(&self, job_id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM job_leases WHERE job_id = ?")
+            .bind(job_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Drop every lease; used at startup, after interrupted jobs are requeued.
+    pub async fn release_all_job_leases(&self) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM job_leases")
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Remove leases past their expiry and put their jobs back in the queue.
+    /// Returns the expired leases.
+    pub async fn expire_job_leases(&self) -> Result<Vec<JobLease>> {
+        let mut tx = self.pool.begin().await?;
+        let leases = sqlx::query_as::<_, JobLease>(
+            "DELETE FROM job_leases WHERE expires_at <= datetime('now') RETURNING *",
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        for lease in &leases {
+            sqlx::query(
+                "UPDATE jobs
+                 SET status = 'queued', progress = 0.0, updated_at = CURRENT_TIMESTAMP
+                 WHERE id = ? AND status IN ('analyzing', 'encoding', 'remuxing', 'resuming')",
+            )
+            .bind(lease.job_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(leases)
+    }
+}
@@ -155,6 +155,38 @@ pub fn explanation_from_json(payload: &str) -> Option<Explanation> {
 }
 
 pub fn decision_from_legacy(action: &str, legacy_reason: &str) -> Explanation {
+    let explanation = decision_from_reason_code(action, legacy_reason);
+    let (_, measured) = split_legacy_reason(legacy_reason);
+    with_dolby_vision_outcome(explanation, &measured)
+}
+
+/// The planner appends the Dolby Vision outcome to the reason that triggered
+/// the transcode as `dolby_vision*` parameters.
+fn with_dolby_vision_outcome(
+    mut explanation: Explanation,
+    measured: &BTreeMap<String, Value>,
+) -> Explanation {
+    let note = match measured_string(measured, "dolby_vision").as_deref() {
+        Some("preserved") => " The Dolby Vision RPU is carried into the output.",
+        Some("stripped_to_hdr10") => {
+            " The Dolby Vision RPU is dropped and the base layer keeps its HDR10 mastering metadata."
+        }
+        _ => return explanation,
+    };
+    explanation.detail.push_str(note);
+    for key in [
+        "dolby_vision",
+        "dolby_vision_profile",
+        "dolby_vision_encoder",
+    ] {
+        if let Some(value) = measured.get(key) {
+            explanation = explanation.with_measured(key, value.clone());
+        }
+    }
+    explanation
+}
+
+fn decision_from_reason_code(action: &str, legacy_reason: &str) -> Explanation {
     let (legacy_code, measured) = split_legacy_reason(legacy_reason);
 
     if legacy_reason == "Output path matches input path" {
@@ -288,6 +320,33 @@ pub fn decision_from_legacy(action: &str, legacy_reason: &str) -> Explanation {
                 .cloned()
                 .unwrap_or_else(|| json!("unknown")),
         ),
+        "already_processed_by_alchemist" => {
+            let version = measured_string(&measured, "version");
+            let settings_changed = measured
+                .get("settings_changed")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+            let made_by = version.as_deref().map_or("Alchemist".to_string(), |version| {
+                format!("Alchemist {version}")
+            });
+            let detail = if settings_changed {
+                format!("This file carries the marker {made_by} writes into its outputs. It was made with different settings, but reprocessing on settings changes is off.")
+            } else {
+                format!("This file carries the marker {made_by} writes into its outputs and was made with the current settings, so encoding it again would only lose quality.")
+            };
+            Explanation::new(
+                ExplanationCategory::Decision,
+                "already_processed_by_alchemist",
+                "Already processed by Alchemist",
+                detail,
+                settings_changed.then(|| {
+                    "Enable reprocess_on_settings_change in Settings -> Transcoding to re-encode files made with older settings.".to_string()
+                }),
+                legacy_reason,
+            )
+            .with_measured("version", version.map_or(Value::Null, |value| json!(value)))
+            .with_measured("settings_changed", json!(settings_changed))
+        }
         "already_target_codec" => {
             let codec = measured_string(&measured, "codec").unwrap_or_else(|| "target codec".to_string());
             let bit_depth = measured_i64(&measured, "bit_depth");
@@ -548,6 +607,125 @@ pub fn decision_from_legacy(action: &str, legacy_reason: &str) -> Explanation {
                 .unwrap_or(Value::Null),
         )
         .with_measured("bpp", measured.get("bpp").cloned().unwrap_or(Value::Null)),
+        "dolby_vision_profile5_no_fallback" => Explanation::new(
+            ExplanationCategory::Decision,
+            "dolby_vision_profile5_no_fallback",
+            "Dolby Vision profile 5",
+            "This file is Dolby Vision profile 5, which has no HDR10 base layer. Without the RPU the colors are wrong, and tonemapping would guess from the wrong signal, so Alchemist leaves it alone.".to_string(),
+            None,
+            legacy_reason,
+        )
+        .with_measured("profile", json!(5)),
+        "profile_rule_skip" => {
+            let rule = measured_string(&measured, "rule");
+            let rule_id = measured_i64(&measured, "rule_id");
+            let detail = match &rule {
+                Some(rule) => format!("The profile rule \"{rule}\" matched this file and is set to skip it."),
+                None => "A profile rule matched this file and is set to skip it.".to_string(),
+            };
+            Explanation::new(
+                ExplanationCategory::Decision,
+                "profile_rule_skip",
+                "Skipped by profile rule",
+                detail,
+                Some("Edit or disable the matching profile rule to process these files.".to_string()),
+                legacy_reason,
+            )
+            .with_measured("rule_id", rule_id.map_or(Value::Null, |value| json!(value)))
+            .with_measured("rule", rule.map_or(Value::Null, |value| json!(value)))
+        }
+        "dolby_vision_skipped" => {
+            let profile = measured_string(&measured, "profile");
+            Explanation::new(
+                ExplanationCategory::Decision,
+                "dolby_vision_skipped",
+                "Dolby Vision skipped",
+                "This file carries Dolby Vision metadata and the library profile is set to skip Dolby Vision sources.".to_string(),
+                Some("Set the profile's Dolby Vision mode to strip or preserve to process these files.".to_string()),
+                legacy_reason,
+            )
+            .with_measured("profile", profile.map_or(Value::Null, |value| json!(value)))
+        }
+        "dolby_vision_preserve_unavailable" => {
+            let profile = measured_string(&measured, "profile");
+            let encoder = measured_string(&measured, "encoder");
+            let detail = match (&profile, &encoder) {
+                (Some(profile), Some(encoder)) => format!(
+                    "The library profile asks to preserve Dolby Vision, but profile {profile} on {encoder} cannot keep the RPU. Only profile 8.1 encoded with libx265 can."
+                ),
+                _ => "The library profile asks to preserve Dolby Vision, but this source and encoder cannot keep the RPU. Only profile 8.1 encoded with libx265 can.".to_string(),
+            };
+            Explanation::new(
+                ExplanationCategory::Decision,
+                "dolby_vision_preserve_unavailable",
+                "Dolby Vision cannot be preserved",
+                detail,
+                Some("Use strip mode to keep the HDR10 base layer, or make libx265 available for HEVC encodes.".to_string()),
+                legacy_reason,
+            )
+            .with_measured("profile", profile.map_or(Value::Null, |value| json!(value)))
+            .with_measured("encoder", encoder.map_or(Value::Null, |value| json!(value)))
+        }
+        "dolby_vision_preserved" => {
+            let profile = measured_string(&measured, "profile");
+            let encoder = measured_string(&measured, "encoder");
+            Explanation::new(
+                ExplanationCategory::Decision,
+                "dolby_vision_preserved",
+                "Preserve Dolby Vision",
+                format!(
+                    "Alchemist will re-encode this Dolby Vision profile {} file and carry its RPU into the output.",
+                    profile.as_deref().unwrap_or("8.1")
+                ),
+                None,
+                legacy_reason,
+            )
+            .with_measured("profile", profile.map_or(Value::Null, |value| json!(value)))
+            .with_measured("encoder", encoder.map_or(Value::Null, |value| json!(value)))
+        }
+        "dolby_vision_stripped_to_hdr10" => {
+            let profile = measured_string(&measured, "profile");
+            Explanation::new(
+                ExplanationCategory::Decision,
+                "dolby_vision_stripped_to_hdr10",
+                "Strip Dolby Vision",
+                "Alchemist will drop the Dolby Vision RPU and encode the base layer with its HDR10 mastering metadata intact.".to_string(),
+                None,
+                legacy_reason,
+            )
+            .with_measured("profile", profile.map_or(Value::Null, |value| json!(value)))
+        }
+        "downscale_to_max_resolution" => {
+            let dimensions = |width: &str, height: &str| {
+                measured_i64(&measured, width)
+                    .zip(measured_i64(&measured, height))
+                    .map(|(width, height)| format!("{width}x{height}"))
+            };
+            let detail = match (
+                dimensions("source_width", "source_height"),
+                dimensions("target_width", "target_height"),
+            ) {
+                (Some(source), Some(target)) => format!(
+                    "The library profile caps output resolution, so Alchemist will downscale this {source} file to {target} while transcoding."
+                ),
+                _ => "The library profile caps output resolution, so Alchemist will downscale this file while transcoding.".to_string(),
+            };
+            let mut explanation = Explanation::new(
+                ExplanationCategory::Decision,
+                "downscale_to_max_resolution",
+                "Downscale to profile resolution",
+                detail,
+                None,
+                legacy_reason,
+            );
+            for key in ["source_width", "source_height", "target_width", "target_height"] {
+                explanation = explanation.with_measured(
+                    key,
+                    measured_i64(&measured, key).map_or(Value::Null, |value| json!(value)),
+                );
+            }
+            explanation
+        }
         "remux_mp4_to_mkv_stream_copy" => Explanation::new(
             ExplanationCategory::Decision,
             "remux_mp4_to_mkv_stream_copy",
@@ -960,6 +1138,67 @@ mod tests {
         assert_eq!(explanation.summary, "Output disk is full");
     }
 
+    #[test]
+    fn downscale_decision_reports_both_sizes() {
+        let explanation = decision_from_legacy(
+            "transcode",
+            "downscale_to_max_resolution|source_width=3840,source_height=2160,target_width=1920,target_height=1080",
+        );
+        assert_eq!(explanation.code, "downscale_to_max_resolution");
+        assert!(explanation.detail.contains("3840x2160 file to 1920x1080"));
+        assert_eq!(explanation.measured.get("target_width"), Some(&json!(1920)));
+    }
+
+    #[test]
+    fn processed_marker_skip_points_at_reprocessing_only_when_settings_changed() {
+        let explanation = decision_from_legacy(
+            "skip",
+            "already_processed_by_alchemist|version=0.3.5-rc.4,settings_changed=true",
+        );
+        assert_eq!(explanation.code, "already_processed_by_alchemist");
+        assert!(explanation.detail.contains("Alchemist 0.3.5-rc.4"));
+        assert!(explanation.operator_guidance.is_some());
+
+        let unchanged = decision_from_legacy(
+            "skip",
+            "already_processed_by_alchemist|version=0.3.5,settings_changed=false",
+        );
+        assert_eq!(unchanged.operator_guidance, None);
+        assert_eq!(
+            unchanged.measured.get("settings_changed"),
+            Some(&json!(false))
+        );
+    }
+
+    #[test]
+    fn dolby_vision_outcomes_have_distinct_codes() {
+        let cases = [
+            ("skip", "dolby_vision_profile5_no_fallback|profile=5"),
+            ("skip", "dolby_vision_skipped|profile=8.1"),
+            (
+                "skip",
+                "dolby_vision_preserve_unavailable|profile=7.6,encoder=libx265",
+            ),
+            (
+                "transcode",
+                "dolby_vision_preserved|profile=8.1,encoder=libx265",
+            ),
+            ("transcode", "dolby_vision_stripped_to_hdr10|profile=8.1"),
+        ];
+        let codes = cases
+            .iter()
+            .map(|(action, reason)| decision_from_legacy(action, reason).code)
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(codes.len(), cases.len());
+
+        let explanation = decision_from_legacy(
+            "skip",
+            "dolby_vision_preserve_unavailable|profile=7.6,encoder=libx265",
+        );
+        assert!(explanation.detail.contains("profile 7.6 on libx265"));
+        assert_eq!(explanation.measured.get("profile"), Some(&json!("7.6")));
+    }
+
     #[test]
     fn round_trips_json_payload() {
         let explanation = decision_from_legacy(
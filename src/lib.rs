@@ -19,6 +19,7 @@ pub mod telemetry;
 pub mod update;
 pub mod version;
 pub mod wizard;
+pub mod worker;
 
 pub use config::QualityProfile;
 pub use media::ffmpeg::{EncodeStats, EncoderCapabilities, HardwareAccelerators};
@@ -3,7 +3,6 @@
 use alchemist::db::{EventChannels, SystemEvent};
 use alchemist::error::Result;
 use alchemist::mcp::McpServer;
-use alchemist::media::pipeline::Planner as _;
 use alchemist::system::hardware;
 use alchemist::version;
 use alchemist::wizard::ConfigWizard;
@@ -98,6 +97,21 @@ enum Commands {
     },
     /// Run the embedded self-test pipeline to verify transcoding components
     Selftest,
+    /// Encode jobs leased from another Alchemist instance
+    Worker {
+        /// Base URL of the main instance, e.g. http://nas:3000
+        #[arg(long)]
+        server: String,
+        /// API token with the worker access level
+        #[arg(long)]
+        token: String,
+        /// Name shown on the main instance (defaults to the host name)
+        #[arg(long)]
+        name: Option<String>,
+        /// Map a path prefix on the main instance to this machine; repeatable
+        #[arg(long = "path-map", value_name = "SERVER=LOCAL")]
+        path_map: Vec<String>,
+    },
 }
 
 #[derive(Debug, Serialize)]
@@ -393,6 +407,16 @@ async fn run() -> Result<()> {
         return Ok(());
     }
 
+    if let Some(Commands::Worker {
+        server,
+        token,
+        name,
+        path_map,
+    }) = args.command.clone()
+    {
+        return run_worker(config_path.as_path(), server, token, name, path_map).await;
+    }
+
     let is_server_mode = args.command.is_none();
 
     let boot_start = Instant::now();
@@ -538,6 +562,10 @@ async fn run() -> Result<()> {
         Ok(_) => {}
         Err(err) => error!("Failed to reset interrupted jobs: {}", err),
     }
+    // Remote workers re-register and lease afresh after a restart.
+    if let Err(err) = db.release_all_job_leases().await {
+        error!("Failed to release remote job leases: {}", err);
+    }
 
     // Also clean up any temp files left by cancelled jobs
     // (process was killed before runtime cleanup could run)
@@ -802,6 +830,25 @@ async fn run() -> Result<()> {
         }
     });
 
+    let recycle_db = db.clone();
+    let recycle_config = config.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60 * 60));
+        loop {
+            interval.tick().await;
+
+            let recycle_bin = recycle_config.read().await.files.recycle_bin.clone();
+            match alchemist::media::recycle::sweep(&recycle_db, &recycle_bin).await {
+                Ok(summary) if summary.purged > 0 => info!(
+                    "Purged {} recycled originals ({} bytes)",
+                    summary.purged, summary.freed_bytes
+                ),
+                Ok(_) => {}
+                Err(err) => error!("Failed to sweep recycle bin: {}", err),
+            }
+        }
+    });
+
     let agent = Arc::new(
         Agent::new(
             db.clone(),
@@ -839,6 +886,19 @@ async fn run() -> Result<()> {
     tokio::spawn(async move {
         proc.run_loop().await;
     });
+    tokio::spawn(alchemist::system::governor::run(
+        agent.clone(),
+        config.clone(),
+    ));
+    tokio::spawn(alchemist::system::space_goals::run(
+        agent.clone(),
+        db.clone(),
+        event_channels.clone(),
+    ));
+    tokio::spawn(alchemist::server::workers::run_lease_monitor(
+        db.clone(),
+        event_channels.clone(),
+    ));
 
     if is_server_mode {
         info!("Starting web server...");
@@ -902,7 +962,10 @@ async fn run() -> Result<()> {
                     {
                         Ok(all_dirs) => {
                             info!("Updating file watcher with {} directories", all_dirs.len());
-                            if let Err(e) = file_watcher.watch(&all_dirs) {
+                            let poll = alchemist::system::poll_watcher::PollSettings::from_config(
+                                &config_snapshot.scanner,
+                            );
+                            if let Err(e) = file_watcher.watch_with_polling(&all_dirs, poll) {
                                 error!("Failed to update file watcher: {}", e);
                             }
                         }
@@ -1107,12 +1170,56 @@ async fn run() -> Result<()> {
                     std::process::exit(1);
                 }
             }
+            // Dispatched before any server state is created.
+            Commands::Worker { .. } => {}
         }
     }
 
     Ok(())
 }
 
+async fn run_worker(
+    config_path: &Path,
+    server: String,
+    token: String,
+    name: Option<String>,
+    path_map: Vec<String>,
+) -> Result<()> {
+    // The local config only shapes hardware detection (preferred vendor,
+    // device path, CPU encoding); everything else comes from the server.
+    let config = if config_path.exists() {
+        config::Config::load(config_path)
+            .map_err(|err| alchemist::error::AlchemistError::Config(err.to_string()))?
+    } else {
+        config::Config::default()
+    };
+    let path_translations = path_map
+        .iter()
+        .map(|value| alchemist::worker::parse_path_map(value))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(alchemist::error::AlchemistError::Config)?;
+    let name = name
+        .or_else(sysinfo::System::host_name)
+        .unwrap_or_else(|| "worker".to_string());
+    let hardware = hardware::detect_hardware_for_config(&config).await?;
+    info!(
+        "Worker \"{}\" using {:?} ({})",
+        name,
+        hardware.vendor,
+        hardware.supported_codecs.join(", ")
+    );
+    alchemist::worker::run(
+        alchemist::worker::WorkerOptions {
+            server,
+            token,
+            name,
+            path_translations,
+        },
+        hardware,
+    )
+    .await
+}
+
 async fn wait_for_cli_jobs(db: &db::Db) -> Result<()> {
     info!("Waiting for jobs to complete...");
     loop {
@@ -1305,8 +1412,8 @@ async fn build_cli_plan(
             }
         };
 
-        let profile = match db.get_profile_for_path(&input_path.to_string_lossy()).await {
-            Ok(profile) => profile,
+        let selection = match alchemist::media::profile_rules::select_profile(db, &analysis).await {
+            Ok(selection) => selection,
             Err(err) => {
                 items.push(CliPlanItem {
                     input_path: input_path_string,
@@ -1325,7 +1432,7 @@ async fn build_cli_plan(
         };
 
         let plan = match planner
-            .plan(&analysis, &output_path, profile.as_ref())
+            .plan_with_selection(&analysis, &output_path, &selection)
             .await
         {
             Ok(plan) => plan,
@@ -1333,7 +1440,7 @@ async fn build_cli_plan(
                 items.push(CliPlanItem {
                     input_path: input_path_string,
                     output_path: Some(output_path.display().to_string()),
-                    profile: profile.as_ref().map(|p| p.name.clone()),
+                    profile: selection.profile.as_ref().map(|p| p.name.clone()),
                     decision: "error".to_string(),
                     reason: "planning failed".to_string(),
                     encoder: None,
@@ -1361,7 +1468,7 @@ async fn build_cli_plan(
         items.push(CliPlanItem {
             input_path: input_path_string,
             output_path: Some(output_path.display().to_string()),
-            profile: profile.as_ref().map(|p| p.name.clone()),
+            profile: selection.profile.as_ref().map(|p| p.name.clone()),
             decision,
             reason,
             encoder: plan
@@ -1658,6 +1765,28 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn worker_subcommand_parses_repeated_path_maps() {
+        let args = Args::try_parse_from([
+            "alchemist",
+            "worker",
+            "--server",
+            "http://nas:3000",
+            "--token",
+            "alc_tok_x",
+            "--path-map",
+            "/media=/mnt/media",
+            "--path-map",
+            "/tv=/mnt/tv",
+        ])
+        .unwrap_or_else(|err| panic!("failed to parse worker subcommand: {err}"));
+        assert!(matches!(
+            args.command,
+            Some(Commands::Worker { server, name: None, path_map, .. })
+                if server == "http://nas:3000" && path_map == ["/media=/mnt/media", "/tv=/mnt/tv"]
+        ));
+    }
+
     #[test]
     fn config_reload_matches_create_modify_and_rename_events() {
         let config_path = PathBuf::from("/tmp/alchemist-config.toml");
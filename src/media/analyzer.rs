@@ -21,9 +21,25 @@ const FFPROBE_ANALYZE_ARGS: &[&str] = &[
     "-print_format",
     "json",
     "-show_entries",
-    "format=duration,size,bit_rate,format_name,format_long_name:stream=codec_type,codec_name,pix_fmt,width,height,coded_width,coded_height,bit_rate,bits_per_raw_sample,channel_layout,channels,avg_frame_rate,r_frame_rate,nb_frames,duration,disposition,color_primaries,color_transfer,color_space,color_range,field_order:stream_side_data=side_data_type:stream_tags=language,title:chapter=id",
+    "format=duration,size,bit_rate,format_name,format_long_name:stream=codec_type,codec_name,pix_fmt,width,height,coded_width,coded_height,bit_rate,bits_per_raw_sample,channel_layout,channels,avg_frame_rate,r_frame_rate,nb_frames,duration,disposition,color_primaries,color_transfer,color_space,color_range,field_order:stream_side_data=side_data_type,dv_profile,dv_bl_signal_compatibility_id:stream_tags=language,title:format_tags=ALCHEMIST_JOB,alchemist_job:chapter=id",
 ];
-const ANALYZER_REPORT_CACHE_SCHEMA: &str = "analysis_report_v1";
+const ANALYZER_REPORT_CACHE_SCHEMA: &str = "analysis_report_v6";
+const CROPDETECT_TIMEOUT_SECS: u64 = 30;
+/// Crop samples as fractions of the runtime, spread to catch aspect changes.
+const CROPDETECT_SAMPLE_POSITIONS: [f64; 5] = [0.1, 0.3, 0.5, 0.7, 0.9];
+const CROPDETECT_FRAMES_PER_SAMPLE: &str = "12";
+/// Fewer agreeing samples than this is not enough to trust a crop.
+const CROPDETECT_MIN_SAMPLES: usize = 3;
+/// Samples whose edges differ by no more than this count as the same crop.
+const CROP_TOLERANCE_PX: u32 = 4;
+/// Bars thinner than this in both axes are not worth a filter pass.
+const MIN_CROP_PX: u32 = 8;
+const IDET_TIMEOUT_SECS: u64 = 30;
+/// Frames `idet` classifies; enough for its multi-frame detector to settle
+/// past the first few undetermined frames.
+const IDET_FRAMES: &str = "300";
+/// HDR10+ only shows up as per-frame side data; a few packets are enough.
+const HDR10_PLUS_PROBE_INTERVAL: &str = "%+#5";
 static FFPROBE_VERSION_MARKER: OnceCell<String> = OnceCell::const_new();
 
 async fn run_ffprobe(args: &[&str], path: &Path) -> Result<std::process::Output> {
@@ -161,6 +177,12 @@ pub struct Stream {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SideData {
     pub side_data_type: Option<String>,
+    /// Dolby Vision profile from the DOVI configuration record.
+    #[serde(default)]
+    pub dv_profile: Option<u32>,
+    /// Base-layer compatibility: 1 = HDR10, 2 = SDR, 4 = HLG, 0 = none.
+    #[serde(default)]
+    pub dv_bl_signal_compatibility_id: Option<u32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -182,6 +204,15 @@ pub struct Format {
     pub duration: String,
     pub size: String,
     pub bit_rate: String,
+    #[serde(default)]
+    pub tags: Option<FormatTags>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FormatTags {
+    /// Processed-by marker; see [`crate::media::marker`].
+    #[serde(rename = "ALCHEMIST_JOB", alias = "alchemist_job")]
+    pub alchemist_job: Option<String>,
 }
 
 pub struct FfmpegAnalyzer;
@@ -192,7 +223,9 @@ impl AnalyzerTrait for FfmpegAnalyzer {
 
         let output = run_ffprobe(FFPROBE_ANALYZE_ARGS, &path).await?;
 
-        tokio::task::spawn_blocking(move || {
+        let blocking_path = path.clone();
+        let analysis: Result<MediaAnalysis> = tokio::task::spawn_blocking(move || {
+            let path = blocking_path;
             let metadata: FfprobeMetadata =
                 serde_json::from_slice(&output.stdout).map_err(|e| {
                     AlchemistError::Analyzer(format!("Failed to parse ffprobe JSON: {}", e))
@@ -261,8 +294,11 @@ impl AnalyzerTrait for FfmpegAnalyzer {
 
             let color_transfer = video_stream.color_transfer.clone();
             let color_primaries = video_stream.color_primaries.clone();
-            let dynamic_range =
-                detect_dynamic_range(color_transfer.as_deref(), color_primaries.as_deref());
+            let dynamic_range = if has_dolby_vision_metadata(video_stream) {
+                DynamicRange::DolbyVision
+            } else {
+                detect_dynamic_range(color_transfer.as_deref(), color_primaries.as_deref())
+            };
 
             let mut warnings = Vec::new();
 
@@ -340,6 +376,12 @@ impl AnalyzerTrait for FfmpegAnalyzer {
                 subtitle_streams,
                 audio_streams,
                 chapter_count: u32::try_from(metadata.chapters.len()).unwrap_or(u32::MAX),
+                alchemist_marker: metadata
+                    .format
+                    .tags
+                    .as_ref()
+                    .and_then(|tags| tags.alchemist_job.as_deref())
+                    .and_then(crate::media::marker::ProcessedMarker::parse),
             };
 
             let analysis_report = build_analyzer_report(
@@ -359,7 +401,36 @@ impl AnalyzerTrait for FfmpegAnalyzer {
             })
         })
         .await
-        .map_err(|e| AlchemistError::Analyzer(format!("spawn_blocking failed: {}", e)))?
+        .map_err(|e| AlchemistError::Analyzer(format!("spawn_blocking failed: {}", e)))?;
+
+        let mut analysis = analysis?;
+        // Container field-order flags are often wrong on old rips, so only
+        // trust them once idet has looked at the actual pictures.
+        if analysis
+            .analysis_report
+            .labels
+            .contains(&AnalyzerLabel::InterlacedMetadata)
+            && let Some(probe) = probe_field_order(&path, analysis.metadata.duration_secs).await
+        {
+            apply_field_order_probe(&mut analysis.analysis_report, &probe);
+        }
+        // Containers rarely declare HDR10+ at the stream level, so PQ sources
+        // get a short look at their first frames.
+        if matches!(
+            analysis.metadata.dynamic_range,
+            DynamicRange::Hdr10 | DynamicRange::DolbyVision
+        ) && !analysis
+            .analysis_report
+            .labels
+            .contains(&AnalyzerLabel::Hdr10PlusMetadata)
+            && probe_hdr10_plus(&path).await
+        {
+            push_label(
+                &mut analysis.analysis_report.labels,
+                AnalyzerLabel::Hdr10PlusMetadata,
+            );
+        }
+        Ok(analysis)
     }
 }
 
@@ -424,8 +495,29 @@ impl FfmpegAnalyzer {
         }
 
         let analysis = self.analyze(path).await?;
+        Self::store_cached_analysis(db, &cache_key, &analysis).await;
+        Ok(analysis)
+    }
+
+    /// Record `analysis` as the probe result for `path` as it is on disk now,
+    /// so later `analyze_with_cache` calls reuse it.
+    pub async fn cache_analysis(
+        &self,
+        db: &crate::db::Db,
+        path: &Path,
+        analysis: &MediaAnalysis,
+    ) -> Result<()> {
+        let cache_key = Self::probe_cache_key_for_path(path).await?;
+        Self::store_cached_analysis(db, &cache_key, analysis).await;
+        Ok(())
+    }
 
-        match serde_json::to_string(&analysis) {
+    async fn store_cached_analysis(
+        db: &crate::db::Db,
+        cache_key: &ProbeCacheKey,
+        analysis: &MediaAnalysis,
+    ) {
+        match serde_json::to_string(analysis) {
             Ok(serialized) => {
                 if let Err(err) = db
                     .upsert_media_probe_cache_with_file_id(
@@ -451,8 +543,6 @@ impl FfmpegAnalyzer {
                 );
             }
         }
-
-        Ok(analysis)
     }
 }
 
@@ -479,7 +569,7 @@ impl Analyzer {
                 "-print_format",
                 "json",
                 "-show_entries",
-                "format=duration,size,bit_rate,format_name,format_long_name:stream=codec_type,codec_name,pix_fmt,width,height,coded_width,coded_height,bit_rate,bits_per_raw_sample,channel_layout,channels,avg_frame_rate,r_frame_rate,nb_frames,duration,disposition,color_primaries,color_transfer,color_space,color_range,field_order:stream_side_data=side_data_type:stream_tags=language,title:chapter=id",
+                "format=duration,size,bit_rate,format_name,format_long_name:stream=codec_type,codec_name,pix_fmt,width,height,coded_width,coded_height,bit_rate,bits_per_raw_sample,channel_layout,channels,avg_frame_rate,r_frame_rate,nb_frames,duration,disposition,color_primaries,color_transfer,color_space,color_range,field_order:stream_side_data=side_data_type,dv_profile,dv_bl_signal_compatibility_id:stream_tags=language,title:chapter=id",
             ],
             path,
         )
@@ -692,21 +782,7 @@ impl Analyzer {
             return false;
         }
 
-        // Only transcode lossless or exotic heavy codecs.
-        // Standard compressed codecs (eac3, ac3, dts) copy
-        // fine into MKV regardless of bitrate — eac3 Atmos
-        // at 768 kbps is normal and should not be transcoded.
-        let heavy_codecs = [
-            "truehd",
-            "mlp",
-            "dts-hd",
-            "flac",
-            "pcm_s24le",
-            "pcm_s16le",
-            "pcm_s32le",
-            "pcm_f32le",
-        ];
-        heavy_codecs.contains(&stream.codec_name.to_lowercase().as_str())
+        audio_codec_is_heavy(&stream.codec_name)
     }
 }
 
@@ -870,9 +946,13 @@ fn build_analyzer_report(
     if has_bt2020_metadata && has_missing_color_transfer {
         push_label(&mut labels, AnalyzerLabel::Bt2020WithoutTransfer);
     }
+    let dolby_vision_record = dolby_vision_record(video_stream);
     if has_dolby_vision_metadata(video_stream) {
         push_label(&mut labels, AnalyzerLabel::DolbyVisionMetadata);
     }
+    if has_hdr10_plus_metadata(&video_stream.side_data_list) {
+        push_label(&mut labels, AnalyzerLabel::Hdr10PlusMetadata);
+    }
     if video_stream
         .field_order
         .as_deref()
@@ -909,6 +989,10 @@ fn build_analyzer_report(
             has_missing_color_transfer: Some(has_missing_color_transfer),
             fps_from_average_rate,
             fps_from_frame_count,
+            dolby_vision_profile: dolby_vision_record.and_then(|record| record.dv_profile),
+            dolby_vision_bl_compat_id: dolby_vision_record
+                .and_then(|record| record.dv_bl_signal_compatibility_id),
+            ..AnalyzerMetrics::default()
         },
     }
 }
@@ -986,7 +1070,24 @@ fn remux_like_density(video_bitrate_bps: Option<u64>, container_bitrate_bps: Opt
     }
 }
 
-fn audio_codec_is_lossless(codec_name: &str) -> bool {
+/// Lossless or exotic codecs worth re-encoding. Standard compressed codecs
+/// (eac3, ac3, dts) copy fine into MKV regardless of bitrate — eac3 Atmos at
+/// 768 kbps is normal and should not be transcoded.
+pub(crate) fn audio_codec_is_heavy(codec_name: &str) -> bool {
+    matches!(
+        codec_name.to_ascii_lowercase().as_str(),
+        "truehd"
+            | "mlp"
+            | "dts-hd"
+            | "flac"
+            | "pcm_s24le"
+            | "pcm_s16le"
+            | "pcm_s32le"
+            | "pcm_f32le"
+    )
+}
+
+pub(crate) fn audio_codec_is_lossless(codec_name: &str) -> bool {
     matches!(
         codec_name.to_ascii_lowercase().as_str(),
         "truehd" | "mlp" | "flac" | "alac" | "pcm_s24le" | "pcm_s16le" | "pcm_s32le" | "pcm_f32le"
@@ -1017,6 +1118,347 @@ fn has_dolby_vision_metadata(stream: &Stream) -> bool {
     })
 }
 
+fn has_hdr10_plus_metadata(side_data_list: &[SideData]) -> bool {
+    side_data_list.iter().any(|side_data| {
+        side_data.side_data_type.as_deref().is_some_and(|value| {
+            let value = value.to_ascii_uppercase();
+            value.contains("SMPTE2094-40") || value.contains("HDR10+")
+        })
+    })
+}
+
+fn dolby_vision_record(stream: &Stream) -> Option<&SideData> {
+    stream
+        .side_data_list
+        .iter()
+        .find(|side_data| side_data.dv_profile.is_some())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CropRect {
+    pub width: u32,
+    pub height: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl CropRect {
+    fn close_to(&self, other: &CropRect) -> bool {
+        self.width.abs_diff(other.width) <= CROP_TOLERANCE_PX
+            && self.height.abs_diff(other.height) <= CROP_TOLERANCE_PX
+            && self.x.abs_diff(other.x) <= CROP_TOLERANCE_PX
+            && self.y.abs_diff(other.y) <= CROP_TOLERANCE_PX
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CropDetection {
+    /// Settled crop, present only when the samples agree and the bars are
+    /// wide enough to be worth removing.
+    pub rect: Option<CropRect>,
+    pub sample_count: u32,
+    pub samples_agree: bool,
+}
+
+/// Run `cropdetect` on a handful of short windows across the runtime. Returns
+/// `None` when FFmpeg is unavailable or too few samples produced a reading.
+async fn detect_crop(
+    path: &Path,
+    duration_secs: f64,
+    width: u32,
+    height: u32,
+) -> Option<CropDetection> {
+    if duration_secs <= 0.0 || width == 0 || height == 0 {
+        return None;
+    }
+
+    let mut samples = Vec::new();
+    for position in CROPDETECT_SAMPLE_POSITIONS {
+        let start = format!("{:.3}", duration_secs * position);
+        let result = tokio::time::timeout(
+            std::time::Duration::from_secs(CROPDETECT_TIMEOUT_SECS),
+            Command::new("ffmpeg")
+                .args(["-hide_banner", "-nostats", "-ss", &start, "-i"])
+                .arg(path)
+                .args([
+                    "-map",
+                    "0:v:0",
+                    "-frames:v",
+                    CROPDETECT_FRAMES_PER_SAMPLE,
+                    "-vf",
+                    "cropdetect=limit=24:round=2:reset=0",
+                    "-an",
+                    "-sn",
+                    "-f",
+                    "null",
+                    "-",
+                ])
+                .kill_on_drop(true)
+                .output(),
+        )
+        .await;
+        match result {
+            Ok(Ok(output)) if output.status.success() => {
+                if let Some(rect) =
+                    parse_cropdetect_output(&String::from_utf8_lossy(&output.stderr))
+                {
+                    samples.push(rect);
+                }
+            }
+            Ok(Ok(_)) | Err(_) => continue,
+            Ok(Err(err)) => {
+                tracing::debug!("cropdetect unavailable for {}: {err}", path.display());
+                return None;
+            }
+        }
+    }
+
+    settle_crop(&samples, width, height)
+}
+
+/// The last `crop=w:h:x:y` reading in FFmpeg's cropdetect log.
+fn parse_cropdetect_output(stderr: &str) -> Option<CropRect> {
+    stderr.lines().rev().find_map(|line| {
+        let (_, tail) = line.rsplit_once("crop=")?;
+        let mut parts = tail
+            .split_whitespace()
+            .next()?
+            .split(':')
+            .map(|part| part.parse::<i64>().ok());
+        let width = u32::try_from(parts.next()??).ok()?;
+        let height = u32::try_from(parts.next()??).ok()?;
+        let x = u32::try_from(parts.next()??).ok()?;
+        let y = u32::try_from(parts.next()??).ok()?;
+        Some(CropRect {
+            width,
+            height,
+            x,
+            y,
+        })
+    })
+}
+
+/// Reduce per-sample readings to a single crop. Near-black samples (which
+/// cropdetect reports as a tiny rectangle) are ignored; any remaining
+/// disagreement means the aspect ratio changes during the film, so no crop is
+/// proposed.
+fn settle_crop(samples: &[CropRect], width: u32, height: u32) -> Option<CropDetection> {
+    let plausible = samples
+        .iter()
+        .filter(|rect| {
+            rect.width >= width / 2
+                && rect.height >= height / 2
+                && rect.x + rect.width <= width
+                && rect.y + rect.height <= height
+        })
+        .copied()
+        .collect::<Vec<_>>();
+    if plausible.len() < CROPDETECT_MIN_SAMPLES {
+        return None;
+    }
+
+    let sample_count = u32::try_from(plausible.len()).unwrap_or(u32::MAX);
+    let first = plausible[0];
+    if !plausible.iter().all(|rect| rect.close_to(&first)) {
+        return Some(CropDetection {
+            rect: None,
+            sample_count,
+            samples_agree: false,
+        });
+    }
+
+    // Keep the union of the readings so no sample loses picture.
+    let left = plausible.iter().map(|rect| rect.x).min().unwrap_or(0);
+    let top = plausible.iter().map(|rect| rect.y).min().unwrap_or(0);
+    let right = plausible
+        .iter()
+        .map(|rect| rect.x + rect.width)
+        .max()
+        .unwrap_or(width);
+    let bottom = plausible
+        .iter()
+        .map(|rect| rect.y + rect.height)
+        .max()
+        .unwrap_or(height);
+    let rect = CropRect {
+        width: (right - left) & !1,
+        height: (bottom - top) & !1,
+        x: left,
+        y: top,
+    };
+    let worth_cropping = width - rect.width >= MIN_CROP_PX || height - rect.height >= MIN_CROP_PX;
+
+    Some(CropDetection {
+        rect: worth_cropping.then_some(rect),
+        sample_count,
+        samples_agree: true,
+    })
+}
+
+/// `analysis` with black-bar facts filled in. Cropdetect decodes several
+/// windows of the source, so the analysis pass leaves it out and the planner
+/// asks for it only when the applied profile enables auto crop.
+pub async fn with_crop_detection(analysis: &MediaAnalysis) -> MediaAnalysis {
+    let mut analysis = analysis.clone();
+    if analysis.analysis_report.metrics.crop_sample_count.is_some() {
+        return analysis;
+    }
+    let metadata = &analysis.metadata;
+    if let Some(detection) = detect_crop(
+        &metadata.path,
+        metadata.duration_secs,
+        metadata.width,
+        metadata.height,
+    )
+    .await
+    {
+        apply_crop_detection(&mut analysis.analysis_report, &detection);
+    }
+    analysis
+}
+
+fn apply_crop_detection(report: &mut AnalyzerReport, detection: &CropDetection) {
+    report.metrics.crop_sample_count = Some(detection.sample_count);
+    report.metrics.crop_samples_agree = Some(detection.samples_agree);
+    if let Some(rect) = detection.rect {
+        report.metrics.crop_width = Some(rect.width);
+        report.metrics.crop_height = Some(rect.height);
+        report.metrics.crop_x = Some(rect.x);
+        report.metrics.crop_y = Some(rect.y);
+        push_label(&mut report.labels, AnalyzerLabel::BlackBars);
+    }
+    if !detection.samples_agree {
+        push_label(&mut report.labels, AnalyzerLabel::VariableCrop);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldOrderProbe {
+    /// Frames `idet` classified as top- or bottom-field-first.
+    pub interlaced_frames: u32,
+    pub progressive_frames: u32,
+}
+
+impl FieldOrderProbe {
+    pub fn confirms_interlacing(&self) -> bool {
+        self.interlaced_frames > 0 && self.interlaced_frames >= self.progressive_frames
+    }
+}
+
+/// Run `idet` on a window from the middle of the runtime, away from
+/// progressive studio logos and credits. Returns `None` when FFmpeg is
+/// unavailable or produced no classification.
+async fn probe_field_order(path: &Path, duration_secs: f64) -> Option<FieldOrderProbe> {
+    let start = format!("{:.3}", duration_secs.max(0.0) * 0.5);
+    let result = tokio::time::timeout(
+        std::time::Duration::from_secs(IDET_TIMEOUT_SECS),
+        Command::new("ffmpeg")
+            .args(["-hide_banner", "-nostats", "-ss", &start, "-i"])
+            .arg(path)
+            .args([
+                "-map",
+                "0:v:0",
+                "-frames:v",
+                IDET_FRAMES,
+                "-vf",
+                "idet",
+                "-an",
+                "-sn",
+                "-f",
+                "null",
+                "-",
+            ])
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await;
+    match result {
+        Ok(Ok(output)) if output.status.success() => {
+            parse_idet_output(&String::from_utf8_lossy(&output.stderr))
+        }
+        Ok(Ok(_)) | Err(_) => None,
+        Ok(Err(err)) => {
+            tracing::debug!("idet unavailable for {}: {err}", path.display());
+            None
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FrameSideDataProbe {
+    #[serde(default)]
+    frames: Vec<ProbedFrame>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbedFrame {
+    #[serde(default)]
+    side_data_list: Vec<SideData>,
+}
+
+/// Whether the first few video frames carry HDR10+ side data. Probe
+/// failures count as "not found" rather than failing the analysis.
+pub(crate) async fn probe_hdr10_plus(path: &Path) -> bool {
+    match run_ffprobe(
+        &[
+            "-v",
+            "quiet",
+            "-select_streams",
+            "v:0",
+            "-read_intervals",
+            HDR10_PLUS_PROBE_INTERVAL,
+            "-print_format",
+            "json",
+            "-show_entries",
+            "frame_side_data=side_data_type",
+        ],
+        path,
+    )
+    .await
+    {
+        Ok(output) => parse_frame_side_data(&output.stdout),
+        Err(err) => {
+            tracing::debug!("HDR10+ frame probe failed for {}: {err}", path.display());
+            false
+        }
+    }
+}
+
+fn parse_frame_side_data(stdout: &[u8]) -> bool {
+    serde_json::from_slice::<FrameSideDataProbe>(stdout).is_ok_and(|probe| {
+        probe
+            .frames
+            .iter()
+            .any(|frame| has_hdr10_plus_metadata(&frame.side_data_list))
+    })
+}
+
+/// Reads the `Multi frame detection` summary, which smooths over the
+/// single-frame detector's noise.
+fn parse_idet_output(stderr: &str) -> Option<FieldOrderProbe> {
+    let line = stderr
+        .lines()
+        .rev()
+        .find(|line| line.contains("Multi frame detection:"))?;
+    let count = |label: &str| -> Option<u32> {
+        let (_, tail) = line.split_once(label)?;
+        tail.split_whitespace().next()?.parse().ok()
+    };
+    let tff = count("TFF:")?;
+    let bff = count("BFF:")?;
+    let progressive = count("Progressive:")?;
+    Some(FieldOrderProbe {
+        interlaced_frames: tff.saturating_add(bff),
+        progressive_frames: progressive,
+    })
+}
+
+fn apply_field_order_probe(report: &mut AnalyzerReport, probe: &FieldOrderProbe) {
+    report.metrics.idet_interlaced_frames = Some(probe.interlaced_frames);
+    report.metrics.idet_progressive_frames = Some(probe.progressive_frames);
+    report.metrics.interlace_confirmed = Some(probe.confirms_interlacing());
+}
+
 fn field_order_is_interlaced(field_order: &str) -> bool {
     matches!(
         field_order.to_ascii_lowercase().as_str(),
@@ -1134,6 +1576,7 @@ mod tests {
             audio_streams: Vec::new(),
             dynamic_range: DynamicRange::Sdr,
             chapter_count: 0,
+            alchemist_marker: None,
         }
     }
 
@@ -1389,6 +1832,8 @@ mod tests {
         video.field_order = Some("tt".to_string());
         video.side_data_list = vec![SideData {
             side_data_type: Some("DOVI configuration record".to_string()),
+            dv_profile: Some(8),
+            dv_bl_signal_compatibility_id: Some(1),
         }];
         let warnings = vec![
             AnalysisWarning::MissingVideoBitrate,
@@ -1410,6 +1855,8 @@ mod tests {
 
         assert!(report.labels.contains(&AnalyzerLabel::HdrMetadata));
         assert!(report.labels.contains(&AnalyzerLabel::DolbyVisionMetadata));
+        assert_eq!(report.metrics.dolby_vision_profile, Some(8));
+        assert_eq!(report.metrics.dolby_vision_bl_compat_id, Some(1));
         assert!(report.labels.contains(&AnalyzerLabel::InterlacedMetadata));
         assert!(
             report
@@ -1562,6 +2009,174 @@ mod tests {
         assert_eq!(metadata.chapters.len(), 2);
     }
 
+    #[test]
+    fn ffprobe_metadata_reads_the_processed_by_marker() {
+        let json = r#"{
+            "streams": [],
+            "format": {
+                "format_name": "mov,mp4,m4a,3gp,3g2,mj2",
+                "duration": "60.0",
+                "size": "1000",
+                "bit_rate": "1000",
+                "tags": { "alchemist_job": "v=1;version=0.3.5;profile=default;source=ab;settings=cd" }
+            }
+        }"#;
+
+        let metadata: FfprobeMetadata = match serde_json::from_str(json) {
+            Ok(metadata) => metadata,
+            Err(err) => panic!("ffprobe metadata json failed to decode: {err}"),
+        };
+        let marker = metadata
+            .format
+            .tags
+            .and_then(|tags| tags.alchemist_job)
+            .and_then(|value| crate::media::marker::ProcessedMarker::parse(&value));
+        assert_eq!(
+            marker.map(|marker| marker.settings_digest),
+            Some("cd".to_string())
+        );
+    }
+
+    fn crop(width: u32, height: u32, x: u32, y: u32) -> CropRect {
+        CropRect {
+            width,
+            height,
+            x,
+            y,
+        }
+    }
+
+    #[test]
+    fn cropdetect_output_uses_last_reading() {
+        let stderr = "[Parsed_cropdetect_0 @ 0x1] x1:0 x2:1919 y1:140 y2:939 w:1920 h:800 x:0 y:140 pts:1 t:0.04 limit:0.094 crop=1920:800:0:140\n\
+                      [Parsed_cropdetect_0 @ 0x1] x1:0 x2:1919 y1:138 y2:941 w:1920 h:804 x:0 y:138 pts:2 t:0.08 limit:0.094 crop=1920:804:0:138\n\
+                      [out#0/null] video:1kB";
+        assert_eq!(
+            parse_cropdetect_output(stderr),
+            Some(crop(1920, 804, 0, 138))
+        );
+        assert_eq!(parse_cropdetect_output("no readings here"), None);
+        assert_eq!(parse_cropdetect_output("crop=-1920:800:0:0"), None);
+    }
+
+    #[test]
+    fn settle_crop_accepts_stable_letterbox_and_skips_black_frames() {
+        let samples = vec![
+            crop(1920, 800, 0, 140),
+            crop(1920, 804, 0, 138),
+            crop(16, 16, 952, 532),
+            crop(1920, 800, 0, 140),
+        ];
+        let Some(detection) = settle_crop(&samples, 1920, 1080) else {
+            panic!("expected a crop decision");
+        };
+
+        assert!(detection.samples_agree);
+        assert_eq!(detection.sample_count, 3);
+        assert_eq!(detection.rect, Some(crop(1920, 804, 0, 138)));
+    }
+
+    #[test]
+    fn settle_crop_refuses_variable_aspect_samples() {
+        let samples = vec![
+            crop(1920, 800, 0, 140),
+            crop(1920, 1080, 0, 0),
+            crop(1920, 800, 0, 140),
+        ];
+        let Some(detection) = settle_crop(&samples, 1920, 1080) else {
+            panic!("expected a crop decision");
+        };
+
+        assert!(!detection.samples_agree);
+        assert_eq!(detection.rect, None);
+
+        let mut report = AnalyzerReport::default();
+        apply_crop_detection(&mut report, &detection);
+        assert!(report.labels.contains(&AnalyzerLabel::VariableCrop));
+        assert!(!report.labels.contains(&AnalyzerLabel::BlackBars));
+        assert_eq!(report.metrics.crop_samples_agree, Some(false));
+    }
+
+    #[test]
+    fn settle_crop_ignores_negligible_bars_and_thin_evidence() {
+        let full = vec![crop(1920, 1076, 0, 2); 3];
+        let Some(detection) = settle_crop(&full, 1920, 1080) else {
+            panic!("expected a crop decision");
+        };
+        assert!(detection.samples_agree);
+        assert_eq!(detection.rect, None);
+
+        assert_eq!(settle_crop(&full[..2], 1920, 1080), None);
+    }
+
+    #[test]
+    fn crop_detection_populates_report_metrics() {
+        let mut report = AnalyzerReport::default();
+        apply_crop_detection(
+            &mut report,
+            &CropDetection {
+                rect: Some(crop(1920, 800, 0, 140)),
+                sample_count: 5,
+                samples_agree: true,
+            },
+        );
+
+        assert_eq!(report.labels, vec![AnalyzerLabel::BlackBars]);
+        assert_eq!(report.metrics.crop_width, Some(1920));
+        assert_eq!(report.metrics.crop_height, Some(800));
+        assert_eq!(report.metrics.crop_y, Some(140));
+        assert_eq!(report.metrics.crop_sample_count, Some(5));
+    }
+
+    #[test]
+    fn idet_summary_confirms_interlaced_pictures() {
+        let stderr = "\
+[Parsed_idet_0 @ 0x55d5] Repeated Fields: Neither:   300 Top:     0 Bottom:     0
+[Parsed_idet_0 @ 0x55d5] Single frame detection: TFF:   201 BFF:     0 Progressive:    61 Undetermined:    38
+[Parsed_idet_0 @ 0x55d5] Multi frame detection: TFF:   287 BFF:     0 Progressive:     9 Undetermined:     4
+";
+        let Some(probe) = parse_idet_output(stderr) else {
+            panic!("expected an idet summary");
+        };
+        assert_eq!(probe.interlaced_frames, 287);
+        assert_eq!(probe.progressive_frames, 9);
+        assert!(probe.confirms_interlacing());
+
+        let mut report = AnalyzerReport::default();
+        apply_field_order_probe(&mut report, &probe);
+        assert_eq!(report.metrics.interlace_confirmed, Some(true));
+    }
+
+    #[test]
+    fn idet_summary_rejects_mislabelled_progressive_video() {
+        let stderr = "[Parsed_idet_0 @ 0x55d5] Multi frame detection: TFF:     3 BFF:     0 Progressive:   290 Undetermined:     7";
+        let Some(probe) = parse_idet_output(stderr) else {
+            panic!("expected an idet summary");
+        };
+        assert!(!probe.confirms_interlacing());
+        assert_eq!(parse_idet_output("no summary here"), None);
+    }
+
+    #[test]
+    fn hdr10_plus_is_read_from_stream_and_frame_side_data() {
+        let mut video = stream("video", "hevc");
+        video.side_data_list = vec![SideData {
+            side_data_type: Some("HDR Dynamic Metadata SMPTE2094-40 (HDR10+)".to_string()),
+            dv_profile: None,
+            dv_bl_signal_compatibility_id: None,
+        }];
+        let streams = vec![video];
+        let report = build_analyzer_report(&metadata(), &[], &streams, &streams[0], None, None);
+        assert!(report.labels.contains(&AnalyzerLabel::Hdr10PlusMetadata));
+
+        let frames = br#"{"frames":[{"side_data_list":[{"side_data_type":"Mastering display metadata"}]},{"side_data_list":[{"side_data_type":"HDR Dynamic Metadata SMPTE2094-40 (HDR10+)"}]}]}"#;
+        assert!(parse_frame_side_data(frames));
+        let static_only =
+            br#"{"frames":[{"side_data_list":[{"side_data_type":"Mastering display metadata"}]}]}"#;
+        assert!(!parse_frame_side_data(static_only));
+        assert!(!parse_frame_side_data(b"not json"));
+    }
+
     #[test]
     fn partial_analysis_report_json_defaults_nested_fields() {
         let report: AnalyzerReport = match serde_json::from_str(r#"{"labels":["heavy_audio"]}"#) {
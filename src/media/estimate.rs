@@ -0,0 +1,187 @@
+//! Output size and encode time estimates used to order the queue.
+//!
+//! The starting point is a target bits-per-pixel for the output codec applied
+//! to the source's frame size, frame rate and duration, capped at the source
+//! size. Completed encodes of similar sources (same source and output codec)
+//! are blended in as they accumulate, so the estimate drifts from the rule of
+//! thumb towards what this library actually produces.
+
+use crate::config::OutputCodec;
+use crate::db::EncodeHistory;
+use crate::media::pipeline::{MediaMetadata, TranscodePlan};
+
+/// History samples needed before they outweigh the bpp heuristic.
+const HISTORY_PRIOR_SAMPLES: f64 = 5.0;
+/// Assumed encode speed (media seconds per wall second) without history.
+const DEFAULT_ENCODE_SPEED: f64 = 1.0;
+/// Remuxes only copy streams, so they run far faster than realtime.
+const REMUX_SPEED: f64 = 50.0;
+/// Output/input ratio assumed when the source's bpp cannot be computed.
+const FALLBACK_OUTPUT_RATIO: f64 = 0.6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JobEstimate {
+    pub source_size_bytes: u64,
+    pub estimated_output_bytes: u64,
+    pub estimated_encode_seconds: Option<f64>,
+}
+
+impl JobEstimate {
+    pub fn estimated_savings_bytes(&self) -> u64 {
+        self.source_size_bytes
+            .saturating_sub(self.estimated_output_bytes)
+    }
+}
+
+/// Bits per pixel a typical encode at the default quality lands on.
+fn target_bpp(codec: OutputCodec) -> f64 {
+    match codec {
+        OutputCodec::Av1 => 0.035,
+        OutputCodec::Hevc => 0.045,
+        OutputCodec::H264 => 0.07,
+    }
+}
+
+fn heuristic_output_bytes(metadata: &MediaMetadata, codec: OutputCodec) -> Option<f64> {
+    if metadata.width == 0
+        || metadata.height == 0
+        || metadata.fps <= 0.0
+        || metadata.duration_secs <= 0.0
+    {
+        return None;
+    }
+    let pixels_per_second = metadata.width as f64 * metadata.height as f64 * metadata.fps;
+    let video_bits = target_bpp(codec) * pixels_per_second * metadata.duration_secs;
+    let audio_bits = metadata.audio_bitrate_bps.unwrap_or(0) as f64 * metadata.duration_secs;
+    Some((video_bits + audio_bits) / 8.0)
+}
+
+/// Estimate what carrying out `plan` on `metadata` produces and how long it
+/// takes.
+pub fn estimate_for_plan(
+    metadata: &MediaMetadata,
+    plan: &TranscodePlan,
+    history: &EncodeHistory,
+) -> JobEstimate {
+    let codec = (!plan.is_remux && !plan.copy_video)
+        .then(|| plan.output_codec.unwrap_or(plan.requested_codec));
+    estimate(metadata, codec, history)
+}
+
+/// `codec` is the video codec encoded to, or `None` for a stream copy.
+fn estimate(
+    metadata: &MediaMetadata,
+    codec: Option<OutputCodec>,
+    history: &EncodeHistory,
+) -> JobEstimate {
+    let source_size_bytes = metadata.size_bytes;
+    let source = source_size_bytes as f64;
+    let duration = (metadata.duration_secs > 0.0).then_some(metadata.duration_secs);
+
+    let Some(codec) = codec else {
+        return JobEstimate {
+            source_size_bytes,
+            estimated_output_bytes: source_size_bytes,
+            estimated_encode_seconds: duration.map(|duration| duration / REMUX_SPEED),
+        };
+    };
+
+    let heuristic = heuristic_output_bytes(metadata, codec)
+        .unwrap_or(source * FALLBACK_OUTPUT_RATIO)
+        .min(source);
+    let output = match history.avg_output_ratio {
+        Some(ratio) if history.sample_size > 0 && ratio > 0.0 => {
+            let samples = history.sample_size as f64;
+            let weight = samples / (samples + HISTORY_PRIOR_SAMPLES);
+            weight * ratio.min(1.0) * source + (1.0 - weight) * heuristic
+        }
+        _ => heuristic,
+    };
+
+    let speed = history
+        .avg_encode_speed
+        .filter(|speed| *speed > 0.0)
+        .unwrap_or(DEFAULT_ENCODE_SPEED);
+
+    JobEstimate {
+        source_size_bytes,
+        estimated_output_bytes: output.round().max(0.0) as u64,
+        estimated_encode_seconds: duration.map(|duration| duration / speed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::pipeline::DynamicRange;
+
+    fn metadata() -> MediaMetadata {
+        MediaMetadata {
+            path: "/tmp/in.mkv".into(),
+            duration_secs: 3600.0,
+            codec_name: "h264".to_string(),
+            width: 1920,
+            height: 1080,
+            bit_depth: Some(8),
+            color_primaries: None,
+            color_transfer: None,
+            color_space: None,
+            color_range: None,
+            size_bytes: 8 * 1024 * 1024 * 1024,
+            video_bitrate_bps: Some(18_000_000),
+            container_bitrate_bps: Some(18_500_000),
+            fps: 24.0,
+            container: "matroska".to_string(),
+            audio_codec: None,
+            audio_bitrate_bps: None,
+            audio_channels: None,
+            audio_is_heavy: false,
+            subtitle_streams: Vec::new(),
+            audio_streams: Vec::new(),
+            dynamic_range: DynamicRange::Sdr,
+            chapter_count: 0,
+            alchemist_marker: None,
+        }
+    }
+
+    #[test]
+    fn heuristic_estimate_scales_with_frame_size_and_caps_at_source() {
+        let estimated = estimate(
+            &metadata(),
+            Some(OutputCodec::Hevc),
+            &EncodeHistory::default(),
+        );
+        // 0.045 bpp of 1080p24 for an hour is about 1 GB.
+        assert!((900_000_000..1_100_000_000).contains(&estimated.estimated_output_bytes));
+        assert!(estimated.estimated_savings_bytes() > 7_000_000_000);
+        assert_eq!(estimated.estimated_encode_seconds, Some(3600.0));
+
+        let tiny = MediaMetadata {
+            size_bytes: 100 * 1024 * 1024,
+            ..metadata()
+        };
+        let estimated = estimate(&tiny, Some(OutputCodec::Hevc), &EncodeHistory::default());
+        assert_eq!(estimated.estimated_savings_bytes(), 0);
+    }
+
+    #[test]
+    fn history_pulls_the_estimate_towards_observed_ratios() {
+        let history = EncodeHistory {
+            sample_size: 45,
+            avg_output_ratio: Some(0.5),
+            avg_encode_speed: Some(4.0),
+        };
+        let estimated = estimate(&metadata(), Some(OutputCodec::Hevc), &history);
+        let half = metadata().size_bytes / 2;
+        assert!(estimated.estimated_output_bytes > half * 9 / 10);
+        assert!(estimated.estimated_output_bytes < half);
+        assert_eq!(estimated.estimated_encode_seconds, Some(900.0));
+    }
+
+    #[test]
+    fn remux_estimates_no_savings() {
+        let estimated = estimate(&metadata(), None, &EncodeHistory::default());
+        assert_eq!(estimated.estimated_savings_bytes(), 0);
+        assert_eq!(estimated.estimated_encode_seconds, Some(72.0));
+    }
+}
@@ -1,5 +1,5 @@
 use crate::db::{Db, EventChannels, Job, JobEvent};
-use crate::error::Result;
+use crate::error::{AlchemistError, Result};
 use crate::media::pipeline::{Encoder, ExecutionResult, Executor, MediaAnalysis, TranscodePlan};
 use crate::orchestrator::{
     AsyncExecutionObserver, ExecutionObserver, TranscodeRequest, Transcoder,
@@ -113,145 +113,243 @@ impl Executor for FfmpegExecutor {
         plan: &TranscodePlan,
         analysis: &MediaAnalysis,
     ) -> Result<ExecutionResult> {
-        let input_path = PathBuf::from(&job.input_path);
-        let output_path = plan
-            .output_path
-            .as_ref()
-            .cloned()
-            .unwrap_or_else(|| PathBuf::from(&job.output_path));
-        let encoder = plan.encoder;
-        let planned_output_codec = plan.output_codec.unwrap_or_else(|| {
-            encoder
-                .map(Encoder::output_codec)
-                .unwrap_or(plan.requested_codec)
-        });
-        let used_backend = plan.backend.or_else(|| encoder.map(Encoder::backend));
         let observer: Arc<dyn ExecutionObserver> = Arc::new(JobExecutionObserver::new(
             job.id,
             self.db.clone(),
             self.event_channels.clone(),
         ));
+        execute_plan(
+            &self.transcoder,
+            self.hw_info.as_ref(),
+            self.dry_run,
+            ExecutionTarget {
+                job,
+                plan,
+                analysis,
+            },
+            observer,
+        )
+        .await
+    }
+}
 
-        tracing::info!(
-            "Job {} execution path: requested_codec={}, planned_codec={}, encoder={:?}, backend={:?}, fallback={:?}",
-            job.id,
-            plan.requested_codec.as_str(),
-            planned_output_codec.as_str(),
-            encoder.map(|value| value.ffmpeg_encoder_name()),
-            used_backend.map(|value| value.as_str()),
-            plan.fallback
-                .as_ref()
-                .map(|fallback| fallback.reason.as_str())
-        );
+/// What `execute_plan` runs: `plan` for `job`, whose source `analysis`
+/// describes.
+pub struct ExecutionTarget<'a> {
+    pub job: &'a Job,
+    pub plan: &'a TranscodePlan,
+    pub analysis: &'a MediaAnalysis,
+}
 
-        self.transcoder
-            .transcode_media(TranscodeRequest {
+/// Run a plan and probe what was actually produced, reporting logs and
+/// progress to `observer`. Remote workers call this directly with an
+/// observer that streams back to the server.
+pub async fn execute_plan(
+    transcoder: &Transcoder,
+    hw_info: Option<&HardwareInfo>,
+    dry_run: bool,
+    target: ExecutionTarget<'_>,
+    observer: Arc<dyn ExecutionObserver>,
+) -> Result<ExecutionResult> {
+    let ExecutionTarget {
+        job,
+        plan,
+        analysis,
+    } = target;
+    let input_path = PathBuf::from(&job.input_path);
+    let output_path = plan
+        .output_path
+        .as_ref()
+        .cloned()
+        .unwrap_or_else(|| PathBuf::from(&job.output_path));
+    let encoder = plan.encoder;
+    let planned_output_codec = plan.output_codec.unwrap_or_else(|| {
+        encoder
+            .map(Encoder::output_codec)
+            .unwrap_or(plan.requested_codec)
+    });
+    let used_backend = plan.backend.or_else(|| encoder.map(Encoder::backend));
+
+    tracing::info!(
+        "Job {} execution path: requested_codec={}, planned_codec={}, encoder={:?}, backend={:?}, fallback={:?}",
+        job.id,
+        plan.requested_codec.as_str(),
+        planned_output_codec.as_str(),
+        encoder.map(|value| value.ffmpeg_encoder_name()),
+        used_backend.map(|value| value.as_str()),
+        plan.fallback
+            .as_ref()
+            .map(|fallback| fallback.reason.as_str())
+    );
+
+    let hdr10_plus_metadata =
+        if plan.preserve_hdr10_plus && encoder == Some(Encoder::HevcX265) && !dry_run {
+            let mut cancel = transcoder.register_cancel_channel(job.id);
+            let extracted =
+                extract_hdr10_plus(&input_path, &output_path, observer.as_ref(), &mut cancel).await;
+            transcoder.release_cancel_channel(job.id);
+            extracted?
+        } else {
+            None
+        };
+
+    let transcoded = transcoder
+        .transcode_media(TranscodeRequest {
+            job_id: Some(job.id),
+            input: &input_path,
+            output: &output_path,
+            hw_info,
+            dry_run,
+            metadata: &analysis.metadata,
+            plan,
+            observer: Some(observer.clone()),
+            clip_start_seconds: None,
+            clip_duration_seconds: None,
+            hdr10_plus_metadata: hdr10_plus_metadata.as_deref(),
+        })
+        .await;
+    if let Some(path) = &hdr10_plus_metadata {
+        let _ = tokio::fs::remove_file(path).await;
+    }
+    transcoded?;
+    if hdr10_plus_metadata.is_some()
+        && !crate::media::analyzer::probe_hdr10_plus(&output_path).await
+    {
+        observer
+            .on_log(
+                "HDR10+ metadata is missing from the output; this FFmpeg's libx265 was built \
+                 without HDR10+ support. The output keeps static HDR10 only."
+                    .to_string(),
+            )
+            .await;
+    }
+
+    if !plan.subtitles.sidecar_outputs().is_empty() {
+        transcoder
+            .extract_subtitles(TranscodeRequest {
                 job_id: Some(job.id),
                 input: &input_path,
                 output: &output_path,
-                hw_info: self.hw_info.as_ref(),
-                dry_run: self.dry_run,
+                hw_info,
+                dry_run,
                 metadata: &analysis.metadata,
                 plan,
-                observer: Some(observer.clone()),
+                observer: Some(observer),
                 clip_start_seconds: None,
                 clip_duration_seconds: None,
+                hdr10_plus_metadata: None,
             })
             .await?;
+    }
 
-        if matches!(
-            plan.subtitles,
-            crate::media::pipeline::SubtitleStreamPlan::Extract { .. }
-        ) {
-            self.transcoder
-                .extract_subtitles(TranscodeRequest {
-                    job_id: Some(job.id),
-                    input: &input_path,
-                    output: &output_path,
-                    hw_info: self.hw_info.as_ref(),
-                    dry_run: self.dry_run,
-                    metadata: &analysis.metadata,
-                    plan,
-                    observer: Some(observer),
-                    clip_start_seconds: None,
-                    clip_duration_seconds: None,
-                })
-                .await?;
-        }
-
-        let actual_probe = if !self.dry_run && output_path.exists() {
-            crate::media::analyzer::Analyzer::probe_output_details(&output_path)
-                .await
-                .ok()
-        } else {
-            None
-        };
-        let actual_output_codec = actual_probe
-            .as_ref()
-            .and_then(|probe| output_codec_from_name(&probe.codec_name));
-        let actual_encoder_name = actual_probe
+    let actual_probe = if !dry_run && output_path.exists() {
+        crate::media::analyzer::Analyzer::probe_output_details(&output_path)
+            .await
+            .ok()
+    } else {
+        None
+    };
+    let actual_output_codec = actual_probe
+        .as_ref()
+        .and_then(|probe| output_codec_from_name(&probe.codec_name));
+    let actual_encoder_name = actual_probe
+        .as_ref()
+        .and_then(|probe| {
+            probe
+                .stream_encoder_tag
+                .clone()
+                .or_else(|| probe.format_encoder_tag.clone())
+        })
+        .or_else(|| {
+            if plan.is_remux {
+                Some("copy".to_string())
+            } else {
+                encoder.map(|encoder| encoder.ffmpeg_encoder_name().to_string())
+            }
+        });
+    let codec_mismatch = actual_output_codec
+        .is_some_and(|actual_output_codec| actual_output_codec != planned_output_codec);
+    let encoder_mismatch = encoder.is_some_and(|encoder| {
+        actual_probe
             .as_ref()
-            .and_then(|probe| {
-                probe
-                    .stream_encoder_tag
-                    .clone()
-                    .or_else(|| probe.format_encoder_tag.clone())
-            })
-            .or_else(|| {
-                if plan.is_remux {
-                    Some("copy".to_string())
-                } else {
-                    encoder.map(|encoder| encoder.ffmpeg_encoder_name().to_string())
-                }
-            });
-        let codec_mismatch = actual_output_codec
-            .is_some_and(|actual_output_codec| actual_output_codec != planned_output_codec);
-        let encoder_mismatch = encoder.is_some_and(|encoder| {
+            .and_then(|probe| probe.stream_encoder_tag.as_deref())
+            .is_some_and(|tag| !encoder_tag_matches(encoder, tag))
+    });
+
+    if let (true, Some(codec)) = (codec_mismatch, actual_output_codec) {
+        tracing::warn!(
+            "Job {}: Planned codec {} but output probed as {}",
+            job.id,
+            planned_output_codec.as_str(),
+            codec.as_str()
+        );
+    }
+
+    if let (true, Some(enc)) = (encoder_mismatch, encoder) {
+        tracing::warn!(
+            "Job {}: Planned encoder {} but stream tag reported {:?}",
+            job.id,
+            enc.ffmpeg_encoder_name(),
             actual_probe
                 .as_ref()
                 .and_then(|probe| probe.stream_encoder_tag.as_deref())
-                .is_some_and(|tag| !encoder_tag_matches(encoder, tag))
-        });
+        );
+    }
 
-        if let (true, Some(codec)) = (codec_mismatch, actual_output_codec) {
-            tracing::warn!(
-                "Job {}: Planned codec {} but output probed as {}",
-                job.id,
-                planned_output_codec.as_str(),
-                codec.as_str()
-            );
-        }
+    tracing::info!(
+        "Job {} output probe: actual_codec={:?}, actual_encoder={:?}, fallback_occurred={}",
+        job.id,
+        actual_output_codec.map(|value| value.as_str()),
+        actual_encoder_name.as_deref(),
+        plan.fallback.is_some() || codec_mismatch || encoder_mismatch
+    );
+
+    Ok(ExecutionResult {
+        requested_codec: plan.requested_codec,
+        planned_output_codec,
+        requested_encoder: encoder,
+        used_encoder: encoder,
+        used_backend,
+        fallback: plan.fallback.clone(),
+        fallback_occurred: plan.fallback.is_some() || codec_mismatch || encoder_mismatch,
+        actual_output_codec,
+        actual_encoder_name,
+    })
+}
 
-        if let (true, Some(enc)) = (encoder_mismatch, encoder) {
-            tracing::warn!(
-                "Job {}: Planned encoder {} but stream tag reported {:?}",
-                job.id,
-                enc.ffmpeg_encoder_name(),
-                actual_probe
-                    .as_ref()
-                    .and_then(|probe| probe.stream_encoder_tag.as_deref())
-            );
+/// Write the source's HDR10+ metadata next to the output for x265. On
+/// failure the encode goes ahead with static HDR10 only, and says so; a
+/// cancelled job stops here.
+async fn extract_hdr10_plus(
+    input: &std::path::Path,
+    output: &std::path::Path,
+    observer: &dyn ExecutionObserver,
+    cancel: &mut tokio::sync::oneshot::Receiver<()>,
+) -> Result<Option<PathBuf>> {
+    let path = crate::media::hdr10_plus::metadata_path_for(output);
+    observer
+        .on_log("Extracting HDR10+ metadata...".to_string())
+        .await;
+    match crate::media::hdr10_plus::extract(input, &path, cancel).await {
+        Ok(frames) => {
+            observer
+                .on_log(format!("Extracted HDR10+ metadata for {frames} frames."))
+                .await;
+            Ok(Some(path))
+        }
+        Err(AlchemistError::Cancelled) => {
+            let _ = tokio::fs::remove_file(&path).await;
+            Err(AlchemistError::Cancelled)
+        }
+        Err(err) => {
+            let _ = tokio::fs::remove_file(&path).await;
+            observer
+                .on_log(format!(
+                    "HDR10+ metadata could not be extracted; the output keeps static HDR10 only: {err}"
+                ))
+                .await;
+            Ok(None)
         }
-
-        tracing::info!(
-            "Job {} output probe: actual_codec={:?}, actual_encoder={:?}, fallback_occurred={}",
-            job.id,
-            actual_output_codec.map(|value| value.as_str()),
-            actual_encoder_name.as_deref(),
-            plan.fallback.is_some() || codec_mismatch || encoder_mismatch
-        );
-
-        Ok(ExecutionResult {
-            requested_codec: plan.requested_codec,
-            planned_output_codec,
-            requested_encoder: encoder,
-            used_encoder: encoder,
-            used_backend,
-            fallback: plan.fallback.clone(),
-            fallback_occurred: plan.fallback.is_some() || codec_mismatch || encoder_mismatch,
-            actual_output_codec,
-            actual_encoder_name,
-        })
     }
 }
 
@@ -302,8 +400,8 @@ mod tests {
     use super::*;
     use crate::db::Db;
     use crate::media::pipeline::{
-        AnalysisConfidence, AudioStreamPlan, DynamicRange, Encoder, EncoderBackend, MediaMetadata,
-        RateControl, SubtitleStreamPlan, TranscodeDecision,
+        AnalysisConfidence, DynamicRange, Encoder, EncoderBackend, MediaMetadata, RateControl,
+        SubtitleStreamPlan, TranscodeDecision,
     };
     use crate::orchestrator::LocalExecutionObserver;
     use std::path::Path;
@@ -486,6 +584,7 @@ mod tests {
             audio_streams: Vec::new(),
             dynamic_range: DynamicRange::Sdr,
             chapter_count: 0,
+            alchemist_marker: None,
         };
         let analysis = MediaAnalysis {
             metadata,
@@ -508,12 +607,15 @@ mod tests {
             rate_control: Some(RateControl::Crf { value: 21 }),
             encoder_preset: Some("ultrafast".to_string()),
             threads: 1,
-            audio: AudioStreamPlan::Drop,
-            audio_stream_indices: None,
+            audio: Vec::new(),
             subtitles: SubtitleStreamPlan::Drop,
             filters: Vec::new(),
             allow_fallback: true,
             fallback: None,
+            preserve_dolby_vision: false,
+            preserve_hdr10_plus: false,
+            downgrades: Vec::new(),
+            marker: None,
         };
 
         let err = match executor.execute(&job, &plan, &analysis).await {
@@ -3,8 +3,8 @@
 
 use crate::error::{AlchemistError, Result};
 use crate::media::pipeline::{
-    AudioCodec, AudioStreamPlan, Encoder, FilterStep, RateControl, SubtitleStreamPlan,
-    TranscodePlan,
+    AudioCodec, AudioStreamPlan, AudioTrackPlan, DeinterlaceBackend, Encoder, FilterStep,
+    RateControl, SidecarOutputPlan, SubtitleStreamPlan, TranscodePlan,
 };
 use crate::system::hardware::{CommandRunner, HardwareInfo, SystemCommandRunner};
 use serde::{Deserialize, Serialize};
@@ -22,6 +22,10 @@ mod qsv;
 mod vaapi;
 mod videotoolbox;
 
+const DOLBY_VISION_DEFAULT_VBV_KBPS: u64 = 40_000;
+const DOLBY_VISION_MIN_VBV_KBPS: u64 = 10_000;
+const DOLBY_VISION_MAX_VBV_KBPS: u64 = 160_000;
+
 #[derive(Debug, Clone, Default)]
 pub struct HardwareAccelerators {
     pub available: HashSet<String>,
@@ -137,6 +141,7 @@ pub struct FFmpegCommandBuilder<'a> {
     hw_info: Option<&'a HardwareInfo>,
     clip_start_seconds: Option<f64>,
     clip_duration_seconds: Option<f64>,
+    hdr10_plus_metadata: Option<&'a Path>,
 }
 
 impl<'a> FFmpegCommandBuilder<'a> {
@@ -154,6 +159,7 @@ impl<'a> FFmpegCommandBuilder<'a> {
             hw_info: None,
             clip_start_seconds: None,
             clip_duration_seconds: None,
+            hdr10_plus_metadata: None,
         }
     }
 
@@ -172,6 +178,12 @@ impl<'a> FFmpegCommandBuilder<'a> {
         self
     }
 
+    /// x265 `dhdr10-info` file holding the source's HDR10+ metadata.
+    pub fn with_hdr10_plus_metadata(mut self, path: Option<&'a Path>) -> Self {
+        self.hdr10_plus_metadata = path;
+        self
+    }
+
     pub fn build(self) -> Result<tokio::process::Command> {
         // build_args() renders every operand as a lossy String (kept for the
         // flag-ordering unit tests). For the spawned process, substitute the
@@ -203,7 +215,7 @@ impl<'a> FFmpegCommandBuilder<'a> {
 
     pub fn build_args(&self) -> Result<Vec<String>> {
         if self.plan.is_remux {
-            return Ok(vec![
+            let mut args = vec![
                 "-v".to_string(),
                 "error".to_string(),
                 "-i".to_string(),
@@ -212,9 +224,15 @@ impl<'a> FFmpegCommandBuilder<'a> {
                 "copy".to_string(),
                 "-map".to_string(),
                 "0".to_string(),
-                "-y".to_string(),
-                self.output.display().to_string(),
-            ]);
+            ];
+            apply_marker(&mut args, self.plan);
+            if self.plan.marker.is_some() && uses_quicktime_container(&self.plan.container) {
+                args.push("-movflags".to_string());
+                args.push("+use_metadata_tags".to_string());
+            }
+            args.push("-y".to_string());
+            args.push(self.output.display().to_string());
+            return Ok(args);
         }
 
         let rate_control = self.plan.rate_control.clone();
@@ -242,23 +260,25 @@ impl<'a> FFmpegCommandBuilder<'a> {
         args.push("-map".to_string());
         args.push("0:v:0".to_string());
 
-        if !matches!(self.plan.audio, AudioStreamPlan::Drop) {
-            match &self.plan.audio_stream_indices {
-                None => {
+        for track in self.plan.audio.iter().filter(|track| track.is_output()) {
+            args.push("-map".to_string());
+            match track.source_index {
+                Some(index) => args.push(format!("0:a:{index}")),
+                None => args.push("0:a?".to_string()),
+            }
+        }
+        match &self.plan.subtitles {
+            SubtitleStreamPlan::CopyAllCompatible => {
+                args.push("-map".to_string());
+                args.push("0:s?".to_string());
+            }
+            SubtitleStreamPlan::Select { streams, .. } => {
+                for stream in streams {
                     args.push("-map".to_string());
-                    args.push("0:a?".to_string());
-                }
-                Some(indices) => {
-                    for &index in indices {
-                        args.push("-map".to_string());
-                        args.push(format!("0:a:{index}"));
-                    }
+                    args.push(format!("0:s:{}", stream.stream_index));
                 }
             }
-        }
-        if matches!(self.plan.subtitles, SubtitleStreamPlan::CopyAllCompatible) {
-            args.push("-map".to_string());
-            args.push("0:s?".to_string());
+            _ => {}
         }
 
         if self.plan.copy_video {
@@ -337,10 +357,22 @@ impl<'a> FFmpegCommandBuilder<'a> {
         apply_audio_plan(&mut args, &self.plan.audio);
         apply_subtitle_plan(&mut args, &self.plan.subtitles);
         apply_color_metadata(&mut args, self.metadata, &self.plan.filters);
+        apply_dynamic_hdr_metadata(
+            &mut args,
+            self.plan,
+            self.metadata,
+            self.hdr10_plus_metadata,
+        );
+        apply_marker(&mut args, self.plan);
 
         if uses_quicktime_container(&self.plan.container) {
             args.push("-movflags".to_string());
-            args.push("+faststart".to_string());
+            // The MP4 muxer drops tags it does not know unless asked to keep them.
+            args.push(if self.plan.marker.is_some() {
+                "+faststart+use_metadata_tags".to_string()
+            } else {
+                "+faststart".to_string()
+            });
         }
 
         args.push("-f".to_string());
@@ -350,9 +382,7 @@ impl<'a> FFmpegCommandBuilder<'a> {
     }
 
     pub fn build_subtitle_extract_args(&self) -> Result<Option<Vec<String>>> {
-        let SubtitleStreamPlan::Extract { outputs } = &self.plan.subtitles else {
-            return Ok(None);
-        };
+        let outputs = self.plan.subtitles.sidecar_outputs();
         if outputs.is_empty() {
             return Ok(None);
         }
@@ -371,7 +401,7 @@ impl<'a> FFmpegCommandBuilder<'a> {
             args.push("-c:s".to_string());
             args.push(sidecar_output.codec.clone());
             args.push("-f".to_string());
-            args.push(sidecar_output.codec.clone());
+            args.push(sidecar_muxer(sidecar_output).to_string());
             args.push(sidecar_output.temp_path.display().to_string());
         }
 
@@ -382,9 +412,7 @@ impl<'a> FFmpegCommandBuilder<'a> {
     /// non-UTF-8 input/sidecar filenames survive. Mirrors the operand order of
     /// [`Self::build_subtitle_extract_args`], which is retained for unit tests.
     pub fn build_subtitle_extract(&self) -> Result<Option<tokio::process::Command>> {
-        let SubtitleStreamPlan::Extract { outputs } = &self.plan.subtitles else {
-            return Ok(None);
-        };
+        let outputs = self.plan.subtitles.sidecar_outputs();
         if outputs.is_empty() {
             return Ok(None);
         }
@@ -402,7 +430,7 @@ impl<'a> FFmpegCommandBuilder<'a> {
                 .arg("-c:s")
                 .arg(&sidecar_output.codec)
                 .arg("-f")
-                .arg(&sidecar_output.codec)
+                .arg(sidecar_muxer(sidecar_output))
                 .arg(sidecar_output.temp_path.as_os_str());
         }
 
@@ -411,6 +439,13 @@ impl<'a> FFmpegCommandBuilder<'a> {
     }
 }
 
+fn sidecar_muxer(sidecar_output: &SidecarOutputPlan) -> &str {
+    sidecar_output
+        .muxer
+        .as_deref()
+        .unwrap_or(&sidecar_output.codec)
+}
+
 fn default_quality(rate_control: &Option<RateControl>, fallback: u8) -> u8 {
     match rate_control {
         Some(RateControl::Cq { value }) => *value,
@@ -421,32 +456,97 @@ fn default_quality(rate_control: &Option<RateControl>, fallback: u8) -> u8 {
     }
 }
 
-fn apply_audio_plan(args: &mut Vec<String>, plan: &AudioStreamPlan) {
-    match plan {
+fn apply_audio_plan(args: &mut Vec<String>, plan: &[AudioTrackPlan]) {
+    let outputs: Vec<&AudioTrackPlan> = plan.iter().filter(|track| track.is_output()).collect();
+    let Some(first) = outputs.first() else {
+        args.push("-an".to_string());
+        return;
+    };
+
+    // Tracks that all share one action keep the short, stream-wide form.
+    let uniform = outputs.iter().all(|track| {
+        track.action == first.action
+            && !matches!(track.action, AudioStreamPlan::DeriveDownmix { .. })
+    });
+    if uniform {
+        apply_audio_action(args, &first.action, "a");
+        return;
+    }
+
+    for (output_index, track) in outputs.iter().enumerate() {
+        apply_audio_action(args, &track.action, &format!("a:{output_index}"));
+        if matches!(track.action, AudioStreamPlan::DeriveDownmix { .. }) {
+            let disposition = if track.default { "default" } else { "0" };
+            args.extend([
+                format!("-disposition:a:{output_index}"),
+                disposition.to_string(),
+                format!("-metadata:s:a:{output_index}"),
+                "title=Stereo".to_string(),
+            ]);
+            if let Some(language) = &track.language {
+                args.extend([
+                    format!("-metadata:s:a:{output_index}"),
+                    format!("language={language}"),
+                ]);
+            }
+        }
+    }
+}
+
+fn apply_audio_action(args: &mut Vec<String>, action: &AudioStreamPlan, specifier: &str) {
+    match action {
         AudioStreamPlan::Copy => {
-            args.extend(["-c:a".to_string(), "copy".to_string()]);
+            args.extend([format!("-c:{specifier}"), "copy".to_string()]);
         }
         AudioStreamPlan::Transcode {
             codec,
             bitrate_kbps,
             channels,
+            loudness,
         } => {
             args.extend([
-                "-c:a".to_string(),
+                format!("-c:{specifier}"),
                 codec.ffmpeg_name().to_string(),
-                "-b:a".to_string(),
+                format!("-b:{specifier}"),
                 format!("{bitrate_kbps}k"),
             ]);
             if let Some(channels) = channels {
-                args.extend(["-ac".to_string(), channels.to_string()]);
+                args.extend([format!("-ac:{specifier}"), channels.to_string()]);
+            }
+            if let Some(filter) = loudness
+                .as_ref()
+                .and_then(crate::media::loudness::second_pass_filter)
+            {
+                // loudnorm resamples to 192 kHz internally.
+                args.extend([
+                    format!("-filter:{specifier}"),
+                    filter,
+                    format!("-ar:{specifier}"),
+                    crate::media::loudness::OUTPUT_SAMPLE_RATE.to_string(),
+                ]);
             }
             if matches!(codec, AudioCodec::Aac) {
-                args.extend(["-profile:a".to_string(), "aac_low".to_string()]);
+                args.extend([format!("-profile:{specifier}"), "aac_low".to_string()]);
             }
         }
-        AudioStreamPlan::Drop => {
-            args.push("-an".to_string());
+        AudioStreamPlan::DeriveDownmix {
+            codec,
+            bitrate_kbps,
+            channels,
+        } => {
+            args.extend([
+                format!("-c:{specifier}"),
+                codec.ffmpeg_name().to_string(),
+                format!("-b:{specifier}"),
+                format!("{bitrate_kbps}k"),
+                format!("-ac:{specifier}"),
+                channels.to_string(),
+            ]);
+            if matches!(codec, AudioCodec::Aac) {
+                args.extend([format!("-profile:{specifier}"), "aac_low".to_string()]);
+            }
         }
+        AudioStreamPlan::Drop => {}
     }
 }
 
@@ -455,14 +555,38 @@ fn apply_subtitle_plan(args: &mut Vec<String>, plan: &SubtitleStreamPlan) {
         SubtitleStreamPlan::CopyAllCompatible => {
             args.extend(["-c:s".to_string(), "copy".to_string()]);
         }
+        SubtitleStreamPlan::Select { streams, .. } if !streams.is_empty() => {
+            for (output_index, stream) in streams.iter().enumerate() {
+                args.extend([
+                    format!("-c:s:{output_index}"),
+                    stream
+                        .convert_to
+                        .clone()
+                        .unwrap_or_else(|| "copy".to_string()),
+                ]);
+            }
+        }
         SubtitleStreamPlan::Drop
         | SubtitleStreamPlan::Burn { .. }
-        | SubtitleStreamPlan::Extract { .. } => {
+        | SubtitleStreamPlan::Extract { .. }
+        | SubtitleStreamPlan::Select { .. } => {
             args.push("-sn".to_string());
         }
     }
 }
 
+/// Tag the output with the plan's processed-by marker.
+fn apply_marker(args: &mut Vec<String>, plan: &TranscodePlan) {
+    if let Some(marker) = &plan.marker {
+        args.push("-metadata".to_string());
+        args.push(format!(
+            "{}={}",
+            crate::media::marker::TAG,
+            marker.tag_value()
+        ));
+    }
+}
+
 fn apply_color_metadata(
     args: &mut Vec<String>,
     metadata: &crate::media::pipeline::MediaMetadata,
@@ -517,6 +641,48 @@ fn apply_color_metadata(
     }
 }
 
+/// Encoder switches for Dolby Vision RPUs and HDR10+ dynamic metadata. The
+/// planner only sets these flags for encoders that can carry them; x265
+/// options share a single `-x265-params` list because FFmpeg keeps only one.
+/// HDR10+ reaches x265 only through the extracted `dhdr10-info` file, since
+/// FFmpeg does not forward the decoded side data.
+fn apply_dynamic_hdr_metadata(
+    args: &mut Vec<String>,
+    plan: &TranscodePlan,
+    metadata: &crate::media::pipeline::MediaMetadata,
+    hdr10_plus_metadata: Option<&Path>,
+) {
+    let mut x265_params = Vec::new();
+    if plan.preserve_dolby_vision {
+        // libx265 refuses RPU coding without VBV, so bound the rate near the
+        // source bitrate and keep the buffer at one second.
+        let maxrate_kbps = metadata
+            .video_bitrate_bps
+            .map(|bps| bps * 3 / 2 / 1000)
+            .unwrap_or(DOLBY_VISION_DEFAULT_VBV_KBPS)
+            .clamp(DOLBY_VISION_MIN_VBV_KBPS, DOLBY_VISION_MAX_VBV_KBPS);
+        args.extend(["-dolbyvision".to_string(), "1".to_string()]);
+        x265_params.push(format!("vbv-maxrate={maxrate_kbps}"));
+        x265_params.push(format!("vbv-bufsize={maxrate_kbps}"));
+    }
+    if plan.preserve_hdr10_plus
+        && plan.encoder == Some(Encoder::HevcX265)
+        && let Some(path) = hdr10_plus_metadata
+    {
+        x265_params.extend([
+            "hdr10=1".to_string(),
+            "hdr10-opt=1".to_string(),
+            format!(
+                "dhdr10-info={}",
+                crate::media::hdr10_plus::escape_x265_param(&path.to_string_lossy())
+            ),
+        ]);
+    }
+    if !x265_params.is_empty() {
+        args.extend(["-x265-params".to_string(), x265_params.join(":")]);
+    }
+}
+
 fn render_filtergraph(input: &Path, filters: &[FilterStep]) -> Option<String> {
     if filters.is_empty() {
         return None;
@@ -533,15 +699,51 @@ fn render_filtergraph(input: &Path, filters: &[FilterStep]) -> Option<String> {
                 "zscale=t=linear:npl={peak},tonemap=tonemap={}:desat={desat},zscale=p=bt709:t=bt709:m=bt709:r=tv,format=yuv420p,setparams=color_primaries=bt709:color_trc=bt709:colorspace=bt709:range=tv",
                 algorithm.as_str()
             ),
+            FilterStep::StripDolbyVision => {
+                "sidedata=mode=delete:type=DOVI_RPU_BUFFER,sidedata=mode=delete:type=DOVI_METADATA"
+                    .to_string()
+            }
             FilterStep::Format { pixel_format } => format!("format={pixel_format}"),
             FilterStep::SubtitleBurn { stream_index } => format!(
                 "subtitles=filename='{}':si={stream_index}",
                 escape_filter_path(input)
             ),
             FilterStep::HwUpload => "hwupload".to_string(),
+            FilterStep::HwUploadCuda => "hwupload_cuda".to_string(),
+            FilterStep::Deinterlace {
+                algorithm,
+                rate,
+                backend,
+            } => match backend {
+                DeinterlaceBackend::Software => format!(
+                    "{}=mode={}:parity=auto:deint=all",
+                    algorithm.as_str(),
+                    rate.as_str()
+                ),
+                DeinterlaceBackend::Vaapi => {
+                    format!("deinterlace_vaapi=rate={}", rate.hardware_rate())
+                }
+                DeinterlaceBackend::Qsv => format!(
+                    "vpp_qsv=deinterlace=advanced:rate={}",
+                    rate.hardware_rate()
+                ),
+            },
+            FilterStep::Crop {
+                width,
+                height,
+                x,
+                y,
+            } => format!("crop={width}:{height}:{x}:{y}"),
             FilterStep::Scale { width, height } => {
-                format!("scale=w={width}:h={height}:force_original_aspect_ratio=decrease")
+                format!(
+                    "scale=w={width}:h={height}:force_original_aspect_ratio=decrease:force_divisible_by=2"
+                )
             }
+            FilterStep::HwScale {
+                scaler,
+                width,
+                height,
+            } => format!("{}=w={width}:h={height}", scaler.filter_name()),
             FilterStep::StripHdrMetadata => {
                 "setparams=color_primaries=bt709:color_trc=bt709:colorspace=bt709:range=tv"
                     .to_string()
@@ -737,11 +939,40 @@ pub struct QualityScore {
 impl QualityScore {
     pub fn compute(original: &Path, encoded: &Path) -> Result<Self> {
         info!("Computing quality metrics for {:?}", encoded);
+        Self::run_vmaf(original, encoded, None)
+    }
+
+    /// Score a clip encoded with `FFmpegCommandBuilder::with_clip` against the
+    /// matching window of the original. The reference is input-seeked so both
+    /// sides start on the same frame.
+    pub fn compute_clip(
+        original: &Path,
+        encoded: &Path,
+        clip_start_seconds: f64,
+        clip_duration_seconds: f64,
+    ) -> Result<Self> {
+        info!(
+            "Computing quality metrics for {:?} ({:.1}s @ {:.1}s)",
+            encoded, clip_duration_seconds, clip_start_seconds
+        );
+        Self::run_vmaf(
+            original,
+            encoded,
+            Some((clip_start_seconds, clip_duration_seconds)),
+        )
+    }
 
-        let output = Command::new("ffmpeg")
-            .arg("-hide_banner")
-            .arg("-i")
-            .arg(encoded)
+    fn run_vmaf(original: &Path, encoded: &Path, window: Option<(f64, f64)>) -> Result<Self> {
+        let mut command = Command::new("ffmpeg");
+        command.arg("-hide_banner").arg("-i").arg(encoded);
+        if let Some((start, duration)) = window {
+            command
+                .arg("-ss")
+                .arg(format!("{start:.3}"))
+                .arg("-t")
+                .arg(format!("{duration:.3}"));
+        }
+        let output = command
             .arg("-i")
             .arg(original)
             .arg("-lavfi")
@@ -922,6 +1153,7 @@ mod tests {
             audio_streams: Vec::new(),
             dynamic_range: DynamicRange::Hdr10,
             chapter_count: 0,
+            alchemist_marker: None,
         }
     }
 
@@ -967,12 +1199,15 @@ mod tests {
                 _ => "".to_string(),
             }),
             threads: 0,
-            audio: AudioStreamPlan::Copy,
-            audio_stream_indices: None,
+            audio: vec![AudioTrackPlan::all_streams(AudioStreamPlan::Copy)],
             subtitles: SubtitleStreamPlan::CopyAllCompatible,
             filters,
             allow_fallback: true,
             fallback: None,
+            preserve_dolby_vision: false,
+            preserve_hdr10_plus: false,
+            downgrades: Vec::new(),
+            marker: None,
         }
     }
 
@@ -1223,6 +1458,93 @@ mod tests {
         assert!(!args.contains(&"hvc1".to_string()));
     }
 
+    #[test]
+    fn dolby_vision_strip_and_preserve_args() {
+        let mut metadata = metadata();
+        metadata.video_bitrate_bps = Some(20_000_000);
+        let mut plan = plan_for(Encoder::HevcX265);
+        plan.filters = vec![FilterStep::StripDolbyVision];
+        let builder = FFmpegCommandBuilder::new(
+            Path::new("/tmp/in.mkv"),
+            Path::new("/tmp/out.mkv"),
+            &metadata,
+            &plan,
+        );
+        let args = builder
+            .build_args()
+            .unwrap_or_else(|err| panic!("failed to build strip args: {err}"));
+        assert!(args.contains(
+            &"sidedata=mode=delete:type=DOVI_RPU_BUFFER,sidedata=mode=delete:type=DOVI_METADATA"
+                .to_string()
+        ));
+        assert!(!args.contains(&"-dolbyvision".to_string()));
+
+        plan.filters.clear();
+        plan.preserve_dolby_vision = true;
+        let builder = FFmpegCommandBuilder::new(
+            Path::new("/tmp/in.mkv"),
+            Path::new("/tmp/out.mkv"),
+            &metadata,
+            &plan,
+        );
+        let args = builder
+            .build_args()
+            .unwrap_or_else(|err| panic!("failed to build preserve args: {err}"));
+        let dv_index = args
+            .iter()
+            .position(|arg| arg == "-dolbyvision")
+            .unwrap_or_else(|| panic!("missing -dolbyvision in {args:?}"));
+        assert_eq!(args[dv_index + 1], "1");
+        assert!(args.contains(&"vbv-maxrate=30000:vbv-bufsize=30000".to_string()));
+    }
+
+    #[test]
+    fn hdr10_plus_passthrough_args_per_encoder() {
+        let metadata = metadata();
+        let build = |plan: &TranscodePlan, hdr10_plus: Option<&Path>| {
+            FFmpegCommandBuilder::new(
+                Path::new("/tmp/in.mkv"),
+                Path::new("/tmp/out.mkv"),
+                &metadata,
+                plan,
+            )
+            .with_hdr10_plus_metadata(hdr10_plus)
+            .build_args()
+            .unwrap_or_else(|err| panic!("failed to build hdr10+ args: {err}"))
+        };
+        let x265_params = |args: &[String]| {
+            args.iter()
+                .position(|arg| arg == "-x265-params")
+                .map(|index| args[index + 1].clone())
+        };
+
+        let mut x265 = plan_for(Encoder::HevcX265);
+        x265.preserve_hdr10_plus = true;
+        x265.preserve_dolby_vision = true;
+        let json = Path::new("/tmp/out.mkv.hdr10plus.json");
+        let args = build(&x265, Some(json));
+        assert_eq!(
+            x265_params(&args).as_deref(),
+            Some(
+                "vbv-maxrate=12000:vbv-bufsize=12000:hdr10=1:hdr10-opt=1:dhdr10-info=/tmp/out.mkv.hdr10plus.json"
+            )
+        );
+        assert_eq!(args.iter().filter(|arg| *arg == "-x265-params").count(), 1);
+
+        // Without extracted metadata there is nothing to pass through.
+        let args = build(&x265, None);
+        assert_eq!(
+            x265_params(&args).as_deref(),
+            Some("vbv-maxrate=12000:vbv-bufsize=12000")
+        );
+
+        let mut svt = plan_for(Encoder::Av1Svt);
+        svt.preserve_hdr10_plus = true;
+        let args = build(&svt, Some(json));
+        assert!(!args.contains(&"-svtav1-params".to_string()));
+        assert!(!args.iter().any(|arg| arg.contains("dhdr10-info")));
+    }
+
     #[test]
     fn hevc_x265_mp4_adds_hvc1_tag() {
         let metadata = metadata();
@@ -1276,6 +1598,55 @@ mod tests {
         assert!(args.windows(2).any(|window| window == ["-qp_p", "19"]));
     }
 
+    #[test]
+    fn crop_renders_ahead_of_hwupload_for_hardware_backends() {
+        for encoder in [Encoder::HevcVaapi, Encoder::HevcQsv, Encoder::HevcX265] {
+            let mut plan = plan_for(encoder);
+            plan.filters.insert(
+                0,
+                FilterStep::Crop {
+                    width: 1920,
+                    height: 800,
+                    x: 0,
+                    y: 140,
+                },
+            );
+            let Some(graph) = render_filtergraph(Path::new("/tmp/in.mkv"), &plan.filters) else {
+                panic!("expected a filtergraph for {encoder:?}");
+            };
+            assert!(graph.starts_with("crop=1920:800:0:140,"));
+            if encoder.backend() == crate::media::pipeline::EncoderBackend::Vaapi {
+                assert!(graph.ends_with("format=nv12,hwupload"));
+            }
+        }
+    }
+
+    #[test]
+    fn deinterlace_renders_per_backend() {
+        let render = |backend| {
+            render_filtergraph(
+                Path::new("/tmp/in.mkv"),
+                &[FilterStep::Deinterlace {
+                    algorithm: crate::config::DeinterlaceAlgorithm::Yadif,
+                    rate: crate::config::DeinterlaceRate::SendField,
+                    backend,
+                }],
+            )
+        };
+        assert_eq!(
+            render(DeinterlaceBackend::Software).as_deref(),
+            Some("yadif=mode=send_field:parity=auto:deint=all")
+        );
+        assert_eq!(
+            render(DeinterlaceBackend::Vaapi).as_deref(),
+            Some("deinterlace_vaapi=rate=field")
+        );
+        assert_eq!(
+            render(DeinterlaceBackend::Qsv).as_deref(),
+            Some("vpp_qsv=deinterlace=advanced:rate=field")
+        );
+    }
+
     #[test]
     fn clip_window_adds_trim_arguments() {
         let metadata = metadata();
@@ -1297,11 +1668,12 @@ mod tests {
     fn mp4_audio_transcode_uses_aac_profile() {
         let mut plan = plan_for(Encoder::H264X264);
         plan.container = "mp4".to_string();
-        plan.audio = AudioStreamPlan::Transcode {
+        plan.audio = vec![AudioTrackPlan::all_streams(AudioStreamPlan::Transcode {
             codec: AudioCodec::Aac,
             bitrate_kbps: 192,
             channels: None,
-        };
+            loudness: None,
+        })];
         plan.requested_codec = OutputCodec::H264;
         let metadata = metadata();
         let builder = FFmpegCommandBuilder::new(
@@ -1347,12 +1719,14 @@ mod tests {
                 SidecarOutputPlan {
                     stream_index: 0,
                     codec: "srt".to_string(),
+                    muxer: None,
                     final_path: Path::new("/tmp/out.eng.srt").to_path_buf(),
                     temp_path: Path::new("/tmp/out.eng.srt.alchemist-part").to_path_buf(),
                 },
                 SidecarOutputPlan {
                     stream_index: 1,
                     codec: "ass".to_string(),
+                    muxer: None,
                     final_path: Path::new("/tmp/out.jpn.ass").to_path_buf(),
                     temp_path: Path::new("/tmp/out.jpn.ass.alchemist-part").to_path_buf(),
                 },
@@ -1376,6 +1750,60 @@ mod tests {
         assert!(args.contains(&"/tmp/out.jpn.ass.alchemist-part".to_string()));
     }
 
+    #[test]
+    fn selected_subtitles_convert_per_stream_and_extract_image_sidecars() {
+        let metadata = metadata();
+        let mut plan = plan_for(Encoder::H264X264);
+        plan.container = "mp4".to_string();
+        plan.subtitles = SubtitleStreamPlan::Select {
+            streams: vec![
+                crate::media::pipeline::SubtitleTrackPlan {
+                    stream_index: 0,
+                    convert_to: Some("mov_text".to_string()),
+                },
+                crate::media::pipeline::SubtitleTrackPlan {
+                    stream_index: 2,
+                    convert_to: None,
+                },
+            ],
+            sidecars: vec![SidecarOutputPlan {
+                stream_index: 1,
+                codec: "copy".to_string(),
+                muxer: Some("matroska".to_string()),
+                final_path: Path::new("/tmp/out.eng.mks").to_path_buf(),
+                temp_path: Path::new("/tmp/out.eng.mks.alchemist-part").to_path_buf(),
+            }],
+        };
+        let builder = FFmpegCommandBuilder::new(
+            Path::new("/tmp/in.mkv"),
+            Path::new("/tmp/out.mp4"),
+            &metadata,
+            &plan,
+        );
+        let args = builder
+            .build_args()
+            .unwrap_or_else(|err| panic!("failed to build subtitle args: {err}"));
+        assert!(args.windows(2).any(|window| window == ["-map", "0:s:0"]));
+        assert!(args.windows(2).any(|window| window == ["-map", "0:s:2"]));
+        assert!(
+            args.windows(2)
+                .any(|window| window == ["-c:s:0", "mov_text"])
+        );
+        assert!(args.windows(2).any(|window| window == ["-c:s:1", "copy"]));
+        assert!(!args.contains(&"0:s?".to_string()));
+
+        let extract_args = builder
+            .build_subtitle_extract_args()
+            .unwrap_or_else(|err| panic!("failed to build sidecar args: {err}"))
+            .unwrap_or_else(|| panic!("expected sidecar args"));
+        assert!(
+            extract_args
+                .windows(2)
+                .any(|window| window == ["-f", "matroska"])
+        );
+        assert!(extract_args.contains(&"/tmp/out.eng.mks.alchemist-part".to_string()));
+    }
+
     #[test]
     fn remux_command_uses_stream_copy_without_encoder_args() {
         let metadata = metadata();
@@ -1411,11 +1839,60 @@ mod tests {
         );
     }
 
+    #[test]
+    fn outputs_carry_the_processed_by_marker() {
+        let metadata = metadata();
+        let mut plan = plan_for(Encoder::H264X264);
+        plan.container = "mp4".to_string();
+        plan.marker = Some(crate::media::marker::ProcessedMarker::new(
+            &metadata,
+            Some("Balanced"),
+            "a1b2c3d4e5f60718".to_string(),
+        ));
+        let tag = |args: &[String]| {
+            args.iter()
+                .position(|arg| arg == "-metadata")
+                .and_then(|index| args.get(index + 1))
+                .cloned()
+                .unwrap_or_default()
+        };
+
+        let args = FFmpegCommandBuilder::new(
+            Path::new("/tmp/in.mkv"),
+            Path::new("/tmp/out.mp4"),
+            &metadata,
+            &plan,
+        )
+        .build_args()
+        .unwrap_or_else(|err| panic!("failed to build marked args: {err}"));
+        assert!(tag(&args).starts_with("ALCHEMIST_JOB=v=1;version="));
+        assert!(tag(&args).contains(";profile=Balanced;"));
+        assert!(args.contains(&"+faststart+use_metadata_tags".to_string()));
+
+        plan.is_remux = true;
+        let args = FFmpegCommandBuilder::new(
+            Path::new("/tmp/in.mkv"),
+            Path::new("/tmp/out.mp4"),
+            &metadata,
+            &plan,
+        )
+        .build_args()
+        .unwrap_or_else(|err| panic!("failed to build marked remux args: {err}"));
+        assert!(tag(&args).ends_with(";settings=a1b2c3d4e5f60718"));
+        assert_eq!(args.last().map(String::as_str), Some("/tmp/out.mp4"));
+    }
+
     #[test]
     fn selected_audio_streams_map_only_requested_indices() {
         let metadata = metadata();
         let mut plan = plan_for(Encoder::H264X264);
-        plan.audio_stream_indices = Some(vec![0, 2]);
+        plan.audio = [0, 2]
+            .into_iter()
+            .map(|index| AudioTrackPlan {
+                source_index: Some(index),
+                ..AudioTrackPlan::all_streams(AudioStreamPlan::Copy)
+            })
+            .collect();
         let builder = FFmpegCommandBuilder::new(
             Path::new("/tmp/in.mkv"),
             Path::new("/tmp/out.mkv"),
@@ -1430,6 +1907,116 @@ mod tests {
         assert!(!args.contains(&"0:a?".to_string()));
     }
 
+    #[test]
+    fn derived_stereo_track_gets_per_stream_args_and_dispositions() {
+        let metadata = metadata();
+        let mut plan = plan_for(Encoder::H264X264);
+        plan.audio = vec![
+            AudioTrackPlan {
+                source_index: Some(0),
+                action: AudioStreamPlan::Copy,
+                default: true,
+                language: Some("eng".to_string()),
+            },
+            AudioTrackPlan {
+                source_index: Some(0),
+                action: AudioStreamPlan::DeriveDownmix {
+                    codec: AudioCodec::Aac,
+                    bitrate_kbps: 160,
+                    channels: 2,
+                },
+                default: false,
+                language: Some("eng".to_string()),
+            },
+            AudioTrackPlan {
+                source_index: Some(1),
+                action: AudioStreamPlan::Drop,
+                default: false,
+                language: None,
+            },
+        ];
+        let builder = FFmpegCommandBuilder::new(
+            Path::new("/tmp/in.mkv"),
+            Path::new("/tmp/out.mkv"),
+            &metadata,
+            &plan,
+        );
+        let args = builder
+            .build_args()
+            .unwrap_or_else(|err| panic!("failed to build derived audio args: {err}"));
+
+        let maps: Vec<&String> = args
+            .windows(2)
+            .filter(|window| window[0] == "-map")
+            .map(|window| &window[1])
+            .collect();
+        assert_eq!(maps, ["0:v:0", "0:a:0", "0:a:0", "0:s?"]);
+        for expected in [
+            ["-c:a:0", "copy"],
+            ["-c:a:1", "aac"],
+            ["-b:a:1", "160k"],
+            ["-ac:a:1", "2"],
+            ["-disposition:a:1", "0"],
+            ["-metadata:s:a:1", "language=eng"],
+        ] {
+            assert!(
+                args.windows(2).any(|window| window == expected),
+                "missing {expected:?} in {args:?}"
+            );
+        }
+        assert!(!args.contains(&"-an".to_string()));
+    }
+
+    #[test]
+    fn measured_loudness_filters_only_the_reencoded_track() {
+        let metadata = metadata();
+        let mut plan = plan_for(Encoder::H264X264);
+        let mut loudness = crate::media::pipeline::LoudnessNormalization::new(-23.0);
+        loudness.measured = Some(crate::media::pipeline::LoudnessMeasurement {
+            input_i: -30.0,
+            input_tp: -6.0,
+            input_lra: 9.0,
+            input_thresh: -41.0,
+            target_offset: 0.4,
+        });
+        plan.audio = vec![
+            AudioTrackPlan {
+                source_index: Some(0),
+                action: AudioStreamPlan::Copy,
+                default: true,
+                language: None,
+            },
+            AudioTrackPlan {
+                source_index: Some(1),
+                action: AudioStreamPlan::Transcode {
+                    codec: AudioCodec::Aac,
+                    bitrate_kbps: 192,
+                    channels: None,
+                    loudness: Some(loudness),
+                },
+                default: false,
+                language: None,
+            },
+        ];
+        let builder = FFmpegCommandBuilder::new(
+            Path::new("/tmp/in.mkv"),
+            Path::new("/tmp/out.mkv"),
+            &metadata,
+            &plan,
+        );
+        let args = builder
+            .build_args()
+            .unwrap_or_else(|err| panic!("failed to build loudness args: {err}"));
+
+        let filter_index = args
+            .iter()
+            .position(|arg| arg == "-filter:a:1")
+            .unwrap_or_else(|| panic!("missing loudnorm filter in {args:?}"));
+        assert!(args[filter_index + 1].contains("measured_I=-30.00"));
+        assert!(args.windows(2).any(|window| window == ["-ar:a:1", "48000"]));
+        assert!(!args.iter().any(|arg| arg == "-filter:a:0" || arg == "-af"));
+    }
+
     #[test]
     fn encoder_capabilities_detect_with_runner_parses_video_and_audio_encoders() {
         let runner = FakeRunner {
@@ -0,0 +1,123 @@
+//! Path globs shared by profile rules, scan exclusions, and hooks.
+//!
+//! `*` and `?` never cross a `/`, `**` matches across directories, and `**/`
+//! also matches zero directories. Matching walks the text once while tracking
+//! every pattern position still alive, so a pattern full of stars cannot make
+//! it backtrack.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    /// `?`: one character other than `/`.
+    AnyChar,
+    /// `*`: any run of characters other than `/`.
+    Star,
+    /// `**`: any run of characters.
+    DoubleStar,
+}
+
+/// Match `text` against a glob where `*` and `?` never cross a `/` and `**`
+/// matches any number of directories.
+pub(crate) fn glob_matches(pattern: &str, text: &str) -> bool {
+    let tokens = tokenize(pattern);
+    let mut states = vec![false; tokens.len() + 1];
+    enter(&tokens, &mut states, 0);
+    for c in text.chars() {
+        let mut next = vec![false; tokens.len() + 1];
+        for (position, token) in tokens.iter().enumerate() {
+            if !states[position] {
+                continue;
+            }
+            match token {
+                Token::Literal(literal) if *literal == c => enter(&tokens, &mut next, position + 1),
+                Token::AnyChar if c != '/' => enter(&tokens, &mut next, position + 1),
+                Token::Star if c != '/' => {
+                    next[position] = true;
+                    enter(&tokens, &mut next, position + 1);
+                }
+                Token::DoubleStar => {
+                    next[position] = true;
+                    enter(&tokens, &mut next, position + 1);
+                }
+                _ => {}
+            }
+        }
+        if !next.contains(&true) {
+            return false;
+        }
+        states = next;
+    }
+    states[tokens.len()]
+}
+
+fn tokenize(pattern: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            '*' if chars.next_if_eq(&'*').is_some() => Token::DoubleStar,
+            '*' => Token::Star,
+            '?' => Token::AnyChar,
+            literal => Token::Literal(literal),
+        });
+    }
+    tokens
+}
+
+/// Mark `start` live, plus every position reachable from it without
+/// consuming text: past a star that matches nothing, and past the `/` of a
+/// `**/` that matches zero directories.
+fn enter(tokens: &[Token], states: &mut [bool], start: usize) {
+    let mut visited = vec![false; states.len()];
+    let mut pending = vec![start];
+    while let Some(position) = pending.pop() {
+        if std::mem::replace(&mut visited[position], true) {
+            continue;
+        }
+        states[position] = true;
+        match tokens.get(position) {
+            Some(Token::Star) => pending.push(position + 1),
+            Some(Token::DoubleStar) => {
+                pending.push(position + 1);
+                if tokens.get(position + 1) == Some(&Token::Literal('/')) {
+                    pending.push(position + 2);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs_respect_path_components() {
+        assert!(glob_matches("**/Anime/**", "/media/tv/Anime/Show/e01.mkv"));
+        assert!(glob_matches("/media/**/*.mkv", "/media/e01.mkv"));
+        assert!(glob_matches("*.mkv", "e01.mkv"));
+        assert!(glob_matches("e0?.mkv", "e01.mkv"));
+        assert!(!glob_matches("/media/*.mkv", "/media/tv/e01.mkv"));
+        assert!(!glob_matches("**/anime/**", "/media/tv/Anime/e01.mkv"));
+    }
+
+    #[test]
+    fn double_star_slash_skips_only_whole_directories() {
+        assert!(glob_matches("**/e01.mkv", "e01.mkv"));
+        assert!(glob_matches("**/e01.mkv", "tv/show/e01.mkv"));
+        assert!(!glob_matches("**/e01.mkv", "xe01.mkv"));
+        assert!(glob_matches("tv/**", "tv/show/e01.mkv"));
+        assert!(!glob_matches("e0?.mkv", "e0/.mkv"));
+        assert!(glob_matches("", ""));
+        assert!(!glob_matches("", "e01.mkv"));
+    }
+
+    #[test]
+    fn many_stars_do_not_backtrack() {
+        let text = "a".repeat(200);
+        let pattern = format!("{}b", "**a".repeat(30));
+        assert!(!glob_matches(&pattern, &text));
+        assert!(glob_matches(&"*a".repeat(30), &text));
+    }
+}
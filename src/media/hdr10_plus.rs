@@ -0,0 +1,440 @@
+//! HDR10+ dynamic metadata passthrough for libx265.
+//!
+//! FFmpeg decodes the SMPTE ST 2094-40 metadata of every frame but its
+//! libx265 wrapper does not hand it to the encoder. Before the encode, the
+//! source's per-frame metadata is read with `ffprobe` and written as the JSON
+//! file x265's `dhdr10-info` option reads, the same layout `hdr10plus_tool`
+//! produces. After the encode the output is probed, since an x265 built
+//! without HDR10+ support ignores the file.
+
+use crate::error::{AlchemistError, Result};
+use serde_json::json;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::oneshot;
+
+/// Raw ST 2094-40 values are scaled integers; FFmpeg reports them as
+/// fractions of these denominators.
+const MAXRGB_SCALE: f64 = 100_000.0;
+const KNEE_POINT_SCALE: f64 = 4095.0;
+const BEZIER_ANCHOR_SCALE: f64 = 1023.0;
+
+/// One frame's metadata, first processing window only; x265 writes one.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct FrameMetadata {
+    targeted_system_display_maximum_luminance: u32,
+    maxscl: [u32; 3],
+    average_maxrgb: u32,
+    distribution_index: Vec<u32>,
+    distribution_values: Vec<u32>,
+    /// Knee point and anchors, when the frame carries a tone-mapping curve.
+    bezier_curve: Option<(u32, u32, Vec<u32>)>,
+}
+
+/// Where the metadata for an encode writing `output` is kept.
+pub fn metadata_path_for(output: &Path) -> PathBuf {
+    let mut name = output.file_name().unwrap_or_default().to_os_string();
+    name.push(".hdr10plus.json");
+    output.with_file_name(name)
+}
+
+/// Escape a value for FFmpeg's `-x265-params` list, which splits on `:`.
+pub fn escape_x265_param(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        if matches!(ch, ':' | '\\' | '\'') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+/// Read the HDR10+ metadata of every frame of `input`'s first video stream
+/// into an x265 `dhdr10-info` file at `dest`. Returns the frame count, or
+/// `Cancelled` once `cancel` fires.
+pub async fn extract(
+    input: &Path,
+    dest: &Path,
+    cancel: &mut oneshot::Receiver<()>,
+) -> Result<usize> {
+    let mut child = Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "frame_side_data",
+            "-print_format",
+            "default",
+        ])
+        .arg(input)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| {
+            AlchemistError::Analyzer(format!("Failed to run HDR10+ extraction: {err}"))
+        })?;
+    let Some(stdout) = child.stdout.take() else {
+        return Err(AlchemistError::Analyzer(
+            "HDR10+ extraction produced no output".to_string(),
+        ));
+    };
+
+    // Decoding every frame takes a while; parse as ffprobe goes rather than
+    // buffering its output.
+    let mut parser = FrameParser::default();
+    let mut lines = BufReader::new(stdout).lines();
+    loop {
+        tokio::select! {
+            line = lines.next_line() => match line? {
+                Some(line) => parser.push_line(&line),
+                None => break,
+            },
+            _ = &mut *cancel => {
+                let _ = child.kill().await;
+                return Err(AlchemistError::Cancelled);
+            }
+        }
+    }
+    let status = child.wait().await?;
+    if !status.success() {
+        return Err(AlchemistError::Analyzer(format!(
+            "HDR10+ extraction failed ({status})"
+        )));
+    }
+
+    let frames = fill_gaps(parser.frames)
+        .ok_or_else(|| AlchemistError::Analyzer("No frame carries HDR10+ metadata".to_string()))?;
+    let document = x265_json(&frames);
+    let dest = dest.to_path_buf();
+    let count = frames.len();
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&dest)?);
+        serde_json::to_writer(&mut file, &document)
+            .map_err(|err| AlchemistError::Unknown(format!("HDR10+ metadata: {err}")))?;
+        file.flush()?;
+        Ok(())
+    })
+    .await
+    .map_err(|err| AlchemistError::Unknown(format!("HDR10+ metadata writer failed: {err}")))??;
+    Ok(count)
+}
+
+/// Collects frames from ffprobe's `default` output, where every frame is a
+/// `[FRAME]` block holding one block per side data entry.
+#[derive(Default)]
+struct FrameParser {
+    frames: Vec<Option<FrameMetadata>>,
+    frame: Option<Option<FrameMetadata>>,
+    block: Option<Vec<(String, String)>>,
+}
+
+impl FrameParser {
+    fn push_line(&mut self, line: &str) {
+        let line = line.trim();
+        match line {
+            "[FRAME]" => self.frame = Some(None),
+            "[/FRAME]" => {
+                if let Some(frame) = self.frame.take() {
+                    self.frames.push(frame);
+                }
+            }
+            _ if line.starts_with("[/") => {
+                if let (Some(block), Some(frame)) = (self.block.take(), self.frame.as_mut())
+                    && frame.is_none()
+                {
+                    *frame = parse_side_data(&block);
+                }
+            }
+            _ if line.starts_with('[') => self.block = Some(Vec::new()),
+            _ => {
+                if let (Some(block), Some((key, value))) =
+                    (self.block.as_mut(), line.split_once('='))
+                {
+                    block.push((key.to_string(), value.to_string()));
+                }
+            }
+        }
+    }
+}
+
+/// A `num/den` fraction, or a plain number, scaled back to its raw integer.
+fn raw_value(value: &str, scale: f64) -> Option<u32> {
+    let value = match value.split_once('/') {
+        Some((num, den)) => {
+            let den = den.trim().parse::<f64>().ok().filter(|den| *den != 0.0)?;
+            num.trim().parse::<f64>().ok()? / den
+        }
+        None => value.trim().parse::<f64>().ok()?,
+    };
+    (value.is_finite() && value >= 0.0).then(|| (value * scale).round() as u32)
+}
+
+fn parse_side_data(block: &[(String, String)]) -> Option<FrameMetadata> {
+    let is_hdr10_plus = block.iter().any(|(key, value)| {
+        key == "side_data_type" && {
+            let value = value.to_ascii_uppercase();
+            value.contains("SMPTE2094-40") || value.contains("HDR10+")
+        }
+    });
+    if !is_hdr10_plus {
+        return None;
+    }
+    // Later windows repeat the same keys; the first occurrence is window 0.
+    let values = |key: &'static str| {
+        block
+            .iter()
+            .filter(move |(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    };
+    let first =
+        |key: &'static str, scale: f64| values(key).next().and_then(|v| raw_value(v, scale));
+
+    let maxscl: Vec<u32> = values("maxscl")
+        .take(3)
+        .filter_map(|value| raw_value(value, MAXRGB_SCALE))
+        .collect();
+    let maxscl: [u32; 3] = maxscl.try_into().ok()?;
+    let percentiles = first("num_distribution_maxrgb_percentiles", 1.0).unwrap_or(0) as usize;
+    let distribution_index = values("distribution_maxrgb_percentage")
+        .take(percentiles)
+        .filter_map(|value| raw_value(value, 1.0))
+        .collect::<Vec<_>>();
+    let distribution_values = values("distribution_maxrgb_percentile")
+        .take(percentiles)
+        .filter_map(|value| raw_value(value, MAXRGB_SCALE))
+        .collect::<Vec<_>>();
+    if distribution_index.len() != percentiles || distribution_values.len() != percentiles {
+        return None;
+    }
+
+    let bezier_curve = if first("tone_mapping_flag", 1.0) == Some(1) {
+        let anchors = first("num_bezier_curve_anchors", 1.0).unwrap_or(0) as usize;
+        Some((
+            first("knee_point_x", KNEE_POINT_SCALE)?,
+            first("knee_point_y", KNEE_POINT_SCALE)?,
+            values("bezier_curve_anchors")
+                .take(anchors)
+                .filter_map(|value| raw_value(value, BEZIER_ANCHOR_SCALE))
+                .collect(),
+        ))
+    } else {
+        None
+    };
+
+    Some(FrameMetadata {
+        targeted_system_display_maximum_luminance: first(
+            "targeted_system_display_maximum_luminance",
+            1.0,
+        )
+        .unwrap_or(0),
+        maxscl,
+        average_maxrgb: first("average_maxrgb", MAXRGB_SCALE)?,
+        distribution_index,
+        distribution_values,
+        bezier_curve,
+    })
+}
+
+/// x265 maps entries to frames by position, so a frame without metadata
+/// repeats the one before it (the first one, at the start). `None` when no
+/// frame has any.
+fn fill_gaps(frames: Vec<Option<FrameMetadata>>) -> Option<Vec<FrameMetadata>> {
+    let mut previous = frames.iter().flatten().next()?.clone();
+    Some(
+        frames
+            .into_iter()
+            .map(|frame| {
+                if let Some(frame) = frame {
+                    previous = frame;
+                }
+                previous.clone()
+            })
+            .collect(),
+    )
+}
+
+/// The `dhdr10-info` document: `hdr10plus_tool`'s layout, which x265 reads.
+fn x265_json(frames: &[FrameMetadata]) -> serde_json::Value {
+    let profile = if frames.iter().any(|frame| frame.bezier_curve.is_some()) {
+        "B"
+    } else {
+        "A"
+    };
+    let mut scene_id = 0;
+    let mut scene_frame_index = 0;
+    let scenes: Vec<serde_json::Value> = frames
+        .iter()
+        .enumerate()
+        .map(|(index, frame)| {
+            if index > 0 {
+                if frames[index - 1] == *frame {
+                    scene_frame_index += 1;
+                } else {
+                    scene_id += 1;
+                    scene_frame_index = 0;
+                }
+            }
+            let mut scene = json!({
+                "LuminanceParameters": {
+                    "AverageRGB": frame.average_maxrgb,
+                    "LuminanceDistributions": {
+                        "DistributionIndex": frame.distribution_index,
+                        "DistributionValues": frame.distribution_values,
+                    },
+                    "MaxScl": frame.maxscl,
+                },
+                "NumberOfWindows": 1,
+                "TargetedSystemDisplayMaximumLuminance":
+                    frame.targeted_system_display_maximum_luminance,
+                "SceneFrameIndex": scene_frame_index,
+                "SceneId": scene_id,
+                "SequenceFrameIndex": index,
+            });
+            if let Some((knee_x, knee_y, anchors)) = &frame.bezier_curve {
+                scene["BezierCurveData"] = json!({
+                    "Anchors": anchors,
+                    "KneePointX": knee_x,
+                    "KneePointY": knee_y,
+                });
+            }
+            scene
+        })
+        .collect();
+    json!({
+        "JSONInfo": { "HDR10plusProfile": profile, "Version": "1.0" },
+        "SceneInfo": scenes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROBE_OUTPUT: &str = "\
+[FRAME]
+[SIDE_DATA]
+side_data_type=Mastering display metadata
+[/SIDE_DATA]
+[SIDE_DATA]
+side_data_type=HDR Dynamic Metadata SMPTE2094-40 (HDR10+)
+application version=1
+num_windows=1
+targeted_system_display_maximum_luminance=400/1
+maxscl=37000/100000
+maxscl=32500/100000
+maxscl=28000/100000
+average_maxrgb=1200/100000
+num_distribution_maxrgb_percentiles=3
+distribution_maxrgb_percentage=1
+distribution_maxrgb_percentile=10/100000
+distribution_maxrgb_percentage=50
+distribution_maxrgb_percentile=900/100000
+distribution_maxrgb_percentage=99
+distribution_maxrgb_percentile=30000/100000
+fraction_bright_pixels=0/1000
+tone_mapping_flag=1
+knee_point_x=17/4095
+knee_point_y=64/4095
+num_bezier_curve_anchors=2
+bezier_curve_anchors=102/1023
+bezier_curve_anchors=204/1023
+color_saturation_mapping_flag=0
+[/SIDE_DATA]
+[/FRAME]
+[FRAME]
+[/FRAME]
+[FRAME]
+[SIDE_DATA]
+side_data_type=HDR Dynamic Metadata SMPTE2094-40 (HDR10+)
+targeted_system_display_maximum_luminance=400/1
+maxscl=1/10
+maxscl=1/10
+maxscl=1/10
+average_maxrgb=1/100
+num_distribution_maxrgb_percentiles=0
+tone_mapping_flag=0
+[/SIDE_DATA]
+[/FRAME]
+";
+
+    fn parse(output: &str) -> Vec<Option<FrameMetadata>> {
+        let mut parser = FrameParser::default();
+        for line in output.lines() {
+            parser.push_line(line);
+        }
+        parser.frames
+    }
+
+    #[test]
+    fn frames_are_read_back_as_raw_values() {
+        let frames = parse(PROBE_OUTPUT);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(
+            frames[0],
+            Some(FrameMetadata {
+                targeted_system_display_maximum_luminance: 400,
+                maxscl: [37000, 32500, 28000],
+                average_maxrgb: 1200,
+                distribution_index: vec![1, 50, 99],
+                distribution_values: vec![10, 900, 30000],
+                bezier_curve: Some((17, 64, vec![102, 204])),
+            })
+        );
+        assert_eq!(frames[1], None);
+        let last = frames[2].clone().unwrap_or_default();
+        assert_eq!(last.maxscl, [10000; 3]);
+        assert_eq!(last.average_maxrgb, 1000);
+        assert_eq!(last.bezier_curve, None);
+    }
+
+    #[test]
+    fn every_frame_gets_an_x265_scene_entry() {
+        let frames = fill_gaps(parse(PROBE_OUTPUT)).unwrap_or_default();
+        let document = x265_json(&frames);
+        assert_eq!(document["JSONInfo"]["HDR10plusProfile"], "B");
+
+        let scenes = document["SceneInfo"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        assert_eq!(scenes.len(), 3);
+        let first = &scenes[0];
+        assert_eq!(
+            first["LuminanceParameters"]["MaxScl"],
+            json!([37000, 32500, 28000])
+        );
+        assert_eq!(
+            first["LuminanceParameters"]["LuminanceDistributions"]["DistributionValues"],
+            json!([10, 900, 30000])
+        );
+        assert_eq!(first["BezierCurveData"]["Anchors"], json!([102, 204]));
+        assert_eq!(first["TargetedSystemDisplayMaximumLuminance"], 400);
+
+        // The frame without metadata repeats the first, in the same scene.
+        assert_eq!(scenes[1]["SceneId"], 0);
+        assert_eq!(scenes[1]["SceneFrameIndex"], 1);
+        assert_eq!(scenes[2]["SceneId"], 1);
+        assert_eq!(scenes[2]["SequenceFrameIndex"], 2);
+        assert!(scenes[2].get("BezierCurveData").is_none());
+
+        assert_eq!(fill_gaps(vec![None, None]), None);
+    }
+
+    #[test]
+    fn x265_param_values_escape_separators() {
+        assert_eq!(
+            escape_x265_param(r"C:\media\out.mkv.hdr10plus.json"),
+            r"C\:\\media\\out.mkv.hdr10plus.json"
+        );
+        assert_eq!(
+            metadata_path_for(Path::new("/media/out.tmp.mkv")),
+            PathBuf::from("/media/out.tmp.mkv.hdr10plus.json")
+        );
+    }
+}
@@ -0,0 +1,331 @@
+//! User-defined post-processing hooks.
+//!
+//! Hooks run after a job reaches `completed`, `failed`, or `skipped`, one at a
+//! time in config order. The command is spawned directly (no shell) with the
+//! job described in `ALCHEMIST_*` environment variables; its output is
+//! captured for the job log. A hook is killed when it outlives its timeout or
+//! when the job is cancelled, including by engine shutdown.
+
+use crate::config::{HookConfig, HookEvent};
+use crate::media::glob::glob_matches;
+use std::collections::VecDeque;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::oneshot;
+
+/// Lines of stdout and of stderr kept per hook run.
+pub const MAX_CAPTURED_LINES: usize = 200;
+
+/// Job details passed to hooks as environment variables.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HookContext {
+    pub job_id: i64,
+    pub input_path: String,
+    pub output_path: String,
+    pub input_size_bytes: Option<u64>,
+    pub output_size_bytes: Option<u64>,
+    pub input_codec: Option<String>,
+    pub output_codec: Option<String>,
+    pub vmaf_score: Option<f64>,
+    pub decision_code: Option<String>,
+}
+
+impl HookContext {
+    pub fn env(&self, event: HookEvent) -> Vec<(&'static str, String)> {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        vec![
+            ("ALCHEMIST_EVENT", event.as_str().to_string()),
+            ("ALCHEMIST_JOB_ID", self.job_id.to_string()),
+            ("ALCHEMIST_INPUT_PATH", self.input_path.clone()),
+            ("ALCHEMIST_OUTPUT_PATH", self.output_path.clone()),
+            (
+                "ALCHEMIST_INPUT_SIZE",
+                optional(self.input_size_bytes.map(|size| size.to_string())),
+            ),
+            (
+                "ALCHEMIST_OUTPUT_SIZE",
+                optional(self.output_size_bytes.map(|size| size.to_string())),
+            ),
+            ("ALCHEMIST_INPUT_CODEC", optional(self.input_codec.clone())),
+            (
+                "ALCHEMIST_OUTPUT_CODEC",
+                optional(self.output_codec.clone()),
+            ),
+            (
+                "ALCHEMIST_VMAF",
+                optional(self.vmaf_score.map(|score| format!("{score:.2}"))),
+            ),
+            (
+                "ALCHEMIST_DECISION_CODE",
+                optional(self.decision_code.clone()),
+            ),
+        ]
+    }
+}
+
+/// Whether `hook` runs for a job that ended with `event`.
+pub fn hook_applies(hook: &HookConfig, event: HookEvent, input_path: &str) -> bool {
+    hook.enabled
+        && hook.events.contains(&event)
+        && hook
+            .path_filter
+            .as_deref()
+            .map(str::trim)
+            .filter(|pattern| !pattern.is_empty())
+            .is_none_or(|pattern| glob_matches(pattern, input_path))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookOutcome {
+    Succeeded,
+    Exited(Option<i32>),
+    TimedOut,
+    Cancelled,
+    SpawnFailed(String),
+}
+
+impl HookOutcome {
+    pub fn is_success(&self) -> bool {
+        matches!(self, Self::Succeeded)
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Succeeded => "exited successfully".to_string(),
+            Self::Exited(Some(code)) => format!("exited with code {code}"),
+            Self::Exited(None) => "was terminated by a signal".to_string(),
+            Self::TimedOut => "timed out and was killed".to_string(),
+            Self::Cancelled => "was killed because the job was cancelled".to_string(),
+            Self::SpawnFailed(err) => format!("could not be started: {err}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookRun {
+    pub outcome: HookOutcome,
+    pub stdout: Vec<String>,
+    pub stderr: Vec<String>,
+}
+
+/// How long a finished or killed hook's readers get to drain what is still
+/// buffered in its pipes. A background process the hook left behind can hold
+/// them open indefinitely.
+const PIPE_DRAIN: Duration = Duration::from_secs(1);
+
+/// The last `MAX_CAPTURED_LINES` non-empty lines read from one of a hook's
+/// pipes. Lines are kept as they arrive, so a hook that is killed still
+/// leaves what it printed before.
+struct LineCapture {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    reader: tokio::task::JoinHandle<()>,
+}
+
+impl LineCapture {
+    fn start<R>(pipe: Option<R>) -> Self
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        let lines = Arc::new(Mutex::new(VecDeque::new()));
+        let sink = lines.clone();
+        let reader = tokio::spawn(async move {
+            let Some(pipe) = pipe else {
+                return;
+            };
+            let mut pipe = BufReader::new(pipe);
+            let mut buf = Vec::new();
+            loop {
+                buf.clear();
+                match pipe.read_until(b'\n', &mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => {}
+                }
+                let line = String::from_utf8_lossy(&buf).trim_end().to_string();
+                if line.is_empty() {
+                    continue;
+                }
+                let mut lines = match sink.lock() {
+                    Ok(lines) => lines,
+                    Err(e) => e.into_inner(),
+                };
+                if lines.len() == MAX_CAPTURED_LINES {
+                    lines.pop_front();
+                }
+                lines.push_back(line);
+            }
+        });
+        Self { lines, reader }
+    }
+
+    async fn finish(mut self) -> Vec<String> {
+        if tokio::time::timeout(PIPE_DRAIN, &mut self.reader)
+            .await
+            .is_err()
+        {
+            self.reader.abort();
+        }
+        let lines = match self.lines.lock() {
+            Ok(lines) => lines,
+            Err(e) => e.into_inner(),
+        };
+        lines.iter().cloned().collect()
+    }
+}
+
+/// Run one hook to completion, its timeout, or cancellation.
+pub async fn run_hook(
+    hook: &HookConfig,
+    event: HookEvent,
+    context: &HookContext,
+    cancel: &mut oneshot::Receiver<()>,
+) -> HookRun {
+    let mut command = tokio::process::Command::new(&hook.command);
+    command
+        .args(&hook.args)
+        .envs(context.env(event))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(err) => {
+            return HookRun {
+                outcome: HookOutcome::SpawnFailed(err.to_string()),
+                stdout: Vec::new(),
+                stderr: Vec::new(),
+            };
+        }
+    };
+    let stdout = LineCapture::start(child.stdout.take());
+    let stderr = LineCapture::start(child.stderr.take());
+
+    let timeout = Duration::from_secs(hook.timeout_secs.max(1));
+    let outcome = tokio::select! {
+        result = tokio::time::timeout(timeout, child.wait()) => match result {
+            Ok(Ok(status)) if status.success() => HookOutcome::Succeeded,
+            Ok(Ok(status)) => HookOutcome::Exited(status.code()),
+            Ok(Err(err)) => HookOutcome::SpawnFailed(err.to_string()),
+            Err(_) => HookOutcome::TimedOut,
+        },
+        _ = cancel => HookOutcome::Cancelled,
+    };
+    if matches!(outcome, HookOutcome::TimedOut | HookOutcome::Cancelled)
+        && let Err(err) = child.kill().await
+    {
+        tracing::warn!("Failed to kill hook '{}': {err}", hook.name);
+    }
+
+    HookRun {
+        outcome,
+        stdout: stdout.finish().await,
+        stderr: stderr.finish().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(command: &str, args: &[&str]) -> HookConfig {
+        HookConfig {
+            name: "test".to_string(),
+            command: command.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            events: vec![HookEvent::Completed],
+            path_filter: None,
+            timeout_secs: 10,
+            flag_job_on_error: false,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn hooks_match_on_event_and_path_filter() {
+        let mut config = hook("true", &[]);
+        assert!(hook_applies(
+            &config,
+            HookEvent::Completed,
+            "/media/tv/a.mkv"
+        ));
+        assert!(!hook_applies(&config, HookEvent::Failed, "/media/tv/a.mkv"));
+
+        config.path_filter = Some("/media/movies/**".to_string());
+        assert!(!hook_applies(
+            &config,
+            HookEvent::Completed,
+            "/media/tv/a.mkv"
+        ));
+        assert!(hook_applies(
+            &config,
+            HookEvent::Completed,
+            "/media/movies/b/b.mkv"
+        ));
+
+        config.enabled = false;
+        assert!(!hook_applies(
+            &config,
+            HookEvent::Completed,
+            "/media/movies/b/b.mkv"
+        ));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn hooks_see_job_env_and_report_exit_status() {
+        let context = HookContext {
+            job_id: 7,
+            input_path: "/media/a.mkv".to_string(),
+            decision_code: Some("transcode_recommended".to_string()),
+            ..HookContext::default()
+        };
+        let (_tx, mut cancel) = oneshot::channel();
+
+        let run = run_hook(
+            &hook(
+                "sh",
+                &[
+                    "-c",
+                    "echo \"$ALCHEMIST_JOB_ID $ALCHEMIST_EVENT $ALCHEMIST_DECISION_CODE\"; echo oops >&2; exit 3",
+                ],
+            ),
+            HookEvent::Completed,
+            &context,
+            &mut cancel,
+        )
+        .await;
+        assert_eq!(run.outcome, HookOutcome::Exited(Some(3)));
+        assert_eq!(run.stdout, vec!["7 completed transcode_recommended"]);
+        assert_eq!(run.stderr, vec!["oops"]);
+
+        // Lines printed before a kill stay in the log.
+        let mut slow = hook(
+            "sh",
+            &["-c", "echo started; echo warming up >&2; exec sleep 5"],
+        );
+        slow.timeout_secs = 1;
+        let run = run_hook(&slow, HookEvent::Completed, &context, &mut cancel).await;
+        assert_eq!(run.outcome, HookOutcome::TimedOut);
+        assert_eq!(run.stdout, vec!["started"]);
+        assert_eq!(run.stderr, vec!["warming up"]);
+
+        let (tx, mut cancel) = oneshot::channel();
+        let cancel_later = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            let _ = tx.send(());
+        });
+        let run = run_hook(
+            &hook("sh", &["-c", "echo uploading; exec sleep 5"]),
+            HookEvent::Completed,
+            &context,
+            &mut cancel,
+        )
+        .await;
+        let _ = cancel_later.await;
+        assert_eq!(run.outcome, HookOutcome::Cancelled);
+        assert_eq!(run.stdout, vec!["uploading"]);
+    }
+}
@@ -0,0 +1,330 @@
+//! Exclusion rules for library scans and the file watcher.
+//!
+//! Two sources keep a path out of the queue: exclude globs configured on a
+//! watch directory, and `.alchemistignore` files anywhere beneath it. Globs
+//! are matched against the path relative to the watch directory, and a glob
+//! without a `/` is also matched against the bare file or directory name.
+//!
+//! Ignore files follow gitignore semantics: patterns are relative to the
+//! directory holding the file, a pattern without a slash matches at any
+//! depth, a trailing `/` matches only directories, `!` re-includes, and
+//! deeper files and later lines take precedence. Excluding a directory
+//! excludes everything under it.
+
+use crate::media::glob::glob_matches;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+pub const IGNORE_FILE_NAME: &str = ".alchemistignore";
+
+/// Source label for a match against a watch directory's exclude globs.
+pub const EXCLUDE_GLOBS_SOURCE: &str = "exclude_globs";
+
+/// The rule that excluded a path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Exclusion {
+    /// The pattern as written.
+    pub rule: String,
+    /// `exclude_globs`, or the ignore file and line the pattern came from.
+    pub source: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExcludedFile {
+    pub path: String,
+    #[serde(flatten)]
+    pub exclusion: Exclusion,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IgnorePattern {
+    raw: String,
+    line: usize,
+    glob: String,
+    negated: bool,
+    dir_only: bool,
+}
+
+impl IgnorePattern {
+    fn matches(&self, relative: &str, is_dir: bool) -> bool {
+        (!self.dir_only || is_dir) && glob_matches(&self.glob, relative)
+    }
+}
+
+fn parse_ignore_file(contents: &str) -> Vec<IgnorePattern> {
+    let mut patterns = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let raw = line.trim_end();
+        if raw.is_empty() || raw.starts_with('#') {
+            continue;
+        }
+        let (negated, body) = match raw.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            // `\#` and `\!` escape a literal leading character.
+            None => (false, raw.strip_prefix('\\').unwrap_or(raw)),
+        };
+        let (dir_only, body) = match body.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, body),
+        };
+        if body.is_empty() {
+            continue;
+        }
+        let glob = if let Some(anchored) = body.strip_prefix('/') {
+            anchored.to_string()
+        } else if body.contains('/') {
+            body.to_string()
+        } else {
+            format!("**/{body}")
+        };
+        patterns.push(IgnorePattern {
+            raw: raw.to_string(),
+            line: index + 1,
+            glob,
+            negated,
+            dir_only,
+        });
+    }
+    patterns
+}
+
+fn relative_text(path: &Path, base: &Path) -> Option<String> {
+    let relative = path.strip_prefix(base).ok()?;
+    if relative.as_os_str().is_empty() {
+        return None;
+    }
+    Some(relative.to_string_lossy().replace('\\', "/"))
+}
+
+type IgnoreCache = HashMap<PathBuf, Option<Arc<Vec<IgnorePattern>>>>;
+
+/// Exclude globs and ignore files for one watch root. Parsed ignore files
+/// are cached per directory until `forget_ignore_files` is called.
+#[derive(Debug)]
+pub struct ExcludeRules {
+    root: PathBuf,
+    globs: Vec<String>,
+    ignore_files: Mutex<IgnoreCache>,
+}
+
+impl ExcludeRules {
+    pub fn new(root: impl Into<PathBuf>, globs: &[String]) -> Self {
+        Self {
+            root: root.into(),
+            globs: globs
+                .iter()
+                .map(|glob| glob.trim().to_string())
+                .filter(|glob| !glob.is_empty())
+                .collect(),
+            ignore_files: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Why `path` itself is excluded. Directories between it and the root
+    /// are not checked; a pruning walk has already rejected those.
+    pub fn entry_exclusion(&self, path: &Path, is_dir: bool) -> Option<Exclusion> {
+        let relative = relative_text(path, &self.root)?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let dir_relative = format!("{relative}/");
+        for glob in &self.globs {
+            if glob_matches(glob, &relative)
+                || (is_dir && glob_matches(glob, &dir_relative))
+                || (!glob.contains('/') && glob_matches(glob, &name))
+            {
+                return Some(Exclusion {
+                    rule: glob.clone(),
+                    source: EXCLUDE_GLOBS_SOURCE.to_string(),
+                });
+            }
+        }
+        self.ignore_file_exclusion(path, is_dir)
+    }
+
+    /// Why the file at `path`, or any directory between it and the root,
+    /// is excluded.
+    pub fn exclusion_for(&self, path: &Path) -> Option<Exclusion> {
+        if !path.starts_with(&self.root) {
+            return None;
+        }
+        let mut ancestors = path
+            .ancestors()
+            .skip(1)
+            .take_while(|ancestor| *ancestor != self.root && ancestor.starts_with(&self.root))
+            .collect::<Vec<_>>();
+        ancestors.reverse();
+        ancestors
+            .into_iter()
+            .find_map(|dir| self.entry_exclusion(dir, true))
+            .or_else(|| self.entry_exclusion(path, false))
+    }
+
+    /// Drop cached ignore files so edits are picked up.
+    pub fn forget_ignore_files(&self) {
+        if let Ok(mut cache) = self.ignore_files.lock() {
+            cache.clear();
+        }
+    }
+
+    fn ignore_file_exclusion(&self, path: &Path, is_dir: bool) -> Option<Exclusion> {
+        let parent = path.parent()?;
+        let mut dirs = parent
+            .ancestors()
+            .take_while(|dir| dir.starts_with(&self.root))
+            .collect::<Vec<_>>();
+        dirs.reverse();
+
+        // Later lines and deeper files override earlier matches.
+        let mut decision: Option<(&Path, IgnorePattern)> = None;
+        for dir in dirs {
+            let Some(patterns) = self.ignore_patterns(dir) else {
+                continue;
+            };
+            let Some(relative) = relative_text(path, dir) else {
+                continue;
+            };
+            if let Some(pattern) = patterns
+                .iter()
+                .rev()
+                .find(|pattern| pattern.matches(&relative, is_dir))
+            {
+                decision = Some((dir, pattern.clone()));
+            }
+        }
+
+        let (dir, pattern) = decision.filter(|(_, pattern)| !pattern.negated)?;
+        Some(Exclusion {
+            rule: pattern.raw,
+            source: format!("{}:{}", dir.join(IGNORE_FILE_NAME).display(), pattern.line),
+        })
+    }
+
+    fn ignore_patterns(&self, dir: &Path) -> Option<Arc<Vec<IgnorePattern>>> {
+        let load = || {
+            std::fs::read_to_string(dir.join(IGNORE_FILE_NAME))
+                .ok()
+                .map(|contents| parse_ignore_file(&contents))
+                .filter(|patterns| !patterns.is_empty())
+                .map(Arc::new)
+        };
+        match self.ignore_files.lock() {
+            Ok(mut cache) => cache.entry(dir.to_path_buf()).or_insert_with(load).clone(),
+            Err(_) => load(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn unique_temp_dir(label: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "alchemist_ignore_{label}_{}",
+            rand::random::<u64>()
+        ));
+        let _ = fs::create_dir_all(&path);
+        path
+    }
+
+    #[test]
+    fn exclude_globs_match_relative_paths_and_names() {
+        let root = PathBuf::from("/library");
+        let rules = ExcludeRules::new(
+            &root,
+            &[
+                "**/Extras/**".to_string(),
+                "*sample*".to_string(),
+                "  ".to_string(),
+            ],
+        );
+
+        let extras = rules.exclusion_for(&root.join("Movie (2020)/Extras/featurette.mkv"));
+        assert_eq!(
+            extras.map(|exclusion| exclusion.rule),
+            Some("**/Extras/**".to_string())
+        );
+        let sample = rules.exclusion_for(&root.join("Movie (2020)/movie-sample.mkv"));
+        assert_eq!(
+            sample.map(|exclusion| exclusion.source),
+            Some(EXCLUDE_GLOBS_SOURCE.to_string())
+        );
+        assert!(
+            rules
+                .entry_exclusion(&root.join("Show/Extras"), true)
+                .is_some()
+        );
+        assert!(
+            rules
+                .exclusion_for(&root.join("Movie (2020)/movie.mkv"))
+                .is_none()
+        );
+        assert!(
+            rules
+                .exclusion_for(Path::new("/elsewhere/sample.mkv"))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn ignore_files_follow_gitignore_semantics() -> anyhow::Result<()> {
+        let root = unique_temp_dir("gitignore");
+        let show = root.join("Show");
+        fs::create_dir_all(show.join("Season 01"))?;
+        fs::create_dir_all(root.join("@eaDir/Show"))?;
+        fs::write(
+            root.join(IGNORE_FILE_NAME),
+            "# thumbnails\n@eaDir/\n*.sample.mkv\n/top.mkv\n",
+        )?;
+        fs::write(show.join(IGNORE_FILE_NAME), "!keep.sample.mkv\nSeason */\n")?;
+
+        let rules = ExcludeRules::new(&root, &[]);
+        let eadir = rules.exclusion_for(&root.join("@eaDir/Show/thumb.mkv"));
+        let Some(eadir) = eadir else {
+            panic!("expected @eaDir to be excluded");
+        };
+        assert_eq!(eadir.rule, "@eaDir/");
+        assert!(eadir.source.ends_with(&format!("{IGNORE_FILE_NAME}:2")));
+
+        assert!(rules.exclusion_for(&show.join("a.sample.mkv")).is_some());
+        assert!(rules.exclusion_for(&show.join("keep.sample.mkv")).is_none());
+        assert!(
+            rules
+                .exclusion_for(&show.join("Season 01/e01.mkv"))
+                .is_some()
+        );
+        assert!(rules.exclusion_for(&root.join("top.mkv")).is_some());
+        assert!(rules.exclusion_for(&show.join("top.mkv")).is_none());
+
+        // Directory-only patterns never match files.
+        assert!(rules.exclusion_for(&root.join("@eaDir")).is_none());
+
+        let _ = fs::remove_dir_all(root);
+        Ok(())
+    }
+
+    #[test]
+    fn cached_ignore_files_reload_after_forget() -> anyhow::Result<()> {
+        let root = unique_temp_dir("reload");
+        let file = root.join("movie.mkv");
+        let rules = ExcludeRules::new(&root, &[]);
+        assert!(rules.exclusion_for(&file).is_none());
+
+        fs::write(root.join(IGNORE_FILE_NAME), "movie.mkv\n")?;
+        assert!(rules.exclusion_for(&file).is_none());
+        rules.forget_ignore_files();
+        assert!(rules.exclusion_for(&file).is_some());
+
+        let _ = fs::remove_dir_all(root);
+        Ok(())
+    }
+}
@@ -0,0 +1,254 @@
+//! Two-pass EBU R128 loudness normalization.
+//!
+//! When a library profile sets `loudness_target_lufs`, every audio track the
+//! plan re-encodes is run through `loudnorm` once before the encode to measure
+//! its integrated loudness, true peak and loudness range. The encode then
+//! feeds those values back into `loudnorm` in linear mode, which applies one
+//! gain for the whole track instead of pumping the level. Copied tracks are
+//! never filtered.
+
+use crate::error::{AlchemistError, Result};
+use crate::explanations::Explanation;
+use crate::media::pipeline::{
+    AudioStreamPlan, AudioTrackPlan, LoudnessMeasurement, LoudnessNormalization,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::path::Path;
+use tokio::process::Command;
+
+/// True-peak ceiling for normalized tracks, dBTP.
+pub const DEFAULT_TRUE_PEAK_DBTP: f64 = -1.5;
+/// Loudness range target, LU.
+pub const DEFAULT_LRA: f64 = 11.0;
+/// `loudnorm` upsamples to 192 kHz, so normalized tracks are resampled back.
+pub const OUTPUT_SAMPLE_RATE: u32 = 48_000;
+/// The measurement pass decodes the whole track.
+const MEASURE_TIMEOUT_SECS: u64 = 1800;
+
+/// `loudnorm` settings for the first, measuring pass.
+pub fn measurement_filter(normalization: &LoudnessNormalization) -> String {
+    format!(
+        "loudnorm=I={}:TP={}:LRA={}:print_format=json",
+        normalization.target_lufs, normalization.true_peak_dbtp, normalization.lra
+    )
+}
+
+/// `loudnorm` settings for the encode. `None` until the track is measured.
+pub fn second_pass_filter(normalization: &LoudnessNormalization) -> Option<String> {
+    let measured = normalization.measured.as_ref()?;
+    Some(format!(
+        "loudnorm=I={}:TP={}:LRA={}:measured_I={:.2}:measured_TP={:.2}:measured_LRA={:.2}:measured_thresh={:.2}:offset={:.2}:linear=true:print_format=summary",
+        normalization.target_lufs,
+        normalization.true_peak_dbtp,
+        normalization.lra,
+        measured.input_i,
+        measured.input_tp,
+        measured.input_lra,
+        measured.input_thresh,
+        measured.target_offset,
+    ))
+}
+
+/// Run the measurement pass over audio stream `0:a:{source_index}`.
+pub async fn measure(
+    input: &Path,
+    source_index: usize,
+    normalization: &LoudnessNormalization,
+) -> Result<LoudnessMeasurement> {
+    let map = format!("0:a:{source_index}");
+    let filter = measurement_filter(normalization);
+    let output = tokio::time::timeout(
+        std::time::Duration::from_secs(MEASURE_TIMEOUT_SECS),
+        Command::new("ffmpeg")
+            .args(["-hide_banner", "-nostats", "-i"])
+            .arg(input)
+            .args(["-map", &map, "-vn", "-sn", "-dn", "-af", &filter])
+            .args(["-f", "null", "-"])
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await;
+    let output = match output {
+        Ok(Ok(output)) => output,
+        Ok(Err(err)) => {
+            return Err(AlchemistError::FFmpeg(format!(
+                "Failed to run loudness measurement: {err}"
+            )));
+        }
+        Err(_) => {
+            return Err(AlchemistError::FFmpeg(format!(
+                "Loudness measurement timed out after {MEASURE_TIMEOUT_SECS}s"
+            )));
+        }
+    };
+    if !output.status.success() {
+        return Err(AlchemistError::FFmpeg(format!(
+            "Loudness measurement failed for {map}"
+        )));
+    }
+    parse_measurement(&String::from_utf8_lossy(&output.stderr))
+}
+
+#[derive(Debug, Deserialize)]
+struct LoudnormReport {
+    input_i: String,
+    input_tp: String,
+    input_lra: String,
+    input_thresh: String,
+    target_offset: String,
+}
+
+/// Read the JSON block `loudnorm` prints at the end of the first pass.
+fn parse_measurement(stderr: &str) -> Result<LoudnessMeasurement> {
+    let block = stderr
+        .rfind('{')
+        .and_then(|start| Some(&stderr[start..=start + stderr[start..].find('}')?]));
+    let Some(block) = block else {
+        return Err(AlchemistError::FFmpeg(
+            "loudnorm did not report a measurement".to_string(),
+        ));
+    };
+    let report: LoudnormReport = serde_json::from_str(block)
+        .map_err(|err| AlchemistError::FFmpeg(format!("Unreadable loudnorm report: {err}")))?;
+
+    let value = |raw: &str| -> Result<f64> {
+        raw.trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite())
+            .ok_or_else(|| {
+                AlchemistError::FFmpeg(format!(
+                    "loudnorm reported {raw:?}; the track is silent or too short to measure"
+                ))
+            })
+    };
+    Ok(LoudnessMeasurement {
+        input_i: value(&report.input_i)?,
+        input_tp: value(&report.input_tp)?,
+        input_lra: value(&report.input_lra)?,
+        input_thresh: value(&report.input_thresh)?,
+        target_offset: value(&report.target_offset)?,
+    })
+}
+
+/// One-line description of a measured track for job logs and explanations.
+pub fn describe(measured: &LoudnessMeasurement) -> String {
+    format!(
+        "{:.1} LUFS, {:.1} dBTP, LRA {:.1} LU",
+        measured.input_i, measured.input_tp, measured.input_lra
+    )
+}
+
+/// Attach each measured track to the job's decision explanation, keyed by its
+/// source audio stream.
+pub fn annotate(mut explanation: Explanation, tracks: &[AudioTrackPlan]) -> Explanation {
+    let mut target = None;
+    for track in tracks {
+        let AudioStreamPlan::Transcode {
+            loudness: Some(normalization),
+            ..
+        } = &track.action
+        else {
+            continue;
+        };
+        let (Some(source_index), Some(measured)) =
+            (track.source_index, normalization.measured.as_ref())
+        else {
+            continue;
+        };
+        target = Some(normalization.target_lufs);
+        explanation = explanation.with_measured(
+            format!("loudness_a{source_index}"),
+            json!(describe(measured)),
+        );
+    }
+    match target {
+        Some(target) => explanation.with_measured("loudness_target_lufs", json!(target)),
+        None => explanation,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::pipeline::AudioCodec;
+
+    const FIRST_PASS_STDERR: &str = r#"
+[Parsed_loudnorm_0 @ 0x5581]
+{
+	"input_i" : "-27.61",
+	"input_tp" : "-4.47",
+	"input_lra" : "18.10",
+	"input_thresh" : "-39.20",
+	"output_i" : "-16.58",
+	"output_tp" : "-1.50",
+	"output_lra" : "14.50",
+	"output_thresh" : "-27.71",
+	"normalization_type" : "dynamic",
+	"target_offset" : "0.58"
+}
+"#;
+
+    #[test]
+    fn first_pass_report_feeds_second_pass_filter() {
+        let measured = parse_measurement(FIRST_PASS_STDERR)
+            .unwrap_or_else(|err| panic!("failed to parse loudnorm report: {err}"));
+        assert_eq!(measured.input_i, -27.61);
+        assert_eq!(measured.target_offset, 0.58);
+
+        let mut normalization = LoudnessNormalization::new(-23.0);
+        assert!(second_pass_filter(&normalization).is_none());
+        normalization.measured = Some(measured);
+        let filter = second_pass_filter(&normalization)
+            .unwrap_or_else(|| panic!("expected a second-pass filter"));
+        assert!(filter.starts_with("loudnorm=I=-23:TP=-1.5:LRA=11:measured_I=-27.61:"));
+        assert!(filter.contains(":offset=0.58:linear=true"));
+    }
+
+    #[test]
+    fn silent_tracks_are_not_measured() {
+        let stderr = FIRST_PASS_STDERR.replace("\"-27.61\"", "\"-inf\"");
+        assert!(parse_measurement(&stderr).is_err());
+        assert!(parse_measurement("no report here").is_err());
+    }
+
+    #[test]
+    fn annotate_lists_measured_tracks_by_source_stream() {
+        let mut normalization = LoudnessNormalization::new(-16.0);
+        normalization.measured = parse_measurement(FIRST_PASS_STDERR).ok();
+        let tracks = vec![
+            AudioTrackPlan {
+                source_index: Some(1),
+                action: AudioStreamPlan::Transcode {
+                    codec: AudioCodec::Aac,
+                    bitrate_kbps: 192,
+                    channels: None,
+                    loudness: Some(normalization),
+                },
+                default: true,
+                language: None,
+            },
+            AudioTrackPlan {
+                source_index: Some(0),
+                action: AudioStreamPlan::Copy,
+                default: false,
+                language: None,
+            },
+        ];
+        let explanation = annotate(
+            crate::explanations::decision_from_legacy(
+                "transcode",
+                "transcode_recommended|target_codec=av1,current_codec=h264,bpp=0.120",
+            ),
+            &tracks,
+        );
+
+        assert_eq!(
+            explanation.measured["loudness_a1"],
+            json!("-27.6 LUFS, -4.5 dBTP, LRA 18.1 LU")
+        );
+        assert_eq!(explanation.measured["loudness_target_lufs"], json!(-16.0));
+        assert!(!explanation.measured.contains_key("loudness_a0"));
+    }
+}
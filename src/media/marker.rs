@@ -0,0 +1,169 @@
+//! Processed-by marker embedded in every output.
+//!
+//! Encodes and remuxes write an `ALCHEMIST_JOB` container tag such as
+//! `v=1;version=0.3.5;profile=Balanced;source=3f9c…;settings=a1b2…`. The
+//! analyzer reads it back, so an output that was renamed by another tool,
+//! or found again after the database was reset, is still recognized as
+//! Alchemist's own work instead of being encoded a second time.
+
+use crate::media::pipeline::MediaMetadata;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Container tag key. Matroska stores tag names upper-case; MP4 keeps the
+/// case it was written with.
+pub const TAG: &str = "ALCHEMIST_JOB";
+
+/// Version of the tag value layout.
+const FORMAT_VERSION: &str = "1";
+
+/// Hex digits kept from each SHA-256 digest.
+const DIGEST_LEN: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessedMarker {
+    /// Alchemist version that wrote the output.
+    pub version: String,
+    /// Library profile the output was planned with; `default` without one.
+    pub profile: String,
+    /// Identity of the source the output was made from; see [`source_hash`].
+    pub source_hash: String,
+    /// Digest of the encode settings; see [`digest`].
+    pub settings_digest: String,
+}
+
+impl ProcessedMarker {
+    pub fn new(source: &MediaMetadata, profile: Option<&str>, settings_digest: String) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            profile: profile.unwrap_or("default").to_string(),
+            source_hash: source_hash(source),
+            settings_digest,
+        }
+    }
+
+    /// Value written under [`TAG`].
+    pub fn tag_value(&self) -> String {
+        format!(
+            "v={FORMAT_VERSION};version={};profile={};source={};settings={}",
+            escape(&self.version),
+            escape(&self.profile),
+            escape(&self.source_hash),
+            escape(&self.settings_digest)
+        )
+    }
+
+    /// Parse a tag value. `None` for anything that is not a marker this
+    /// version understands.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut format_version = None;
+        let mut version = None;
+        let mut profile = None;
+        let mut source_hash = None;
+        let mut settings_digest = None;
+        for field in value.trim().split(';') {
+            let (key, value) = field.split_once('=')?;
+            let value = unescape(value);
+            match key.trim() {
+                "v" => format_version = Some(value),
+                "version" => version = Some(value),
+                "profile" => profile = Some(value),
+                "source" => source_hash = Some(value),
+                "settings" => settings_digest = Some(value),
+                _ => {}
+            }
+        }
+        if format_version.as_deref() != Some(FORMAT_VERSION) {
+            return None;
+        }
+        Some(Self {
+            version: version?,
+            profile: profile.unwrap_or_else(|| "default".to_string()),
+            source_hash: source_hash.unwrap_or_default(),
+            settings_digest: settings_digest?,
+        })
+    }
+}
+
+/// Field values may hold any text, such as a profile named `4K; HDR`.
+fn escape(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace(';', "%3B")
+        .replace('=', "%3D")
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("%3D", "=")
+        .replace("%3B", ";")
+        .replace("%25", "%")
+}
+
+fn short_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(DIGEST_LEN);
+    for byte in bytes {
+        use std::fmt::Write as _;
+        let _ = write!(&mut out, "{byte:02x}");
+        if out.len() >= DIGEST_LEN {
+            break;
+        }
+    }
+    out.truncate(DIGEST_LEN);
+    out
+}
+
+/// Stable digest of any serializable settings value.
+pub fn digest(value: &impl Serialize) -> String {
+    let serialized = serde_json::to_vec(value).unwrap_or_default();
+    short_hex(&Sha256::digest(serialized))
+}
+
+/// Identity of a source from its size, runtime and video stream. Cheap to
+/// compute and unaffected by renames, unlike a path or a full content hash.
+pub fn source_hash(source: &MediaMetadata) -> String {
+    digest(&(
+        source.size_bytes,
+        (source.duration_secs * 1000.0).round() as i64,
+        source.codec_name.to_ascii_lowercase(),
+        source.width,
+        source.height,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marker_round_trips_through_its_tag_value() {
+        let marker = ProcessedMarker {
+            version: "0.3.5".to_string(),
+            profile: "4K; HDR=yes 100%".to_string(),
+            source_hash: "3f9c0a1b2c3d4e5f".to_string(),
+            settings_digest: "a1b2c3d4e5f60718".to_string(),
+        };
+        let value = marker.tag_value();
+        assert!(value.starts_with("v=1;version=0.3.5;profile=4K%3B HDR%3Dyes 100%25;"));
+        assert_eq!(ProcessedMarker::parse(&value), Some(marker));
+    }
+
+    #[test]
+    fn unknown_or_foreign_tags_are_not_markers() {
+        assert_eq!(ProcessedMarker::parse(""), None);
+        assert_eq!(ProcessedMarker::parse("encoded by someone else"), None);
+        assert_eq!(
+            ProcessedMarker::parse("v=2;version=9.0;settings=abcdef"),
+            None
+        );
+        assert_eq!(ProcessedMarker::parse("v=1;version=0.3.5"), None);
+    }
+
+    #[test]
+    fn digests_are_short_and_stable() {
+        let first = digest(&("hevc", 24));
+        assert_eq!(first.len(), DIGEST_LEN);
+        assert_eq!(first, digest(&("hevc", 24)));
+        assert_ne!(first, digest(&("hevc", 26)));
+    }
+}
@@ -1,8 +1,22 @@
 pub mod analyzer;
+pub mod estimate;
 pub mod executor;
 pub mod ffmpeg;
+pub mod glob;
+pub mod hdr10_plus;
 pub mod health;
+pub mod hooks;
+pub mod ignore;
+pub mod loudness;
+pub mod marker;
+pub mod output_template;
+pub mod overrides;
 pub mod pipeline;
 pub mod planner;
 pub mod processor;
+pub mod profile_rules;
+pub mod quality_search;
+pub mod recycle;
 pub mod scanner;
+pub mod sidecars;
+pub mod variants;
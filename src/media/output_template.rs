@@ -0,0 +1,647 @@
+//! Output filename templates.
+//!
+//! `files.output_template` replaces suffix-plus-extension naming with a
+//! pattern such as `{parent}/{stem} [{codec} {height}p].{ext}` or
+//! `{root}/{library}/{relative_dir}/{stem}.{ext}`. Path tokens are filled in
+//! when a file is enqueued. Media tokens depend on the plan, so they stay in
+//! the job's output path as `{token}` placeholders until the job is planned
+//! and are resolved then. A relative template is placed beside the source.
+//! `{{` and `}}` write literal braces.
+
+use crate::media::pipeline::{
+    AudioStreamPlan, DynamicRange, FilterStep, MediaMetadata, TranscodePlan,
+};
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Stem,
+    Ext,
+    Suffix,
+    Parent,
+    Root,
+    Library,
+    RelativeDir,
+    Codec,
+    Width,
+    Height,
+    Hdr,
+    AudioCodec,
+    Profile,
+}
+
+/// Values `{hdr}` renders to.
+const HDR_LABELS: [&str; 5] = ["SDR", "HDR10", "HDR10+", "HLG", "DV"];
+
+/// Video codec names `{codec}` can render to: the output codecs, plus the
+/// common source codecs a remux keeps.
+const VIDEO_CODECS: [&str; 9] = [
+    "av1",
+    "hevc",
+    "h264",
+    "mpeg2video",
+    "mpeg4",
+    "vc1",
+    "vp8",
+    "vp9",
+    "prores",
+];
+
+const AUDIO_CODECS: [&str; 12] = [
+    "aac", "opus", "mp3", "ac3", "eac3", "dts", "truehd", "flac", "vorbis", "alac", "mp2", "none",
+];
+
+impl Token {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "stem" => Self::Stem,
+            "ext" => Self::Ext,
+            "suffix" => Self::Suffix,
+            "parent" => Self::Parent,
+            "root" => Self::Root,
+            "library" => Self::Library,
+            "relative_dir" => Self::RelativeDir,
+            "codec" => Self::Codec,
+            "width" => Self::Width,
+            "height" => Self::Height,
+            "hdr" => Self::Hdr,
+            "audio_codec" => Self::AudioCodec,
+            "profile" => Self::Profile,
+            _ => return None,
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Stem => "stem",
+            Self::Ext => "ext",
+            Self::Suffix => "suffix",
+            Self::Parent => "parent",
+            Self::Root => "root",
+            Self::Library => "library",
+            Self::RelativeDir => "relative_dir",
+            Self::Codec => "codec",
+            Self::Width => "width",
+            Self::Height => "height",
+            Self::Hdr => "hdr",
+            Self::AudioCodec => "audio_codec",
+            Self::Profile => "profile",
+        }
+    }
+
+    /// Known only once the job is planned.
+    fn is_media(self) -> bool {
+        matches!(
+            self,
+            Self::Codec | Self::Width | Self::Height | Self::Hdr | Self::AudioCodec | Self::Profile
+        )
+    }
+
+    /// Expands to a directory path rather than a single name.
+    fn is_directory(self) -> bool {
+        matches!(self, Self::Parent | Self::Root | Self::RelativeDir)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Token(Token),
+}
+
+/// Path-derived token values, known when a file is enqueued.
+#[derive(Debug, Clone)]
+pub struct PathValues {
+    pub stem: String,
+    pub ext: String,
+    pub suffix: String,
+    pub parent: PathBuf,
+    /// `output_root` when set, else the watch folder, else `parent`.
+    pub root: PathBuf,
+    /// Name of the watch folder the source was found in.
+    pub library: String,
+    /// Source directory relative to its watch folder; empty at the top.
+    pub relative_dir: PathBuf,
+}
+
+impl PathValues {
+    pub fn for_source(
+        input_path: &Path,
+        source_root: Option<&Path>,
+        output_extension: &str,
+        output_suffix: &str,
+        output_root: Option<&str>,
+    ) -> Self {
+        let parent = input_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let source_root = source_root.filter(|root| input_path.starts_with(root));
+        let relative_dir = source_root
+            .and_then(|root| parent.strip_prefix(root).ok())
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let library = source_root
+            .unwrap_or(&parent)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let root = output_root
+            .map(str::trim)
+            .filter(|root| !root.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| source_root.map(Path::to_path_buf))
+            .unwrap_or_else(|| parent.clone());
+        Self {
+            stem: input_path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            ext: output_extension.trim_start_matches('.').to_string(),
+            suffix: output_suffix.to_string(),
+            parent,
+            root,
+            library,
+            relative_dir,
+        }
+    }
+}
+
+/// Plan-derived token values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaValues {
+    pub codec: String,
+    pub width: u32,
+    pub height: u32,
+    pub hdr: &'static str,
+    pub audio_codec: String,
+    pub profile: String,
+}
+
+impl MediaValues {
+    pub fn from_plan(
+        metadata: &MediaMetadata,
+        plan: &TranscodePlan,
+        profile: Option<&str>,
+    ) -> Self {
+        let codec = if plan.copy_video {
+            metadata.codec_name.to_ascii_lowercase()
+        } else {
+            plan.output_codec
+                .unwrap_or(plan.requested_codec)
+                .as_str()
+                .to_string()
+        };
+
+        let (mut width, mut height) = (metadata.width, metadata.height);
+        if !plan.copy_video {
+            for filter in &plan.filters {
+                match filter {
+                    FilterStep::Crop {
+                        width: w,
+                        height: h,
+                        ..
+                    }
+                    | FilterStep::Scale {
+                        width: w,
+                        height: h,
+                    }
+                    | FilterStep::HwScale {
+                        width: w,
+                        height: h,
+                        ..
+                    } => (width, height) = (*w, *h),
+                    _ => {}
+                }
+            }
+        }
+
+        let tonemapped = !plan.copy_video
+            && plan.filters.iter().any(|filter| {
+                matches!(
+                    filter,
+                    FilterStep::Tonemap { .. } | FilterStep::StripHdrMetadata
+                )
+            });
+        let hdr = match metadata.dynamic_range {
+            _ if tonemapped => "SDR",
+            DynamicRange::DolbyVision if plan.copy_video || plan.preserve_dolby_vision => "DV",
+            DynamicRange::DolbyVision => "HDR10",
+            DynamicRange::Hdr10 if plan.preserve_hdr10_plus => "HDR10+",
+            DynamicRange::Hdr10 => "HDR10",
+            DynamicRange::Hlg => "HLG",
+            DynamicRange::Sdr | DynamicRange::Unknown => "SDR",
+        };
+
+        let audio_codec = plan
+            .audio
+            .iter()
+            .find(|track| track.is_output())
+            .map(|track| match &track.action {
+                AudioStreamPlan::Transcode { codec, .. }
+                | AudioStreamPlan::DeriveDownmix { codec, .. } => codec.as_str().to_string(),
+                AudioStreamPlan::Copy | AudioStreamPlan::Drop => metadata
+                    .audio_streams
+                    .iter()
+                    .find(|stream| Some(stream.stream_index) == track.source_index)
+                    .map(|stream| stream.codec_name.clone())
+                    .or_else(|| metadata.audio_codec.clone())
+                    .unwrap_or_else(|| "none".to_string())
+                    .to_ascii_lowercase(),
+            })
+            .unwrap_or_else(|| "none".to_string());
+
+        Self {
+            codec,
+            width,
+            height,
+            hdr,
+            audio_codec,
+            profile: profile.unwrap_or("default").to_string(),
+        }
+    }
+
+    fn value(&self, token: Token) -> Option<String> {
+        Some(match token {
+            Token::Codec => self.codec.clone(),
+            Token::Width => self.width.to_string(),
+            Token::Height => self.height.to_string(),
+            Token::Hdr => self.hdr.to_string(),
+            Token::AudioCodec => self.audio_codec.clone(),
+            Token::Profile => self.profile.clone(),
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputTemplate {
+    segments: Vec<Segment>,
+}
+
+fn parse_segments(template: &str) -> std::result::Result<Vec<Segment>, String> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some('{') | None => {
+                            return Err(format!("unclosed '{{' in output template \"{template}\""));
+                        }
+                        Some(ch) => name.push(ch),
+                    }
+                }
+                let token = Token::from_name(name.trim())
+                    .ok_or_else(|| format!("unknown output template token {{{name}}}"))?;
+                if !literal.is_empty() {
+                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                }
+                segments.push(Segment::Token(token));
+            }
+            '}' => {
+                return Err(format!(
+                    "unmatched '}}' in output template \"{template}\"; write '}}}}' for a literal brace"
+                ));
+            }
+            ch => literal.push(ch),
+        }
+    }
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+    Ok(segments)
+}
+
+fn is_separator(ch: char) -> bool {
+    ch == '/' || ch == '\\'
+}
+
+fn escape_braces(value: &str) -> String {
+    value.replace('{', "{{").replace('}', "}}")
+}
+
+/// Single-name values never introduce extra path levels.
+fn sanitize_name(value: &str) -> String {
+    value.replace(is_separator, "_")
+}
+
+impl OutputTemplate {
+    /// Parse and validate a template: known tokens, balanced braces, no `..`
+    /// segments, and a file name that includes `{stem}` so distinct sources
+    /// never share an output.
+    pub fn parse(template: &str) -> std::result::Result<Self, String> {
+        let template = template.trim();
+        if template.is_empty() {
+            return Err("output template must not be empty".to_string());
+        }
+        let segments = parse_segments(template)?;
+        let parsed = Self { segments };
+
+        let file_name = parsed.file_name_segments();
+        if let Some(token) = file_name.iter().find_map(|segment| match segment {
+            Segment::Token(token) if token.is_directory() => Some(*token),
+            _ => None,
+        }) {
+            return Err(format!(
+                "{{{}}} is a directory and cannot be part of the output file name",
+                token.name()
+            ));
+        }
+        if !file_name.contains(&Segment::Token(Token::Stem)) {
+            return Err(format!(
+                "output template \"{template}\" must include {{stem}} in its file name"
+            ));
+        }
+        let has_parent_segment = parsed.segments.iter().any(|segment| match segment {
+            Segment::Literal(text) => text.split(is_separator).any(|part| part == ".."),
+            Segment::Token(_) => false,
+        });
+        if has_parent_segment {
+            return Err("output template must not contain '..' segments".to_string());
+        }
+        Ok(parsed)
+    }
+
+    /// Segments after the last path separator.
+    fn file_name_segments(&self) -> Vec<Segment> {
+        let mut file_name = Vec::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => match text.rfind(is_separator) {
+                    Some(index) => {
+                        file_name.clear();
+                        let rest = &text[index + 1..];
+                        if !rest.is_empty() {
+                            file_name.push(Segment::Literal(rest.to_string()));
+                        }
+                    }
+                    None => file_name.push(segment.clone()),
+                },
+                Segment::Token(token) if token.is_directory() => {
+                    file_name.clear();
+                    file_name.push(segment.clone());
+                }
+                Segment::Token(_) => file_name.push(segment.clone()),
+            }
+        }
+        file_name
+    }
+
+    pub fn has_media_tokens(&self) -> bool {
+        self.segments
+            .iter()
+            .any(|segment| matches!(segment, Segment::Token(token) if token.is_media()))
+    }
+
+    /// Render the output path for a source. Without `media`, media tokens
+    /// are left as `{token}` placeholders (and literal braces escaped) for
+    /// [`resolve_placeholders`] to fill in once the job is planned.
+    pub fn render(&self, path: &PathValues, media: Option<&MediaValues>) -> PathBuf {
+        let placeholders = media.is_none() && self.has_media_tokens();
+        let escape = |value: &str| {
+            if placeholders {
+                escape_braces(value)
+            } else {
+                value.to_string()
+            }
+        };
+        let mut rendered = String::new();
+        for segment in &self.segments {
+            let value = match segment {
+                Segment::Literal(text) => escape(text),
+                Segment::Token(token) => match token {
+                    Token::Stem => escape(&sanitize_name(&path.stem)),
+                    Token::Ext => escape(&sanitize_name(&path.ext)),
+                    Token::Suffix => escape(&sanitize_name(&path.suffix)),
+                    Token::Library => escape(&sanitize_name(&path.library)),
+                    Token::Parent => escape(&path.parent.to_string_lossy()),
+                    Token::Root => escape(&path.root.to_string_lossy()),
+                    Token::RelativeDir => escape(&path.relative_dir.to_string_lossy()),
+                    media_token => match media.and_then(|media| media.value(*media_token)) {
+                        Some(value) => sanitize_name(&value),
+                        None => format!("{{{}}}", media_token.name()),
+                    },
+                },
+            };
+            rendered.push_str(&value);
+        }
+
+        let rendered = normalize(Path::new(&rendered));
+        if rendered.is_absolute() {
+            rendered
+        } else {
+            path.parent.join(rendered)
+        }
+    }
+
+    /// Whether `file_name` could have been produced by this template with the
+    /// given extension and suffix. Templates whose file name has nothing to
+    /// tell an output from a source (such as `{stem}.{ext}`) never match, so
+    /// their outputs are recognized through job tracking instead.
+    pub fn matches_file_name(&self, file_name: &str, ext: &str, suffix: &str) -> bool {
+        let segments = self.file_name_segments();
+        let distinctive = segments.iter().any(|segment| match segment {
+            Segment::Literal(text) => text != ".",
+            Segment::Token(Token::Suffix) => !suffix.is_empty(),
+            Segment::Token(token) => matches!(
+                token,
+                Token::Codec | Token::Width | Token::Height | Token::Hdr | Token::AudioCodec
+            ),
+        });
+        distinctive && match_segments(&segments, file_name, ext.trim_start_matches('.'), suffix)
+    }
+}
+
+fn token_accepts(token: Token, value: &str, ext: &str, suffix: &str) -> bool {
+    match token {
+        Token::Ext => value.eq_ignore_ascii_case(ext),
+        Token::Suffix => value == suffix,
+        Token::Codec => VIDEO_CODECS.contains(&value),
+        Token::Width | Token::Height => {
+            !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit())
+        }
+        Token::Hdr => HDR_LABELS.contains(&value),
+        Token::AudioCodec => AUDIO_CODECS.contains(&value) || value.starts_with("pcm_"),
+        Token::Stem | Token::Library | Token::Profile => !value.is_empty(),
+        Token::Parent | Token::Root | Token::RelativeDir => false,
+    }
+}
+
+fn match_segments(segments: &[Segment], name: &str, ext: &str, suffix: &str) -> bool {
+    let Some((first, rest)) = segments.split_first() else {
+        return name.is_empty();
+    };
+    match first {
+        Segment::Literal(text) => name
+            .strip_prefix(text.as_str())
+            .is_some_and(|name| match_segments(rest, name, ext, suffix)),
+        Segment::Token(token) => (0..=name.len())
+            .filter(|end| name.is_char_boundary(*end))
+            .any(|end| {
+                token_accepts(*token, &name[..end], ext, suffix)
+                    && match_segments(rest, &name[end..], ext, suffix)
+            }),
+    }
+}
+
+/// Drop empty and `.` components so empty tokens such as a top-level
+/// `{relative_dir}` do not leave `//` behind.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect()
+}
+
+/// Fill in the media placeholders a templated output path kept from
+/// enqueue. `None` when `path` has no placeholders left to resolve.
+pub fn resolve_placeholders(path: &Path, media: &MediaValues) -> Option<PathBuf> {
+    let segments = parse_segments(path.to_str()?).ok()?;
+    let has_media = segments
+        .iter()
+        .any(|segment| matches!(segment, Segment::Token(token) if token.is_media()));
+    let only_media = segments
+        .iter()
+        .all(|segment| !matches!(segment, Segment::Token(token) if !token.is_media()));
+    if !has_media || !only_media {
+        return None;
+    }
+    let mut rendered = String::new();
+    for segment in &segments {
+        match segment {
+            Segment::Literal(text) => rendered.push_str(text),
+            Segment::Token(token) => {
+                rendered.push_str(&sanitize_name(&media.value(*token).unwrap_or_default()));
+            }
+        }
+    }
+    Some(PathBuf::from(rendered))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path_values(input: &str, root: Option<&str>, output_root: Option<&str>) -> PathValues {
+        PathValues::for_source(
+            Path::new(input),
+            root.map(Path::new),
+            "mkv",
+            "-alchemist",
+            output_root,
+        )
+    }
+
+    fn media() -> MediaValues {
+        MediaValues {
+            codec: "hevc".to_string(),
+            width: 1920,
+            height: 1080,
+            hdr: "SDR",
+            audio_codec: "aac".to_string(),
+            profile: "Balanced".to_string(),
+        }
+    }
+
+    #[test]
+    fn templates_reject_unknown_tokens_and_unsafe_names() {
+        assert!(OutputTemplate::parse("{parent}/{stem} [{codec} {height}p].{ext}").is_ok());
+        assert!(OutputTemplate::parse("{root}/{library}/{relative_dir}/{stem}.{ext}").is_ok());
+        for (template, error) in [
+            ("{stem}.{extension}", "unknown output template token"),
+            ("{stem.{ext}", "unclosed"),
+            ("{stem}}.{ext}", "unmatched"),
+            ("{parent}/{codec}.{ext}", "must include {stem}"),
+            ("{stem}/{parent}.{ext}", "is a directory"),
+            ("../{stem}.{ext}", "'..'"),
+            ("  ", "must not be empty"),
+        ] {
+            let err = OutputTemplate::parse(template).err().unwrap_or_default();
+            assert!(err.contains(error), "{template}: {err}");
+        }
+    }
+
+    #[test]
+    fn media_tokens_stay_placeholders_until_the_plan_is_known() {
+        let template = OutputTemplate::parse("{parent}/{stem} [{codec} {height}p].{ext}")
+            .unwrap_or_else(|err| panic!("{err}"));
+        let values = path_values("/media/movies/Heat {1995}.mp4", Some("/media"), None);
+
+        let provisional = template.render(&values, None);
+        assert_eq!(
+            provisional,
+            PathBuf::from("/media/movies/Heat {{1995}} [{codec} {height}p].mkv")
+        );
+        assert_eq!(
+            resolve_placeholders(&provisional, &media()),
+            Some(PathBuf::from("/media/movies/Heat {1995} [hevc 1080p].mkv"))
+        );
+        assert_eq!(
+            template.render(&values, Some(&media())),
+            PathBuf::from("/media/movies/Heat {1995} [hevc 1080p].mkv")
+        );
+        assert_eq!(
+            resolve_placeholders(Path::new("/media/movies/Heat.mkv"), &media()),
+            None
+        );
+    }
+
+    #[test]
+    fn path_tokens_mirror_the_library_layout() {
+        let template = OutputTemplate::parse("{root}/{library}/{relative_dir}/{stem}.{ext}")
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(
+            template.render(
+                &path_values("/srv/tv/Show/S01/e1.mp4", Some("/srv/tv"), Some("/encoded")),
+                None
+            ),
+            PathBuf::from("/encoded/tv/Show/S01/e1.mkv")
+        );
+        assert_eq!(
+            template.render(
+                &path_values("/srv/tv/e1.mp4", Some("/srv/tv"), Some("/encoded")),
+                None
+            ),
+            PathBuf::from("/encoded/tv/e1.mkv")
+        );
+
+        let relative =
+            OutputTemplate::parse("{stem}-{profile}.{ext}").unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(
+            relative.render(&path_values("/srv/tv/e1.mp4", None, None), Some(&media())),
+            PathBuf::from("/srv/tv/e1-Balanced.mkv")
+        );
+    }
+
+    #[test]
+    fn rendered_outputs_match_their_template() {
+        let template = OutputTemplate::parse("{parent}/{stem} [{codec} {height}p].{ext}")
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert!(template.matches_file_name("Heat [hevc 1080p].mkv", "mkv", "-alchemist"));
+        assert!(template.matches_file_name("Heat [x] [av1 2160p].MKV", "mkv", "-alchemist"));
+        assert!(!template.matches_file_name("Heat [hevc 1080p].mp4", "mkv", "-alchemist"));
+        assert!(!template.matches_file_name("Heat [director cut].mkv", "mkv", "-alchemist"));
+        assert!(!template.matches_file_name("Heat.mkv", "mkv", "-alchemist"));
+
+        let dotted =
+            OutputTemplate::parse("{stem}.{codec}.{ext}").unwrap_or_else(|err| panic!("{err}"));
+        assert!(dotted.matches_file_name("Movie.2020.hevc.mkv", "mkv", ""));
+        assert!(!dotted.matches_file_name("Movie.2020.mkv", "mkv", ""));
+
+        let plain = OutputTemplate::parse("/encoded/{relative_dir}/{stem}.{ext}")
+            .unwrap_or_else(|err| panic!("{err}"));
+        assert!(!plain.matches_file_name("Movie.mkv", "mkv", "-alchemist"));
+    }
+}
@@ -0,0 +1,131 @@
+//! Per-job setting overrides supplied when a file is enqueued.
+//!
+//! Overrides let automation encode one file differently without a throwaway
+//! library profile. They are stored beside the job and layered over whatever
+//! profile the job resolves to when it is planned, so they survive retries
+//! and restarts. Unset fields fall through to the profile, then to the
+//! global transcode settings.
+
+use crate::config::{AudioMode, HdrMode, OutputCodec, QualityProfile, StreamRules};
+use crate::db::FileSettings;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobOverrides {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub codec: Option<OutputCodec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality_profile: Option<QualityProfile>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crf: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hdr_mode: Option<HdrMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_mode: Option<AudioMode>,
+    /// Replaces the global stream rules as a whole.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_rules: Option<StreamRules>,
+    /// Absolute output path, used instead of the file settings' naming.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_path: Option<String>,
+    /// `keep` or `replace`, for an output that already exists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replace_strategy: Option<String>,
+    /// Whether to remove the original once the output is in place.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delete_source: Option<bool>,
+}
+
+impl JobOverrides {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Reject overrides the pipeline could not act on.
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if self.crf.is_some_and(|crf| !(0..=63).contains(&crf)) {
+            return Err("crf must be between 0 and 63".to_string());
+        }
+        if let Some(path) = &self.output_path
+            && !Path::new(path.trim()).is_absolute()
+        {
+            return Err("output_path must be absolute".to_string());
+        }
+        if let Some(strategy) = &self.replace_strategy
+            && !["keep", "replace"].contains(&strategy.trim().to_ascii_lowercase().as_str())
+        {
+            return Err("replace_strategy must be keep or replace".to_string());
+        }
+        Ok(())
+    }
+
+    /// `settings` with the replace strategy and source deletion overridden.
+    pub fn apply_to_file_settings(&self, settings: &FileSettings) -> FileSettings {
+        let mut settings = settings.clone();
+        if let Some(strategy) = &self.replace_strategy {
+            settings.replace_strategy = strategy.trim().to_ascii_lowercase();
+        }
+        if let Some(delete_source) = self.delete_source {
+            settings.delete_source = delete_source;
+        }
+        settings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_parse_sparse_payloads_and_reject_unknown_fields() {
+        let Ok(overrides) = serde_json::from_value::<JobOverrides>(serde_json::json!({
+            "codec": "av1",
+            "crf": 28,
+            "stream_rules": { "strip_audio_by_title": ["commentary"] },
+            "delete_source": false
+        })) else {
+            panic!("expected overrides to parse");
+        };
+        assert_eq!(overrides.codec, Some(OutputCodec::Av1));
+        assert_eq!(overrides.crf, Some(28));
+        assert!(overrides.validate().is_ok());
+        assert!(!overrides.is_empty());
+
+        assert!(serde_json::from_value::<JobOverrides>(serde_json::json!({ "crff": 28 })).is_err());
+    }
+
+    #[test]
+    fn overrides_validate_ranges_and_paths() {
+        let crf = JobOverrides {
+            crf: Some(80),
+            ..JobOverrides::default()
+        };
+        assert!(crf.validate().is_err());
+        let relative = JobOverrides {
+            output_path: Some("out/movie.mkv".to_string()),
+            ..JobOverrides::default()
+        };
+        assert!(relative.validate().is_err());
+        let strategy = JobOverrides {
+            replace_strategy: Some("sometimes".to_string()),
+            ..JobOverrides::default()
+        };
+        assert!(strategy.validate().is_err());
+    }
+
+    #[test]
+    fn file_settings_take_replace_strategy_and_delete_source() {
+        let settings = crate::media::pipeline::default_file_settings();
+        let overrides = JobOverrides {
+            replace_strategy: Some("Replace".to_string()),
+            delete_source: Some(true),
+            ..JobOverrides::default()
+        };
+        let merged = overrides.apply_to_file_settings(&settings);
+        assert!(merged.should_replace_existing_output());
+        assert!(merged.delete_source);
+        assert_eq!(merged.output_suffix, settings.output_suffix);
+    }
+}
@@ -2,10 +2,11 @@ use crate::db::Job;
 use crate::error::Result;
 use crate::media::analyzer::FfmpegAnalyzer;
 use crate::media::executor::FfmpegExecutor;
+use crate::media::output_template::{MediaValues, resolve_placeholders};
 use crate::media::planner::BasicPlanner;
 use crate::orchestrator::AsyncExecutionObserver;
 use crate::orchestrator::Transcoder;
-use crate::system::hardware::HardwareState;
+use crate::system::hardware::{HardwareInfo, HardwareState};
 use crate::telemetry::{TelemetryEvent, encoder_label, hardware_label, resolution_bucket};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -41,6 +42,9 @@ pub struct MediaMetadata {
     pub dynamic_range: DynamicRange,
     #[serde(default)]
     pub chapter_count: u32,
+    /// Processed-by marker found in the file; see [`crate::media::marker`].
+    #[serde(default)]
+    pub alchemist_marker: Option<crate::media::marker::ProcessedMarker>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -104,8 +108,12 @@ pub enum AnalyzerLabel {
     HdrMetadata,
     Bt2020WithoutTransfer,
     DolbyVisionMetadata,
+    /// SMPTE ST 2094-40 dynamic metadata, from stream or frame side data.
+    Hdr10PlusMetadata,
     InterlacedMetadata,
     VariableFrameRateHint,
+    BlackBars,
+    VariableCrop,
     MissingVideoBitrate,
     MissingContainerBitrate,
     MissingDuration,
@@ -144,6 +152,28 @@ pub struct AnalyzerMetrics {
     pub fps_from_average_rate: Option<f64>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub fps_from_frame_count: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crop_width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crop_height: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crop_x: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crop_y: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crop_sample_count: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crop_samples_agree: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idet_interlaced_frames: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idet_progressive_frames: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interlace_confirmed: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dolby_vision_profile: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dolby_vision_bl_compat_id: Option<u32>,
 }
 
 impl AnalyzerMetrics {
@@ -169,7 +199,7 @@ pub struct MediaAnalysis {
     pub analysis_report: AnalyzerReport,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum DynamicRange {
     Sdr,
@@ -363,6 +393,31 @@ pub struct PlannedFallback {
     pub reason: String,
 }
 
+/// Source metadata the plan knowingly loses. Unlike a fallback the encode
+/// itself is unchanged; the loss is surfaced next to the decision.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DowngradeKind {
+    Hdr10PlusToHdr10,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PlannedDowngrade {
+    pub kind: DowngradeKind,
+    pub reason: String,
+}
+
+impl PlannedDowngrade {
+    pub fn message(&self) -> String {
+        match self.kind {
+            DowngradeKind::Hdr10PlusToHdr10 => format!(
+                "HDR10+ dynamic metadata will be dropped; the output keeps static HDR10 metadata only ({})",
+                self.reason
+            ),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum AudioCodec {
@@ -372,6 +427,14 @@ pub enum AudioCodec {
 }
 
 impl AudioCodec {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Aac => "aac",
+            Self::Opus => "opus",
+            Self::Mp3 => "mp3",
+        }
+    }
+
     pub fn ffmpeg_name(&self) -> &'static str {
         match self {
             Self::Aac => "aac",
@@ -381,7 +444,8 @@ impl AudioCodec {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// What happens to one source audio stream.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum AudioStreamPlan {
     Copy,
@@ -389,23 +453,125 @@ pub enum AudioStreamPlan {
         codec: AudioCodec,
         bitrate_kbps: u16,
         channels: Option<u32>,
+        /// EBU R128 normalization. Only re-encoded tracks carry one, so copied
+        /// streams are never touched.
+        #[serde(default)]
+        loudness: Option<LoudnessNormalization>,
+    },
+    /// An extra track mixed down from the source stream. The source keeps
+    /// its own entry, so this never replaces the original track.
+    DeriveDownmix {
+        codec: AudioCodec,
+        bitrate_kbps: u16,
+        channels: u32,
     },
     Drop,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Two-pass `loudnorm` settings for one re-encoded audio track.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LoudnessNormalization {
+    pub target_lufs: f64,
+    pub true_peak_dbtp: f64,
+    pub lra: f64,
+    /// First-pass measurement. The second pass only runs once this is set;
+    /// a track whose measurement failed keeps its loudness.
+    #[serde(default)]
+    pub measured: Option<LoudnessMeasurement>,
+}
+
+impl LoudnessNormalization {
+    pub fn new(target_lufs: f64) -> Self {
+        Self {
+            target_lufs,
+            true_peak_dbtp: crate::media::loudness::DEFAULT_TRUE_PEAK_DBTP,
+            lra: crate::media::loudness::DEFAULT_LRA,
+            measured: None,
+        }
+    }
+}
+
+/// Values `loudnorm` reports after the measurement pass.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LoudnessMeasurement {
+    /// Integrated loudness, LUFS.
+    pub input_i: f64,
+    /// True peak, dBTP.
+    pub input_tp: f64,
+    /// Loudness range, LU.
+    pub input_lra: f64,
+    pub input_thresh: f64,
+    pub target_offset: f64,
+}
+
+/// One entry of the per-stream audio plan. Output tracks follow the order of
+/// the non-`Drop` entries.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AudioTrackPlan {
+    /// Audio-relative source stream (`0:a:N`). `None` applies the action to
+    /// every audio stream, for files analyzed without per-stream metadata.
+    pub source_index: Option<usize>,
+    pub action: AudioStreamPlan,
+    /// Dispositions written on the output track. Derived tracks are never
+    /// default, so only their source stream carries the flag.
+    pub default: bool,
+    pub language: Option<String>,
+}
+
+impl AudioTrackPlan {
+    /// Single entry that treats every source audio stream the same way.
+    pub fn all_streams(action: AudioStreamPlan) -> Self {
+        Self {
+            source_index: None,
+            action,
+            default: false,
+            language: None,
+        }
+    }
+
+    pub fn is_output(&self) -> bool {
+        !matches!(self.action, AudioStreamPlan::Drop)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum SubtitleStreamPlan {
     CopyAllCompatible,
     Drop,
-    Burn { stream_index: usize },
-    Extract { outputs: Vec<SidecarOutputPlan> },
+    Burn {
+        stream_index: usize,
+    },
+    Extract {
+        outputs: Vec<SidecarOutputPlan>,
+    },
+    /// Individually mapped streams, chosen by the subtitle stream rules or
+    /// because the container cannot carry every source stream as-is.
+    Select {
+        streams: Vec<SubtitleTrackPlan>,
+        /// Streams the container cannot carry, written next to the output.
+        #[serde(default)]
+        sidecars: Vec<SidecarOutputPlan>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SubtitleTrackPlan {
+    /// Subtitle-relative source stream (`0:s:N`).
+    pub stream_index: usize,
+    /// Codec to convert to (for example `mov_text` for MP4). `None` copies.
+    #[serde(default)]
+    pub convert_to: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct SidecarOutputPlan {
     pub stream_index: usize,
     pub codec: String,
+    /// FFmpeg muxer when it differs from `codec`, such as `matroska` for
+    /// image subtitles copied into `.mks` files.
+    #[serde(default)]
+    pub muxer: Option<String>,
     pub final_path: PathBuf,
     pub temp_path: PathBuf,
 }
@@ -414,14 +580,53 @@ impl SubtitleStreamPlan {
     pub fn sidecar_outputs(&self) -> &[SidecarOutputPlan] {
         match self {
             SubtitleStreamPlan::Extract { outputs } => outputs.as_slice(),
+            SubtitleStreamPlan::Select { sidecars, .. } => sidecars.as_slice(),
             _ => &[],
         }
     }
+
+    pub fn sidecar_outputs_mut(&mut self) -> &mut [SidecarOutputPlan] {
+        match self {
+            SubtitleStreamPlan::Extract { outputs } => outputs.as_mut_slice(),
+            SubtitleStreamPlan::Select { sidecars, .. } => sidecars.as_mut_slice(),
+            _ => &mut [],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HwScaler {
+    Vaapi,
+    Qsv,
+    Cuda,
+}
+
+impl HwScaler {
+    pub fn filter_name(&self) -> &'static str {
+        match self {
+            Self::Vaapi => "scale_vaapi",
+            Self::Qsv => "scale_qsv",
+            Self::Cuda => "scale_cuda",
+        }
+    }
+}
+
+/// Where a deinterlace step runs. Hardware deinterlacers pick their own
+/// algorithm; the configured one only applies in software.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeinterlaceBackend {
+    Software,
+    Vaapi,
+    Qsv,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case", tag = "kind")]
 pub enum FilterStep {
+    /// Drops Dolby Vision RPU side data so only the HDR10 base layer is encoded.
+    StripDolbyVision,
     Tonemap {
         algorithm: crate::config::TonemapAlgorithm,
         peak: f32,
@@ -434,10 +639,29 @@ pub enum FilterStep {
         stream_index: usize,
     },
     HwUpload,
+    Deinterlace {
+        algorithm: crate::config::DeinterlaceAlgorithm,
+        rate: crate::config::DeinterlaceRate,
+        backend: DeinterlaceBackend,
+    },
+    /// Uploads software frames to a CUDA device for `scale_cuda`.
+    HwUploadCuda,
+    Crop {
+        width: u32,
+        height: u32,
+        x: u32,
+        y: u32,
+    },
     Scale {
         width: u32,
         height: u32,
     },
+    /// Scales frames that already live on the GPU, after the hwupload step.
+    HwScale {
+        scaler: HwScaler,
+        width: u32,
+        height: u32,
+    },
     StripHdrMetadata,
     Custom {
         filter: String,
@@ -458,14 +682,24 @@ pub struct TranscodePlan {
     pub rate_control: Option<RateControl>,
     pub encoder_preset: Option<String>,
     pub threads: usize,
-    pub audio: AudioStreamPlan,
-    /// If Some, only these audio stream indices are mapped.
-    /// If None, all audio streams are mapped (default behavior).
-    pub audio_stream_indices: Option<Vec<usize>>,
+    /// Per-stream audio plan. An empty list, or one with only `Drop`
+    /// entries, produces a file without audio.
+    pub audio: Vec<AudioTrackPlan>,
     pub subtitles: SubtitleStreamPlan,
     pub filters: Vec<FilterStep>,
     pub allow_fallback: bool,
     pub fallback: Option<PlannedFallback>,
+    /// Carry the source's Dolby Vision RPU into the encoded stream.
+    #[serde(default)]
+    pub preserve_dolby_vision: bool,
+    /// Carry HDR10+ dynamic metadata into the encoded stream.
+    #[serde(default)]
+    pub preserve_hdr10_plus: bool,
+    #[serde(default)]
+    pub downgrades: Vec<PlannedDowngrade>,
+    /// Processed-by marker written into the output.
+    #[serde(default)]
+    pub marker: Option<crate::media::marker::ProcessedMarker>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -522,6 +756,14 @@ pub struct Pipeline {
     hardware_state: HardwareState,
     event_channels: Arc<crate::db::EventChannels>,
     dry_run: bool,
+    /// Encoder backends new encodes may use; empty allows all.
+    allowed_backends: Vec<EncoderBackend>,
+}
+
+/// What [`Pipeline::planned_encode`] expects a job's plan to need.
+pub(crate) struct PlannedEncode {
+    pub codec: crate::config::OutputCodec,
+    pub target_vmaf: Option<f64>,
 }
 
 struct FinalizeJobContext<'a> {
@@ -696,15 +938,91 @@ impl Pipeline {
             hardware_state,
             event_channels,
             dry_run,
+            allowed_backends: Vec::new(),
         }
     }
 
+    /// Restrict encodes to these backends, e.g. for the active schedule window.
+    pub fn with_allowed_backends(mut self, allowed_backends: Vec<EncoderBackend>) -> Self {
+        self.allowed_backends = allowed_backends;
+        self
+    }
+
+    pub(crate) fn allowed_backends(&self) -> &[EncoderBackend] {
+        &self.allowed_backends
+    }
+
+    /// How `job` will be planned: the output codec from its overrides, then
+    /// the profile chosen by profile rules or its watch folder, then the
+    /// global setting, and the profile's VMAF target unless an explicit CRF
+    /// replaces it. Reads the file's cached analysis when there is one.
+    pub(crate) async fn planned_encode(&self, job: &crate::db::Job) -> Result<PlannedEncode> {
+        let analysis = FfmpegAnalyzer
+            .analyze_with_cache(&self.db, Path::new(&job.input_path))
+            .await?;
+        let selection = crate::media::profile_rules::select_profile(&self.db, &analysis).await?;
+        let overrides = self.job_overrides(job.id).await;
+        let target_vmaf = selection
+            .profile
+            .as_ref()
+            .and_then(|profile| profile.target_vmaf)
+            .filter(|_| overrides.as_ref().is_none_or(|o| o.crf.is_none()));
+        let config = self.config.read().await;
+        let codec = crate::media::planner::EffectiveSettings::resolve(
+            &config,
+            selection.profile.as_ref(),
+            overrides.as_ref(),
+        )
+        .codec;
+        Ok(PlannedEncode { codec, target_vmaf })
+    }
+
     async fn store_job_input_metadata(&self, job_id: i64, metadata: &MediaMetadata) {
         if let Err(err) = self.db.set_job_input_metadata(job_id, metadata).await {
             tracing::warn!(job_id, "Failed to store input metadata: {err}");
         }
     }
 
+    /// Estimate the job's output size and encode time for queue ordering.
+    async fn store_job_estimates(
+        &self,
+        job_id: i64,
+        metadata: &MediaMetadata,
+        plan: &TranscodePlan,
+    ) {
+        let output_codec = plan.output_codec.unwrap_or(plan.requested_codec);
+        let history = match self
+            .db
+            .get_encode_history(&metadata.codec_name, output_codec.as_str(), 50)
+            .await
+        {
+            Ok(history) => history,
+            Err(err) => {
+                tracing::warn!(job_id, "Failed to load encode history: {err}");
+                crate::db::EncodeHistory::default()
+            }
+        };
+        let estimate = crate::media::estimate::estimate_for_plan(metadata, plan, &history);
+        tracing::debug!(
+            job_id,
+            savings_bytes = estimate.estimated_savings_bytes(),
+            history_samples = history.sample_size,
+            "Estimated job output"
+        );
+        if let Err(err) = self
+            .db
+            .set_job_estimates(
+                job_id,
+                estimate.source_size_bytes,
+                estimate.estimated_output_bytes,
+                estimate.estimated_encode_seconds,
+            )
+            .await
+        {
+            tracing::warn!(job_id, "Failed to store job estimates: {err}");
+        }
+    }
+
     async fn record_job_log(&self, job_id: i64, level: &str, message: &str) {
         if let Err(err) = self.db.add_log(level, Some(job_id), message).await {
             tracing::warn!(job_id, "Failed to record log: {err}");
@@ -915,6 +1233,7 @@ impl Pipeline {
                 observer: Some(observer),
                 clip_start_seconds: Some(segment.start_secs),
                 clip_duration_seconds: Some(segment.duration_secs),
+                hdr10_plus_metadata: None,
             })
             .await;
 
@@ -1181,6 +1500,34 @@ pub async fn enqueue_discovered_with_db(
     }
 }
 
+/// Enqueue one submitted file with its enqueue-time overrides stored on the
+/// job. The output path and replace strategy overrides also govern the
+/// checks made here; `None` clears overrides left by an earlier enqueue.
+pub async fn enqueue_discovered_with_overrides(
+    db: &crate::db::Db,
+    discovered: DiscoveredMedia,
+    overrides: Option<&crate::media::overrides::JobOverrides>,
+) -> Result<bool> {
+    let mut settings = match db.get_file_settings().await {
+        Ok(settings) => settings,
+        Err(e) => {
+            tracing::error!("Failed to fetch file settings, using defaults: {}", e);
+            default_file_settings()
+        }
+    };
+    if let Some(overrides) = overrides {
+        settings = overrides.apply_to_file_settings(&settings);
+    }
+    let output_path = overrides
+        .and_then(|overrides| overrides.output_path.as_deref())
+        .map(|path| PathBuf::from(path.trim()));
+
+    match resolve_discovered(db, &discovered, &settings, output_path).await? {
+        Some(prepared) => db.enqueue_job_with_overrides(&prepared, overrides).await,
+        None => Ok(false),
+    }
+}
+
 /// Resolve a discovered file into a row ready for insertion, or `None` if it
 /// should be skipped (matches a generated-output pattern, already tracked as a
 /// job output, or its output already exists and `replace_strategy = keep`).
@@ -1194,14 +1541,24 @@ pub async fn resolve_discovered_for_enqueue(
     db: &crate::db::Db,
     discovered: &DiscoveredMedia,
     settings: &crate::db::FileSettings,
+) -> Result<Option<crate::db::PreparedEnqueue>> {
+    resolve_discovered(db, discovered, settings, None).await
+}
+
+async fn resolve_discovered(
+    db: &crate::db::Db,
+    discovered: &DiscoveredMedia,
+    settings: &crate::db::FileSettings,
+    output_path: Option<PathBuf>,
 ) -> Result<Option<crate::db::PreparedEnqueue>> {
     if let Some(reason) = skip_reason_for_discovered_path(db, &discovered.path, settings).await? {
         tracing::info!("Skipping {:?} ({})", discovered.path, reason);
         return Ok(None);
     }
 
-    let output_path =
-        settings.output_path_for_source(&discovered.path, discovered.source_root.as_deref());
+    let output_path = output_path.unwrap_or_else(|| {
+        settings.output_path_for_source(&discovered.path, discovered.source_root.as_deref())
+    });
     if output_path.exists() && !settings.should_replace_existing_output() {
         tracing::info!(
             "Skipping {:?} (output exists, replace_strategy = keep)",
@@ -1244,6 +1601,7 @@ pub fn default_file_settings() -> crate::db::FileSettings {
         output_suffix: "-alchemist".to_string(),
         replace_strategy: "keep".to_string(),
         output_root: None,
+        output_template: None,
     }
 }
 
@@ -1262,6 +1620,17 @@ pub(crate) fn matches_generated_output_pattern(
         }
     }
 
+    if let Some(template) = settings.output_template()
+        && path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| {
+                template.matches_file_name(name, expected_extension, &settings.output_suffix)
+            })
+    {
+        return true;
+    }
+
     let suffix = if settings.output_suffix.is_empty() {
         "-alchemist"
     } else {
@@ -1290,6 +1659,40 @@ pub async fn skip_reason_for_discovered_path(
     Ok(None)
 }
 
+/// Where a templated output path landed once the plan was known.
+enum ResolvedOutput {
+    /// The path had no media placeholders left to fill.
+    Unchanged,
+    Resolved(PathBuf),
+    /// The placeholders resolved onto the source file itself.
+    MatchesInput(PathBuf),
+}
+
+/// Whether `output` names the same file as `input`. Both sides are
+/// canonicalized, so `..` segments and symlinked directories cannot hide a
+/// match. An output that does not exist yet is compared through its parent.
+fn output_is_input(output: &Path, input: &Path) -> bool {
+    if output == input {
+        return true;
+    }
+    let Ok(input) = std::fs::canonicalize(input) else {
+        return false;
+    };
+    let output = match std::fs::canonicalize(output) {
+        Ok(output) => output,
+        Err(_) => {
+            let (Some(parent), Some(name)) = (output.parent(), output.file_name()) else {
+                return false;
+            };
+            match std::fs::canonicalize(parent) {
+                Ok(parent) => parent.join(name),
+                Err(_) => return false,
+            }
+        }
+    };
+    output == input
+}
+
 /// Creates a temporary output path for encoding.
 /// Uses a predictable `.alchemist.<job_id>.tmp` suffix - this is acceptable because:
 /// 1. The suffix is unique to Alchemist and unlikely to conflict
@@ -1298,7 +1701,7 @@ pub async fn skip_reason_for_discovered_path(
 ///    paths collide never share (and clobber) the same temp file
 ///
 /// The job id keeps the path deterministic per job, preserving resume semantics.
-fn temp_output_path_for(path: &Path, job_id: i64) -> PathBuf {
+pub(crate) fn temp_output_path_for(path: &Path, job_id: i64) -> PathBuf {
     let parent = path.parent().unwrap_or_else(|| Path::new(""));
     let filename = path
         .file_name()
@@ -1369,7 +1772,10 @@ fn plan_hash_for_resume(
 fn resumable_plan_supported(plan: &TranscodePlan, metadata: &MediaMetadata) -> bool {
     matches!(plan.decision, TranscodeDecision::Transcode { .. })
         && !plan.is_remux
-        && !matches!(plan.subtitles, SubtitleStreamPlan::Extract { .. })
+        && plan.subtitles.sidecar_outputs().is_empty()
+        // x265 matches HDR10+ metadata to frames counted from the start of
+        // the whole source, which a segment would restart.
+        && !plan.preserve_hdr10_plus
         && metadata.duration_secs > 0.0
 }
 
@@ -1469,9 +1875,10 @@ impl Pipeline {
         // Get the output path for planning
         let output_path = std::path::PathBuf::from(&job.output_path);
 
-        // Get profile for this job's input path (if any)
-        let profile = match self.db.get_profile_for_path(&job.input_path).await {
-            Ok(p) => p,
+        // Profile rules first, then the watch folder profile (if any)
+        let selection = match crate::media::profile_rules::select_profile(&self.db, &analysis).await
+        {
+            Ok(selection) => selection,
             Err(e) => {
                 let reason = format!("profile_lookup_failed|error={e}");
                 let failure_explanation = crate::explanations::failure_from_summary(&reason);
@@ -1487,9 +1894,10 @@ impl Pipeline {
         // Run the planner
         let config_snapshot = Arc::new(self.config.read().await.clone());
         let hw_info = self.hardware_state.snapshot().await;
-        let planner = crate::media::planner::BasicPlanner::new(config_snapshot, hw_info);
+        let planner = crate::media::planner::BasicPlanner::new(config_snapshot, hw_info)
+            .with_overrides(self.job_overrides(job_id).await);
         let plan = match planner
-            .plan(&analysis, &output_path, profile.as_ref())
+            .plan_with_selection(&analysis, &output_path, &selection)
             .await
         {
             Ok(p) => p,
@@ -1521,12 +1929,16 @@ impl Pipeline {
             }
             crate::media::pipeline::TranscodeDecision::Remux { reason } => {
                 self.record_job_decision(job_id, "transcode", reason).await;
+                self.store_job_estimates(job_id, &analysis.metadata, &plan)
+                    .await;
                 // Leave as queued — will be picked up for remux when engine starts
                 self.update_job_state(job_id, crate::db::JobState::Queued)
                     .await?;
             }
             crate::media::pipeline::TranscodeDecision::Transcode { reason } => {
                 self.record_job_decision(job_id, "transcode", reason).await;
+                self.store_job_estimates(job_id, &analysis.metadata, &plan)
+                    .await;
                 // Leave as queued — will be picked up for encoding when engine starts
                 self.update_job_state(job_id, crate::db::JobState::Queued)
                     .await?;
@@ -1536,16 +1948,657 @@ impl Pipeline {
         Ok(())
     }
 
-    pub async fn process_job(&self, job: Job) -> std::result::Result<(), JobFailure> {
-        let file_path = PathBuf::from(&job.input_path);
+    /// Queue the output variants the job's profile declares, once the job
+    /// itself completed or was skipped. Each variant runs as a job of its
+    /// own, linked to this one; its failure never changes the job.
+    pub async fn enqueue_variants(&self, job_id: i64) {
+        let job = match self.db.get_job_by_id(job_id).await {
+            Ok(Some(job)) => job,
+            Ok(None) => return,
+            Err(err) => {
+                tracing::warn!(job_id, "Failed to load job for output variants: {err}");
+                return;
+            }
+        };
+        if !matches!(
+            job.status,
+            crate::db::JobState::Completed | crate::db::JobState::Skipped
+        ) {
+            return;
+        }
+        if !matches!(
+            self.db.get_conversion_job_by_linked_job_id(job_id).await,
+            Ok(None)
+        ) || !matches!(self.db.get_variant_link(job_id).await, Ok(None))
+        {
+            return;
+        }
+        let Some(source) = variant_source(&job) else {
+            return;
+        };
+
+        let analysis = match FfmpegAnalyzer.analyze_with_cache(&self.db, &source).await {
+            Ok(analysis) => analysis,
+            Err(err) => {
+                tracing::warn!(
+                    job_id,
+                    "Failed to analyze source for output variants: {err}"
+                );
+                return;
+            }
+        };
+        let profile = match crate::media::profile_rules::select_profile(&self.db, &analysis).await {
+            Ok(selection) => selection.profile,
+            Err(err) => {
+                tracing::warn!(
+                    job_id,
+                    "Failed to resolve profile for output variants: {err}"
+                );
+                return;
+            }
+        };
+        let Some(profile) = profile else {
+            return;
+        };
+        let variants = profile.output_variants();
+        if variants.is_empty() {
+            return;
+        }
+
+        let input_path = PathBuf::from(&job.input_path);
+        let source_root = self.db.get_watch_dirs().await.ok().and_then(|dirs| {
+            dirs.into_iter()
+                .map(|dir| PathBuf::from(dir.path))
+                .filter(|root| input_path.starts_with(root))
+                .max_by_key(|root| root.as_os_str().len())
+        });
+
+        for variant in &variants {
+            let output_path = crate::media::variants::variant_output_path(
+                &input_path,
+                source_root.as_deref(),
+                variant,
+            );
+            if output_path == source
+                || output_path == input_path
+                || output_path == Path::new(&job.output_path)
+            {
+                let message = format!(
+                    "Variant \"{}\" not queued: its output path collides with the job's input or output",
+                    variant.name
+                );
+                tracing::warn!(job_id, "{message}");
+                self.record_job_log(job_id, "warn", &message).await;
+                continue;
+            }
+            let key = crate::media::variants::variant_job_key(&input_path, &variant.name);
+            match self
+                .db
+                .enqueue_variant_job(job_id, &variant.name, &key, &output_path.to_string_lossy())
+                .await
+            {
+                Ok(variant_job_id) => {
+                    let message = format!(
+                        "Variant \"{}\" queued as job {}",
+                        variant.name, variant_job_id
+                    );
+                    tracing::info!(job_id, "{message}");
+                    self.record_job_log(job_id, "info", &message).await;
+                    let _ = self
+                        .event_channels
+                        .jobs
+                        .send(crate::db::JobEvent::StateChanged {
+                            job_id: variant_job_id,
+                            status: crate::db::JobState::Queued,
+                        });
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        job_id,
+                        "Failed to queue output variant {}: {err}",
+                        variant.name
+                    );
+                }
+            }
+        }
+    }
+
+    /// The plan for a variant job: the variant of its parent's profile,
+    /// planned from the parent's source. `None` when the profile no longer
+    /// declares the variant.
+    async fn plan_variant(
+        &self,
+        link: &crate::db::VariantLink,
+        analysis: &MediaAnalysis,
+        output_path: &Path,
+        planner: &BasicPlanner,
+    ) -> Result<Option<TranscodePlan>> {
+        let selection = crate::media::profile_rules::select_profile(&self.db, analysis).await?;
+        let Some(variant) = selection.profile.as_ref().and_then(|profile| {
+            profile
+                .output_variants()
+                .into_iter()
+                .find(|variant| variant.name == link.name)
+                .map(|variant| crate::media::variants::variant_profile(profile, &variant))
+        }) else {
+            return Ok(None);
+        };
+        planner
+            .plan(analysis, output_path, Some(&variant))
+            .await
+            .map(Some)
+    }
 
-        let file_settings = match self.db.get_file_settings().await {
+    async fn job_overrides(&self, job_id: i64) -> Option<crate::media::overrides::JobOverrides> {
+        match self.db.get_job_overrides(job_id).await {
+            Ok(overrides) => overrides,
+            Err(err) => {
+                tracing::warn!(job_id, "Failed to load job overrides: {err}");
+                None
+            }
+        }
+    }
+
+    /// File settings with the job's replace strategy and source deletion
+    /// overrides applied.
+    async fn job_file_settings(&self, job_id: i64) -> crate::db::FileSettings {
+        let settings = match self.db.get_file_settings().await {
             Ok(settings) => settings,
             Err(e) => {
                 tracing::error!("Failed to fetch file settings, using defaults: {}", e);
                 default_file_settings()
             }
         };
+        match self.job_overrides(job_id).await {
+            Some(overrides) => overrides.apply_to_file_settings(&settings),
+            None => settings,
+        }
+    }
+
+    /// Fill in the media placeholders a templated output path kept from
+    /// enqueue now that `plan` is known. Subtitle sidecars planned beside the
+    /// output follow it, and the final path is recorded on the job. A path
+    /// that resolves onto the source file is returned as `MatchesInput` and
+    /// never recorded.
+    async fn resolve_templated_output(
+        &self,
+        job_id: i64,
+        output_path: &Path,
+        plan: &mut TranscodePlan,
+        metadata: &MediaMetadata,
+        profile: Option<&str>,
+    ) -> Result<ResolvedOutput> {
+        let media = MediaValues::from_plan(metadata, plan, profile);
+        let Some(resolved) = resolve_placeholders(output_path, &media) else {
+            return Ok(ResolvedOutput::Unchanged);
+        };
+        if output_is_input(&resolved, &metadata.path) {
+            return Ok(ResolvedOutput::MatchesInput(resolved));
+        }
+        for sidecar in plan.subtitles.sidecar_outputs_mut() {
+            if let Some(path) = resolve_placeholders(&sidecar.final_path, &media) {
+                sidecar.final_path = path;
+            }
+            if let Some(path) = resolve_placeholders(&sidecar.temp_path, &media) {
+                sidecar.temp_path = path;
+            }
+        }
+        self.db
+            .set_job_output_path(job_id, &resolved.to_string_lossy())
+            .await?;
+        Ok(ResolvedOutput::Resolved(resolved))
+    }
+
+    /// Skip a job whose resolved output path cannot be written.
+    async fn skip_output_conflict(&self, job_id: i64, reason: &str, resolved: &Path) {
+        tracing::info!("Job {}: {} ({:?}). Skipping.", job_id, reason, resolved);
+        self.record_job_decision(job_id, "skip", reason).await;
+        let _ = self
+            .update_job_state(job_id, crate::db::JobState::Skipped)
+            .await;
+    }
+
+    async fn fail_job_with_message(&self, job_id: i64, message: &str) {
+        tracing::error!("Job {}: {}", job_id, message);
+        self.record_job_log(job_id, "error", message).await;
+        let explanation = crate::explanations::failure_from_summary(message);
+        self.record_job_failure_explanation(job_id, &explanation)
+            .await;
+        if let Err(e) = self
+            .update_job_state(job_id, crate::db::JobState::Failed)
+            .await
+        {
+            tracing::warn!(job_id, "Failed to update job state: {e}");
+        }
+    }
+
+    /// Plan a job a remote worker leased, for that worker's hardware. This is
+    /// the planning half of `process_job`. Jobs with a VMAF target are never
+    /// leased, since the search's probe encodes would run here instead of on
+    /// the worker. Returns `None` when the job was closed without an encode.
+    pub async fn plan_remote_job(
+        &self,
+        job: &Job,
+        analysis: &MediaAnalysis,
+        worker_hardware: Option<HardwareInfo>,
+    ) -> std::result::Result<Option<TranscodePlan>, JobFailure> {
+        if self.db.increment_attempt_count(job.id).await.is_err() {
+            return Err(JobFailure::Transient);
+        }
+        if self.orchestrator.is_cancel_requested(job.id).await {
+            let _ = self
+                .update_job_state(job.id, crate::db::JobState::Cancelled)
+                .await;
+            return Ok(None);
+        }
+
+        let output_path = PathBuf::from(&job.output_path);
+        if output_path == Path::new(&job.input_path) {
+            self.skip_output_conflict(job.id, "Output path matches input path", &output_path)
+                .await;
+            return Ok(None);
+        }
+        let file_settings = self.job_file_settings(job.id).await;
+        if output_path.exists() && !file_settings.should_replace_existing_output() {
+            self.record_job_decision(job.id, "skip", "Output already exists")
+                .await;
+            let _ = self
+                .update_job_state(job.id, crate::db::JobState::Skipped)
+                .await;
+            return Ok(None);
+        }
+
+        let mut config_snapshot = self.config.read().await.clone();
+        let mut hw_info = worker_hardware;
+        crate::media::planner::restrict_to_backends(
+            &mut config_snapshot,
+            hw_info.as_mut(),
+            &self.allowed_backends,
+        );
+        let selection = match crate::media::profile_rules::select_profile(&self.db, analysis).await
+        {
+            Ok(selection) => selection,
+            Err(err) => {
+                self.fail_job_with_message(
+                    job.id,
+                    &format!("Failed to resolve library profile: {err}"),
+                )
+                .await;
+                return Err(JobFailure::Transient);
+            }
+        };
+        let planner = BasicPlanner::new(Arc::new(config_snapshot), hw_info)
+            .with_overrides(self.job_overrides(job.id).await);
+        let mut plan = match planner
+            .plan_with_selection(analysis, &output_path, &selection)
+            .await
+        {
+            Ok(plan) => plan,
+            Err(e) => {
+                self.fail_job_with_message(job.id, &format!("Planner failed: {e}"))
+                    .await;
+                return Err(JobFailure::PlannerBug);
+            }
+        };
+
+        let (action, reason, next_status) = match &plan.decision {
+            TranscodeDecision::Transcode { reason } => {
+                ("encode", reason.clone(), crate::db::JobState::Encoding)
+            }
+            TranscodeDecision::Remux { reason } => {
+                ("remux", reason.clone(), crate::db::JobState::Remuxing)
+            }
+            TranscodeDecision::Skip { reason } => {
+                self.record_job_decision(job.id, "skip", reason).await;
+                let _ = self
+                    .update_job_state(job.id, crate::db::JobState::Skipped)
+                    .await;
+                return Ok(None);
+            }
+        };
+        let profile = selection
+            .profile
+            .as_ref()
+            .map(|profile| profile.name.as_str());
+        let output_path = match self
+            .resolve_templated_output(job.id, &output_path, &mut plan, &analysis.metadata, profile)
+            .await
+        {
+            Ok(ResolvedOutput::Resolved(resolved)) => {
+                if resolved.exists() && !file_settings.should_replace_existing_output() {
+                    self.skip_output_conflict(job.id, "Output already exists", &resolved)
+                        .await;
+                    return Ok(None);
+                }
+                resolved
+            }
+            Ok(ResolvedOutput::MatchesInput(resolved)) => {
+                self.skip_output_conflict(job.id, "Output path matches input path", &resolved)
+                    .await;
+                return Ok(None);
+            }
+            Ok(ResolvedOutput::Unchanged) => output_path,
+            Err(err) => {
+                self.fail_job_with_message(
+                    job.id,
+                    &format!("Failed to record resolved output path: {err}"),
+                )
+                .await;
+                return Err(JobFailure::Transient);
+            }
+        };
+        plan.output_path = Some(temp_output_path_for(&output_path, job.id));
+        if action == "encode" {
+            self.run_loudness_measurement(job, &mut plan).await;
+        }
+
+        let mut explanation = crate::explanations::decision_from_legacy(action, &reason);
+        explanation = crate::media::loudness::annotate(explanation, &plan.audio);
+        explanation = selection.annotate(explanation);
+        for downgrade in &plan.downgrades {
+            self.record_job_log(job.id, "warn", &downgrade.message())
+                .await;
+        }
+        self.record_job_decision_with_explanation(job.id, action, &explanation)
+            .await;
+        let _ = self
+            .event_channels
+            .jobs
+            .send(crate::db::JobEvent::Decision {
+                job_id: job.id,
+                action: action.to_string(),
+                reason: explanation.legacy_reason.clone(),
+                explanation: Some(explanation),
+            });
+        if self.update_job_state(job.id, next_status).await.is_err() {
+            return Err(JobFailure::Transient);
+        }
+        self.update_job_progress(job.id, 0.0).await;
+        Ok(Some(plan))
+    }
+
+    /// Record what a remote worker reported for `job`, finalizing an encoded
+    /// output through the same gates as a local encode. `planned` holds what
+    /// `plan_remote_job` produced, if the worker got that far.
+    pub async fn finalize_remote_job(
+        &self,
+        job: Job,
+        planned: Option<(&TranscodePlan, &MediaMetadata)>,
+        report: crate::worker::RemoteJobReport,
+    ) -> std::result::Result<(), JobFailure> {
+        let input_path = PathBuf::from(&job.input_path);
+        let output_path = PathBuf::from(&job.output_path);
+        let temp_output_path = temp_output_path_for(&output_path, job.id);
+        let start_time = Instant::now()
+            .checked_sub(std::time::Duration::from_secs_f64(
+                report.encode_seconds.max(0.0),
+            ))
+            .unwrap_or_else(Instant::now);
+        let attempt_number = job.attempt_count;
+        let failed_attempt = |outcome: &str, code: Option<String>, summary: Option<String>| {
+            crate::db::EncodeAttemptInput {
+                job_id: job.id,
+                attempt_number,
+                started_at: Some(report.started_at.to_rfc3339()),
+                outcome: outcome.to_string(),
+                failure_code: code,
+                failure_summary: summary,
+                input_size_bytes: planned.map(|(_, metadata)| metadata.size_bytes as i64),
+                output_size_bytes: None,
+                encode_time_seconds: Some(report.encode_seconds),
+            }
+        };
+        let discard_outputs = || async {
+            if temp_output_path.exists() {
+                let _ = tokio::fs::remove_file(&temp_output_path).await;
+            }
+            if let Some((plan, _)) = planned {
+                cleanup_temp_subtitle_output(job.id, plan).await;
+            }
+        };
+
+        let execution_result = match report.outcome {
+            crate::worker::RemoteJobOutcome::Encoded { execution_result } => execution_result,
+            crate::worker::RemoteJobOutcome::Cancelled => {
+                discard_outputs().await;
+                let _ = self
+                    .update_job_state(job.id, crate::db::JobState::Cancelled)
+                    .await;
+                self.record_encode_attempt(job.id, failed_attempt("cancelled", None, None))
+                    .await;
+                return Ok(());
+            }
+            crate::worker::RemoteJobOutcome::Failed { failure, message } => {
+                discard_outputs().await;
+                self.fail_job_with_message(job.id, &message).await;
+                let code = crate::explanations::failure_from_summary(&message).code;
+                self.record_encode_attempt(
+                    job.id,
+                    failed_attempt("failed", Some(code), Some(message)),
+                )
+                .await;
+                return Err(failure);
+            }
+        };
+
+        let Some((plan, metadata)) = planned else {
+            discard_outputs().await;
+            self.fail_job_with_message(job.id, "Worker reported an encode that was never planned")
+                .await;
+            return Err(JobFailure::Transient);
+        };
+        if execution_result.fallback_occurred && !plan.allow_fallback {
+            let summary = "Encoder fallback detected and not allowed.";
+            discard_outputs().await;
+            self.fail_job_with_message(job.id, summary).await;
+            self.record_encode_attempt(
+                job.id,
+                failed_attempt(
+                    "failed",
+                    Some("fallback_blocked".to_string()),
+                    Some(summary.to_string()),
+                ),
+            )
+            .await;
+            return Err(JobFailure::EncoderUnavailable);
+        }
+
+        if let Err(err) = self
+            .finalize_job(
+                job.clone(),
+                &input_path,
+                FinalizeJobContext {
+                    output_path: &output_path,
+                    temp_output_path: &temp_output_path,
+                    plan,
+                    bypass_quality_gates: false,
+                    start_time,
+                    encode_started_at: report.started_at,
+                    attempt_number,
+                    metadata,
+                    execution_result: &execution_result,
+                },
+            )
+            .await
+        {
+            let config_snapshot = self.config.read().await.clone();
+            self.handle_finalize_failure(
+                job.id,
+                FinalizeFailureContext {
+                    plan,
+                    metadata,
+                    execution_result: &execution_result,
+                    config_snapshot: &config_snapshot,
+                    start_time,
+                    encode_started_at: report.started_at,
+                    attempt_number,
+                    temp_output_path: &temp_output_path,
+                },
+                &err,
+            )
+            .await;
+            return Err(JobFailure::Transient);
+        }
+        Ok(())
+    }
+
+    /// Run configured hooks for the state the job ended in. Cancelled jobs and
+    /// jobs requeued for a retry run none, so callers run this after any
+    /// retry decision.
+    pub async fn run_post_hooks(&self, job_id: i64) {
+        let hooks = self.config.read().await.hooks.clone();
+        if hooks.is_empty() {
+            return;
+        }
+        let job = match self.db.get_job_by_id(job_id).await {
+            Ok(Some(job)) => job,
+            Ok(None) => return,
+            Err(err) => {
+                tracing::warn!(
+                    job_id,
+                    "Failed to load job for post-processing hooks: {err}"
+                );
+                return;
+            }
+        };
+        // Hooks follow library files; a variant job's input path only names it.
+        if !matches!(self.db.get_variant_link(job_id).await, Ok(None)) {
+            return;
+        }
+        let event = match job.status {
+            crate::db::JobState::Completed => crate::config::HookEvent::Completed,
+            crate::db::JobState::Failed => crate::config::HookEvent::Failed,
+            crate::db::JobState::Skipped => crate::config::HookEvent::Skipped,
+            _ => return,
+        };
+        let hooks = hooks
+            .into_iter()
+            .filter(|hook| crate::media::hooks::hook_applies(hook, event, &job.input_path))
+            .collect::<Vec<_>>();
+        if hooks.is_empty() {
+            return;
+        }
+
+        let context = self.hook_context(&job, event).await;
+        let mut cancel = self.orchestrator.register_cancel_channel(job_id);
+        for hook in &hooks {
+            self.record_job_log(
+                job_id,
+                "info",
+                &format!("Running {} hook '{}'", event.as_str(), hook.name),
+            )
+            .await;
+            let run = crate::media::hooks::run_hook(hook, event, &context, &mut cancel).await;
+            for line in &run.stdout {
+                self.record_job_log(job_id, "info", &format!("[hook {}] {line}", hook.name))
+                    .await;
+            }
+            for line in &run.stderr {
+                self.record_job_log(job_id, "warn", &format!("[hook {}] {line}", hook.name))
+                    .await;
+            }
+
+            let message = format!("Hook '{}' {}", hook.name, run.outcome.describe());
+            if run.outcome.is_success() {
+                self.record_job_log(job_id, "info", &message).await;
+                continue;
+            }
+            if run.outcome == crate::media::hooks::HookOutcome::Cancelled {
+                tracing::warn!(job_id, "{message}");
+                self.record_job_log(job_id, "warn", &message).await;
+                break;
+            }
+            // The job is already finalized, so a hook failure never changes
+            // its state; flagged hooks raise it as an error instead.
+            if hook.flag_job_on_error {
+                let message = format!("{message}; the job stays {}", job.status);
+                tracing::error!(job_id, "{message}");
+                self.record_job_log(job_id, "error", &message).await;
+            } else {
+                tracing::warn!(job_id, "{message}");
+                self.record_job_log(job_id, "warn", &message).await;
+            }
+        }
+        self.orchestrator.release_cancel_channel(job_id);
+    }
+
+    async fn hook_context(
+        &self,
+        job: &Job,
+        event: crate::config::HookEvent,
+    ) -> crate::media::hooks::HookContext {
+        let input_metadata = job.input_metadata();
+        let stats = self.db.get_encode_stats_by_job_id(job.id).await.ok();
+        let output_codec = self
+            .db
+            .get_encode_completion_summary(job.id)
+            .await
+            .ok()
+            .flatten()
+            .and_then(|summary| summary.codec);
+        let explanation = if event == crate::config::HookEvent::Failed {
+            self.db.get_job_failure_explanation(job.id).await
+        } else {
+            self.db.get_job_decision_explanation(job.id).await
+        };
+        crate::media::hooks::HookContext {
+            job_id: job.id,
+            input_path: job.input_path.clone(),
+            output_path: job.output_path.clone(),
+            input_size_bytes: stats
+                .as_ref()
+                .map(|stats| stats.input_size_bytes.max(0) as u64)
+                .or_else(|| input_metadata.as_ref().map(|metadata| metadata.size_bytes)),
+            output_size_bytes: stats
+                .as_ref()
+                .map(|stats| stats.output_size_bytes.max(0) as u64),
+            input_codec: input_metadata.map(|metadata| metadata.codec_name),
+            output_codec,
+            vmaf_score: stats.as_ref().and_then(|stats| stats.vmaf_score),
+            decision_code: explanation
+                .ok()
+                .flatten()
+                .map(|explanation| explanation.code),
+        }
+    }
+
+    pub async fn process_job(&self, job: Job) -> std::result::Result<(), JobFailure> {
+        let variant = match self.db.get_variant_link(job.id).await {
+            Ok(variant) => variant,
+            Err(err) => {
+                tracing::warn!(job_id = job.id, "Failed to load variant link: {err}");
+                return Err(JobFailure::Transient);
+            }
+        };
+        // A variant job is encoded from its parent's source; its own input
+        // path only names it.
+        let job = match variant.as_ref() {
+            Some(link) => {
+                let parent = match self.db.get_job_by_id(link.parent_job_id).await {
+                    Ok(parent) => parent,
+                    Err(err) => {
+                        tracing::warn!(job_id = job.id, "Failed to load parent job: {err}");
+                        return Err(JobFailure::Transient);
+                    }
+                };
+                let Some(source) = parent.as_ref().and_then(variant_source) else {
+                    self.record_job_decision(job.id, "skip", "Variant source no longer exists")
+                        .await;
+                    let _ = self
+                        .update_job_state(job.id, crate::db::JobState::Skipped)
+                        .await;
+                    return Ok(());
+                };
+                Job {
+                    input_path: source.to_string_lossy().into_owned(),
+                    ..job
+                }
+            }
+            None => job,
+        };
+        let file_path = PathBuf::from(&job.input_path);
+
+        let file_settings = self.job_file_settings(job.id).await;
 
         let output_path = PathBuf::from(&job.output_path);
         let temp_output_path = temp_output_path_for(&output_path, job.id);
@@ -1563,7 +2616,17 @@ impl Pipeline {
             return Ok(());
         }
 
-        if output_path.exists() && !file_settings.should_replace_existing_output() {
+        if variant.is_some() {
+            if crate::media::variants::is_up_to_date(&file_path, &output_path) {
+                self.skip_output_conflict(
+                    job.id,
+                    "Up-to-date variant already exists",
+                    &output_path,
+                )
+                .await;
+                return Ok(());
+            }
+        } else if output_path.exists() && !file_settings.should_replace_existing_output() {
             tracing::info!(
                 "Job {}: Output exists and replace_strategy is keep. Skipping.",
                 job.id
@@ -1654,8 +2717,13 @@ impl Pipeline {
             Err(_) => return Err(JobFailure::Transient),
         }
 
-        let config_snapshot = self.config.read().await.clone();
-        let hw_info = self.hardware_state.snapshot().await;
+        let mut config_snapshot = self.config.read().await.clone();
+        let mut hw_info = self.hardware_state.snapshot().await;
+        crate::media::planner::restrict_to_backends(
+            &mut config_snapshot,
+            hw_info.as_mut(),
+            &self.allowed_backends,
+        );
         let conversion_job = match self.db.get_conversion_job_by_linked_job_id(job.id).await {
             Ok(conversion_job) => conversion_job,
             Err(err) => {
@@ -1674,7 +2742,11 @@ impl Pipeline {
                 return Err(JobFailure::Transient);
             }
         };
-        let bypass_quality_gates = conversion_job.is_some();
+        // A variant is a compatibility copy, not a replacement for the
+        // source, so the size and quality gates do not apply to it.
+        let bypass_quality_gates = conversion_job.is_some() || variant.is_some();
+        let mut target_vmaf = None;
+        let mut profile_selection = None;
         let mut plan = if let Some(conversion_job) = conversion_job.as_ref() {
             let settings: crate::conversion::ConversionSettings =
                 match serde_json::from_str(&conversion_job.settings_json) {
@@ -1714,28 +2786,79 @@ impl Pipeline {
                     return Err(JobFailure::PlannerBug);
                 }
             }
-        } else {
+        } else if let Some(link) = variant.as_ref() {
             let planner = BasicPlanner::new(Arc::new(config_snapshot.clone()), hw_info.clone());
-            let profile = match self.db.get_profile_for_path(&job.input_path).await {
-                Ok(profile) => profile,
+            match self
+                .plan_variant(link, &analysis, &output_path, &planner)
+                .await
+            {
+                Ok(Some(plan)) => plan,
+                Ok(None) => {
+                    self.record_job_decision(
+                        job.id,
+                        "skip",
+                        "Variant is no longer declared by the profile",
+                    )
+                    .await;
+                    let _ = self
+                        .update_job_state(job.id, crate::db::JobState::Skipped)
+                        .await;
+                    return Ok(());
+                }
                 Err(err) => {
-                    let msg = format!("Failed to resolve library profile: {err}");
-                    tracing::error!("Job {}: {}", job.id, msg);
-                    self.record_job_log(job.id, "error", &msg).await;
-                    let explanation = crate::explanations::failure_from_summary(&msg);
-                    self.record_job_failure_explanation(job.id, &explanation)
+                    self.fail_job_with_message(job.id, &format!("Planner failed: {err}"))
                         .await;
-                    if let Err(e) = self
-                        .update_job_state(job.id, crate::db::JobState::Failed)
-                        .await
-                    {
-                        tracing::warn!(job_id = job.id, "Failed to update job state: {e}");
-                    }
-                    return Err(JobFailure::Transient);
+                    return Err(JobFailure::PlannerBug);
                 }
-            };
+            }
+        } else {
+            let overrides = self.job_overrides(job.id).await;
+            // An explicit CRF takes the place of the target-quality search.
+            let crf_overridden = overrides.as_ref().is_some_and(|o| o.crf.is_some());
+            let planner = BasicPlanner::new(Arc::new(config_snapshot.clone()), hw_info.clone())
+                .with_overrides(overrides);
+            let selection =
+                match crate::media::profile_rules::select_profile(&self.db, &analysis).await {
+                    Ok(selection) => selection,
+                    Err(err) => {
+                        let msg = format!("Failed to resolve library profile: {err}");
+                        tracing::error!("Job {}: {}", job.id, msg);
+                        self.record_job_log(job.id, "error", &msg).await;
+                        let explanation = crate::explanations::failure_from_summary(&msg);
+                        self.record_job_failure_explanation(job.id, &explanation)
+                            .await;
+                        if let Err(e) = self
+                            .update_job_state(job.id, crate::db::JobState::Failed)
+                            .await
+                        {
+                            tracing::warn!(job_id = job.id, "Failed to update job state: {e}");
+                        }
+                        return Err(JobFailure::Transient);
+                    }
+                };
+            if let Some(rule) = &selection.rule {
+                let msg = format!(
+                    "Profile rule \"{}\" matched ({})",
+                    rule.name,
+                    selection
+                        .profile
+                        .as_ref()
+                        .map_or("skip".to_string(), |profile| format!(
+                            "profile {}",
+                            profile.name
+                        ))
+                );
+                tracing::info!("Job {}: {}", job.id, msg);
+                self.record_job_log(job.id, "info", &msg).await;
+            }
+            target_vmaf = selection
+                .profile
+                .as_ref()
+                .and_then(|profile| profile.target_vmaf)
+                .filter(|_| !crf_overridden);
+            profile_selection = Some(selection.clone());
             match planner
-                .plan(&analysis, &output_path, profile.as_ref())
+                .plan_with_selection(&analysis, &output_path, &selection)
                 .await
             {
                 Ok(plan) => plan,
@@ -1757,6 +2880,51 @@ impl Pipeline {
             }
         };
 
+        let (output_path, temp_output_path) =
+            if matches!(plan.decision, TranscodeDecision::Skip { .. }) {
+                (output_path, temp_output_path)
+            } else {
+                let profile = profile_selection
+                    .as_ref()
+                    .and_then(|selection| selection.profile.as_ref())
+                    .map(|profile| profile.name.as_str());
+                match self
+                    .resolve_templated_output(job.id, &output_path, &mut plan, metadata, profile)
+                    .await
+                {
+                    Ok(ResolvedOutput::MatchesInput(resolved)) => {
+                        self.skip_output_conflict(
+                            job.id,
+                            "Output path matches input path",
+                            &resolved,
+                        )
+                        .await;
+                        return Ok(());
+                    }
+                    Ok(ResolvedOutput::Resolved(resolved)) => {
+                        if resolved.exists() && !file_settings.should_replace_existing_output() {
+                            self.skip_output_conflict(job.id, "Output already exists", &resolved)
+                                .await;
+                            return Ok(());
+                        }
+                        let resolved_temp = temp_output_path_for(&resolved, job.id);
+                        if resolved_temp.exists() {
+                            let _ = std::fs::remove_file(&resolved_temp);
+                        }
+                        tracing::info!("Job {}: Output path resolved to {:?}", job.id, resolved);
+                        (resolved, resolved_temp)
+                    }
+                    Ok(ResolvedOutput::Unchanged) => (output_path, temp_output_path),
+                    Err(err) => {
+                        self.fail_job_with_message(
+                            job.id,
+                            &format!("Failed to record resolved output path: {err}"),
+                        )
+                        .await;
+                        return Err(JobFailure::Transient);
+                    }
+                }
+            };
         if !matches!(plan.decision, TranscodeDecision::Skip { .. }) {
             plan.output_path = Some(temp_output_path.clone());
         }
@@ -1774,6 +2942,26 @@ impl Pipeline {
             }
         }
 
+        let quality_search = match target_vmaf {
+            Some(target_vmaf) if matches!(plan.decision, TranscodeDecision::Transcode { .. }) => {
+                self.run_quality_search(
+                    &job,
+                    &mut plan,
+                    metadata,
+                    hw_info.as_ref(),
+                    target_vmaf,
+                    &output_path,
+                )
+                .await
+            }
+            _ => None,
+        };
+        if matches!(plan.decision, TranscodeDecision::Transcode { .. })
+            && !matches!(self.should_stop_job(job.id).await, Ok(true))
+        {
+            self.run_loudness_measurement(&job, &mut plan).await;
+        }
+
         match self.should_stop_job(job.id).await {
             Ok(true) => {
                 tracing::info!("Job {} was cancelled during encode planning.", job.id);
@@ -1827,7 +3015,30 @@ impl Pipeline {
             job.id,
             &reason
         );
-        let explanation = crate::explanations::decision_from_legacy(action, &reason);
+        let mut explanation = crate::explanations::decision_from_legacy(action, &reason);
+        if let Some(outcome) = quality_search.as_ref() {
+            explanation = outcome.annotate(explanation);
+        }
+        explanation = crate::media::loudness::annotate(explanation, &plan.audio);
+        if let Some(selection) = profile_selection.as_ref() {
+            explanation = selection.annotate(explanation);
+        }
+        if !plan.downgrades.is_empty() {
+            explanation = explanation.with_measured(
+                "planned_downgrades",
+                serde_json::json!(
+                    plan.downgrades
+                        .iter()
+                        .map(|downgrade| downgrade.reason.as_str())
+                        .collect::<Vec<_>>()
+                ),
+            );
+        }
+        for downgrade in &plan.downgrades {
+            tracing::warn!("Job {}: {}", job.id, downgrade.message());
+            self.record_job_log(job.id, "warn", &downgrade.message())
+                .await;
+        }
         self.record_job_decision_with_explanation(job.id, action, &explanation)
             .await;
         let _ = self
@@ -1853,6 +3064,19 @@ impl Pipeline {
             Err(_) => return Err(JobFailure::Transient),
         }
 
+        // Variants are written into their own output tree.
+        if variant.is_some()
+            && let Some(parent) = output_path.parent()
+            && let Err(err) = tokio::fs::create_dir_all(parent).await
+        {
+            self.fail_job_with_message(
+                job.id,
+                &format!("Failed to create {}: {err}", parent.display()),
+            )
+            .await;
+            return Err(JobFailure::Transient);
+        }
+
         self.emit_telemetry_event(TelemetryEventParams {
             telemetry_enabled: config_snapshot.system.enable_telemetry,
             output_codec: config_snapshot.transcode.output_codec,
@@ -2148,6 +3372,139 @@ impl Pipeline {
         }
     }
 
+    /// Probe-encode short clips to find the most compressed rate control that
+    /// still meets the profile's VMAF target, and apply it to `plan`. Any
+    /// failure keeps the planned rate control; the full encode still runs and
+    /// the post-encode VMAF gate remains the backstop.
+    async fn run_quality_search(
+        &self,
+        job: &Job,
+        plan: &mut TranscodePlan,
+        metadata: &MediaMetadata,
+        hw_info: Option<&crate::system::hardware::HardwareInfo>,
+        target_vmaf: f64,
+        output_path: &Path,
+    ) -> Option<crate::media::quality_search::QualitySearchOutcome> {
+        if let Some(reason) = crate::media::quality_search::unsupported_reason(plan) {
+            let message = format!(
+                "Target VMAF {target_vmaf:.1} ignored because {reason}; using planned quality."
+            );
+            tracing::info!(job_id = job.id, "{message}");
+            self.record_job_log(job.id, "info", &message).await;
+            return None;
+        }
+
+        tracing::info!(
+            "[Job {}] Searching for rate control meeting VMAF {:.1}...",
+            job.id,
+            target_vmaf
+        );
+        let search_start = std::time::Instant::now();
+        let scratch_dir = output_path.parent().unwrap_or_else(|| Path::new(""));
+        let cancel = std::sync::Mutex::new(self.orchestrator.register_cancel_channel(job.id));
+        let request = crate::media::quality_search::QualitySearchRequest {
+            job_id: job.id,
+            input: Path::new(&job.input_path),
+            metadata,
+            plan,
+            hw_info,
+            target_vmaf,
+            scratch_dir,
+            cancel: &cancel,
+        };
+        let searched = crate::media::quality_search::search_rate_control(request).await;
+        self.orchestrator.release_cancel_channel(job.id);
+        match searched {
+            Ok(outcome) => {
+                let probes = outcome
+                    .probes
+                    .iter()
+                    .map(|probe| format!("{}={:.2}", probe.value, probe.vmaf))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let message = if outcome.target_met {
+                    format!(
+                        "Target VMAF {:.1}: selected quality value {} after {} probe(s) in {:.1}s [{}].",
+                        target_vmaf,
+                        outcome.chosen_value().unwrap_or_default(),
+                        outcome.probes.len(),
+                        search_start.elapsed().as_secs_f64(),
+                        probes
+                    )
+                } else {
+                    format!(
+                        "Target VMAF {:.1} not reachable in the search range; using highest-quality value {} [{}].",
+                        target_vmaf,
+                        outcome.chosen_value().unwrap_or_default(),
+                        probes
+                    )
+                };
+                tracing::info!(job_id = job.id, "{message}");
+                self.record_job_log(job.id, "info", &message).await;
+                plan.rate_control = Some(outcome.rate_control.clone());
+                Some(outcome)
+            }
+            Err(crate::error::AlchemistError::Cancelled) => {
+                tracing::info!(job_id = job.id, "Target VMAF search cancelled.");
+                let _ = self
+                    .update_job_state(job.id, crate::db::JobState::Cancelled)
+                    .await;
+                None
+            }
+            Err(err) => {
+                let message =
+                    format!("Target VMAF search failed; using planned quality instead: {err}");
+                tracing::warn!(job_id = job.id, "{message}");
+                self.record_job_log(job.id, "warn", &message).await;
+                None
+            }
+        }
+    }
+
+    /// First `loudnorm` pass for every track the plan normalizes. A track whose
+    /// measurement fails is encoded without normalization rather than failing
+    /// the job.
+    async fn run_loudness_measurement(&self, job: &Job, plan: &mut TranscodePlan) {
+        for track in &mut plan.audio {
+            let Some(source_index) = track.source_index else {
+                continue;
+            };
+            let AudioStreamPlan::Transcode { loudness, .. } = &mut track.action else {
+                continue;
+            };
+            let Some(normalization) = loudness.as_mut() else {
+                continue;
+            };
+            match crate::media::loudness::measure(
+                Path::new(&job.input_path),
+                source_index,
+                normalization,
+            )
+            .await
+            {
+                Ok(measured) => {
+                    let message = format!(
+                        "Loudness of audio stream {}: {}; normalizing to {:.1} LUFS.",
+                        source_index,
+                        crate::media::loudness::describe(&measured),
+                        normalization.target_lufs
+                    );
+                    tracing::info!(job_id = job.id, "{message}");
+                    self.record_job_log(job.id, "info", &message).await;
+                    normalization.measured = Some(measured);
+                }
+                Err(err) => {
+                    let message = format!(
+                        "Loudness measurement failed for audio stream {source_index}; encoding without normalization: {err}"
+                    );
+                    tracing::warn!(job_id = job.id, "{message}");
+                    self.record_job_log(job.id, "warn", &message).await;
+                    *loudness = None;
+                }
+            }
+        }
+    }
+
     async fn update_job_state(&self, job_id: i64, status: crate::db::JobState) -> Result<()> {
         if self.orchestrator.is_cancel_requested(job_id).await {
             match status {
@@ -2554,16 +3911,25 @@ impl Pipeline {
         self.verify_chapter_preservation(job_id, context.metadata, context.output_path)
             .await;
 
-        if let Ok(file_settings) = self.db.get_file_settings().await
-            && file_settings.delete_source
-        {
+        // A variant job's input is its parent's source: never delete it or
+        // carry its sidecars.
+        let is_variant = !matches!(self.db.get_variant_link(job_id).await, Ok(None));
+        let mut source_removed = false;
+        if !is_variant && self.job_file_settings(job_id).await.delete_source {
             // Safety: verify the promoted output is intact before destroying the source.
             // This prevents data loss if the filesystem silently corrupted the output
             // during rename (e.g., stale NFS/SMB mount, full disk).
             match std::fs::metadata(context.output_path) {
                 Ok(m) if m.len() > 0 => {
-                    if let Err(e) = std::fs::remove_file(input_path) {
+                    let recycle_bin = self.config.read().await.files.recycle_bin.clone();
+                    if recycle_bin.enabled {
+                        source_removed = self
+                            .recycle_source(job_id, input_path, context.output_path, &recycle_bin)
+                            .await;
+                    } else if let Err(e) = std::fs::remove_file(input_path) {
                         tracing::warn!("Failed to delete source {:?}: {}", input_path, e);
+                    } else {
+                        source_removed = true;
                     }
                 }
                 Ok(_) => {
@@ -2584,9 +3950,102 @@ impl Pipeline {
             }
         }
 
+        // A kept source keeps its sidecars; they only follow a source that
+        // is gone.
+        if !is_variant && source_removed && config.files.carry_sidecars {
+            self.carry_sidecars(job_id, input_path, context.output_path, context.plan)
+                .await;
+        }
+
         Ok(())
     }
 
+    /// Move the removed source's sidecars over to the output's name.
+    /// Failures are logged on the job and never fail it.
+    async fn carry_sidecars(
+        &self,
+        job_id: i64,
+        input_path: &Path,
+        output_path: &Path,
+        plan: &TranscodePlan,
+    ) {
+        let exclude = plan
+            .subtitles
+            .sidecar_outputs()
+            .iter()
+            .map(|sidecar| sidecar.final_path.clone())
+            .collect::<Vec<_>>();
+        let sidecars = crate::media::sidecars::discover(
+            input_path,
+            output_path,
+            &crate::media::scanner::Scanner::new().extensions,
+            &exclude,
+        );
+        if sidecars.is_empty() {
+            return;
+        }
+        match crate::media::sidecars::carry(&self.db, job_id, sidecars).await {
+            Ok(outcome) => {
+                for sidecar in &outcome.carried {
+                    let message = format!(
+                        "Sidecar {} moved to {}",
+                        sidecar.source.display(),
+                        sidecar.target.display()
+                    );
+                    tracing::info!("Job {}: {}", job_id, message);
+                    self.record_job_log(job_id, "info", &message).await;
+                }
+                for warning in &outcome.warnings {
+                    tracing::warn!("Job {}: {}", job_id, warning);
+                    self.record_job_log(job_id, "warn", warning).await;
+                }
+            }
+            Err(err) => {
+                let message = format!("Failed to record carried sidecars: {err}");
+                tracing::warn!("Job {}: {}", job_id, message);
+                self.record_job_log(job_id, "warn", &message).await;
+            }
+        }
+    }
+
+    /// Move the source into the recycle bin. On failure the source is kept:
+    /// with the bin enabled, nothing is deleted without a way back. Returns
+    /// whether the source was moved.
+    async fn recycle_source(
+        &self,
+        job_id: i64,
+        input_path: &Path,
+        output_path: &Path,
+        recycle_bin: &crate::config::RecycleBinConfig,
+    ) -> bool {
+        match crate::media::recycle::recycle_original(
+            &self.db,
+            job_id,
+            input_path,
+            output_path,
+            recycle_bin,
+        )
+        .await
+        {
+            Ok(recycled_path) => {
+                let message = format!(
+                    "Original moved to recycle bin at {}",
+                    recycled_path.display()
+                );
+                tracing::info!("Job {}: {}", job_id, message);
+                self.record_job_log(job_id, "info", &message).await;
+                true
+            }
+            Err(err) => {
+                let message =
+                    format!("Failed to move original to recycle bin; source preserved: {err}");
+                tracing::warn!("Job {}: {}", job_id, message);
+                self.record_job_log(job_id, "warn", &message).await;
+                false
+            }
+        }
+    }
+
     async fn handle_finalize_failure(
         &self,
         job_id: i64,
@@ -2721,7 +4180,21 @@ async fn cleanup_temp_subtitle_output(job_id: i64, plan: &TranscodePlan) {
     }
 }
 
-fn map_failure(error: &crate::error::AlchemistError) -> JobFailure {
+/// The file a job's output variants are encoded from. With delete_source
+/// the original is gone and the finished output is the best remaining
+/// source.
+fn variant_source(job: &Job) -> Option<PathBuf> {
+    let input_path = PathBuf::from(&job.input_path);
+    if input_path.exists() {
+        Some(input_path)
+    } else if job.status == crate::db::JobState::Completed && Path::new(&job.output_path).exists() {
+        Some(PathBuf::from(&job.output_path))
+    } else {
+        None
+    }
+}
+
+pub(crate) fn map_failure(error: &crate::error::AlchemistError) -> JobFailure {
     use crate::error::AlchemistError;
     match error {
         AlchemistError::EncoderUnavailable(_) => JobFailure::EncoderUnavailable,
@@ -2876,6 +4349,51 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn generated_output_pattern_matches_template_outputs() {
+        let mut settings = default_file_settings();
+        settings.output_template = Some("{parent}/{stem} [{codec} {height}p].{ext}".to_string());
+        assert!(matches_generated_output_pattern(
+            Path::new("/media/Heat [hevc 2160p].mkv"),
+            &settings,
+        ));
+        assert!(!matches_generated_output_pattern(
+            Path::new("/media/Heat [Director's Cut].mkv"),
+            &settings,
+        ));
+        assert!(!matches_generated_output_pattern(
+            Path::new("/media/Heat.mkv"),
+            &settings,
+        ));
+    }
+
+    #[test]
+    fn output_is_input_compares_canonical_paths() -> anyhow::Result<()> {
+        let temp_root = std::env::temp_dir().join(format!(
+            "alchemist_output_is_input_{}",
+            rand::random::<u64>()
+        ));
+        let movies = temp_root.join("movies");
+        std::fs::create_dir_all(&movies)?;
+        let input = movies.join("Heat.mkv");
+        std::fs::write(&input, b"source")?;
+
+        assert!(output_is_input(
+            &temp_root.join("movies/../movies/Heat.mkv"),
+            &input
+        ));
+        assert!(!output_is_input(&movies.join("Heat [hevc].mkv"), &input));
+        #[cfg(unix)]
+        {
+            let link = temp_root.join("link");
+            std::os::unix::fs::symlink(&movies, &link)?;
+            assert!(output_is_input(&link.join("Heat.mkv"), &input));
+        }
+
+        let _ = std::fs::remove_dir_all(temp_root);
+        Ok(())
+    }
+
     #[test]
     fn map_failure_classifies_crash_resource_and_bad_input_failures() {
         assert!(matches!(
@@ -2944,12 +4462,15 @@ mod tests {
             rate_control: Some(RateControl::Cq { value: 28 }),
             encoder_preset: None,
             threads: 0,
-            audio: AudioStreamPlan::Copy,
-            audio_stream_indices: None,
+            audio: vec![AudioTrackPlan::all_streams(AudioStreamPlan::Copy)],
             subtitles: SubtitleStreamPlan::CopyAllCompatible,
             filters: Vec::new(),
             allow_fallback: true,
             fallback: None,
+            preserve_dolby_vision: false,
+            preserve_hdr10_plus: false,
+            downgrades: Vec::new(),
+            marker: None,
         };
 
         let Some(fallback) = cpu_fallback_plan(&plan, &config) else {
@@ -2986,7 +4507,7 @@ mod tests {
             rand::random::<u64>()
         ));
         let db = Db::new(db_path.to_string_lossy().as_ref()).await?;
-        db.update_file_settings(false, "mkv", "", "keep", None)
+        db.update_file_settings(false, "mkv", "", "keep", None, None)
             .await?;
 
         let input = Path::new("/library/movie.mkv");
@@ -3036,12 +4557,12 @@ mod tests {
             rate_control: Some(RateControl::Crf { value: 21 }),
             encoder_preset: Some("medium".to_string()),
             threads: 0,
-            audio: AudioStreamPlan::Copy,
-            audio_stream_indices: None,
+            audio: vec![AudioTrackPlan::all_streams(AudioStreamPlan::Copy)],
             subtitles: SubtitleStreamPlan::Extract {
                 outputs: vec![SidecarOutputPlan {
                     stream_index: 0,
                     codec: "srt".to_string(),
+                    muxer: None,
                     final_path: temp_root.join("movie.eng.srt"),
                     temp_path: temp_sidecar.clone(),
                 }],
@@ -3049,6 +4570,10 @@ mod tests {
             filters: Vec::new(),
             allow_fallback: true,
             fallback: None,
+            preserve_dolby_vision: false,
+            preserve_hdr10_plus: false,
+            downgrades: Vec::new(),
+            marker: None,
         };
 
         cleanup_temp_subtitle_output(1, &plan).await;
@@ -3130,12 +4655,15 @@ mod tests {
             rate_control: Some(RateControl::Crf { value: 21 }),
             encoder_preset: Some("medium".to_string()),
             threads: 0,
-            audio: AudioStreamPlan::Copy,
-            audio_stream_indices: None,
+            audio: vec![AudioTrackPlan::all_streams(AudioStreamPlan::Copy)],
             subtitles: SubtitleStreamPlan::Drop,
             filters: Vec::new(),
             allow_fallback: true,
             fallback: None,
+            preserve_dolby_vision: false,
+            preserve_hdr10_plus: false,
+            downgrades: Vec::new(),
+            marker: None,
         };
         let metadata = MediaMetadata {
             path: input.clone(),
@@ -3161,6 +4689,7 @@ mod tests {
             audio_streams: Vec::new(),
             dynamic_range: DynamicRange::Sdr,
             chapter_count: 0,
+            alchemist_marker: None,
         };
         let result = ExecutionResult {
             requested_codec: crate::config::OutputCodec::H264,
@@ -3227,6 +4756,95 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn failing_hook_with_flag_job_on_error_keeps_skipped_job() -> anyhow::Result<()> {
+        let db_path = std::env::temp_dir().join(format!(
+            "alchemist_hook_failure_{}.db",
+            rand::random::<u64>()
+        ));
+        let temp_root =
+            std::env::temp_dir().join(format!("alchemist_hook_failure_{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&temp_root)?;
+
+        let db = Arc::new(Db::new(db_path.to_string_lossy().as_ref()).await?);
+        db.update_file_settings(false, "mkv", "-alchemist", "keep", None, None)
+            .await?;
+
+        let input = temp_root.join("movie.mkv");
+        let output = temp_root.join("movie-alchemist.mkv");
+        std::fs::write(&input, b"source")?;
+        std::fs::write(&output, b"existing-output")?;
+        let _ = db
+            .enqueue_job(&input, &output, SystemTime::UNIX_EPOCH)
+            .await?;
+        let job = db
+            .get_job_by_input_path(input.to_string_lossy().as_ref())
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("missing queued job"))?;
+
+        let mut config = crate::config::Config::default();
+        config.hooks = vec![crate::config::HookConfig {
+            name: "notify".to_string(),
+            command: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                "echo \"skipped $ALCHEMIST_JOB_ID\"; exit 2".to_string(),
+            ],
+            events: vec![crate::config::HookEvent::Skipped],
+            path_filter: None,
+            timeout_secs: 10,
+            flag_job_on_error: true,
+            enabled: true,
+        }];
+        let hardware_state = HardwareState::new(Some(HardwareInfo {
+            vendor: Vendor::Cpu,
+            device_path: None,
+            supported_codecs: vec!["av1".to_string()],
+            backends: Vec::new(),
+            detection_notes: Vec::new(),
+            selection_reason: String::new(),
+            probe_summary: crate::system::hardware::ProbeSummary::default(),
+        }));
+        let (jobs_tx, _) = tokio::sync::broadcast::channel(100);
+        let (config_tx, _) = tokio::sync::broadcast::channel(10);
+        let (system_tx, _) = tokio::sync::broadcast::channel(10);
+        let event_channels = Arc::new(crate::db::EventChannels {
+            jobs: jobs_tx,
+            config: config_tx,
+            system: system_tx,
+        });
+        let pipeline = Pipeline::new(
+            db.clone(),
+            Arc::new(Transcoder::new()),
+            Arc::new(RwLock::new(config)),
+            hardware_state,
+            event_channels,
+            false,
+        );
+
+        pipeline
+            .process_job(job.clone())
+            .await
+            .map_err(|err| anyhow::anyhow!("process_job failed: {err:?}"))?;
+        pipeline.run_post_hooks(job.id).await;
+        let updated = db
+            .get_job_by_id(job.id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("missing job"))?;
+        assert_eq!(updated.status, crate::db::JobState::Skipped);
+
+        let logs = db.get_logs_for_job(job.id, 50).await?;
+        let expected = format!("[hook notify] skipped {}", job.id);
+        assert!(logs.iter().any(|entry| entry.message == expected));
+        assert!(logs.iter().any(|entry| entry.level == "error"
+            && entry.message == "Hook 'notify' exited with code 2; the job stays skipped"));
+
+        let _ = std::fs::remove_dir_all(temp_root);
+        let _ = std::fs::remove_file(db_path);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn process_job_skips_even_when_decision_persistence_fails() -> anyhow::Result<()> {
         let db_path = std::env::temp_dir().join(format!(
@@ -3240,7 +4858,7 @@ mod tests {
         std::fs::create_dir_all(&temp_root)?;
 
         let db = Arc::new(Db::new(db_path.to_string_lossy().as_ref()).await?);
-        db.update_file_settings(false, "mkv", "-alchemist", "keep", None)
+        db.update_file_settings(false, "mkv", "-alchemist", "keep", None, None)
             .await?;
 
         let input = temp_root.join("movie.mkv");
@@ -3566,12 +5184,15 @@ mod tests {
             rate_control: Some(RateControl::Crf { value: 21 }),
             encoder_preset: Some("medium".to_string()),
             threads: 0,
-            audio: AudioStreamPlan::Drop,
-            audio_stream_indices: None,
+            audio: Vec::new(),
             subtitles: SubtitleStreamPlan::Drop,
             filters: Vec::new(),
             allow_fallback: true,
             fallback: None,
+            preserve_dolby_vision: false,
+            preserve_hdr10_plus: false,
+            downgrades: Vec::new(),
+            marker: None,
         };
         let metadata = MediaMetadata {
             path: input.clone(),
@@ -3597,6 +5218,7 @@ mod tests {
             audio_streams: Vec::new(),
             dynamic_range: DynamicRange::Sdr,
             chapter_count: 0,
+            alchemist_marker: None,
         };
         let result = ExecutionResult {
             requested_codec: crate::config::OutputCodec::H264,
@@ -3713,12 +5335,15 @@ mod tests {
             rate_control: Some(RateControl::Crf { value: 21 }),
             encoder_preset: Some("medium".to_string()),
             threads: 0,
-            audio: AudioStreamPlan::Drop,
-            audio_stream_indices: None,
+            audio: Vec::new(),
             subtitles: SubtitleStreamPlan::Drop,
             filters: Vec::new(),
             allow_fallback: true,
             fallback: None,
+            preserve_dolby_vision: false,
+            preserve_hdr10_plus: false,
+            downgrades: Vec::new(),
+            marker: None,
         };
         let metadata = MediaMetadata {
             path: input.clone(),
@@ -3744,6 +5369,7 @@ mod tests {
             audio_streams: Vec::new(),
             dynamic_range: DynamicRange::Sdr,
             chapter_count: 0,
+            alchemist_marker: None,
         };
         let result = ExecutionResult {
             requested_codec: crate::config::OutputCodec::H264,
@@ -3893,12 +5519,15 @@ mod tests {
             rate_control: Some(RateControl::Crf { value: 21 }),
             encoder_preset: Some("medium".to_string()),
             threads: 0,
-            audio: AudioStreamPlan::Copy,
-            audio_stream_indices: None,
+            audio: vec![AudioTrackPlan::all_streams(AudioStreamPlan::Copy)],
             subtitles: SubtitleStreamPlan::Drop,
             filters: Vec::new(),
             allow_fallback: true,
             fallback: None,
+            preserve_dolby_vision: false,
+            preserve_hdr10_plus: false,
+            downgrades: Vec::new(),
+            marker: None,
         };
         let metadata = MediaMetadata {
             path: input.clone(),
@@ -3924,6 +5553,7 @@ mod tests {
             audio_streams: Vec::new(),
             dynamic_range: DynamicRange::Sdr,
             chapter_count: 0,
+            alchemist_marker: None,
         };
         let result = ExecutionResult {
             requested_codec: crate::config::OutputCodec::H264,
@@ -4058,12 +5688,15 @@ mod tests {
             rate_control: Some(RateControl::Crf { value: 21 }),
             encoder_preset: Some("ultrafast".to_string()),
             threads: 0,
-            audio: AudioStreamPlan::Drop,
-            audio_stream_indices: None,
+            audio: Vec::new(),
             subtitles: SubtitleStreamPlan::Drop,
             filters: Vec::new(),
             allow_fallback: true,
             fallback: None,
+            preserve_dolby_vision: false,
+            preserve_hdr10_plus: false,
+            downgrades: Vec::new(),
+            marker: None,
         };
 
         let (_session, segments) = pipeline
@@ -4193,12 +5826,15 @@ mod tests {
             rate_control: Some(RateControl::Crf { value: 21 }),
             encoder_preset: Some("ultrafast".to_string()),
             threads: 0,
-            audio: AudioStreamPlan::Drop,
-            audio_stream_indices: None,
+            audio: Vec::new(),
             subtitles: SubtitleStreamPlan::Drop,
             filters: Vec::new(),
             allow_fallback: true,
             fallback: None,
+            preserve_dolby_vision: false,
+            preserve_hdr10_plus: false,
+            downgrades: Vec::new(),
+            marker: None,
         };
 
         let (session, _segments) = pipeline
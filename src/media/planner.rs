@@ -1,11 +1,19 @@
-use crate::config::{AudioMode, Config, HdrMode, OutputCodec, QualityProfile, SubtitleMode};
+use crate::config::{
+    AudioMode, Config, DeinterlaceAlgorithm, DeinterlaceMode, DeinterlaceRate, DolbyVisionMode,
+    HdrMode, MaxResolution, OutputCodec, QualityProfile, StreamRules, SubtitleMode,
+};
 use crate::error::Result;
+use crate::media::marker::ProcessedMarker;
+use crate::media::overrides::JobOverrides;
 use crate::media::pipeline::{
-    AudioCodec, AudioStreamPlan, Encoder, FallbackKind, FilterStep, MediaAnalysis, PlannedFallback,
-    Planner, RateControl, SidecarOutputPlan, SubtitleStreamMetadata, SubtitleStreamPlan,
+    AudioCodec, AudioStreamMetadata, AudioStreamPlan, AudioTrackPlan, DeinterlaceBackend,
+    DowngradeKind, Encoder, EncoderBackend, FallbackKind, FilterStep, HwScaler,
+    LoudnessNormalization, MediaAnalysis, PlannedDowngrade, PlannedFallback, Planner, RateControl,
+    SidecarOutputPlan, SubtitleStreamMetadata, SubtitleStreamPlan, SubtitleTrackPlan,
     TranscodeDecision, TranscodePlan,
 };
 use crate::system::hardware::{HardwareBackend, HardwareInfo, Vendor};
+use serde::Serialize;
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::sync::Arc;
@@ -14,6 +22,55 @@ pub struct BasicPlanner {
     config: Arc<Config>,
     hw_info: Option<HardwareInfo>,
     encoder_caps: crate::media::ffmpeg::EncoderCapabilities,
+    overrides: Option<JobOverrides>,
+}
+
+/// Encode settings for one job: its overrides layered over its profile,
+/// layered over the global transcode settings.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EffectiveSettings {
+    pub codec: OutputCodec,
+    pub quality_profile: QualityProfile,
+    pub crf: Option<i32>,
+    pub hdr_mode: HdrMode,
+    /// `None` leaves each track to the planner's compatibility rules.
+    pub audio_mode: Option<AudioMode>,
+    pub stream_rules: StreamRules,
+}
+
+impl EffectiveSettings {
+    pub fn resolve(
+        config: &Config,
+        profile: Option<&crate::db::LibraryProfile>,
+        overrides: Option<&JobOverrides>,
+    ) -> Self {
+        let transcode = &config.transcode;
+        Self {
+            codec: overrides
+                .and_then(|overrides| overrides.codec)
+                .or_else(|| profile.map(|profile| output_codec_from_profile(&profile.codec)))
+                .unwrap_or(transcode.output_codec),
+            quality_profile: overrides
+                .and_then(|overrides| overrides.quality_profile)
+                .or_else(|| {
+                    profile.map(|profile| quality_profile_from_profile(&profile.quality_profile))
+                })
+                .unwrap_or(transcode.quality_profile),
+            crf: overrides
+                .and_then(|overrides| overrides.crf)
+                .or_else(|| profile.and_then(|profile| profile.crf_override)),
+            hdr_mode: overrides
+                .and_then(|overrides| overrides.hdr_mode)
+                .or_else(|| profile.map(|profile| hdr_mode_from_profile(&profile.hdr_mode)))
+                .unwrap_or(transcode.hdr_mode),
+            audio_mode: overrides
+                .and_then(|overrides| overrides.audio_mode)
+                .or_else(|| profile.map(|profile| audio_mode_from_profile(&profile.audio_mode))),
+            stream_rules: overrides
+                .and_then(|overrides| overrides.stream_rules.clone())
+                .unwrap_or_else(|| transcode.stream_rules.clone()),
+        }
+    }
 }
 
 impl BasicPlanner {
@@ -22,7 +79,35 @@ impl BasicPlanner {
             config,
             hw_info,
             encoder_caps: crate::media::ffmpeg::encoder_caps_clone(),
+            overrides: None,
+        }
+    }
+
+    /// Layer a job's enqueue-time overrides over whichever profile it plans with.
+    pub fn with_overrides(mut self, overrides: Option<JobOverrides>) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Plan with the outcome of profile rule selection. A skip rule yields a
+    /// skip plan without consulting the encoder inventory.
+    pub async fn plan_with_selection(
+        &self,
+        analysis: &MediaAnalysis,
+        output_path: &Path,
+        selection: &crate::media::profile_rules::ProfileSelection,
+    ) -> Result<TranscodePlan> {
+        if let Some(reason) = selection.skip_reason() {
+            return Ok(skip_plan(
+                reason,
+                normalize_container(output_path, &analysis.metadata.container),
+                self.config.transcode.output_codec,
+                self.config.transcode.allow_fallback,
+                self.config.transcode.threads,
+            ));
         }
+        self.plan(analysis, output_path, selection.profile.as_ref())
+            .await
     }
 }
 
@@ -53,19 +138,59 @@ impl Planner for BasicPlanner {
         output_path: &Path,
         profile: Option<&crate::db::LibraryProfile>,
     ) -> Result<TranscodePlan> {
+        let auto_crop = profile.is_some_and(|profile| profile.auto_crop);
+        let cropped;
+        let analysis = if auto_crop {
+            cropped = crate::media::analyzer::with_crop_detection(analysis).await;
+            &cropped
+        } else {
+            analysis
+        };
         let container = normalize_container(output_path, &analysis.metadata.container);
-        let requested_codec = profile
-            .map(|profile| output_codec_from_profile(&profile.codec))
-            .unwrap_or(self.config.transcode.output_codec);
-        let quality_profile = profile
-            .map(|profile| quality_profile_from_profile(&profile.quality_profile))
-            .unwrap_or(self.config.transcode.quality_profile);
-        let hdr_mode = profile
-            .map(|profile| hdr_mode_from_profile(&profile.hdr_mode))
-            .unwrap_or(self.config.transcode.hdr_mode);
-        let audio_mode = profile.map(|profile| audio_mode_from_profile(&profile.audio_mode));
-        let crf_override = profile.and_then(|profile| profile.crf_override);
-        let decision = should_transcode(analysis, &self.config, requested_codec, &container);
+        let settings = EffectiveSettings::resolve(&self.config, profile, self.overrides.as_ref());
+        let requested_codec = settings.codec;
+        let quality_profile = settings.quality_profile;
+        let mut hdr_mode = settings.hdr_mode;
+        let audio_mode = settings.audio_mode;
+        let crf_override = settings.crf;
+        let loudness_target = profile.and_then(|profile| profile.loudness_target_lufs);
+        let output_size = profile
+            .and_then(|profile| profile.max_resolution.as_deref())
+            .and_then(MaxResolution::parse)
+            .and_then(|max| downscale_target(output_frame_size(analysis, auto_crop), max));
+        let dolby_vision_mode = profile
+            .and_then(|profile| DolbyVisionMode::parse(&profile.dolby_vision_mode))
+            .unwrap_or_default();
+
+        let settings_digest = settings_digest(&settings, &container, profile);
+        if let Some(previous) = &analysis.metadata.alchemist_marker {
+            let settings_changed = previous.settings_digest != settings_digest;
+            if !(settings_changed && self.config.transcode.reprocess_on_settings_change) {
+                return Ok(skip_plan(
+                    format!(
+                        "already_processed_by_alchemist|version={},settings_changed={settings_changed}",
+                        previous.version.replace([',', '|'], " ")
+                    ),
+                    container,
+                    requested_codec,
+                    self.config.transcode.allow_fallback,
+                    self.config.transcode.threads,
+                ));
+            }
+        }
+        let marker = ProcessedMarker::new(
+            &analysis.metadata,
+            profile.map(|profile| profile.name.as_str()),
+            settings_digest,
+        );
+
+        let mut decision = should_transcode(
+            analysis,
+            &self.config,
+            requested_codec,
+            &container,
+            output_size,
+        );
 
         if let TranscodeDecision::Skip { reason } = &decision {
             return Ok(skip_plan(
@@ -93,15 +218,38 @@ impl Planner for BasicPlanner {
                 rate_control: None,
                 encoder_preset: None,
                 threads: self.config.transcode.threads,
-                audio: AudioStreamPlan::Copy,
-                audio_stream_indices: None,
+                audio: vec![AudioTrackPlan::all_streams(AudioStreamPlan::Copy)],
                 subtitles: SubtitleStreamPlan::CopyAllCompatible,
                 filters: Vec::new(),
                 allow_fallback: self.config.transcode.allow_fallback,
                 fallback: None,
+                preserve_dolby_vision: false,
+                preserve_hdr10_plus: false,
+                downgrades: Vec::new(),
+                marker: Some(marker),
             });
         }
 
+        let dolby_vision = DolbyVisionSource::from_analysis(analysis);
+        if let Some(source) = dolby_vision {
+            let skip_reason = if source.profile == Some(5) {
+                Some("dolby_vision_profile5_no_fallback|profile=5".to_string())
+            } else if dolby_vision_mode == DolbyVisionMode::Skip {
+                Some(format!("dolby_vision_skipped|profile={}", source.label()))
+            } else {
+                None
+            };
+            if let Some(reason) = skip_reason {
+                return Ok(skip_plan(
+                    reason,
+                    container,
+                    requested_codec,
+                    self.config.transcode.allow_fallback,
+                    self.config.transcode.threads,
+                ));
+            }
+        }
+
         let available_encoders =
             build_available_encoders(&self.config, self.hw_info.as_ref(), &self.encoder_caps);
 
@@ -152,11 +300,51 @@ impl Planner for BasicPlanner {
             ));
         };
 
+        let mut preserve_dolby_vision = false;
+        if let Some(source) = dolby_vision {
+            if dolby_vision_mode == DolbyVisionMode::Preserve {
+                if !source.supports_preserve(encoder) {
+                    return Ok(skip_plan(
+                        format!(
+                            "dolby_vision_preserve_unavailable|profile={},encoder={}",
+                            source.label(),
+                            encoder.ffmpeg_encoder_name()
+                        ),
+                        container,
+                        requested_codec,
+                        self.config.transcode.allow_fallback,
+                        self.config.transcode.threads,
+                    ));
+                }
+                // The RPU describes the PQ base layer; tonemapping would
+                // leave it pointing at pixels that no longer exist.
+                hdr_mode = HdrMode::Preserve;
+                preserve_dolby_vision = true;
+                append_reason_params(
+                    &mut decision,
+                    &format!(
+                        "dolby_vision=preserved,dolby_vision_profile={},dolby_vision_encoder={}",
+                        source.label(),
+                        encoder.ffmpeg_encoder_name()
+                    ),
+                );
+            } else {
+                append_reason_params(
+                    &mut decision,
+                    &format!(
+                        "dolby_vision=stripped_to_hdr10,dolby_vision_profile={}",
+                        source.label()
+                    ),
+                );
+            }
+        }
+
         let subtitles = match plan_subtitles(
             &analysis.metadata.subtitle_streams,
             &container,
             output_path,
             self.config.transcode.subtitle_mode,
+            &settings.stream_rules,
         ) {
             Ok(plan) => plan,
             Err(reason) => {
@@ -170,19 +358,30 @@ impl Planner for BasicPlanner {
             }
         };
 
-        let audio = plan_audio(
-            analysis.metadata.audio_codec.as_deref(),
-            analysis.metadata.audio_channels,
-            analysis.metadata.audio_is_heavy,
+        let mut audio = plan_audio(
+            &analysis.metadata,
             &container,
             audio_mode,
+            &settings.stream_rules,
             &self.encoder_caps,
         );
-        let audio_stream_indices = filter_audio_streams(
-            &analysis.metadata.audio_streams,
-            &self.config.transcode.stream_rules,
+        if let Some(target) = loudness_target {
+            apply_loudness_target(&mut audio, target);
+        }
+        let filters = plan_filters(
+            analysis,
+            encoder,
+            &self.config,
+            &subtitles,
+            hdr_mode,
+            FrameOptions {
+                auto_crop,
+                output_size,
+                hw_filters: hardware_scaler(encoder, self.hw_info.as_ref()),
+                strip_dolby_vision: dolby_vision.is_some() && !preserve_dolby_vision,
+            },
         );
-        let filters = plan_filters(analysis, encoder, &self.config, &subtitles, hdr_mode);
+        let (preserve_hdr10_plus, downgrades) = plan_hdr10_plus(analysis, encoder, &filters);
         let (rate_control, encoder_preset) =
             encoder_runtime_settings(encoder, &self.config, quality_profile, crf_override);
 
@@ -200,15 +399,36 @@ impl Planner for BasicPlanner {
             encoder_preset,
             threads: self.config.transcode.threads,
             audio,
-            audio_stream_indices,
             subtitles,
             filters,
             allow_fallback: self.config.transcode.allow_fallback,
             fallback,
+            preserve_dolby_vision,
+            preserve_hdr10_plus,
+            downgrades,
+            marker: Some(marker),
         })
     }
 }
 
+/// Digest of the settings that shape an output, recorded in its
+/// processed-by marker so a later settings change can be told apart.
+fn settings_digest(
+    settings: &EffectiveSettings,
+    container: &str,
+    profile: Option<&crate::db::LibraryProfile>,
+) -> String {
+    crate::media::marker::digest(&serde_json::json!({
+        "settings": settings,
+        "container": container,
+        "max_resolution": profile.and_then(|profile| profile.max_resolution.as_deref()),
+        "auto_crop": profile.is_some_and(|profile| profile.auto_crop),
+        "target_vmaf": profile.and_then(|profile| profile.target_vmaf),
+        "loudness_target_lufs": profile.and_then(|profile| profile.loudness_target_lufs),
+        "dolby_vision_mode": profile.map(|profile| profile.dolby_vision_mode.as_str()),
+    }))
+}
+
 fn skip_plan(
     reason: String,
     container: String,
@@ -229,12 +449,15 @@ fn skip_plan(
         rate_control: None,
         encoder_preset: None,
         threads,
-        audio: AudioStreamPlan::Copy,
-        audio_stream_indices: None,
+        audio: vec![AudioTrackPlan::all_streams(AudioStreamPlan::Copy)],
         subtitles: SubtitleStreamPlan::CopyAllCompatible,
         filters: Vec::new(),
         allow_fallback,
         fallback: None,
+        preserve_dolby_vision: false,
+        preserve_hdr10_plus: false,
+        downgrades: Vec::new(),
+        marker: None,
     }
 }
 
@@ -259,6 +482,7 @@ fn should_transcode(
     config: &Config,
     target_codec: OutputCodec,
     target_container: &str,
+    output_size: Option<(u32, u32)>,
 ) -> TranscodeDecision {
     let metadata = &analysis.metadata;
     let target_codec_str = target_codec.as_str();
@@ -282,7 +506,9 @@ fn should_transcode(
         None
     };
 
-    if let Some(skip_reason) = already_target_codec_reason {
+    // A profile resolution cap turns an otherwise finished file into work:
+    // the codec may already match, but the frame is larger than allowed.
+    if let Some(skip_reason) = already_target_codec_reason.filter(|_| output_size.is_none()) {
         if container_requires_remux(&input_container, target_container) {
             return TranscodeDecision::Remux {
                 reason: format!(
@@ -314,16 +540,19 @@ fn should_transcode(
                 .or(estimated_container_bitrate)
         }
     });
-    let width = metadata.width as f64;
-    let height = metadata.height as f64;
-    let fps = metadata.fps;
-
-    if width == 0.0 || height == 0.0 {
+    if metadata.width == 0 || metadata.height == 0 {
         return TranscodeDecision::Skip {
             reason: "incomplete_metadata|missing=resolution".to_string(),
         };
     }
 
+    // Efficiency is judged against the frame that will actually be encoded,
+    // so a downscaled output spreads the source bitrate over fewer pixels.
+    let (width, height) = output_size
+        .map(|(width, height)| (width as f64, height as f64))
+        .unwrap_or((metadata.width as f64, metadata.height as f64));
+    let fps = metadata.fps;
+
     let bpp = if bitrate.unwrap_or(0) == 0 || fps <= 0.0 {
         None
     } else {
@@ -374,6 +603,15 @@ fn should_transcode(
         };
     }
 
+    if let Some((target_width, target_height)) = output_size {
+        return TranscodeDecision::Transcode {
+            reason: format!(
+                "downscale_to_max_resolution|source_width={},source_height={},target_width={target_width},target_height={target_height}",
+                metadata.width, metadata.height
+            ),
+        };
+    }
+
     if metadata.codec_name.eq_ignore_ascii_case("h264") {
         return TranscodeDecision::Transcode {
             reason: "transcode_h264_source|current_codec=h264".to_string(),
@@ -391,6 +629,15 @@ fn should_transcode(
     }
 }
 
+/// Extra `key=value` parameters on a decision's reason, so an outcome such as
+/// the Dolby Vision handling sits alongside the reason that caused the encode.
+fn append_reason_params(decision: &mut TranscodeDecision, params: &str) {
+    if let TranscodeDecision::Transcode { reason } = decision {
+        reason.push(if reason.contains('|') { ',' } else { '|' });
+        reason.push_str(params);
+    }
+}
+
 fn build_available_encoders(
     config: &Config,
     hw_info: Option<&HardwareInfo>,
@@ -409,13 +656,7 @@ fn build_available_encoders(
         }
     }
 
-    let allow_cpu = config.hardware.allow_cpu_encoding
-        && match hw_info {
-            Some(hw) => hw.vendor == Vendor::Cpu || config.hardware.allow_cpu_fallback,
-            None => config.hardware.allow_cpu_fallback,
-        };
-
-    if allow_cpu {
+    if cpu_encoding_allowed(config, hw_info) {
         for encoder in [
             (Encoder::Av1Svt, caps.has_libsvtav1()),
             (Encoder::Av1Aom, caps.has_video_encoder("libaom-av1")),
@@ -431,6 +672,78 @@ fn build_available_encoders(
     available
 }
 
+fn backend_for_hardware(kind: HardwareBackend) -> EncoderBackend {
+    match kind {
+        HardwareBackend::Qsv => EncoderBackend::Qsv,
+        HardwareBackend::Nvenc => EncoderBackend::Nvenc,
+        HardwareBackend::Vaapi => EncoderBackend::Vaapi,
+        HardwareBackend::Amf => EncoderBackend::Amf,
+        HardwareBackend::Videotoolbox => EncoderBackend::Videotoolbox,
+    }
+}
+
+fn cpu_encoding_allowed(config: &Config, hw_info: Option<&HardwareInfo>) -> bool {
+    config.hardware.allow_cpu_encoding
+        && match hw_info {
+            Some(hw) => hw.vendor == Vendor::Cpu || config.hardware.allow_cpu_fallback,
+            None => config.hardware.allow_cpu_fallback,
+        }
+}
+
+/// Backends this host could encode on under `config`, ignoring codec support.
+pub fn usable_backends(config: &Config, hw_info: Option<&HardwareInfo>) -> Vec<EncoderBackend> {
+    let mut backends = Vec::new();
+    for backend in hw_info.iter().flat_map(|hw| &hw.backends) {
+        let backend = backend_for_hardware(backend.kind);
+        if !backends.contains(&backend) {
+            backends.push(backend);
+        }
+    }
+    if cpu_encoding_allowed(config, hw_info) {
+        backends.push(EncoderBackend::Cpu);
+    }
+    backends
+}
+
+/// Whether `hw` can encode `codec` on one of `allowed` backends, as a remote
+/// worker must to lease a job. An empty list allows every backend.
+pub fn hardware_encodes_codec(
+    hw: &HardwareInfo,
+    codec: OutputCodec,
+    allowed: &[EncoderBackend],
+) -> bool {
+    if allowed.is_empty() {
+        return hw.supports_codec(codec.as_str());
+    }
+    let on_hardware = hw.backends.iter().any(|backend| {
+        backend.codec == codec.as_str() && allowed.contains(&backend_for_hardware(backend.kind))
+    });
+    let on_cpu = hw.vendor == Vendor::Cpu
+        && allowed.contains(&EncoderBackend::Cpu)
+        && hw.supports_codec(codec.as_str());
+    on_hardware || on_cpu
+}
+
+/// Narrow planning inputs to `allowed` backends, as a schedule window's
+/// allow-list requires. An empty list leaves everything available.
+pub fn restrict_to_backends(
+    config: &mut Config,
+    hw_info: Option<&mut HardwareInfo>,
+    allowed: &[EncoderBackend],
+) {
+    if allowed.is_empty() {
+        return;
+    }
+    if let Some(hw) = hw_info {
+        hw.backends
+            .retain(|backend| allowed.contains(&backend_for_hardware(backend.kind)));
+    }
+    if !allowed.contains(&EncoderBackend::Cpu) {
+        config.hardware.allow_cpu_encoding = false;
+        config.hardware.allow_cpu_fallback = false;
+    }
+}
+
 fn encoder_for_backend(kind: HardwareBackend, codec: &str) -> Option<Encoder> {
     match (kind, codec.to_ascii_lowercase().as_str()) {
         (HardwareBackend::Qsv, "av1") => Some(Encoder::Av1Qsv),
@@ -707,7 +1020,112 @@ fn encoder_runtime_settings(
     )
 }
 
+/// Build the per-stream audio plan. Files analyzed without per-stream
+/// metadata fall back to one action applied to every audio stream.
 fn plan_audio(
+    metadata: &crate::media::pipeline::MediaMetadata,
+    container: &str,
+    audio_mode: Option<AudioMode>,
+    rules: &crate::config::StreamRules,
+    encoder_caps: &crate::media::ffmpeg::EncoderCapabilities,
+) -> Vec<AudioTrackPlan> {
+    if metadata.audio_streams.is_empty() {
+        return vec![AudioTrackPlan::all_streams(plan_audio_stream(
+            metadata.audio_codec.as_deref(),
+            metadata.audio_channels,
+            metadata.audio_is_heavy,
+            container,
+            audio_mode,
+            encoder_caps,
+        ))];
+    }
+
+    let kept = filter_audio_streams(&metadata.audio_streams, rules);
+    let mut tracks: Vec<AudioTrackPlan> = metadata
+        .audio_streams
+        .iter()
+        .map(|stream| {
+            let keep = kept
+                .as_ref()
+                .is_none_or(|kept| kept.contains(&stream.stream_index));
+            let action = if keep {
+                plan_audio_stream(
+                    Some(&stream.codec_name),
+                    stream.channels,
+                    crate::media::analyzer::audio_codec_is_heavy(&stream.codec_name),
+                    container,
+                    audio_mode,
+                    encoder_caps,
+                )
+            } else {
+                AudioStreamPlan::Drop
+            };
+            AudioTrackPlan {
+                source_index: Some(stream.stream_index),
+                action,
+                default: stream.default,
+                language: stream.language.clone(),
+            }
+        })
+        .collect();
+
+    if audio_mode == Some(AudioMode::CopyPlusStereo) {
+        insert_stereo_downmix(&mut tracks, &metadata.audio_streams);
+    }
+
+    tracks
+}
+
+/// Add a stereo AAC track derived from the default kept track (or the first
+/// kept track when none is flagged default), right after its source. Sources
+/// that are already stereo or mono need no extra track.
+fn insert_stereo_downmix(tracks: &mut Vec<AudioTrackPlan>, streams: &[AudioStreamMetadata]) {
+    let source = tracks
+        .iter()
+        .position(|track| track.is_output() && track.default)
+        .or_else(|| tracks.iter().position(AudioTrackPlan::is_output));
+    let Some(position) = source else {
+        return;
+    };
+    let source = &tracks[position];
+    let channels = streams
+        .iter()
+        .find(|stream| Some(stream.stream_index) == source.source_index)
+        .and_then(|stream| stream.channels);
+    if channels.is_none_or(|channels| channels <= 2) {
+        return;
+    }
+
+    let derived = AudioTrackPlan {
+        source_index: source.source_index,
+        action: AudioStreamPlan::DeriveDownmix {
+            codec: AudioCodec::Aac,
+            bitrate_kbps: audio_bitrate_kbps(AudioCodec::Aac, Some(2)),
+            channels: 2,
+        },
+        // FFmpeg would copy the source's default flag onto the second map of
+        // the same stream; the source track stays the one default.
+        default: false,
+        language: source.language.clone(),
+    };
+    tracks.insert(position + 1, derived);
+}
+
+/// Ask for loudness normalization on every re-encoded track with a known
+/// source stream; the measurement pass needs one stream to read. Copied and
+/// derived tracks are left alone.
+fn apply_loudness_target(tracks: &mut [AudioTrackPlan], target_lufs: f64) {
+    for track in tracks
+        .iter_mut()
+        .filter(|track| track.source_index.is_some())
+    {
+        if let AudioStreamPlan::Transcode { loudness, .. } = &mut track.action {
+            *loudness = Some(LoudnessNormalization::new(target_lufs));
+        }
+    }
+}
+
+fn plan_audio_stream(
     audio_codec: Option<&str>,
     audio_channels: Option<u32>,
     audio_is_heavy: bool,
@@ -717,7 +1135,17 @@ fn plan_audio(
 ) -> AudioStreamPlan {
     if let Some(audio_mode) = audio_mode {
         return match audio_mode {
-            AudioMode::Copy => {
+            AudioMode::TranscodeLossless
+                if audio_codec.is_some_and(crate::media::analyzer::audio_codec_is_lossless) =>
+            {
+                AudioStreamPlan::Transcode {
+                    codec: AudioCodec::Aac,
+                    bitrate_kbps: audio_bitrate_kbps(AudioCodec::Aac, audio_channels),
+                    channels: None,
+                    loudness: None,
+                }
+            }
+            AudioMode::Copy | AudioMode::CopyPlusStereo | AudioMode::TranscodeLossless => {
                 let Some(audio_codec) = audio_codec else {
                     return AudioStreamPlan::Copy;
                 };
@@ -728,6 +1156,7 @@ fn plan_audio(
                         codec: AudioCodec::Aac,
                         bitrate_kbps: audio_bitrate_kbps(AudioCodec::Aac, audio_channels),
                         channels: None,
+                        loudness: None,
                     }
                 }
             }
@@ -735,11 +1164,13 @@ fn plan_audio(
                 codec: AudioCodec::Aac,
                 bitrate_kbps: audio_bitrate_kbps(AudioCodec::Aac, audio_channels),
                 channels: None,
+                loudness: None,
             },
             AudioMode::AacStereo => AudioStreamPlan::Transcode {
                 codec: AudioCodec::Aac,
                 bitrate_kbps: audio_bitrate_kbps(AudioCodec::Aac, Some(2)),
                 channels: Some(2),
+                loudness: None,
             },
         };
     }
@@ -762,6 +1193,7 @@ fn plan_audio(
             codec,
             bitrate_kbps: audio_bitrate_kbps(codec, audio_channels),
             channels: None,
+            loudness: None,
         };
     }
 
@@ -890,32 +1322,27 @@ fn plan_subtitles(
     container: &str,
     output_path: &Path,
     mode: SubtitleMode,
+    rules: &crate::config::StreamRules,
 ) -> std::result::Result<SubtitleStreamPlan, String> {
+    let kept = filter_subtitle_streams(subtitle_streams, rules);
     match mode {
         SubtitleMode::Copy => {
-            if !subtitle_copy_supported(container, subtitle_streams) {
-                return Err(format!(
-                    "Container {} cannot safely copy subtitle codecs {:?}",
-                    container,
-                    subtitle_streams
-                        .iter()
-                        .map(|stream| stream.codec_name.clone())
-                        .collect::<Vec<_>>()
-                ));
+            if !rules.has_subtitle_rules() && subtitle_copy_supported(container, subtitle_streams) {
+                return Ok(SubtitleStreamPlan::CopyAllCompatible);
             }
-            Ok(SubtitleStreamPlan::CopyAllCompatible)
+            plan_subtitle_selection(&kept, container, output_path)
         }
         SubtitleMode::None => Ok(SubtitleStreamPlan::Drop),
-        SubtitleMode::Burn => select_burn_subtitle_stream(subtitle_streams)
+        SubtitleMode::Burn => select_burn_subtitle_stream(&kept)
             .map(|stream| SubtitleStreamPlan::Burn {
                 stream_index: stream.stream_index,
             })
             .ok_or_else(|| "No burnable text subtitle stream available".to_string()),
         SubtitleMode::Extract => {
-            if subtitle_streams.is_empty() {
+            if kept.is_empty() {
                 Ok(SubtitleStreamPlan::Drop)
             } else {
-                let outputs = sidecar_outputs_for(output_path, subtitle_streams);
+                let outputs = sidecar_outputs_for(output_path, &kept, false);
                 if outputs.is_empty() {
                     Ok(SubtitleStreamPlan::Drop)
                 } else {
@@ -926,6 +1353,94 @@ fn plan_subtitles(
     }
 }
 
+/// Map each kept stream on its own. MP4-family outputs convert text
+/// subtitles to `mov_text` and move image subtitles to Matroska sidecars;
+/// anything else they cannot hold fails the plan as before.
+fn plan_subtitle_selection(
+    kept: &[SubtitleStreamMetadata],
+    container: &str,
+    output_path: &Path,
+) -> std::result::Result<SubtitleStreamPlan, String> {
+    let mut streams = Vec::new();
+    let mut image_streams = Vec::new();
+    let mut unsupported = Vec::new();
+    for stream in kept {
+        let codec_name = stream.codec_name.to_ascii_lowercase();
+        if !is_mp4_family_container(container) || matches!(codec_name.as_str(), "mov_text" | "tx3g")
+        {
+            streams.push(SubtitleTrackPlan {
+                stream_index: stream.stream_index,
+                convert_to: None,
+            });
+        } else if subtitle_sidecar_codec_and_extension(&codec_name).is_some()
+            || codec_name == "text"
+        {
+            streams.push(SubtitleTrackPlan {
+                stream_index: stream.stream_index,
+                convert_to: Some("mov_text".to_string()),
+            });
+        } else if subtitle_codec_is_image(&codec_name) {
+            image_streams.push(stream.clone());
+        } else {
+            unsupported.push(stream.codec_name.clone());
+        }
+    }
+
+    if !unsupported.is_empty() {
+        return Err(format!(
+            "Container {container} cannot safely copy subtitle codecs {unsupported:?}"
+        ));
+    }
+    let sidecars = sidecar_outputs_for(output_path, &image_streams, true);
+    if streams.is_empty() && sidecars.is_empty() {
+        return Ok(SubtitleStreamPlan::Drop);
+    }
+    Ok(SubtitleStreamPlan::Select { streams, sidecars })
+}
+
+/// Apply the subtitle stream rules. Unlike audio, nothing falls back when
+/// every track is filtered out; a file without subtitles is valid.
+fn filter_subtitle_streams(
+    streams: &[SubtitleStreamMetadata],
+    rules: &crate::config::StreamRules,
+) -> Vec<SubtitleStreamMetadata> {
+    streams
+        .iter()
+        .filter(|stream| {
+            if let Some(title) = &stream.title {
+                let title_lower = title.to_lowercase();
+                if rules
+                    .strip_subtitles_by_title
+                    .iter()
+                    .any(|keyword| title_lower.contains(&keyword.to_lowercase()))
+                {
+                    return false;
+                }
+            }
+
+            if !rules.keep_subtitle_languages.is_empty()
+                && let Some(language) = &stream.language
+                && !rules
+                    .keep_subtitle_languages
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(language))
+            {
+                return false;
+            }
+
+            !rules.keep_only_forced_subtitles || stream.forced
+        })
+        .cloned()
+        .collect()
+}
+
+fn subtitle_codec_is_image(codec_name: &str) -> bool {
+    matches!(
+        codec_name,
+        "hdmv_pgs_subtitle" | "pgs" | "dvd_subtitle" | "dvdsub" | "dvb_subtitle" | "xsub"
+    )
+}
+
 pub(crate) fn subtitle_copy_supported(
     container: &str,
     subtitle_streams: &[SubtitleStreamMetadata],
@@ -959,9 +1474,13 @@ fn select_burn_subtitle_stream(
         .or_else(|| subtitle_streams.iter().find(|stream| stream.burnable))
 }
 
+/// Sidecar files for `subtitle_streams`. Text streams are converted to their
+/// plain-text format; image streams are copied into Matroska (`.mks`) files
+/// when `image_as_matroska` is set and skipped otherwise.
 fn sidecar_outputs_for(
     output_path: &Path,
     subtitle_streams: &[SubtitleStreamMetadata],
+    image_as_matroska: bool,
 ) -> Vec<SidecarOutputPlan> {
     let parent = output_path.parent().unwrap_or_else(|| Path::new(""));
     let stem = output_path
@@ -973,7 +1492,14 @@ fn sidecar_outputs_for(
 
     for stream in subtitle_streams {
         let codec_name = stream.codec_name.to_ascii_lowercase();
-        let Some((codec, extension)) = subtitle_sidecar_codec_and_extension(&codec_name) else {
+        let sidecar = match subtitle_sidecar_codec_and_extension(&codec_name) {
+            Some((codec, extension)) => Some((codec, None, extension)),
+            None if image_as_matroska && subtitle_codec_is_image(&codec_name) => {
+                Some(("copy", Some("matroska"), "mks"))
+            }
+            None => None,
+        };
+        let Some((codec, muxer, extension)) = sidecar else {
             if matches!(
                 codec_name.as_str(),
                 "dvd_subtitle" | "dvdsub" | "hdmv_pgs_subtitle"
@@ -1009,6 +1535,7 @@ fn sidecar_outputs_for(
         outputs.push(SidecarOutputPlan {
             stream_index: stream.stream_index,
             codec: codec.to_string(),
+            muxer: muxer.map(str::to_string),
             final_path,
             temp_path,
         });
@@ -1077,8 +1604,40 @@ fn plan_filters(
     config: &Config,
     subtitles: &SubtitleStreamPlan,
     hdr_mode: HdrMode,
+    frame: FrameOptions,
 ) -> Vec<FilterStep> {
     let mut filters = Vec::new();
+    let deinterlace = deinterlace_settings(analysis, config);
+    let hw_deinterlace = match frame.hw_filters {
+        Some(HwScaler::Vaapi) => Some(DeinterlaceBackend::Vaapi),
+        Some(HwScaler::Qsv) => Some(DeinterlaceBackend::Qsv),
+        Some(HwScaler::Cuda) | None => None,
+    };
+    let hw_scale = frame.output_size.zip(frame.hw_filters);
+
+    if frame.strip_dolby_vision {
+        filters.push(FilterStep::StripDolbyVision);
+    }
+
+    // Software deinterlacing runs before anything else so crop, tonemap,
+    // and scaling all work on progressive frames.
+    if let Some((algorithm, rate)) = deinterlace
+        && hw_deinterlace.is_none()
+    {
+        filters.push(FilterStep::Deinterlace {
+            algorithm,
+            rate,
+            backend: DeinterlaceBackend::Software,
+        });
+    }
+
+    // Crop runs in software, ahead of any hwupload, so every later step sees
+    // the final frame size.
+    if frame.auto_crop
+        && let Some(crop) = crop_filter(analysis)
+    {
+        filters.push(crop);
+    }
 
     if analysis.metadata.dynamic_range.is_hdr() && hdr_mode == crate::config::HdrMode::Tonemap {
         filters.push(FilterStep::Tonemap {
@@ -1094,14 +1653,57 @@ fn plan_filters(
         });
     }
 
-    if encoder.backend() == crate::media::pipeline::EncoderBackend::Vaapi {
+    if let Some((width, height)) = frame.output_size
+        && frame.hw_filters.is_none()
+    {
+        filters.push(FilterStep::Scale { width, height });
+    }
+
+    // VAAPI encoders always take device frames. QSV and CUDA only need an
+    // upload when a GPU deinterlace or scale step follows.
+    let gpu_steps = hw_scale.is_some() || (deinterlace.is_some() && hw_deinterlace.is_some());
+    if encoder.backend() == EncoderBackend::Vaapi {
         filters.push(FilterStep::Format {
             pixel_format: "nv12".to_string(),
         });
         filters.push(FilterStep::HwUpload);
+    } else if gpu_steps {
+        match frame.hw_filters {
+            Some(HwScaler::Qsv) => {
+                filters.push(FilterStep::Format {
+                    pixel_format: upload_pixel_format(analysis, &filters).to_string(),
+                });
+                filters.push(FilterStep::HwUpload);
+            }
+            Some(HwScaler::Cuda) => {
+                filters.push(FilterStep::Format {
+                    pixel_format: upload_pixel_format(analysis, &filters).to_string(),
+                });
+                filters.push(FilterStep::HwUploadCuda);
+            }
+            Some(HwScaler::Vaapi) | None => {}
+        }
+    }
+
+    if let Some((algorithm, rate)) = deinterlace
+        && let Some(backend) = hw_deinterlace
+    {
+        filters.push(FilterStep::Deinterlace {
+            algorithm,
+            rate,
+            backend,
+        });
+    }
+
+    if let Some(((width, height), scaler)) = hw_scale {
+        filters.push(FilterStep::HwScale {
+            scaler,
+            width,
+            height,
+        });
     }
 
-    if encoder.backend() == crate::media::pipeline::EncoderBackend::Videotoolbox {
+    if encoder.backend() == EncoderBackend::Videotoolbox {
         filters.push(FilterStep::Format {
             pixel_format: "yuv420p".to_string(),
         });
@@ -1110,50 +1712,230 @@ fn plan_filters(
     filters
 }
 
-fn parse_quality_u8(value: &str, default_value: u8) -> u8 {
-    value.parse().unwrap_or(default_value)
+/// HDR10+ survives only while the picture stays PQ and the encoder can write
+/// ST 2094-40 metadata: libx265, fed the source's metadata through
+/// `dhdr10-info`. Other encoders keep the static HDR10 tags, and the lost
+/// dynamic metadata is recorded as a downgrade.
+fn plan_hdr10_plus(
+    analysis: &MediaAnalysis,
+    encoder: Encoder,
+    filters: &[FilterStep],
+) -> (bool, Vec<PlannedDowngrade>) {
+    let has_hdr10_plus = analysis
+        .analysis_report
+        .labels
+        .contains(&crate::media::pipeline::AnalyzerLabel::Hdr10PlusMetadata);
+    let tonemapped = filters
+        .iter()
+        .any(|step| matches!(step, FilterStep::Tonemap { .. }));
+    if !has_hdr10_plus || tonemapped {
+        return (false, Vec::new());
+    }
+    if encoder == Encoder::HevcX265 {
+        return (true, Vec::new());
+    }
+    (
+        false,
+        vec![PlannedDowngrade {
+            kind: DowngradeKind::Hdr10PlusToHdr10,
+            reason: format!(
+                "hdr10_plus_dropped|encoder={}",
+                encoder.ffmpeg_encoder_name()
+            ),
+        }],
+    )
 }
 
-fn apply_crf_override(rate_control: RateControl, crf_override: Option<i32>) -> RateControl {
-    let Some(crf_override) = crf_override else {
-        return rate_control;
-    };
-    let value = crf_override.clamp(0, u8::MAX as i32) as u8;
-    match rate_control {
-        RateControl::Crf { .. } => RateControl::Crf { value },
-        RateControl::Cq { .. } => RateControl::Cq { value },
-        RateControl::QsvQuality { .. } => RateControl::QsvQuality { value },
-        RateControl::Bitrate { kbps } => RateControl::Bitrate { kbps },
-    }
+/// Dolby Vision configuration record reported by the analyzer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DolbyVisionSource {
+    profile: Option<u32>,
+    bl_compat_id: Option<u32>,
 }
 
-fn output_codec_from_profile(value: &str) -> OutputCodec {
-    match value.trim().to_ascii_lowercase().as_str() {
-        "hevc" | "h265" => OutputCodec::Hevc,
-        "h264" | "avc" => OutputCodec::H264,
-        _ => OutputCodec::Av1,
+impl DolbyVisionSource {
+    fn from_analysis(analysis: &MediaAnalysis) -> Option<Self> {
+        let report = &analysis.analysis_report;
+        if !report
+            .labels
+            .contains(&crate::media::pipeline::AnalyzerLabel::DolbyVisionMetadata)
+        {
+            return None;
+        }
+        Some(Self {
+            profile: report.metrics.dolby_vision_profile,
+            bl_compat_id: report.metrics.dolby_vision_bl_compat_id,
+        })
     }
-}
 
-fn quality_profile_from_profile(value: &str) -> QualityProfile {
-    match value.trim().to_ascii_lowercase().as_str() {
-        "quality" => QualityProfile::Quality,
-        "speed" => QualityProfile::Speed,
-        _ => QualityProfile::Balanced,
+    /// Profile as it is usually written, e.g. `8.1`.
+    fn label(&self) -> String {
+        match (self.profile, self.bl_compat_id) {
+            (Some(profile), Some(compat)) if compat > 0 => format!("{profile}.{compat}"),
+            (Some(profile), _) => profile.to_string(),
+            (None, _) => "unknown".to_string(),
+        }
     }
-}
 
-fn hdr_mode_from_profile(value: &str) -> HdrMode {
-    match value.trim().to_ascii_lowercase().as_str() {
-        "tonemap" => HdrMode::Tonemap,
-        _ => HdrMode::Preserve,
+    /// Only profile 8.1 (HDR10-compatible base layer) can be re-encoded with
+    /// its RPU intact, and only libx265 accepts the RPU side data.
+    fn supports_preserve(&self, encoder: Encoder) -> bool {
+        self.profile == Some(8) && self.bl_compat_id == Some(1) && encoder == Encoder::HevcX265
     }
 }
 
-fn audio_mode_from_profile(value: &str) -> AudioMode {
+/// Profile- and hardware-dependent inputs to the video filter chain.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct FrameOptions {
+    auto_crop: bool,
+    /// Target size when the profile's resolution cap applies.
+    output_size: Option<(u32, u32)>,
+    /// GPU filter family the encoder can take frames from, if any.
+    hw_filters: Option<HwScaler>,
+    /// Drop Dolby Vision RPUs so the encoder only sees the HDR10 base layer.
+    strip_dolby_vision: bool,
+}
+
+/// Deinterlacer settings when the configured policy applies to this file.
+/// `auto` waits for the analyzer's idet probe to confirm the container flag.
+fn deinterlace_settings(
+    analysis: &MediaAnalysis,
+    config: &Config,
+) -> Option<(DeinterlaceAlgorithm, DeinterlaceRate)> {
+    let report = &analysis.analysis_report;
+    let enabled = match config.transcode.deinterlace_mode {
+        DeinterlaceMode::Off => false,
+        DeinterlaceMode::Auto => {
+            report
+                .labels
+                .contains(&crate::media::pipeline::AnalyzerLabel::InterlacedMetadata)
+                && report.metrics.interlace_confirmed == Some(true)
+        }
+        DeinterlaceMode::Always => true,
+    };
+    enabled.then_some((
+        config.transcode.deinterlace_algorithm,
+        config.transcode.deinterlace_rate,
+    ))
+}
+
+/// Frame size the encoder would see without any scaling: the crop rectangle
+/// when auto crop applies, otherwise the source dimensions.
+fn output_frame_size(analysis: &MediaAnalysis, auto_crop: bool) -> (u32, u32) {
+    match auto_crop.then(|| crop_filter(analysis)).flatten() {
+        Some(FilterStep::Crop { width, height, .. }) => (width, height),
+        _ => (analysis.metadata.width, analysis.metadata.height),
+    }
+}
+
+/// Fits `(width, height)` inside the resolution cap, preserving the aspect
+/// ratio and orientation. Returns `None` when the frame already fits.
+fn downscale_target((width, height): (u32, u32), max: MaxResolution) -> Option<(u32, u32)> {
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let (max_long, max_short) = max.bounds();
+    let (long, short) = (width.max(height), width.min(height));
+    if long <= max_long && short <= max_short {
+        return None;
+    }
+
+    let factor = (max_long as f64 / long as f64).min(max_short as f64 / short as f64);
+    let even = |value: u32| ((((value as f64) * factor).round() as u32) & !1).max(2);
+    Some((even(width), even(height)))
+}
+
+/// GPU filter family usable in front of `encoder`.
+fn hardware_scaler(encoder: Encoder, hw_info: Option<&HardwareInfo>) -> Option<HwScaler> {
+    match encoder.backend() {
+        EncoderBackend::Vaapi => Some(HwScaler::Vaapi),
+        // hwupload for QSV needs the filter device the QSV args only declare
+        // when a render node is known; otherwise scale in software.
+        EncoderBackend::Qsv => hw_info
+            .and_then(|hw| hw.device_path.as_ref())
+            .map(|_| HwScaler::Qsv),
+        EncoderBackend::Nvenc => Some(HwScaler::Cuda),
+        EncoderBackend::Cpu | EncoderBackend::Amf | EncoderBackend::Videotoolbox => None,
+    }
+}
+
+/// Software format handed to hwupload: keep 10-bit sources at 10 bits unless
+/// an earlier tonemap already produced 8-bit frames.
+fn upload_pixel_format(analysis: &MediaAnalysis, filters: &[FilterStep]) -> &'static str {
+    let tonemapped = filters
+        .iter()
+        .any(|step| matches!(step, FilterStep::Tonemap { .. }));
+    if !tonemapped && analysis.metadata.bit_depth.is_some_and(|depth| depth > 8) {
+        "p010le"
+    } else {
+        "nv12"
+    }
+}
+
+/// The analyzer's settled crop, if it found one the samples agreed on.
+fn crop_filter(analysis: &MediaAnalysis) -> Option<FilterStep> {
+    let report = &analysis.analysis_report;
+    if !report
+        .labels
+        .contains(&crate::media::pipeline::AnalyzerLabel::BlackBars)
+        || report.metrics.crop_samples_agree != Some(true)
+    {
+        return None;
+    }
+    Some(FilterStep::Crop {
+        width: report.metrics.crop_width?,
+        height: report.metrics.crop_height?,
+        x: report.metrics.crop_x?,
+        y: report.metrics.crop_y?,
+    })
+}
+
+fn parse_quality_u8(value: &str, default_value: u8) -> u8 {
+    value.parse().unwrap_or(default_value)
+}
+
+fn apply_crf_override(rate_control: RateControl, crf_override: Option<i32>) -> RateControl {
+    let Some(crf_override) = crf_override else {
+        return rate_control;
+    };
+    let value = crf_override.clamp(0, u8::MAX as i32) as u8;
+    match rate_control {
+        RateControl::Crf { .. } => RateControl::Crf { value },
+        RateControl::Cq { .. } => RateControl::Cq { value },
+        RateControl::QsvQuality { .. } => RateControl::QsvQuality { value },
+        RateControl::Bitrate { kbps } => RateControl::Bitrate { kbps },
+    }
+}
+
+fn output_codec_from_profile(value: &str) -> OutputCodec {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "hevc" | "h265" => OutputCodec::Hevc,
+        "h264" | "avc" => OutputCodec::H264,
+        _ => OutputCodec::Av1,
+    }
+}
+
+fn quality_profile_from_profile(value: &str) -> QualityProfile {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "quality" => QualityProfile::Quality,
+        "speed" => QualityProfile::Speed,
+        _ => QualityProfile::Balanced,
+    }
+}
+
+fn hdr_mode_from_profile(value: &str) -> HdrMode {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "tonemap" => HdrMode::Tonemap,
+        _ => HdrMode::Preserve,
+    }
+}
+
+fn audio_mode_from_profile(value: &str) -> AudioMode {
     match value.trim().to_ascii_lowercase().as_str() {
         "aac" => AudioMode::Aac,
         "aac_stereo" => AudioMode::AacStereo,
+        "copy_plus_stereo" => AudioMode::CopyPlusStereo,
+        "transcode_lossless" => AudioMode::TranscodeLossless,
         _ => AudioMode::Copy,
     }
 }
@@ -1179,6 +1961,12 @@ mod tests {
         config
     }
 
+    fn auto_deinterlace_config() -> Config {
+        let mut config = config();
+        config.transcode.deinterlace_mode = DeinterlaceMode::Auto;
+        config
+    }
+
     fn analysis() -> MediaAnalysis {
         MediaAnalysis {
             metadata: MediaMetadata {
@@ -1213,6 +2001,7 @@ mod tests {
                 audio_streams: vec![],
                 dynamic_range: DynamicRange::Sdr,
                 chapter_count: 0,
+                alchemist_marker: None,
             },
             warnings: Vec::new(),
             confidence: AnalysisConfidence::High,
@@ -1220,21 +2009,73 @@ mod tests {
         }
     }
 
+    fn subtitle(
+        stream_index: usize,
+        codec_name: &str,
+        language: &str,
+        title: Option<&str>,
+        forced: bool,
+    ) -> SubtitleStreamMetadata {
+        SubtitleStreamMetadata {
+            stream_index,
+            codec_name: codec_name.to_string(),
+            language: Some(language.to_string()),
+            title: title.map(str::to_string),
+            default: false,
+            forced,
+            burnable: codec_name == "subrip",
+        }
+    }
+
     #[test]
-    fn mp4_subtitle_copy_fails_fast() {
+    fn mp4_text_subtitles_convert_to_mov_text_and_images_become_sidecars() {
+        let plan = plan_subtitles(
+            &[
+                subtitle(0, "subrip", "eng", None, false),
+                subtitle(1, "hdmv_pgs_subtitle", "eng", None, false),
+                subtitle(2, "mov_text", "fra", None, false),
+            ],
+            "mp4",
+            Path::new("/tmp/library/movie.mp4"),
+            SubtitleMode::Copy,
+            &crate::config::StreamRules::default(),
+        )
+        .unwrap_or_else(|err| panic!("failed to plan mp4 subtitles: {err}"));
+
+        let SubtitleStreamPlan::Select { streams, sidecars } = plan else {
+            panic!("expected a per-stream subtitle plan");
+        };
+        assert_eq!(
+            streams,
+            vec![
+                SubtitleTrackPlan {
+                    stream_index: 0,
+                    convert_to: Some("mov_text".to_string()),
+                },
+                SubtitleTrackPlan {
+                    stream_index: 2,
+                    convert_to: None,
+                },
+            ]
+        );
+        assert_eq!(sidecars.len(), 1);
+        assert_eq!(sidecars[0].stream_index, 1);
+        assert_eq!(sidecars[0].codec, "copy");
+        assert_eq!(sidecars[0].muxer.as_deref(), Some("matroska"));
+        assert_eq!(
+            sidecars[0].final_path,
+            Path::new("/tmp/library/movie.eng.mks")
+        );
+    }
+
+    #[test]
+    fn mp4_subtitle_copy_still_fails_for_uncarriable_codecs() {
         let reason = match plan_subtitles(
-            &[SubtitleStreamMetadata {
-                stream_index: 0,
-                codec_name: "subrip".to_string(),
-                language: None,
-                title: None,
-                default: false,
-                forced: false,
-                burnable: true,
-            }],
+            &[subtitle(0, "eia_608", "eng", None, false)],
             "mp4",
             Path::new("/tmp/out.mp4"),
             SubtitleMode::Copy,
+            &crate::config::StreamRules::default(),
         ) {
             Ok(_) => panic!("expected mp4 subtitle copy planning to fail"),
             Err(reason) => reason,
@@ -1242,11 +2083,51 @@ mod tests {
         assert!(reason.contains("cannot safely copy"));
     }
 
+    #[test]
+    fn subtitle_rules_filter_by_title_language_and_forced() {
+        let streams = [
+            subtitle(0, "subrip", "eng", None, false),
+            subtitle(1, "subrip", "eng", Some("English SDH"), false),
+            subtitle(2, "subrip", "eng", Some("Forced"), true),
+            subtitle(3, "subrip", "spa", None, true),
+        ];
+        let mut rules = crate::config::StreamRules {
+            strip_subtitles_by_title: vec!["sdh".to_string()],
+            keep_subtitle_languages: vec!["eng".to_string()],
+            ..crate::config::StreamRules::default()
+        };
+        let kept: Vec<usize> = filter_subtitle_streams(&streams, &rules)
+            .iter()
+            .map(|stream| stream.stream_index)
+            .collect();
+        assert_eq!(kept, vec![0, 2]);
+
+        rules.keep_only_forced_subtitles = true;
+        let plan = plan_subtitles(
+            &streams,
+            "mkv",
+            Path::new("/tmp/out.mkv"),
+            SubtitleMode::Copy,
+            &rules,
+        )
+        .unwrap_or_else(|err| panic!("failed to plan subtitles: {err}"));
+        assert_eq!(
+            plan,
+            SubtitleStreamPlan::Select {
+                streams: vec![SubtitleTrackPlan {
+                    stream_index: 2,
+                    convert_to: None,
+                }],
+                sidecars: Vec::new(),
+            }
+        );
+    }
+
     #[test]
     fn heavy_audio_prefers_transcode() {
         let mut encoder_caps = crate::media::ffmpeg::EncoderCapabilities::default();
         encoder_caps.audio_encoders.insert("libopus".to_string());
-        let plan = plan_audio(Some("flac"), Some(6), true, "mkv", None, &encoder_caps);
+        let plan = plan_audio_stream(Some("flac"), Some(6), true, "mkv", None, &encoder_caps);
         assert!(matches!(
             plan,
             AudioStreamPlan::Transcode {
@@ -1259,7 +2140,7 @@ mod tests {
     #[test]
     fn heavy_audio_falls_back_to_aac_when_libopus_is_unavailable() {
         let encoder_caps = crate::media::ffmpeg::EncoderCapabilities::default();
-        let plan = plan_audio(Some("flac"), Some(6), true, "mkv", None, &encoder_caps);
+        let plan = plan_audio_stream(Some("flac"), Some(6), true, "mkv", None, &encoder_caps);
         assert!(matches!(
             plan,
             AudioStreamPlan::Transcode {
@@ -1269,6 +2150,124 @@ mod tests {
         ));
     }
 
+    fn surround_and_commentary_streams() -> Vec<AudioStreamMetadata> {
+        vec![
+            AudioStreamMetadata {
+                stream_index: 0,
+                codec_name: "truehd".to_string(),
+                language: Some("eng".to_string()),
+                title: None,
+                channels: Some(8),
+                default: true,
+                forced: false,
+            },
+            AudioStreamMetadata {
+                stream_index: 1,
+                codec_name: "ac3".to_string(),
+                language: Some("eng".to_string()),
+                title: Some("Commentary".to_string()),
+                channels: Some(2),
+                default: false,
+                forced: false,
+            },
+        ]
+    }
+
+    #[test]
+    fn copy_plus_stereo_derives_track_after_default_source() {
+        let mut metadata = analysis().metadata;
+        metadata.audio_streams = surround_and_commentary_streams();
+        let tracks = plan_audio(
+            &metadata,
+            "mkv",
+            Some(AudioMode::CopyPlusStereo),
+            &crate::config::StreamRules::default(),
+            &crate::media::ffmpeg::EncoderCapabilities::default(),
+        );
+
+        assert_eq!(tracks.len(), 3);
+        assert_eq!(tracks[0].action, AudioStreamPlan::Copy);
+        assert_eq!(tracks[1].source_index, Some(0));
+        assert!(matches!(
+            tracks[1].action,
+            AudioStreamPlan::DeriveDownmix {
+                codec: AudioCodec::Aac,
+                channels: 2,
+                ..
+            }
+        ));
+        assert!(tracks[0].default);
+        assert!(!tracks[1].default);
+        assert_eq!(tracks[1].language.as_deref(), Some("eng"));
+        assert_eq!(tracks[2].source_index, Some(1));
+    }
+
+    #[test]
+    fn transcode_lossless_only_touches_lossless_tracks() {
+        let mut metadata = analysis().metadata;
+        metadata.audio_streams = surround_and_commentary_streams();
+        let tracks = plan_audio(
+            &metadata,
+            "mkv",
+            Some(AudioMode::TranscodeLossless),
+            &crate::config::StreamRules::default(),
+            &crate::media::ffmpeg::EncoderCapabilities::default(),
+        );
+
+        assert_eq!(tracks.len(), 2);
+        assert!(matches!(
+            tracks[0].action,
+            AudioStreamPlan::Transcode {
+                codec: AudioCodec::Aac,
+                ..
+            }
+        ));
+        assert_eq!(tracks[1].action, AudioStreamPlan::Copy);
+    }
+
+    #[test]
+    fn loudness_target_only_applies_to_reencoded_tracks() {
+        let mut metadata = analysis().metadata;
+        metadata.audio_streams = surround_and_commentary_streams();
+        let mut tracks = plan_audio(
+            &metadata,
+            "mkv",
+            Some(AudioMode::TranscodeLossless),
+            &crate::config::StreamRules::default(),
+            &crate::media::ffmpeg::EncoderCapabilities::default(),
+        );
+        apply_loudness_target(&mut tracks, -23.0);
+
+        let AudioStreamPlan::Transcode { loudness, .. } = &tracks[0].action else {
+            panic!("expected the TrueHD track to be re-encoded");
+        };
+        assert_eq!(
+            loudness.as_ref().map(|loudness| loudness.target_lufs),
+            Some(-23.0)
+        );
+        assert_eq!(tracks[1].action, AudioStreamPlan::Copy);
+    }
+
+    #[test]
+    fn stream_rules_drop_individual_audio_tracks() {
+        let mut metadata = analysis().metadata;
+        metadata.audio_streams = surround_and_commentary_streams();
+        let rules = crate::config::StreamRules {
+            strip_audio_by_title: vec!["commentary".to_string()],
+            ..crate::config::StreamRules::default()
+        };
+        let tracks = plan_audio(
+            &metadata,
+            "mkv",
+            Some(AudioMode::Copy),
+            &rules,
+            &crate::media::ffmpeg::EncoderCapabilities::default(),
+        );
+
+        assert!(tracks[0].is_output());
+        assert_eq!(tracks[1].action, AudioStreamPlan::Drop);
+    }
+
     #[test]
     fn vaapi_plan_includes_hwupload_filter() {
         let mut cfg = config();
@@ -1279,6 +2278,7 @@ mod tests {
             &cfg,
             &SubtitleStreamPlan::Drop,
             HdrMode::Preserve,
+            FrameOptions::default(),
         );
         assert!(matches!(
             filters.as_slice(),
@@ -1286,6 +2286,230 @@ mod tests {
         ));
     }
 
+    fn letterboxed_analysis(samples_agree: bool) -> MediaAnalysis {
+        let mut analysis = analysis();
+        let report = &mut analysis.analysis_report;
+        if samples_agree {
+            report
+                .labels
+                .push(crate::media::pipeline::AnalyzerLabel::BlackBars);
+            report.metrics.crop_width = Some(1920);
+            report.metrics.crop_height = Some(800);
+            report.metrics.crop_x = Some(0);
+            report.metrics.crop_y = Some(140);
+        } else {
+            report
+                .labels
+                .push(crate::media::pipeline::AnalyzerLabel::VariableCrop);
+        }
+        report.metrics.crop_sample_count = Some(5);
+        report.metrics.crop_samples_agree = Some(samples_agree);
+        analysis
+    }
+
+    fn interlaced_analysis(confirmed: Option<bool>) -> MediaAnalysis {
+        let mut analysis = analysis();
+        let report = &mut analysis.analysis_report;
+        report
+            .labels
+            .push(crate::media::pipeline::AnalyzerLabel::InterlacedMetadata);
+        report.metrics.interlace_confirmed = confirmed;
+        analysis
+    }
+
+    #[test]
+    fn auto_deinterlace_waits_for_idet_confirmation() {
+        let plan = |analysis: &MediaAnalysis| {
+            plan_filters(
+                analysis,
+                Encoder::HevcX265,
+                &auto_deinterlace_config(),
+                &SubtitleStreamPlan::Drop,
+                HdrMode::Preserve,
+                FrameOptions::default(),
+            )
+        };
+        assert!(plan(&interlaced_analysis(None)).is_empty());
+        assert!(plan(&interlaced_analysis(Some(false))).is_empty());
+        assert!(matches!(
+            plan(&interlaced_analysis(Some(true))).as_slice(),
+            [FilterStep::Deinterlace {
+                algorithm: DeinterlaceAlgorithm::Bwdif,
+                rate: DeinterlaceRate::SendFrame,
+                backend: DeinterlaceBackend::Software,
+            }]
+        ));
+    }
+
+    #[test]
+    fn deinterlace_policy_off_and_always_ignore_the_probe() {
+        // Off is the default.
+        let mut cfg = config();
+        let off = plan_filters(
+            &interlaced_analysis(Some(true)),
+            Encoder::HevcX265,
+            &cfg,
+            &SubtitleStreamPlan::Drop,
+            HdrMode::Preserve,
+            FrameOptions::default(),
+        );
+        assert!(off.is_empty());
+
+        cfg.transcode.deinterlace_mode = DeinterlaceMode::Always;
+        cfg.transcode.deinterlace_algorithm = DeinterlaceAlgorithm::Yadif;
+        cfg.transcode.deinterlace_rate = DeinterlaceRate::SendField;
+        let always = plan_filters(
+            &letterboxed_analysis(true),
+            Encoder::HevcX265,
+            &cfg,
+            &SubtitleStreamPlan::Drop,
+            HdrMode::Preserve,
+            FrameOptions {
+                auto_crop: true,
+                ..FrameOptions::default()
+            },
+        );
+        assert!(matches!(
+            always.as_slice(),
+            [
+                FilterStep::Deinterlace {
+                    algorithm: DeinterlaceAlgorithm::Yadif,
+                    rate: DeinterlaceRate::SendField,
+                    backend: DeinterlaceBackend::Software,
+                },
+                FilterStep::Crop { .. }
+            ]
+        ));
+    }
+
+    #[test]
+    fn hardware_deinterlace_follows_hwupload() {
+        let vaapi = plan_filters(
+            &interlaced_analysis(Some(true)),
+            Encoder::HevcVaapi,
+            &auto_deinterlace_config(),
+            &SubtitleStreamPlan::Drop,
+            HdrMode::Preserve,
+            FrameOptions {
+                hw_filters: Some(HwScaler::Vaapi),
+                ..FrameOptions::default()
+            },
+        );
+        assert!(matches!(
+            vaapi.as_slice(),
+            [
+                FilterStep::Format { .. },
+                FilterStep::HwUpload,
+                FilterStep::Deinterlace {
+                    backend: DeinterlaceBackend::Vaapi,
+                    ..
+                }
+            ]
+        ));
+
+        let qsv = plan_filters(
+            &interlaced_analysis(Some(true)),
+            Encoder::HevcQsv,
+            &auto_deinterlace_config(),
+            &SubtitleStreamPlan::Drop,
+            HdrMode::Preserve,
+            FrameOptions {
+                output_size: Some((1280, 720)),
+                hw_filters: Some(HwScaler::Qsv),
+                ..FrameOptions::default()
+            },
+        );
+        assert!(matches!(
+            qsv.as_slice(),
+            [
+                FilterStep::Format { .. },
+                FilterStep::HwUpload,
+                FilterStep::Deinterlace {
+                    backend: DeinterlaceBackend::Qsv,
+                    ..
+                },
+                FilterStep::HwScale {
+                    scaler: HwScaler::Qsv,
+                    ..
+                }
+            ]
+        ));
+
+        // NVENC has no hardware deinterlacer here, so bwdif runs in software.
+        let nvenc = plan_filters(
+            &interlaced_analysis(Some(true)),
+            Encoder::HevcNvenc,
+            &auto_deinterlace_config(),
+            &SubtitleStreamPlan::Drop,
+            HdrMode::Preserve,
+            FrameOptions {
+                hw_filters: Some(HwScaler::Cuda),
+                ..FrameOptions::default()
+            },
+        );
+        assert!(matches!(
+            nvenc.as_slice(),
+            [FilterStep::Deinterlace {
+                backend: DeinterlaceBackend::Software,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn auto_crop_runs_before_vaapi_hwupload() {
+        let filters = plan_filters(
+            &letterboxed_analysis(true),
+            Encoder::HevcVaapi,
+            &config(),
+            &SubtitleStreamPlan::Drop,
+            HdrMode::Preserve,
+            FrameOptions {
+                auto_crop: true,
+                ..FrameOptions::default()
+            },
+        );
+        assert!(matches!(
+            filters.as_slice(),
+            [
+                FilterStep::Crop {
+                    width: 1920,
+                    height: 800,
+                    x: 0,
+                    y: 140
+                },
+                FilterStep::Format { .. },
+                FilterStep::HwUpload
+            ]
+        ));
+    }
+
+    #[test]
+    fn auto_crop_requires_opt_in_and_agreeing_samples() {
+        let opted_out = plan_filters(
+            &letterboxed_analysis(true),
+            Encoder::HevcX265,
+            &config(),
+            &SubtitleStreamPlan::Drop,
+            HdrMode::Preserve,
+            FrameOptions::default(),
+        );
+        assert!(opted_out.is_empty());
+
+        let variable_aspect = plan_filters(
+            &letterboxed_analysis(false),
+            Encoder::HevcX265,
+            &config(),
+            &SubtitleStreamPlan::Drop,
+            HdrMode::Preserve,
+            FrameOptions {
+                auto_crop: true,
+                ..FrameOptions::default()
+            },
+        );
+        assert!(variable_aspect.is_empty());
+    }
+
     #[test]
     fn burn_prefers_forced_then_default_then_first_burnable() {
         let streams = vec![
@@ -1323,6 +2547,7 @@ mod tests {
             "mkv",
             Path::new("/tmp/out.mkv"),
             SubtitleMode::Burn,
+            &crate::config::StreamRules::default(),
         )
         .unwrap_or_else(|err| panic!("failed to build burn plan: {err}"));
         assert!(matches!(plan, SubtitleStreamPlan::Burn { stream_index: 2 }));
@@ -1343,6 +2568,7 @@ mod tests {
             "mkv",
             Path::new("/tmp/out.mkv"),
             SubtitleMode::Burn,
+            &crate::config::StreamRules::default(),
         ) {
             Ok(_) => panic!("expected burn planning to fail without a burnable stream"),
             Err(reason) => reason,
@@ -1365,6 +2591,7 @@ mod tests {
             "mkv",
             Path::new("/tmp/library/movie-alchemist.mkv"),
             SubtitleMode::Extract,
+            &crate::config::StreamRules::default(),
         )
         .unwrap_or_else(|err| panic!("failed to build extract plan: {err}"));
 
@@ -1412,6 +2639,7 @@ mod tests {
             "mkv",
             Path::new("/tmp/library/movie-alchemist.mkv"),
             SubtitleMode::Extract,
+            &crate::config::StreamRules::default(),
         )
         .unwrap_or_else(|err| panic!("failed to build extract plan: {err}"));
 
@@ -1435,7 +2663,7 @@ mod tests {
     fn mp4_target_codec_to_mkv_remuxes_instead_of_skipping() {
         let mut source = analysis();
         source.metadata.container = "mp4".to_string();
-        let decision = should_transcode(&source, &config(), OutputCodec::Hevc, "mkv");
+        let decision = should_transcode(&source, &config(), OutputCodec::Hevc, "mkv", None);
         assert!(matches!(decision, TranscodeDecision::Remux { .. }));
     }
 
@@ -1443,7 +2671,7 @@ mod tests {
     fn already_target_codec_in_mkv_still_skips() {
         let mut source = analysis();
         source.metadata.container = "matroska".to_string();
-        let decision = should_transcode(&source, &config(), OutputCodec::Hevc, "mkv");
+        let decision = should_transcode(&source, &config(), OutputCodec::Hevc, "mkv", None);
         assert!(matches!(decision, TranscodeDecision::Skip { .. }));
     }
 
@@ -1452,13 +2680,13 @@ mod tests {
         let mut source = analysis();
         source.metadata.codec_name = "av1".to_string();
         source.metadata.container = "matroska".to_string();
-        let decision = should_transcode(&source, &config(), OutputCodec::Av1, "mkv");
+        let decision = should_transcode(&source, &config(), OutputCodec::Av1, "mkv", None);
         assert!(matches!(decision, TranscodeDecision::Skip { .. }));
     }
 
     #[test]
     fn already_target_codec_reason_is_stable() {
-        let decision = should_transcode(&analysis(), &config(), OutputCodec::Hevc, "mkv");
+        let decision = should_transcode(&analysis(), &config(), OutputCodec::Hevc, "mkv", None);
         let TranscodeDecision::Skip { reason } = decision else {
             panic!("expected skip decision");
         };
@@ -1474,7 +2702,7 @@ mod tests {
     fn remux_reason_is_stable() {
         let mut source = analysis();
         source.metadata.container = "mp4".to_string();
-        let decision = should_transcode(&source, &config(), OutputCodec::Hevc, "mkv");
+        let decision = should_transcode(&source, &config(), OutputCodec::Hevc, "mkv", None);
         let TranscodeDecision::Remux { reason } = decision else {
             panic!("expected remux decision");
         };
@@ -1492,7 +2720,7 @@ mod tests {
         source.metadata.codec_name = "mpeg4".to_string();
         source.metadata.bit_depth = Some(8);
         source.metadata.video_bitrate_bps = Some(1_000_000);
-        let decision = should_transcode(&source, &config(), OutputCodec::Av1, "mkv");
+        let decision = should_transcode(&source, &config(), OutputCodec::Av1, "mkv", None);
         let TranscodeDecision::Skip { reason } = decision else {
             panic!("expected skip decision");
         };
@@ -1506,7 +2734,7 @@ mod tests {
         source.metadata.codec_name = "mpeg4".to_string();
         source.metadata.bit_depth = Some(8);
         source.metadata.size_bytes = 20 * 1024 * 1024;
-        let decision = should_transcode(&source, &config(), OutputCodec::Av1, "mkv");
+        let decision = should_transcode(&source, &config(), OutputCodec::Av1, "mkv", None);
         let TranscodeDecision::Skip { reason } = decision else {
             panic!("expected skip decision");
         };
@@ -1520,7 +2748,7 @@ mod tests {
         source.metadata.codec_name = "mpeg4".to_string();
         source.metadata.bit_depth = Some(8);
         source.metadata.width = 0;
-        let decision = should_transcode(&source, &config(), OutputCodec::Av1, "mkv");
+        let decision = should_transcode(&source, &config(), OutputCodec::Av1, "mkv", None);
         let TranscodeDecision::Skip { reason } = decision else {
             panic!("expected skip decision");
         };
@@ -1528,6 +2756,406 @@ mod tests {
         assert_eq!(explanation.code, "incomplete_metadata");
     }
 
+    #[test]
+    fn downscale_target_preserves_aspect_and_orientation() {
+        assert_eq!(
+            downscale_target((3840, 2160), MaxResolution::P1080),
+            Some((1920, 1080))
+        );
+        assert_eq!(
+            downscale_target((1920, 800), MaxResolution::P720),
+            Some((1280, 532))
+        );
+        assert_eq!(
+            downscale_target((1080, 1920), MaxResolution::P720),
+            Some((720, 1280))
+        );
+        assert_eq!(downscale_target((1920, 1080), MaxResolution::P1080), None);
+    }
+
+    #[test]
+    fn downscale_overrides_already_target_codec_skip() {
+        let mut source = analysis();
+        source.metadata.width = 3840;
+        source.metadata.height = 2160;
+        let decision = should_transcode(
+            &source,
+            &config(),
+            OutputCodec::Hevc,
+            "mkv",
+            Some((1920, 1080)),
+        );
+        let TranscodeDecision::Transcode { reason } = decision else {
+            panic!("expected transcode decision");
+        };
+        let explanation = crate::explanations::decision_from_legacy("transcode", &reason);
+        assert_eq!(explanation.code, "downscale_to_max_resolution");
+    }
+
+    #[test]
+    fn bpp_threshold_uses_downscaled_pixel_count() {
+        let mut source = analysis();
+        source.metadata.codec_name = "mpeg4".to_string();
+        source.metadata.bit_depth = Some(8);
+        source.metadata.width = 3840;
+        source.metadata.height = 2160;
+        source.metadata.video_bitrate_bps = Some(6_000_000);
+
+        let full_size = should_transcode(&source, &config(), OutputCodec::Av1, "mkv", None);
+        assert!(matches!(full_size, TranscodeDecision::Skip { .. }));
+
+        let downscaled = should_transcode(
+            &source,
+            &config(),
+            OutputCodec::Av1,
+            "mkv",
+            Some((1280, 720)),
+        );
+        assert!(matches!(downscaled, TranscodeDecision::Transcode { .. }));
+    }
+
+    #[test]
+    fn downscale_uses_backend_scaler() {
+        let mut source = analysis();
+        source.metadata.width = 3840;
+        source.metadata.height = 2160;
+        let downscale = |hw_filters| FrameOptions {
+            output_size: Some((1920, 1080)),
+            hw_filters,
+            ..FrameOptions::default()
+        };
+
+        let software = plan_filters(
+            &source,
+            Encoder::HevcX265,
+            &config(),
+            &SubtitleStreamPlan::Drop,
+            HdrMode::Preserve,
+            downscale(None),
+        );
+        assert!(matches!(
+            software.as_slice(),
+            [FilterStep::Scale {
+                width: 1920,
+                height: 1080
+            }]
+        ));
+
+        let vaapi = plan_filters(
+            &source,
+            Encoder::HevcVaapi,
+            &config(),
+            &SubtitleStreamPlan::Drop,
+            HdrMode::Preserve,
+            downscale(Some(HwScaler::Vaapi)),
+        );
+        assert!(matches!(
+            vaapi.as_slice(),
+            [
+                FilterStep::Format { .. },
+                FilterStep::HwUpload,
+                FilterStep::HwScale {
+                    scaler: HwScaler::Vaapi,
+                    ..
+                }
+            ]
+        ));
+
+        let cuda = plan_filters(
+            &source,
+            Encoder::HevcNvenc,
+            &config(),
+            &SubtitleStreamPlan::Drop,
+            HdrMode::Preserve,
+            downscale(Some(HwScaler::Cuda)),
+        );
+        let [
+            FilterStep::Format { pixel_format },
+            FilterStep::HwUploadCuda,
+            FilterStep::HwScale {
+                scaler: HwScaler::Cuda,
+                ..
+            },
+        ] = cuda.as_slice()
+        else {
+            panic!("expected cuda upload and scale, got {cuda:?}");
+        };
+        assert_eq!(pixel_format, "p010le");
+    }
+
+    #[test]
+    fn qsv_scales_in_software_without_a_render_node() {
+        assert_eq!(hardware_scaler(Encoder::HevcQsv, None), None);
+        assert_eq!(
+            hardware_scaler(Encoder::HevcNvenc, None),
+            Some(HwScaler::Cuda)
+        );
+        assert_eq!(hardware_scaler(Encoder::HevcX265, None), None);
+    }
+
+    fn dolby_vision_analysis(profile: u32, compat: u32) -> MediaAnalysis {
+        let mut source = analysis();
+        source.metadata.dynamic_range = DynamicRange::DolbyVision;
+        source
+            .analysis_report
+            .labels
+            .push(crate::media::pipeline::AnalyzerLabel::DolbyVisionMetadata);
+        source.analysis_report.metrics.dolby_vision_profile = Some(profile);
+        source.analysis_report.metrics.dolby_vision_bl_compat_id = Some(compat);
+        source
+    }
+
+    fn profile_with_dolby_vision_mode(mode: &str) -> crate::db::LibraryProfile {
+        crate::db::LibraryProfile {
+            id: 10,
+            name: "Dolby Vision".to_string(),
+            preset: "custom".to_string(),
+            codec: "av1".to_string(),
+            quality_profile: "balanced".to_string(),
+            hdr_mode: "tonemap".to_string(),
+            audio_mode: "copy".to_string(),
+            crf_override: None,
+            target_vmaf: None,
+            auto_crop: false,
+            max_resolution: None,
+            dolby_vision_mode: mode.to_string(),
+            loudness_target_lufs: None,
+            output_variants_json: "[]".to_string(),
+            notes: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn effective_settings_layer_overrides_over_profile_over_config() {
+        let config = config();
+        let profile = profile_with_dolby_vision_mode("strip");
+        let overrides = JobOverrides {
+            codec: Some(OutputCodec::Hevc),
+            crf: Some(24),
+            stream_rules: Some(StreamRules {
+                strip_audio_by_title: vec!["commentary".to_string()],
+                ..StreamRules::default()
+            }),
+            ..JobOverrides::default()
+        };
+
+        let settings = EffectiveSettings::resolve(&config, Some(&profile), Some(&overrides));
+        assert_eq!(settings.codec, OutputCodec::Hevc);
+        assert_eq!(settings.crf, Some(24));
+        assert_eq!(settings.hdr_mode, HdrMode::Tonemap);
+        assert_eq!(settings.audio_mode, Some(AudioMode::Copy));
+        assert_eq!(settings.stream_rules.strip_audio_by_title, ["commentary"]);
+
+        let settings = EffectiveSettings::resolve(&config, None, Some(&overrides));
+        assert_eq!(settings.quality_profile, config.transcode.quality_profile);
+        assert_eq!(settings.audio_mode, None);
+    }
+
+    async fn dolby_vision_skip_reason(source: &MediaAnalysis, mode: &str) -> String {
+        let planner = BasicPlanner::new(Arc::new(config()), None);
+        let profile = profile_with_dolby_vision_mode(mode);
+        let plan = planner
+            .plan(source, Path::new("/tmp/out.mkv"), Some(&profile))
+            .await
+            .unwrap_or_else(|err| panic!("failed to build dolby vision plan: {err}"));
+        let TranscodeDecision::Skip { reason } = plan.decision else {
+            panic!("expected skip decision");
+        };
+        reason
+    }
+
+    #[tokio::test]
+    async fn marked_outputs_are_skipped_unless_their_settings_changed() {
+        let plan_reason = |config: Config, settings_digest: String| async move {
+            let mut source = analysis();
+            source.metadata.alchemist_marker = Some(ProcessedMarker {
+                version: "0.3.5".to_string(),
+                profile: "default".to_string(),
+                source_hash: "3f9c0a1b2c3d4e5f".to_string(),
+                settings_digest,
+            });
+            let plan = BasicPlanner::new(Arc::new(config), None)
+                .plan(&source, Path::new("/tmp/out.mkv"), None)
+                .await
+                .unwrap_or_else(|err| panic!("failed to plan marked source: {err}"));
+            match plan.decision {
+                TranscodeDecision::Skip { reason } => reason,
+                _ => String::new(),
+            }
+        };
+        let current = settings_digest(
+            &EffectiveSettings::resolve(&config(), None, None),
+            "mkv",
+            None,
+        );
+
+        assert_eq!(
+            plan_reason(config(), current.clone()).await,
+            "already_processed_by_alchemist|version=0.3.5,settings_changed=false"
+        );
+        assert_eq!(
+            plan_reason(config(), "stale".to_string()).await,
+            "already_processed_by_alchemist|version=0.3.5,settings_changed=true"
+        );
+
+        let mut reprocess = config();
+        reprocess.transcode.reprocess_on_settings_change = true;
+        assert!(
+            plan_reason(reprocess.clone(), current)
+                .await
+                .starts_with("already_processed_by_alchemist")
+        );
+        assert!(
+            !plan_reason(reprocess, "stale".to_string())
+                .await
+                .starts_with("already_processed_by_alchemist")
+        );
+    }
+
+    #[tokio::test]
+    async fn dolby_vision_profile5_is_skipped_under_every_mode() {
+        for mode in ["skip", "strip", "preserve"] {
+            let reason = dolby_vision_skip_reason(&dolby_vision_analysis(5, 0), mode).await;
+            assert_eq!(reason, "dolby_vision_profile5_no_fallback|profile=5");
+        }
+    }
+
+    #[tokio::test]
+    async fn dolby_vision_skip_mode_reports_profile() {
+        let reason = dolby_vision_skip_reason(&dolby_vision_analysis(8, 1), "skip").await;
+        assert_eq!(reason, "dolby_vision_skipped|profile=8.1");
+    }
+
+    #[tokio::test]
+    async fn dolby_vision_strip_keeps_the_downscale_reason() {
+        let mut encoder_caps = crate::media::ffmpeg::EncoderCapabilities::default();
+        encoder_caps.video_encoders.insert("libsvtav1".to_string());
+        let mut cfg = config();
+        cfg.hardware.allow_cpu_encoding = true;
+        cfg.hardware.allow_cpu_fallback = true;
+        let planner = BasicPlanner {
+            config: Arc::new(cfg),
+            hw_info: None,
+            encoder_caps,
+            overrides: None,
+        };
+        let mut profile = profile_with_dolby_vision_mode("strip");
+        profile.max_resolution = Some("1080p".to_string());
+        let mut source = dolby_vision_analysis(8, 1);
+        source.metadata.width = 3840;
+        source.metadata.height = 2160;
+
+        let plan = planner
+            .plan(&source, Path::new("/tmp/out.mkv"), Some(&profile))
+            .await
+            .unwrap_or_else(|err| panic!("failed to build dolby vision plan: {err}"));
+        let TranscodeDecision::Transcode { reason } = plan.decision else {
+            panic!("expected transcode decision, got {:?}", plan.decision);
+        };
+        assert!(reason.starts_with("downscale_to_max_resolution|"));
+        assert!(reason.ends_with(",dolby_vision=stripped_to_hdr10,dolby_vision_profile=8.1"));
+
+        let explanation = crate::explanations::decision_from_legacy("transcode", &reason);
+        assert_eq!(explanation.code, "downscale_to_max_resolution");
+        assert!(explanation.detail.contains("Dolby Vision RPU is dropped"));
+        assert_eq!(
+            explanation.measured.get("dolby_vision"),
+            Some(&serde_json::json!("stripped_to_hdr10"))
+        );
+    }
+
+    #[test]
+    fn dolby_vision_preserve_needs_profile_8_1_on_x265() {
+        let Some(source) = DolbyVisionSource::from_analysis(&dolby_vision_analysis(8, 1)) else {
+            panic!("expected dolby vision source");
+        };
+        assert!(source.supports_preserve(Encoder::HevcX265));
+        assert!(!source.supports_preserve(Encoder::HevcNvenc));
+        assert!(!source.supports_preserve(Encoder::Av1Svt));
+
+        let Some(hlg_base) = DolbyVisionSource::from_analysis(&dolby_vision_analysis(8, 4)) else {
+            panic!("expected dolby vision source");
+        };
+        assert_eq!(hlg_base.label(), "8.4");
+        assert!(!hlg_base.supports_preserve(Encoder::HevcX265));
+        assert!(DolbyVisionSource::from_analysis(&analysis()).is_none());
+    }
+
+    #[test]
+    fn dolby_vision_strip_runs_first() {
+        let mut source = dolby_vision_analysis(8, 1);
+        source.metadata.width = 3840;
+        source.metadata.height = 2160;
+        let filters = plan_filters(
+            &source,
+            Encoder::HevcVaapi,
+            &config(),
+            &SubtitleStreamPlan::Drop,
+            HdrMode::Preserve,
+            FrameOptions {
+                output_size: Some((1920, 1080)),
+                hw_filters: Some(HwScaler::Vaapi),
+                strip_dolby_vision: true,
+                ..FrameOptions::default()
+            },
+        );
+        assert!(matches!(
+            filters.as_slice(),
+            [
+                FilterStep::StripDolbyVision,
+                FilterStep::Format { .. },
+                FilterStep::HwUpload,
+                FilterStep::HwScale { .. }
+            ]
+        ));
+    }
+
+    #[test]
+    fn hdr10_plus_is_kept_or_reported_as_a_downgrade() {
+        let mut source = analysis();
+        source.metadata.dynamic_range = DynamicRange::Hdr10;
+        source
+            .analysis_report
+            .labels
+            .push(crate::media::pipeline::AnalyzerLabel::Hdr10PlusMetadata);
+
+        assert_eq!(
+            plan_hdr10_plus(&source, Encoder::HevcX265, &[]),
+            (true, Vec::new())
+        );
+
+        for (encoder, name) in [
+            (Encoder::HevcNvenc, "hevc_nvenc"),
+            (Encoder::Av1Svt, "libsvtav1"),
+        ] {
+            let (preserve, downgrades) = plan_hdr10_plus(&source, encoder, &[]);
+            assert!(!preserve);
+            assert_eq!(
+                downgrades,
+                vec![PlannedDowngrade {
+                    kind: DowngradeKind::Hdr10PlusToHdr10,
+                    reason: format!("hdr10_plus_dropped|encoder={name}"),
+                }]
+            );
+        }
+
+        let tonemap = [FilterStep::Tonemap {
+            algorithm: TonemapAlgorithm::Hable,
+            peak: 100.0,
+            desat: 0.2,
+        }];
+        assert_eq!(
+            plan_hdr10_plus(&source, Encoder::HevcNvenc, &tonemap),
+            (false, Vec::new())
+        );
+        assert_eq!(
+            plan_hdr10_plus(&analysis(), Encoder::HevcNvenc, &[]),
+            (false, Vec::new())
+        );
+    }
+
     #[tokio::test]
     async fn no_available_encoders_reason_is_stable() {
         let mut cfg = config();
@@ -1685,6 +3313,7 @@ mod tests {
             strip_audio_by_title: vec!["commentary".to_string()],
             keep_audio_languages: Vec::new(),
             keep_only_default_audio: false,
+            ..crate::config::StreamRules::default()
         };
 
         let kept = filter_audio_streams(
@@ -1720,6 +3349,7 @@ mod tests {
             strip_audio_by_title: vec!["commentary".to_string()],
             keep_audio_languages: vec!["jpn".to_string()],
             keep_only_default_audio: false,
+            ..crate::config::StreamRules::default()
         };
 
         let kept = filter_audio_streams(
@@ -1755,6 +3385,7 @@ mod tests {
             strip_audio_by_title: Vec::new(),
             keep_audio_languages: vec!["jpn".to_string()],
             keep_only_default_audio: true,
+            ..crate::config::StreamRules::default()
         };
 
         let kept = filter_audio_streams(
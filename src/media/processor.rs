@@ -34,6 +34,12 @@ pub struct Agent {
     /// next job's output filesystem is below the configured free-space minimum.
     disk_blocked: Arc<AtomicBool>,
     disk_block_reason: Arc<std::sync::Mutex<Option<String>>>,
+    /// The schedule window in force, set by the scheduler.
+    schedule_window: Arc<std::sync::Mutex<Option<crate::scheduler::ActiveScheduleWindow>>>,
+    /// Set while the active window allows none of this host's backends.
+    schedule_backend_blocked: Arc<AtomicBool>,
+    /// Why the resource governor is throttling job starts, if it is.
+    throttle_reason: Arc<std::sync::Mutex<Option<String>>>,
 }
 
 impl Agent {
@@ -72,9 +78,155 @@ impl Agent {
             analysis_semaphore: Arc::new(tokio::sync::Semaphore::new(concurrent_jobs.clamp(1, 4))),
             disk_blocked: Arc::new(AtomicBool::new(false)),
             disk_block_reason: Arc::new(std::sync::Mutex::new(None)),
+            schedule_window: Arc::new(std::sync::Mutex::new(None)),
+            schedule_backend_blocked: Arc::new(AtomicBool::new(false)),
+            throttle_reason: Arc::new(std::sync::Mutex::new(None)),
         }
     }
 
+    pub fn active_schedule_window(&self) -> Option<crate::scheduler::ActiveScheduleWindow> {
+        self.schedule_window
+            .lock()
+            .ok()
+            .and_then(|window| window.clone())
+    }
+
+    pub fn set_active_schedule_window(
+        &self,
+        window: Option<crate::scheduler::ActiveScheduleWindow>,
+    ) {
+        match self.schedule_window.lock() {
+            Ok(mut current) => *current = window,
+            Err(e) => *e.into_inner() = window,
+        }
+        let _ = self
+            .event_channels
+            .system
+            .send(SystemEvent::EngineStatusChanged);
+    }
+
+    /// Whether job starts are held because the active schedule window allows
+    /// none of the encoder backends this host can use.
+    pub fn is_schedule_backend_blocked(&self) -> bool {
+        self.schedule_backend_blocked.load(Ordering::SeqCst)
+    }
+
+    async fn schedule_backends_should_hold(&self) -> bool {
+        let allowed = self
+            .active_schedule_window()
+            .map(|window| window.allowed_backends)
+            .unwrap_or_default();
+        let blocked = if allowed.is_empty() {
+            false
+        } else {
+            let hw_info = self.hardware_state.snapshot().await;
+            let config = self.config.read().await;
+            !crate::media::planner::usable_backends(&config, hw_info.as_ref())
+                .iter()
+                .any(|backend| allowed.contains(backend))
+        };
+        if self
+            .schedule_backend_blocked
+            .swap(blocked, Ordering::SeqCst)
+            != blocked
+        {
+            if blocked {
+                warn!("Engine holding jobs — schedule window allows none of this host's encoders.");
+            } else {
+                info!("Schedule backend hold cleared — resuming job starts.");
+            }
+            let _ = self
+                .event_channels
+                .system
+                .send(SystemEvent::EngineStatusChanged);
+        }
+        blocked
+    }
+
+    /// Why the resource governor is throttling the engine, if it is.
+    pub fn throttle_reason(&self) -> Option<String> {
+        self.throttle_reason
+            .lock()
+            .ok()
+            .and_then(|reason| reason.clone())
+    }
+
+    pub fn is_throttled(&self) -> bool {
+        self.throttle_reason().is_some()
+    }
+
+    /// Set by the resource governor each time it samples the host.
+    pub fn set_throttle(&self, reason: Option<String>) {
+        let previous = match self.throttle_reason.lock() {
+            Ok(mut current) => std::mem::replace(&mut *current, reason.clone()),
+            Err(e) => std::mem::replace(&mut *e.into_inner(), reason.clone()),
+        };
+        match (&previous, &reason) {
+            (None, Some(reason)) => warn!("Engine throttled — {reason}"),
+            (Some(_), None) => info!("Resource governor cleared — resuming job starts."),
+            _ => {}
+        }
+        if previous != reason {
+            let _ = self
+                .event_channels
+                .system
+                .send(SystemEvent::EngineStatusChanged);
+        }
+    }
+
+    /// Stop or resume running encodes for the resource governor; the stall
+    /// check leaves stopped encodes alone.
+    pub fn set_encodes_suspended(&self, suspended: bool) {
+        if self.orchestrator.encodes_suspended() != suspended {
+            if suspended {
+                warn!("Resource governor stopping running encodes.");
+            } else {
+                info!("Resource governor resuming stopped encodes.");
+            }
+        }
+        self.orchestrator.set_encodes_suspended(suspended);
+    }
+
+    pub fn encodes_suspended(&self) -> bool {
+        self.orchestrator.encodes_suspended()
+    }
+
+    /// Whether the resource governor holds this iteration: always under
+    /// `action = "pause"`, or once `reduced_concurrent_jobs` are running
+    /// under `action = "reduce"`.
+    async fn governor_should_hold(&self) -> bool {
+        self.governor_holds_at(self.in_flight_jobs.load(Ordering::SeqCst))
+            .await
+    }
+
+    async fn governor_holds_at(&self, running: usize) -> bool {
+        if !self.is_throttled() {
+            return false;
+        }
+        let governor = self.config.read().await.system.resource_governor.clone();
+        match governor.action {
+            crate::config::GovernorAction::Pause => true,
+            crate::config::GovernorAction::Reduce => running >= governor.reduced_concurrent_jobs,
+        }
+    }
+
+    /// Whether a remote worker must wait before leasing another job. Leases
+    /// follow the holds the claim loop applies to local starts, with the
+    /// `leased` jobs counted beside local ones against the governor's reduced
+    /// limit and the schedule window's concurrency.
+    pub(crate) async fn lease_should_hold(&self, leased: usize) -> bool {
+        if self.is_paused() || self.is_draining() || self.is_disk_blocked() {
+            return true;
+        }
+        let running = self.in_flight_jobs.load(Ordering::SeqCst) + leased;
+        if self.governor_holds_at(running).await {
+            return true;
+        }
+        self.active_schedule_window()
+            .and_then(|window| window.concurrent_jobs)
+            .is_some_and(|limit| running >= limit)
+    }
+
     /// Whether the engine is currently holding jobs because of the AUTO-3 disk
     /// guardrail (the next queued job's output filesystem is below the
     /// configured free-space minimum).
@@ -122,6 +274,34 @@ impl Agent {
         }
     }
 
+    /// How the next job is picked. Space goals that cannot be read are left
+    /// out rather than stalling the queue.
+    pub(crate) async fn queue_ranking(&self) -> crate::db::QueueRanking {
+        let order = self.config.read().await.transcode.queue_order;
+        let mut ranking = match self.db.queue_ranking(order).await {
+            Ok(ranking) => ranking,
+            Err(e) => {
+                debug!("Failed to load space goals for queue ranking: {e}");
+                crate::db::QueueRanking {
+                    order,
+                    ..crate::db::QueueRanking::default()
+                }
+            }
+        };
+        ranking.hold_cpu_retries = !self.window_allows_cpu();
+        ranking
+    }
+
+    /// Whether the active schedule window, if any, lets jobs encode on the CPU.
+    fn window_allows_cpu(&self) -> bool {
+        self.active_schedule_window().is_none_or(|window| {
+            window.allowed_backends.is_empty()
+                || window
+                    .allowed_backends
+                    .contains(&crate::media::pipeline::EncoderBackend::Cpu)
+        })
+    }
+
     /// AUTO-3: returns `true` when the engine should hold this iteration because
     /// the next queued job's output filesystem has less than
     /// `system.min_free_space_gb` free. Fails open — a disabled guardrail, no
@@ -133,7 +313,7 @@ impl Agent {
             return false;
         }
 
-        let next = match self.db.get_next_job().await {
+        let next = match self.db.get_next_job(&self.queue_ranking().await).await {
             Ok(Some(job)) => job,
             Ok(None) => {
                 self.clear_disk_block();
@@ -469,6 +649,19 @@ impl Agent {
         );
     }
 
+    /// Set mode and concurrency directly, as schedule windows do. Unlike
+    /// `apply_mode`, the concurrency need not follow from the mode.
+    pub async fn set_runtime_limits(
+        &self,
+        mode: crate::config::EngineMode,
+        concurrent_jobs: usize,
+        manual_override: bool,
+    ) {
+        *self.engine_mode.write().await = mode;
+        self.set_manual_override(manual_override);
+        self.set_concurrent_jobs(concurrent_jobs).await;
+    }
+
     pub fn set_manual_override(&self, value: bool) {
         self.manual_override.store(value, Ordering::SeqCst);
     }
@@ -557,6 +750,19 @@ impl Agent {
                 continue;
             }
 
+            // A schedule window's backend allow-list that excludes every
+            // encoder this host has would otherwise skip each job it plans.
+            if self.schedule_backends_should_hold().await {
+                tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
+                continue;
+            }
+
+            // The host is busy with other work; let it have the machine.
+            if self.governor_should_hold().await {
+                tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+                continue;
+            }
+
             if self.hardware_state.snapshot().await.is_none() {
                 debug!("Hardware detection pending; engine claim loop is waiting.");
                 tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
@@ -602,7 +808,8 @@ impl Agent {
             }
 
             let current_mode = *self.engine_mode.read().await;
-            match self.db.claim_next_job_with_mode(current_mode).await {
+            let ranking = self.queue_ranking().await;
+            match self.db.claim_next_job_ranked(current_mode, &ranking).await {
                 Ok(Some(job)) => {
                     self.idle_notified.store(false, Ordering::SeqCst);
                     let next_in_flight = self.in_flight_jobs.fetch_add(1, Ordering::SeqCst) + 1;
@@ -659,44 +866,108 @@ impl Agent {
     }
 
     pub async fn process_job(&self, job: crate::db::Job) -> Result<()> {
-        use crate::media::pipeline::JobFailure;
-
         let job_id = job.id;
-        // The pipeline increments attempt_count during the run, so the number of
-        // attempts made once it returns is the pre-run count plus one.
-        let attempts_made = job.attempt_count.saturating_add(1);
+        let Some(pipeline) = self.pipeline_for_job(job_id).await else {
+            // The window changed between the claim and now; the claim query
+            // leaves the job alone until a window allows the CPU again.
+            info!(
+                job_id,
+                "CPU retry held by the schedule window; returning to queue."
+            );
+            self.db
+                .update_job_status(job_id, crate::db::JobState::Queued)
+                .await?;
+            return Ok(());
+        };
+        let outcome = pipeline.process_job(job).await;
+        self.conclude_job(&pipeline, job_id, outcome).await
+    }
+
+    /// The pipeline for running `job_id`, held to the CPU when a retry asked
+    /// for it. `None` when the retry must run on the CPU but the active
+    /// schedule window forbids it.
+    pub(crate) async fn pipeline_for_job(&self, job_id: i64) -> Option<Pipeline> {
+        let retry_on_cpu = match self.db.get_job_retry_state(job_id).await {
+            Ok(state) => state.retry_on_cpu,
+            Err(e) => {
+                warn!(job_id, "Failed to load retry state: {e}");
+                false
+            }
+        };
         let pipeline = self.pipeline();
+        if !retry_on_cpu {
+            return Some(pipeline);
+        }
+        self.window_allows_cpu().then(|| {
+            pipeline.with_allowed_backends(vec![crate::media::pipeline::EncoderBackend::Cpu])
+        })
+    }
+
+    /// Attempts `job_id` has made, including the run that just ended. The
+    /// pipeline counts each attempt as it starts, locally or for a worker.
+    async fn attempts_made(&self, job_id: i64) -> u32 {
+        match self.db.get_job_by_id(job_id).await {
+            Ok(Some(job)) => job.attempt_count.max(0) as u32,
+            Ok(None) => 1,
+            Err(e) => {
+                warn!(job_id, "Failed to load attempt count: {e}");
+                1
+            }
+        }
+    }
 
-        match pipeline.process_job(job).await {
+    /// Apply the retry policy to a finished run of `job_id`, then produce
+    /// its output variants and run post hooks unless it was requeued.
+    pub(crate) async fn conclude_job(
+        &self,
+        pipeline: &Pipeline,
+        job_id: i64,
+        outcome: std::result::Result<(), crate::media::pipeline::JobFailure>,
+    ) -> Result<()> {
+        use crate::media::pipeline::JobFailure;
+
+        let result = match outcome {
             Ok(()) => Ok(()),
             Err(failure) => {
+                let attempts_made = self.attempts_made(job_id).await;
                 let code = job_failure_code(&failure);
-                // Only genuinely transient failures (transient IO, a full disk
-                // that may clear) are worth retrying; deterministic failures
-                // (corrupt media, encoder-open, planner bug) fail fast. The
-                // pipeline already attempts a one-time CPU fallback for hardware
-                // encoder-open failures before surfacing them here.
-                if matches!(failure, JobFailure::Transient)
-                    && attempts_made < MAX_TRANSIENT_ATTEMPTS
-                {
-                    let backoff = transient_backoff(attempts_made);
+                let (policy, allow_cpu_fallback) = {
+                    let config = self.config.read().await;
+                    (
+                        config.retry.policy_for(&failure).clone(),
+                        config.hardware.allow_cpu_encoding && config.hardware.allow_cpu_fallback,
+                    )
+                };
+                let encoder_unavailable = matches!(failure, JobFailure::EncoderUnavailable);
+                let on_cpu = encoder_unavailable && policy.fallback_to_cpu && allow_cpu_fallback;
+                let mut delay = policy.next_delay(attempts_made);
+                if encoder_unavailable && !on_cpu && delay.is_some() {
+                    // Another attempt would hit the same missing encoder.
+                    delay = None;
+                    let message = "Encoder unavailable and CPU fallback is off; not retrying.";
+                    tracing::warn!(job_id, "{message}");
+                    let _ = self.db.add_log("warn", Some(job_id), message).await;
+                }
+                // Each failure kind retries per its policy; by default only
+                // transient failures and unavailable encoders (on CPU) retry.
+                if let Some(delay) = delay {
                     tracing::warn!(
                         job_id,
                         attempt = attempts_made,
-                        max_attempts = MAX_TRANSIENT_ATTEMPTS,
+                        max_attempts = policy.max_attempts,
                         error_code = code,
-                        "Transient job failure; requeueing in {}s",
-                        backoff.as_secs()
+                        "Job failure is retryable; requeueing in {}s{}",
+                        delay.as_secs(),
+                        if on_cpu { " on CPU" } else { "" }
                     );
-                    tokio::time::sleep(backoff).await;
                     if let Err(e) = self
                         .db
-                        .update_job_status(job_id, crate::db::JobState::Queued)
+                        .schedule_job_retry(job_id, delay, code, on_cpu)
                         .await
                     {
-                        tracing::error!(job_id, "Failed to requeue transient job: {e}");
+                        tracing::error!(job_id, "Failed to requeue job for retry: {e}");
                         return Err(crate::error::AlchemistError::Unknown(format!(
-                            "job {job_id} requeue failed after transient error: {e}"
+                            "job {job_id} requeue failed after {code} error: {e}"
                         )));
                     }
                     return Ok(());
@@ -708,10 +979,14 @@ impl Agent {
                     "job {job_id} failed ({code})"
                 )))
             }
-        }
+        };
+        pipeline.enqueue_variants(job_id).await;
+        // After the retry decision, so a requeued attempt runs no hooks.
+        pipeline.run_post_hooks(job_id).await;
+        result
     }
 
-    fn pipeline(&self) -> Pipeline {
+    pub(crate) fn pipeline(&self) -> Pipeline {
         Pipeline::new(
             self.db.clone(),
             self.orchestrator.clone(),
@@ -720,6 +995,11 @@ impl Agent {
             self.event_channels.clone(),
             self.dry_run,
         )
+        .with_allowed_backends(
+            self.active_schedule_window()
+                .map(|window| window.allowed_backends)
+                .unwrap_or_default(),
+        )
     }
 
     /// Gracefully shutdown the agent.
@@ -745,18 +1025,6 @@ impl Agent {
     }
 }
 
-/// Maximum total encode attempts (including the first) for a transient failure
-/// before the job is left in the Failed state.
-const MAX_TRANSIENT_ATTEMPTS: i32 = 3;
-
-/// Capped exponential backoff before requeueing a transient failure. Holding the
-/// concurrency permit during this sleep naturally throttles a systemic problem
-/// (e.g. a full disk) instead of hot-looping.
-fn transient_backoff(attempt: i32) -> tokio::time::Duration {
-    let secs = 2u64.saturating_pow(attempt.clamp(1, 4) as u32).min(30);
-    tokio::time::Duration::from_secs(secs)
-}
-
 /// Stable code for a `JobFailure`, aligned with the docs error reference
 /// (`errors#<code>`) so log lines and the failure surface agree.
 fn job_failure_code(failure: &crate::media::pipeline::JobFailure) -> &'static str {
@@ -774,10 +1042,29 @@ mod retry_tests {
     use super::*;
 
     #[test]
-    fn transient_backoff_is_bounded_and_increasing() {
-        assert_eq!(transient_backoff(1).as_secs(), 2);
-        assert_eq!(transient_backoff(2).as_secs(), 4);
-        assert!(transient_backoff(10).as_secs() <= 30);
+    fn default_retry_policies_match_failure_kinds() {
+        use crate::config::RetryConfig;
+        use crate::media::pipeline::JobFailure;
+        let retry = RetryConfig::default();
+
+        let transient = retry.policy_for(&JobFailure::Transient);
+        assert_eq!(transient.next_delay(1).map(|d| d.as_secs()), Some(300));
+        assert_eq!(transient.next_delay(2).map(|d| d.as_secs()), Some(600));
+        assert_eq!(transient.next_delay(3), None);
+
+        assert_eq!(
+            retry.policy_for(&JobFailure::MediaCorrupt).next_delay(1),
+            None
+        );
+        assert_eq!(
+            retry.policy_for(&JobFailure::PlannerBug).next_delay(1),
+            None
+        );
+
+        let encoder = retry.policy_for(&JobFailure::EncoderUnavailable);
+        assert!(encoder.fallback_to_cpu);
+        assert_eq!(encoder.next_delay(1).map(|d| d.as_secs()), Some(60));
+        assert_eq!(encoder.next_delay(2), None);
     }
 
     #[test]
@@ -0,0 +1,424 @@
+//! Ordered rules that pick a library profile from the file itself.
+//!
+//! Watch folders assign one profile to everything beneath them, which does not
+//! fit mixed folders. Profile rules run first: the enabled rule with the lowest
+//! position whose conditions all hold decides, either naming a profile or
+//! skipping the file. When no rule matches, the watch folder profile applies as
+//! before.
+
+use crate::db::{Db, LibraryProfile, ProfileRule};
+use crate::error::Result;
+use crate::explanations::Explanation;
+use crate::media::glob::glob_matches;
+use crate::media::pipeline::{AnalyzerLabel, DynamicRange, MediaAnalysis};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// What a matching rule does with the file.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleAction {
+    /// Plan the file with the rule's profile.
+    #[default]
+    Profile,
+    /// Record a skip decision without planning.
+    Skip,
+}
+
+impl RuleAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Profile => "profile",
+            Self::Skip => "skip",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "profile" => Some(Self::Profile),
+            "skip" => Some(Self::Skip),
+            _ => None,
+        }
+    }
+}
+
+/// Conditions a file must meet for a rule to match. Every condition that is
+/// set must hold; list conditions match when any entry does, except `labels`,
+/// which requires all of them. A rule with no conditions matches every file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RuleConditions {
+    /// Video codec names as reported by ffprobe, for example `h264` or `hevc`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub codecs: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_height: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dynamic_ranges: Vec<DynamicRange>,
+    /// Analyzer labels that must all be present.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<AnalyzerLabel>,
+    /// Globs matched against the full path, or against the file name when the
+    /// pattern has no `/`. `*` and `?` stay within one path component; `**`
+    /// spans directories.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub path_globs: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_size_mb: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_size_mb: Option<u64>,
+}
+
+impl RuleConditions {
+    pub fn from_json(value: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(value)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// Reject bounds that can never match and globs that are blank.
+    pub fn validate(&self) -> std::result::Result<(), &'static str> {
+        let inverted = |min: Option<u64>, max: Option<u64>| matches!((min, max), (Some(min), Some(max)) if min > max);
+        if inverted(self.min_width.map(u64::from), self.max_width.map(u64::from)) {
+            return Err("min_width must not exceed max_width");
+        }
+        if inverted(
+            self.min_height.map(u64::from),
+            self.max_height.map(u64::from),
+        ) {
+            return Err("min_height must not exceed max_height");
+        }
+        if inverted(self.min_size_mb, self.max_size_mb) {
+            return Err("min_size_mb must not exceed max_size_mb");
+        }
+        if self.codecs.iter().any(|codec| codec.trim().is_empty()) {
+            return Err("codecs must not contain empty entries");
+        }
+        if self.path_globs.iter().any(|glob| glob.trim().is_empty()) {
+            return Err("path_globs must not contain empty entries");
+        }
+        Ok(())
+    }
+
+    pub fn matches(&self, analysis: &MediaAnalysis) -> bool {
+        let metadata = &analysis.metadata;
+        if !self.codecs.is_empty()
+            && !self
+                .codecs
+                .iter()
+                .any(|codec| codec.trim().eq_ignore_ascii_case(&metadata.codec_name))
+        {
+            return false;
+        }
+        let within = |value: u64, min: Option<u64>, max: Option<u64>| {
+            min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+        };
+        if !within(
+            u64::from(metadata.width),
+            self.min_width.map(u64::from),
+            self.max_width.map(u64::from),
+        ) || !within(
+            u64::from(metadata.height),
+            self.min_height.map(u64::from),
+            self.max_height.map(u64::from),
+        ) {
+            return false;
+        }
+        let size_mb = metadata.size_bytes / (1024 * 1024);
+        if !within(size_mb, self.min_size_mb, self.max_size_mb) {
+            return false;
+        }
+        if !self.dynamic_ranges.is_empty() && !self.dynamic_ranges.contains(&metadata.dynamic_range)
+        {
+            return false;
+        }
+        if !self
+            .labels
+            .iter()
+            .all(|label| analysis.analysis_report.labels.contains(label))
+        {
+            return false;
+        }
+        if !self.path_globs.is_empty() {
+            let path = metadata.path.to_string_lossy().replace('\\', "/");
+            let file_name = path.rsplit('/').next().unwrap_or(&path).to_string();
+            return self.path_globs.iter().any(|glob| {
+                let glob = glob.trim().replace('\\', "/");
+                let subject = if glob.contains('/') {
+                    &path
+                } else {
+                    &file_name
+                };
+                glob_matches(&glob, subject)
+            });
+        }
+        true
+    }
+}
+
+/// The first enabled rule matching `analysis`, in position order.
+pub fn first_matching_rule<'a>(
+    rules: &'a [ProfileRule],
+    analysis: &MediaAnalysis,
+) -> Option<&'a ProfileRule> {
+    rules.iter().find(|rule| rule_matches(rule, analysis))
+}
+
+/// Disabled rules and rules whose stored conditions no longer parse never
+/// match.
+fn rule_matches(rule: &ProfileRule, analysis: &MediaAnalysis) -> bool {
+    if !rule.enabled {
+        return false;
+    }
+    match RuleConditions::from_json(&rule.conditions_json) {
+        Ok(conditions) => conditions.matches(analysis),
+        Err(err) => {
+            tracing::warn!(
+                "Ignoring profile rule {} ({}): unreadable conditions: {}",
+                rule.id,
+                rule.name,
+                err
+            );
+            false
+        }
+    }
+}
+
+/// The profile a file is planned with, and the rule that chose it.
+#[derive(Debug, Clone, Default)]
+pub struct ProfileSelection {
+    pub profile: Option<LibraryProfile>,
+    /// `None` when the watch folder profile (or no profile) applies.
+    pub rule: Option<ProfileRule>,
+}
+
+impl ProfileSelection {
+    /// Legacy skip reason when the matching rule skips the file.
+    pub fn skip_reason(&self) -> Option<String> {
+        let rule = self.rule.as_ref()?;
+        (RuleAction::parse(&rule.action) == Some(RuleAction::Skip)).then(|| {
+            format!(
+                "profile_rule_skip|rule_id={},rule={}",
+                rule.id,
+                reason_safe(&rule.name)
+            )
+        })
+    }
+
+    /// Record the matching rule on the job's decision explanation.
+    pub fn annotate(&self, explanation: Explanation) -> Explanation {
+        match &self.rule {
+            Some(rule) => explanation
+                .with_measured("profile_rule_id", json!(rule.id))
+                .with_measured("profile_rule", json!(rule.name)),
+            None => explanation,
+        }
+    }
+}
+
+/// Legacy reasons are `code|key=value,...`; keep rule names from breaking
+/// that shape.
+fn reason_safe(name: &str) -> String {
+    name.replace(['|', ',', '='], " ").trim().to_string()
+}
+
+/// Resolve the profile for an analyzed file: profile rules first, then the
+/// watch folder assignment. A rule naming a profile that no longer exists is
+/// passed over.
+pub async fn select_profile(db: &Db, analysis: &MediaAnalysis) -> Result<ProfileSelection> {
+    let rules = db.get_profile_rules().await?;
+    for rule in rules.iter().filter(|rule| rule_matches(rule, analysis)) {
+        match RuleAction::parse(&rule.action) {
+            Some(RuleAction::Skip) => {
+                return Ok(ProfileSelection {
+                    profile: None,
+                    rule: Some(rule.clone()),
+                });
+            }
+            Some(RuleAction::Profile) => {
+                let profile = match rule.profile_id {
+                    Some(profile_id) => db.get_profile(profile_id).await?,
+                    None => None,
+                };
+                if profile.is_some() {
+                    return Ok(ProfileSelection {
+                        profile,
+                        rule: Some(rule.clone()),
+                    });
+                }
+                tracing::warn!(
+                    "Profile rule {} ({}) names a missing profile; trying the next rule",
+                    rule.id,
+                    rule.name
+                );
+            }
+            None => {
+                tracing::warn!(
+                    "Ignoring profile rule {} ({}): unknown action {:?}",
+                    rule.id,
+                    rule.name,
+                    rule.action
+                );
+            }
+        }
+    }
+
+    let path = analysis.metadata.path.to_string_lossy();
+    Ok(ProfileSelection {
+        profile: db.get_profile_for_path(&path).await?,
+        rule: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::pipeline::{AnalysisConfidence, AnalyzerReport, MediaMetadata};
+    use chrono::Utc;
+    use std::path::PathBuf;
+
+    fn analysis(path: &str, codec: &str, width: u32, height: u32) -> MediaAnalysis {
+        MediaAnalysis {
+            metadata: MediaMetadata {
+                path: PathBuf::from(path),
+                duration_secs: 1400.0,
+                codec_name: codec.to_string(),
+                width,
+                height,
+                bit_depth: Some(8),
+                color_primaries: None,
+                color_transfer: None,
+                color_space: None,
+                color_range: None,
+                size_bytes: 2_000 * 1024 * 1024,
+                video_bitrate_bps: None,
+                container_bitrate_bps: None,
+                fps: 23.976,
+                container: "matroska".to_string(),
+                audio_codec: None,
+                audio_bitrate_bps: None,
+                audio_channels: None,
+                audio_is_heavy: false,
+                subtitle_streams: Vec::new(),
+                audio_streams: Vec::new(),
+                dynamic_range: DynamicRange::Sdr,
+                chapter_count: 0,
+                alchemist_marker: None,
+            },
+            warnings: Vec::new(),
+            confidence: AnalysisConfidence::High,
+            analysis_report: AnalyzerReport::default(),
+        }
+    }
+
+    fn rule(id: i64, name: &str, conditions: &str, action: RuleAction) -> ProfileRule {
+        ProfileRule {
+            id,
+            name: name.to_string(),
+            position: id,
+            enabled: true,
+            conditions_json: conditions.to_string(),
+            action: action.as_str().to_string(),
+            profile_id: (action == RuleAction::Profile).then_some(id),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn first_enabled_match_in_position_order_wins() {
+        let mut uhd_hdr = analysis("/media/movies/film.mkv", "hevc", 3840, 2160);
+        uhd_hdr.metadata.dynamic_range = DynamicRange::Hdr10;
+        let small_h264 = analysis("/media/tv/show.mkv", "h264", 960, 540);
+        let mut anime = analysis("/media/Anime/ep01.mkv", "h264", 1920, 1080);
+        anime
+            .analysis_report
+            .labels
+            .push(AnalyzerLabel::StyledSubtitle);
+
+        let mut disabled = rule(1, "everything", "{}", RuleAction::Skip);
+        disabled.enabled = false;
+        let rules = vec![
+            disabled,
+            rule(
+                2,
+                "2160p HDR",
+                r#"{"min_height":2160,"dynamic_ranges":["hdr10","dolby_vision"]}"#,
+                RuleAction::Profile,
+            ),
+            rule(
+                3,
+                "small h264",
+                r#"{"codecs":["H264"],"max_height":719}"#,
+                RuleAction::Profile,
+            ),
+            rule(
+                4,
+                "anime",
+                r#"{"path_globs":["**/Anime/**"],"labels":["styled_subtitle"]}"#,
+                RuleAction::Profile,
+            ),
+            rule(5, "broken", "not json", RuleAction::Skip),
+        ];
+
+        let matched = |analysis: &MediaAnalysis| {
+            first_matching_rule(&rules, analysis).map(|rule| rule.name.as_str())
+        };
+        assert_eq!(matched(&uhd_hdr), Some("2160p HDR"));
+        assert_eq!(matched(&small_h264), Some("small h264"));
+        assert_eq!(matched(&anime), Some("anime"));
+        assert_eq!(
+            matched(&analysis("/media/tv/other.mkv", "h264", 1920, 1080)),
+            None
+        );
+    }
+
+    #[test]
+    fn size_bounds_are_in_megabytes() {
+        let file = analysis("/media/a.mkv", "h264", 1920, 1080);
+        let conditions = RuleConditions {
+            min_size_mb: Some(1_000),
+            max_size_mb: Some(2_000),
+            ..RuleConditions::default()
+        };
+        assert!(conditions.matches(&file));
+        let conditions = RuleConditions {
+            max_size_mb: Some(1_999),
+            ..RuleConditions::default()
+        };
+        assert!(!conditions.matches(&file));
+        assert!(
+            RuleConditions {
+                min_size_mb: Some(5),
+                max_size_mb: Some(1),
+                ..RuleConditions::default()
+            }
+            .validate()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn skip_rules_produce_a_parseable_reason() {
+        let selection = ProfileSelection {
+            profile: None,
+            rule: Some(rule(7, "extras, trailers", "{}", RuleAction::Skip)),
+        };
+        let reason = selection
+            .skip_reason()
+            .unwrap_or_else(|| panic!("expected a skip reason"));
+        assert_eq!(reason, "profile_rule_skip|rule_id=7,rule=extras  trailers");
+
+        let explanation = crate::explanations::decision_from_legacy("skip", &reason);
+        assert_eq!(explanation.code, "profile_rule_skip");
+        assert_eq!(explanation.measured["rule_id"], json!(7));
+    }
+}
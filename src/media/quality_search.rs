@@ -0,0 +1,425 @@
+//! Target-quality rate control search.
+//!
+//! When a library profile sets `target_vmaf`, the pipeline encodes a few short
+//! clips spread across the runtime at candidate quality values, scores each
+//! against the matching window of the source, and binary-searches for the most
+//! compressed value whose worst clip still reaches the target. The winning
+//! value replaces the plan's rate control before the full encode starts, so a
+//! low score is caught in seconds instead of after hours of encoding.
+
+use crate::error::{AlchemistError, Result};
+use crate::explanations::Explanation;
+use crate::media::ffmpeg::{FFmpegCommandBuilder, QualityScore};
+use crate::media::pipeline::{
+    Encoder, FilterStep, MediaMetadata, RateControl, SubtitleStreamPlan, TranscodePlan,
+};
+use crate::system::hardware::HardwareInfo;
+use serde::Serialize;
+use serde_json::json;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+/// Length of each probe clip.
+pub const PROBE_CLIP_SECONDS: f64 = 4.0;
+/// Probe clip positions as fractions of the runtime. Skips intros and credits.
+const PROBE_SAMPLE_POSITIONS: [f64; 3] = [0.2, 0.5, 0.8];
+/// Upper bound on candidate values tried; covers a 64-wide range.
+const MAX_SEARCH_STEPS: usize = 6;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QualityProbe {
+    /// Candidate CRF/CQ/global_quality value.
+    pub value: u8,
+    /// Lowest VMAF across the probe clips at this value.
+    pub vmaf: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct QualitySearchOutcome {
+    pub target_vmaf: f64,
+    pub rate_control: RateControl,
+    pub probes: Vec<QualityProbe>,
+    /// False when even the highest-quality candidate missed the target; the
+    /// search then settles on that candidate rather than failing the job.
+    pub target_met: bool,
+}
+
+impl QualitySearchOutcome {
+    pub fn chosen_value(&self) -> Option<u8> {
+        rate_control_value(&self.rate_control)
+    }
+
+    /// Attach the search result to the job's decision explanation.
+    pub fn annotate(&self, explanation: Explanation) -> Explanation {
+        explanation
+            .with_measured("target_vmaf", json!(self.target_vmaf))
+            .with_measured(
+                "target_vmaf_rate_control",
+                json!(rate_control_kind(&self.rate_control)),
+            )
+            .with_measured("target_vmaf_chosen", json!(self.chosen_value()))
+            .with_measured("target_vmaf_met", json!(self.target_met))
+            .with_measured("target_vmaf_probes", json!(self.probes))
+    }
+}
+
+pub struct QualitySearchRequest<'a> {
+    pub job_id: i64,
+    pub input: &'a Path,
+    pub metadata: &'a MediaMetadata,
+    pub plan: &'a TranscodePlan,
+    pub hw_info: Option<&'a HardwareInfo>,
+    pub target_vmaf: f64,
+    /// Directory for probe clips; each clip is removed once scored.
+    pub scratch_dir: &'a Path,
+    /// The job's cancel channel, checked before each probe encode.
+    pub cancel: &'a std::sync::Mutex<tokio::sync::oneshot::Receiver<()>>,
+}
+
+impl QualitySearchRequest<'_> {
+    fn cancel_requested(&self) -> bool {
+        let mut cancel = match self.cancel.lock() {
+            Ok(cancel) => cancel,
+            Err(e) => e.into_inner(),
+        };
+        cancel.try_recv().is_ok()
+    }
+}
+
+/// Why a plan cannot use the target-quality search, if it cannot.
+pub fn unsupported_reason(plan: &TranscodePlan) -> Option<&'static str> {
+    if plan.copy_video || plan.encoder.is_none() {
+        return Some("the video stream is not re-encoded");
+    }
+    match plan.rate_control {
+        Some(RateControl::Crf { .. })
+        | Some(RateControl::Cq { .. })
+        | Some(RateControl::QsvQuality { .. }) => {}
+        _ => return Some("the encoder uses bitrate rate control"),
+    }
+    // VMAF compares the probe with the untouched source, so any filter that
+    // changes the picture would be scored as quality loss.
+    if plan.filters.iter().any(|filter| {
+        !matches!(
+            filter,
+            FilterStep::Format { .. }
+                | FilterStep::HwUpload
+                | FilterStep::StripHdrMetadata
+                | FilterStep::StripDolbyVision
+        )
+    }) {
+        return Some("the plan applies picture-altering filters");
+    }
+    None
+}
+
+/// Candidate range per encoder. Higher values always mean smaller output.
+pub fn search_range(encoder: Encoder) -> (u8, u8) {
+    match encoder {
+        Encoder::Av1Svt | Encoder::Av1Aom => (18, 55),
+        Encoder::HevcX265 | Encoder::H264X264 => (14, 38),
+        Encoder::Av1Videotoolbox | Encoder::HevcVideotoolbox | Encoder::H264Videotoolbox => {
+            (10, 51)
+        }
+        _ => (14, 45),
+    }
+}
+
+/// Start/duration pairs for the probe clips. Short files get a single clip
+/// from the middle; files shorter than one clip are probed whole.
+pub fn probe_windows(duration_secs: f64) -> Vec<(f64, f64)> {
+    if !duration_secs.is_finite() || duration_secs <= 0.0 {
+        return Vec::new();
+    }
+    if duration_secs <= PROBE_CLIP_SECONDS {
+        return vec![(0.0, duration_secs)];
+    }
+    let sample_span = PROBE_CLIP_SECONDS * PROBE_SAMPLE_POSITIONS.len() as f64;
+    if duration_secs < sample_span * 2.0 {
+        let start = ((duration_secs - PROBE_CLIP_SECONDS) / 2.0).max(0.0);
+        return vec![(start, PROBE_CLIP_SECONDS)];
+    }
+    PROBE_SAMPLE_POSITIONS
+        .iter()
+        .map(|position| {
+            let start = (duration_secs * position).min(duration_secs - PROBE_CLIP_SECONDS);
+            (start, PROBE_CLIP_SECONDS)
+        })
+        .collect()
+}
+
+pub async fn search_rate_control(
+    request: QualitySearchRequest<'_>,
+) -> Result<QualitySearchOutcome> {
+    if let Some(reason) = unsupported_reason(request.plan) {
+        return Err(AlchemistError::Config(format!(
+            "Target-quality search unavailable: {reason}"
+        )));
+    }
+    let (Some(encoder), Some(planned)) = (request.plan.encoder, request.plan.rate_control.as_ref())
+    else {
+        return Err(AlchemistError::Config(
+            "Target-quality search requires an encoder".to_string(),
+        ));
+    };
+    let windows = probe_windows(request.metadata.duration_secs);
+    if windows.is_empty() {
+        return Err(AlchemistError::Analyzer(
+            "Target-quality search requires a known duration".to_string(),
+        ));
+    }
+
+    let (low, high) = search_range(encoder);
+    let (chosen, probes) = binary_search(low, high, request.target_vmaf, |value| {
+        let windows = windows.clone();
+        let request = &request;
+        async move {
+            let mut worst: Option<f64> = None;
+            for (index, (start, duration)) in windows.into_iter().enumerate() {
+                if request.cancel_requested() {
+                    return Err(AlchemistError::Cancelled);
+                }
+                let score = probe_clip(request, value, index, start, duration).await?;
+                worst = Some(worst.map_or(score, |current: f64| current.min(score)));
+            }
+            worst.ok_or_else(|| {
+                AlchemistError::QualityCheckFailed("No probe clips were scored".to_string())
+            })
+        }
+    })
+    .await?;
+
+    let target_met = chosen.is_some();
+    let value = chosen.unwrap_or(low);
+    let rate_control = with_value(planned, value).ok_or_else(|| {
+        AlchemistError::Config("Target-quality search hit bitrate rate control".to_string())
+    })?;
+
+    Ok(QualitySearchOutcome {
+        target_vmaf: request.target_vmaf,
+        rate_control,
+        probes,
+        target_met,
+    })
+}
+
+/// Find the highest value in `low..=high` whose score reaches `target`,
+/// assuming scores fall as the value rises. Returns `None` when no candidate
+/// reaches the target, along with every probe taken.
+pub(crate) async fn binary_search<F, Fut>(
+    low: u8,
+    high: u8,
+    target: f64,
+    mut score: F,
+) -> Result<(Option<u8>, Vec<QualityProbe>)>
+where
+    F: FnMut(u8) -> Fut,
+    Fut: Future<Output = Result<f64>>,
+{
+    let mut probes = Vec::new();
+    let mut best = None;
+    let mut low = i32::from(low);
+    let mut high = i32::from(high);
+
+    while low <= high && probes.len() < MAX_SEARCH_STEPS {
+        let mid = (low + high) / 2;
+        let value = mid as u8;
+        let vmaf = score(value).await?;
+        probes.push(QualityProbe { value, vmaf });
+        if vmaf >= target {
+            best = Some(value);
+            low = mid + 1;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    Ok((best, probes))
+}
+
+async fn probe_clip(
+    request: &QualitySearchRequest<'_>,
+    value: u8,
+    index: usize,
+    start: f64,
+    duration: f64,
+) -> Result<f64> {
+    let output = probe_output_path(request.scratch_dir, request.job_id, value, index);
+    let plan = probe_plan(request.plan, value, &output)?;
+
+    let mut command = FFmpegCommandBuilder::new(request.input, &output, request.metadata, &plan)
+        .with_hardware(request.hw_info)
+        .with_clip(Some(start), Some(duration))
+        .build()?;
+    let encoded = command
+        .output()
+        .await
+        .map_err(|err| AlchemistError::FFmpeg(format!("Failed to start probe encode: {err}")))?;
+    if !encoded.status.success() {
+        let _ = tokio::fs::remove_file(&output).await;
+        let stderr = String::from_utf8_lossy(&encoded.stderr);
+        let tail = stderr.lines().rev().take(5).collect::<Vec<_>>();
+        return Err(AlchemistError::FFmpeg(format!(
+            "Probe encode at {value} failed: {}",
+            tail.into_iter().rev().collect::<Vec<_>>().join("\n")
+        )));
+    }
+
+    let original = request.input.to_path_buf();
+    let encoded_path = output.clone();
+    let score = tokio::task::spawn_blocking(move || {
+        QualityScore::compute_clip(&original, &encoded_path, start, duration)
+    })
+    .await
+    .map_err(|err| AlchemistError::Unknown(format!("Probe scoring task failed: {err}")));
+    let _ = tokio::fs::remove_file(&output).await;
+
+    score??.vmaf.ok_or_else(|| {
+        AlchemistError::QualityCheckFailed(format!("No VMAF score for probe at {value}"))
+    })
+}
+
+/// The job's plan reduced to video only, muxed to Matroska, at `value`.
+fn probe_plan(plan: &TranscodePlan, value: u8, output: &Path) -> Result<TranscodePlan> {
+    let rate_control = plan
+        .rate_control
+        .as_ref()
+        .and_then(|rate_control| with_value(rate_control, value))
+        .ok_or_else(|| {
+            AlchemistError::Config("Probe plan requires quality rate control".to_string())
+        })?;
+    let mut probe = plan.clone();
+    probe.rate_control = Some(rate_control);
+    probe.output_path = Some(output.to_path_buf());
+    probe.container = "mkv".to_string();
+    probe.audio = Vec::new();
+    probe.subtitles = SubtitleStreamPlan::Drop;
+    Ok(probe)
+}
+
+fn probe_output_path(scratch_dir: &Path, job_id: i64, value: u8, index: usize) -> PathBuf {
+    scratch_dir.join(format!(".alchemist.probe-{job_id}-{value}-{index}.mkv"))
+}
+
+fn with_value(rate_control: &RateControl, value: u8) -> Option<RateControl> {
+    match rate_control {
+        RateControl::Crf { .. } => Some(RateControl::Crf { value }),
+        RateControl::Cq { .. } => Some(RateControl::Cq { value }),
+        RateControl::QsvQuality { .. } => Some(RateControl::QsvQuality { value }),
+        RateControl::Bitrate { .. } => None,
+    }
+}
+
+fn rate_control_value(rate_control: &RateControl) -> Option<u8> {
+    match rate_control {
+        RateControl::Crf { value }
+        | RateControl::Cq { value }
+        | RateControl::QsvQuality { value } => Some(*value),
+        RateControl::Bitrate { .. } => None,
+    }
+}
+
+fn rate_control_kind(rate_control: &RateControl) -> &'static str {
+    match rate_control {
+        RateControl::Crf { .. } => "crf",
+        RateControl::Cq { .. } => "cq",
+        RateControl::QsvQuality { .. } => "qsv_quality",
+        RateControl::Bitrate { .. } => "bitrate",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn synthetic_score(value: u8) -> f64 {
+        // Falls one VMAF point per step above 20.
+        100.0 - f64::from(value.saturating_sub(20))
+    }
+
+    #[tokio::test]
+    async fn binary_search_picks_highest_value_meeting_target() {
+        let (chosen, probes) =
+            match binary_search(
+                14,
+                45,
+                93.0,
+                |value| async move { Ok(synthetic_score(value)) },
+            )
+            .await
+            {
+                Ok(result) => result,
+                Err(err) => panic!("search failed: {err}"),
+            };
+
+        assert_eq!(chosen, Some(27));
+        assert!(probes.len() <= MAX_SEARCH_STEPS);
+        assert!(probes.iter().any(|probe| probe.value == 27));
+    }
+
+    #[tokio::test]
+    async fn binary_search_reports_unmet_target() {
+        let (chosen, probes) =
+            match binary_search(
+                14,
+                45,
+                101.0,
+                |value| async move { Ok(synthetic_score(value)) },
+            )
+            .await
+            {
+                Ok(result) => result,
+                Err(err) => panic!("search failed: {err}"),
+            };
+
+        assert_eq!(chosen, None);
+        assert!(!probes.is_empty());
+    }
+
+    #[test]
+    fn probe_windows_spread_long_files_and_center_short_ones() {
+        let long = probe_windows(3600.0);
+        assert_eq!(long.len(), PROBE_SAMPLE_POSITIONS.len());
+        assert_eq!(long[0], (720.0, PROBE_CLIP_SECONDS));
+
+        let short = probe_windows(10.0);
+        assert_eq!(short, vec![(3.0, PROBE_CLIP_SECONDS)]);
+
+        assert_eq!(probe_windows(2.5), vec![(0.0, 2.5)]);
+        assert!(probe_windows(0.0).is_empty());
+    }
+
+    #[test]
+    fn outcome_annotates_decision_explanation() {
+        let outcome = QualitySearchOutcome {
+            target_vmaf: 95.0,
+            rate_control: RateControl::Crf { value: 30 },
+            probes: vec![
+                QualityProbe {
+                    value: 34,
+                    vmaf: 93.2,
+                },
+                QualityProbe {
+                    value: 30,
+                    vmaf: 95.4,
+                },
+            ],
+            target_met: true,
+        };
+        let explanation = outcome.annotate(crate::explanations::decision_from_legacy(
+            "transcode",
+            "transcode_recommended|target_codec=av1,current_codec=h264,bpp=0.120",
+        ));
+
+        assert_eq!(explanation.code, "transcode_recommended");
+        assert_eq!(explanation.measured["target_vmaf_chosen"], json!(30));
+        assert_eq!(
+            explanation.measured["target_vmaf_rate_control"],
+            json!("crf")
+        );
+        assert_eq!(
+            explanation.measured["target_vmaf_probes"][0],
+            json!({ "value": 34, "vmaf": 93.2 })
+        );
+    }
+}
@@ -0,0 +1,286 @@
+//! Recycle bin for originals removed by `delete_source`.
+//!
+//! With the bin enabled, finalization moves the original aside instead of
+//! deleting it and records a manifest row linking it to the job. Restoring
+//! removes the encoded output, puts the original back, returns any sidecars
+//! that were moved to the output's name, and resets the job to `cancelled`
+//! without its savings stats. A background sweep
+//! purges held originals once they pass the retention window or the bin
+//! grows past its size cap, oldest first.
+//!
+//! Recycled files carry a `.recycled` suffix so library scans and the watcher
+//! never mistake them for new media, wherever the bin lives.
+
+use crate::config::RecycleBinConfig;
+use crate::db::{Db, RecycleBinEntry};
+use crate::error::{AlchemistError, Result};
+use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
+
+/// Folder created beside the source when no recycle directory is configured.
+pub const DEFAULT_RECYCLE_DIR_NAME: &str = ".alchemist-recycle";
+const RECYCLED_SUFFIX: &str = ".recycled";
+const BYTES_PER_GIB: u64 = 1024 * 1024 * 1024;
+
+/// Where the original of `job_id` is recycled to, before collision handling.
+pub fn recycle_path_for(original: &Path, job_id: i64, config: &RecycleBinConfig) -> PathBuf {
+    let directory = match config
+        .directory
+        .as_deref()
+        .map(str::trim)
+        .filter(|directory| !directory.is_empty())
+    {
+        Some(directory) => PathBuf::from(directory),
+        None => original
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join(DEFAULT_RECYCLE_DIR_NAME),
+    };
+    let file_name = original
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "original".to_string());
+    directory.join(format!("{job_id}-{file_name}{RECYCLED_SUFFIX}"))
+}
+
+fn unused_path(candidate: PathBuf) -> PathBuf {
+    if !candidate.exists() {
+        return candidate;
+    }
+    let name = candidate
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    (1..)
+        .map(|n| candidate.with_file_name(format!("{n}-{name}")))
+        .find(|path| !path.exists())
+        .unwrap_or(candidate)
+}
+
+/// Rename `from` to `to`, falling back to copy-and-delete when they are on
+/// different filesystems. A failed copy leaves `from` untouched.
+async fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    let from = from.to_path_buf();
+    let to = to.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        match std::fs::rename(&from, &to) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::CrossesDevices => {
+                if let Err(err) = std::fs::copy(&from, &to) {
+                    let _ = std::fs::remove_file(&to);
+                    return Err(err);
+                }
+                std::fs::remove_file(&from)
+            }
+            Err(err) => Err(err),
+        }
+    })
+    .await
+    .map_err(std::io::Error::other)?
+}
+
+/// Move a finished job's original into the recycle bin and record it.
+pub async fn recycle_original(
+    db: &Db,
+    job_id: i64,
+    original: &Path,
+    output: &Path,
+    config: &RecycleBinConfig,
+) -> Result<PathBuf> {
+    let size_bytes = std::fs::metadata(original)?.len();
+    let target = unused_path(recycle_path_for(original, job_id, config));
+    move_file(original, &target).await?;
+
+    if let Err(err) = db
+        .record_recycled_original(
+            job_id,
+            &original.to_string_lossy(),
+            &target.to_string_lossy(),
+            Some(&output.to_string_lossy()),
+            size_bytes as i64,
+        )
+        .await
+    {
+        // Without a manifest row the original could never be restored or
+        // purged, so put it back where it was.
+        if let Err(restore_err) = move_file(&target, original).await {
+            tracing::error!(
+                "Job {}: original left at {:?} after recording it failed: {}",
+                job_id,
+                target,
+                restore_err
+            );
+        }
+        return Err(err);
+    }
+    Ok(target)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestoreOutcome {
+    pub restored_path: String,
+    /// The encoded output that was removed, if it still existed.
+    pub removed_output: Option<String>,
+    /// Sidecars moved back from the output's name to the original's.
+    pub restored_sidecars: Vec<String>,
+}
+
+/// Remove the encoded output that replaced a held original, put the original
+/// back at its source path, and move the job's carried sidecars back beside
+/// it. The entry is marked restored, and the job reset, only once the files
+/// are in place. The caller checks that the source path is free.
+pub async fn restore(db: &Db, entry: &RecycleBinEntry) -> Result<RestoreOutcome> {
+    let original = Path::new(&entry.original_path);
+
+    // Remove the output first: if that fails, nothing has moved and the
+    // entry is still held for another attempt.
+    let removed_output = match entry.output_path.as_deref() {
+        Some(output) if Path::new(output) != original && Path::new(output).exists() => {
+            tokio::fs::remove_file(output).await.map_err(|err| {
+                AlchemistError::Io(std::io::Error::new(
+                    err.kind(),
+                    format!("removing output {output} failed: {err}"),
+                ))
+            })?;
+            Some(output.to_string())
+        }
+        _ => None,
+    };
+
+    move_file(Path::new(&entry.recycled_path), original).await?;
+    let restored_sidecars = match entry.job_id {
+        Some(job_id) => crate::media::sidecars::restore_moved(db, job_id).await?,
+        None => Vec::new(),
+    };
+    db.complete_recycle_restore(entry).await?;
+
+    Ok(RestoreOutcome {
+        restored_path: entry.original_path.clone(),
+        removed_output,
+        restored_sidecars,
+    })
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SweepSummary {
+    pub purged: usize,
+    pub freed_bytes: u64,
+}
+
+/// Held entries (oldest first) that the retention policy purges: everything
+/// past `retention_days`, then the oldest of the rest until the bin fits
+/// under `max_size_gb`.
+fn entries_to_purge<'a>(
+    entries: &'a [RecycleBinEntry],
+    config: &RecycleBinConfig,
+    now: DateTime<Utc>,
+) -> Vec<&'a RecycleBinEntry> {
+    let cutoff = config
+        .retention_days
+        .map(|days| now - chrono::Duration::days(i64::from(days)));
+    let cap = config
+        .max_size_gb
+        .map(|gb| u64::from(gb).saturating_mul(BYTES_PER_GIB));
+    let mut held_bytes = entries
+        .iter()
+        .map(|entry| entry.size_bytes.max(0) as u64)
+        .sum::<u64>();
+
+    let mut purge = Vec::new();
+    for entry in entries {
+        let expired = cutoff.is_some_and(|cutoff| entry.recycled_at < cutoff);
+        let over_cap = cap.is_some_and(|cap| held_bytes > cap);
+        if expired || over_cap {
+            held_bytes = held_bytes.saturating_sub(entry.size_bytes.max(0) as u64);
+            purge.push(entry);
+        }
+    }
+    purge
+}
+
+/// Enforce the retention policy. Runs whether or not the bin is enabled so
+/// originals recycled earlier still age out.
+pub async fn sweep(db: &Db, config: &RecycleBinConfig) -> Result<SweepSummary> {
+    let entries = db.get_held_recycle_entries().await?;
+    let mut summary = SweepSummary::default();
+    for entry in entries_to_purge(&entries, config, Utc::now()) {
+        match tokio::fs::remove_file(&entry.recycled_path).await {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => {
+                tracing::warn!(
+                    "Failed to purge recycled original {}: {}",
+                    entry.recycled_path,
+                    err
+                );
+                continue;
+            }
+        }
+        db.resolve_recycle_entry(entry.id, "purged").await?;
+        summary.purged += 1;
+        summary.freed_bytes += entry.size_bytes.max(0) as u64;
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: i64, age_days: i64, size_gb: u64) -> RecycleBinEntry {
+        RecycleBinEntry {
+            id,
+            job_id: Some(id),
+            original_path: format!("/media/{id}.mkv"),
+            recycled_path: format!("/media/.alchemist-recycle/{id}-{id}.mkv.recycled"),
+            output_path: Some(format!("/media/{id}-alchemist.mkv")),
+            size_bytes: (size_gb * BYTES_PER_GIB) as i64,
+            status: "held".to_string(),
+            recycled_at: Utc::now() - chrono::Duration::days(age_days),
+            resolved_at: None,
+        }
+    }
+
+    #[test]
+    fn recycled_names_keep_the_job_and_leave_media_extensions() {
+        let config = RecycleBinConfig::default();
+        let path = recycle_path_for(Path::new("/media/tv/show.mkv"), 42, &config);
+        assert_eq!(
+            path,
+            PathBuf::from("/media/tv/.alchemist-recycle/42-show.mkv.recycled")
+        );
+
+        let config = RecycleBinConfig {
+            directory: Some("/trash".to_string()),
+            ..RecycleBinConfig::default()
+        };
+        let path = recycle_path_for(Path::new("/media/tv/show.mkv"), 42, &config);
+        assert_eq!(path, PathBuf::from("/trash/42-show.mkv.recycled"));
+    }
+
+    #[test]
+    fn retention_purges_expired_entries_then_oldest_over_cap() {
+        let entries = vec![entry(1, 40, 10), entry(2, 20, 30), entry(3, 5, 30)];
+        let config = RecycleBinConfig {
+            enabled: true,
+            directory: None,
+            retention_days: Some(30),
+            max_size_gb: Some(40),
+        };
+        let purged = entries_to_purge(&entries, &config, Utc::now())
+            .into_iter()
+            .map(|entry| entry.id)
+            .collect::<Vec<_>>();
+        assert_eq!(purged, vec![1, 2]);
+
+        let config = RecycleBinConfig {
+            retention_days: None,
+            max_size_gb: None,
+            ..config
+        };
+        assert!(entries_to_purge(&entries, &config, Utc::now()).is_empty());
+    }
+}
@@ -6,6 +6,7 @@ use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use tracing::{debug, error};
 use walkdir::WalkDir;
 
+use crate::media::ignore::{ExcludeRules, ExcludedFile};
 use crate::media::pipeline::DiscoveredMedia;
 
 pub struct Scanner {
@@ -18,6 +19,8 @@ pub struct BoundedScanResult {
     pub scanned_entries: usize,
     pub truncated: bool,
     pub timed_out: bool,
+    /// Media files left out by an exclude glob or ignore file.
+    pub excluded: Vec<ExcludedFile>,
 }
 
 /// PERF-3 aggressive directory pruning. When enabled and `last_scanned_at`
@@ -29,10 +32,15 @@ pub struct BoundedScanResult {
 ///
 /// Top-level entries of a watch root are never pruned regardless of
 /// mtime so that newly added direct children are still discovered.
+///
+/// Excluded paths are pruned whether or not `enabled` is set: the watch
+/// root's exclude globs from `exclude_globs_by_root`, plus any
+/// `.alchemistignore` files found during the walk.
 #[derive(Debug, Clone, Default)]
 pub struct PruneOptions {
     pub enabled: bool,
     pub last_scanned_by_root: HashMap<PathBuf, i64>,
+    pub exclude_globs_by_root: HashMap<PathBuf, Vec<String>>,
 }
 
 impl Default for Scanner {
@@ -73,8 +81,32 @@ impl Scanner {
         max_entries: usize,
         max_files: usize,
         deadline: Instant,
+    ) -> BoundedScanResult {
+        let rules = ExcludeRules::new(directory.clone(), &[]);
+        self.scan_directory_bounded_excluding(
+            directory,
+            recursive,
+            max_entries,
+            max_files,
+            deadline,
+            &rules,
+        )
+    }
+
+    /// `scan_directory_bounded`, reporting media files that `rules` exclude
+    /// instead of returning them. Excluded subtrees are still walked so the
+    /// preview can name the files they hold.
+    pub fn scan_directory_bounded_excluding(
+        &self,
+        directory: PathBuf,
+        recursive: bool,
+        max_entries: usize,
+        max_files: usize,
+        deadline: Instant,
+        rules: &ExcludeRules,
     ) -> BoundedScanResult {
         let mut files = Vec::new();
+        let mut excluded = Vec::new();
         let mut scanned_entries = 0usize;
         let mut truncated = false;
         let mut timed_out = false;
@@ -108,6 +140,13 @@ impl Scanner {
             if !self.extensions.contains(&ext.to_lowercase()) {
                 continue;
             }
+            if let Some(exclusion) = rules.exclusion_for(entry.path()) {
+                excluded.push(ExcludedFile {
+                    path: entry.path().to_string_lossy().to_string(),
+                    exclusion,
+                });
+                continue;
+            }
 
             let mtime = entry
                 .metadata()
@@ -127,12 +166,14 @@ impl Scanner {
         // Keep preview output stable without asking WalkDir to pre-enumerate
         // and sort every child of a potentially huge directory.
         files.sort_by(|left, right| left.path.cmp(&right.path));
+        excluded.sort_by(|left, right| left.path.cmp(&right.path));
 
         BoundedScanResult {
             files,
             scanned_entries,
             truncated,
             timed_out,
+            excluded,
         }
     }
 
@@ -160,6 +201,14 @@ impl Scanner {
             };
 
             let root_for_filter = dir.clone();
+            let rules = ExcludeRules::new(
+                dir.clone(),
+                prune
+                    .exclude_globs_by_root
+                    .get(&dir)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default(),
+            );
             let prune_enabled = prune.enabled;
             let last_scanned = prune
                 .last_scanned_by_root
@@ -172,6 +221,17 @@ impl Scanner {
             // direct children are never pruned so new top-level entries are
             // still picked up.
             let walker = walker_base.into_iter().filter_entry(move |entry| {
+                if let Some(exclusion) =
+                    rules.entry_exclusion(entry.path(), entry.file_type().is_dir())
+                {
+                    debug!(
+                        "Excluding {:?} ({} from {})",
+                        entry.path(),
+                        exclusion.rule,
+                        exclusion.source
+                    );
+                    return false;
+                }
                 if !prune_enabled || last_scanned.is_none() {
                     return true;
                 }
@@ -371,7 +431,7 @@ mod tests {
         let scanner = Scanner::new();
         let mut prune = PruneOptions {
             enabled: true,
-            last_scanned_by_root: HashMap::new(),
+            ..PruneOptions::default()
         };
         prune
             .last_scanned_by_root
@@ -419,7 +479,7 @@ mod tests {
         let scanner = Scanner::new();
         let prune = PruneOptions {
             enabled: false,
-            last_scanned_by_root: HashMap::new(),
+            ..PruneOptions::default()
         };
         let found = scanner.scan_with_options(vec![(root.clone(), true)], &prune);
         assert!(
@@ -432,6 +492,51 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn scan_prunes_exclude_globs_and_ignore_files() -> anyhow::Result<()> {
+        let root = unique_temp_dir("excludes");
+        let movie = root.join("Movie (2020)");
+        fs::create_dir_all(movie.join("Extras"))?;
+        fs::create_dir_all(root.join("@eaDir"))?;
+        fs::write(movie.join("movie.mkv"), b"x")?;
+        fs::write(movie.join("movie-sample.mkv"), b"x")?;
+        fs::write(movie.join("Extras/featurette.mkv"), b"x")?;
+        fs::write(root.join("@eaDir/thumb.mkv"), b"x")?;
+        fs::write(
+            root.join(crate::media::ignore::IGNORE_FILE_NAME),
+            "@eaDir/\n",
+        )?;
+
+        let mut prune = PruneOptions::default();
+        prune.exclude_globs_by_root.insert(
+            root.clone(),
+            vec!["**/Extras/**".to_string(), "*sample*".to_string()],
+        );
+        let found = Scanner::new().scan_with_options(vec![(root.clone(), true)], &prune);
+        let paths = found.iter().map(|m| m.path.clone()).collect::<Vec<_>>();
+        assert_eq!(paths, vec![movie.join("movie.mkv")]);
+
+        let rules = ExcludeRules::new(root.clone(), &["*sample*".to_string()]);
+        let preview = Scanner::new().scan_directory_bounded_excluding(
+            root.clone(),
+            true,
+            100,
+            10,
+            Instant::now() + std::time::Duration::from_secs(1),
+            &rules,
+        );
+        assert_eq!(preview.files.len(), 2);
+        assert_eq!(preview.excluded.len(), 2);
+        assert!(
+            preview.excluded.iter().any(|file| {
+                file.path.ends_with("thumb.mkv") && file.exclusion.rule == "@eaDir/"
+            })
+        );
+
+        let _ = fs::remove_dir_all(root);
+        Ok(())
+    }
+
     /// Helper: bash dir mtime backwards so it appears older than any
     /// "last_scanned_at" we'll set in the test. Uses utimensat via
     /// std::fs::File::set_modified — falls back to no-op on systems
@@ -0,0 +1,382 @@
+//! Companion files that follow a source to its output.
+//!
+//! Media servers pair subtitles, NFO files, artwork and trickplay folders
+//! with a video by name: `movie.en.forced.srt`, `movie.nfo`,
+//! `movie-poster.jpg`, `movie.trickplay/`. When a job writes its output
+//! under another stem or directory those pairings break, so when the source
+//! is deleted or recycled, finalization moves each sidecar over to the
+//! output's name. A kept source keeps its sidecars. Every moved sidecar is
+//! recorded on the job, and moves are undone when the recycled original is
+//! restored.
+
+use crate::db::Db;
+use crate::error::Result;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Kodi/Jellyfin artwork names that follow `<stem>-`, as in `movie-poster.jpg`.
+/// Other `-` suffixes are left alone so `movie-2.srt` is not claimed by
+/// `movie.mkv`.
+const ARTWORK_SUFFIXES: &[&str] = &[
+    "backdrop",
+    "banner",
+    "clearart",
+    "clearlogo",
+    "disc",
+    "discart",
+    "fanart",
+    "keyart",
+    "landscape",
+    "logo",
+    "poster",
+    "thumb",
+];
+
+/// `job_sidecars.action` of a sidecar moved to the output's name.
+pub const MOVED: &str = "moved";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SidecarMatch {
+    pub source: PathBuf,
+    pub target: PathBuf,
+}
+
+/// The part of `name` after `stem` when it names a sidecar of `stem`.
+fn sidecar_tail<'a>(name: &'a str, stem: &str) -> Option<&'a str> {
+    let tail = name.strip_prefix(stem)?;
+    if tail.len() > 1 && tail.starts_with('.') {
+        return Some(tail);
+    }
+    let artwork = tail.strip_prefix('-')?;
+    let suffix = artwork.split('.').next().unwrap_or_default();
+    ARTWORK_SUFFIXES
+        .contains(&suffix.to_ascii_lowercase().as_str())
+        .then_some(tail)
+}
+
+/// Sidecars of `source` and where each goes beside `output`. Empty when the
+/// output keeps the source's directory and stem, since the names already
+/// pair up. Other media files, and sidecars that belong to a sibling video
+/// with a longer stem (`movie.part2.srt` next to `movie.part2.mkv`), are
+/// skipped, as is anything in `exclude`.
+pub fn discover(
+    source: &Path,
+    output: &Path,
+    media_extensions: &[String],
+    exclude: &[PathBuf],
+) -> Vec<SidecarMatch> {
+    let (Some(source_dir), Some(source_stem)) = (source.parent(), source.file_stem()) else {
+        return Vec::new();
+    };
+    let (Some(output_dir), Some(output_stem)) = (output.parent(), output.file_stem()) else {
+        return Vec::new();
+    };
+    if source_dir == output_dir && source_stem == output_stem {
+        return Vec::new();
+    }
+    let source_stem = source_stem.to_string_lossy();
+    let output_stem = output_stem.to_string_lossy();
+    let output_name = output
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let Ok(entries) = std::fs::read_dir(source_dir) else {
+        return Vec::new();
+    };
+    let mut names = Vec::new();
+    let mut sibling_stems = HashSet::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = entry.path();
+        let is_media = path
+            .extension()
+            .is_some_and(|ext| media_extensions.contains(&ext.to_string_lossy().to_lowercase()));
+        if is_media {
+            if let Some(stem) = path.file_stem() {
+                let stem = stem.to_string_lossy().into_owned();
+                if stem.len() > source_stem.len() {
+                    sibling_stems.insert(stem);
+                }
+            }
+            continue;
+        }
+        names.push(name);
+    }
+    names.sort();
+
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let tail = sidecar_tail(&name, &source_stem)?;
+            let belongs_to_sibling = sibling_stems
+                .iter()
+                .any(|stem| sidecar_tail(&name, stem).is_some());
+            if belongs_to_sibling
+                || (source_dir == output_dir && name.starts_with(&output_name))
+                || name.ends_with(".tmp")
+            {
+                return None;
+            }
+            let source = source_dir.join(&name);
+            let target = output_dir.join(format!("{output_stem}{tail}"));
+            if exclude.contains(&source) || exclude.contains(&target) || source == target {
+                return None;
+            }
+            Some(SidecarMatch { source, target })
+        })
+        .collect()
+}
+
+fn copy_tree(from: &Path, to: &Path) -> std::io::Result<()> {
+    if !from.is_dir() {
+        std::fs::copy(from, to)?;
+        return Ok(());
+    }
+    for entry in walkdir::WalkDir::new(from) {
+        let entry = entry.map_err(std::io::Error::other)?;
+        let relative = entry
+            .path()
+            .strip_prefix(from)
+            .map_err(std::io::Error::other)?;
+        let destination = to.join(relative);
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&destination)?;
+        } else {
+            std::fs::copy(entry.path(), &destination)?;
+        }
+    }
+    Ok(())
+}
+
+fn remove_tree(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// Move a sidecar file or folder, falling back to copy-and-delete across
+/// filesystems. A failed copy is cleaned up and leaves `from` intact.
+fn transfer(from: &Path, to: &Path) -> std::io::Result<()> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    match std::fs::rename(from, to) {
+        Ok(()) => return Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::CrossesDevices => {}
+        Err(err) => return Err(err),
+    }
+    if let Err(err) = copy_tree(from, to) {
+        let _ = remove_tree(to);
+        return Err(err);
+    }
+    remove_tree(from)
+}
+
+#[derive(Debug, Default)]
+pub struct CarryOutcome {
+    pub carried: Vec<SidecarMatch>,
+    /// Human-readable reasons for sidecars that were left where they were.
+    pub warnings: Vec<String>,
+}
+
+/// Move `sidecars` over for `job_id` and record each one that landed.
+/// Existing files at a target are never overwritten.
+pub async fn carry(db: &Db, job_id: i64, sidecars: Vec<SidecarMatch>) -> Result<CarryOutcome> {
+    db.clear_job_sidecars(job_id).await?;
+    let mut outcome = CarryOutcome::default();
+    for sidecar in sidecars {
+        if sidecar.target.exists() {
+            outcome.warnings.push(format!(
+                "Sidecar {} not carried over: {} already exists",
+                sidecar.source.display(),
+                sidecar.target.display()
+            ));
+            continue;
+        }
+        let (from, to) = (sidecar.source.clone(), sidecar.target.clone());
+        let result = tokio::task::spawn_blocking(move || transfer(&from, &to))
+            .await
+            .map_err(std::io::Error::other)
+            .and_then(|result| result);
+        if let Err(err) = result {
+            outcome.warnings.push(format!(
+                "Sidecar {} not carried over: {}",
+                sidecar.source.display(),
+                err
+            ));
+            continue;
+        }
+        db.record_job_sidecar(
+            job_id,
+            &sidecar.source.to_string_lossy(),
+            &sidecar.target.to_string_lossy(),
+            MOVED,
+        )
+        .await?;
+        outcome.carried.push(sidecar);
+    }
+    Ok(outcome)
+}
+
+/// Move the sidecars `job_id` moved back to their source names. Returns the
+/// restored source paths; sidecars whose source name is taken again, or
+/// that no longer exist under the output name, are left alone.
+pub async fn restore_moved(db: &Db, job_id: i64) -> Result<Vec<String>> {
+    let mut restored = Vec::new();
+    for sidecar in db.get_job_sidecars(job_id).await? {
+        if sidecar.action != MOVED {
+            continue;
+        }
+        let from = PathBuf::from(&sidecar.output_path);
+        let to = PathBuf::from(&sidecar.source_path);
+        if !from.exists() || to.exists() {
+            continue;
+        }
+        let result = tokio::task::spawn_blocking(move || transfer(&from, &to))
+            .await
+            .map_err(std::io::Error::other)
+            .and_then(|result| result);
+        match result {
+            Ok(()) => {
+                db.set_job_sidecar_action(sidecar.id, "restored").await?;
+                restored.push(sidecar.source_path);
+            }
+            Err(err) => tracing::warn!(
+                "Job {}: failed to restore sidecar {}: {}",
+                job_id,
+                sidecar.source_path,
+                err
+            ),
+        }
+    }
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(prefix: &str) -> std::io::Result<PathBuf> {
+        let path = std::env::temp_dir().join(format!("{prefix}_{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&path)?;
+        Ok(path)
+    }
+
+    fn file_name(path: &Path) -> String {
+        path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    fn media_extensions() -> Vec<String> {
+        crate::media::scanner::Scanner::new().extensions
+    }
+
+    #[test]
+    fn sidecars_follow_the_output_stem_and_skip_unrelated_files() -> std::io::Result<()> {
+        let dir = temp_dir("alchemist_sidecars_discover")?;
+        for name in [
+            "movie.mkv",
+            "movie.srt",
+            "movie.en.forced.srt",
+            "movie.nfo",
+            "movie-poster.jpg",
+            "movie-2.srt",
+            "movie-alchemist.mkv",
+            "movie.part2.mkv",
+            "movie.part2.srt",
+            "movies.txt",
+        ] {
+            std::fs::write(dir.join(name), b"x")?;
+        }
+        std::fs::create_dir_all(dir.join("movie.trickplay"))?;
+
+        let matches = discover(
+            &dir.join("movie.mkv"),
+            &dir.join("movie-alchemist.mkv"),
+            &media_extensions(),
+            &[],
+        );
+        let pairs = matches
+            .iter()
+            .map(|m| (file_name(&m.source), file_name(&m.target)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pairs,
+            vec![
+                (
+                    "movie-poster.jpg".into(),
+                    "movie-alchemist-poster.jpg".into()
+                ),
+                (
+                    "movie.en.forced.srt".into(),
+                    "movie-alchemist.en.forced.srt".into()
+                ),
+                ("movie.nfo".into(), "movie-alchemist.nfo".into()),
+                ("movie.srt".into(), "movie-alchemist.srt".into()),
+                ("movie.trickplay".into(), "movie-alchemist.trickplay".into()),
+            ]
+        );
+
+        assert!(
+            discover(
+                &dir.join("movie.mkv"),
+                &dir.join("movie.mp4"),
+                &media_extensions(),
+                &[]
+            )
+            .is_empty()
+        );
+        let _ = std::fs::remove_dir_all(dir);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn moved_sidecars_are_recorded_and_restored() -> anyhow::Result<()> {
+        let db_path =
+            std::env::temp_dir().join(format!("alchemist_sidecars_{}.db", rand::random::<u64>()));
+        let db = Db::new(db_path.to_string_lossy().as_ref()).await?;
+        let source_dir = temp_dir("alchemist_sidecars_source")?;
+        let output_dir = temp_dir("alchemist_sidecars_output")?;
+        let source = source_dir.join("show.mkv");
+        let output = output_dir.join("show.mkv");
+        std::fs::write(source_dir.join("show.srt"), b"subs")?;
+        std::fs::create_dir_all(source_dir.join("show.trickplay"))?;
+        std::fs::write(source_dir.join("show.trickplay/0.jpg"), b"tile")?;
+
+        let _ = db
+            .enqueue_job(&source, &output, std::time::SystemTime::UNIX_EPOCH)
+            .await?;
+        let job = db
+            .get_job_by_input_path(&source.to_string_lossy())
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("job was not enqueued"))?;
+
+        let matches = discover(&source, &output, &media_extensions(), &[]);
+        let outcome = carry(&db, job.id, matches).await?;
+        assert_eq!(outcome.carried.len(), 2);
+        assert!(outcome.warnings.is_empty());
+        assert!(output_dir.join("show.srt").exists());
+        assert!(output_dir.join("show.trickplay/0.jpg").exists());
+        assert!(!source_dir.join("show.srt").exists());
+
+        let restored = restore_moved(&db, job.id).await?;
+        assert_eq!(restored.len(), 2);
+        assert!(source_dir.join("show.srt").exists());
+        assert!(source_dir.join("show.trickplay/0.jpg").exists());
+        assert!(
+            db.get_job_sidecars(job.id)
+                .await?
+                .iter()
+                .all(|sidecar| sidecar.action == "restored")
+        );
+
+        let _ = std::fs::remove_dir_all(source_dir);
+        let _ = std::fs::remove_dir_all(output_dir);
+        let _ = std::fs::remove_file(db_path);
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+//! Additional outputs a library profile produces from each source.
+//!
+//! A profile's main output follows the file settings as before. Each declared
+//! variant is planned from the same source with the variant's codec,
+//! resolution cap and audio mode layered over the profile, and written into
+//! its own output root, mirroring the source's place under its watch folder.
+//! Once the main job finishes, each variant is queued as a job of its own,
+//! linked to the main job, and runs through the normal queue with its own
+//! decision, stats, retries and cancel. One failing variant never fails the
+//! job or its siblings. A variant whose output is at least as new as the
+//! source is skipped.
+
+use crate::config::{AudioMode, MaxResolution, OutputCodec};
+use crate::db::LibraryProfile;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Containers a variant may be written in.
+pub const VARIANT_CONTAINERS: [&str; 3] = ["mkv", "mp4", "mov"];
+
+fn default_container() -> String {
+    "mkv".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputVariant {
+    /// Unique within the profile; names the child row in job details.
+    pub name: String,
+    pub codec: OutputCodec,
+    #[serde(default)]
+    pub max_resolution: Option<MaxResolution>,
+    #[serde(default)]
+    pub audio_mode: AudioMode,
+    #[serde(default = "default_container")]
+    pub container: String,
+    /// Root of the parallel tree the variant is written into.
+    pub output_root: String,
+}
+
+/// Reject variant lists the pipeline could not act on.
+pub fn validate_variants(variants: &[OutputVariant]) -> std::result::Result<(), String> {
+    let mut names = std::collections::HashSet::new();
+    for variant in variants {
+        let name = variant.name.trim();
+        if name.is_empty() {
+            return Err("output variant names must not be empty".to_string());
+        }
+        if !names.insert(name.to_ascii_lowercase()) {
+            return Err(format!("output variant name \"{name}\" is used twice"));
+        }
+        if !VARIANT_CONTAINERS.contains(&variant.container.trim().to_ascii_lowercase().as_str()) {
+            return Err(format!(
+                "output variant \"{name}\" container must be one of {}",
+                VARIANT_CONTAINERS.join(", ")
+            ));
+        }
+        let root = variant.output_root.trim();
+        if root.is_empty() || !Path::new(root).is_absolute() {
+            return Err(format!(
+                "output variant \"{name}\" needs an absolute output_root"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Where `variant` of `input_path` is written: the source's path below
+/// `source_root` re-rooted at the variant's output root, with the variant's
+/// container as extension. Without a source root only the file name is kept.
+pub fn variant_output_path(
+    input_path: &Path,
+    source_root: Option<&Path>,
+    variant: &OutputVariant,
+) -> PathBuf {
+    let relative = source_root
+        .and_then(|root| input_path.strip_prefix(root).ok())
+        .filter(|relative| relative.file_name().is_some())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from(input_path.file_name().unwrap_or_default()));
+    let mut output = PathBuf::from(variant.output_root.trim()).join(relative);
+    output.set_extension(variant.container.trim().to_ascii_lowercase());
+    output
+}
+
+/// The unique `input_path` key of the job encoding `name` of `input_path`.
+/// The variant job reads its real input from its parent.
+pub fn variant_job_key(input_path: &Path, name: &str) -> String {
+    format!("{}#variant={}", input_path.display(), name)
+}
+
+/// The profile a variant is planned with: the base profile with the
+/// variant's codec, resolution cap and audio mode. Rate-control settings
+/// tuned for the base codec do not carry over.
+pub fn variant_profile(base: &LibraryProfile, variant: &OutputVariant) -> LibraryProfile {
+    LibraryProfile {
+        codec: variant.codec.as_str().to_string(),
+        max_resolution: variant
+            .max_resolution
+            .map(|resolution| resolution.as_str().to_string()),
+        audio_mode: variant.audio_mode.as_str().to_string(),
+        crf_override: None,
+        target_vmaf: None,
+        output_variants_json: "[]".to_string(),
+        ..base.clone()
+    }
+}
+
+/// Whether `output` exists and was written no earlier than `source` changed.
+pub fn is_up_to_date(source: &Path, output: &Path) -> bool {
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|meta| meta.modified());
+    match (modified(source), modified(output)) {
+        (Ok(source), Ok(output)) => output >= source,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tablet() -> OutputVariant {
+        OutputVariant {
+            name: "tablet".to_string(),
+            codec: OutputCodec::H264,
+            max_resolution: Some(MaxResolution::P1080),
+            audio_mode: AudioMode::AacStereo,
+            container: "mp4".to_string(),
+            output_root: "/compat".to_string(),
+        }
+    }
+
+    #[test]
+    fn variant_paths_mirror_the_watch_folder_layout() {
+        let input = Path::new("/media/movies/Film (2020)/Film.mkv");
+        assert_eq!(
+            variant_output_path(input, Some(Path::new("/media/movies")), &tablet()),
+            PathBuf::from("/compat/Film (2020)/Film.mp4")
+        );
+        assert_eq!(
+            variant_output_path(input, None, &tablet()),
+            PathBuf::from("/compat/Film.mp4")
+        );
+    }
+
+    #[test]
+    fn variant_lists_need_unique_names_and_absolute_roots() {
+        assert!(validate_variants(&[tablet()]).is_ok());
+        assert!(validate_variants(&[tablet(), tablet()]).is_err());
+        let relative = OutputVariant {
+            output_root: "compat".to_string(),
+            ..tablet()
+        };
+        assert!(validate_variants(&[relative]).is_err());
+        let avi = OutputVariant {
+            container: "avi".to_string(),
+            ..tablet()
+        };
+        assert!(validate_variants(&[avi]).is_err());
+    }
+
+    #[test]
+    fn variants_deserialize_with_defaults() -> serde_json::Result<()> {
+        let variants: Vec<OutputVariant> = serde_json::from_str(
+            r#"[{"name": "tablet", "codec": "h264", "output_root": "/compat"}]"#,
+        )?;
+        assert_eq!(variants[0].container, "mkv");
+        assert_eq!(variants[0].audio_mode, AudioMode::Copy);
+        assert_eq!(variants[0].max_resolution, None);
+        Ok(())
+    }
+}
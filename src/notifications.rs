@@ -109,6 +109,9 @@ enum NotifiableEvent {
     DiskSpaceLow {
         reason: String,
     },
+    SpaceGoalCompleted {
+        message: String,
+    },
 }
 
 fn event_key(event: &NotifiableEvent) -> Option<&'static str> {
@@ -129,6 +132,9 @@ fn event_key(event: &NotifiableEvent) -> Option<&'static str> {
         NotifiableEvent::DiskSpaceLow { .. } => {
             Some(crate::config::NOTIFICATION_EVENT_DISK_SPACE_LOW)
         }
+        NotifiableEvent::SpaceGoalCompleted { .. } => {
+            Some(crate::config::NOTIFICATION_EVENT_SPACE_GOAL_COMPLETED)
+        }
     }
 }
 
@@ -307,6 +313,17 @@ impl NotificationManager {
                             );
                         }
                     }
+                    Ok(SystemEvent::SpaceGoalCompleted { message, .. }) => {
+                        if let Err(e) = manager_clone
+                            .handle_event(NotifiableEvent::SpaceGoalCompleted { message })
+                            .await
+                        {
+                            error!(
+                                "Notification error: {}",
+                                crate::redact::redact_secrets(&e.to_string())
+                            );
+                        }
+                    }
                     Ok(_) => {} // Ignore ScanStarted, EngineStatusChanged, HardwareStateChanged
                     Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {
                         warn!("Notification system listener lagged")
@@ -677,6 +694,7 @@ impl NotificationManager {
                     "Low disk space — the engine is holding jobs until space is reclaimed. {reason}"
                 )
             }
+            NotifiableEvent::SpaceGoalCompleted { message } => message.clone(),
         }
     }
 
@@ -7,6 +7,7 @@ use std::future::Future;
 use std::path::Path;
 use std::pin::Pin;
 use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::sync::oneshot;
@@ -18,6 +19,12 @@ pub struct Transcoder {
     cancel_channels: Arc<Mutex<HashMap<i64, oneshot::Sender<()>>>>,
     pending_cancels: Arc<Mutex<HashSet<i64>>>,
     pub(crate) cancel_requested: Arc<tokio::sync::RwLock<HashSet<i64>>>,
+    /// Set while the resource governor holds running FFmpeg processes
+    /// stopped; the stall check must not mistake that for a hang.
+    encodes_suspended: Arc<AtomicBool>,
+    /// Bumped on every suspend and resume, so a wait that spanned a
+    /// suspension is not counted as a stall either.
+    suspend_epoch: Arc<AtomicU64>,
 }
 
 pub struct TranscodeRequest<'a> {
@@ -31,6 +38,8 @@ pub struct TranscodeRequest<'a> {
     pub observer: Option<Arc<dyn ExecutionObserver>>,
     pub clip_start_seconds: Option<f64>,
     pub clip_duration_seconds: Option<f64>,
+    /// x265 `dhdr10-info` file with the source's HDR10+ metadata.
+    pub hdr10_plus_metadata: Option<&'a Path>,
 }
 
 #[allow(async_fn_in_trait)]
@@ -84,9 +93,22 @@ impl Transcoder {
             cancel_channels: Arc::new(Mutex::new(HashMap::new())),
             pending_cancels: Arc::new(Mutex::new(HashSet::new())),
             cancel_requested: Arc::new(tokio::sync::RwLock::new(HashSet::new())),
+            encodes_suspended: Arc::new(AtomicBool::new(false)),
+            suspend_epoch: Arc::new(AtomicU64::new(0)),
         }
     }
 
+    /// Record whether running FFmpeg processes are stopped by the governor.
+    pub fn set_encodes_suspended(&self, suspended: bool) {
+        if self.encodes_suspended.swap(suspended, Ordering::SeqCst) != suspended {
+            self.suspend_epoch.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    pub fn encodes_suspended(&self) -> bool {
+        self.encodes_suspended.load(Ordering::SeqCst)
+    }
+
     pub async fn is_cancel_requested(&self, job_id: i64) -> bool {
         self.cancel_requested.read().await.contains(&job_id)
     }
@@ -127,6 +149,46 @@ impl Transcoder {
         }
     }
 
+    /// Register a cancel channel for work that runs outside FFmpeg, such as
+    /// post-processing hooks, so `cancel_job` and shutdown reach it too.
+    pub fn register_cancel_channel(&self, job_id: i64) -> oneshot::Receiver<()> {
+        let (tx, rx) = oneshot::channel();
+        let was_pending = match self.pending_cancels.lock() {
+            Ok(mut pending) => pending.remove(&job_id),
+            Err(e) => {
+                error!("Pending cancels lock poisoned, recovering: {}", e);
+                e.into_inner().remove(&job_id)
+            }
+        };
+        if was_pending {
+            let _ = tx.send(());
+            return rx;
+        }
+        match self.cancel_channels.lock() {
+            Ok(mut channels) => {
+                channels.insert(job_id, tx);
+            }
+            Err(e) => {
+                error!("Cancel channels lock poisoned, recovering: {}", e);
+                e.into_inner().insert(job_id, tx);
+            }
+        }
+        rx
+    }
+
+    /// Drop a channel added by `register_cancel_channel`.
+    pub fn release_cancel_channel(&self, job_id: i64) {
+        match self.cancel_channels.lock() {
+            Ok(mut channels) => {
+                channels.remove(&job_id);
+            }
+            Err(e) => {
+                error!("Cancel channels lock poisoned, recovering: {}", e);
+                e.into_inner().remove(&job_id);
+            }
+        }
+    }
+
     /// Cancel all currently running jobs. Used during graceful shutdown.
     pub fn cancel_all_jobs(&self) -> usize {
         let mut channels = match self.cancel_channels.lock() {
@@ -190,6 +252,7 @@ impl Transcoder {
         )
         .with_hardware(request.hw_info)
         .with_clip(request.clip_start_seconds, request.clip_duration_seconds)
+        .with_hdr10_plus_metadata(request.hdr10_plus_metadata)
         .build()?;
 
         info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
@@ -322,6 +385,7 @@ impl Transcoder {
         let mut first_frame_logged = false;
 
         loop {
+            let suspend_epoch = self.suspend_epoch.load(Ordering::SeqCst);
             tokio::select! {
                 line_res_timeout = tokio::time::timeout(tokio::time::Duration::from_secs(120), reader.next_line()) => {
                     match line_res_timeout {
@@ -372,6 +436,9 @@ impl Transcoder {
                                 break;
                             }
                         },
+                        // Stopped by the resource governor, not hung.
+                        Err(_) if self.encodes_suspended()
+                            || self.suspend_epoch.load(Ordering::SeqCst) != suspend_epoch => {}
                         Err(_) => {
                             error!("Job {:?} stalled: No output from FFmpeg for 2 minutes. Killing process...", job_id);
                             let _ = child.kill().await;
@@ -1,6 +1,9 @@
 use crate::Agent;
+use crate::config::EngineMode;
 use crate::db::Db;
+use crate::media::pipeline::EncoderBackend;
 use chrono::{Datelike, Local, Timelike};
+use serde::Serialize;
 use std::sync::Arc;
 use tokio::sync::Notify;
 use tokio::time::Duration;
@@ -10,6 +13,33 @@ pub struct Scheduler {
     db: Arc<Db>,
     agent: Arc<Agent>,
     notify: Arc<Notify>,
+    /// Engine settings from before the current window's overrides.
+    baseline: std::sync::Mutex<Option<EngineBaseline>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct EngineBaseline {
+    mode: EngineMode,
+    concurrent_jobs: usize,
+    manual_override: bool,
+}
+
+/// The schedule window currently in force, as reported by the engine status.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ActiveScheduleWindow {
+    pub id: i64,
+    pub start_time: String,
+    pub end_time: String,
+    pub priority: i32,
+    pub engine_mode: Option<EngineMode>,
+    pub concurrent_jobs: Option<usize>,
+    pub allowed_backends: Vec<EncoderBackend>,
+}
+
+impl ActiveScheduleWindow {
+    fn has_engine_overrides(&self) -> bool {
+        self.engine_mode.is_some() || self.concurrent_jobs.is_some()
+    }
 }
 
 #[derive(Clone)]
@@ -23,6 +53,7 @@ impl Scheduler {
             db,
             agent,
             notify: Arc::new(Notify::new()),
+            baseline: std::sync::Mutex::new(None),
         }
     }
 
@@ -57,68 +88,18 @@ impl Scheduler {
         let enabled_windows: Vec<_> = windows.into_iter().filter(|w| w.enabled).collect();
 
         if enabled_windows.is_empty() {
-            // No schedule active -> Do nothing, leave current state alone
+            // No schedule active -> Do nothing, leave current state alone,
+            // apart from undoing any overrides a removed window applied.
+            self.apply_window(None).await;
             return Ok(());
         }
 
         let now = Local::now();
         let current_minutes = now.hour() * 60 + now.minute();
         let current_day = now.weekday().num_days_from_sunday() as i32; // 0=Sun, 6=Sat
-        let previous_day = (current_day + 6) % 7;
-
-        let mut in_window = false;
+        let active = select_active_window(&enabled_windows, current_day, current_minutes);
 
-        for window in enabled_windows {
-            // Parse days
-            let days: Vec<i32> = match serde_json::from_str(&window.days_of_week) {
-                Ok(v) => v,
-                Err(e) => {
-                    warn!(
-                        "Failed to parse days_of_week for schedule window {}: {}",
-                        window.id, e
-                    );
-                    continue;
-                }
-            };
-
-            let start_minutes = match parse_schedule_minutes(&window.start_time) {
-                Some(value) => value,
-                None => {
-                    warn!("Invalid schedule start_time '{}'", window.start_time);
-                    continue;
-                }
-            };
-            let end_minutes = match parse_schedule_minutes(&window.end_time) {
-                Some(value) => value,
-                None => {
-                    warn!("Invalid schedule end_time '{}'", window.end_time);
-                    continue;
-                }
-            };
-
-            if start_minutes <= end_minutes {
-                // Normal same-day window.
-                if days.contains(&current_day)
-                    && current_minutes >= start_minutes
-                    && current_minutes < end_minutes
-                {
-                    in_window = true;
-                    break;
-                }
-            } else {
-                // Overnight window split across two calendar days.
-                let in_late_segment =
-                    days.contains(&current_day) && current_minutes >= start_minutes;
-                let in_early_segment =
-                    days.contains(&previous_day) && current_minutes < end_minutes;
-                if in_late_segment || in_early_segment {
-                    in_window = true;
-                    break;
-                }
-            }
-        }
-
-        if in_window {
+        if active.is_some() {
             // Allowed to run
             if self.agent.is_scheduler_paused() {
                 self.agent.set_scheduler_paused(false);
@@ -129,9 +110,78 @@ impl Scheduler {
                 self.agent.set_scheduler_paused(true);
             }
         }
+        self.apply_window(active).await;
 
         Ok(())
     }
+
+    /// Apply the overrides of the window that just became active, or restore
+    /// the engine settings that were in place before the first override.
+    /// Only runs on a change of window, so manual changes made mid-window
+    /// stand until the next boundary.
+    async fn apply_window(&self, active: Option<ActiveScheduleWindow>) {
+        if self.agent.active_schedule_window() == active {
+            return;
+        }
+
+        let overriding = active
+            .as_ref()
+            .filter(|window| window.has_engine_overrides());
+        if let Some(window) = overriding {
+            let current = EngineBaseline {
+                mode: self.agent.current_mode().await,
+                concurrent_jobs: self.agent.concurrent_jobs_limit(),
+                manual_override: self.agent.is_manual_override(),
+            };
+            let baseline = match self.baseline.lock() {
+                Ok(mut baseline) => *baseline.get_or_insert(current),
+                Err(e) => {
+                    error!("Scheduler baseline lock poisoned: {}", e);
+                    return;
+                }
+            };
+            let mode = window.engine_mode.unwrap_or(baseline.mode);
+            let (concurrent_jobs, manual_override) =
+                match (window.concurrent_jobs, window.engine_mode) {
+                    (Some(jobs), _) => (jobs, true),
+                    (None, Some(mode)) => (mode.concurrent_jobs_for_cpu_count(cpu_count()), false),
+                    (None, None) => (baseline.concurrent_jobs, baseline.manual_override),
+                };
+            info!(
+                "Schedule window {} active: mode '{}', {} concurrent jobs",
+                window.id,
+                mode.as_str(),
+                concurrent_jobs
+            );
+            self.agent
+                .set_runtime_limits(mode, concurrent_jobs, manual_override)
+                .await;
+        } else {
+            let baseline = match self.baseline.lock() {
+                Ok(mut baseline) => baseline.take(),
+                Err(e) => {
+                    error!("Scheduler baseline lock poisoned: {}", e);
+                    None
+                }
+            };
+            if let Some(baseline) = baseline {
+                info!(
+                    "Schedule overrides ended: restoring mode '{}', {} concurrent jobs",
+                    baseline.mode.as_str(),
+                    baseline.concurrent_jobs
+                );
+                self.agent
+                    .set_runtime_limits(
+                        baseline.mode,
+                        baseline.concurrent_jobs,
+                        baseline.manual_override,
+                    )
+                    .await;
+            }
+        }
+
+        self.agent.set_active_schedule_window(active);
+    }
 }
 
 impl SchedulerHandle {
@@ -153,3 +203,159 @@ fn parse_schedule_minutes(value: &str) -> Option<u32> {
     }
     Some(hour * 60 + minute)
 }
+
+fn cpu_count() -> usize {
+    std::thread::available_parallelism()
+        .map(|count| count.get())
+        .unwrap_or(1)
+}
+
+fn window_contains(
+    days: &[i32],
+    start_minutes: u32,
+    end_minutes: u32,
+    current_day: i32,
+    current_minutes: u32,
+) -> bool {
+    if start_minutes <= end_minutes {
+        // Normal same-day window.
+        days.contains(&current_day)
+            && current_minutes >= start_minutes
+            && current_minutes < end_minutes
+    } else {
+        // Overnight window split across two calendar days.
+        let previous_day = (current_day + 6) % 7;
+        let in_late_segment = days.contains(&current_day) && current_minutes >= start_minutes;
+        let in_early_segment = days.contains(&previous_day) && current_minutes < end_minutes;
+        in_late_segment || in_early_segment
+    }
+}
+
+/// The window in force at `current_day`/`current_minutes`: the highest
+/// priority of the windows containing that time, ties going to the window
+/// listed first.
+fn select_active_window(
+    windows: &[crate::db::ScheduleWindow],
+    current_day: i32,
+    current_minutes: u32,
+) -> Option<ActiveScheduleWindow> {
+    let mut active: Option<&crate::db::ScheduleWindow> = None;
+    for window in windows {
+        // Parse days
+        let days: Vec<i32> = match serde_json::from_str(&window.days_of_week) {
+            Ok(v) => v,
+            Err(e) => {
+                warn!(
+                    "Failed to parse days_of_week for schedule window {}: {}",
+                    window.id, e
+                );
+                continue;
+            }
+        };
+
+        let start_minutes = match parse_schedule_minutes(&window.start_time) {
+            Some(value) => value,
+            None => {
+                warn!("Invalid schedule start_time '{}'", window.start_time);
+                continue;
+            }
+        };
+        let end_minutes = match parse_schedule_minutes(&window.end_time) {
+            Some(value) => value,
+            None => {
+                warn!("Invalid schedule end_time '{}'", window.end_time);
+                continue;
+            }
+        };
+
+        if window_contains(
+            &days,
+            start_minutes,
+            end_minutes,
+            current_day,
+            current_minutes,
+        ) && active.is_none_or(|current| window.priority > current.priority)
+        {
+            active = Some(window);
+        }
+    }
+
+    active.map(|window| ActiveScheduleWindow {
+        id: window.id,
+        start_time: window.start_time.clone(),
+        end_time: window.end_time.clone(),
+        priority: window.priority,
+        engine_mode: window.engine_mode.as_deref().and_then(EngineMode::parse),
+        concurrent_jobs: window
+            .concurrent_jobs
+            .filter(|jobs| *jobs > 0)
+            .map(|jobs| jobs as usize),
+        allowed_backends: serde_json::from_str(&window.allowed_backends).unwrap_or_else(|e| {
+            warn!(
+                "Failed to parse allowed_backends for schedule window {}: {}",
+                window.id, e
+            );
+            Vec::new()
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(id: i64, start: &str, end: &str, priority: i32) -> crate::db::ScheduleWindow {
+        crate::db::ScheduleWindow {
+            id,
+            start_time: start.to_string(),
+            end_time: end.to_string(),
+            days_of_week: "[1,2,3,4,5]".to_string(),
+            enabled: true,
+            engine_mode: None,
+            concurrent_jobs: None,
+            allowed_backends: "[]".to_string(),
+            priority,
+        }
+    }
+
+    #[test]
+    fn overlapping_windows_resolve_by_priority_then_order() {
+        let mut nightly = window(1, "01:00", "07:00", 10);
+        nightly.engine_mode = Some("throughput".to_string());
+        nightly.concurrent_jobs = Some(4);
+        let mut daytime = window(2, "00:00", "23:59", 0);
+        daytime.engine_mode = Some("background".to_string());
+        daytime.allowed_backends = r#"["nvenc","qsv"]"#.to_string();
+        let windows = vec![daytime, nightly, window(3, "00:00", "23:59", 0)];
+
+        // Monday 03:00: both match, the nightly window has higher priority.
+        let Some(active) = select_active_window(&windows, 1, 3 * 60) else {
+            panic!("expected an active window");
+        };
+        assert_eq!(active.id, 1);
+        assert_eq!(active.engine_mode, Some(EngineMode::Throughput));
+        assert_eq!(active.concurrent_jobs, Some(4));
+
+        // Monday 12:00: windows 2 and 3 tie, the first listed wins.
+        let Some(active) = select_active_window(&windows, 1, 12 * 60) else {
+            panic!("expected an active window");
+        };
+        assert_eq!(active.id, 2);
+        assert_eq!(
+            active.allowed_backends,
+            vec![EncoderBackend::Nvenc, EncoderBackend::Qsv]
+        );
+
+        // Sunday is not in any window.
+        assert!(select_active_window(&windows, 0, 12 * 60).is_none());
+    }
+
+    #[test]
+    fn overnight_windows_cover_the_early_hours_of_the_next_day() {
+        let windows = vec![window(1, "22:00", "06:00", 0)];
+        // Friday 23:00 and Saturday 05:00 belong to Friday's window.
+        assert!(select_active_window(&windows, 5, 23 * 60).is_some());
+        assert!(select_active_window(&windows, 6, 5 * 60).is_some());
+        assert!(select_active_window(&windows, 6, 23 * 60).is_none());
+    }
+}
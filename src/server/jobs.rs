@@ -1,7 +1,7 @@
 //! Job CRUD, batch operations, queue control handlers.
 
 use super::{AppState, api_error_response, api_ok_response, is_row_not_found};
-use crate::db::{Job, JobState};
+use crate::db::{Job, JobEvent, JobState};
 use crate::error::Result;
 use crate::explanations::Explanation;
 use axum::{
@@ -31,6 +31,8 @@ struct BlockedJobsResponse {
 #[derive(Deserialize)]
 pub(crate) struct EnqueueJobPayload {
     path: String,
+    #[serde(default)]
+    overrides: crate::media::overrides::JobOverrides,
 }
 
 #[derive(Serialize)]
@@ -95,7 +97,17 @@ async fn purge_resume_sessions_for_jobs(state: &AppState, ids: &[i64]) {
 pub(crate) async fn enqueue_job_from_submitted_path(
     state: &AppState,
     submitted_path: &str,
+    overrides: Option<&crate::media::overrides::JobOverrides>,
 ) -> std::result::Result<EnqueueJobResponse, (StatusCode, &'static str, String)> {
+    if let Some(overrides) = overrides
+        && let Err(message) = overrides.validate()
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "ENQUEUE_OVERRIDES_INVALID",
+            message,
+        ));
+    }
     if submitted_path.is_empty() {
         return Err((
             StatusCode::BAD_REQUEST,
@@ -158,17 +170,17 @@ pub(crate) async fn enqueue_job_from_submitted_path(
         }
     }
 
-    let mut is_allowed = false;
+    let mut canonical_roots = Vec::with_capacity(allowed_roots.len());
     for root in allowed_roots {
-        if let Ok(canonical_root) = tokio::fs::canonicalize(&root).await
-            && canonical_path.starts_with(&canonical_root)
-        {
-            is_allowed = true;
-            break;
+        if let Ok(canonical_root) = tokio::fs::canonicalize(&root).await {
+            canonical_roots.push(canonical_root);
         }
     }
 
-    if !is_allowed {
+    if !canonical_roots
+        .iter()
+        .any(|root| canonical_path.starts_with(root))
+    {
         return Err((
             StatusCode::FORBIDDEN,
             "ENQUEUE_PATH_FORBIDDEN",
@@ -177,6 +189,10 @@ pub(crate) async fn enqueue_job_from_submitted_path(
         ));
     }
 
+    if let Some(output_path) = overrides.and_then(|overrides| overrides.output_path.as_deref()) {
+        check_output_path_override(state, &canonical_path, output_path, canonical_roots).await?;
+    }
+
     let extension = canonical_path
         .extension()
         .and_then(|value| value.to_str())
@@ -210,7 +226,13 @@ pub(crate) async fn enqueue_job_from_submitted_path(
         source_root: resolve_source_root(&canonical_path, &watch_dirs),
     };
 
-    match crate::media::pipeline::enqueue_discovered_with_db(state.db.as_ref(), discovered).await {
+    match crate::media::pipeline::enqueue_discovered_with_overrides(
+        state.db.as_ref(),
+        discovered,
+        overrides,
+    )
+    .await
+    {
         Ok(true) => Ok(EnqueueJobResponse {
             enqueued: true,
             message: format!("Enqueued {}.", canonical_path.display()),
@@ -228,17 +250,71 @@ pub(crate) async fn enqueue_job_from_submitted_path(
     }
 }
 
+/// An output path override must sit, like the source, inside a library
+/// folder or the configured output root, and must not be the source itself.
+async fn check_output_path_override(
+    state: &AppState,
+    input_path: &FsPath,
+    output_path: &str,
+    mut allowed_roots: Vec<PathBuf>,
+) -> std::result::Result<(), (StatusCode, &'static str, String)> {
+    let output_path = PathBuf::from(output_path.trim());
+    let invalid = |message: &str| {
+        Err((
+            StatusCode::BAD_REQUEST,
+            "ENQUEUE_OUTPUT_PATH_INVALID",
+            message.to_string(),
+        ))
+    };
+    let (Some(parent), Some(file_name)) = (output_path.parent(), output_path.file_name()) else {
+        return invalid("output_path must name a file.");
+    };
+    let Ok(canonical_parent) = tokio::fs::canonicalize(parent).await else {
+        return invalid("output_path must be in an existing directory.");
+    };
+    if canonical_parent.join(file_name) == input_path {
+        return invalid("output_path must differ from the source path.");
+    }
+
+    if let Ok(settings) = state.db.get_file_settings().await
+        && let Some(output_root) = settings
+            .output_root
+            .filter(|value| !value.trim().is_empty())
+        && let Ok(canonical_root) = tokio::fs::canonicalize(output_root).await
+    {
+        allowed_roots.push(canonical_root);
+    }
+    if !allowed_roots
+        .iter()
+        .any(|root| canonical_parent.starts_with(root))
+    {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "ENQUEUE_OUTPUT_PATH_FORBIDDEN",
+            "output_path must be within a configured library folder or the output root."
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
 pub(crate) async fn enqueue_job_handler(
     State(state): State<Arc<AppState>>,
     axum::Json(payload): axum::Json<EnqueueJobPayload>,
 ) -> impl IntoResponse {
-    match enqueue_job_from_submitted_path(state.as_ref(), payload.path.trim()).await {
+    let overrides = (!payload.overrides.is_empty()).then_some(&payload.overrides);
+    match enqueue_job_from_submitted_path(state.as_ref(), payload.path.trim(), overrides).await {
         Ok(res) => axum::Json(res).into_response(),
         Err((status, code, msg)) => api_error_response(status, code, msg),
     }
 }
 
 pub(crate) async fn request_job_cancel(state: &AppState, job: &Job) -> Result<bool> {
+    // A remote worker picks the request up from its next heartbeat.
+    if job.is_active() && state.db.get_job_lease(job.id).await?.is_some() {
+        state.transcoder.add_cancel_request(job.id).await;
+        return Ok(true);
+    }
     match job.status {
         JobState::Queued => {
             state.transcoder.add_cancel_request(job.id).await;
@@ -650,6 +726,125 @@ pub(crate) async fn restart_job_handler(
     }
 }
 
+/// Skip the rest of a queued job's retry backoff.
+pub(crate) async fn retry_job_now_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match state.db.retry_job_now(id).await {
+        Ok(true) => api_ok_response(),
+        Ok(false) => match state.db.get_job_by_id(id).await {
+            Ok(Some(_)) => api_error_response(
+                StatusCode::CONFLICT,
+                "JOB_NOT_WAITING_FOR_RETRY",
+                "Job is not queued for a retry",
+            ),
+            Ok(None) => api_error_response(StatusCode::NOT_FOUND, "JOB_NOT_FOUND", "Job not found"),
+            Err(e) => api_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "GET_JOB_FAILED",
+                e.to_string(),
+            ),
+        },
+        Err(e) => api_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "RETRY_JOB_FAILED",
+            e.to_string(),
+        ),
+    }
+}
+
+/// Put a job's recycled original back and remove the encoded output.
+pub(crate) async fn restore_job_original_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match state.db.get_job_by_id(id).await {
+        Ok(Some(job)) if job.is_active() => {
+            return blocked_jobs_response("restore is blocked while the job is active", &[job]);
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return api_error_response(StatusCode::NOT_FOUND, "JOB_NOT_FOUND", "Job not found");
+        }
+        Err(e) => {
+            return api_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "GET_JOB_FAILED",
+                e.to_string(),
+            );
+        }
+    }
+
+    let entry = match state.db.get_held_recycle_entry_for_job(id).await {
+        Ok(Some(entry)) => entry,
+        Ok(None) => {
+            return api_error_response(
+                StatusCode::NOT_FOUND,
+                "RECYCLED_ORIGINAL_NOT_FOUND",
+                "No recycled original is held for this job",
+            );
+        }
+        Err(e) => {
+            return api_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "GET_RECYCLED_ORIGINAL_FAILED",
+                e.to_string(),
+            );
+        }
+    };
+    if !FsPath::new(&entry.recycled_path).exists() {
+        return api_error_response(
+            StatusCode::GONE,
+            "RECYCLED_ORIGINAL_MISSING",
+            format!("Recycled original is missing: {}", entry.recycled_path),
+        );
+    }
+    if FsPath::new(&entry.original_path).exists() {
+        return api_error_response(
+            StatusCode::CONFLICT,
+            "RESTORE_TARGET_EXISTS",
+            format!("A file already exists at {}", entry.original_path),
+        );
+    }
+
+    match crate::media::recycle::restore(state.db.as_ref(), &entry).await {
+        Ok(outcome) => {
+            let mut message = match &outcome.removed_output {
+                Some(output) => format!(
+                    "Original restored to {}; encoded output {} removed",
+                    outcome.restored_path, output
+                ),
+                None => format!("Original restored to {}", outcome.restored_path),
+            };
+            if !outcome.restored_sidecars.is_empty() {
+                message.push_str(&format!(
+                    "; {} sidecar(s) moved back",
+                    outcome.restored_sidecars.len()
+                ));
+            }
+            if let Err(err) = state.db.add_log("info", Some(id), &message).await {
+                tracing::warn!(job_id = id, "Failed to record log: {err}");
+            }
+            let _ = state.event_channels.jobs.send(JobEvent::StateChanged {
+                job_id: id,
+                status: JobState::Cancelled,
+            });
+            axum::Json(serde_json::json!({
+                "restored_path": outcome.restored_path,
+                "removed_output": outcome.removed_output,
+                "restored_sidecars": outcome.restored_sidecars,
+            }))
+            .into_response()
+        }
+        Err(e) => api_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "RESTORE_ORIGINAL_FAILED",
+            e.to_string(),
+        ),
+    }
+}
+
 pub(crate) async fn delete_job_handler(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
@@ -793,6 +988,50 @@ pub(crate) struct JobDetailResponse {
     decision_explanation: Option<Explanation>,
     failure_explanation: Option<Explanation>,
     queue_position: Option<u32>,
+    /// Estimated seconds until the job starts, for queued jobs.
+    queue_wait_seconds: Option<i64>,
+    retry: crate::db::JobRetryState,
+    /// Output variants produced for the job's profile.
+    variants: Vec<crate::db::JobVariant>,
+    /// Companion files carried over to the output's name.
+    sidecars: Vec<crate::db::JobSidecar>,
+    /// Overrides supplied when the job was enqueued.
+    overrides: Option<crate::media::overrides::JobOverrides>,
+    effective_settings: EffectiveJobSettings,
+}
+
+/// What the job is planned with once its overrides are layered over its
+/// profile and the global settings.
+#[derive(Serialize)]
+struct EffectiveJobSettings {
+    profile: Option<String>,
+    #[serde(flatten)]
+    encode: crate::media::planner::EffectiveSettings,
+    replace_strategy: String,
+    delete_source: bool,
+}
+
+/// The profile the job was last planned with: the one named by the profile
+/// rule recorded on its decision, else its watch folder's.
+async fn job_profile(
+    state: &AppState,
+    job: &Job,
+    decision: Option<&Explanation>,
+) -> Result<Option<crate::db::LibraryProfile>> {
+    let rule_id = decision
+        .and_then(|explanation| explanation.measured.get("profile_rule_id"))
+        .and_then(|value| value.as_i64());
+    if let Some(rule_id) = rule_id {
+        let rules = state.db.get_profile_rules().await?;
+        if let Some(profile_id) = rules
+            .iter()
+            .find(|rule| rule.id == rule_id)
+            .and_then(|rule| rule.profile_id)
+        {
+            return state.db.get_profile(profile_id).await;
+        }
+    }
+    state.db.get_profile_for_path(&job.input_path).await
 }
 
 pub(crate) async fn get_job_detail_handler(
@@ -897,8 +1136,18 @@ pub(crate) async fn get_job_detail_handler(
     };
     let encode_history_runs = group_encode_attempts_by_run(&encode_attempts);
 
+    let queue_order = state.config.read().await.transcode.queue_order;
     let queue_position = if job.status == JobState::Queued {
-        match state.db.get_queue_position(id).await {
+        let position = match state.db.queue_ranking(queue_order).await {
+            Ok(ranking) => {
+                state
+                    .db
+                    .get_queue_position(id, &ranking, state.agent.concurrent_jobs_limit())
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+        match position {
             Ok(position) => position,
             Err(err) => {
                 return api_error_response(
@@ -912,6 +1161,84 @@ pub(crate) async fn get_job_detail_handler(
         None
     };
 
+    let retry = match state.db.get_job_retry_state(id).await {
+        Ok(retry) => retry,
+        Err(err) => {
+            return api_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "GET_RETRY_STATE_FAILED",
+                err.to_string(),
+            );
+        }
+    };
+
+    let variants = match state.db.get_job_variants(id).await {
+        Ok(variants) => variants,
+        Err(err) => {
+            return api_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "GET_JOB_VARIANTS_FAILED",
+                err.to_string(),
+            );
+        }
+    };
+
+    let sidecars = match state.db.get_job_sidecars(id).await {
+        Ok(sidecars) => sidecars,
+        Err(err) => {
+            return api_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "GET_JOB_SIDECARS_FAILED",
+                err.to_string(),
+            );
+        }
+    };
+
+    let overrides = match state.db.get_job_overrides(id).await {
+        Ok(overrides) => overrides,
+        Err(err) => {
+            return api_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "GET_JOB_OVERRIDES_FAILED",
+                err.to_string(),
+            );
+        }
+    };
+    let profile = match job_profile(&state, &job, decision_explanation.as_ref()).await {
+        Ok(profile) => profile,
+        Err(err) => {
+            return api_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "GET_JOB_PROFILE_FAILED",
+                err.to_string(),
+            );
+        }
+    };
+    let file_settings = match state.db.get_file_settings().await {
+        Ok(settings) => settings,
+        Err(err) => {
+            return api_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "GET_FILE_SETTINGS_FAILED",
+                err.to_string(),
+            );
+        }
+    };
+    let file_settings = match &overrides {
+        Some(overrides) => overrides.apply_to_file_settings(&file_settings),
+        None => file_settings,
+    };
+    let effective_settings = EffectiveJobSettings {
+        encode: crate::media::planner::EffectiveSettings::resolve(
+            &*state.config.read().await,
+            profile.as_ref(),
+            overrides.as_ref(),
+        ),
+        profile: profile.map(|profile| profile.name),
+        replace_strategy: file_settings.replace_strategy,
+        delete_source: file_settings.delete_source,
+    };
+
     axum::Json(JobDetailResponse {
         job,
         metadata,
@@ -922,7 +1249,13 @@ pub(crate) async fn get_job_detail_handler(
         job_failure_summary,
         decision_explanation,
         failure_explanation,
-        queue_position,
+        queue_position: queue_position.map(|queue| queue.position),
+        queue_wait_seconds: queue_position.and_then(|queue| queue.est_wait_seconds),
+        retry,
+        variants,
+        sidecars,
+        overrides,
+        effective_settings,
     })
     .into_response()
 }
@@ -993,6 +1326,10 @@ pub(crate) async fn engine_status_handler(State(state): State<Arc<AppState>>) ->
         "concurrent_limit": state.agent.concurrent_jobs_limit(),
         "is_manual_override": state.agent.is_manual_override(),
         "hardware_pending": hardware_pending,
+        "schedule_window": state.agent.active_schedule_window(),
+        "schedule_backend_blocked": state.agent.is_schedule_backend_blocked(),
+        "throttled": state.agent.is_throttled(),
+        "throttle_reason": state.agent.throttle_reason(),
     }))
 }
 
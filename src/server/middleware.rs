@@ -1,5 +1,6 @@
 //! Authentication, rate limiting, and security middleware.
 
+use super::workers::WorkerToken;
 use super::{API_REQUEST_CONTEXT, ApiRequestContext, AppState, api_error_response};
 use crate::db::ApiTokenAccessLevel;
 use axum::{
@@ -103,7 +104,7 @@ fn valid_request_id(value: &str) -> bool {
 
 pub(crate) async fn auth_middleware(
     State(state): State<Arc<AppState>>,
-    req: Request,
+    mut req: Request,
     next: Next,
 ) -> Response {
     let path = req.uri().path();
@@ -236,6 +237,20 @@ pub(crate) async fn auth_middleware(
                                 "Forbidden",
                             );
                         }
+                        ApiTokenAccessLevel::Worker => {
+                            if worker_api_token_allows(&method, normalized_path.as_str()) {
+                                req.extensions_mut().insert(WorkerToken {
+                                    token_id: api_token.id,
+                                    worker_id: api_token.worker_id,
+                                });
+                                return next.run(req).await;
+                            }
+                            return api_error_response(
+                                StatusCode::FORBIDDEN,
+                                "API_TOKEN_FORBIDDEN",
+                                "Forbidden",
+                            );
+                        }
                     }
                 }
                 Ok(None) => {}
@@ -349,6 +364,10 @@ fn jellyfin_api_token_allows(method: &Method, path: &str) -> bool {
         || path.starts_with("/api/jobs/") && path.ends_with("/details")
 }
 
+fn worker_api_token_allows(method: &Method, path: &str) -> bool {
+    *method == Method::POST && path.starts_with("/api/workers/")
+}
+
 pub(crate) async fn rate_limit_middleware(
     State(state): State<Arc<AppState>>,
     req: Request,
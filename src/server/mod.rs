@@ -12,6 +12,7 @@ pub mod stats;
 pub mod system;
 pub mod webhooks;
 pub mod wizard;
+pub mod workers;
 
 #[cfg(test)]
 mod tests;
@@ -513,6 +514,7 @@ fn app_router(state: Arc<AppState>) -> Router {
     use system::*;
     use webhooks::*;
     use wizard::*;
+    use workers::*;
 
     Router::new()
         .nest("/api/v1", v1_api_router())
@@ -526,6 +528,26 @@ fn app_router(state: Arc<AppState>) -> Router {
         .route("/api/stats/daily", get(daily_stats_handler))
         .route("/api/stats/detailed", get(detailed_stats_handler))
         .route("/api/stats/savings", get(savings_summary_handler))
+        .route(
+            "/api/space-goals",
+            get(list_space_goals_handler).post(create_space_goal_handler),
+        )
+        .route("/api/space-goals/{id}", delete(delete_space_goal_handler))
+        .route("/api/workers", get(list_workers_handler))
+        .route("/api/workers/register", post(register_worker_handler))
+        .route("/api/workers/{id}/lease", post(lease_job_handler))
+        .route(
+            "/api/workers/{id}/jobs/{job_id}/plan",
+            post(plan_leased_job_handler),
+        )
+        .route(
+            "/api/workers/{id}/jobs/{job_id}/heartbeat",
+            post(heartbeat_leased_job_handler),
+        )
+        .route(
+            "/api/workers/{id}/jobs/{job_id}/complete",
+            post(complete_leased_job_handler),
+        )
         .route("/api/stats/skip-reasons", get(skip_reasons_handler))
         .route("/api/stats/top-reason-codes", get(top_reason_codes_handler))
         // Canonical job list endpoint.
@@ -543,6 +565,8 @@ fn app_router(state: Arc<AppState>) -> Router {
         .route("/api/jobs/{id}/cancel", post(cancel_job_handler))
         .route("/api/jobs/{id}/priority", post(update_job_priority_handler))
         .route("/api/jobs/{id}/restart", post(restart_job_handler))
+        .route("/api/jobs/{id}/retry-now", post(retry_job_now_handler))
+        .route("/api/jobs/{id}/restore", post(restore_job_original_handler))
         .route("/api/jobs/{id}/delete", post(delete_job_handler))
         .route("/api/jobs/{id}/details", get(get_job_detail_handler))
         .route(
@@ -628,6 +652,15 @@ fn app_router(state: Arc<AppState>) -> Router {
             "/api/profiles/{id}",
             axum::routing::put(update_profile_handler).delete(delete_profile_handler),
         )
+        .route(
+            "/api/profile-rules",
+            get(list_profile_rules_handler).post(create_profile_rule_handler),
+        )
+        .route("/api/profile-rules/test", post(test_profile_rules_handler))
+        .route(
+            "/api/profile-rules/{id}",
+            axum::routing::put(update_profile_rule_handler).delete(delete_profile_rule_handler),
+        )
         .route(
             "/api/settings/notifications",
             get(get_notifications_handler)
@@ -758,6 +791,7 @@ fn v1_api_router() -> Router<Arc<AppState>> {
     use system::*;
     use webhooks::*;
     use wizard::*;
+    use workers::*;
 
     Router::new()
         .route("/scan/start", post(start_scan_handler))
@@ -769,6 +803,26 @@ fn v1_api_router() -> Router<Arc<AppState>> {
         .route("/stats/daily", get(daily_stats_handler))
         .route("/stats/detailed", get(detailed_stats_handler))
         .route("/stats/savings", get(savings_summary_handler))
+        .route(
+            "/space-goals",
+            get(list_space_goals_handler).post(create_space_goal_handler),
+        )
+        .route("/space-goals/{id}", delete(delete_space_goal_handler))
+        .route("/workers", get(list_workers_handler))
+        .route("/workers/register", post(register_worker_handler))
+        .route("/workers/{id}/lease", post(lease_job_handler))
+        .route(
+            "/workers/{id}/jobs/{job_id}/plan",
+            post(plan_leased_job_handler),
+        )
+        .route(
+            "/workers/{id}/jobs/{job_id}/heartbeat",
+            post(heartbeat_leased_job_handler),
+        )
+        .route(
+            "/workers/{id}/jobs/{job_id}/complete",
+            post(complete_leased_job_handler),
+        )
         .route("/stats/skip-reasons", get(skip_reasons_handler))
         .route("/stats/top-reason-codes", get(top_reason_codes_handler))
         .route("/jobs", get(jobs_table_handler))
@@ -781,6 +835,8 @@ fn v1_api_router() -> Router<Arc<AppState>> {
         .route("/jobs/{id}/cancel", post(cancel_job_handler))
         .route("/jobs/{id}/priority", post(update_job_priority_handler))
         .route("/jobs/{id}/restart", post(restart_job_handler))
+        .route("/jobs/{id}/retry-now", post(retry_job_now_handler))
+        .route("/jobs/{id}/restore", post(restore_job_original_handler))
         .route("/jobs/{id}/details", get(get_job_detail_handler))
         .route(
             "/conversion/uploads",
@@ -865,6 +921,15 @@ fn v1_api_router() -> Router<Arc<AppState>> {
             "/profiles/{id}",
             put(update_profile_handler).delete(delete_profile_handler),
         )
+        .route(
+            "/profile-rules",
+            get(list_profile_rules_handler).post(create_profile_rule_handler),
+        )
+        .route("/profile-rules/test", post(test_profile_rules_handler))
+        .route(
+            "/profile-rules/{id}",
+            put(update_profile_rule_handler).delete(delete_profile_rule_handler),
+        )
         .route(
             "/settings/notifications",
             get(get_notifications_handler)
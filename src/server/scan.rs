@@ -4,6 +4,7 @@ use super::{
     AppState, api_accepted_response, api_error_response, api_ok_response, is_row_not_found,
     refresh_file_watcher, save_config_or_response,
 };
+use crate::media::profile_rules::{RuleAction, RuleConditions};
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
@@ -267,7 +268,6 @@ pub(crate) async fn preview_library_path_handler(
     let hw_info = state.hardware_state.snapshot().await;
     let planner = crate::media::planner::BasicPlanner::new(config_snapshot, hw_info);
     let analyzer = crate::media::analyzer::FfmpegAnalyzer;
-    use crate::media::pipeline::Planner as _;
 
     let mut counts = LibraryPreviewCounts::default();
     let mut bytes = LibraryPreviewBytes::default();
@@ -311,15 +311,12 @@ pub(crate) async fn preview_library_path_handler(
 
         let output_path =
             file_settings.output_path_for_source(path, discovered.source_root.as_deref());
-        let profile = state
-            .db
-            .get_profile_for_path(&path.to_string_lossy())
+        let selection = crate::media::profile_rules::select_profile(&state.db, &analysis)
             .await
-            .ok()
-            .flatten();
+            .unwrap_or_default();
 
         let plan = match planner
-            .plan(&analysis, &output_path, profile.as_ref())
+            .plan_with_selection(&analysis, &output_path, &selection)
             .await
         {
             Ok(p) => p,
@@ -598,6 +595,10 @@ pub(crate) async fn rescan_library_health_issue_handler(
 pub(crate) struct AddWatchDirPayload {
     path: String,
     is_recursive: Option<bool>,
+    #[serde(default)]
+    exclude_globs: Vec<String>,
+    #[serde(default)]
+    watch_mode: crate::config::WatchMode,
 }
 
 pub(crate) async fn get_watch_dirs_handler(
@@ -643,6 +644,13 @@ pub(crate) async fn add_watch_dir_handler(
         .push(crate::config::WatchDirConfig {
             path: normalized_path.clone(),
             is_recursive: payload.is_recursive.unwrap_or(true),
+            exclude_globs: payload
+                .exclude_globs
+                .iter()
+                .map(|glob| glob.trim().to_string())
+                .filter(|glob| !glob.is_empty())
+                .collect(),
+            watch_mode: payload.watch_mode,
         });
     if let Err(response) = save_config_or_response(&state, &next_config).await {
         return *response;
@@ -752,6 +760,12 @@ struct LibraryProfileResponse {
     hdr_mode: String,
     audio_mode: String,
     crf_override: Option<i32>,
+    target_vmaf: Option<f64>,
+    auto_crop: bool,
+    max_resolution: Option<String>,
+    dolby_vision_mode: String,
+    loudness_target_lufs: Option<f64>,
+    output_variants: Vec<crate::media::variants::OutputVariant>,
     notes: Option<String>,
     created_at: chrono::DateTime<Utc>,
     updated_at: chrono::DateTime<Utc>,
@@ -767,6 +781,18 @@ pub(crate) struct LibraryProfilePayload {
     hdr_mode: String,
     audio_mode: String,
     crf_override: Option<i32>,
+    #[serde(default)]
+    target_vmaf: Option<f64>,
+    #[serde(default)]
+    auto_crop: bool,
+    #[serde(default)]
+    max_resolution: Option<String>,
+    #[serde(default)]
+    dolby_vision_mode: Option<String>,
+    #[serde(default)]
+    loudness_target_lufs: Option<f64>,
+    #[serde(default)]
+    output_variants: Vec<crate::media::variants::OutputVariant>,
     notes: Option<String>,
 }
 
@@ -782,6 +808,7 @@ fn is_builtin_profile_id(id: i64) -> bool {
 }
 
 fn library_profile_response(profile: crate::db::LibraryProfile) -> LibraryProfileResponse {
+    let output_variants = profile.output_variants();
     LibraryProfileResponse {
         id: profile.id,
         name: profile.name,
@@ -791,6 +818,12 @@ fn library_profile_response(profile: crate::db::LibraryProfile) -> LibraryProfil
         hdr_mode: profile.hdr_mode,
         audio_mode: profile.audio_mode,
         crf_override: profile.crf_override,
+        target_vmaf: profile.target_vmaf,
+        auto_crop: profile.auto_crop,
+        max_resolution: profile.max_resolution,
+        dolby_vision_mode: profile.dolby_vision_mode,
+        loudness_target_lufs: profile.loudness_target_lufs,
+        output_variants,
         notes: profile.notes,
         created_at: profile.created_at,
         updated_at: profile.updated_at,
@@ -800,25 +833,52 @@ fn library_profile_response(profile: crate::db::LibraryProfile) -> LibraryProfil
 
 fn validate_library_profile_payload(
     payload: &LibraryProfilePayload,
-) -> std::result::Result<(), &'static str> {
+) -> std::result::Result<(), String> {
     if payload.name.trim().is_empty() {
-        return Err("name must not be empty");
+        return Err("name must not be empty".into());
     }
     if payload.preset.trim().is_empty() {
-        return Err("preset must not be empty");
+        return Err("preset must not be empty".into());
     }
     if payload.codec.trim().is_empty() {
-        return Err("codec must not be empty");
+        return Err("codec must not be empty".into());
     }
     if payload.quality_profile.trim().is_empty() {
-        return Err("quality_profile must not be empty");
+        return Err("quality_profile must not be empty".into());
     }
     if payload.hdr_mode.trim().is_empty() {
-        return Err("hdr_mode must not be empty");
+        return Err("hdr_mode must not be empty".into());
     }
     if payload.audio_mode.trim().is_empty() {
-        return Err("audio_mode must not be empty");
+        return Err("audio_mode must not be empty".into());
+    }
+    if payload
+        .target_vmaf
+        .is_some_and(|target| !target.is_finite() || target <= 0.0 || target > 100.0)
+    {
+        return Err("target_vmaf must be between 0 and 100".into());
+    }
+    if payload
+        .max_resolution
+        .as_deref()
+        .is_some_and(|value| crate::config::MaxResolution::parse(value).is_none())
+    {
+        return Err("max_resolution must be one of 480p, 720p, 1080p, 1440p, 2160p".into());
+    }
+    if payload
+        .dolby_vision_mode
+        .as_deref()
+        .is_some_and(|value| crate::config::DolbyVisionMode::parse(value).is_none())
+    {
+        return Err("dolby_vision_mode must be one of skip, strip, preserve".into());
+    }
+    if payload
+        .loudness_target_lufs
+        .is_some_and(|target| !target.is_finite() || !(-70.0..=-5.0).contains(&target))
+    {
+        return Err("loudness_target_lufs must be between -70 and -5".into());
     }
+    crate::media::variants::validate_variants(&payload.output_variants)?;
     Ok(())
 }
 
@@ -831,6 +891,34 @@ fn to_new_library_profile(payload: LibraryProfilePayload) -> crate::db::NewLibra
         hdr_mode: payload.hdr_mode.trim().to_ascii_lowercase(),
         audio_mode: payload.audio_mode.trim().to_ascii_lowercase(),
         crf_override: payload.crf_override,
+        target_vmaf: payload.target_vmaf,
+        auto_crop: payload.auto_crop,
+        max_resolution: payload
+            .max_resolution
+            .as_deref()
+            .and_then(crate::config::MaxResolution::parse)
+            .map(|resolution| resolution.as_str().to_string()),
+        dolby_vision_mode: payload
+            .dolby_vision_mode
+            .as_deref()
+            .and_then(crate::config::DolbyVisionMode::parse)
+            .unwrap_or_default()
+            .as_str()
+            .to_string(),
+        loudness_target_lufs: payload.loudness_target_lufs,
+        output_variants_json: serde_json::to_string(
+            &payload
+                .output_variants
+                .into_iter()
+                .map(|variant| crate::media::variants::OutputVariant {
+                    name: variant.name.trim().to_string(),
+                    container: variant.container.trim().to_ascii_lowercase(),
+                    output_root: variant.output_root.trim().to_string(),
+                    ..variant
+                })
+                .collect::<Vec<_>>(),
+        )
+        .unwrap_or_else(|_| "[]".to_string()),
         notes: payload
             .notes
             .map(|notes| notes.trim().to_string())
@@ -868,6 +956,12 @@ pub(crate) async fn get_profile_presets_handler() -> impl IntoResponse {
                 "hdr_mode": preset.hdr_mode.as_str(),
                 "audio_mode": preset.audio_mode.as_str(),
                 "crf_override": preset.crf_override,
+                "target_vmaf": null,
+                "auto_crop": false,
+                "max_resolution": null,
+                "dolby_vision_mode": crate::config::DolbyVisionMode::default().as_str(),
+                "loudness_target_lufs": null,
+                "output_variants": [],
                 "notes": preset.notes,
                 "builtin": true
             })
@@ -974,6 +1068,24 @@ pub(crate) async fn delete_profile_handler(
         );
     }
 
+    match state.db.count_profile_rules_using_profile(id).await {
+        Ok(count) if count > 0 => {
+            return api_error_response(
+                StatusCode::CONFLICT,
+                "PROFILE_IN_USE",
+                "Profile is still used by one or more profile rules",
+            );
+        }
+        Ok(_) => {}
+        Err(err) => {
+            return api_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "COUNT_PROFILE_USAGE_FAILED",
+                err.to_string(),
+            );
+        }
+    }
+
     match state.db.count_watch_dirs_using_profile(id).await {
         Ok(count) if count > 0 => api_error_response(
             StatusCode::CONFLICT,
@@ -1001,6 +1113,334 @@ pub(crate) async fn delete_profile_handler(
     }
 }
 
+// Profile rule handlers
+
+#[derive(Serialize)]
+struct ProfileRuleResponse {
+    id: i64,
+    name: String,
+    position: i64,
+    enabled: bool,
+    conditions: serde_json::Value,
+    action: String,
+    profile_id: Option<i64>,
+    created_at: chrono::DateTime<Utc>,
+    updated_at: chrono::DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ProfileRulePayload {
+    name: String,
+    #[serde(default)]
+    position: i64,
+    #[serde(default = "default_rule_enabled")]
+    enabled: bool,
+    #[serde(default)]
+    conditions: RuleConditions,
+    action: String,
+    #[serde(default)]
+    profile_id: Option<i64>,
+}
+
+fn default_rule_enabled() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ProfileRuleTestPayload {
+    path: String,
+}
+
+#[derive(Serialize)]
+struct ProfileRuleEvaluation {
+    id: i64,
+    name: String,
+    enabled: bool,
+    matched: bool,
+}
+
+#[derive(Serialize)]
+struct ProfileRuleTestResponse {
+    path: String,
+    /// `rule`, `watch_folder`, or `default` when no profile applies.
+    source: &'static str,
+    /// `skip` when the matching rule skips the file, otherwise `profile`.
+    action: &'static str,
+    matched_rule: Option<ProfileRuleResponse>,
+    profile: Option<LibraryProfileResponse>,
+    rules: Vec<ProfileRuleEvaluation>,
+}
+
+fn profile_rule_response(rule: crate::db::ProfileRule) -> ProfileRuleResponse {
+    ProfileRuleResponse {
+        conditions: serde_json::from_str(&rule.conditions_json).unwrap_or(serde_json::Value::Null),
+        id: rule.id,
+        name: rule.name,
+        position: rule.position,
+        enabled: rule.enabled,
+        action: rule.action,
+        profile_id: rule.profile_id,
+        created_at: rule.created_at,
+        updated_at: rule.updated_at,
+    }
+}
+
+/// Validate the payload and resolve it into a row. Profile rules must name a
+/// profile that exists; skip rules never carry one.
+async fn to_new_profile_rule(
+    state: &AppState,
+    payload: ProfileRulePayload,
+) -> std::result::Result<crate::db::NewProfileRule, axum::response::Response> {
+    let invalid = |message: &str| {
+        api_error_response(StatusCode::BAD_REQUEST, "INVALID_PROFILE_RULE", message)
+    };
+    if payload.name.trim().is_empty() {
+        return Err(invalid("name must not be empty"));
+    }
+    let Some(action) = RuleAction::parse(&payload.action) else {
+        return Err(invalid("action must be one of profile, skip"));
+    };
+    if let Err(message) = payload.conditions.validate() {
+        return Err(invalid(message));
+    }
+    let profile_id = match action {
+        RuleAction::Skip => None,
+        RuleAction::Profile => {
+            let Some(profile_id) = payload.profile_id else {
+                return Err(invalid("profile_id is required when action is profile"));
+            };
+            match state.db.get_profile(profile_id).await {
+                Ok(Some(_)) => Some(profile_id),
+                Ok(None) => {
+                    return Err(api_error_response(
+                        StatusCode::NOT_FOUND,
+                        "PROFILE_NOT_FOUND",
+                        "Profile not found",
+                    ));
+                }
+                Err(err) => {
+                    return Err(api_error_response(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "GET_PROFILE_FAILED",
+                        err.to_string(),
+                    ));
+                }
+            }
+        }
+    };
+
+    Ok(crate::db::NewProfileRule {
+        name: payload.name.trim().to_string(),
+        position: payload.position,
+        enabled: payload.enabled,
+        conditions_json: payload.conditions.to_json(),
+        action: action.as_str().to_string(),
+        profile_id,
+    })
+}
+
+pub(crate) async fn list_profile_rules_handler(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    match state.db.get_profile_rules().await {
+        Ok(rules) => axum::Json(
+            rules
+                .into_iter()
+                .map(profile_rule_response)
+                .collect::<Vec<_>>(),
+        )
+        .into_response(),
+        Err(err) => api_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "GET_PROFILE_RULES_FAILED",
+            err.to_string(),
+        ),
+    }
+}
+
+pub(crate) async fn create_profile_rule_handler(
+    State(state): State<Arc<AppState>>,
+    axum::Json(payload): axum::Json<ProfileRulePayload>,
+) -> impl IntoResponse {
+    let new_rule = match to_new_profile_rule(&state, payload).await {
+        Ok(rule) => rule,
+        Err(response) => return response,
+    };
+    let id = match state.db.create_profile_rule(new_rule).await {
+        Ok(id) => id,
+        Err(err) => {
+            return api_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "CREATE_PROFILE_RULE_FAILED",
+                err.to_string(),
+            );
+        }
+    };
+
+    match state.db.get_profile_rule(id).await {
+        Ok(Some(rule)) => {
+            (StatusCode::CREATED, axum::Json(profile_rule_response(rule))).into_response()
+        }
+        Ok(None) => (
+            StatusCode::CREATED,
+            axum::Json(serde_json::json!({ "ok": true, "id": id })),
+        )
+            .into_response(),
+        Err(err) => api_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "GET_PROFILE_RULE_FAILED",
+            err.to_string(),
+        ),
+    }
+}
+
+pub(crate) async fn update_profile_rule_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    axum::Json(payload): axum::Json<ProfileRulePayload>,
+) -> impl IntoResponse {
+    let new_rule = match to_new_profile_rule(&state, payload).await {
+        Ok(rule) => rule,
+        Err(response) => return response,
+    };
+
+    match state.db.update_profile_rule(id, new_rule).await {
+        Ok(_) => match state.db.get_profile_rule(id).await {
+            Ok(Some(rule)) => axum::Json(profile_rule_response(rule)).into_response(),
+            Ok(None) => api_error_response(
+                StatusCode::NOT_FOUND,
+                "PROFILE_RULE_NOT_FOUND",
+                "Profile rule not found",
+            ),
+            Err(err) => api_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "GET_PROFILE_RULE_FAILED",
+                err.to_string(),
+            ),
+        },
+        Err(err) if is_row_not_found(&err) => api_error_response(
+            StatusCode::NOT_FOUND,
+            "PROFILE_RULE_NOT_FOUND",
+            "Profile rule not found",
+        ),
+        Err(err) => api_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "UPDATE_PROFILE_RULE_FAILED",
+            err.to_string(),
+        ),
+    }
+}
+
+pub(crate) async fn delete_profile_rule_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match state.db.delete_profile_rule(id).await {
+        Ok(_) => api_ok_response(),
+        Err(err) if is_row_not_found(&err) => api_error_response(
+            StatusCode::NOT_FOUND,
+            "PROFILE_RULE_NOT_FOUND",
+            "Profile rule not found",
+        ),
+        Err(err) => api_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "DELETE_PROFILE_RULE_FAILED",
+            err.to_string(),
+        ),
+    }
+}
+
+/// Analyze one file and report which rule, and which profile, would apply.
+pub(crate) async fn test_profile_rules_handler(
+    State(state): State<Arc<AppState>>,
+    axum::Json(payload): axum::Json<ProfileRuleTestPayload>,
+) -> impl IntoResponse {
+    let path = std::path::PathBuf::from(payload.path.trim());
+    // Check the root first so the response never reveals whether a file
+    // outside the library exists.
+    if !preview_path_is_within_allowed_root(&state, &path).await {
+        return api_error_response(
+            StatusCode::FORBIDDEN,
+            "RULE_TEST_PATH_FORBIDDEN",
+            "Path must be within a configured library folder or watch directory.",
+        );
+    }
+    if !path.is_file() {
+        return api_error_response(
+            StatusCode::BAD_REQUEST,
+            "RULE_TEST_PATH_MISSING",
+            format!("File does not exist: {}", payload.path),
+        );
+    }
+
+    let analyzer = crate::media::analyzer::FfmpegAnalyzer;
+    let analysis = match analyzer.analyze_with_cache(&state.db, &path).await {
+        Ok(analysis) => analysis,
+        Err(err) => {
+            return api_error_response(
+                StatusCode::BAD_REQUEST,
+                "RULE_TEST_ANALYSIS_FAILED",
+                err.to_string(),
+            );
+        }
+    };
+
+    let rules = match state.db.get_profile_rules().await {
+        Ok(rules) => rules,
+        Err(err) => {
+            return api_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "GET_PROFILE_RULES_FAILED",
+                err.to_string(),
+            );
+        }
+    };
+    let selection = match crate::media::profile_rules::select_profile(&state.db, &analysis).await {
+        Ok(selection) => selection,
+        Err(err) => {
+            return api_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "GET_PROFILE_FAILED",
+                err.to_string(),
+            );
+        }
+    };
+
+    let evaluations = rules
+        .into_iter()
+        .map(|rule| ProfileRuleEvaluation {
+            matched: crate::media::profile_rules::first_matching_rule(
+                std::slice::from_ref(&rule),
+                &analysis,
+            )
+            .is_some(),
+            id: rule.id,
+            name: rule.name,
+            enabled: rule.enabled,
+        })
+        .collect();
+    let source = match (&selection.rule, &selection.profile) {
+        (Some(_), _) => "rule",
+        (None, Some(_)) => "watch_folder",
+        (None, None) => "default",
+    };
+    let action = if selection.skip_reason().is_some() {
+        "skip"
+    } else {
+        "profile"
+    };
+
+    axum::Json(ProfileRuleTestResponse {
+        path: path.display().to_string(),
+        source,
+        action,
+        matched_rule: selection.rule.map(profile_rule_response),
+        profile: selection.profile.map(library_profile_response),
+        rules: evaluations,
+    })
+    .into_response()
+}
+
 pub(crate) async fn assign_watch_dir_profile_handler(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
@@ -44,9 +44,19 @@ pub(crate) struct TranscodeSettingsPayload {
     #[serde(default = "crate::config::default_tonemap_desat")]
     pub(crate) tonemap_desat: f32,
     #[serde(default)]
+    pub(crate) deinterlace_mode: crate::config::DeinterlaceMode,
+    #[serde(default)]
+    pub(crate) deinterlace_algorithm: crate::config::DeinterlaceAlgorithm,
+    #[serde(default)]
+    pub(crate) deinterlace_rate: crate::config::DeinterlaceRate,
+    #[serde(default)]
     pub(crate) subtitle_mode: crate::config::SubtitleMode,
     #[serde(default)]
     pub(crate) stream_rules: crate::config::StreamRules,
+    #[serde(default)]
+    pub(crate) queue_order: crate::config::QueueOrder,
+    #[serde(default)]
+    pub(crate) reprocess_on_settings_change: bool,
 }
 
 pub(crate) async fn get_transcode_settings_handler(
@@ -66,8 +76,13 @@ pub(crate) async fn get_transcode_settings_handler(
         tonemap_algorithm: config.transcode.tonemap_algorithm,
         tonemap_peak: config.transcode.tonemap_peak,
         tonemap_desat: config.transcode.tonemap_desat,
+        deinterlace_mode: config.transcode.deinterlace_mode,
+        deinterlace_algorithm: config.transcode.deinterlace_algorithm,
+        deinterlace_rate: config.transcode.deinterlace_rate,
         subtitle_mode: config.transcode.subtitle_mode,
         stream_rules: config.transcode.stream_rules.clone(),
+        queue_order: config.transcode.queue_order,
+        reprocess_on_settings_change: config.transcode.reprocess_on_settings_change,
     })
 }
 
@@ -93,8 +108,13 @@ pub(crate) async fn update_transcode_settings_handler(
     next_config.transcode.tonemap_algorithm = payload.tonemap_algorithm;
     next_config.transcode.tonemap_peak = payload.tonemap_peak;
     next_config.transcode.tonemap_desat = payload.tonemap_desat;
+    next_config.transcode.deinterlace_mode = payload.deinterlace_mode;
+    next_config.transcode.deinterlace_algorithm = payload.deinterlace_algorithm;
+    next_config.transcode.deinterlace_rate = payload.deinterlace_rate;
     next_config.transcode.subtitle_mode = payload.subtitle_mode;
     next_config.transcode.stream_rules = payload.stream_rules.clone();
+    next_config.transcode.queue_order = payload.queue_order;
+    next_config.transcode.reprocess_on_settings_change = payload.reprocess_on_settings_change;
 
     if let Err(e) = next_config.validate() {
         return api_error_response(
@@ -460,7 +480,12 @@ pub(crate) struct ConfigValidationResponse {
 
 fn config_validation_warnings(config: &Config) -> Vec<String> {
     let mut warnings = Vec::new();
-    if config.files.delete_source {
+    if config.files.delete_source && config.files.recycle_bin.enabled {
+        warnings.push(
+            "delete_source is enabled; originals are moved to the recycle bin and purged by its retention policy."
+                .to_string(),
+        );
+    } else if config.files.delete_source {
         warnings.push(
             "delete_source is enabled; successful future transcodes can remove originals."
                 .to_string(),
@@ -1060,6 +1085,14 @@ pub(crate) struct AddSchedulePayload {
     end_time: String,
     days_of_week: Vec<i32>,
     enabled: bool,
+    #[serde(default)]
+    engine_mode: Option<crate::config::EngineMode>,
+    #[serde(default)]
+    concurrent_jobs: Option<usize>,
+    #[serde(default)]
+    allowed_backends: Vec<crate::media::pipeline::EncoderBackend>,
+    #[serde(default)]
+    priority: i32,
 }
 
 pub(crate) async fn add_schedule_handler(
@@ -1107,6 +1140,10 @@ pub(crate) async fn add_schedule_handler(
             end_time: end_time.clone(),
             days_of_week: payload.days_of_week.clone(),
             enabled: payload.enabled,
+            engine_mode: payload.engine_mode,
+            concurrent_jobs: payload.concurrent_jobs,
+            allowed_backends: payload.allowed_backends.clone(),
+            priority: payload.priority,
         });
 
     if let Err(e) = next_config.validate() {
@@ -1190,6 +1227,9 @@ pub(crate) async fn get_file_settings_handler(
         "output_suffix": config.files.output_suffix,
         "replace_strategy": config.files.replace_strategy,
         "output_root": config.files.output_root,
+        "output_template": config.files.output_template,
+        "recycle_bin": config.files.recycle_bin,
+        "carry_sidecars": config.files.carry_sidecars,
     }))
     .into_response()
 }
@@ -1202,6 +1242,16 @@ pub(crate) struct UpdateFileSettingsPayload {
     replace_strategy: String,
     #[serde(default)]
     output_root: Option<String>,
+    /// Omitted by older clients; the current template is kept. An empty
+    /// string clears it.
+    #[serde(default)]
+    output_template: Option<String>,
+    /// Omitted by older clients; the current recycle bin settings are kept.
+    #[serde(default)]
+    recycle_bin: Option<crate::config::RecycleBinConfig>,
+    /// Omitted by older clients; the current setting is kept.
+    #[serde(default)]
+    carry_sidecars: Option<bool>,
 }
 
 pub(crate) async fn update_file_settings_handler(
@@ -1225,12 +1275,38 @@ pub(crate) async fn update_file_settings_handler(
             }
         };
 
+    let mut recycle_bin = payload.recycle_bin.clone();
+    if let Some(recycle_bin) = recycle_bin.as_mut() {
+        recycle_bin.directory = match normalize_optional_directory(
+            recycle_bin.directory.as_deref(),
+            "recycle_bin.directory",
+        ) {
+            Ok(value) => value,
+            Err(msg) => {
+                return api_error_response(StatusCode::BAD_REQUEST, "FILE_SETTINGS_INVALID", msg);
+            }
+        };
+    }
+
     let mut next_config = state.config.read().await.clone();
     next_config.files.delete_source = payload.delete_source;
     next_config.files.output_extension = payload.output_extension.clone();
     next_config.files.output_suffix = payload.output_suffix.clone();
     next_config.files.replace_strategy = payload.replace_strategy.clone();
     next_config.files.output_root = output_root.clone();
+    if let Some(template) = &payload.output_template {
+        let template = template.trim();
+        next_config.files.output_template = (!template.is_empty()).then(|| template.to_string());
+    }
+    let output_template = next_config.files.output_template.clone();
+    if let Some(recycle_bin) = recycle_bin {
+        next_config.files.recycle_bin = recycle_bin;
+    }
+    let recycle_bin = next_config.files.recycle_bin.clone();
+    if let Some(carry_sidecars) = payload.carry_sidecars {
+        next_config.files.carry_sidecars = carry_sidecars;
+    }
+    let carry_sidecars = next_config.files.carry_sidecars;
 
     if let Err(e) = next_config.validate() {
         return api_error_response(
@@ -1253,6 +1329,9 @@ pub(crate) async fn update_file_settings_handler(
         "output_suffix": payload.output_suffix,
         "replace_strategy": payload.replace_strategy,
         "output_root": output_root,
+        "output_template": output_template,
+        "recycle_bin": recycle_bin,
+        "carry_sidecars": carry_sidecars,
     }))
     .into_response()
 }
@@ -127,6 +127,19 @@ pub(crate) fn sse_message_for_system_event(event: &SystemEvent) -> SseMessage {
             event_name: "disk_space_low",
             data: serde_json::json!({ "reason": reason }).to_string(),
         },
+        SystemEvent::SpaceGoalCompleted {
+            goal_id,
+            outcome,
+            message,
+        } => SseMessage {
+            event_name: "space_goal_completed",
+            data: serde_json::json!({
+                "goal_id": goal_id,
+                "outcome": outcome,
+                "message": message,
+            })
+            .to_string(),
+        },
     }
 }
 
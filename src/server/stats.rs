@@ -1,10 +1,13 @@
 //! Statistics and savings dashboard handlers.
 
-use super::{AppState, api_error_response, config_read_error_response};
-use crate::db::Db;
+use super::{
+    AppState, api_error_response, api_ok_response, config_read_error_response, is_row_not_found,
+};
+use crate::db::{Db, NewSpaceGoal, SpaceGoal};
 use crate::error::Result;
+use crate::system::space_goals::{GoalProgress, goal_progress};
 use axum::{
-    extract::State,
+    extract::{Path, State},
     http::{HeaderMap, HeaderValue, StatusCode, header},
     response::{IntoResponse, Response},
 };
@@ -118,6 +121,166 @@ pub(crate) async fn savings_summary_handler(
     }
 }
 
+#[derive(serde::Serialize)]
+struct SpaceGoalResponse {
+    #[serde(flatten)]
+    goal: SpaceGoal,
+    #[serde(flatten)]
+    progress: Option<GoalProgress>,
+}
+
+async fn space_goal_response(db: &Db, goal: SpaceGoal) -> SpaceGoalResponse {
+    // Finished goals keep their record; only active ones are measured.
+    let progress = if goal.status == "active" {
+        goal_progress(db, &goal).await.ok()
+    } else {
+        None
+    };
+    SpaceGoalResponse { goal, progress }
+}
+
+pub(crate) async fn list_space_goals_handler(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let goals = match state.db.get_space_goals().await {
+        Ok(goals) => goals,
+        Err(err) => return config_read_error_response("load space goals", &err),
+    };
+    let mut items = Vec::with_capacity(goals.len());
+    for goal in goals {
+        items.push(space_goal_response(state.db.as_ref(), goal).await);
+    }
+    axum::Json(serde_json::json!({ "goals": items })).into_response()
+}
+
+fn default_drain_when_met() -> bool {
+    true
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct CreateSpaceGoalPayload {
+    path: String,
+    /// `free` or `reclaimed`.
+    kind: String,
+    target_gib: f64,
+    #[serde(default)]
+    deadline: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default = "default_drain_when_met")]
+    drain_when_met: bool,
+}
+
+pub(crate) async fn create_space_goal_handler(
+    State(state): State<Arc<AppState>>,
+    axum::Json(payload): axum::Json<CreateSpaceGoalPayload>,
+) -> impl IntoResponse {
+    if !matches!(payload.kind.as_str(), "free" | "reclaimed") {
+        return api_error_response(
+            StatusCode::BAD_REQUEST,
+            "SPACE_GOAL_INVALID",
+            "kind must be free or reclaimed",
+        );
+    }
+    if !payload.target_gib.is_finite() || payload.target_gib <= 0.0 {
+        return api_error_response(
+            StatusCode::BAD_REQUEST,
+            "SPACE_GOAL_INVALID",
+            "target_gib must be greater than zero",
+        );
+    }
+    if payload
+        .deadline
+        .is_some_and(|deadline| deadline <= chrono::Utc::now())
+    {
+        return api_error_response(
+            StatusCode::BAD_REQUEST,
+            "SPACE_GOAL_INVALID",
+            "deadline must be in the future",
+        );
+    }
+
+    let path = std::path::PathBuf::from(payload.path.trim());
+    let Some(device_id) = crate::system::device_id::device_id_for_async(&path).await else {
+        return api_error_response(
+            StatusCode::BAD_REQUEST,
+            "SPACE_GOAL_INVALID",
+            format!(
+                "{} does not exist or its filesystem cannot be identified",
+                path.display()
+            ),
+        );
+    };
+
+    match state.db.get_active_space_goal_devices().await {
+        Ok(devices) if devices.contains(&device_id) => {
+            return api_error_response(
+                StatusCode::CONFLICT,
+                "SPACE_GOAL_EXISTS",
+                "An active space goal already covers this filesystem",
+            );
+        }
+        Ok(_) => {}
+        Err(err) => return config_read_error_response("load space goals", &err),
+    }
+
+    let free_path = path.clone();
+    let start_free_bytes = tokio::task::spawn_blocking(move || {
+        crate::system::disk_space::available_bytes_for_path(&free_path)
+    })
+    .await
+    .ok()
+    .flatten()
+    .map(|bytes| bytes as i64);
+
+    let goal = NewSpaceGoal {
+        path: path.to_string_lossy().into_owned(),
+        device_id,
+        target_kind: payload.kind,
+        target_gib: payload.target_gib,
+        deadline: payload.deadline,
+        drain_when_met: payload.drain_when_met,
+        start_free_bytes,
+    };
+    let id = match state.db.create_space_goal(goal).await {
+        Ok(id) => id,
+        Err(err) => {
+            return api_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "SPACE_GOAL_CREATE_FAILED",
+                err.to_string(),
+            );
+        }
+    };
+
+    match state.db.get_space_goal(id).await {
+        Ok(Some(goal)) => (
+            StatusCode::CREATED,
+            axum::Json(space_goal_response(state.db.as_ref(), goal).await),
+        )
+            .into_response(),
+        Ok(None) => api_ok_response(),
+        Err(err) => config_read_error_response("load space goal", &err),
+    }
+}
+
+pub(crate) async fn delete_space_goal_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match state.db.delete_space_goal(id).await {
+        Ok(()) => api_ok_response(),
+        Err(err) if is_row_not_found(&err) => api_error_response(
+            StatusCode::NOT_FOUND,
+            "SPACE_GOAL_NOT_FOUND",
+            "Space goal not found",
+        ),
+        Err(err) => api_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "SPACE_GOAL_DELETE_FAILED",
+            err.to_string(),
+        ),
+    }
+}
+
 pub(crate) async fn skip_reasons_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     match state.db.get_skip_reason_counts().await {
         Ok(counts) => {
@@ -1,7 +1,7 @@
 //! System information, hardware info, resources, health handlers.
 
 use super::{AppState, api_error_response, config_read_error_response};
-use crate::media::pipeline::{Planner as _, TranscodeDecision};
+use crate::media::pipeline::TranscodeDecision;
 use async_compression::tokio::bufread::{GzipDecoder, GzipEncoder};
 use axum::{
     body::Body,
@@ -47,6 +47,8 @@ pub(crate) struct ProcessorStatusResponse {
     draining: bool,
     disk_blocked: bool,
     disk_block_reason: Option<String>,
+    throttled: bool,
+    throttle_reason: Option<String>,
     active_jobs: i64,
     concurrent_limit: usize,
 }
@@ -176,6 +178,7 @@ pub(crate) async fn processor_status_handler(State(state): State<Arc<AppState>>)
     let draining = state.agent.is_draining();
     let disk_blocked = state.agent.is_disk_blocked();
     let disk_block_reason = state.agent.disk_block_reason();
+    let throttle_reason = state.agent.throttle_reason();
     let active_jobs = stats.active;
 
     let (blocked_reason, message) = if manual_paused {
@@ -201,6 +204,11 @@ pub(crate) async fn processor_status_handler(State(state): State<Arc<AppState>>)
                 None => "The engine is holding jobs because free disk space is low.".to_string(),
             },
         )
+    } else if let Some(reason) = &throttle_reason {
+        (
+            Some("throttled"),
+            format!("The engine is throttled: {reason}."),
+        )
     } else if active_jobs >= concurrent_limit as i64 {
         (
             Some("workers_busy"),
@@ -218,6 +226,8 @@ pub(crate) async fn processor_status_handler(State(state): State<Arc<AppState>>)
         draining,
         disk_blocked,
         disk_block_reason,
+        throttled: throttle_reason.is_some(),
+        throttle_reason,
         active_jobs,
         concurrent_limit,
     })
@@ -326,9 +336,9 @@ pub(crate) async fn library_intelligence_handler(State(state): State<Arc<AppStat
             analysis_report: crate::media::pipeline::AnalyzerReport::default(),
         };
 
-        let profile: Option<crate::db::LibraryProfile> =
-            match state.db.get_profile_for_path(&job.input_path).await {
-                Ok(p) => p,
+        let selection =
+            match crate::media::profile_rules::select_profile(&state.db, &analysis).await {
+                Ok(selection) => selection,
                 Err(err) => {
                     error!(
                         "Failed to fetch profile for intelligence recommendation at {}: {}",
@@ -343,10 +353,10 @@ pub(crate) async fn library_intelligence_handler(State(state): State<Arc<AppStat
             };
 
         if let Ok(plan) = planner
-            .plan(
+            .plan_with_selection(
                 &analysis,
                 std::path::Path::new(&job.output_path),
-                profile.as_ref(),
+                &selection,
             )
             .await
             && matches!(plan.decision, TranscodeDecision::Remux { .. })
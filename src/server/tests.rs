@@ -353,8 +353,13 @@ fn sample_transcode_payload() -> TranscodeSettingsPayload {
         tonemap_algorithm: crate::config::TonemapAlgorithm::Hable,
         tonemap_peak: 100.0,
         tonemap_desat: 0.2,
+        deinterlace_mode: crate::config::DeinterlaceMode::Auto,
+        deinterlace_algorithm: crate::config::DeinterlaceAlgorithm::Bwdif,
+        deinterlace_rate: crate::config::DeinterlaceRate::SendFrame,
         subtitle_mode: crate::config::SubtitleMode::Copy,
         stream_rules: crate::config::StreamRules::default(),
+        queue_order: crate::config::QueueOrder::Fifo,
+        reprocess_on_settings_change: false,
     }
 }
 
@@ -384,6 +389,7 @@ fn sample_media_analysis(path: &Path) -> crate::media::pipeline::MediaAnalysis {
             audio_streams: Vec::new(),
             dynamic_range: crate::media::pipeline::DynamicRange::Sdr,
             chapter_count: 0,
+            alchemist_marker: None,
         },
         warnings: Vec::new(),
         confidence: crate::media::pipeline::AnalysisConfidence::High,
@@ -680,6 +686,47 @@ async fn engine_status_endpoint_reports_draining_state()
     Ok(())
 }
 
+#[tokio::test]
+async fn engine_status_endpoint_reports_active_schedule_window()
+-> std::result::Result<(), Box<dyn std::error::Error>> {
+    let (state, app, config_path, db_path) = build_test_app(false, 8, |_| {}).await?;
+    let token = create_session(state.db.as_ref()).await?;
+
+    state
+        .agent
+        .set_active_schedule_window(Some(crate::scheduler::ActiveScheduleWindow {
+            id: 3,
+            start_time: "01:00".to_string(),
+            end_time: "07:00".to_string(),
+            priority: 10,
+            engine_mode: Some(crate::config::EngineMode::Throughput),
+            concurrent_jobs: Some(4),
+            allowed_backends: vec![crate::media::pipeline::EncoderBackend::Nvenc],
+        }));
+
+    let response = app
+        .clone()
+        .oneshot(auth_request(
+            Method::GET,
+            "/api/v1/engine/status",
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let payload: serde_json::Value = serde_json::from_str(&body_text(response).await)?;
+    assert_eq!(payload["schedule_window"]["id"], 3);
+    assert_eq!(payload["schedule_window"]["engine_mode"], "throughput");
+    assert_eq!(payload["schedule_window"]["concurrent_jobs"], 4);
+    assert_eq!(
+        payload["schedule_window"]["allowed_backends"],
+        serde_json::json!(["nvenc"])
+    );
+
+    cleanup_paths(&[config_path, db_path]);
+    Ok(())
+}
+
 #[tokio::test]
 async fn v1_engine_status_alias_uses_existing_handler()
 -> std::result::Result<(), Box<dyn std::error::Error>> {
@@ -800,6 +847,294 @@ async fn v1_job_delete_route_uses_canonical_rest_path()
     Ok(())
 }
 
+#[tokio::test]
+async fn restore_moves_recycled_original_back_and_removes_output()
+-> std::result::Result<(), Box<dyn std::error::Error>> {
+    let (state, app, config_path, db_path) = build_test_app(false, 8, |_| {}).await?;
+    let token = create_session(state.db.as_ref()).await?;
+    let (job, input_path, output_path) = seed_job(state.db.as_ref(), JobState::Completed).await?;
+    std::fs::write(&output_path, b"encoded")?;
+    state
+        .db
+        .save_encode_stats(crate::db::EncodeStatsInput {
+            job_id: job.id,
+            input_size: 2_000,
+            output_size: 1_000,
+            compression_ratio: 0.5,
+            encode_time: 10.0,
+            encode_speed: 1.0,
+            avg_bitrate: 1_000.0,
+            vmaf_score: None,
+            output_codec: Some("hevc".to_string()),
+        })
+        .await?;
+    let recycle_dir = temp_path("alchemist_recycle", "d");
+    let recycle_bin = crate::config::RecycleBinConfig {
+        enabled: true,
+        directory: Some(recycle_dir.to_string_lossy().into_owned()),
+        ..crate::config::RecycleBinConfig::default()
+    };
+    let recycled = crate::media::recycle::recycle_original(
+        state.db.as_ref(),
+        job.id,
+        &input_path,
+        &output_path,
+        &recycle_bin,
+    )
+    .await?;
+    assert!(!input_path.exists());
+    assert!(recycled.exists());
+
+    let restore_uri = format!("/api/v1/jobs/{}/restore", job.id);
+    let response = app
+        .clone()
+        .oneshot(auth_request(
+            Method::POST,
+            &restore_uri,
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let payload: serde_json::Value = serde_json::from_str(&body_text(response).await)?;
+    assert_eq!(
+        payload["restored_path"],
+        input_path.to_string_lossy().as_ref()
+    );
+    assert_eq!(
+        payload["removed_output"],
+        output_path.to_string_lossy().as_ref()
+    );
+    assert_eq!(payload["restored_sidecars"], serde_json::json!([]));
+    assert_eq!(std::fs::read(&input_path)?, b"test");
+    assert!(!output_path.exists());
+    assert!(!recycled.exists());
+    let restored_job = state
+        .db
+        .get_job_by_id(job.id)
+        .await?
+        .ok_or("restored job missing")?;
+    assert_eq!(restored_job.status, JobState::Cancelled);
+    assert!(state.db.get_encode_stats_by_job_id(job.id).await.is_err());
+
+    let response = app
+        .clone()
+        .oneshot(auth_request(
+            Method::POST,
+            &restore_uri,
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    cleanup_paths(&[input_path, output_path, recycle_dir, config_path, db_path]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn retry_now_skips_backoff_and_job_detail_reports_next_retry()
+-> std::result::Result<(), Box<dyn std::error::Error>> {
+    let (state, app, config_path, db_path) = build_test_app(false, 8, |_| {}).await?;
+    let token = create_session(state.db.as_ref()).await?;
+    let (job, input_path, output_path) = seed_job(state.db.as_ref(), JobState::Failed).await?;
+    state
+        .db
+        .schedule_job_retry(
+            job.id,
+            std::time::Duration::from_secs(600),
+            "transient",
+            false,
+        )
+        .await?;
+
+    let response = app
+        .clone()
+        .oneshot(auth_request(
+            Method::GET,
+            &format!("/api/jobs/{}/details", job.id),
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let payload: serde_json::Value = serde_json::from_str(&body_text(response).await)?;
+    assert!(payload["retry"]["next_retry_at"].is_string());
+    assert_eq!(payload["retry"]["last_failure_kind"], "transient");
+
+    let retry_uri = format!("/api/v1/jobs/{}/retry-now", job.id);
+    let response = app
+        .clone()
+        .oneshot(auth_request(
+            Method::POST,
+            &retry_uri,
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        state.db.get_job_retry_state(job.id).await?.next_retry_at,
+        None
+    );
+
+    let response = app
+        .clone()
+        .oneshot(auth_request(
+            Method::POST,
+            &retry_uri,
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    cleanup_paths(&[input_path, output_path, config_path, db_path]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn retry_now_rejects_jobs_not_waiting_for_a_retry()
+-> std::result::Result<(), Box<dyn std::error::Error>> {
+    let (state, app, config_path, db_path) = build_test_app(false, 8, |_| {}).await?;
+    let token = create_session(state.db.as_ref()).await?;
+    let (job, input_path, output_path) = seed_job(state.db.as_ref(), JobState::Queued).await?;
+
+    let response = app
+        .clone()
+        .oneshot(auth_request(
+            Method::GET,
+            &format!("/api/jobs/{}/details", job.id),
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let payload: serde_json::Value = serde_json::from_str(&body_text(response).await)?;
+    assert!(payload["retry"]["next_retry_at"].is_null());
+    assert_eq!(payload["retry"]["retry_on_cpu"], false);
+
+    let response = app
+        .clone()
+        .oneshot(auth_request(
+            Method::POST,
+            &format!("/api/v1/jobs/{}/retry-now", job.id),
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = app
+        .clone()
+        .oneshot(auth_request(
+            Method::POST,
+            "/api/v1/jobs/999999/retry-now",
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    cleanup_paths(&[input_path, output_path, config_path, db_path]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn conclude_job_counts_attempts_from_the_job()
+-> std::result::Result<(), Box<dyn std::error::Error>> {
+    use crate::media::pipeline::JobFailure;
+
+    let (state, _app, config_path, db_path) = build_test_app(false, 8, |config| {
+        config.retry.transient.max_attempts = 2;
+    })
+    .await?;
+    let (job, input_path, output_path) = seed_job(state.db.as_ref(), JobState::Failed).await?;
+    let pipeline = state.agent.pipeline();
+
+    // Each attempt is counted as it starts, whether it runs here or on a
+    // worker, so the first failure leaves one attempt to go.
+    state.db.increment_attempt_count(job.id).await?;
+    state
+        .agent
+        .conclude_job(&pipeline, job.id, Err(JobFailure::Transient))
+        .await?;
+    let retry = state.db.get_job_retry_state(job.id).await?;
+    assert!(retry.next_retry_at.is_some());
+
+    state.db.increment_attempt_count(job.id).await?;
+    state.db.update_job_status(job.id, JobState::Failed).await?;
+    let concluded = state
+        .agent
+        .conclude_job(&pipeline, job.id, Err(JobFailure::Transient))
+        .await;
+    assert!(concluded.is_err());
+    let Some(failed) = state.db.get_job_by_id(job.id).await? else {
+        panic!("expected concluded job");
+    };
+    assert_eq!(failed.status, JobState::Failed);
+
+    cleanup_paths(&[input_path, output_path, config_path, db_path]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn encoder_unavailable_retries_only_when_it_can_move_to_cpu()
+-> std::result::Result<(), Box<dyn std::error::Error>> {
+    use crate::media::pipeline::JobFailure;
+
+    let (state, app, config_path, db_path) = build_test_app(false, 8, |config| {
+        config.hardware.allow_cpu_fallback = false;
+    })
+    .await?;
+    let token = create_session(state.db.as_ref()).await?;
+    let (job, input_path, output_path) = seed_job(state.db.as_ref(), JobState::Failed).await?;
+    state.db.increment_attempt_count(job.id).await?;
+    let details_uri = format!("/api/jobs/{}/details", job.id);
+    let pipeline = state.agent.pipeline();
+
+    let concluded = state
+        .agent
+        .conclude_job(&pipeline, job.id, Err(JobFailure::EncoderUnavailable))
+        .await;
+    assert!(concluded.is_err());
+    let response = app
+        .clone()
+        .oneshot(auth_request(
+            Method::GET,
+            &details_uri,
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    let payload: serde_json::Value = serde_json::from_str(&body_text(response).await)?;
+    assert_eq!(payload["job"]["status"], "failed");
+    assert!(payload["retry"]["next_retry_at"].is_null());
+
+    state.config.write().await.hardware.allow_cpu_fallback = true;
+    state
+        .agent
+        .conclude_job(&pipeline, job.id, Err(JobFailure::EncoderUnavailable))
+        .await?;
+    let response = app
+        .clone()
+        .oneshot(auth_request(
+            Method::GET,
+            &details_uri,
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    let payload: serde_json::Value = serde_json::from_str(&body_text(response).await)?;
+    assert_eq!(payload["job"]["status"], "queued");
+    assert!(payload["retry"]["next_retry_at"].is_string());
+    assert_eq!(payload["retry"]["retry_on_cpu"], true);
+    assert_eq!(payload["retry"]["last_failure_kind"], "encoder_unavailable");
+
+    cleanup_paths(&[input_path, output_path, config_path, db_path]);
+    Ok(())
+}
+
 #[tokio::test]
 async fn processor_status_endpoint_reports_blocking_reason_precedence()
 -> std::result::Result<(), Box<dyn std::error::Error>> {
@@ -820,6 +1155,35 @@ async fn processor_status_endpoint_reports_blocking_reason_precedence()
     let payload: serde_json::Value = serde_json::from_str(&body_text(response).await)?;
     assert_eq!(payload["blocked_reason"], "workers_busy");
 
+    state
+        .agent
+        .set_throttle(Some("Plex Transcoder is running".to_string()));
+    let response = app
+        .clone()
+        .oneshot(auth_request(
+            Method::GET,
+            "/api/processor/status",
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    let payload: serde_json::Value = serde_json::from_str(&body_text(response).await)?;
+    assert_eq!(payload["blocked_reason"], "throttled");
+    assert_eq!(payload["throttle_reason"], "Plex Transcoder is running");
+
+    let response = app
+        .clone()
+        .oneshot(auth_request(
+            Method::GET,
+            "/api/v1/engine/status",
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    let payload: serde_json::Value = serde_json::from_str(&body_text(response).await)?;
+    assert_eq!(payload["throttled"], true);
+    assert_eq!(payload["throttle_reason"], "Plex Transcoder is running");
+
     state.agent.drain();
     let response = app
         .clone()
@@ -1121,138 +1485,612 @@ async fn read_only_api_token_cannot_access_settings_config()
 }
 
 #[tokio::test]
-async fn arr_webhook_api_token_scope_is_limited_to_arr_webhook_route()
+async fn arr_webhook_api_token_scope_is_limited_to_arr_webhook_route()
+-> std::result::Result<(), Box<dyn std::error::Error>> {
+    let (state, app, config_path, db_path) = build_test_app(false, 8, |_| {}).await?;
+    let token = create_api_token(
+        state.db.as_ref(),
+        crate::db::ApiTokenAccessLevel::ArrWebhook,
+    )
+    .await?;
+
+    let webhook_response = app
+        .clone()
+        .oneshot(bearer_json_request(
+            Method::POST,
+            "/api/webhooks/arr",
+            &token,
+            json!({
+                "eventType": "Test"
+            }),
+        ))
+        .await?;
+    assert_eq!(webhook_response.status(), StatusCode::ACCEPTED);
+
+    let blocked_response = app
+        .oneshot(bearer_request(
+            Method::POST,
+            "/api/engine/resume",
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(blocked_response.status(), StatusCode::FORBIDDEN);
+
+    cleanup_paths(&[config_path, db_path]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn jellyfin_api_token_scope_allows_enqueue_events_and_job_details_only()
+-> std::result::Result<(), Box<dyn std::error::Error>> {
+    let media_root = temp_path("alchemist_jellyfin_token_root", "dir");
+    std::fs::create_dir_all(&media_root)?;
+    let input_path = media_root.join("movie.mkv");
+    std::fs::write(&input_path, b"not a real movie")?;
+
+    let (state, app, config_path, db_path) = build_test_app(false, 8, |config| {
+        config.scanner.directories = vec![media_root.to_string_lossy().to_string()];
+    })
+    .await?;
+    let token =
+        create_api_token(state.db.as_ref(), crate::db::ApiTokenAccessLevel::Jellyfin).await?;
+
+    let info_response = app
+        .clone()
+        .oneshot(bearer_request(
+            Method::GET,
+            "/api/v1/system/info",
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(info_response.status(), StatusCode::OK);
+
+    let events_response = app
+        .clone()
+        .oneshot(bearer_request(
+            Method::GET,
+            "/api/v1/events",
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(events_response.status(), StatusCode::OK);
+
+    let enqueue_response = app
+        .clone()
+        .oneshot(bearer_json_request(
+            Method::POST,
+            "/api/v1/jobs/enqueue",
+            &token,
+            json!({
+                "path": input_path.to_string_lossy()
+            }),
+        ))
+        .await?;
+    assert_eq!(enqueue_response.status(), StatusCode::OK);
+
+    let details_response = app
+        .clone()
+        .oneshot(bearer_request(
+            Method::GET,
+            "/api/v1/jobs/999999/details",
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(details_response.status(), StatusCode::NOT_FOUND);
+
+    let settings_response = app
+        .clone()
+        .oneshot(bearer_request(
+            Method::GET,
+            "/api/v1/settings/config",
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(settings_response.status(), StatusCode::FORBIDDEN);
+
+    let engine_response = app
+        .clone()
+        .oneshot(bearer_request(
+            Method::POST,
+            "/api/v1/engine/resume",
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(engine_response.status(), StatusCode::FORBIDDEN);
+
+    let arr_response = app
+        .oneshot(bearer_json_request(
+            Method::POST,
+            "/api/v1/webhooks/arr",
+            &token,
+            json!({
+                "eventType": "Test"
+            }),
+        ))
+        .await?;
+    assert_eq!(arr_response.status(), StatusCode::FORBIDDEN);
+
+    cleanup_paths(&[input_path, media_root, config_path, db_path]);
+    Ok(())
+}
+
+fn worker_register_request(token: &str, name: &str, supported_codecs: &[&str]) -> Request<Body> {
+    bearer_json_request(
+        Method::POST,
+        "/api/v1/workers/register",
+        token,
+        json!({
+            "name": name,
+            "hardware": {
+                "vendor": "cpu",
+                "device_path": null,
+                "supported_codecs": supported_codecs
+            }
+        }),
+    )
+}
+
+async fn register_test_worker(
+    app: &Router,
+    token: &str,
+    name: &str,
+    supported_codecs: &[&str],
+) -> std::result::Result<i64, Box<dyn std::error::Error>> {
+    let register = app
+        .clone()
+        .oneshot(worker_register_request(token, name, supported_codecs))
+        .await?;
+    assert_eq!(register.status(), StatusCode::OK);
+    let registered: serde_json::Value = serde_json::from_str(&body_text(register).await)?;
+    let Some(worker_id) = registered["worker_id"].as_i64() else {
+        panic!("missing worker_id in {registered}");
+    };
+    Ok(worker_id)
+}
+
+async fn lease_status(
+    app: &Router,
+    token: &str,
+    worker_id: i64,
+) -> std::result::Result<StatusCode, Box<dyn std::error::Error>> {
+    let response = app
+        .clone()
+        .oneshot(bearer_request(
+            Method::POST,
+            &format!("/api/v1/workers/{worker_id}/lease"),
+            token,
+            Body::empty(),
+        ))
+        .await?;
+    Ok(response.status())
+}
+
+#[tokio::test]
+async fn worker_tokens_act_only_as_the_worker_they_registered()
+-> std::result::Result<(), Box<dyn std::error::Error>> {
+    let (state, app, config_path, db_path) = build_test_app(false, 8, |_| {}).await?;
+    let garage_token =
+        create_api_token(state.db.as_ref(), crate::db::ApiTokenAccessLevel::Worker).await?;
+    let attic_token =
+        create_api_token(state.db.as_ref(), crate::db::ApiTokenAccessLevel::Worker).await?;
+    let unused_token =
+        create_api_token(state.db.as_ref(), crate::db::ApiTokenAccessLevel::Worker).await?;
+
+    let garage = register_test_worker(&app, &garage_token, "garage-pc", &["hevc"]).await?;
+    // Registering again as itself keeps the binding.
+    assert_eq!(
+        register_test_worker(&app, &garage_token, "garage-pc", &["hevc"]).await?,
+        garage
+    );
+    let attic = register_test_worker(&app, &attic_token, "attic-pc", &["hevc"]).await?;
+
+    // A bound token cannot register another worker, nor claim one bound elsewhere.
+    for (token, name) in [(&garage_token, "attic-pc"), (&attic_token, "garage-pc")] {
+        let response = app
+            .clone()
+            .oneshot(worker_register_request(token, name, &["hevc"]))
+            .await?;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(body_text(response).await.contains("WORKER_TOKEN_MISMATCH"));
+    }
+
+    // Requests for another worker's id are refused.
+    assert_eq!(
+        lease_status(&app, &attic_token, garage).await?,
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        lease_status(&app, &unused_token, garage).await?,
+        StatusCode::FORBIDDEN
+    );
+    let heartbeat = app
+        .clone()
+        .oneshot(bearer_json_request(
+            Method::POST,
+            &format!("/api/v1/workers/{attic}/jobs/1/heartbeat"),
+            &garage_token,
+            json!({ "progress": 10.0 }),
+        ))
+        .await?;
+    assert_eq!(heartbeat.status(), StatusCode::FORBIDDEN);
+
+    assert_eq!(
+        lease_status(&app, &garage_token, garage).await?,
+        StatusCode::NO_CONTENT
+    );
+    assert_eq!(
+        lease_status(&app, &attic_token, attic).await?,
+        StatusCode::NO_CONTENT
+    );
+
+    cleanup_paths(&[config_path, db_path]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn worker_leases_follow_engine_holds_and_the_planned_codec()
+-> std::result::Result<(), Box<dyn std::error::Error>> {
+    let (state, app, config_path, db_path) = build_test_app(false, 8, |config| {
+        config.transcode.output_codec = crate::config::OutputCodec::Hevc;
+    })
+    .await?;
+    let token = create_api_token(state.db.as_ref(), crate::db::ApiTokenAccessLevel::Worker).await?;
+    let (job, input_path, output_path) = seed_job(state.db.as_ref(), JobState::Queued).await?;
+    let analyzer = crate::media::analyzer::FfmpegAnalyzer;
+    analyzer
+        .cache_analysis(
+            state.db.as_ref(),
+            &input_path,
+            &sample_media_analysis(&input_path),
+        )
+        .await?;
+    let worker_id = register_test_worker(&app, &token, "garage-pc", &["hevc", "h264"]).await?;
+
+    // The resource governor holds remote starts as it holds local ones.
+    state.agent.set_throttle(Some("CPU busy".to_string()));
+    assert_eq!(
+        lease_status(&app, &token, worker_id).await?,
+        StatusCode::NO_CONTENT
+    );
+    state.agent.set_throttle(None);
+
+    // A window that allows only NVENC leaves nothing for a CPU worker.
+    state
+        .agent
+        .set_active_schedule_window(Some(crate::scheduler::ActiveScheduleWindow {
+            id: 1,
+            start_time: "01:00".to_string(),
+            end_time: "07:00".to_string(),
+            priority: 10,
+            engine_mode: None,
+            concurrent_jobs: None,
+            allowed_backends: vec![crate::media::pipeline::EncoderBackend::Nvenc],
+        }));
+    assert_eq!(
+        lease_status(&app, &token, worker_id).await?,
+        StatusCode::NO_CONTENT
+    );
+    state.agent.set_active_schedule_window(None);
+
+    // A profile rule picks AV1, which this worker cannot encode.
+    let profile_id = state
+        .db
+        .create_profile(crate::db::NewLibraryProfile {
+            name: "Archive".to_string(),
+            preset: "balanced".to_string(),
+            codec: "av1".to_string(),
+            quality_profile: "balanced".to_string(),
+            hdr_mode: "preserve".to_string(),
+            audio_mode: "copy".to_string(),
+            crf_override: None,
+            target_vmaf: None,
+            auto_crop: false,
+            max_resolution: None,
+            dolby_vision_mode: "strip".to_string(),
+            loudness_target_lufs: None,
+            output_variants_json: "[]".to_string(),
+            notes: None,
+        })
+        .await?;
+    state
+        .db
+        .create_profile_rule(crate::db::NewProfileRule {
+            name: "H.264 to AV1".to_string(),
+            position: 10,
+            enabled: true,
+            conditions_json: json!({ "codecs": ["h264"] }).to_string(),
+            action: "profile".to_string(),
+            profile_id: Some(profile_id),
+        })
+        .await?;
+    assert_eq!(
+        lease_status(&app, &token, worker_id).await?,
+        StatusCode::NO_CONTENT
+    );
+
+    // The job's own codec override wins over the rule's profile.
+    state
+        .db
+        .enqueue_job_with_overrides(
+            &crate::db::PreparedEnqueue {
+                input_path: job.input_path.clone(),
+                output_path: job.output_path.clone(),
+                mtime_hash: "0.0".to_string(),
+                source_device: None,
+            },
+            Some(&crate::media::overrides::JobOverrides {
+                codec: Some(crate::config::OutputCodec::Hevc),
+                ..Default::default()
+            }),
+        )
+        .await?;
+    assert_eq!(lease_status(&app, &token, worker_id).await?, StatusCode::OK);
+
+    // Leased jobs count against the window's concurrency.
+    let (_, second_input, second_output) = seed_job(state.db.as_ref(), JobState::Queued).await?;
+    analyzer
+        .cache_analysis(
+            state.db.as_ref(),
+            &second_input,
+            &sample_media_analysis(&second_input),
+        )
+        .await?;
+    let second_token =
+        create_api_token(state.db.as_ref(), crate::db::ApiTokenAccessLevel::Worker).await?;
+    let second_worker =
+        register_test_worker(&app, &second_token, "attic-pc", &["av1", "hevc"]).await?;
+    state
+        .agent
+        .set_active_schedule_window(Some(crate::scheduler::ActiveScheduleWindow {
+            id: 2,
+            start_time: "01:00".to_string(),
+            end_time: "07:00".to_string(),
+            priority: 10,
+            engine_mode: None,
+            concurrent_jobs: Some(1),
+            allowed_backends: Vec::new(),
+        }));
+    assert_eq!(
+        lease_status(&app, &second_token, second_worker).await?,
+        StatusCode::NO_CONTENT
+    );
+    state.agent.set_active_schedule_window(None);
+    assert_eq!(
+        lease_status(&app, &second_token, second_worker).await?,
+        StatusCode::OK
+    );
+
+    cleanup_paths(&[
+        input_path,
+        output_path,
+        second_input,
+        second_output,
+        config_path,
+        db_path,
+    ]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn jobs_with_a_vmaf_target_are_not_leased()
 -> std::result::Result<(), Box<dyn std::error::Error>> {
     let (state, app, config_path, db_path) = build_test_app(false, 8, |_| {}).await?;
-    let token = create_api_token(
-        state.db.as_ref(),
-        crate::db::ApiTokenAccessLevel::ArrWebhook,
-    )
-    .await?;
-
-    let webhook_response = app
-        .clone()
-        .oneshot(bearer_json_request(
-            Method::POST,
-            "/api/webhooks/arr",
-            &token,
-            json!({
-                "eventType": "Test"
-            }),
-        ))
+    let token = create_api_token(state.db.as_ref(), crate::db::ApiTokenAccessLevel::Worker).await?;
+    let (job, input_path, output_path) = seed_job(state.db.as_ref(), JobState::Queued).await?;
+    crate::media::analyzer::FfmpegAnalyzer
+        .cache_analysis(
+            state.db.as_ref(),
+            &input_path,
+            &sample_media_analysis(&input_path),
+        )
         .await?;
-    assert_eq!(webhook_response.status(), StatusCode::ACCEPTED);
+    let profile_id = state
+        .db
+        .create_profile(crate::db::NewLibraryProfile {
+            name: "Targeted".to_string(),
+            preset: "balanced".to_string(),
+            codec: "hevc".to_string(),
+            quality_profile: "balanced".to_string(),
+            hdr_mode: "preserve".to_string(),
+            audio_mode: "copy".to_string(),
+            crf_override: None,
+            target_vmaf: Some(93.0),
+            auto_crop: false,
+            max_resolution: None,
+            dolby_vision_mode: "strip".to_string(),
+            loudness_target_lufs: None,
+            output_variants_json: "[]".to_string(),
+            notes: None,
+        })
+        .await?;
+    state
+        .db
+        .create_profile_rule(crate::db::NewProfileRule {
+            name: "Everything".to_string(),
+            position: 10,
+            enabled: true,
+            conditions_json: json!({}).to_string(),
+            action: "profile".to_string(),
+            profile_id: Some(profile_id),
+        })
+        .await?;
+    let worker_id = register_test_worker(&app, &token, "garage-pc", &["hevc", "h264"]).await?;
 
-    let blocked_response = app
-        .oneshot(bearer_request(
-            Method::POST,
-            "/api/engine/resume",
-            &token,
-            Body::empty(),
-        ))
+    // The search's probe encodes would run on this instance, not the worker.
+    assert_eq!(
+        lease_status(&app, &token, worker_id).await?,
+        StatusCode::NO_CONTENT
+    );
+
+    // An explicit CRF replaces the search, so the job can go remote.
+    state
+        .db
+        .enqueue_job_with_overrides(
+            &crate::db::PreparedEnqueue {
+                input_path: job.input_path.clone(),
+                output_path: job.output_path.clone(),
+                mtime_hash: "0.0".to_string(),
+                source_device: None,
+            },
+            Some(&crate::media::overrides::JobOverrides {
+                crf: Some(24),
+                ..Default::default()
+            }),
+        )
         .await?;
-    assert_eq!(blocked_response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(lease_status(&app, &token, worker_id).await?, StatusCode::OK);
 
-    cleanup_paths(&[config_path, db_path]);
+    cleanup_paths(&[input_path, output_path, config_path, db_path]);
     Ok(())
 }
 
 #[tokio::test]
-async fn jellyfin_api_token_scope_allows_enqueue_events_and_job_details_only()
+async fn remote_planning_refuses_an_output_path_that_is_the_input()
 -> std::result::Result<(), Box<dyn std::error::Error>> {
-    let media_root = temp_path("alchemist_jellyfin_token_root", "dir");
-    std::fs::create_dir_all(&media_root)?;
-    let input_path = media_root.join("movie.mkv");
-    std::fs::write(&input_path, b"not a real movie")?;
-
     let (state, app, config_path, db_path) = build_test_app(false, 8, |config| {
-        config.scanner.directories = vec![media_root.to_string_lossy().to_string()];
+        config.transcode.output_codec = crate::config::OutputCodec::Hevc;
     })
     .await?;
-    let token =
-        create_api_token(state.db.as_ref(), crate::db::ApiTokenAccessLevel::Jellyfin).await?;
+    let token = create_api_token(state.db.as_ref(), crate::db::ApiTokenAccessLevel::Worker).await?;
+    let (job, input_path, output_path) = seed_job(state.db.as_ref(), JobState::Queued).await?;
+    // Enqueue refuses such a job; an older row may still carry one.
+    sqlx::query("UPDATE jobs SET output_path = input_path WHERE id = ?")
+        .bind(job.id)
+        .execute(&state.db.pool)
+        .await?;
+    let analysis = sample_media_analysis(&input_path);
+    crate::media::analyzer::FfmpegAnalyzer
+        .cache_analysis(state.db.as_ref(), &input_path, &analysis)
+        .await?;
+    let worker_id = register_test_worker(&app, &token, "garage-pc", &["hevc"]).await?;
+    assert_eq!(lease_status(&app, &token, worker_id).await?, StatusCode::OK);
 
-    let info_response = app
+    let response = app
         .clone()
-        .oneshot(bearer_request(
-            Method::GET,
-            "/api/v1/system/info",
+        .oneshot(bearer_json_request(
+            Method::POST,
+            &format!("/api/v1/workers/{worker_id}/jobs/{}/plan", job.id),
             &token,
-            Body::empty(),
+            json!({ "analysis": analysis }),
         ))
         .await?;
-    assert_eq!(info_response.status(), StatusCode::OK);
+    assert_eq!(response.status(), StatusCode::OK);
+    let payload: serde_json::Value = serde_json::from_str(&body_text(response).await)?;
+    assert!(payload["plan"].is_null());
+    let Some(job) = state.db.get_job_by_id(job.id).await? else {
+        panic!("expected planned job");
+    };
+    assert_eq!(job.status, JobState::Skipped);
+    assert_eq!(
+        job.decision_reason.as_deref(),
+        Some("Output path matches input path")
+    );
+    assert!(input_path.exists());
 
-    let events_response = app
+    cleanup_paths(&[input_path, output_path, config_path, db_path]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn worker_token_leases_jobs_and_expired_leases_are_requeued()
+-> std::result::Result<(), Box<dyn std::error::Error>> {
+    let (state, app, config_path, db_path) = build_test_app(false, 8, |_| {}).await?;
+    let token = create_api_token(state.db.as_ref(), crate::db::ApiTokenAccessLevel::Worker).await?;
+    let (job, input_path, output_path) = seed_job(state.db.as_ref(), JobState::Queued).await?;
+    crate::media::analyzer::FfmpegAnalyzer
+        .cache_analysis(
+            state.db.as_ref(),
+            &input_path,
+            &sample_media_analysis(&input_path),
+        )
+        .await?;
+
+    let forbidden = app
         .clone()
         .oneshot(bearer_request(
             Method::GET,
-            "/api/v1/events",
+            "/api/v1/workers",
             &token,
             Body::empty(),
         ))
         .await?;
-    assert_eq!(events_response.status(), StatusCode::OK);
+    assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
 
-    let enqueue_response = app
+    let register = app
         .clone()
         .oneshot(bearer_json_request(
             Method::POST,
-            "/api/v1/jobs/enqueue",
+            "/api/v1/workers/register",
             &token,
             json!({
-                "path": input_path.to_string_lossy()
+                "name": "garage-pc",
+                "hardware": {
+                    "vendor": "cpu",
+                    "device_path": null,
+                    "supported_codecs": ["av1", "hevc", "h264"]
+                }
             }),
         ))
         .await?;
-    assert_eq!(enqueue_response.status(), StatusCode::OK);
-
-    let details_response = app
-        .clone()
-        .oneshot(bearer_request(
-            Method::GET,
-            "/api/v1/jobs/999999/details",
-            &token,
-            Body::empty(),
-        ))
-        .await?;
-    assert_eq!(details_response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(register.status(), StatusCode::OK);
+    let registered: serde_json::Value = serde_json::from_str(&body_text(register).await)?;
+    let Some(worker_id) = registered["worker_id"].as_i64() else {
+        panic!("missing worker_id in {registered}");
+    };
 
-    let settings_response = app
+    let lease = app
         .clone()
         .oneshot(bearer_request(
-            Method::GET,
-            "/api/v1/settings/config",
+            Method::POST,
+            &format!("/api/v1/workers/{worker_id}/lease"),
             &token,
             Body::empty(),
         ))
         .await?;
-    assert_eq!(settings_response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(lease.status(), StatusCode::OK);
+    let leased: serde_json::Value = serde_json::from_str(&body_text(lease).await)?;
+    assert_eq!(leased["job"]["id"].as_i64(), Some(job.id));
 
-    let engine_response = app
+    let heartbeat_uri = format!("/api/v1/workers/{worker_id}/jobs/{}/heartbeat", job.id);
+    let heartbeat = app
         .clone()
-        .oneshot(bearer_request(
+        .oneshot(bearer_json_request(
             Method::POST,
-            "/api/v1/engine/resume",
+            &heartbeat_uri,
             &token,
-            Body::empty(),
+            json!({ "progress": 12.5, "time": "00:01:00", "logs": ["frame=100"] }),
         ))
         .await?;
-    assert_eq!(engine_response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(heartbeat.status(), StatusCode::OK);
+    assert!(body_text(heartbeat).await.contains("\"cancel\":false"));
 
-    let arr_response = app
+    // Simulate the worker going silent past its lease.
+    assert!(state.db.renew_job_lease(job.id, worker_id, 0).await?);
+    let expired = state.db.expire_job_leases().await?;
+    assert_eq!(expired.len(), 1);
+    let Some(requeued) = state.db.get_job_by_id(job.id).await? else {
+        panic!("expected leased job");
+    };
+    assert_eq!(requeued.status, JobState::Queued);
+
+    let lost = app
+        .clone()
         .oneshot(bearer_json_request(
             Method::POST,
-            "/api/v1/webhooks/arr",
+            &heartbeat_uri,
             &token,
-            json!({
-                "eventType": "Test"
-            }),
+            json!({ "progress": 50.0, "time": "00:02:00", "logs": [] }),
         ))
         .await?;
-    assert_eq!(arr_response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(lost.status(), StatusCode::CONFLICT);
 
-    cleanup_paths(&[input_path, media_root, config_path, db_path]);
+    cleanup_paths(&[input_path, output_path, config_path, db_path]);
     Ok(())
 }
 
@@ -1558,6 +2396,10 @@ async fn setup_complete_accepts_nested_settings_payload()
         end_time: "06:00".to_string(),
         days_of_week: vec![1, 2, 3],
         enabled: true,
+        engine_mode: None,
+        concurrent_jobs: None,
+        allowed_backends: Vec::new(),
+        priority: 0,
     }];
 
     let response = app
@@ -2024,6 +2866,7 @@ async fn transcode_settings_round_trip_subtitle_mode()
         strip_audio_by_title: vec!["commentary".to_string()],
         keep_audio_languages: vec!["eng".to_string()],
         keep_only_default_audio: false,
+        ..crate::config::StreamRules::default()
     };
     let response = app
         .clone()
@@ -2111,6 +2954,73 @@ async fn system_settings_round_trip_watch_enabled()
     Ok(())
 }
 
+#[tokio::test]
+async fn file_settings_validate_and_project_output_template()
+-> std::result::Result<(), Box<dyn std::error::Error>> {
+    let (state, app, config_path, db_path) = build_test_app(false, 8, |_| {}).await?;
+    let token = create_session(state.db.as_ref()).await?;
+    let payload = |template: &str| {
+        json!({
+            "delete_source": false,
+            "output_extension": "mkv",
+            "output_suffix": "-alchemist",
+            "replace_strategy": "keep",
+            "output_template": template
+        })
+    };
+
+    let response = app
+        .clone()
+        .oneshot(auth_json_request(
+            Method::POST,
+            "/api/settings/files",
+            &token,
+            payload("{parent}/{stem} [{resolution}].{ext}"),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(
+        body_text(response)
+            .await
+            .contains("unknown output template token")
+    );
+
+    let response = app
+        .clone()
+        .oneshot(auth_json_request(
+            Method::POST,
+            "/api/settings/files",
+            &token,
+            payload("{parent}/{stem} [{codec} {height}p].{ext}"),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let file_settings = state.db.get_file_settings().await?;
+    assert_eq!(
+        file_settings.output_template.as_deref(),
+        Some("{parent}/{stem} [{codec} {height}p].{ext}")
+    );
+    assert_eq!(
+        file_settings.output_path_for(std::path::Path::new("/media/Heat.mp4")),
+        std::path::PathBuf::from("/media/Heat [{codec} {height}p].mkv")
+    );
+
+    let response = app
+        .clone()
+        .oneshot(auth_json_request(
+            Method::POST,
+            "/api/settings/files",
+            &token,
+            payload(""),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(state.db.get_file_settings().await?.output_template, None);
+
+    cleanup_paths(&[config_path, db_path]);
+    Ok(())
+}
+
 #[tokio::test]
 async fn conversion_upload_rejects_oversized_requests_without_persisting_jobs()
 -> std::result::Result<(), Box<dyn std::error::Error>> {
@@ -2683,7 +3593,7 @@ async fn restore_validation_accepts_downloaded_backup_snapshot()
     let payload: serde_json::Value =
         serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await?)?;
     assert_eq!(payload["valid"], true);
-    assert_eq!(payload["schema_version"], "18");
+    assert_eq!(payload["schema_version"], "39");
     assert_eq!(payload["min_compatible_version"], "0.2.5");
     assert_eq!(payload["job_count"], 1);
     assert!(payload["migration_count"].as_i64().unwrap_or(0) > 0);
@@ -2738,6 +3648,8 @@ async fn settings_bundle_put_projects_extended_settings_to_db()
     payload.scanner.extra_watch_dirs = vec![crate::config::WatchDirConfig {
         path: "/tmp/library".to_string(),
         is_recursive: true,
+        exclude_globs: vec!["**/Extras/**".to_string()],
+        watch_mode: crate::config::WatchMode::Poll,
     }];
     payload.files.output_suffix = "-custom".to_string();
     payload.schedule.windows = vec![crate::config::ScheduleWindowConfig {
@@ -2745,6 +3657,10 @@ async fn settings_bundle_put_projects_extended_settings_to_db()
         end_time: "06:00".to_string(),
         days_of_week: vec![1, 2, 3],
         enabled: true,
+        engine_mode: None,
+        concurrent_jobs: None,
+        allowed_backends: Vec::new(),
+        priority: 0,
     }];
     payload.notifications.enabled = true;
     payload.notifications.targets = vec![crate::config::NotificationTargetConfig {
@@ -2771,6 +3687,11 @@ async fn settings_bundle_put_projects_extended_settings_to_db()
     let watch_dirs = state.db.get_watch_dirs().await?;
     assert_eq!(watch_dirs.len(), 1);
     assert_eq!(watch_dirs[0].path, "/tmp/library");
+    assert_eq!(
+        watch_dirs[0].exclude_globs(),
+        vec!["**/Extras/**".to_string()]
+    );
+    assert_eq!(watch_dirs[0].watch_mode(), crate::config::WatchMode::Poll);
 
     let file_settings = state.db.get_file_settings().await?;
     assert_eq!(file_settings.output_suffix, "-custom");
@@ -2908,66 +3829,144 @@ async fn delete_notification_removes_only_one_duplicate_target()
     crate::settings::project_config_to_db(state.db.as_ref(), &projected).await?;
     let token = create_session(state.db.as_ref()).await?;
 
-    let targets = state.db.get_notification_targets().await?;
-    assert_eq!(targets.len(), 2);
+    let targets = state.db.get_notification_targets().await?;
+    assert_eq!(targets.len(), 2);
+
+    let response = app
+        .clone()
+        .oneshot(auth_request(
+            Method::DELETE,
+            &format!("/api/settings/notifications/{}", targets[0].id),
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let persisted = crate::config::Config::load(config_path.as_path())?;
+    assert_eq!(persisted.notifications.targets.len(), 1);
+
+    let stored_targets = state.db.get_notification_targets().await?;
+    assert_eq!(stored_targets.len(), 1);
+
+    cleanup_paths(&[config_path, db_path]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn delete_schedule_removes_only_one_duplicate_window()
+-> std::result::Result<(), Box<dyn std::error::Error>> {
+    let duplicate_window = crate::config::ScheduleWindowConfig {
+        start_time: "22:00".to_string(),
+        end_time: "06:00".to_string(),
+        days_of_week: vec![1, 2, 3],
+        enabled: true,
+        engine_mode: None,
+        concurrent_jobs: None,
+        allowed_backends: Vec::new(),
+        priority: 0,
+    };
+    let (state, app, config_path, db_path) = build_test_app(false, 8, |config| {
+        config.schedule.windows = vec![duplicate_window.clone(), duplicate_window.clone()];
+    })
+    .await?;
+    let projected = state.config.read().await.clone();
+    crate::settings::project_config_to_db(state.db.as_ref(), &projected).await?;
+    let token = create_session(state.db.as_ref()).await?;
+
+    let windows = state.db.get_schedule_windows().await?;
+    assert_eq!(windows.len(), 2);
+
+    let response = app
+        .clone()
+        .oneshot(auth_request(
+            Method::DELETE,
+            &format!("/api/settings/schedule/{}", windows[0].id),
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let persisted = crate::config::Config::load(config_path.as_path())?;
+    assert_eq!(persisted.schedule.windows.len(), 1);
+
+    let stored_windows = state.db.get_schedule_windows().await?;
+    assert_eq!(stored_windows.len(), 1);
+
+    cleanup_paths(&[config_path, db_path]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn space_goals_are_created_listed_and_deleted()
+-> std::result::Result<(), Box<dyn std::error::Error>> {
+    let (state, app, config_path, db_path) = build_test_app(false, 8, |_| {}).await?;
+    let token = create_session(state.db.as_ref()).await?;
+    let path = std::env::temp_dir().to_string_lossy().into_owned();
+
+    let response = app
+        .clone()
+        .oneshot(auth_json_request(
+            Method::POST,
+            "/api/space-goals",
+            &token,
+            json!({ "path": path, "kind": "reclaimed", "target_gib": 500.0 }),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: serde_json::Value = serde_json::from_str(&body_text(response).await)?;
+    assert_eq!(created["status"], "active");
+    assert_eq!(created["drain_when_met"], true);
+    assert_eq!(created["progress_percent"], 0.0);
+    let id = created["id"].as_i64().ok_or("missing goal id")?;
+
+    let response = app
+        .clone()
+        .oneshot(auth_json_request(
+            Method::POST,
+            "/api/space-goals",
+            &token,
+            json!({ "path": path, "kind": "free", "target_gib": 10.0 }),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = app
+        .clone()
+        .oneshot(auth_json_request(
+            Method::POST,
+            "/api/space-goals",
+            &token,
+            json!({ "path": "/nonexistent/alchemist-goal", "kind": "free", "target_gib": 10.0 }),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 
     let response = app
         .clone()
         .oneshot(auth_request(
-            Method::DELETE,
-            &format!("/api/settings/notifications/{}", targets[0].id),
+            Method::GET,
+            "/api/space-goals",
             &token,
             Body::empty(),
         ))
         .await?;
     assert_eq!(response.status(), StatusCode::OK);
-
-    let persisted = crate::config::Config::load(config_path.as_path())?;
-    assert_eq!(persisted.notifications.targets.len(), 1);
-
-    let stored_targets = state.db.get_notification_targets().await?;
-    assert_eq!(stored_targets.len(), 1);
-
-    cleanup_paths(&[config_path, db_path]);
-    Ok(())
-}
-
-#[tokio::test]
-async fn delete_schedule_removes_only_one_duplicate_window()
--> std::result::Result<(), Box<dyn std::error::Error>> {
-    let duplicate_window = crate::config::ScheduleWindowConfig {
-        start_time: "22:00".to_string(),
-        end_time: "06:00".to_string(),
-        days_of_week: vec![1, 2, 3],
-        enabled: true,
-    };
-    let (state, app, config_path, db_path) = build_test_app(false, 8, |config| {
-        config.schedule.windows = vec![duplicate_window.clone(), duplicate_window.clone()];
-    })
-    .await?;
-    let projected = state.config.read().await.clone();
-    crate::settings::project_config_to_db(state.db.as_ref(), &projected).await?;
-    let token = create_session(state.db.as_ref()).await?;
-
-    let windows = state.db.get_schedule_windows().await?;
-    assert_eq!(windows.len(), 2);
+    let listed: serde_json::Value = serde_json::from_str(&body_text(response).await)?;
+    assert_eq!(listed["goals"].as_array().map(Vec::len), Some(1));
 
     let response = app
         .clone()
         .oneshot(auth_request(
             Method::DELETE,
-            &format!("/api/settings/schedule/{}", windows[0].id),
+            &format!("/api/space-goals/{id}"),
             &token,
             Body::empty(),
         ))
         .await?;
     assert_eq!(response.status(), StatusCode::OK);
-
-    let persisted = crate::config::Config::load(config_path.as_path())?;
-    assert_eq!(persisted.schedule.windows.len(), 1);
-
-    let stored_windows = state.db.get_schedule_windows().await?;
-    assert_eq!(stored_windows.len(), 1);
+    assert!(state.db.get_space_goals().await?.is_empty());
 
     cleanup_paths(&[config_path, db_path]);
     Ok(())
@@ -3334,6 +4333,105 @@ async fn enqueue_job_endpoint_accepts_supported_absolute_files()
     Ok(())
 }
 
+#[tokio::test]
+async fn enqueue_job_overrides_are_persisted_and_reported_in_details()
+-> std::result::Result<(), Box<dyn std::error::Error>> {
+    let (state, app, config_path, db_path) = build_test_app(false, 8, |config| {
+        config
+            .scanner
+            .directories
+            .push(std::env::temp_dir().to_string_lossy().to_string());
+    })
+    .await?;
+    let token = create_session(state.db.as_ref()).await?;
+
+    let input_path = temp_path("alchemist_enqueue_overrides", "mkv");
+    std::fs::write(&input_path, b"test")?;
+    let canonical_input = std::fs::canonicalize(&input_path)?;
+    let output_path = canonical_input.with_file_name("alchemist_enqueue_overrides-av1.mkv");
+
+    let invalid = app
+        .clone()
+        .oneshot(auth_json_request(
+            Method::POST,
+            "/api/jobs/enqueue",
+            &token,
+            json!({ "path": input_path.to_string_lossy(), "overrides": { "crf": 99 } }),
+        ))
+        .await?;
+    assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .clone()
+        .oneshot(auth_json_request(
+            Method::POST,
+            "/api/jobs/enqueue",
+            &token,
+            json!({
+                "path": input_path.to_string_lossy(),
+                "overrides": {
+                    "codec": "av1",
+                    "crf": 28,
+                    "stream_rules": { "strip_audio_by_title": ["commentary"] },
+                    "output_path": output_path.to_string_lossy(),
+                    "replace_strategy": "replace",
+                    "delete_source": false
+                }
+            }),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let Some(job) = state
+        .db
+        .get_job_by_input_path(canonical_input.to_string_lossy().as_ref())
+        .await?
+    else {
+        panic!("expected enqueued job");
+    };
+    assert_eq!(job.output_path, output_path.to_string_lossy());
+    let Some(overrides) = state.db.get_job_overrides(job.id).await? else {
+        panic!("expected stored overrides");
+    };
+    assert_eq!(overrides.crf, Some(28));
+
+    let details = app
+        .clone()
+        .oneshot(auth_request(
+            Method::GET,
+            &format!("/api/jobs/{}/details", job.id),
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(details.status(), StatusCode::OK);
+    let details: serde_json::Value = serde_json::from_str(&body_text(details).await)?;
+    let effective = &details["effective_settings"];
+    assert_eq!(effective["codec"], "av1");
+    assert_eq!(effective["crf"], 28);
+    assert_eq!(effective["replace_strategy"], "replace");
+    assert_eq!(
+        effective["stream_rules"]["strip_audio_by_title"],
+        json!(["commentary"])
+    );
+
+    // Enqueuing again without overrides goes back to the default settings.
+    let response = app
+        .clone()
+        .oneshot(auth_json_request(
+            Method::POST,
+            "/api/jobs/enqueue",
+            &token,
+            json!({ "path": input_path.to_string_lossy() }),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(state.db.get_job_overrides(job.id).await?.is_none());
+
+    cleanup_paths(&[input_path, config_path, db_path]);
+    Ok(())
+}
+
 #[tokio::test]
 async fn enqueue_job_endpoint_rejects_relative_paths_and_unsupported_extensions()
 -> std::result::Result<(), Box<dyn std::error::Error>> {
@@ -4065,3 +5163,224 @@ async fn system_selftest_returns_429_when_busy()
     cleanup_paths(&[config_path, db_path]);
     Ok(())
 }
+
+#[tokio::test]
+async fn profile_rules_crud_orders_rules_and_guards_profiles()
+-> std::result::Result<(), Box<dyn std::error::Error>> {
+    let (state, app, config_path, db_path) = build_test_app(false, 8, |_| {}).await?;
+    let token = create_session(state.db.as_ref()).await?;
+    let profile_id = state
+        .db
+        .create_profile(crate::db::NewLibraryProfile {
+            name: "Anime".to_string(),
+            preset: "balanced".to_string(),
+            codec: "av1".to_string(),
+            quality_profile: "balanced".to_string(),
+            hdr_mode: "preserve".to_string(),
+            audio_mode: "copy".to_string(),
+            crf_override: None,
+            target_vmaf: None,
+            auto_crop: false,
+            max_resolution: None,
+            dolby_vision_mode: "strip".to_string(),
+            loudness_target_lufs: None,
+            output_variants_json: "[]".to_string(),
+            notes: None,
+        })
+        .await?;
+
+    let response = app
+        .clone()
+        .oneshot(auth_json_request(
+            Method::POST,
+            "/api/profile-rules",
+            &token,
+            json!({
+                "name": "Anime folders",
+                "position": 20,
+                "conditions": { "path_globs": ["**/Anime/**"] },
+                "action": "profile",
+                "profile_id": profile_id
+            }),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let anime: serde_json::Value = serde_json::from_str(&body_text(response).await)?;
+    assert_eq!(anime["conditions"]["path_globs"][0], "**/Anime/**");
+    assert_eq!(anime["enabled"], true);
+
+    let response = app
+        .clone()
+        .oneshot(auth_json_request(
+            Method::POST,
+            "/api/profile-rules",
+            &token,
+            json!({
+                "name": "Skip samples",
+                "position": 10,
+                "conditions": { "max_size_mb": 50 },
+                "action": "skip",
+                "profile_id": profile_id
+            }),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let skip: serde_json::Value = serde_json::from_str(&body_text(response).await)?;
+    assert_eq!(skip["profile_id"], serde_json::Value::Null);
+
+    let response = app
+        .clone()
+        .oneshot(auth_request(
+            Method::GET,
+            "/api/profile-rules",
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let rules: serde_json::Value = serde_json::from_str(&body_text(response).await)?;
+    assert_eq!(rules[0]["name"], "Skip samples");
+    assert_eq!(rules[1]["name"], "Anime folders");
+
+    for (payload, status) in [
+        (
+            json!({ "name": "bad", "action": "encode" }),
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            json!({ "name": "bad", "action": "profile" }),
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            json!({ "name": "bad", "action": "skip", "conditions": { "min_height": 2160, "max_height": 720 } }),
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            json!({ "name": "bad", "action": "profile", "profile_id": 9999 }),
+            StatusCode::NOT_FOUND,
+        ),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(auth_json_request(
+                Method::POST,
+                "/api/profile-rules",
+                &token,
+                payload,
+            ))
+            .await?;
+        assert_eq!(response.status(), status);
+    }
+
+    let response = app
+        .clone()
+        .oneshot(auth_request(
+            Method::DELETE,
+            &format!("/api/profiles/{profile_id}"),
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let anime_id = anime["id"].as_i64().unwrap_or_default();
+    let response = app
+        .clone()
+        .oneshot(auth_json_request(
+            Method::PUT,
+            &format!("/api/profile-rules/{anime_id}"),
+            &token,
+            json!({
+                "name": "Anime folders",
+                "position": 5,
+                "enabled": false,
+                "conditions": { "path_globs": ["**/Anime/**"] },
+                "action": "profile",
+                "profile_id": profile_id
+            }),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated: serde_json::Value = serde_json::from_str(&body_text(response).await)?;
+    assert_eq!(updated["position"], 5);
+    assert_eq!(updated["enabled"], false);
+
+    let response = app
+        .clone()
+        .oneshot(auth_request(
+            Method::DELETE,
+            &format!("/api/profile-rules/{anime_id}"),
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .clone()
+        .oneshot(auth_request(
+            Method::DELETE,
+            &format!("/api/profile-rules/{anime_id}"),
+            &token,
+            Body::empty(),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    cleanup_paths(&[config_path, db_path]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn profile_rule_test_rejects_paths_outside_library()
+-> std::result::Result<(), Box<dyn std::error::Error>> {
+    let media_root = temp_path("alchemist_rule_test_root", "dir");
+    std::fs::create_dir_all(&media_root)?;
+    let (state, app, config_path, db_path) = build_test_app(false, 8, |config| {
+        config.scanner.directories = vec![media_root.to_string_lossy().to_string()];
+    })
+    .await?;
+    let token = create_session(state.db.as_ref()).await?;
+
+    let response = app
+        .clone()
+        .oneshot(auth_json_request(
+            Method::POST,
+            "/api/profile-rules/test",
+            &token,
+            json!({ "path": config_path.display().to_string() }),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert!(
+        body_text(response)
+            .await
+            .contains("RULE_TEST_PATH_FORBIDDEN")
+    );
+
+    let response = app
+        .clone()
+        .oneshot(auth_json_request(
+            Method::POST,
+            "/api/profile-rules/test",
+            &token,
+            json!({ "path": "/definitely/missing/file.mkv" }),
+        ))
+        .await?;
+    // Paths outside the library are refused before their existence is checked.
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .clone()
+        .oneshot(auth_json_request(
+            Method::POST,
+            "/api/profile-rules/test",
+            &token,
+            json!({ "path": media_root.display().to_string() }),
+        ))
+        .await?;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(body_text(response).await.contains("RULE_TEST_PATH_MISSING"));
+
+    cleanup_paths(&[config_path, db_path, media_root]);
+    Ok(())
+}
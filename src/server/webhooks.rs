@@ -135,43 +135,6 @@ fn resolve_arr_payload_path(payload: &ArrWebhookPayload) -> Option<String> {
     first_non_empty([imported_primary, direct_file_path, joined_relative])
 }
 
-fn matches_prefix(path: &str, prefix: &str) -> bool {
-    if path == prefix {
-        return true;
-    }
-    match path.strip_prefix(prefix) {
-        Some(rest) => rest.starts_with('/') || rest.starts_with('\\'),
-        None => false,
-    }
-}
-
-fn apply_translation(path: &str, translation: &ArrPathTranslation) -> Option<String> {
-    let from = translation.from.trim();
-    let to = translation.to.trim();
-    if from.is_empty() || to.is_empty() || !matches_prefix(path, from) {
-        return None;
-    }
-    let suffix = path.strip_prefix(from).unwrap_or_default();
-    Some(format!("{to}{suffix}"))
-}
-
-fn translate_arr_path(path: &str, translations: &[ArrPathTranslation]) -> String {
-    let mut selected: Option<&ArrPathTranslation> = None;
-    for translation in translations {
-        if apply_translation(path, translation).is_some() {
-            let is_better = selected
-                .map(|current| translation.from.len() > current.from.len())
-                .unwrap_or(true);
-            if is_better {
-                selected = Some(translation);
-            }
-        }
-    }
-    selected
-        .and_then(|translation| apply_translation(path, translation))
-        .unwrap_or_else(|| path.to_string())
-}
-
 pub(crate) async fn arr_webhook_handler(
     State(state): State<Arc<AppState>>,
     axum::Json(payload): axum::Json<ArrWebhookPayload>,
@@ -202,10 +165,12 @@ pub(crate) async fn arr_webhook_handler(
 
     let translated = {
         let config = state.config.read().await;
-        translate_arr_path(&resolved, &config.system.arr_path_translations)
+        ArrPathTranslation::translate(&resolved, &config.system.arr_path_translations)
     };
 
-    match super::jobs::enqueue_job_from_submitted_path(state.as_ref(), translated.trim()).await {
+    match super::jobs::enqueue_job_from_submitted_path(state.as_ref(), translated.trim(), None)
+        .await
+    {
         Ok(enqueue_result) => axum::Json(ArrWebhookResponse {
             accepted: true,
             enqueued: enqueue_result.enqueued,
@@ -270,7 +235,7 @@ mod tests {
 
     #[test]
     fn uses_longest_matching_translation_prefix() {
-        let translated = translate_arr_path(
+        let translated = ArrPathTranslation::translate(
             "/data/media/tv/show.mkv",
             &[
                 ArrPathTranslation {
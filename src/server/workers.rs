@@ -0,0 +1,584 @@
+//! Remote worker endpoints and lease expiry; `crate::worker` is the client.
+
+use super::{AppState, api_error_response, api_ok_response};
+use crate::db::{Db, EventChannels, Job, JobEvent, JobLease, JobState, Worker};
+use crate::media::pipeline::{MediaMetadata, TranscodePlan, temp_output_path_for};
+use crate::system::hardware::HardwareInfo;
+use crate::worker::{
+    HEARTBEAT_SECS, HeartbeatRequest, HeartbeatResponse, LEASE_SECS, LeaseResponse, PlanJobRequest,
+    PlanJobResponse, RegisterWorkerRequest, RegisterWorkerResponse, RemoteJobReport,
+};
+use axum::{
+    Extension,
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+/// Queued jobs considered per lease request when matching a worker's codecs.
+const LEASE_CANDIDATES: i64 = 50;
+const LEASE_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// The worker token a request was made with, attached by the auth
+/// middleware. Sessions and full-access tokens carry none.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct WorkerToken {
+    pub token_id: i64,
+    /// The worker this token registered, if it has yet.
+    pub worker_id: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct WorkerResponse {
+    #[serde(flatten)]
+    worker: Worker,
+    hardware: Option<HardwareInfo>,
+    leased_job_ids: Vec<i64>,
+}
+
+pub(crate) async fn list_workers_handler(State(state): State<Arc<AppState>>) -> Response {
+    let workers = match state.db.get_workers().await {
+        Ok(workers) => workers,
+        Err(err) => {
+            return api_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "GET_WORKERS_FAILED",
+                err.to_string(),
+            );
+        }
+    };
+    let mut response = Vec::with_capacity(workers.len());
+    for worker in workers {
+        let leased_job_ids = match state.db.get_worker_leases(worker.id).await {
+            Ok(leases) => leases.into_iter().map(|lease| lease.job_id).collect(),
+            Err(err) => {
+                return api_error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "GET_WORKERS_FAILED",
+                    err.to_string(),
+                );
+            }
+        };
+        response.push(WorkerResponse {
+            hardware: serde_json::from_str(&worker.hardware_json).ok(),
+            worker,
+            leased_job_ids,
+        });
+    }
+    axum::Json(serde_json::json!({ "workers": response })).into_response()
+}
+
+pub(crate) async fn register_worker_handler(
+    State(state): State<Arc<AppState>>,
+    worker_token: Option<Extension<WorkerToken>>,
+    axum::Json(payload): axum::Json<RegisterWorkerRequest>,
+) -> Response {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return api_error_response(
+            StatusCode::BAD_REQUEST,
+            "WORKER_NAME_REQUIRED",
+            "Worker name must not be empty",
+        );
+    }
+    let hardware_json = match serde_json::to_string(&payload.hardware) {
+        Ok(json) => json,
+        Err(err) => {
+            return api_error_response(
+                StatusCode::BAD_REQUEST,
+                "INVALID_WORKER_HARDWARE",
+                err.to_string(),
+            );
+        }
+    };
+    let token_id = worker_token.map(|Extension(token)| token.token_id);
+    match state
+        .db
+        .register_worker(name, &hardware_json, token_id)
+        .await
+    {
+        Ok(None) => api_error_response(
+            StatusCode::FORBIDDEN,
+            "WORKER_TOKEN_MISMATCH",
+            "This token belongs to another worker",
+        ),
+        Ok(Some(worker)) => {
+            info!(
+                "Worker \"{}\" registered (codecs: {})",
+                worker.name,
+                payload.hardware.supported_codecs.join(", ")
+            );
+            axum::Json(RegisterWorkerResponse {
+                worker_id: worker.id,
+                lease_secs: LEASE_SECS,
+                heartbeat_secs: HEARTBEAT_SECS,
+            })
+            .into_response()
+        }
+        Err(err) => api_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "REGISTER_WORKER_FAILED",
+            err.to_string(),
+        ),
+    }
+}
+
+/// The worker `id`, refused with `403` when a worker token made the request
+/// for a worker other than its own.
+async fn load_worker(
+    state: &AppState,
+    worker_token: Option<Extension<WorkerToken>>,
+    id: i64,
+) -> Result<(Worker, HardwareInfo), Response> {
+    if let Some(Extension(token)) = worker_token
+        && token.worker_id != Some(id)
+    {
+        return Err(api_error_response(
+            StatusCode::FORBIDDEN,
+            "WORKER_TOKEN_MISMATCH",
+            "This token belongs to another worker",
+        ));
+    }
+    let worker = match state.db.get_worker(id).await {
+        Ok(Some(worker)) => worker,
+        Ok(None) => {
+            return Err(api_error_response(
+                StatusCode::NOT_FOUND,
+                "WORKER_NOT_FOUND",
+                "Worker not found; register again",
+            ));
+        }
+        Err(err) => {
+            return Err(api_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "GET_WORKER_FAILED",
+                err.to_string(),
+            ));
+        }
+    };
+    let hardware = serde_json::from_str(&worker.hardware_json).map_err(|err| {
+        api_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "INVALID_WORKER_HARDWARE",
+            err.to_string(),
+        )
+    })?;
+    if let Err(err) = state.db.touch_worker(worker.id).await {
+        warn!(
+            "Failed to record contact from worker {}: {err}",
+            worker.name
+        );
+    }
+    Ok((worker, hardware))
+}
+
+/// The lease `worker_id` holds on `job_id`, or `409 LEASE_LOST`.
+async fn held_lease(state: &AppState, worker_id: i64, job_id: i64) -> Result<JobLease, Response> {
+    match state.db.get_job_lease(job_id).await {
+        Ok(Some(lease)) if lease.worker_id == worker_id => Ok(lease),
+        Ok(_) => Err(api_error_response(
+            StatusCode::CONFLICT,
+            "LEASE_LOST",
+            "This worker no longer holds the job",
+        )),
+        Err(err) => Err(api_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "GET_JOB_LEASE_FAILED",
+            err.to_string(),
+        )),
+    }
+}
+
+async fn leased_job(state: &AppState, job_id: i64) -> Result<Job, Response> {
+    match state.db.get_job_by_id(job_id).await {
+        Ok(Some(job)) => Ok(job),
+        Ok(None) => Err(api_error_response(
+            StatusCode::NOT_FOUND,
+            "JOB_NOT_FOUND",
+            "Job not found",
+        )),
+        Err(err) => Err(api_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "GET_JOB_FAILED",
+            err.to_string(),
+        )),
+    }
+}
+
+/// Put an abandoned lease's job back in the queue.
+async fn requeue_lease(db: &Db, event_channels: &EventChannels, lease: &JobLease, reason: &str) {
+    if let Ok(Some(job)) = db.get_job_by_id(lease.job_id).await {
+        let temp_path = temp_output_path_for(std::path::Path::new(&job.output_path), job.id);
+        if temp_path.exists()
+            && let Err(err) = std::fs::remove_file(&temp_path)
+        {
+            warn!(
+                job_id = job.id,
+                "Failed to remove abandoned temp output: {err}"
+            );
+        }
+    }
+    warn!(job_id = lease.job_id, "{reason}");
+    let _ = db.add_log("warn", Some(lease.job_id), reason).await;
+    let _ = event_channels.jobs.send(JobEvent::StateChanged {
+        job_id: lease.job_id,
+        status: JobState::Queued,
+    });
+}
+
+pub(crate) async fn lease_job_handler(
+    State(state): State<Arc<AppState>>,
+    Path(worker_id): Path<i64>,
+    worker_token: Option<Extension<WorkerToken>>,
+) -> Response {
+    let (worker, hardware) = match load_worker(&state, worker_token, worker_id).await {
+        Ok(worker) => worker,
+        Err(response) => return response,
+    };
+
+    // A worker asks for work only when it is idle, so anything it still
+    // holds was abandoned, for example by a restart mid-encode.
+    if let Ok(leases) = state.db.get_worker_leases(worker.id).await {
+        for lease in leases {
+            if state.db.release_job_lease(lease.job_id).await.is_ok()
+                && state
+                    .db
+                    .update_job_status(lease.job_id, JobState::Queued)
+                    .await
+                    .is_ok()
+            {
+                let reason = format!(
+                    "Worker \"{}\" abandoned the job; returned to the queue.",
+                    worker.name
+                );
+                requeue_lease(&state.db, &state.event_channels, &lease, &reason).await;
+            }
+        }
+    }
+
+    let leased = match state.db.count_job_leases().await {
+        Ok(count) => usize::try_from(count).unwrap_or(usize::MAX),
+        Err(err) => {
+            return api_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "LEASE_JOB_FAILED",
+                err.to_string(),
+            );
+        }
+    };
+    if state.agent.lease_should_hold(leased).await {
+        return StatusCode::NO_CONTENT.into_response();
+    }
+
+    let ranking = state.agent.queue_ranking().await;
+    let candidates = match state
+        .db
+        .get_claimable_jobs(&ranking, LEASE_CANDIDATES)
+        .await
+    {
+        Ok(candidates) => candidates,
+        Err(err) => {
+            return api_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "LEASE_JOB_FAILED",
+                err.to_string(),
+            );
+        }
+    };
+    for candidate in candidates {
+        // Conversions are interactive; they stay on this instance.
+        if !matches!(
+            state
+                .db
+                .get_conversion_job_by_linked_job_id(candidate.id)
+                .await,
+            Ok(None)
+        ) {
+            continue;
+        }
+        // Variants read their parent's source from this instance's paths.
+        if !matches!(state.db.get_variant_link(candidate.id).await, Ok(None)) {
+            continue;
+        }
+        // Match the worker the way the job will be planned: the same
+        // backend restrictions and the codec its profile and overrides pick.
+        let Some(pipeline) = state.agent.pipeline_for_job(candidate.id).await else {
+            continue;
+        };
+        let planned = match pipeline.planned_encode(&candidate).await {
+            Ok(planned) => planned,
+            Err(err) => {
+                debug!(
+                    job_id = candidate.id,
+                    "Not leasing; output codec unresolved: {err}"
+                );
+                continue;
+            }
+        };
+        // The VMAF target search probe-encodes here, so those jobs stay local.
+        if planned.target_vmaf.is_some() {
+            continue;
+        }
+        if !crate::media::planner::hardware_encodes_codec(
+            &hardware,
+            planned.codec,
+            pipeline.allowed_backends(),
+        ) {
+            continue;
+        }
+        match state
+            .db
+            .lease_job(candidate.id, worker.id, LEASE_SECS)
+            .await
+        {
+            Ok(Some(job)) => {
+                let message = format!("Leased to worker \"{}\".", worker.name);
+                info!(job_id = job.id, "{message}");
+                let _ = state.db.add_log("info", Some(job.id), &message).await;
+                let _ = state.event_channels.jobs.send(JobEvent::StateChanged {
+                    job_id: job.id,
+                    status: JobState::Analyzing,
+                });
+                return axum::Json(LeaseResponse { job }).into_response();
+            }
+            Ok(None) => continue,
+            Err(err) => {
+                return api_error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "LEASE_JOB_FAILED",
+                    err.to_string(),
+                );
+            }
+        }
+    }
+    StatusCode::NO_CONTENT.into_response()
+}
+
+pub(crate) async fn plan_leased_job_handler(
+    State(state): State<Arc<AppState>>,
+    Path((worker_id, job_id)): Path<(i64, i64)>,
+    worker_token: Option<Extension<WorkerToken>>,
+    axum::Json(payload): axum::Json<PlanJobRequest>,
+) -> Response {
+    let (worker, hardware) = match load_worker(&state, worker_token, worker_id).await {
+        Ok(worker) => worker,
+        Err(response) => return response,
+    };
+    if let Err(response) = held_lease(&state, worker.id, job_id).await {
+        return response;
+    }
+    let job = match leased_job(&state, job_id).await {
+        Ok(job) => job,
+        Err(response) => return response,
+    };
+    let _ = state
+        .db
+        .renew_job_lease(job_id, worker.id, LEASE_SECS)
+        .await;
+
+    let Some(pipeline) = state.agent.pipeline_for_job(job_id).await else {
+        // The window changed after the lease; wait for one that allows the CPU.
+        let _ = state.db.release_job_lease(job_id).await;
+        let _ = state.db.update_job_status(job_id, JobState::Queued).await;
+        let _ = state.event_channels.jobs.send(JobEvent::StateChanged {
+            job_id,
+            status: JobState::Queued,
+        });
+        return axum::Json(PlanJobResponse { plan: None }).into_response();
+    };
+    let outcome = pipeline
+        .plan_remote_job(&job, &payload.analysis, Some(hardware))
+        .await;
+    let closed = match outcome {
+        Ok(Some(plan)) => {
+            let stored = serde_json::to_string(&plan).and_then(|plan_json| {
+                serde_json::to_string(&payload.analysis.metadata)
+                    .map(|metadata_json| (plan_json, metadata_json))
+            });
+            match stored {
+                Ok((plan_json, metadata_json)) => {
+                    if let Err(err) = state
+                        .db
+                        .set_job_lease_plan(job_id, &plan_json, &metadata_json)
+                        .await
+                    {
+                        return api_error_response(
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            "PLAN_JOB_FAILED",
+                            err.to_string(),
+                        );
+                    }
+                    return axum::Json(PlanJobResponse { plan: Some(plan) }).into_response();
+                }
+                Err(err) => {
+                    return api_error_response(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "PLAN_JOB_FAILED",
+                        err.to_string(),
+                    );
+                }
+            }
+        }
+        Ok(None) => Ok(()),
+        Err(failure) => Err(failure),
+    };
+
+    // Skipped, cancelled, or failed while planning: nothing for the worker
+    // to encode. Variants and hooks may run long, so conclude off-request.
+    let _ = state.db.release_job_lease(job_id).await;
+    let agent = state.agent.clone();
+    tokio::spawn(async move {
+        if let Err(err) = agent.conclude_job(&pipeline, job_id, closed).await {
+            error!(job_id, "Remote job processing error: {err}");
+        }
+    });
+    axum::Json(PlanJobResponse { plan: None }).into_response()
+}
+
+pub(crate) async fn heartbeat_leased_job_handler(
+    State(state): State<Arc<AppState>>,
+    Path((worker_id, job_id)): Path<(i64, i64)>,
+    worker_token: Option<Extension<WorkerToken>>,
+    axum::Json(payload): axum::Json<HeartbeatRequest>,
+) -> Response {
+    let (worker, _) = match load_worker(&state, worker_token, worker_id).await {
+        Ok(worker) => worker,
+        Err(response) => return response,
+    };
+    match state
+        .db
+        .renew_job_lease(job_id, worker.id, LEASE_SECS)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            return api_error_response(
+                StatusCode::CONFLICT,
+                "LEASE_LOST",
+                "This worker no longer holds the job",
+            );
+        }
+        Err(err) => {
+            return api_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "RENEW_JOB_LEASE_FAILED",
+                err.to_string(),
+            );
+        }
+    }
+
+    for message in payload.logs {
+        let _ = state.event_channels.jobs.send(JobEvent::Log {
+            level: "info".to_string(),
+            job_id: Some(job_id),
+            message: message.clone(),
+        });
+        let _ = state.db.add_log("info", Some(job_id), &message).await;
+    }
+    if let Some(percentage) = payload.progress {
+        let _ = state.db.update_job_progress(job_id, percentage).await;
+        let _ = state.event_channels.jobs.send(JobEvent::Progress {
+            job_id,
+            percentage,
+            time: payload.time,
+        });
+    }
+
+    let cancel = state.transcoder.is_cancel_requested(job_id).await
+        || matches!(
+            state.db.get_job_by_id(job_id).await,
+            Ok(Some(job)) if job.status == JobState::Cancelled
+        );
+    axum::Json(HeartbeatResponse { cancel }).into_response()
+}
+
+pub(crate) async fn complete_leased_job_handler(
+    State(state): State<Arc<AppState>>,
+    Path((worker_id, job_id)): Path<(i64, i64)>,
+    worker_token: Option<Extension<WorkerToken>>,
+    axum::Json(report): axum::Json<RemoteJobReport>,
+) -> Response {
+    let (worker, _) = match load_worker(&state, worker_token, worker_id).await {
+        Ok(worker) => worker,
+        Err(response) => return response,
+    };
+    let lease = match held_lease(&state, worker.id, job_id).await {
+        Ok(lease) => lease,
+        Err(response) => return response,
+    };
+    let job = match leased_job(&state, job_id).await {
+        Ok(job) => job,
+        Err(response) => return response,
+    };
+    let planned = lease
+        .plan_json
+        .as_deref()
+        .zip(lease.metadata_json.as_deref())
+        .and_then(|(plan_json, metadata_json)| {
+            let plan: TranscodePlan = serde_json::from_str(plan_json).ok()?;
+            let metadata: MediaMetadata = serde_json::from_str(metadata_json).ok()?;
+            Some((plan, metadata))
+        });
+    if let Err(err) = state.db.release_job_lease(job_id).await {
+        return api_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "RELEASE_JOB_LEASE_FAILED",
+            err.to_string(),
+        );
+    }
+
+    // Finalization can compute VMAF for minutes; the worker need not wait.
+    let agent = state.agent.clone();
+    tokio::spawn(async move {
+        // The encode is done; finalizing does not depend on the backend.
+        let pipeline = agent.pipeline();
+        let outcome = pipeline
+            .finalize_remote_job(
+                job,
+                planned.as_ref().map(|(plan, metadata)| (plan, metadata)),
+                report,
+            )
+            .await;
+        if let Err(err) = agent.conclude_job(&pipeline, job_id, outcome).await {
+            error!(job_id, "Remote job processing error: {err}");
+        }
+    });
+    api_ok_response()
+}
+
+/// Requeue jobs whose worker stopped heartbeating and mark silent workers
+/// offline, every `LEASE_CHECK_INTERVAL`.
+pub async fn run_lease_monitor(db: Arc<Db>, event_channels: Arc<EventChannels>) {
+    loop {
+        match db.expire_job_leases().await {
+            Ok(expired) => {
+                for lease in &expired {
+                    let worker = db
+                        .get_worker(lease.worker_id)
+                        .await
+                        .ok()
+                        .flatten()
+                        .map_or_else(|| format!("#{}", lease.worker_id), |worker| worker.name);
+                    let reason = format!(
+                        "Worker \"{worker}\" stopped responding; job returned to the queue."
+                    );
+                    requeue_lease(&db, &event_channels, lease, &reason).await;
+                }
+            }
+            Err(err) => warn!("Failed to expire job leases: {err}"),
+        }
+        match db.mark_stale_workers_offline(LEASE_SECS).await {
+            Ok(names) => {
+                for name in names {
+                    info!("Worker \"{name}\" is offline");
+                }
+            }
+            Err(err) => warn!("Failed to update worker status: {err}"),
+        }
+        tokio::time::sleep(LEASE_CHECK_INTERVAL).await;
+    }
+}
@@ -9,7 +9,7 @@
 
 use std::path::{Path, PathBuf};
 
-use sysinfo::Disks;
+use sysinfo::{Disk, Disks};
 
 /// One GiB in bytes.
 pub const GIB: u64 = 1024 * 1024 * 1024;
@@ -43,10 +43,21 @@ fn normalize_for_mount_match(path: &Path) -> PathBuf {
 /// nested mount resolves to the correct filesystem rather than its parent.
 pub fn available_bytes_for_path(path: &Path) -> Option<u64> {
     let disks = Disks::new_with_refreshed_list();
+    containing_disk(&disks, path).map(|disk| disk.available_space())
+}
+
+/// Filesystem type (`ext4`, `nfs4`, `fuse.mergerfs`, ...) of the mount that
+/// contains `path`, resolved the same way as `available_bytes_for_path`.
+pub fn filesystem_type_for_path(path: &Path) -> Option<String> {
+    let disks = Disks::new_with_refreshed_list();
+    containing_disk(&disks, path).map(|disk| disk.file_system().to_string_lossy().to_string())
+}
+
+fn containing_disk<'a>(disks: &'a Disks, path: &Path) -> Option<&'a Disk> {
     // Normalize away Windows verbatim prefixes so a `\\?\C:\...` output path
     // still matches sysinfo's `C:\` mount point (see normalize_for_mount_match).
     let path = normalize_for_mount_match(path);
-    let mut best: Option<(usize, u64)> = None;
+    let mut best: Option<(usize, &Disk)> = None;
     for disk in disks.list() {
         let mount = normalize_for_mount_match(disk.mount_point());
         if path.starts_with(&mount) {
@@ -56,11 +67,11 @@ pub fn available_bytes_for_path(path: &Path) -> Option<u64> {
                 None => true,
             };
             if is_better {
-                best = Some((len, disk.available_space()));
+                best = Some((len, disk));
             }
         }
     }
-    best.map(|(_, available)| available)
+    best.map(|(_, disk)| disk)
 }
 
 /// Whether the disk guardrail should hold jobs targeting a filesystem with
@@ -1,6 +1,7 @@
 use crate::config::Config;
 use crate::db::Db;
 use crate::error::{AlchemistError, Result};
+use crate::media::ignore::{ExcludeRules, ExcludedFile};
 use crate::media::scanner::Scanner;
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeSet, HashSet};
@@ -64,6 +65,10 @@ pub struct FsRecommendationsResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FsPreviewRequest {
     pub directories: Vec<String>,
+    /// Exclude globs applied to every requested directory, alongside any
+    /// `.alchemistignore` files found beneath them.
+    #[serde(default)]
+    pub exclude_globs: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -73,6 +78,9 @@ pub struct FsPreviewDirectory {
     pub readable: bool,
     pub media_files: usize,
     pub sample_files: Vec<String>,
+    /// Media files left out by an exclude rule, up to the sample limit.
+    pub excluded_files: Vec<ExcludedFile>,
+    pub excluded_count: usize,
     pub media_hint: MediaHint,
     pub warnings: Vec<String>,
 }
@@ -303,21 +311,25 @@ fn preview_blocking(request: FsPreviewRequest) -> Result<FsPreviewResponse> {
         let readable = exists && canonical.is_dir() && std::fs::read_dir(&canonical).is_ok();
         let mut directory_truncated = false;
         let mut timed_out = false;
+        let mut excluded = Vec::new();
         let scan_results = if readable
             && remaining_entries > 0
             && remaining_media_files > 0
             && Instant::now() < deadline
         {
-            let result = scanner.scan_directory_bounded(
+            let rules = ExcludeRules::new(canonical.clone(), &request.exclude_globs);
+            let result = scanner.scan_directory_bounded_excluding(
                 canonical.clone(),
                 true,
                 remaining_entries,
                 remaining_media_files.saturating_add(1),
                 deadline,
+                &rules,
             );
             remaining_entries = remaining_entries.saturating_sub(result.scanned_entries);
             directory_truncated = result.truncated || result.files.len() > remaining_media_files;
             timed_out = result.timed_out;
+            excluded = result.excluded;
             result
                 .files
                 .into_iter()
@@ -340,6 +352,8 @@ fn preview_blocking(request: FsPreviewRequest) -> Result<FsPreviewResponse> {
             .take(FS_PREVIEW_SAMPLE_LIMIT)
             .map(|media| media.path.to_string_lossy().to_string())
             .collect::<Vec<_>>();
+        let excluded_count = excluded.len();
+        excluded.truncate(FS_PREVIEW_SAMPLE_LIMIT);
 
         let mut dir_warnings = directory_warnings(&canonical, readable);
         if directory_truncated {
@@ -360,6 +374,8 @@ fn preview_blocking(request: FsPreviewRequest) -> Result<FsPreviewResponse> {
             readable,
             media_files,
             sample_files,
+            excluded_files: excluded,
+            excluded_count,
             media_hint: classify_media_hint(&canonical),
             warnings: dir_warnings,
         });
@@ -804,6 +820,7 @@ mod tests {
 
         let response = preview_blocking(FsPreviewRequest {
             directories: vec![root.to_string_lossy().to_string()],
+            exclude_globs: Vec::new(),
         });
         assert!(response.is_ok());
         let response = response.unwrap_or_else(|err| panic!("preview failed: {err}"));
@@ -823,6 +840,43 @@ mod tests {
         let _ = SystemTime::UNIX_EPOCH;
     }
 
+    #[test]
+    fn preview_reports_excluded_files_and_rules() {
+        let root = std::env::temp_dir().join(format!(
+            "alchemist_fs_preview_excluded_{}",
+            rand::random::<u64>()
+        ));
+        assert!(std::fs::create_dir_all(root.join("Extras")).is_ok());
+        assert!(std::fs::write(root.join("movie.mkv"), b"video").is_ok());
+        assert!(std::fs::write(root.join("Extras/featurette.mkv"), b"video").is_ok());
+        assert!(std::fs::write(root.join("movie-sample.mkv"), b"video").is_ok());
+        assert!(
+            std::fs::write(
+                root.join(crate::media::ignore::IGNORE_FILE_NAME),
+                "*-sample.mkv\n"
+            )
+            .is_ok()
+        );
+
+        let response = preview_blocking(FsPreviewRequest {
+            directories: vec![root.to_string_lossy().to_string()],
+            exclude_globs: vec!["**/Extras/**".to_string()],
+        });
+        let response = response.unwrap_or_else(|err| panic!("preview failed: {err}"));
+
+        assert_eq!(response.total_media_files, 1);
+        let directory = &response.directories[0];
+        assert_eq!(directory.excluded_count, 2);
+        let rules = directory
+            .excluded_files
+            .iter()
+            .map(|file| file.exclusion.rule.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(rules, vec!["**/Extras/**", "*-sample.mkv"]);
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
     #[test]
     fn preview_caps_large_roots_and_reports_partial_counts() {
         let root = std::env::temp_dir().join(format!(
@@ -836,6 +890,7 @@ mod tests {
 
         let response = preview_blocking(FsPreviewRequest {
             directories: vec![root.to_string_lossy().to_string()],
+            exclude_globs: Vec::new(),
         });
         assert!(response.is_ok());
         let response = response.unwrap_or_else(|err| panic!("preview failed: {err}"));
@@ -859,6 +914,7 @@ mod tests {
     fn preview_rejects_too_many_directories() {
         let response = preview_blocking(FsPreviewRequest {
             directories: vec!["/tmp".to_string(); FS_PREVIEW_MAX_DIRECTORIES + 1],
+            exclude_globs: Vec::new(),
         });
         assert!(response.is_err());
     }
@@ -0,0 +1,451 @@
+//! Resource governor: throttles the engine while the host is busy.
+//!
+//! A background task samples CPU, memory, temperature and the process list
+//! every `check_interval_secs`. Crossing any configured threshold, or a listed
+//! process appearing, throttles the engine at once. Resuming needs every
+//! reading to sit `resume_margin` below its threshold for `resume_after_secs`,
+//! so a load hovering around a threshold does not flap the engine.
+//!
+//! Alchemist's own process tree, ffmpeg children included, is left out of the
+//! CPU reading and the process list; otherwise every full-speed encode would
+//! throttle the engine and keep it throttled for as long as it ran.
+//!
+//! Throttling holds new job starts. Under `action = "pause"` it also stops
+//! running FFmpeg processes (SIGSTOP) and continues them once it clears.
+
+use crate::config::{Config, GovernorAction, ResourceGovernorConfig};
+use crate::media::processor::Agent;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use sysinfo::{Components, Pid, ProcessesToUpdate, Signal, System};
+use tokio::sync::RwLock;
+use tracing::debug;
+
+/// One reading of the host's load.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResourceSample {
+    pub cpu_percent: f32,
+    /// Share of `cpu_percent` used by Alchemist and its children.
+    pub own_cpu_percent: f32,
+    pub memory_percent: f32,
+    /// Hottest sensor, when the host exposes any.
+    pub max_temperature_c: Option<f32>,
+    /// Names of running processes outside Alchemist's own tree, lowercased.
+    pub process_names: Vec<String>,
+}
+
+impl ResourceSample {
+    /// CPU usage by everything other than Alchemist.
+    fn other_cpu_percent(&self) -> f32 {
+        (self.cpu_percent - self.own_cpu_percent).max(0.0)
+    }
+}
+
+/// Why the host counts as busy, with each threshold lowered by `margin`.
+fn busy_reason(
+    config: &ResourceGovernorConfig,
+    sample: &ResourceSample,
+    margin: f32,
+) -> Option<String> {
+    if let Some(max) = config.max_cpu_percent
+        && sample.other_cpu_percent() >= max - margin
+    {
+        return Some(format!(
+            "CPU at {:.0}% from other programs (limit {:.0}%)",
+            sample.other_cpu_percent(),
+            max
+        ));
+    }
+    if let Some(max) = config.max_memory_percent
+        && sample.memory_percent >= max - margin
+    {
+        return Some(format!(
+            "memory at {:.0}% (limit {:.0}%)",
+            sample.memory_percent, max
+        ));
+    }
+    if let Some(max) = config.max_temperature_c
+        && let Some(temperature) = sample.max_temperature_c
+        && temperature >= max - margin
+    {
+        return Some(format!(
+            "temperature at {:.0}°C (limit {:.0}°C)",
+            temperature, max
+        ));
+    }
+    config
+        .busy_processes
+        .iter()
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty())
+        .find(|name| {
+            sample
+                .process_names
+                .iter()
+                .any(|running| running.eq_ignore_ascii_case(name))
+        })
+        .map(|name| format!("{name} is running"))
+}
+
+/// Throttle state carried between samples.
+#[derive(Debug, Default)]
+pub struct Governor {
+    reason: Option<String>,
+    clear_since: Option<Instant>,
+}
+
+impl Governor {
+    /// Fold in a new sample and return the throttle reason, if throttled.
+    pub fn update(
+        &mut self,
+        config: &ResourceGovernorConfig,
+        sample: &ResourceSample,
+        now: Instant,
+    ) -> Option<String> {
+        if !config.enabled {
+            *self = Self::default();
+            return None;
+        }
+
+        if let Some(reason) = busy_reason(config, sample, 0.0) {
+            self.reason = Some(reason);
+            self.clear_since = None;
+        } else if self.reason.is_some() {
+            if busy_reason(config, sample, config.resume_margin.max(0.0)).is_some() {
+                self.clear_since = None;
+            } else {
+                let clear_since = *self.clear_since.get_or_insert(now);
+                if now.duration_since(clear_since) >= Duration::from_secs(config.resume_after_secs)
+                {
+                    *self = Self::default();
+                }
+            }
+        }
+        self.reason.clone()
+    }
+}
+
+/// `root` and every process descended from it, given each process's parent.
+fn process_tree(parents: &HashMap<Pid, Option<Pid>>, root: Pid) -> HashSet<Pid> {
+    let mut tree = HashSet::from([root]);
+    for &pid in parents.keys() {
+        let mut chain = Vec::new();
+        let mut current = Some(pid);
+        // Walk up until the chain meets the tree or runs out; the length
+        // bound guards against a parent cycle from a reused pid.
+        while let Some(pid) = current
+            && !tree.contains(&pid)
+            && chain.len() <= parents.len()
+        {
+            chain.push(pid);
+            current = parents.get(&pid).copied().flatten();
+        }
+        if current.is_some_and(|pid| tree.contains(&pid)) {
+            tree.extend(chain);
+        }
+    }
+    tree
+}
+
+/// Whether a process is an FFmpeg run, judged by its name or executable.
+fn is_ffmpeg(process: &sysinfo::Process) -> bool {
+    let is_ffmpeg = |name: &std::ffi::OsStr| {
+        name.to_string_lossy()
+            .to_ascii_lowercase()
+            .starts_with("ffmpeg")
+    };
+    is_ffmpeg(process.name())
+        || process
+            .exe()
+            .and_then(|exe| exe.file_name())
+            .is_some_and(is_ffmpeg)
+}
+
+/// The host readings plus the FFmpeg processes the governor stops under
+/// `action = "pause"`.
+struct Probe {
+    sys: System,
+    components: Components,
+    /// FFmpeg processes in Alchemist's own tree as of the last sample.
+    own_encoders: Vec<Pid>,
+    /// Encoders the governor stopped and must continue once it clears.
+    stopped: HashSet<Pid>,
+}
+
+impl Probe {
+    fn new() -> Self {
+        Self {
+            sys: System::new(),
+            components: Components::new_with_refreshed_list(),
+            own_encoders: Vec::new(),
+            stopped: HashSet::new(),
+        }
+    }
+
+    fn sample(&mut self, config: &ResourceGovernorConfig) -> ResourceSample {
+        let sys = &mut self.sys;
+        sys.refresh_cpu_usage();
+        sys.refresh_memory();
+        let memory_percent = if sys.total_memory() > 0 {
+            (sys.used_memory() as f32 / sys.total_memory() as f32) * 100.0
+        } else {
+            0.0
+        };
+
+        let max_temperature_c = if config.max_temperature_c.is_some() {
+            self.components.refresh(true);
+            self.components
+                .iter()
+                .filter_map(|component| component.temperature())
+                .filter(|temperature| temperature.is_finite())
+                .reduce(f32::max)
+        } else {
+            None
+        };
+
+        sys.refresh_processes(ProcessesToUpdate::All, true);
+        let own_tree = match sysinfo::get_current_pid() {
+            Ok(pid) => {
+                let parents = sys
+                    .processes()
+                    .iter()
+                    .map(|(pid, process)| (*pid, process.parent()))
+                    .collect();
+                process_tree(&parents, pid)
+            }
+            Err(_) => HashSet::new(),
+        };
+        // Per-process usage is relative to one core; the global figure is
+        // relative to all of them.
+        let cores = sys.cpus().len().max(1) as f32;
+        let own_cpu_percent = own_tree
+            .iter()
+            .filter_map(|pid| sys.process(*pid))
+            .map(|process| process.cpu_usage())
+            .sum::<f32>()
+            / cores;
+        self.own_encoders = own_tree
+            .iter()
+            .filter(|pid| sys.process(**pid).is_some_and(is_ffmpeg))
+            .copied()
+            .collect();
+
+        let process_names = if config.busy_processes.is_empty() {
+            Vec::new()
+        } else {
+            sys.processes()
+                .iter()
+                .filter(|(pid, _)| !own_tree.contains(pid))
+                .map(|(_, process)| process)
+                .flat_map(|process| {
+                    // Linux truncates process names to 15 bytes; the executable's
+                    // file name is the full one.
+                    let exe_name = process
+                        .exe()
+                        .and_then(|exe| exe.file_name())
+                        .map(|name| name.to_string_lossy().to_lowercase());
+                    std::iter::once(process.name().to_string_lossy().to_lowercase()).chain(exe_name)
+                })
+                .collect()
+        };
+
+        ResourceSample {
+            cpu_percent: sys.global_cpu_usage(),
+            own_cpu_percent,
+            memory_percent,
+            max_temperature_c,
+            process_names,
+        }
+    }
+
+    /// Stop every encoder from the last sample, or continue the ones
+    /// stopped before. Stopping needs Unix; elsewhere encodes keep running.
+    fn suspend_encoders(&mut self, suspend: bool) {
+        if suspend {
+            for pid in &self.own_encoders {
+                if !self.stopped.contains(pid)
+                    && self
+                        .sys
+                        .process(*pid)
+                        .and_then(|process| process.kill_with(Signal::Stop))
+                        == Some(true)
+                {
+                    self.stopped.insert(*pid);
+                }
+            }
+        } else {
+            for pid in self.stopped.drain() {
+                if let Some(process) = self.sys.process(pid) {
+                    process.kill_with(Signal::Continue);
+                }
+            }
+        }
+    }
+}
+
+/// Sample the host forever, throttling `agent` as the governor decides.
+/// Under `action = "pause"` running encodes are stopped as well, so the
+/// host gets its CPU back at once rather than when they finish.
+pub async fn run(agent: Arc<Agent>, config: Arc<RwLock<Config>>) {
+    let probe = Arc::new(std::sync::Mutex::new(Probe::new()));
+    let mut governor = Governor::default();
+
+    loop {
+        let governor_config = config.read().await.system.resource_governor.clone();
+        let interval = Duration::from_secs(governor_config.check_interval_secs.max(1));
+
+        let reason = if governor_config.enabled {
+            let sample_probe = probe.clone();
+            let sample_config = governor_config.clone();
+            let sample = tokio::task::spawn_blocking(move || {
+                let mut probe = match sample_probe.lock() {
+                    Ok(probe) => probe,
+                    Err(e) => e.into_inner(),
+                };
+                probe.sample(&sample_config)
+            })
+            .await;
+            match sample {
+                Ok(sample) => governor.update(&governor_config, &sample, Instant::now()),
+                Err(e) => {
+                    debug!("Resource governor sample failed: {e}");
+                    agent.throttle_reason()
+                }
+            }
+        } else {
+            governor.update(&governor_config, &ResourceSample::default(), Instant::now());
+            None
+        };
+
+        let suspend = reason.is_some() && governor_config.action == GovernorAction::Pause;
+        agent.set_throttle(reason);
+        // Flag the suspension before stopping anything, so the stall check
+        // never sees a stopped encoder as hung.
+        if suspend {
+            agent.set_encodes_suspended(true);
+        }
+        {
+            let mut probe = match probe.lock() {
+                Ok(probe) => probe,
+                Err(e) => e.into_inner(),
+            };
+            probe.suspend_encoders(suspend);
+        }
+        if !suspend {
+            agent.set_encodes_suspended(false);
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ResourceGovernorConfig {
+        ResourceGovernorConfig {
+            enabled: true,
+            max_cpu_percent: Some(80.0),
+            busy_processes: vec!["Plex Transcoder".to_string()],
+            resume_margin: 10.0,
+            resume_after_secs: 60,
+            ..ResourceGovernorConfig::default()
+        }
+    }
+
+    fn cpu(percent: f32) -> ResourceSample {
+        ResourceSample {
+            cpu_percent: percent,
+            ..ResourceSample::default()
+        }
+    }
+
+    #[test]
+    fn governor_throttles_at_threshold_and_resumes_with_hysteresis() {
+        let config = config();
+        let mut governor = Governor::default();
+        let start = Instant::now();
+
+        assert_eq!(governor.update(&config, &cpu(50.0), start), None);
+        let Some(reason) = governor.update(&config, &cpu(95.0), start) else {
+            panic!("expected CPU load to throttle");
+        };
+        assert!(reason.starts_with("CPU at 95% from other programs"));
+
+        // Below the threshold but inside the margin: still busy.
+        let later = start + Duration::from_secs(120);
+        assert!(governor.update(&config, &cpu(75.0), later).is_some());
+
+        // Clear, but not for long enough yet.
+        assert!(governor.update(&config, &cpu(40.0), later).is_some());
+        let resumed = later + Duration::from_secs(60);
+        assert_eq!(governor.update(&config, &cpu(40.0), resumed), None);
+    }
+
+    #[test]
+    fn governor_ignores_load_from_its_own_encodes() {
+        let config = config();
+        let mut governor = Governor::default();
+        let now = Instant::now();
+        let own_encode = ResourceSample {
+            cpu_percent: 97.0,
+            own_cpu_percent: 92.0,
+            ..ResourceSample::default()
+        };
+        assert_eq!(governor.update(&config, &own_encode, now), None);
+
+        let with_plex = ResourceSample {
+            cpu_percent: 99.0,
+            own_cpu_percent: 15.0,
+            ..ResourceSample::default()
+        };
+        let Some(reason) = governor.update(&config, &with_plex, now) else {
+            panic!("expected other programs' load to throttle");
+        };
+        assert!(reason.starts_with("CPU at 84% from other programs"));
+    }
+
+    #[test]
+    fn process_tree_follows_children_and_grandchildren() {
+        let pid = Pid::from_u32;
+        let parents = HashMap::from([
+            (pid(1), None),
+            (pid(10), Some(pid(1))),
+            (pid(20), Some(pid(10))),
+            (pid(21), Some(pid(20))),
+            (pid(30), Some(pid(1))),
+            (pid(40), Some(pid(41))),
+            (pid(41), Some(pid(40))),
+        ]);
+        let tree = process_tree(&parents, pid(10));
+        assert_eq!(tree, HashSet::from([pid(10), pid(20), pid(21)]));
+    }
+
+    #[test]
+    fn governor_throttles_while_a_listed_process_runs() {
+        let config = ResourceGovernorConfig {
+            resume_after_secs: 0,
+            ..config()
+        };
+        let mut governor = Governor::default();
+        let now = Instant::now();
+        let busy = ResourceSample {
+            process_names: vec!["plex transcoder".to_string()],
+            ..ResourceSample::default()
+        };
+
+        assert_eq!(
+            governor.update(&config, &busy, now),
+            Some("Plex Transcoder is running".to_string())
+        );
+        assert_eq!(governor.update(&config, &cpu(10.0), now), None);
+
+        let disabled = ResourceGovernorConfig {
+            enabled: false,
+            ..config
+        };
+        assert_eq!(governor.update(&disabled, &busy, now), None);
+    }
+}
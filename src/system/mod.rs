@@ -1,7 +1,10 @@
 pub mod device_id;
 pub mod disk_space;
 pub mod fs_browser;
+pub mod governor;
 pub mod hardware;
+pub mod poll_watcher;
 pub mod scanner;
 pub mod selftest;
+pub mod space_goals;
 pub mod watcher;
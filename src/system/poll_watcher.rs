@@ -0,0 +1,328 @@
+//! Polling watch backend for filesystems that never deliver change events.
+//!
+//! NFS, SMB and most FUSE mounts only report changes made through the local
+//! kernel, so files written by another host never reach inotify. The poller
+//! keeps a snapshot of each polled root's media files (size and mtime) and
+//! diffs it on an interval. New and changed files are handed back to the
+//! watcher, which waits for them to settle exactly as it does for native
+//! events. Walks draw from a per-second stat budget so a large share is read
+//! gradually instead of all at once.
+
+use crate::config::{ScannerConfig, WatchMode};
+use crate::media::ignore::{ExcludeRules, IGNORE_FILE_NAME};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollSettings {
+    pub interval: Duration,
+    /// `0` removes the cap.
+    pub max_stats_per_sec: u32,
+}
+
+impl Default for PollSettings {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(crate::config::default_watch_poll_interval_secs()),
+            max_stats_per_sec: crate::config::default_watch_poll_max_stats_per_sec(),
+        }
+    }
+}
+
+impl PollSettings {
+    pub fn from_config(scanner: &ScannerConfig) -> Self {
+        Self {
+            interval: Duration::from_secs(scanner.poll_interval_secs),
+            max_stats_per_sec: scanner.poll_max_stats_per_sec,
+        }
+    }
+}
+
+/// Filesystem types whose remote changes native watchers never see.
+/// `fuseblk` is a local block device (e.g. ntfs-3g) and is left native.
+pub fn is_network_filesystem(fs_type: &str) -> bool {
+    let fs_type = fs_type.trim().to_ascii_lowercase();
+    matches!(
+        fs_type.as_str(),
+        "nfs"
+            | "nfs4"
+            | "cifs"
+            | "smb"
+            | "smbfs"
+            | "smb2"
+            | "smb3"
+            | "9p"
+            | "afs"
+            | "ceph"
+            | "glusterfs"
+            | "lustre"
+            | "davfs"
+            | "virtiofs"
+    ) || (fs_type.starts_with("fuse") && fs_type != "fuseblk")
+}
+
+/// `Native` or `Poll` for a watch directory; `Auto` polls network and FUSE
+/// mounts and uses native events everywhere else.
+pub fn resolve_watch_mode(mode: WatchMode, path: &Path) -> WatchMode {
+    match mode {
+        WatchMode::Auto => match crate::system::disk_space::filesystem_type_for_path(path) {
+            Some(fs_type) if is_network_filesystem(&fs_type) => {
+                info!("Polling {:?}: it is on a {} filesystem", path, fs_type);
+                WatchMode::Poll
+            }
+            _ => WatchMode::Native,
+        },
+        explicit => explicit,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    size: u64,
+    mtime: Option<SystemTime>,
+}
+
+#[derive(Debug, Default)]
+struct Snapshot {
+    files: HashMap<PathBuf, FileStamp>,
+    ignore_files: HashMap<PathBuf, FileStamp>,
+}
+
+impl Snapshot {
+    /// Files that are new, or whose size or mtime moved, since `previous`.
+    fn changed_since(&self, previous: &Snapshot) -> Vec<PathBuf> {
+        let mut changed = self
+            .files
+            .iter()
+            .filter(|(path, stamp)| previous.files.get(*path) != Some(*stamp))
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        changed.sort();
+        changed
+    }
+}
+
+/// Spends at most `per_sec` stat calls in any one-second window, sleeping
+/// out the rest of the window once it is used up.
+struct StatBudget {
+    per_sec: u32,
+    window_start: Instant,
+    used: u32,
+}
+
+impl StatBudget {
+    fn new(per_sec: u32) -> Self {
+        Self {
+            per_sec,
+            window_start: Instant::now(),
+            used: 0,
+        }
+    }
+
+    fn take(&mut self) {
+        if self.per_sec == 0 {
+            return;
+        }
+        let elapsed = self.window_start.elapsed();
+        if elapsed >= Duration::from_secs(1) {
+            self.window_start = Instant::now();
+            self.used = 0;
+        } else if self.used >= self.per_sec {
+            std::thread::sleep(Duration::from_secs(1) - elapsed);
+            self.window_start = Instant::now();
+            self.used = 0;
+        }
+        self.used += 1;
+    }
+}
+
+pub(crate) struct PolledRoot {
+    pub path: PathBuf,
+    pub recursive: bool,
+    pub rules: ExcludeRules,
+}
+
+/// Walk `root` into a snapshot. `None` when the root could not be read or
+/// the poller was stopped, so a passing outage never looks like every file
+/// arriving at once.
+fn take_snapshot(
+    root: &PolledRoot,
+    extensions: &HashSet<String>,
+    budget: &mut StatBudget,
+    stop: &AtomicBool,
+) -> Option<Snapshot> {
+    let walker = if root.recursive {
+        WalkDir::new(&root.path)
+    } else {
+        WalkDir::new(&root.path).max_depth(1)
+    };
+    let mut snapshot = Snapshot::default();
+    let walker = walker.into_iter().filter_entry(|entry| {
+        root.rules
+            .entry_exclusion(entry.path(), entry.file_type().is_dir())
+            .is_none()
+    });
+    for entry in walker {
+        if stop.load(Ordering::SeqCst) {
+            return None;
+        }
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) if err.depth() == 0 => {
+                warn!("Polling {:?} failed: {}", root.path, err);
+                return None;
+            }
+            Err(_) => continue,
+        };
+        budget.take();
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let is_ignore_file = entry.file_name() == IGNORE_FILE_NAME;
+        let is_media = entry
+            .path()
+            .extension()
+            .is_some_and(|ext| extensions.contains(&ext.to_string_lossy().to_lowercase()));
+        if !is_media && !is_ignore_file {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let stamp = FileStamp {
+            size: metadata.len(),
+            mtime: metadata.modified().ok(),
+        };
+        let target = if is_ignore_file {
+            &mut snapshot.ignore_files
+        } else {
+            &mut snapshot.files
+        };
+        target.insert(entry.path().to_path_buf(), stamp);
+    }
+    Some(snapshot)
+}
+
+/// Poll `roots` on a background thread until `stop` is set. The first pass
+/// only records a baseline; library scans cover files that already exist.
+pub(crate) fn spawn_poller(
+    roots: Vec<PolledRoot>,
+    extensions: HashSet<String>,
+    settings: PollSettings,
+    stop: Arc<AtomicBool>,
+    mut emit: impl FnMut(PathBuf) + Send + 'static,
+) -> std::io::Result<()> {
+    std::thread::Builder::new()
+        .name("alchemist-poll-watcher".to_string())
+        .spawn(move || {
+            let mut budget = StatBudget::new(settings.max_stats_per_sec);
+            let mut snapshots: Vec<Option<Snapshot>> = roots.iter().map(|_| None).collect();
+            loop {
+                for (root, previous) in roots.iter().zip(snapshots.iter_mut()) {
+                    if stop.load(Ordering::SeqCst) {
+                        return;
+                    }
+                    let Some(current) = take_snapshot(root, &extensions, &mut budget, &stop) else {
+                        continue;
+                    };
+                    if let Some(previous) = previous {
+                        if current.ignore_files != previous.ignore_files {
+                            root.rules.forget_ignore_files();
+                        }
+                        for path in current.changed_since(previous) {
+                            debug!("Poll detected change: {:?}", path);
+                            emit(path);
+                        }
+                    }
+                    *previous = Some(current);
+                }
+
+                let wake_at = Instant::now() + settings.interval;
+                while Instant::now() < wake_at {
+                    if stop.load(Ordering::SeqCst) {
+                        return;
+                    }
+                    std::thread::sleep(Duration::from_millis(250));
+                }
+            }
+        })
+        .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stamp(size: u64) -> FileStamp {
+        FileStamp {
+            size,
+            mtime: Some(SystemTime::UNIX_EPOCH),
+        }
+    }
+
+    #[test]
+    fn snapshot_diff_reports_new_and_changed_files() {
+        let mut previous = Snapshot::default();
+        previous
+            .files
+            .insert(PathBuf::from("/share/a.mkv"), stamp(1));
+        previous
+            .files
+            .insert(PathBuf::from("/share/b.mkv"), stamp(1));
+        previous
+            .files
+            .insert(PathBuf::from("/share/gone.mkv"), stamp(1));
+
+        let mut current = Snapshot::default();
+        current
+            .files
+            .insert(PathBuf::from("/share/a.mkv"), stamp(1));
+        current
+            .files
+            .insert(PathBuf::from("/share/b.mkv"), stamp(2));
+        current
+            .files
+            .insert(PathBuf::from("/share/c.mkv"), stamp(1));
+
+        assert_eq!(
+            current.changed_since(&previous),
+            vec![PathBuf::from("/share/b.mkv"), PathBuf::from("/share/c.mkv")]
+        );
+    }
+
+    #[test]
+    fn network_and_fuse_filesystems_are_polled() {
+        for fs_type in ["nfs4", "cifs", "smb3", "fuse.mergerfs", "fuse.sshfs", "NFS"] {
+            assert!(is_network_filesystem(fs_type), "{fs_type}");
+        }
+        for fs_type in ["ext4", "btrfs", "zfs", "apfs", "ntfs", "fuseblk", "overlay"] {
+            assert!(!is_network_filesystem(fs_type), "{fs_type}");
+        }
+        assert_eq!(
+            resolve_watch_mode(WatchMode::Poll, Path::new("/")),
+            WatchMode::Poll
+        );
+    }
+
+    #[test]
+    fn stat_budget_waits_out_the_window_once_spent() {
+        let mut budget = StatBudget::new(5);
+        let started = Instant::now();
+        for _ in 0..6 {
+            budget.take();
+        }
+        assert!(started.elapsed() >= Duration::from_millis(900));
+
+        let mut unlimited = StatBudget::new(0);
+        let started = Instant::now();
+        for _ in 0..10_000 {
+            unlimited.take();
+        }
+        assert!(started.elapsed() < Duration::from_millis(900));
+    }
+}
@@ -136,8 +136,15 @@ impl LibraryScanner {
             };
             let mut prune = PruneOptions {
                 enabled: prune_enabled,
-                last_scanned_by_root: HashMap::new(),
+                ..PruneOptions::default()
             };
+            for watch_dir in &watch_dirs {
+                prune
+                    .exclude_globs_by_root
+                    .entry(PathBuf::from(&watch_dir.path))
+                    .or_default()
+                    .extend(watch_dir.exclude_globs());
+            }
             if prune_enabled {
                 for (path, last_scanned) in last_scanned_map {
                     if let Some(ts) = last_scanned {
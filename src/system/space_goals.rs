@@ -0,0 +1,206 @@
+//! Space goals: encode until a filesystem reaches a free-space target.
+//!
+//! A goal names a path and either a free-space figure the filesystem should
+//! reach (`free`) or an amount encodes of its sources should save
+//! (`reclaimed`). While a goal is active the queue starts that filesystem's
+//! highest-yield jobs first. A background task checks progress every
+//! `CHECK_INTERVAL`; once the target is reached the goal is marked met, a
+//! notification goes out, and the engine drains when the goal asks for it
+//! and no other goal is still running. A goal past its deadline expires
+//! without draining.
+
+use crate::db::{Db, EventChannels, SpaceGoal, SystemEvent};
+use crate::media::processor::Agent;
+use crate::system::disk_space::{GIB, as_gib, available_bytes_for_path};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Where a goal stands.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct GoalProgress {
+    pub free_bytes: Option<u64>,
+    pub reclaimed_bytes: u64,
+    pub target_bytes: u64,
+    pub progress_percent: f64,
+    pub met: bool,
+}
+
+/// Progress of `goal` given the filesystem's free space now and the bytes
+/// saved on it since the goal was set. Unknown free space never meets a
+/// `free` goal.
+pub fn progress(goal: &SpaceGoal, free_bytes: Option<u64>, reclaimed_bytes: u64) -> GoalProgress {
+    let target_bytes = (goal.target_gib.max(0.0) * GIB as f64) as u64;
+    let (met, progress_percent) = if goal.target_kind == "free" {
+        match free_bytes {
+            Some(free) => {
+                let start = goal
+                    .start_free_bytes
+                    .map(|start| start.max(0) as u64)
+                    .unwrap_or(0)
+                    .min(target_bytes);
+                let needed = target_bytes - start;
+                let percent = if free >= target_bytes || needed == 0 {
+                    100.0
+                } else {
+                    free.saturating_sub(start) as f64 / needed as f64 * 100.0
+                };
+                (free >= target_bytes, percent)
+            }
+            None => (false, 0.0),
+        }
+    } else {
+        let percent = if target_bytes == 0 {
+            100.0
+        } else {
+            (reclaimed_bytes as f64 / target_bytes as f64 * 100.0).min(100.0)
+        };
+        (reclaimed_bytes >= target_bytes, percent)
+    };
+
+    GoalProgress {
+        free_bytes,
+        reclaimed_bytes,
+        target_bytes,
+        progress_percent,
+        met,
+    }
+}
+
+/// Current progress of `goal`, reading free space and savings.
+pub async fn goal_progress(db: &Db, goal: &SpaceGoal) -> crate::error::Result<GoalProgress> {
+    let path = goal.path.clone();
+    let free_bytes =
+        tokio::task::spawn_blocking(move || available_bytes_for_path(Path::new(&path)))
+            .await
+            .unwrap_or(None);
+    let reclaimed_bytes = db.get_space_goal_savings(goal.id).await?.max(0) as u64;
+    Ok(progress(goal, free_bytes, reclaimed_bytes))
+}
+
+fn completion_message(goal: &SpaceGoal, outcome: &str, progress: &GoalProgress) -> String {
+    let target = match goal.target_kind.as_str() {
+        "free" => format!("{:.1} GiB free", goal.target_gib),
+        _ => format!("{:.1} GiB reclaimed", goal.target_gib),
+    };
+    let reclaimed = as_gib(progress.reclaimed_bytes);
+    match outcome {
+        "met" => format!(
+            "Space goal for {} met: {target} ({reclaimed:.1} GiB reclaimed).",
+            goal.path
+        ),
+        _ => format!(
+            "Space goal for {} expired at {:.0}% of {target} ({reclaimed:.1} GiB reclaimed).",
+            goal.path, progress.progress_percent
+        ),
+    }
+}
+
+/// Close out every active goal that is met or past its deadline. Returns the
+/// goals closed, with their outcome.
+pub async fn check_goals(
+    db: &Db,
+    now: DateTime<Utc>,
+) -> crate::error::Result<Vec<(SpaceGoal, &'static str, GoalProgress)>> {
+    let mut finished = Vec::new();
+    for goal in db.get_active_space_goals().await? {
+        let progress = goal_progress(db, &goal).await?;
+        let outcome = if progress.met {
+            "met"
+        } else if goal.deadline.is_some_and(|deadline| deadline <= now) {
+            "expired"
+        } else {
+            continue;
+        };
+        db.finish_space_goal(goal.id, outcome).await?;
+        finished.push((goal, outcome, progress));
+    }
+    Ok(finished)
+}
+
+/// Watch active goals forever, closing them out and draining `agent` when a
+/// met goal asks for it.
+pub async fn run(agent: Arc<Agent>, db: Arc<Db>, event_channels: Arc<EventChannels>) {
+    loop {
+        match check_goals(&db, Utc::now()).await {
+            Ok(finished) if !finished.is_empty() => {
+                for (goal, outcome, progress) in &finished {
+                    let message = completion_message(goal, outcome, progress);
+                    info!("{message}");
+                    let _ = event_channels.system.send(SystemEvent::SpaceGoalCompleted {
+                        goal_id: goal.id,
+                        outcome: outcome.to_string(),
+                        message,
+                    });
+                }
+
+                let drain_requested = finished
+                    .iter()
+                    .any(|(goal, outcome, _)| *outcome == "met" && goal.drain_when_met);
+                if drain_requested && !agent.is_draining() {
+                    match db.get_active_space_goals().await {
+                        Ok(active) if active.is_empty() => {
+                            agent.drain();
+                            let _ = event_channels.system.send(SystemEvent::EngineStatusChanged);
+                        }
+                        Ok(_) => debug!("Space goal met; other goals still active, not draining"),
+                        Err(e) => warn!("Failed to read active space goals: {e}"),
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Space goal check failed: {e}"),
+        }
+
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn goal(kind: &str, target_gib: f64, start_free_gib: Option<u64>) -> SpaceGoal {
+        SpaceGoal {
+            id: 1,
+            path: "/media".to_string(),
+            device_id: "dev:1".to_string(),
+            target_kind: kind.to_string(),
+            target_gib,
+            deadline: None,
+            drain_when_met: true,
+            start_free_bytes: start_free_gib.map(|gib| (gib * GIB) as i64),
+            status: "active".to_string(),
+            created_at: Utc::now(),
+            finished_at: None,
+        }
+    }
+
+    #[test]
+    fn free_goals_measure_from_the_starting_free_space() {
+        let goal = goal("free", 500.0, Some(300));
+        let halfway = progress(&goal, Some(400 * GIB), 0);
+        assert!(!halfway.met);
+        assert!((halfway.progress_percent - 50.0).abs() < 0.01);
+
+        assert!(progress(&goal, Some(500 * GIB), 0).met);
+        assert!(!progress(&goal, None, 10_000 * GIB).met);
+    }
+
+    #[test]
+    fn reclaimed_goals_count_savings() {
+        let goal = goal("reclaimed", 200.0, None);
+        let quarter = progress(&goal, None, 50 * GIB);
+        assert!(!quarter.met);
+        assert!((quarter.progress_percent - 25.0).abs() < 0.01);
+
+        let done = progress(&goal, None, 250 * GIB);
+        assert!(done.met);
+        assert_eq!(done.progress_percent, 100.0);
+    }
+}
@@ -2,10 +2,12 @@
 //!
 //! Uses the `notify` crate to watch configured directories for new files.
 
-use crate::config::Config as AppConfig;
+use crate::config::{Config as AppConfig, WatchMode};
 use crate::db::Db;
 use crate::error::{AlchemistError, Result};
+use crate::media::ignore::{ExcludeRules, IGNORE_FILE_NAME};
 use crate::media::scanner::Scanner;
+use crate::system::poll_watcher::{PollSettings, PolledRoot, resolve_watch_mode, spawn_poller};
 use notify::{
     Config as NotifyConfig, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
     event::{AccessKind, AccessMode, CreateKind, DataChange, ModifyKind, RenameMode},
@@ -23,6 +25,8 @@ use tracing::{debug, error, info};
 pub struct WatchPath {
     pub path: PathBuf,
     pub recursive: bool,
+    pub exclude_globs: Vec<String>,
+    pub mode: WatchMode,
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
@@ -99,6 +103,7 @@ pub struct FileWatcher {
     tx: mpsc::UnboundedSender<PendingEvent>,
     agent: Option<Arc<crate::media::processor::Agent>>,
     analysis_pending: Arc<AtomicBool>,
+    poll_stop: Arc<std::sync::Mutex<Option<Arc<AtomicBool>>>>,
 }
 
 impl FileWatcher {
@@ -111,6 +116,7 @@ impl FileWatcher {
             tx,
             agent,
             analysis_pending: Arc::new(AtomicBool::new(false)),
+            poll_stop: Arc::new(std::sync::Mutex::new(None)),
         };
         let agent_clone = watcher.agent.clone();
         let analysis_pending_clone = watcher.analysis_pending.clone();
@@ -197,10 +203,24 @@ impl FileWatcher {
 
     /// Update watched directories
     pub fn watch(&self, directories: &[WatchPath]) -> Result<()> {
+        self.watch_with_polling(directories, PollSettings::default())
+    }
+
+    /// Update watched directories. Directories that resolve to
+    /// `WatchMode::Poll` are snapshotted every `poll.interval` instead of
+    /// being registered with the native watcher.
+    pub fn watch_with_polling(&self, directories: &[WatchPath], poll: PollSettings) -> Result<()> {
         let mut inner = self
             .inner
             .lock()
             .map_err(|e| AlchemistError::Watch(format!("Watcher lock poisoned: {}", e)))?;
+        let mut poll_stop = self
+            .poll_stop
+            .lock()
+            .map_err(|e| AlchemistError::Watch(format!("Poller lock poisoned: {}", e)))?;
+        if let Some(stop) = poll_stop.take() {
+            stop.store(true, Ordering::SeqCst);
+        }
 
         // Stop existing watcher implicitly by dropping it (if we replace it)
         // Or explicitly unwatch? Dropping RecommendedWatcher stops it.
@@ -217,15 +237,83 @@ impl FileWatcher {
             .iter()
             .map(|s| s.to_lowercase())
             .collect();
-
-        // Create the watcher
-        let tx_clone = self.tx.clone();
         let watch_roots: Vec<PathBuf> =
             directories.iter().map(|watch| watch.path.clone()).collect();
+        let (polled, native): (Vec<&WatchPath>, Vec<&WatchPath>) = directories
+            .iter()
+            .partition(|watch| resolve_watch_mode(watch.mode, &watch.path) == WatchMode::Poll);
+
+        *inner = if native.is_empty() {
+            None
+        } else {
+            Some(self.native_watcher(&native, &watch_roots, extensions.clone())?)
+        };
 
+        if !polled.is_empty() {
+            let roots = polled
+                .iter()
+                .map(|watch| {
+                    info!(
+                        "Polling directory: {:?} every {}s (recursive: {})",
+                        watch.path,
+                        poll.interval.as_secs(),
+                        watch.recursive
+                    );
+                    PolledRoot {
+                        path: watch.path.clone(),
+                        recursive: watch.recursive,
+                        rules: ExcludeRules::new(watch.path.clone(), &watch.exclude_globs),
+                    }
+                })
+                .collect();
+            let stop = Arc::new(AtomicBool::new(false));
+            let tx = self.tx.clone();
+            spawn_poller(roots, extensions, poll, stop.clone(), move |path| {
+                let source_root = resolve_source_root(&path, &watch_roots);
+                let _ = tx.send(PendingEvent {
+                    key: PendingKey { path, source_root },
+                    hint: StabilityHint::Standard,
+                });
+            })
+            .map_err(|e| AlchemistError::Watch(format!("Failed to start poll watcher: {}", e)))?;
+            *poll_stop = Some(stop);
+        }
+
+        info!(
+            "File watcher updated for {} directories ({} polled)",
+            directories.len(),
+            polled.len()
+        );
+        Ok(())
+    }
+
+    fn native_watcher(
+        &self,
+        directories: &[&WatchPath],
+        watch_roots: &[PathBuf],
+        extensions: HashSet<String>,
+    ) -> Result<RecommendedWatcher> {
+        // Create the watcher
+        let tx_clone = self.tx.clone();
+        let watch_roots = watch_roots.to_vec();
+        let exclude_rules: Vec<ExcludeRules> = directories
+            .iter()
+            .map(|watch| ExcludeRules::new(watch.path.clone(), &watch.exclude_globs))
+            .collect();
         let mut watcher = RecommendedWatcher::new(
             move |res: std::result::Result<Event, notify::Error>| match res {
                 Ok(event) => {
+                    // Any change to an ignore file, including its removal,
+                    // invalidates the parsed copies.
+                    if event.paths.iter().any(|path| {
+                        path.file_name()
+                            .is_some_and(|name| name == IGNORE_FILE_NAME)
+                    }) {
+                        for rules in &exclude_rules {
+                            rules.forget_ignore_files();
+                        }
+                    }
+
                     let Some(hint) = stability_hint_for_event(&event) else {
                         return;
                     };
@@ -235,6 +323,19 @@ impl FileWatcher {
                             && extensions.contains(&ext.to_string_lossy().to_lowercase())
                         {
                             let source_root = resolve_source_root(&path, &watch_roots);
+                            if let Some(exclusion) = source_root
+                                .as_ref()
+                                .and_then(|root| {
+                                    exclude_rules.iter().find(|rules| rules.root() == root)
+                                })
+                                .and_then(|rules| rules.exclusion_for(&path))
+                            {
+                                debug!(
+                                    "Ignoring excluded file {:?} ({} from {})",
+                                    path, exclusion.rule, exclusion.source
+                                );
+                                continue;
+                            }
                             let _ = tx_clone.send(PendingEvent {
                                 key: PendingKey { path, source_root },
                                 hint,
@@ -270,10 +371,7 @@ impl FileWatcher {
             }
         }
 
-        info!("File watcher updated for {} directories", directories.len());
-
-        *inner = Some(watcher);
-        Ok(())
+        Ok(watcher)
     }
 }
 
@@ -286,25 +384,41 @@ pub async fn resolve_watch_paths(
         return Ok(Vec::new());
     }
 
-    let mut watch_dirs: HashMap<PathBuf, bool> = HashMap::new();
+    let mut watch_dirs: HashMap<PathBuf, WatchPath> = HashMap::new();
 
     if config.scanner.watch_enabled {
         for dir in &config.scanner.directories {
-            watch_dirs.insert(PathBuf::from(dir), true);
+            let path = PathBuf::from(dir);
+            watch_dirs.insert(
+                path.clone(),
+                WatchPath {
+                    path,
+                    recursive: true,
+                    exclude_globs: Vec::new(),
+                    mode: WatchMode::Auto,
+                },
+            );
         }
     }
 
     for dir in db.get_watch_dirs().await? {
-        watch_dirs
-            .entry(PathBuf::from(dir.path))
-            .and_modify(|recursive| *recursive |= dir.is_recursive)
-            .or_insert(dir.is_recursive);
+        let exclude_globs = dir.exclude_globs();
+        let mode = dir.watch_mode();
+        let path = PathBuf::from(dir.path);
+        let watch = watch_dirs.entry(path.clone()).or_insert(WatchPath {
+            path,
+            recursive: false,
+            exclude_globs: Vec::new(),
+            mode: WatchMode::Auto,
+        });
+        watch.recursive |= dir.is_recursive;
+        watch.exclude_globs.extend(exclude_globs);
+        if mode != WatchMode::Auto {
+            watch.mode = mode;
+        }
     }
 
-    let mut all_dirs: Vec<WatchPath> = watch_dirs
-        .into_iter()
-        .map(|(path, recursive)| WatchPath { path, recursive })
-        .collect();
+    let mut all_dirs: Vec<WatchPath> = watch_dirs.into_values().collect();
     all_dirs.sort_by(|a, b| a.path.cmp(&b.path));
     Ok(all_dirs)
 }
@@ -316,7 +430,7 @@ pub async fn refresh_from_sources(
     setup_required: bool,
 ) -> Result<()> {
     let dirs = resolve_watch_paths(db, config, setup_required).await?;
-    file_watcher.watch(&dirs)
+    file_watcher.watch_with_polling(&dirs, PollSettings::from_config(&config.scanner))
 }
 
 fn resolve_source_root(path: &Path, watch_roots: &[PathBuf]) -> Option<PathBuf> {
@@ -445,6 +559,8 @@ mod tests {
         watcher.watch(&[WatchPath {
             path: watch_dir.clone(),
             recursive: false,
+            exclude_globs: Vec::new(),
+            mode: WatchMode::Native,
         }])?;
 
         let input_path = watch_dir.join("movie.mp4");
@@ -485,6 +601,8 @@ mod tests {
         watcher.watch(&[WatchPath {
             path: watch_dir.clone(),
             recursive: false,
+            exclude_globs: Vec::new(),
+            mode: WatchMode::Native,
         }])?;
 
         let input_path = watch_dir.join("feature.mp4");
@@ -526,6 +644,8 @@ mod tests {
         watcher.watch(&[WatchPath {
             path: watch_dir.clone(),
             recursive: false,
+            exclude_globs: Vec::new(),
+            mode: WatchMode::Native,
         }])?;
 
         let input_path = watch_dir.join("episode.mp4");
@@ -569,6 +689,8 @@ mod tests {
         watcher.watch(&[WatchPath {
             path: watch_dir.clone(),
             recursive: false,
+            exclude_globs: Vec::new(),
+            mode: WatchMode::Native,
         }])?;
 
         let staging_path = staging_root.join("movie.tmp");
@@ -596,6 +718,90 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn watcher_skips_excluded_files() -> anyhow::Result<()> {
+        let db_path = temp_db_path("alchemist_watcher_excludes");
+        let watch_dir = temp_watch_dir("alchemist_watch_excludes");
+        std::fs::create_dir_all(&watch_dir)?;
+        std::fs::write(watch_dir.join(IGNORE_FILE_NAME), "trailer.mp4\n")?;
+
+        let db = Arc::new(Db::new(db_path.to_string_lossy().as_ref()).await?);
+        let watcher = FileWatcher::new(db.clone(), None);
+        watcher.watch(&[WatchPath {
+            path: watch_dir.clone(),
+            recursive: false,
+            exclude_globs: vec!["*sample*".to_string()],
+            mode: WatchMode::Native,
+        }])?;
+
+        std::fs::write(watch_dir.join("movie-sample.mp4"), b"sample")?;
+        std::fs::write(watch_dir.join("trailer.mp4"), b"trailer")?;
+        let input_path = watch_dir.join("movie.mp4");
+        std::fs::write(&input_path, b"source")?;
+        wait_for_queued_jobs(db.as_ref(), 1).await?;
+        tokio::time::sleep(Duration::from_secs(2)).await;
+
+        let queued = db.get_jobs_by_status(crate::db::JobState::Queued).await?;
+        assert_eq!(queued.len(), 1);
+        assert_eq!(
+            std::fs::canonicalize(&queued[0].input_path)?,
+            std::fs::canonicalize(&input_path)?
+        );
+
+        watcher.watch(&[])?;
+        cleanup_paths(&[watch_dir, db_path]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn poll_mode_enqueues_files_found_by_snapshot_diff() -> anyhow::Result<()> {
+        let db_path = temp_db_path("alchemist_watcher_poll");
+        let watch_dir = temp_watch_dir("alchemist_watch_poll");
+        std::fs::create_dir_all(&watch_dir)?;
+        std::fs::write(watch_dir.join("existing.mp4"), b"baseline")?;
+
+        let db = Arc::new(Db::new(db_path.to_string_lossy().as_ref()).await?);
+        let watcher = FileWatcher::new(db.clone(), None);
+        watcher.watch_with_polling(
+            &[WatchPath {
+                path: watch_dir.clone(),
+                recursive: true,
+                exclude_globs: vec!["*sample*".to_string()],
+                mode: WatchMode::Poll,
+            }],
+            PollSettings {
+                interval: Duration::from_millis(300),
+                max_stats_per_sec: 100,
+            },
+        )?;
+        assert!(
+            watcher
+                .inner
+                .lock()
+                .map(|inner| inner.is_none())
+                .unwrap_or(false)
+        );
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        std::fs::create_dir_all(watch_dir.join("Season 01"))?;
+        let input_path = watch_dir.join("Season 01/episode.mp4");
+        std::fs::write(&input_path, b"source")?;
+        std::fs::write(watch_dir.join("Season 01/episode-sample.mp4"), b"sample")?;
+        wait_for_queued_jobs(db.as_ref(), 1).await?;
+        tokio::time::sleep(Duration::from_secs(2)).await;
+
+        let queued = db.get_jobs_by_status(crate::db::JobState::Queued).await?;
+        assert_eq!(queued.len(), 1);
+        assert_eq!(
+            std::fs::canonicalize(&queued[0].input_path)?,
+            std::fs::canonicalize(&input_path)?
+        );
+
+        watcher.watch(&[])?;
+        cleanup_paths(&[watch_dir, db_path]);
+        Ok(())
+    }
+
     fn cleanup_paths(paths: &[PathBuf]) {
         for path in paths {
             let _ = std::fs::remove_file(path);
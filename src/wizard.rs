@@ -118,9 +118,14 @@ impl ConfigWizard {
                 tonemap_algorithm: crate::config::TonemapAlgorithm::Hable,
                 tonemap_peak: crate::config::default_tonemap_peak(),
                 tonemap_desat: crate::config::default_tonemap_desat(),
+                deinterlace_mode: crate::config::DeinterlaceMode::Off,
+                deinterlace_algorithm: crate::config::DeinterlaceAlgorithm::Bwdif,
+                deinterlace_rate: crate::config::DeinterlaceRate::SendFrame,
                 subtitle_mode: crate::config::SubtitleMode::Copy,
                 stream_rules: crate::config::StreamRules::default(),
                 vmaf_min_score: None,
+                queue_order: crate::config::QueueOrder::default(),
+                reprocess_on_settings_change: false,
             },
             hardware: crate::config::HardwareConfig {
                 preferred_vendor,
@@ -134,12 +139,16 @@ impl ConfigWizard {
                 watch_enabled: false,
                 extra_watch_dirs: Vec::new(),
                 aggressive_directory_pruning: false,
+                poll_interval_secs: crate::config::default_watch_poll_interval_secs(),
+                poll_max_stats_per_sec: crate::config::default_watch_poll_max_stats_per_sec(),
             },
             notifications: crate::config::NotificationsConfig::default(),
             files: crate::config::FileSettingsConfig::default(),
             schedule: crate::config::ScheduleConfig::default(),
             quality: crate::config::QualityConfig::default(),
             system: crate::config::SystemConfig::default(),
+            hooks: Vec::new(),
+            retry: crate::config::RetryConfig::default(),
         };
 
         // Show summary
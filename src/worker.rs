@@ -0,0 +1,566 @@
+//! Remote worker mode: `alchemist worker --server URL --token T`.
+//!
+//! A worker registers its hardware with a main instance and leases queued
+//! jobs whose target codec it can encode. For each lease it probes the source
+//! locally, has the server plan the job for the worker's hardware, runs the
+//! plan, and reports the outcome; the server then finalizes the output
+//! through the same quality gates as a local encode. Job paths are the
+//! server's, rewritten through `--path-map` translations, so both machines
+//! must see the media on shared storage. Heartbeats carry progress and logs
+//! and renew the lease; a lease that is not renewed expires and its job goes
+//! back in the queue.
+
+use crate::config::ArrPathTranslation;
+use crate::db::Job;
+use crate::error::{AlchemistError, Result};
+use crate::media::analyzer::FfmpegAnalyzer;
+use crate::media::executor::{ExecutionTarget, execute_plan};
+use crate::media::ffmpeg::FFmpegProgress;
+use crate::media::pipeline::{
+    Analyzer, ExecutionResult, JobFailure, MediaAnalysis, SubtitleStreamPlan, TranscodePlan,
+};
+use crate::orchestrator::{AsyncExecutionObserver, Transcoder};
+use crate::system::hardware::HardwareInfo;
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+/// How long a lease lasts without a heartbeat.
+pub const LEASE_SECS: u64 = 60;
+/// How often a worker heartbeats while it holds a lease.
+pub const HEARTBEAT_SECS: u64 = 10;
+const IDLE_POLL: Duration = Duration::from_secs(10);
+/// Log lines buffered between heartbeats; older lines are dropped first.
+const MAX_PENDING_LOGS: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterWorkerRequest {
+    pub name: String,
+    pub hardware: HardwareInfo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterWorkerResponse {
+    pub worker_id: i64,
+    pub lease_secs: u64,
+    pub heartbeat_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaseResponse {
+    pub job: Job,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanJobRequest {
+    pub analysis: MediaAnalysis,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanJobResponse {
+    /// `None` when the server closed the job without an encode, for example
+    /// on a skip decision or a cancel.
+    pub plan: Option<TranscodePlan>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HeartbeatRequest {
+    #[serde(default)]
+    pub progress: Option<f64>,
+    #[serde(default)]
+    pub time: String,
+    #[serde(default)]
+    pub logs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeartbeatResponse {
+    /// The job was cancelled on the server; stop encoding it.
+    pub cancel: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum RemoteJobOutcome {
+    Encoded {
+        execution_result: ExecutionResult,
+    },
+    Failed {
+        failure: JobFailure,
+        message: String,
+    },
+    Cancelled,
+}
+
+/// What a worker reports once it stops working on a leased job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteJobReport {
+    #[serde(flatten)]
+    pub outcome: RemoteJobOutcome,
+    pub started_at: DateTime<Utc>,
+    pub encode_seconds: f64,
+}
+
+/// Parse a `SERVER_PREFIX=LOCAL_PREFIX` path map.
+pub fn parse_path_map(value: &str) -> std::result::Result<ArrPathTranslation, String> {
+    match value.split_once('=') {
+        Some((from, to)) if !from.trim().is_empty() && !to.trim().is_empty() => {
+            Ok(ArrPathTranslation {
+                from: from.trim().to_string(),
+                to: to.trim().to_string(),
+            })
+        }
+        _ => Err(format!("expected SERVER_PATH=LOCAL_PATH, got \"{value}\"")),
+    }
+}
+
+fn localize_path(path: &Path, translations: &[ArrPathTranslation]) -> PathBuf {
+    PathBuf::from(ArrPathTranslation::translate(
+        &path.to_string_lossy(),
+        translations,
+    ))
+}
+
+/// `plan` with the output and sidecar paths rewritten for this machine.
+pub fn localize_plan(plan: &TranscodePlan, translations: &[ArrPathTranslation]) -> TranscodePlan {
+    let mut plan = plan.clone();
+    plan.output_path = plan
+        .output_path
+        .map(|path| localize_path(&path, translations));
+    let sidecars = match &mut plan.subtitles {
+        SubtitleStreamPlan::Extract { outputs } => outputs.as_mut_slice(),
+        SubtitleStreamPlan::Select { sidecars, .. } => sidecars.as_mut_slice(),
+        _ => &mut [],
+    };
+    for sidecar in sidecars {
+        sidecar.final_path = localize_path(&sidecar.final_path, translations);
+        sidecar.temp_path = localize_path(&sidecar.temp_path, translations);
+    }
+    plan
+}
+
+pub struct WorkerOptions {
+    /// Base URL of the main instance, such as `http://nas:3000`.
+    pub server: String,
+    /// An API token with the `worker` access level.
+    pub token: String,
+    pub name: String,
+    pub path_translations: Vec<ArrPathTranslation>,
+}
+
+#[derive(Clone)]
+struct ServerClient {
+    http: reqwest::Client,
+    base_url: String,
+    token: String,
+}
+
+impl ServerClient {
+    fn new(options: &WorkerOptions) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(|err| {
+                AlchemistError::Unknown(format!("Failed to build HTTP client: {err}"))
+            })?;
+        Ok(Self {
+            http,
+            base_url: options.server.trim_end_matches('/').to_string(),
+            token: options.token.clone(),
+        })
+    }
+
+    /// POST `body` to `/api/v1/workers{path}`. `Ok(None)` means the server
+    /// answered with no content.
+    async fn post<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> std::result::Result<Option<T>, (Option<StatusCode>, String)> {
+        let response = self
+            .http
+            .post(format!("{}/api/v1/workers{path}", self.base_url))
+            .bearer_auth(&self.token)
+            .json(body)
+            .send()
+            .await
+            .map_err(|err| (None, err.to_string()))?;
+        let status = response.status();
+        if status == StatusCode::NO_CONTENT {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err((Some(status), format!("{status}: {body}")));
+        }
+        response
+            .json()
+            .await
+            .map(Some)
+            .map_err(|err| (Some(status), format!("invalid response: {err}")))
+    }
+}
+
+#[derive(Default)]
+struct PendingUpdate {
+    progress: Option<f64>,
+    time: String,
+    logs: Vec<String>,
+}
+
+/// Buffers encoder logs and progress until the next heartbeat sends them.
+#[derive(Default)]
+struct RemoteObserver {
+    pending: Mutex<PendingUpdate>,
+}
+
+impl RemoteObserver {
+    async fn take(&self) -> HeartbeatRequest {
+        let pending = std::mem::take(&mut *self.pending.lock().await);
+        HeartbeatRequest {
+            progress: pending.progress,
+            time: pending.time,
+            logs: pending.logs,
+        }
+    }
+}
+
+impl AsyncExecutionObserver for RemoteObserver {
+    async fn on_log(&self, message: String) {
+        let mut pending = self.pending.lock().await;
+        if pending.logs.len() >= MAX_PENDING_LOGS {
+            pending.logs.remove(0);
+        }
+        pending.logs.push(message);
+    }
+
+    async fn on_progress(&self, progress: FFmpegProgress, total_duration: f64) {
+        let mut pending = self.pending.lock().await;
+        pending.progress = Some(progress.percentage(total_duration).clamp(0.0, 100.0));
+        pending.time = progress.time;
+    }
+}
+
+struct Worker {
+    client: ServerClient,
+    transcoder: Arc<Transcoder>,
+    hardware: HardwareInfo,
+    name: String,
+    translations: Vec<ArrPathTranslation>,
+}
+
+/// Register with the server and work leased jobs until the process exits.
+pub async fn run(options: WorkerOptions, hardware: HardwareInfo) -> Result<()> {
+    let worker = Worker {
+        client: ServerClient::new(&options)?,
+        transcoder: Arc::new(Transcoder::new()),
+        hardware,
+        name: options.name,
+        translations: options.path_translations,
+    };
+    info!(
+        "Worker \"{}\" connecting to {} (codecs: {})",
+        worker.name,
+        worker.client.base_url,
+        worker.hardware.supported_codecs.join(", ")
+    );
+
+    let mut registration: Option<RegisterWorkerResponse> = None;
+    loop {
+        let worker_id = match &registration {
+            Some(registration) => registration.worker_id,
+            None => match worker.register().await {
+                Ok(response) => {
+                    info!("Registered as worker {}", response.worker_id);
+                    let worker_id = response.worker_id;
+                    registration = Some(response);
+                    worker_id
+                }
+                Err(err) => {
+                    warn!("Registration failed: {err}");
+                    tokio::time::sleep(IDLE_POLL).await;
+                    continue;
+                }
+            },
+        };
+
+        match worker
+            .client
+            .post::<_, LeaseResponse>(&format!("/{worker_id}/lease"), &serde_json::json!({}))
+            .await
+        {
+            Ok(Some(lease)) => worker.run_job(worker_id, lease.job).await,
+            Ok(None) => tokio::time::sleep(IDLE_POLL).await,
+            Err((status, err)) => {
+                warn!("Lease request failed: {err}");
+                if status == Some(StatusCode::NOT_FOUND) {
+                    // The server no longer knows this worker; register again.
+                    registration = None;
+                }
+                tokio::time::sleep(IDLE_POLL).await;
+            }
+        }
+    }
+}
+
+impl Worker {
+    async fn register(&self) -> std::result::Result<RegisterWorkerResponse, String> {
+        let request = RegisterWorkerRequest {
+            name: self.name.clone(),
+            hardware: self.hardware.clone(),
+        };
+        match self.client.post("/register", &request).await {
+            Ok(Some(response)) => Ok(response),
+            Ok(None) => Err("empty registration response".to_string()),
+            Err((_, err)) => Err(err),
+        }
+    }
+
+    async fn run_job(&self, worker_id: i64, job: Job) {
+        let job_path = format!("/{worker_id}/jobs/{}", job.id);
+        info!("Leased job {}: {}", job.id, job.input_path);
+        let started_at = Utc::now();
+        let start = Instant::now();
+
+        let observer = Arc::new(RemoteObserver::default());
+        let heartbeat = tokio::spawn(heartbeat_loop(
+            self.client.clone(),
+            format!("{job_path}/heartbeat"),
+            observer.clone(),
+            self.transcoder.clone(),
+            job.id,
+        ));
+
+        let outcome = self.encode(&job_path, &job, observer.clone()).await;
+        heartbeat.abort();
+
+        let Some(outcome) = outcome else {
+            return;
+        };
+        // Deliver logs written since the last heartbeat before the report.
+        let remaining = observer.take().await;
+        if !remaining.logs.is_empty() {
+            let _ = self
+                .client
+                .post::<_, HeartbeatResponse>(&format!("{job_path}/heartbeat"), &remaining)
+                .await;
+        }
+
+        let report = RemoteJobReport {
+            outcome,
+            started_at,
+            encode_seconds: start.elapsed().as_secs_f64(),
+        };
+        match self
+            .client
+            .post::<_, serde_json::Value>(&format!("{job_path}/complete"), &report)
+            .await
+        {
+            Ok(_) => info!("Reported job {}", job.id),
+            Err((_, err)) => error!("Failed to report job {}: {err}", job.id),
+        }
+    }
+
+    /// Probe, plan, and encode `job`. `None` means there is nothing to report.
+    async fn encode(
+        &self,
+        job_path: &str,
+        job: &Job,
+        observer: Arc<RemoteObserver>,
+    ) -> Option<RemoteJobOutcome> {
+        let local_job = Job {
+            input_path: ArrPathTranslation::translate(&job.input_path, &self.translations),
+            output_path: ArrPathTranslation::translate(&job.output_path, &self.translations),
+            ..job.clone()
+        };
+
+        let mut analysis = match FfmpegAnalyzer
+            .analyze(Path::new(&local_job.input_path))
+            .await
+        {
+            Ok(analysis) => analysis,
+            Err(err) => {
+                return Some(RemoteJobOutcome::Failed {
+                    failure: JobFailure::MediaCorrupt,
+                    message: format!("Probing failed on worker \"{}\": {err}", self.name),
+                });
+            }
+        };
+        let local_analysis = analysis.clone();
+        analysis.metadata.path = PathBuf::from(&job.input_path);
+
+        let plan = match self
+            .client
+            .post::<_, PlanJobResponse>(&format!("{job_path}/plan"), &PlanJobRequest { analysis })
+            .await
+        {
+            Ok(Some(PlanJobResponse { plan: Some(plan) })) => plan,
+            Ok(_) => {
+                info!("Job {} needs no encode", job.id);
+                return None;
+            }
+            Err((_, err)) => {
+                // The lease expires and the job returns to the queue.
+                warn!("Planning job {} failed: {err}", job.id);
+                return None;
+            }
+        };
+        let plan = localize_plan(&plan, &self.translations);
+
+        let result = execute_plan(
+            &self.transcoder,
+            Some(&self.hardware),
+            false,
+            ExecutionTarget {
+                job: &local_job,
+                plan: &plan,
+                analysis: &local_analysis,
+            },
+            observer,
+        )
+        .await;
+
+        match result {
+            Ok(execution_result) => Some(RemoteJobOutcome::Encoded { execution_result }),
+            Err(err) => {
+                if let Some(output) = &plan.output_path
+                    && output.exists()
+                    && let Err(remove_err) = std::fs::remove_file(output)
+                {
+                    warn!("Failed to remove partial output {:?}: {remove_err}", output);
+                }
+                for sidecar in plan.subtitles.sidecar_outputs() {
+                    let _ = std::fs::remove_file(&sidecar.temp_path);
+                }
+                if let AlchemistError::Cancelled = err {
+                    Some(RemoteJobOutcome::Cancelled)
+                } else {
+                    Some(RemoteJobOutcome::Failed {
+                        failure: crate::media::pipeline::map_failure(&err),
+                        message: format!("Transcode failed on worker \"{}\": {err}", self.name),
+                    })
+                }
+            }
+        }
+    }
+}
+
+/// Renew the lease and send buffered progress every `HEARTBEAT_SECS`,
+/// stopping the encode when the server cancels the job or drops the lease.
+async fn heartbeat_loop(
+    client: ServerClient,
+    path: String,
+    observer: Arc<RemoteObserver>,
+    transcoder: Arc<Transcoder>,
+    job_id: i64,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(HEARTBEAT_SECS));
+    loop {
+        interval.tick().await;
+        let update = observer.take().await;
+        let cancel = match client.post::<_, HeartbeatResponse>(&path, &update).await {
+            Ok(Some(response)) => response.cancel,
+            Ok(None) => false,
+            Err((Some(StatusCode::CONFLICT), _)) => {
+                warn!("Lease on job {job_id} was lost; stopping the encode");
+                true
+            }
+            Err((_, err)) => {
+                warn!("Heartbeat for job {job_id} failed: {err}");
+                false
+            }
+        };
+        if cancel {
+            transcoder.add_cancel_request(job_id).await;
+            transcoder.cancel_job(job_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::pipeline::SidecarOutputPlan;
+
+    #[test]
+    fn path_maps_parse_server_and_local_prefixes() {
+        let Ok(map) = parse_path_map("/media=/mnt/nas/media") else {
+            panic!("valid path map rejected");
+        };
+        assert_eq!(map.from, "/media");
+        assert_eq!(map.to, "/mnt/nas/media");
+        assert!(parse_path_map("/media").is_err());
+        assert!(parse_path_map("=/mnt").is_err());
+    }
+
+    #[test]
+    fn report_outcomes_serialize_with_a_tag() -> serde_json::Result<()> {
+        let report = RemoteJobReport {
+            outcome: RemoteJobOutcome::Failed {
+                failure: JobFailure::MediaCorrupt,
+                message: "bad".to_string(),
+            },
+            started_at: Utc::now(),
+            encode_seconds: 1.5,
+        };
+        let value = serde_json::to_value(&report)?;
+        assert_eq!(value["outcome"], "failed");
+        assert_eq!(value["failure"], "media_corrupt");
+        let parsed: RemoteJobReport = serde_json::from_value(value)?;
+        assert!(matches!(parsed.outcome, RemoteJobOutcome::Failed { .. }));
+        Ok(())
+    }
+
+    #[test]
+    fn localized_plans_rewrite_output_and_sidecar_paths() -> serde_json::Result<()> {
+        let translations = vec![ArrPathTranslation {
+            from: "/media".to_string(),
+            to: "/mnt/media".to_string(),
+        }];
+        let mut plan: TranscodePlan = serde_json::from_value(serde_json::json!({
+            "decision": {"Transcode": {"reason": "test"}},
+            "is_remux": false,
+            "copy_video": false,
+            "output_path": "/media/film.alchemist-part-1.mkv",
+            "container": "mkv",
+            "requested_codec": "av1",
+            "output_codec": null,
+            "encoder": null,
+            "backend": null,
+            "rate_control": null,
+            "encoder_preset": null,
+            "threads": 0,
+            "audio": [],
+            "subtitles": "drop",
+            "filters": [],
+            "allow_fallback": true,
+            "fallback": null
+        }))?;
+        plan.subtitles = SubtitleStreamPlan::Extract {
+            outputs: vec![SidecarOutputPlan {
+                stream_index: 0,
+                codec: "srt".to_string(),
+                muxer: None,
+                final_path: PathBuf::from("/media/film.eng.srt"),
+                temp_path: PathBuf::from("/media/film.eng.srt.tmp"),
+            }],
+        };
+
+        let local = localize_plan(&plan, &translations);
+        assert_eq!(
+            local.output_path,
+            Some(PathBuf::from("/mnt/media/film.alchemist-part-1.mkv"))
+        );
+        assert_eq!(
+            local.subtitles.sidecar_outputs()[0].temp_path,
+            PathBuf::from("/mnt/media/film.eng.srt.tmp")
+        );
+        Ok(())
+    }
+}
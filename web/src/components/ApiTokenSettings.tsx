@@ -4,7 +4,7 @@ import { apiAction, apiJson, isApiError } from "../lib/api";
 import { showToast } from "../lib/toast";
 import ConfirmDialog from "./ui/ConfirmDialog";
 
-type ApiTokenAccessLevel = "read_only" | "full_access" | "arr_webhook" | "jellyfin";
+type ApiTokenAccessLevel = "read_only" | "full_access" | "arr_webhook" | "jellyfin" | "worker";
 
 interface ApiToken {
     id: number;
@@ -143,6 +143,7 @@ export default function ApiTokenSettings() {
                         <option value="read_only">Read Only</option>
                         <option value="arr_webhook">ARR Webhook Only</option>
                         <option value="jellyfin">Jellyfin Plugin</option>
+                        <option value="worker">Remote Worker</option>
                         <option value="full_access">Full Access</option>
                     </select>
                 </div>
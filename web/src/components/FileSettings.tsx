@@ -3,12 +3,34 @@ import { AlertTriangle, Save } from "lucide-react";
 import { apiAction, apiJson, isApiError } from "../lib/api";
 import { showToast } from "../lib/toast";
 
+interface RecycleBinSettings {
+    enabled: boolean;
+    directory: string | null;
+    retention_days: number | null;
+    max_size_gb: number | null;
+}
+
 interface FileSettings {
     delete_source: boolean;
     output_extension: string;
     output_suffix: string;
     replace_strategy: string;
     output_root: string | null;
+    output_template: string | null;
+    recycle_bin: RecycleBinSettings;
+    carry_sidecars: boolean;
+}
+
+const DEFAULT_RECYCLE_BIN: RecycleBinSettings = {
+    enabled: false,
+    directory: null,
+    retention_days: 30,
+    max_size_gb: null,
+};
+
+function parseOptionalCount(value: string): number | null {
+    const parsed = Number.parseInt(value, 10);
+    return Number.isFinite(parsed) && parsed > 0 ? parsed : null;
 }
 
 export default function FileSettings() {
@@ -18,6 +40,9 @@ export default function FileSettings() {
         output_suffix: "-alchemist",
         replace_strategy: "keep",
         output_root: null,
+        output_template: null,
+        recycle_bin: DEFAULT_RECYCLE_BIN,
+        carry_sidecars: true,
     });
     const [savedSettings, setSavedSettings] = useState<FileSettings | null>(null);
     const [loading, setLoading] = useState(true);
@@ -30,7 +55,13 @@ export default function FileSettings() {
 
     const fetchSettings = async () => {
         try {
-            const data = await apiJson<FileSettings>("/api/settings/files");
+            const raw = await apiJson<FileSettings>("/api/settings/files");
+            const data = {
+                ...raw,
+                recycle_bin: { ...DEFAULT_RECYCLE_BIN, ...raw.recycle_bin },
+                output_template: raw.output_template ?? null,
+                carry_sidecars: raw.carry_sidecars ?? true,
+            };
             setSettings(data);
             setSavedSettings(data);
             setError(null);
@@ -49,7 +80,8 @@ export default function FileSettings() {
             await apiAction("/api/settings/files", {
                 method: "POST",
                 headers: { "Content-Type": "application/json" },
-                body: JSON.stringify(settings),
+                // An empty template clears it; null would keep the saved one.
+                body: JSON.stringify({ ...settings, output_template: settings.output_template ?? "" }),
             });
             setSavedSettings(settings);
             showToast({ kind: "success", title: "Files", message: "File settings saved." });
@@ -121,6 +153,36 @@ export default function FileSettings() {
                         </p>
                     </div>
 
+                    <div>
+                        <label className="block text-xs font-medium text-helios-slate mb-1">Output Template</label>
+                        <input
+                            aria-label="Output Template"
+                            type="text"
+                            value={settings.output_template ?? ""}
+                            onChange={e => setSettings({ ...settings, output_template: e.target.value || null })}
+                            className="w-full bg-helios-surface border border-helios-line/20 rounded p-2 text-sm text-helios-ink font-mono"
+                            placeholder="{parent}/{stem} [{codec} {height}p].{ext}"
+                        />
+                        <p className="text-xs text-helios-slate mt-1">
+                            Optional. Replaces the suffix naming above. Tokens: {"{stem}"}, {"{ext}"}, {"{suffix}"}, {"{parent}"}, {"{root}"}, {"{library}"}, {"{relative_dir}"}, {"{codec}"}, {"{width}"}, {"{height}"}, {"{hdr}"}, {"{audio_codec}"}, {"{profile}"}.
+                        </p>
+                    </div>
+
+                    <div>
+                        <label className="flex items-center gap-2 cursor-pointer">
+                            <input
+                                type="checkbox"
+                                checked={settings.carry_sidecars}
+                                onChange={e => setSettings({ ...settings, carry_sidecars: e.target.checked })}
+                                className="rounded border-helios-line/30"
+                            />
+                            <span className="text-sm font-medium text-helios-ink">Move sidecar files to the output</span>
+                        </label>
+                        <p className="text-xs text-helios-slate mt-1">
+                            Subtitles, NFO files, artwork and trickplay folders named after the source move to a renamed or relocated output when the source is deleted or recycled. A kept source keeps them.
+                        </p>
+                    </div>
+
                     <div>
                         <label className="block text-xs font-medium text-helios-slate mb-1">Existing Output Policy</label>
                         <select
@@ -154,6 +216,50 @@ export default function FileSettings() {
                                     />
                                     <span className="text-sm font-medium text-helios-ink">Delete source file after success</span>
                                 </label>
+                                <label className="flex items-center gap-2 cursor-pointer mt-3">
+                                    <input
+                                        type="checkbox"
+                                        checked={settings.recycle_bin.enabled}
+                                        onChange={e => setSettings({ ...settings, recycle_bin: { ...settings.recycle_bin, enabled: e.target.checked } })}
+                                        className="rounded border-helios-line/30"
+                                    />
+                                    <span className="text-sm font-medium text-helios-ink">Move originals to the recycle bin instead</span>
+                                </label>
+                                {settings.recycle_bin.enabled && (
+                                    <div className="grid grid-cols-1 md:grid-cols-3 gap-3 mt-3">
+                                        <div>
+                                            <label className="block text-xs font-medium text-helios-slate mb-1">Recycle directory</label>
+                                            <input
+                                                type="text"
+                                                value={settings.recycle_bin.directory ?? ""}
+                                                onChange={e => setSettings({ ...settings, recycle_bin: { ...settings.recycle_bin, directory: e.target.value || null } })}
+                                                placeholder="Beside each source"
+                                                className="w-full bg-helios-surface border border-helios-line/20 rounded p-2 text-sm text-helios-ink"
+                                            />
+                                        </div>
+                                        <div>
+                                            <label className="block text-xs font-medium text-helios-slate mb-1">Keep for (days)</label>
+                                            <input
+                                                type="number"
+                                                min={1}
+                                                value={settings.recycle_bin.retention_days ?? ""}
+                                                onChange={e => setSettings({ ...settings, recycle_bin: { ...settings.recycle_bin, retention_days: parseOptionalCount(e.target.value) } })}
+                                                className="w-full bg-helios-surface border border-helios-line/20 rounded p-2 text-sm text-helios-ink"
+                                            />
+                                        </div>
+                                        <div>
+                                            <label className="block text-xs font-medium text-helios-slate mb-1">Size cap (GiB)</label>
+                                            <input
+                                                type="number"
+                                                min={1}
+                                                value={settings.recycle_bin.max_size_gb ?? ""}
+                                                onChange={e => setSettings({ ...settings, recycle_bin: { ...settings.recycle_bin, max_size_gb: parseOptionalCount(e.target.value) } })}
+                                                placeholder="No cap"
+                                                className="w-full bg-helios-surface border border-helios-line/20 rounded p-2 text-sm text-helios-ink"
+                                            />
+                                        </div>
+                                    </div>
+                                )}
                             </div>
                         </div>
                     </div>
@@ -213,6 +319,22 @@ function buildImpactRows(saved: FileSettings | null, current: FileSettings): str
         );
     }
 
+    if (saved.output_template !== current.output_template) {
+        rows.push(
+            current.output_template
+                ? `Future jobs will be named by the template ${current.output_template}.`
+                : "Future jobs will use the suffix and extension naming."
+        );
+    }
+
+    if (saved.carry_sidecars !== current.carry_sidecars) {
+        rows.push(
+            current.carry_sidecars
+                ? "Sidecar files will follow future outputs that are renamed or written elsewhere when the source is removed."
+                : "Sidecar files will stay beside the source."
+        );
+    }
+
     if (saved.replace_strategy !== current.replace_strategy) {
         rows.push(
             current.replace_strategy === "replace"
@@ -221,11 +343,16 @@ function buildImpactRows(saved: FileSettings | null, current: FileSettings): str
         );
     }
 
-    if (saved.delete_source !== current.delete_source) {
+    if (
+        saved.delete_source !== current.delete_source ||
+        saved.recycle_bin.enabled !== current.recycle_bin.enabled
+    ) {
         rows.push(
-            current.delete_source
-                ? "Delete Source affects future successful transcodes and permanently removes originals."
-                : "Source files will be preserved after future transcodes."
+            !current.delete_source
+                ? "Source files will be preserved after future transcodes."
+                : current.recycle_bin.enabled
+                  ? "Originals of future successful transcodes move to the recycle bin and can be restored from the job until retention purges them."
+                  : "Delete Source affects future successful transcodes and permanently removes originals."
         );
     }
 
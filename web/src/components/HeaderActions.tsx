@@ -13,9 +13,20 @@ interface EngineStatus {
     draining: boolean;
     disk_blocked?: boolean;
     disk_block_reason?: string | null;
+    throttled?: boolean;
+    throttle_reason?: string | null;
     mode: "background" | "balanced" | "throughput";
     concurrent_limit: number;
     is_manual_override: boolean;
+    schedule_window?: {
+        id: number;
+        start_time: string;
+        end_time: string;
+        engine_mode: "background" | "balanced" | "throughput" | null;
+        concurrent_jobs: number | null;
+        allowed_backends: string[];
+    } | null;
+    schedule_backend_blocked?: boolean;
 }
 
 type EngineActionStatus = Pick<EngineStatus, "status">;
@@ -27,6 +38,8 @@ const DEFAULT_ENGINE_STATUS: EngineStatus = {
     draining: false,
     disk_blocked: false,
     disk_block_reason: null,
+    throttled: false,
+    throttle_reason: null,
     mode: "background",
     concurrent_limit: 1,
     is_manual_override: false,
@@ -66,6 +79,11 @@ export default function HeaderActions() {
             label: "Low disk",
             labelColor: "text-helios-solar",
         },
+        throttled: {
+            dot: "bg-helios-solar animate-pulse",
+            label: "Throttled",
+            labelColor: "text-helios-solar",
+        },
     } as const;
 
     const status = engineStatus?.status ?? "paused";
@@ -75,9 +93,11 @@ export default function HeaderActions() {
             ? "draining"
             : engineStatus?.disk_blocked
               ? "disk"
-              : isIdle
-                ? "idle"
-                : status;
+              : status === "running" && engineStatus?.throttled
+                ? "throttled"
+                : isIdle
+                  ? "idle"
+                  : status;
 
     const refreshEngineStatus = async () => {
         const data = await apiJson<EngineStatus>("/api/engine/status");
@@ -220,7 +240,9 @@ export default function HeaderActions() {
                         engineStatus?.disk_blocked
                             ? (engineStatus.disk_block_reason ??
                               "Low disk space — the engine is holding jobs until space is reclaimed.")
-                            : undefined
+                            : engineStatus?.throttled
+                              ? `throttled: ${engineStatus.throttle_reason ?? "the host is busy"}`
+                              : undefined
                     }
                 >
                     <div className={`h-1.5 w-1.5 rounded-full shrink-0 ${statusConfig[displayStatus].dot}`} />
@@ -268,6 +290,14 @@ export default function HeaderActions() {
                         (schedule)
                     </span>
                 )}
+                {engineStatus?.schedule_backend_blocked && (
+                    <span
+                        className="text-xs text-helios-slate/50 italic"
+                        title={`Window ${engineStatus.schedule_window?.start_time ?? ""}–${engineStatus.schedule_window?.end_time ?? ""} allows only: ${engineStatus.schedule_window?.allowed_backends.join(", ") ?? ""}`}
+                    >
+                        (no allowed encoder)
+                    </span>
+                )}
 
                 {/* Divider */}
                 <div className="w-px h-4 bg-helios-line/30 mx-1" />
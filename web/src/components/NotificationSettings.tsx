@@ -60,6 +60,7 @@ const EVENT_OPTIONS = [
     "scan.completed",
     "engine.idle",
     "disk.space_low",
+    "space_goal.completed",
     "daily.summary",
 ];
 
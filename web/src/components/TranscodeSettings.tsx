@@ -26,10 +26,15 @@ interface TranscodeSettingsPayload {
     tonemap_peak: number;
     tonemap_desat: number;
     subtitle_mode: "copy" | "burn" | "extract" | "none";
+    queue_order: "fifo" | "largest_source_first" | "highest_savings_first" | "shortest_duration_first";
+    reprocess_on_settings_change: boolean;
     stream_rules: {
         strip_audio_by_title: string[];
         keep_audio_languages: string[];
         keep_only_default_audio: boolean;
+        strip_subtitles_by_title: string[];
+        keep_subtitle_languages: string[];
+        keep_only_forced_subtitles: boolean;
     };
 }
 
@@ -37,6 +42,9 @@ const defaultStreamRules: TranscodeSettingsPayload["stream_rules"] = {
     strip_audio_by_title: [],
     keep_audio_languages: [],
     keep_only_default_audio: false,
+    strip_subtitles_by_title: [],
+    keep_subtitle_languages: [],
+    keep_only_forced_subtitles: false,
 };
 
 const commentaryShortcutKeywords = ["commentary", "director"];
@@ -103,7 +111,7 @@ export default function TranscodeSettings() {
             const data = await apiJson<TranscodeSettingsPayload>("/api/settings/transcode");
             setSettings({
                 ...data,
-                stream_rules: data.stream_rules ?? defaultStreamRules,
+                stream_rules: { ...defaultStreamRules, ...data.stream_rules },
             });
             setError("");
         } catch (err) {
@@ -272,6 +280,23 @@ export default function TranscodeSettings() {
                     </div>
                 </div>
 
+                <div className="md:col-span-2 flex items-center justify-between rounded-lg border border-helios-line/20 bg-helios-surface-soft/60 p-4">
+                    <div>
+                        <p className="text-xs font-medium text-helios-slate">Reprocess on Settings Change</p>
+                        <p className="text-xs text-helios-slate mt-1">Files Alchemist already produced are skipped. Enable to re-encode them when they were made with different settings.</p>
+                    </div>
+                    <div className="relative inline-flex items-center cursor-pointer">
+                        <input
+                            type="checkbox"
+                            aria-label="Reprocess on Settings Change"
+                            checked={settings.reprocess_on_settings_change ?? false}
+                            onChange={(e) => setSettings({ ...settings, reprocess_on_settings_change: e.target.checked })}
+                            className="sr-only peer"
+                        />
+                        <div className="w-10 h-5 bg-helios-line/20 peer-focus:outline-none rounded-full peer peer-checked:after:translate-x-full peer-checked:after:border-helios-ink after:content-[''] after:absolute after:top-[2px] after:start-[2px] after:bg-helios-ink after:border-helios-line/30 after:border after:rounded-full after:h-4 after:w-4 after:transition-all peer-checked:bg-helios-solar"></div>
+                    </div>
+                </div>
+
                 <div className="md:col-span-2 space-y-3 pt-2">
                     <label className="text-xs font-medium text-helios-slate flex items-center gap-2">
                         <Film size={14} /> Subtitle Handling
@@ -294,6 +319,25 @@ export default function TranscodeSettings() {
                     )}
                 </div>
 
+                <div className="md:col-span-2 space-y-3 pt-2">
+                    <label className="text-xs font-medium text-helios-slate flex items-center gap-2">
+                        <Scale size={14} /> Queue Order
+                    </label>
+                    <select
+                        value={settings.queue_order ?? "fifo"}
+                        onChange={(e) => setSettings({ ...settings, queue_order: e.target.value as TranscodeSettingsPayload["queue_order"] })}
+                        className="w-full bg-helios-surface border border-helios-line/30 rounded-lg px-4 py-3 text-helios-ink focus:border-helios-solar focus:ring-1 focus:ring-helios-solar outline-none transition-all"
+                    >
+                        <option value="fifo">Oldest first</option>
+                        <option value="largest_source_first">Largest source first</option>
+                        <option value="highest_savings_first">Highest estimated savings first</option>
+                        <option value="shortest_duration_first">Shortest estimated encode first</option>
+                    </select>
+                    <p className="text-xs text-helios-slate leading-relaxed mt-1">
+                        Breaks ties between jobs of the same priority. Estimates are made when a file is analyzed; files not yet analyzed wait behind those that have been.
+                    </p>
+                </div>
+
                 <div className="md:col-span-2 space-y-4 pt-2">
                     <label className="text-xs font-medium text-helios-slate flex items-center gap-2">
                         <Film size={14} /> Stream Rules
@@ -400,6 +444,63 @@ export default function TranscodeSettings() {
                             <div className="w-10 h-5 bg-helios-line/20 peer-focus:outline-none rounded-full peer peer-checked:after:translate-x-full peer-checked:after:border-helios-ink after:content-[''] after:absolute after:top-[2px] after:start-[2px] after:bg-helios-ink after:border-helios-line/30 after:border after:rounded-full after:h-4 after:w-4 after:transition-all peer-checked:bg-helios-solar"></div>
                         </div>
                     </div>
+
+                    <div className="space-y-3">
+                        <label className="text-xs font-medium text-helios-slate">
+                            Strip Subtitle Tracks By Title Keyword
+                        </label>
+                        <CommaSeparatedInput
+                            value={settings.stream_rules.strip_subtitles_by_title}
+                            onChange={(val) =>
+                                updateStreamRules({
+                                    strip_subtitles_by_title: val,
+                                })
+                            }
+                            placeholder="sdh, commentary"
+                        />
+                        <p className="text-xs text-helios-slate ml-1">
+                            Subtitle tracks whose title contains any of these words will be removed.
+                        </p>
+                    </div>
+
+                    <div className="space-y-3">
+                        <label className="text-xs font-medium text-helios-slate">
+                            Keep Only These Subtitle Languages
+                        </label>
+                        <CommaSeparatedInput
+                            value={settings.stream_rules.keep_subtitle_languages}
+                            onChange={(val) =>
+                                updateStreamRules({
+                                    keep_subtitle_languages: val,
+                                })
+                            }
+                            placeholder="eng"
+                        />
+                        <p className="text-xs text-helios-slate ml-1">
+                            Only keep subtitle tracks matching these language codes. Tracks with no language tag are always kept.
+                        </p>
+                    </div>
+
+                    <div className="flex items-center justify-between rounded-lg border border-helios-line/20 bg-helios-surface-soft/60 p-4">
+                        <div>
+                            <p className="text-xs font-medium text-helios-slate">Keep only forced subtitles</p>
+                            <p className="text-xs text-helios-slate mt-1">Strip every subtitle track the source file does not flag as forced.</p>
+                        </div>
+                        <div className="relative inline-flex items-center cursor-pointer">
+                            <input
+                                id="forced-subtitles-toggle"
+                                type="checkbox"
+                                checked={settings.stream_rules.keep_only_forced_subtitles}
+                                onChange={(e) =>
+                                    updateStreamRules({
+                                        keep_only_forced_subtitles: e.target.checked,
+                                    })
+                                }
+                                className="sr-only peer"
+                            />
+                            <div className="w-10 h-5 bg-helios-line/20 peer-focus:outline-none rounded-full peer peer-checked:after:translate-x-full peer-checked:after:border-helios-ink after:content-[''] after:absolute after:top-[2px] after:start-[2px] after:bg-helios-ink after:border-helios-line/30 after:border after:rounded-full after:h-4 after:w-4 after:transition-all peer-checked:bg-helios-solar"></div>
+                        </div>
+                    </div>
                 </div>
 
                 {/* HDR + Tonemapping */}
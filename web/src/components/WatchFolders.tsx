@@ -47,6 +47,8 @@ interface WatchDir {
     path: string;
     is_recursive: boolean;
     profile_id: number | null;
+    exclude_globs_json?: string;
+    watch_mode?: "auto" | "native" | "poll";
 }
 
 interface LibraryProfile {
@@ -56,7 +58,7 @@ interface LibraryProfile {
     codec: "av1" | "hevc" | "h264";
     quality_profile: "speed" | "balanced" | "quality";
     hdr_mode: "preserve" | "tonemap";
-    audio_mode: "copy" | "aac" | "aac_stereo";
+    audio_mode: "copy" | "aac" | "aac_stereo" | "copy_plus_stereo" | "transcode_lossless";
     crf_override: number | null;
     notes: string | null;
     builtin: boolean;
@@ -68,7 +70,7 @@ interface ProfileDraft {
     codec: "av1" | "hevc" | "h264";
     quality_profile: "speed" | "balanced" | "quality";
     hdr_mode: "preserve" | "tonemap";
-    audio_mode: "copy" | "aac" | "aac_stereo";
+    audio_mode: "copy" | "aac" | "aac_stereo" | "copy_plus_stereo" | "transcode_lossless";
     crf_override: string;
     notes: string;
 }
@@ -77,7 +79,12 @@ interface SettingsBundleResponse {
     settings: {
         scanner: {
             directories: string[];
-            extra_watch_dirs?: Array<{ path: string; is_recursive: boolean }>;
+            extra_watch_dirs?: Array<{
+                path: string;
+                is_recursive: boolean;
+                exclude_globs?: string[];
+                watch_mode?: "auto" | "native" | "poll";
+            }>;
         };
         [key: string]: unknown;
     };
@@ -371,6 +378,8 @@ export default function WatchFolders() {
                         body: JSON.stringify({
                             path: dir.path,
                             is_recursive: dir.is_recursive,
+                            exclude_globs: JSON.parse(dir.exclude_globs_json ?? "[]"),
+                            watch_mode: dir.watch_mode ?? "auto",
                         }),
                     }).catch(() => undefined);
                 }
@@ -774,6 +783,8 @@ export default function WatchFolders() {
                                         <option value="copy">Copy</option>
                                         <option value="aac">AAC</option>
                                         <option value="aac_stereo">AAC Stereo</option>
+                                        <option value="copy_plus_stereo">Copy + Stereo AAC</option>
+                                        <option value="transcode_lossless">Transcode Lossless Only</option>
                                     </select>
                                 </div>
                                 <div>
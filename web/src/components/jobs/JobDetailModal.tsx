@@ -39,7 +39,7 @@ interface JobDetailModalProps {
     completedEncodeStats: EncodeStats | null;
     focusedEmptyState: { title: string; detail: string } | null;
     openConfirm: (config: ConfirmConfig) => void;
-    handleAction: (id: number, action: "cancel" | "restart" | "delete") => Promise<void>;
+    handleAction: (id: number, action: "cancel" | "restart" | "delete" | "retry-now") => Promise<void>;
     handlePriority: (job: Job, priority: number, label: string) => Promise<void>;
     getStatusBadge: (status: string) => React.ReactElement;
 }
@@ -307,6 +307,15 @@ export function JobDetailModal({
                                             {focusedJob.job.status === "queued" && focusedJob.queue_position != null && (
                                                 <p className="text-xs text-helios-slate mt-1">
                                                     Queue position: <span className="font-semibold text-helios-ink">#{focusedJob.queue_position}</span>
+                                                    {focusedJob.queue_wait_seconds != null && focusedJob.queue_wait_seconds > 0 && (
+                                                        <> · starts in about <span className="font-semibold text-helios-ink">{formatDuration(focusedJob.queue_wait_seconds)}</span></>
+                                                    )}
+                                                </p>
+                                            )}
+                                            {focusedJob.job.status === "queued" && focusedJob.retry?.next_retry_at && (
+                                                <p className="text-xs text-helios-slate mt-1">
+                                                    Next retry: <span className="font-semibold text-helios-ink">{new Date(focusedJob.retry.next_retry_at).toLocaleString()}</span>
+                                                    {focusedJob.retry.retry_on_cpu && " (on CPU)"}
                                                 </p>
                                             )}
                                             {focusedJob.job.status === "queued" && processorStatus?.blocked_reason && (
@@ -463,6 +472,86 @@ export function JobDetailModal({
                                     </details>
                                 )}
 
+                                {focusedJob.effective_settings && (
+                                    <details className="rounded-lg border border-helios-line/15 bg-helios-surface-soft/40 p-4">
+                                        <summary className="cursor-pointer text-xs text-helios-solar">
+                                            Effective Settings{focusedJob.overrides ? " (overridden at enqueue)" : ""}
+                                        </summary>
+                                        <dl className="mt-3 grid grid-cols-2 gap-x-4 gap-y-1 text-xs">
+                                            {([
+                                                ["Profile", focusedJob.effective_settings.profile ?? "None"],
+                                                ["Codec", focusedJob.effective_settings.codec],
+                                                ["Quality", focusedJob.effective_settings.quality_profile],
+                                                ["CRF", focusedJob.effective_settings.crf ?? "Default"],
+                                                ["HDR", focusedJob.effective_settings.hdr_mode],
+                                                ["Audio", focusedJob.effective_settings.audio_mode ?? "Auto"],
+                                                ["Existing output", focusedJob.effective_settings.replace_strategy],
+                                                ["Delete source", focusedJob.effective_settings.delete_source ? "Yes" : "No"],
+                                            ] as const).map(([label, value]) => (
+                                                <div key={label} className="contents">
+                                                    <dt className="text-helios-slate">{label}</dt>
+                                                    <dd className="font-mono text-helios-ink">{String(value)}</dd>
+                                                </div>
+                                            ))}
+                                        </dl>
+                                    </details>
+                                )}
+
+                                {(focusedJob.variants ?? []).length > 0 && (
+                                    <details className="rounded-lg border border-helios-line/15 bg-helios-surface-soft/40 p-4">
+                                        <summary className="cursor-pointer text-xs text-helios-solar">
+                                            Output Variants ({(focusedJob.variants ?? []).length})
+                                        </summary>
+                                        <div className="mt-3 space-y-2">
+                                            {(focusedJob.variants ?? []).map((variant) => (
+                                                <div key={variant.id} className="flex items-start gap-3 rounded-lg border border-helios-line/10 bg-helios-main/50 px-3 py-2 text-xs">
+                                                    <span className={cn(
+                                                        "mt-0.5 shrink-0 rounded px-1.5 py-0.5 font-semibold capitalize",
+                                                        variant.status === "completed" && "bg-status-success/15 text-status-success",
+                                                        variant.status === "failed" && "bg-status-error/15 text-status-error",
+                                                        variant.status !== "completed" && variant.status !== "failed" && "bg-helios-slate/15 text-helios-slate",
+                                                    )}>{variant.status}</span>
+                                                    <div className="min-w-0 flex-1">
+                                                        <div className="flex items-center gap-2">
+                                                            <span className="font-medium text-helios-ink">{variant.name}</span>
+                                                            {variant.input_size_bytes != null && variant.output_size_bytes != null && (
+                                                                <span className="text-helios-slate">
+                                                                    {formatBytes(variant.input_size_bytes)} → {formatBytes(variant.output_size_bytes)}
+                                                                </span>
+                                                            )}
+                                                        </div>
+                                                        <p className="mt-0.5 font-mono text-helios-slate/80 truncate" title={variant.output_path}>{variant.output_path}</p>
+                                                        {(variant.failure_summary ?? variant.decision_reason) && (
+                                                            <p className="mt-0.5 text-helios-slate/80 truncate">{variant.failure_summary ?? variant.decision_reason}</p>
+                                                        )}
+                                                    </div>
+                                                </div>
+                                            ))}
+                                        </div>
+                                    </details>
+                                )}
+
+                                {(focusedJob.sidecars ?? []).length > 0 && (
+                                    <details className="rounded-lg border border-helios-line/15 bg-helios-surface-soft/40 p-4">
+                                        <summary className="cursor-pointer text-xs text-helios-solar">
+                                            Sidecars ({(focusedJob.sidecars ?? []).length})
+                                        </summary>
+                                        <div className="mt-3 space-y-2">
+                                            {(focusedJob.sidecars ?? []).map((sidecar) => (
+                                                <div key={sidecar.id} className="flex items-start gap-3 rounded-lg border border-helios-line/10 bg-helios-main/50 px-3 py-2 text-xs">
+                                                    <span className="mt-0.5 shrink-0 rounded bg-helios-slate/15 px-1.5 py-0.5 font-semibold capitalize text-helios-slate">
+                                                        {sidecar.action}
+                                                    </span>
+                                                    <div className="min-w-0 flex-1">
+                                                        <p className="font-mono text-helios-slate/80 truncate" title={sidecar.source_path}>{sidecar.source_path}</p>
+                                                        <p className="mt-0.5 font-mono text-helios-ink truncate" title={sidecar.output_path}>→ {sidecar.output_path}</p>
+                                                    </div>
+                                                </div>
+                                            ))}
+                                        </div>
+                                    </details>
+                                )}
+
                                 {shouldShowFfmpegOutput && (
                                     <details className="rounded-lg border border-helios-line/15 bg-helios-surface-soft/40 p-4">
                                         <summary className="cursor-pointer text-xs text-helios-solar">
@@ -520,6 +609,14 @@ export function JobDetailModal({
                                                 <RefreshCw size={14} /> Retry Job
                                             </button>
                                         )}
+                                        {focusedJob.job.status === "queued" && focusedJob.retry?.next_retry_at && (
+                                            <button
+                                                onClick={() => void handleAction(focusedJob.job.id, "retry-now")}
+                                                className="px-4 py-2 bg-helios-solar text-helios-main rounded-lg text-sm font-bold flex items-center gap-2 hover:brightness-110 active:scale-95 transition-all shadow-sm"
+                                            >
+                                                <RefreshCw size={14} /> Retry Now
+                                            </button>
+                                        )}
                                         {["encoding", "analyzing", "remuxing"].includes(focusedJob.job.status) && (
                                             <button
                                                 onClick={() =>
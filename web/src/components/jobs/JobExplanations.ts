@@ -30,6 +30,13 @@ export function humanizeSkipReason(reason: string): ExplanationView {
         measured[rawKey.trim()] = rawValueParts.join("=").trim();
     }
 
+    // The planner appends the Dolby Vision outcome to the transcode reason.
+    const dolbyVisionNote = measured.dolby_vision === "preserved"
+        ? " The Dolby Vision RPU is carried into the output."
+        : measured.dolby_vision === "stripped_to_hdr10"
+            ? " The Dolby Vision RPU is dropped and the base layer keeps its HDR10 mastering metadata."
+            : "";
+
     const makeDecision = (
         code: string,
         summary: string,
@@ -39,7 +46,7 @@ export function humanizeSkipReason(reason: string): ExplanationView {
         category: "decision",
         code,
         summary,
-        detail,
+        detail: detail + dolbyVisionNote,
         operator_guidance,
         measured,
         legacy_reason: reason,
@@ -164,6 +171,57 @@ export function humanizeSkipReason(reason: string): ExplanationView {
                 "This file is H.264, which is typically a strong candidate for reclaiming space, so Alchemist prioritized it for transcoding.",
                 null,
             );
+        case "dolby_vision_profile5_no_fallback":
+            return makeDecision(
+                "dolby_vision_profile5_no_fallback",
+                "Dolby Vision profile 5",
+                "This file is Dolby Vision profile 5, which has no HDR10 base layer. Alchemist leaves it alone rather than tonemapping the wrong signal.",
+                null,
+            );
+        case "profile_rule_skip":
+            return makeDecision(
+                "profile_rule_skip",
+                "Skipped by profile rule",
+                measured.rule
+                    ? `The profile rule "${measured.rule}" matched this file and is set to skip it.`
+                    : "A profile rule matched this file and is set to skip it.",
+                "Edit or disable the matching profile rule to process these files.",
+            );
+        case "dolby_vision_skipped":
+            return makeDecision(
+                "dolby_vision_skipped",
+                "Dolby Vision skipped",
+                "This file carries Dolby Vision metadata and the library profile is set to skip Dolby Vision sources.",
+                "Set the profile's Dolby Vision mode to strip or preserve to process these files.",
+            );
+        case "dolby_vision_preserve_unavailable":
+            return makeDecision(
+                "dolby_vision_preserve_unavailable",
+                "Dolby Vision cannot be preserved",
+                `The library profile asks to preserve Dolby Vision, but profile ${measured.profile ?? "unknown"} on ${measured.encoder ?? "this encoder"} cannot keep the RPU. Only profile 8.1 encoded with libx265 can.`,
+                "Use strip mode to keep the HDR10 base layer, or make libx265 available for HEVC encodes.",
+            );
+        case "dolby_vision_preserved":
+            return makeDecision(
+                "dolby_vision_preserved",
+                "Preserve Dolby Vision",
+                `Alchemist will re-encode this Dolby Vision profile ${measured.profile ?? "8.1"} file and carry its RPU into the output.`,
+                null,
+            );
+        case "dolby_vision_stripped_to_hdr10":
+            return makeDecision(
+                "dolby_vision_stripped_to_hdr10",
+                "Strip Dolby Vision",
+                "Alchemist will drop the Dolby Vision RPU and encode the base layer with its HDR10 mastering metadata intact.",
+                null,
+            );
+        case "downscale_to_max_resolution":
+            return makeDecision(
+                "downscale_to_max_resolution",
+                "Downscale to profile resolution",
+                "The library profile caps output resolution, so Alchemist will downscale this file while transcoding.",
+                null,
+            );
         case "transcode_recommended":
             return makeDecision(
                 "transcode_recommended",
@@ -25,7 +25,7 @@ interface JobsTableProps {
     setMenuPosition: (position: { x: number; y: number } | null) => void;
     copyInputPath: (path: string) => Promise<void>;
     openConfirm: (config: ConfirmConfig) => void;
-    handleAction: (id: number, action: "cancel" | "restart" | "delete") => Promise<void>;
+    handleAction: (id: number, action: "cancel" | "restart" | "delete" | "retry-now") => Promise<void>;
     handlePriority: (job: Job, priority: number, label: string) => Promise<void>;
     getStatusBadge: (status: string) => React.ReactElement;
 }
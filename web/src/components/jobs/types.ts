@@ -97,6 +97,51 @@ export interface JobDetail {
     decision_explanation: ExplanationPayload | null;
     failure_explanation: ExplanationPayload | null;
     queue_position: number | null;
+    queue_wait_seconds?: number | null;
+    retry?: JobRetryState;
+    variants?: JobVariant[];
+    sidecars?: JobSidecar[];
+    overrides?: Record<string, unknown> | null;
+    effective_settings?: EffectiveJobSettings;
+}
+
+export interface EffectiveJobSettings {
+    profile: string | null;
+    codec: string;
+    quality_profile: string;
+    crf: number | null;
+    hdr_mode: string;
+    audio_mode: string | null;
+    stream_rules: Record<string, string[] | boolean>;
+    replace_strategy: string;
+    delete_source: boolean;
+}
+
+export interface JobVariant {
+    id: number;
+    variant_job_id: number | null;
+    name: string;
+    output_path: string;
+    status: string;
+    decision_reason: string | null;
+    failure_summary: string | null;
+    input_size_bytes: number | null;
+    output_size_bytes: number | null;
+    encode_time_seconds: number | null;
+    updated_at: string;
+}
+
+export interface JobSidecar {
+    id: number;
+    source_path: string;
+    output_path: string;
+    action: "moved" | "copied" | "restored";
+}
+
+export interface JobRetryState {
+    next_retry_at: string | null;
+    retry_on_cpu: boolean;
+    last_failure_kind: string | null;
 }
 
 export interface ProcessorStatus {
@@ -128,7 +128,7 @@ export function useJobDetailController(options: UseJobDetailControllerOptions =
         }
     }, []);
 
-    const handleAction = useCallback(async (id: number, action: "cancel" | "restart" | "delete") => {
+    const handleAction = useCallback(async (id: number, action: "cancel" | "restart" | "delete" | "retry-now") => {
         try {
             await apiAction(`/api/jobs/${id}/${action}`, { method: "POST" });
             if (action === "delete") {
@@ -77,6 +77,7 @@ export function useJobSSE({
                             ? {
                                 ...prev,
                                 queue_position: status === "queued" ? prev.queue_position : null,
+                                queue_wait_seconds: status === "queued" ? prev.queue_wait_seconds : null,
                                 job: {
                                     ...prev.job,
                                     status,
@@ -21,7 +21,14 @@ export interface SetupSettings {
     scanner: {
         directories: string[];
         watch_enabled: boolean;
-        extra_watch_dirs: Array<{ path: string; is_recursive: boolean }>;
+        extra_watch_dirs: Array<{
+            path: string;
+            is_recursive: boolean;
+            exclude_globs?: string[];
+            watch_mode?: "auto" | "native" | "poll";
+        }>;
+        poll_interval_secs?: number;
+        poll_max_stats_per_sec?: number;
     };
     transcode: {
         concurrent_jobs: number;
@@ -90,12 +97,20 @@ export interface ScanStatus {
     current_folder: string | null;
 }
 
+export interface FsPreviewExcludedFile {
+    path: string;
+    rule: string;
+    source: string;
+}
+
 export interface FsPreviewDirectory {
     path: string;
     exists: boolean;
     readable: boolean;
     media_files: number;
     sample_files: string[];
+    excluded_files: FsPreviewExcludedFile[];
+    excluded_count: number;
     media_hint: "high" | "medium" | "low" | "unknown";
     warnings: string[];
 }